@@ -0,0 +1,18 @@
+/// Generate the gRPC service from its definition. The messages are those
+/// generated by indy-credx, and the definitions are parsed by protox, so
+/// that no protoc installation is needed.
+fn main() {
+    const PROTOS: &[&str] = &[
+        "../indy-credx/proto/indy_credx.proto",
+        "../indy-credx/proto/indy_credx_service.proto",
+    ];
+    for proto in PROTOS {
+        println!("cargo:rerun-if-changed={}", proto);
+    }
+    let descriptors =
+        protox::compile(PROTOS, ["../indy-credx/proto"]).expect("Error parsing proto definitions");
+    tonic_build::configure()
+        .extern_path(".indy.credx.v1", "::indy_credx::proto::v1")
+        .compile_fds(descriptors)
+        .expect("Error generating gRPC service");
+}
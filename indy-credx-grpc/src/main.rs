@@ -0,0 +1,111 @@
+//! Serve the indy-credx gRPC API.
+//!
+//! Usage: `credx-grpc-server [ADDRESS]`, where the address defaults to
+//! `127.0.0.1:50051`. Logging is configured with `RUST_LOG`.
+//!
+//! The server follows the service definition in
+//! `indy-credx/proto/indy_credx_service.proto`. It is stateless, and accepts
+//! cleartext HTTP/2 connections: transport security is left to a proxy in
+//! front of it.
+
+use std::net::SocketAddr;
+use std::process::exit;
+use std::time::Duration;
+
+use tonic::transport::{server::Router, Server};
+use tower::layer::util::{Identity, Stack};
+use tower::limit::GlobalConcurrencyLimitLayer;
+
+mod service;
+
+use service::{CredxServer, CredxService};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:50051";
+
+/// The maximum number of calls served at once, across all connections.
+/// Further calls wait until a call has finished.
+const MAX_CALLS: usize = 256;
+
+/// Connections are probed after this long without a frame from the client,
+/// and closed if the probe is not answered within the same interval
+const KEEPALIVE: Duration = Duration::from_secs(60);
+
+/// The server for the gRPC API
+fn server() -> Router<Stack<GlobalConcurrencyLimitLayer, Identity>> {
+    Server::builder()
+        .layer(GlobalConcurrencyLimitLayer::new(MAX_CALLS))
+        .tcp_nodelay(true)
+        .http2_keepalive_interval(Some(KEEPALIVE))
+        .http2_keepalive_timeout(Some(KEEPALIVE))
+        .add_service(CredxServer::new(CredxService))
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    let mut args = std::env::args().skip(1);
+    let address = args.next().unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    if args.next().is_some() || address.starts_with('-') {
+        eprintln!("Usage: credx-grpc-server [ADDRESS]");
+        exit(2);
+    }
+    let address: SocketAddr = match address.parse() {
+        Ok(address) => address,
+        Err(err) => {
+            eprintln!("Invalid address {}: {}", address, err);
+            exit(2);
+        }
+    };
+    println!("Serving indy-credx gRPC API on {}", address);
+    if let Err(err) = server().serve(address).await {
+        eprintln!("Error serving gRPC API: {}", err);
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indy_credx::{
+        proto::{v1, ProtoMessage},
+        types::Schema,
+    };
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::Code;
+
+    use super::*;
+    use crate::service::CredxClient;
+
+    #[tokio::test]
+    async fn grpc_unary_call() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(server().serve_with_incoming(TcpListenerStream::new(listener)));
+
+        let mut client = CredxClient::connect(format!("http://{}", addr))
+            .await
+            .unwrap();
+        // a long attribute name spans several frames in each direction
+        let long_name = "a".repeat(20000);
+        for attr_name in &["name", long_name.as_str()] {
+            let request = v1::CreateSchemaRequest {
+                origin_did: "NcYxiDXkpYi6ov5FcYDi1e".to_string(),
+                name: "gvt".to_string(),
+                version: "1.0".to_string(),
+                attr_names: vec![attr_name.to_string()],
+                seq_no: None,
+            };
+            let response = client.create_schema(request).await.unwrap();
+            let Schema::SchemaV1(schema) = Schema::from_message(response.into_inner()).unwrap();
+            assert_eq!(schema.id.0, "NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0");
+            assert!(schema.attr_names.0.contains(*attr_name));
+        }
+
+        let request = v1::CreateSchemaRequest {
+            origin_did: "x".to_string(),
+            ..Default::default()
+        };
+        let status = client.create_schema(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+}
@@ -0,0 +1,316 @@
+//! The methods of the `indy.credx.v1.Credx` service
+
+use indy_credx::{
+    issuer,
+    proto::{v1, ProtoMessage},
+    prover,
+    types::{
+        Credential, CredentialDefinition, CredentialDefinitionConfig, CredentialOffer, DidValue,
+        MakeCredentialValues, PresentCredentials, Presentation, Schema, SignatureType,
+    },
+    verifier, Error, ErrorKind,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tonic::{Code, Request, Response, Status};
+
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/indy.credx.v1.rs"));
+}
+
+#[cfg(test)]
+pub use generated::credx_client::CredxClient;
+pub use generated::credx_server::{Credx, CredxServer};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// The stateless implementation of the service
+pub struct CredxService;
+
+/// The status of a call which failed with an error
+fn status(err: Error) -> Status {
+    let code = match err.kind() {
+        ErrorKind::Input => Code::InvalidArgument,
+        ErrorKind::Cancelled => Code::Cancelled,
+        ErrorKind::LimitExceeded => Code::ResourceExhausted,
+        ErrorKind::CredentialRevoked
+        | ErrorKind::InvalidState
+        | ErrorKind::InvalidUserRevocId
+        | ErrorKind::ProofRejected
+        | ErrorKind::RevocationRegistryFull => Code::FailedPrecondition,
+        ErrorKind::IOError | ErrorKind::Unexpected | ErrorKind::HandleEvicted => Code::Internal,
+    };
+    Status::new(code, err.to_string())
+}
+
+/// Run a call on the blocking thread pool, as the cryptographic operations
+/// may take several seconds
+async fn blocking<T, R>(
+    request: Request<T>,
+    f: fn(T) -> Result<R>,
+) -> std::result::Result<Response<R>, Status>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+{
+    let request = request.into_inner();
+    match tokio::task::spawn_blocking(move || f(request)).await {
+        Ok(result) => result.map(Response::new).map_err(status),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
+
+fn missing(field: &str) -> Error {
+    Error::from_msg(
+        ErrorKind::Input,
+        format!("Missing request field: {}", field),
+    )
+}
+
+fn object<T: ProtoMessage>(message: Option<T::Message>, field: &str) -> Result<T> {
+    T::from_message(message.ok_or_else(|| missing(field))?)
+}
+
+fn objects<T: ProtoMessage>(messages: Vec<T::Message>) -> Result<Vec<T>> {
+    messages.into_iter().map(T::from_message).collect()
+}
+
+/// Parse the JSON of an object carried in a `bytes` field
+fn from_json<T: DeserializeOwned>(data: &[u8], field: &str) -> Result<T> {
+    if data.is_empty() {
+        return Err(missing(field));
+    }
+    serde_json::from_slice(data).map_err(|err| {
+        Error::from_msg(ErrorKind::Input, format!("Invalid {}", field)).with_cause(err)
+    })
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    serde_json::to_vec(value).map_err(|err| {
+        Error::from_msg(ErrorKind::Unexpected, "Error serializing response").with_cause(err)
+    })
+}
+
+fn create_schema(req: v1::CreateSchemaRequest) -> Result<v1::Schema> {
+    let schema = issuer::create_schema(
+        &DidValue::from(req.origin_did),
+        &req.name,
+        &req.version,
+        req.attr_names.into(),
+        req.seq_no,
+    )?;
+    schema.to_message()
+}
+
+fn create_credential_definition(
+    req: v1::CreateCredentialDefinitionRequest,
+) -> Result<v1::CreateCredentialDefinitionResponse> {
+    let (cred_def, cred_def_private, key_correctness_proof) = issuer::create_credential_definition(
+        &DidValue::from(req.origin_did),
+        &object::<Schema>(req.schema, "schema")?,
+        &req.tag,
+        SignatureType::CL,
+        CredentialDefinitionConfig::new(req.support_revocation),
+    )?;
+    Ok(v1::CreateCredentialDefinitionResponse {
+        cred_def: Some(cred_def.to_message()?),
+        cred_def_private: to_json(&cred_def_private)?,
+        key_correctness_proof: to_json(&key_correctness_proof)?,
+    })
+}
+
+fn create_credential_offer(req: v1::CreateCredentialOfferRequest) -> Result<v1::JsonObject> {
+    let offer = issuer::create_credential_offer(
+        &req.schema_id.into(),
+        &object::<CredentialDefinition>(req.cred_def, "cred_def")?,
+        &from_json(&req.key_correctness_proof, "key_correctness_proof")?,
+    )?;
+    Ok(v1::JsonObject {
+        json: to_json(&offer)?,
+    })
+}
+
+fn create_master_secret(_req: v1::Empty) -> Result<v1::JsonObject> {
+    let master_secret = prover::create_master_secret()?;
+    Ok(v1::JsonObject {
+        json: to_json(&master_secret)?,
+    })
+}
+
+fn request_credential(req: v1::RequestCredentialRequest) -> Result<v1::RequestCredentialResponse> {
+    let (cred_request, metadata) = prover::create_credential_request(
+        &DidValue::from(req.prover_did),
+        &object::<CredentialDefinition>(req.cred_def, "cred_def")?,
+        &from_json(&req.master_secret, "master_secret")?,
+        &req.master_secret_id,
+        &from_json(&req.cred_offer, "cred_offer")?,
+    )?;
+    Ok(v1::RequestCredentialResponse {
+        cred_request: to_json(&cred_request)?,
+        cred_request_metadata: to_json(&metadata)?,
+    })
+}
+
+fn issue_credential(req: v1::IssueCredentialRequest) -> Result<v1::Credential> {
+    let cred_offer: CredentialOffer = from_json(&req.cred_offer, "cred_offer")?;
+    let mut values = MakeCredentialValues::for_schema(&cred_offer.schema_id);
+    for (name, raw) in req.values {
+        values.add_raw(name, raw)?;
+    }
+    let (credential, _, _) = issuer::create_credential(
+        &object::<CredentialDefinition>(req.cred_def, "cred_def")?,
+        &from_json(&req.cred_def_private, "cred_def_private")?,
+        &cred_offer,
+        &from_json(&req.cred_request, "cred_request")?,
+        values.into(),
+        None,
+    )?;
+    credential.to_message()
+}
+
+fn process_credential(req: v1::ProcessCredentialRequest) -> Result<v1::Credential> {
+    let mut credential = object::<Credential>(req.credential, "credential")?;
+    prover::process_credential(
+        &mut credential,
+        &from_json(&req.cred_request_metadata, "cred_request_metadata")?,
+        &from_json(&req.master_secret, "master_secret")?,
+        &object::<CredentialDefinition>(req.cred_def, "cred_def")?,
+        None,
+    )?;
+    credential.to_message()
+}
+
+/// A credential to present, with the referents it satisfies
+struct PresentedCredential {
+    credential: Credential,
+    revealed_attrs: Vec<String>,
+    unrevealed_attrs: Vec<String>,
+    predicates: Vec<String>,
+}
+
+fn presented_credential(message: v1::PresentedCredential) -> Result<PresentedCredential> {
+    Ok(PresentedCredential {
+        credential: object(message.credential, "credential")?,
+        revealed_attrs: message.revealed_attrs,
+        unrevealed_attrs: message.unrevealed_attrs,
+        predicates: message.predicates,
+    })
+}
+
+fn create_presentation(req: v1::CreatePresentationRequest) -> Result<v1::Presentation> {
+    let credentials = req
+        .credentials
+        .into_iter()
+        .map(presented_credential)
+        .collect::<Result<Vec<_>>>()?;
+    let schemas = objects::<Schema>(req.schemas)?;
+    let cred_defs = objects::<CredentialDefinition>(req.cred_defs)?;
+
+    let mut present = PresentCredentials::default();
+    for presented in credentials.iter() {
+        let mut add = present.add_credential(&presented.credential, None, None);
+        for referent in presented.revealed_attrs.iter() {
+            add.add_requested_attribute(referent.as_str(), true);
+        }
+        for referent in presented.unrevealed_attrs.iter() {
+            add.add_requested_attribute(referent.as_str(), false);
+        }
+        for referent in presented.predicates.iter() {
+            add.add_requested_predicate(referent.as_str());
+        }
+    }
+    let presentation = prover::create_presentation(
+        &from_json(&req.pres_request, "pres_request")?,
+        present,
+        if req.self_attested.is_empty() {
+            None
+        } else {
+            Some(req.self_attested.into_iter().collect())
+        },
+        &from_json(&req.master_secret, "master_secret")?,
+        &schemas.iter().map(|s| (s.id().clone(), s)).collect(),
+        &cred_defs.iter().map(|c| (c.id().clone(), c)).collect(),
+    )?;
+    presentation.to_message()
+}
+
+fn verify_presentation(
+    req: v1::VerifyPresentationRequest,
+) -> Result<v1::VerifyPresentationResponse> {
+    let schemas = objects::<Schema>(req.schemas)?;
+    let cred_defs = objects::<CredentialDefinition>(req.cred_defs)?;
+    let verified = verifier::verify_presentation(
+        &object::<Presentation>(req.presentation, "presentation")?,
+        &from_json(&req.pres_request, "pres_request")?,
+        &schemas.iter().map(|s| (s.id().clone(), s)).collect(),
+        &cred_defs.iter().map(|c| (c.id().clone(), c)).collect(),
+        None,
+        None,
+    )?;
+    Ok(v1::VerifyPresentationResponse { verified })
+}
+
+#[tonic::async_trait]
+impl Credx for CredxService {
+    async fn create_schema(
+        &self,
+        request: Request<v1::CreateSchemaRequest>,
+    ) -> std::result::Result<Response<v1::Schema>, Status> {
+        blocking(request, create_schema).await
+    }
+
+    async fn create_credential_definition(
+        &self,
+        request: Request<v1::CreateCredentialDefinitionRequest>,
+    ) -> std::result::Result<Response<v1::CreateCredentialDefinitionResponse>, Status> {
+        blocking(request, create_credential_definition).await
+    }
+
+    async fn create_credential_offer(
+        &self,
+        request: Request<v1::CreateCredentialOfferRequest>,
+    ) -> std::result::Result<Response<v1::JsonObject>, Status> {
+        blocking(request, create_credential_offer).await
+    }
+
+    async fn create_master_secret(
+        &self,
+        request: Request<v1::Empty>,
+    ) -> std::result::Result<Response<v1::JsonObject>, Status> {
+        blocking(request, create_master_secret).await
+    }
+
+    async fn request_credential(
+        &self,
+        request: Request<v1::RequestCredentialRequest>,
+    ) -> std::result::Result<Response<v1::RequestCredentialResponse>, Status> {
+        blocking(request, request_credential).await
+    }
+
+    async fn issue_credential(
+        &self,
+        request: Request<v1::IssueCredentialRequest>,
+    ) -> std::result::Result<Response<v1::Credential>, Status> {
+        blocking(request, issue_credential).await
+    }
+
+    async fn process_credential(
+        &self,
+        request: Request<v1::ProcessCredentialRequest>,
+    ) -> std::result::Result<Response<v1::Credential>, Status> {
+        blocking(request, process_credential).await
+    }
+
+    async fn create_presentation(
+        &self,
+        request: Request<v1::CreatePresentationRequest>,
+    ) -> std::result::Result<Response<v1::Presentation>, Status> {
+        blocking(request, create_presentation).await
+    }
+
+    async fn verify_presentation(
+        &self,
+        request: Request<v1::VerifyPresentationRequest>,
+    ) -> std::result::Result<Response<v1::VerifyPresentationResponse>, Status> {
+        blocking(request, verify_presentation).await
+    }
+}
@@ -0,0 +1,302 @@
+//! Serve the indy-credx REST API, exposing the issuance and verification
+//! operations to agents which cannot use the FFI.
+//!
+//! Usage: `credx-http-server [ADDRESS]`, where the address defaults to
+//! `127.0.0.1:8080`. Logging is configured with `RUST_LOG`.
+//!
+//! Requests and responses are JSON documents. The service is stateless, and
+//! only accepts cleartext HTTP/1.1 connections: transport security is left
+//! to a proxy in front of it.
+
+use std::convert::Infallible;
+use std::fmt::Display;
+use std::process::exit;
+use std::sync::Arc;
+use std::time::Duration;
+
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::{
+    body::{Bytes, Incoming},
+    header::{ALLOW, CONTENT_TYPE},
+    server::conn::http1,
+    service::service_fn,
+    Request, StatusCode,
+};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use indy_credx::{Error, ErrorKind};
+use log::{debug, warn};
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+
+mod routes;
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+
+/// Idle connections are closed after this long without receiving a request
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+/// The maximum number of connections served at once. Further connections
+/// wait in the listen backlog until a connection is closed.
+const MAX_CONNECTIONS: usize = 256;
+/// The maximum size of a request body
+const MAX_BODY_SIZE: usize = 4 * 1024 * 1024;
+
+/// A response to a request
+#[derive(Debug)]
+pub(crate) struct Response {
+    status: StatusCode,
+    allow: Option<&'static str>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    pub(crate) fn ok(body: Vec<u8>) -> Self {
+        Self {
+            status: StatusCode::OK,
+            allow: None,
+            body,
+        }
+    }
+
+    pub(crate) fn error(status: StatusCode, message: impl Display) -> Self {
+        let body = serde_json::json!({
+            "error": status.canonical_reason().unwrap_or_default(),
+            "message": message.to_string(),
+        });
+        Self {
+            status,
+            allow: None,
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    pub(crate) fn method_not_allowed(allow: &'static str) -> Self {
+        Self {
+            allow: Some(allow),
+            ..Self::error(
+                StatusCode::METHOD_NOT_ALLOWED,
+                format!("Supported methods: {}", allow),
+            )
+        }
+    }
+}
+
+impl From<Error> for Response {
+    fn from(err: Error) -> Self {
+        let status = match err.kind() {
+            ErrorKind::Input => StatusCode::BAD_REQUEST,
+            ErrorKind::LimitExceeded => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorKind::CredentialRevoked
+            | ErrorKind::InvalidState
+            | ErrorKind::InvalidUserRevocId
+            | ErrorKind::ProofRejected
+            | ErrorKind::RevocationRegistryFull => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorKind::Cancelled
+            | ErrorKind::IOError
+            | ErrorKind::Unexpected
+            | ErrorKind::HandleEvicted => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = serde_json::json!({
+            "error": err.kind().as_str(),
+            "message": err.to_string(),
+        });
+        Self {
+            status,
+            allow: None,
+            body: body.to_string().into_bytes(),
+        }
+    }
+}
+
+impl From<Response> for hyper::Response<Full<Bytes>> {
+    fn from(response: Response) -> Self {
+        let mut builder = hyper::Response::builder()
+            .status(response.status)
+            .header(CONTENT_TYPE, "application/json");
+        if let Some(allow) = response.allow {
+            builder = builder.header(ALLOW, allow);
+        }
+        builder
+            .body(Full::new(Bytes::from(response.body)))
+            .expect("Invalid response")
+    }
+}
+
+/// Read the body of a request, which must be JSON if not empty
+async fn read_body(request: Request<Incoming>) -> Result<Bytes, Response> {
+    let media_type = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase());
+    let body = Limited::new(request.into_body(), MAX_BODY_SIZE)
+        .collect()
+        .await
+        .map_err(|err| {
+            if err.is::<LengthLimitError>() {
+                Response::error(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    format!("The request body is limited to {} bytes", MAX_BODY_SIZE),
+                )
+            } else {
+                Response::error(StatusCode::BAD_REQUEST, err)
+            }
+        })?
+        .to_bytes();
+    if !body.is_empty() && media_type.as_deref() != Some("application/json") {
+        return Err(Response::error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "The request body must be application/json",
+        ));
+    }
+    Ok(body)
+}
+
+/// Handle a request on the blocking thread pool, as the cryptographic
+/// operations may take several seconds
+async fn handle(request: Request<Incoming>) -> Result<hyper::Response<Full<Bytes>>, Infallible> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let response = match read_body(request).await {
+        Ok(body) => {
+            let (method, path) = (method.clone(), path.clone());
+            tokio::task::spawn_blocking(move || routes::route(method.as_str(), &path, &body))
+                .await
+                .unwrap_or_else(|err| Response::error(StatusCode::INTERNAL_SERVER_ERROR, err))
+        }
+        Err(response) => response,
+    };
+    debug!("{} {} -> {}", method, path, response.status);
+    Ok(response.into())
+}
+
+/// Serve the REST API to the connections accepted by a listener. At most
+/// `MAX_CONNECTIONS` connections are served at once, and idle connections
+/// are closed after `IDLE_TIMEOUT`.
+async fn serve(listener: TcpListener) {
+    let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    loop {
+        let permit = connections
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("Connection limit closed");
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                warn!("Error accepting HTTP connection: {}", err);
+                continue;
+            }
+        };
+        if let Err(err) = stream.set_nodelay(true) {
+            warn!("Error configuring HTTP connection from {}: {}", peer, err);
+            continue;
+        }
+        tokio::spawn(async move {
+            let _permit = permit;
+            debug!("Accepted HTTP connection from {}", peer);
+            let result = http1::Builder::new()
+                .timer(TokioTimer::new())
+                .header_read_timeout(IDLE_TIMEOUT)
+                .serve_connection(TokioIo::new(stream), service_fn(handle))
+                .await;
+            match result {
+                Ok(()) => debug!("Closed HTTP connection from {}", peer),
+                Err(err) => warn!("Error on HTTP connection from {}: {}", peer, err),
+            }
+        });
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    let mut args = std::env::args().skip(1);
+    let address = args.next().unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    if args.next().is_some() || address.starts_with('-') {
+        eprintln!("Usage: credx-http-server [ADDRESS]");
+        exit(2);
+    }
+    let listener = match TcpListener::bind(&address).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("Error binding to {}: {}", address, err);
+            exit(1);
+        }
+    };
+    println!("Serving indy-credx REST API on {}", address);
+    serve(listener).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::client::conn::http1::{handshake, SendRequest};
+    use hyper::Method;
+
+    use super::*;
+
+    /// Send a request and read the response status and body
+    async fn request(
+        sender: &mut SendRequest<Full<Bytes>>,
+        method: Method,
+        path: &str,
+        content_type: &str,
+        body: &str,
+    ) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header("host", "localhost")
+            .header(CONTENT_TYPE, content_type)
+            .body(Full::new(Bytes::from(body.to_string())))
+            .unwrap();
+        let response = sender.send_request(request).await.unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    async fn post(
+        sender: &mut SendRequest<Full<Bytes>>,
+        path: &str,
+        body: &str,
+    ) -> (StatusCode, serde_json::Value) {
+        request(sender, Method::POST, path, "application/json", body).await
+    }
+
+    #[tokio::test]
+    async fn http_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener));
+        let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let (mut sender, connection) = handshake(TokioIo::new(stream)).await.unwrap();
+        tokio::spawn(connection);
+
+        let (status, body) = post(
+            &mut sender,
+            "/v1/schemas",
+            r#"{"originDid":"NcYxiDXkpYi6ov5FcYDi1e","name":"gvt","version":"1.0","attrNames":["name"]}"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["id"], "NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0");
+
+        let (status, body) = post(&mut sender, "/v1/schemas", r#"{"name":"gvt"}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Input error");
+
+        let (status, _) = request(&mut sender, Method::GET, "/v1/schemas", "", "").await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        let (status, _) = request(&mut sender, Method::GET, "/unknown", "", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = post(&mut sender, "/v1/nonces", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["nonce"].is_string());
+
+        let (status, _) =
+            request(&mut sender, Method::POST, "/v1/schemas", "text/plain", "{}").await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+}
@@ -0,0 +1,203 @@
+//! The routes of the REST API
+
+use std::collections::HashMap;
+
+use hyper::StatusCode;
+use indy_credx::{
+    issuer,
+    types::{
+        AttributeNames, CredentialDefinition, CredentialDefinitionConfig,
+        CredentialDefinitionPrivate, CredentialKeyCorrectnessProof, CredentialOffer,
+        CredentialRequest, DidValue, MakeCredentialValues, Presentation, PresentationRequest,
+        RevocationRegistry, RevocationRegistryDefinition, RevocationRegistryId, Schema, SchemaId,
+        SignatureType, VerificationPolicy,
+    },
+    verifier, Error, ErrorKind,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::Response;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Handle a request by its method and path
+pub(crate) fn route(method: &str, path: &str, body: &[u8]) -> Response {
+    let handler: fn(&[u8]) -> Result<Vec<u8>> = match path {
+        "/health" => {
+            return match method {
+                "GET" => Response::ok(br#"{"status":"ok"}"#.to_vec()),
+                _ => Response::method_not_allowed("GET"),
+            }
+        }
+        "/v1/schemas" => create_schema,
+        "/v1/credential-definitions" => create_credential_definition,
+        "/v1/credential-offers" => create_credential_offer,
+        "/v1/credentials" => create_credential,
+        "/v1/nonces" => create_nonce,
+        "/v1/presentations/verify" => verify_presentation,
+        _ => return Response::error(StatusCode::NOT_FOUND, format!("Unknown path: {}", path)),
+    };
+    if method != "POST" {
+        return Response::method_not_allowed("POST");
+    }
+    handler(body).map_or_else(Response::from, Response::ok)
+}
+
+fn parse<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    serde_json::from_slice(body)
+        .map_err(|err| Error::from_msg(ErrorKind::Input, format!("Invalid request body: {}", err)))
+}
+
+fn to_json(value: &impl Serialize) -> Result<Vec<u8>> {
+    serde_json::to_vec(value).map_err(|err| {
+        Error::from_msg(ErrorKind::Unexpected, "Error serializing response").with_cause(err)
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateSchemaRequest {
+    origin_did: DidValue,
+    name: String,
+    version: String,
+    attr_names: AttributeNames,
+    #[serde(default)]
+    seq_no: Option<u32>,
+}
+
+fn create_schema(body: &[u8]) -> Result<Vec<u8>> {
+    let req: CreateSchemaRequest = parse(body)?;
+    let schema = issuer::create_schema(
+        &req.origin_did,
+        &req.name,
+        &req.version,
+        req.attr_names,
+        req.seq_no,
+    )?;
+    to_json(&schema)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateCredentialDefinitionRequest {
+    origin_did: DidValue,
+    schema: Schema,
+    #[serde(default)]
+    tag: String,
+    #[serde(default)]
+    support_revocation: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateCredentialDefinitionResponse {
+    cred_def: CredentialDefinition,
+    cred_def_private: CredentialDefinitionPrivate,
+    key_correctness_proof: CredentialKeyCorrectnessProof,
+}
+
+fn create_credential_definition(body: &[u8]) -> Result<Vec<u8>> {
+    let req: CreateCredentialDefinitionRequest = parse(body)?;
+    let (cred_def, cred_def_private, key_correctness_proof) = issuer::create_credential_definition(
+        &req.origin_did,
+        &req.schema,
+        &req.tag,
+        SignatureType::CL,
+        CredentialDefinitionConfig::new(req.support_revocation),
+    )?;
+    to_json(&CreateCredentialDefinitionResponse {
+        cred_def,
+        cred_def_private,
+        key_correctness_proof,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateCredentialOfferRequest {
+    schema_id: SchemaId,
+    cred_def: CredentialDefinition,
+    key_correctness_proof: CredentialKeyCorrectnessProof,
+}
+
+fn create_credential_offer(body: &[u8]) -> Result<Vec<u8>> {
+    let req: CreateCredentialOfferRequest = parse(body)?;
+    let offer =
+        issuer::create_credential_offer(&req.schema_id, &req.cred_def, &req.key_correctness_proof)?;
+    to_json(&offer)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateCredentialRequest {
+    cred_def: CredentialDefinition,
+    cred_def_private: CredentialDefinitionPrivate,
+    cred_offer: CredentialOffer,
+    cred_request: CredentialRequest,
+    /// The raw attribute values, which are encoded by the service
+    values: HashMap<String, String>,
+}
+
+fn create_credential(body: &[u8]) -> Result<Vec<u8>> {
+    let req: CreateCredentialRequest = parse(body)?;
+    let mut values = MakeCredentialValues::default();
+    for (name, raw) in req.values {
+        values.add_raw(name, raw)?;
+    }
+    let (credential, _, _) = issuer::create_credential(
+        &req.cred_def,
+        &req.cred_def_private,
+        &req.cred_offer,
+        &req.cred_request,
+        values.into(),
+        None,
+    )?;
+    to_json(&credential)
+}
+
+fn create_nonce(_body: &[u8]) -> Result<Vec<u8>> {
+    let nonce = verifier::generate_nonce()?;
+    to_json(&serde_json::json!({ "nonce": nonce }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VerifyPresentationRequest {
+    presentation: Presentation,
+    pres_req: PresentationRequest,
+    #[serde(default)]
+    schemas: Vec<Schema>,
+    #[serde(default)]
+    cred_defs: Vec<CredentialDefinition>,
+    #[serde(default)]
+    rev_reg_defs: Vec<RevocationRegistryDefinition>,
+    /// The revocation registries for each registry, by timestamp
+    #[serde(default)]
+    rev_regs: HashMap<RevocationRegistryId, HashMap<u64, RevocationRegistry>>,
+    #[serde(default)]
+    policy: VerificationPolicy,
+}
+
+fn verify_presentation(body: &[u8]) -> Result<Vec<u8>> {
+    let req: VerifyPresentationRequest = parse(body)?;
+    let rev_reg_defs = req
+        .rev_reg_defs
+        .iter()
+        .map(|d| (d.id().clone(), d))
+        .collect::<HashMap<_, _>>();
+    let rev_regs = req
+        .rev_regs
+        .iter()
+        .map(|(id, regs)| (id.clone(), regs.iter().map(|(ts, r)| (*ts, r)).collect()))
+        .collect::<HashMap<_, _>>();
+    let verified = verifier::verify_presentation_with_policy(
+        &req.presentation,
+        &req.pres_req,
+        &req.schemas.iter().map(|s| (s.id().clone(), s)).collect(),
+        &req.cred_defs.iter().map(|c| (c.id().clone(), c)).collect(),
+        Some(&rev_reg_defs).filter(|defs| !defs.is_empty()),
+        Some(&rev_regs).filter(|regs| !regs.is_empty()),
+        &req.policy,
+    )?;
+    to_json(&serde_json::json!({ "verified": verified }))
+}
@@ -0,0 +1,20 @@
+fn main() {
+    #[cfg(feature = "proto")]
+    compile_protos();
+}
+
+/// Generate the protocol buffer messages. The definitions are parsed by
+/// protox, so that no protoc installation is needed.
+#[cfg(feature = "proto")]
+fn compile_protos() {
+    const PROTOS: &[&str] = &["proto/indy_credx.proto", "proto/indy_credx_service.proto"];
+    for proto in PROTOS {
+        println!("cargo:rerun-if-changed={}", proto);
+    }
+    let descriptors = protox::compile(PROTOS, ["proto"]).expect("Error parsing proto definitions");
+    prost_build::Config::new()
+        // maps are written in key order, so that an object has one encoding
+        .btree_map(["."])
+        .compile_fds(descriptors)
+        .expect("Error generating protobuf messages");
+}
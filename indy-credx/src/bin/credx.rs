@@ -0,0 +1,525 @@
+//! A command line tool for debugging objects produced by indy-credx and
+//! other implementations.
+//!
+//! Usage: `credx <COMMAND> [ARGS]`. Run `credx help` for the commands.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::process::exit;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use indy_credx::{
+    issuer,
+    tails::TailsFileReader,
+    types::{
+        AttributeNames, Credential, CredentialDefinition, CredentialDefinitionConfig,
+        CredentialOffer, CredentialRequest, DidValue, Presentation, PresentationRequest,
+        RevocationRegistry, RevocationRegistryDefinition, RevocationRegistryDelta,
+        RevocationRegistryId, Schema, SignatureType,
+    },
+    utils::encode_credential_attribute,
+    validation::{self, ValidationProblem},
+    verifier,
+};
+
+const USAGE: &str = "Usage: credx <COMMAND> [ARGS]
+
+Commands:
+  inspect <FILE>
+      Describe the object in a JSON file and report any validation problems
+  create-schema --did <DID> --name <NAME> --version <VERSION> [--seq-no <N>] <ATTR>...
+      Create a schema with the given attribute names
+  create-cred-def --did <DID> --schema <FILE> [--tag <TAG>] [--support-revocation]
+      Create a credential definition, with its private key and key correctness proof
+  encode <VALUE>...
+      Print the encoding of each raw attribute value
+  verify --presentation <FILE> --request <FILE> [--schema <FILE>]... [--cred-def <FILE>]...
+         [--rev-reg-def <FILE>]... [--rev-reg <REV_REG_ID>@<TIMESTAMP>=<FILE>]...
+      Verify a presentation against its presentation request
+  check-tails <FILE> [--rev-reg-def <FILE> | --hash <HASH>]
+      Print the hash of a tails file, and check it against the expected hash
+  help
+      Print this message";
+
+type Result<T> = std::result::Result<T, Box<dyn StdError>>;
+
+/// An error in the command line arguments
+#[derive(Debug)]
+struct UsageError(String);
+
+impl Display for UsageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl StdError for UsageError {}
+
+fn usage<T>(message: impl Into<String>) -> Result<T> {
+    Err(Box::new(UsageError(message.into())))
+}
+
+/// The parsed arguments of a command
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, Vec<String>>,
+    flags: HashSet<String>,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>, options: &[&str], flags: &[&str]) -> Result<Self> {
+        let mut result = Args {
+            positional: Vec::new(),
+            options: HashMap::new(),
+            flags: HashSet::new(),
+        };
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                result.positional.extend(args.by_ref());
+            } else if let Some(name) = arg.strip_prefix("--") {
+                if flags.contains(&name) {
+                    result.flags.insert(name.to_string());
+                } else if options.contains(&name) {
+                    match args.next() {
+                        Some(value) => result
+                            .options
+                            .entry(name.to_string())
+                            .or_default()
+                            .push(value),
+                        None => return usage(format!("Missing value for --{}", name)),
+                    }
+                } else {
+                    return usage(format!("Unknown option: {}", arg));
+                }
+            } else {
+                result.positional.push(arg);
+            }
+        }
+        Ok(result)
+    }
+
+    fn values(&self, name: &str) -> &[String] {
+        self.options
+            .get(name)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn optional(&self, name: &str) -> Result<Option<&str>> {
+        match self.values(name) {
+            [] => Ok(None),
+            [value] => Ok(Some(value.as_str())),
+            _ => usage(format!("--{} may only be given once", name)),
+        }
+    }
+
+    fn required(&self, name: &str) -> Result<&str> {
+        match self.optional(name)? {
+            Some(value) => Ok(value),
+            None => usage(format!("Missing required option --{}", name)),
+        }
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    fn single_positional(&self, what: &str) -> Result<&str> {
+        match self.positional.as_slice() {
+            [value] => Ok(value.as_str()),
+            [] => usage(format!("Missing {}", what)),
+            _ => usage("Unexpected arguments"),
+        }
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: &str) -> Result<T> {
+    let data = fs::read(path).map_err(|err| format!("Error reading {}: {}", path, err))?;
+    Ok(serde_json::from_slice(&data).map_err(|err| format!("Error parsing {}: {}", path, err))?)
+}
+
+fn print_json(value: &impl serde::Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn main() {
+    env_logger::init();
+    let mut args = std::env::args().skip(1);
+    let command = args.next().unwrap_or_default();
+    let result = match command.as_str() {
+        "inspect" => inspect(args),
+        "create-schema" => create_schema(args),
+        "create-cred-def" => create_cred_def(args),
+        "encode" => encode(args),
+        "verify" => verify(args),
+        "check-tails" => check_tails(args),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(true)
+        }
+        "" => usage("Missing command"),
+        _ => usage(format!("Unknown command: {}", command)),
+    };
+    match result {
+        Ok(true) => (),
+        Ok(false) => exit(1),
+        Err(err) if err.is::<UsageError>() => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            exit(2);
+        }
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    }
+}
+
+/// Identify the kind of object from the fields of its JSON representation,
+/// accepting either field naming convention
+fn detect_object(value: &Value) -> Option<&'static str> {
+    let has = |key: &str| value.get(key).is_some();
+    let has_value = |key: &str| value.get("value").and_then(|v| v.get(key)).is_some();
+    if has("attrNames") || has("attr_names") {
+        Some("Schema")
+    } else if has("revocDefType") || has("revoc_def_type") {
+        Some("RevocationRegistryDefinition")
+    } else if has_value("primary") {
+        Some("CredentialDefinition")
+    } else if has("requested_proof") || has("requestedProof") {
+        Some("Presentation")
+    } else if has("requested_attributes") || has("requested_predicates") {
+        Some("PresentationRequest")
+    } else if has("blinded_ms") || has("blindedMs") {
+        Some("CredentialRequest")
+    } else if has("signature") && has("values") {
+        Some("Credential")
+    } else if (has("key_correctness_proof") || has("keyCorrectnessProof")) && has("nonce") {
+        Some("CredentialOffer")
+    } else if has_value("issued") || has_value("revoked") {
+        Some("RevocationRegistryDelta")
+    } else if has_value("accum") {
+        Some("RevocationRegistry")
+    } else if has_value("ms") {
+        Some("MasterSecret")
+    } else {
+        None
+    }
+}
+
+fn join<T: Display>(items: impl IntoIterator<Item = T>) -> String {
+    let mut items = items.into_iter().map(|i| i.to_string()).collect::<Vec<_>>();
+    items.sort();
+    items.join(", ")
+}
+
+fn inspect(args: impl Iterator<Item = String>) -> Result<bool> {
+    let args = Args::parse(args, &[], &[])?;
+    let path = args.single_positional("file name")?;
+    let value: Value = read_json(path)?;
+    let kind = detect_object(&value).ok_or("Unrecognized object")?;
+    println!("Type: {}", kind);
+
+    let problems: Vec<ValidationProblem> = match kind {
+        "Schema" => {
+            let schema: Schema = serde_json::from_value(value)?;
+            let Schema::SchemaV1(v1) = &schema;
+            println!("Id: {}", v1.id);
+            println!("Name: {}", v1.name);
+            println!("Version: {}", v1.version);
+            println!("Attributes: {}", join(v1.attr_names.0.iter()));
+            if let Some(seq_no) = v1.seq_no {
+                println!("Sequence number: {}", seq_no);
+            }
+            validation::validate_schema(&schema)
+        }
+        "CredentialDefinition" => {
+            let cred_def: CredentialDefinition = serde_json::from_value(value)?;
+            let CredentialDefinition::CredentialDefinitionV1(v1) = &cred_def;
+            println!("Id: {}", v1.id);
+            println!("Schema: {}", v1.schema_id);
+            println!("Tag: {}", v1.tag);
+            println!("Supports revocation: {}", v1.value.revocation.is_some());
+            validation::validate_credential_definition(&cred_def, None)?
+        }
+        "RevocationRegistryDefinition" => {
+            let rev_reg_def: RevocationRegistryDefinition = serde_json::from_value(value)?;
+            let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(v1) = &rev_reg_def;
+            println!("Id: {}", v1.id);
+            println!("Credential definition: {}", v1.cred_def_id);
+            println!("Tag: {}", v1.tag);
+            println!("Issuance type: {}", v1.value.issuance_type.to_str());
+            println!("Maximum credentials: {}", v1.value.max_cred_num);
+            println!("Tails hash: {}", v1.value.tails_hash);
+            println!("Tails location: {}", v1.value.tails_location);
+            validation::validate_revocation_registry_definition(&rev_reg_def)
+        }
+        "CredentialOffer" => {
+            let offer: CredentialOffer = serde_json::from_value(value)?;
+            println!("Schema: {}", offer.schema_id);
+            println!("Credential definition: {}", offer.cred_def_id);
+            validation::validate_object(&offer)
+        }
+        "CredentialRequest" => {
+            let request: CredentialRequest = serde_json::from_value(value)?;
+            println!("Prover: {}", request.prover_did);
+            println!("Credential definition: {}", request.cred_def_id);
+            validation::validate_object(&request)
+        }
+        "Credential" => {
+            let credential: Credential = serde_json::from_value(value)?;
+            println!("Schema: {}", credential.schema_id);
+            println!("Credential definition: {}", credential.cred_def_id);
+            if let Some(rev_reg_id) = &credential.rev_reg_id {
+                println!("Revocation registry: {}", rev_reg_id);
+            }
+            let mut values = credential.values.0.iter().collect::<Vec<_>>();
+            values.sort_by_key(|(name, _)| name.as_str());
+            for (name, value) in values {
+                println!("Attribute {}: {:?} ({})", name, value.raw, value.encoded);
+            }
+            validation::validate_credential(&credential)?
+        }
+        "PresentationRequest" => {
+            let pres_req: PresentationRequest = serde_json::from_value(value)?;
+            let payload = pres_req.value();
+            println!("Name: {}", payload.name);
+            println!("Version: {}", payload.version);
+            println!("Nonce: {}", payload.nonce);
+            println!(
+                "Requested attributes: {}",
+                join(payload.requested_attributes.keys())
+            );
+            println!(
+                "Requested predicates: {}",
+                join(payload.requested_predicates.keys())
+            );
+            if let Some(interval) = &payload.non_revoked {
+                println!("Non-revoked: {:?} to {:?}", interval.from, interval.to);
+            }
+            validation::validate_object(&pres_req)
+        }
+        "Presentation" => {
+            let presentation: Presentation = serde_json::from_value(value)?;
+            for (index, identifier) in presentation.identifiers.iter().enumerate() {
+                println!(
+                    "Sub-proof {}: {} ({})",
+                    index, identifier.cred_def_id, identifier.schema_id
+                );
+                if let Some(rev_reg_id) = &identifier.rev_reg_id {
+                    println!(
+                        "  Revocation registry: {} at {:?}",
+                        rev_reg_id, identifier.timestamp
+                    );
+                }
+            }
+            let proof = &presentation.requested_proof;
+            for (referent, attr) in proof.revealed_attrs.iter() {
+                println!(
+                    "Revealed {}: {:?} (sub-proof {})",
+                    referent, attr.raw, attr.sub_proof_index
+                );
+            }
+            println!(
+                "Revealed groups: {}",
+                join(proof.revealed_attr_groups.keys())
+            );
+            println!(
+                "Unrevealed attributes: {}",
+                join(proof.unrevealed_attrs.keys())
+            );
+            println!("Self-attested: {}", join(proof.self_attested_attrs.keys()));
+            println!("Predicates: {}", join(proof.predicates.keys()));
+            validation::validate_object(&presentation)
+        }
+        "RevocationRegistry" => {
+            serde_json::from_value::<RevocationRegistry>(value)?;
+            Vec::new()
+        }
+        "RevocationRegistryDelta" => {
+            let delta: RevocationRegistryDelta = serde_json::from_value(value)?;
+            validation::validate_object(&delta)
+        }
+        // The value of a master secret is not printed
+        _ => Vec::new(),
+    };
+
+    if problems.is_empty() {
+        println!("No problems found");
+    }
+    for problem in problems.iter() {
+        match &problem.field {
+            Some(field) => println!("Problem: {}: {}", field, problem.message),
+            None => println!("Problem: {}", problem.message),
+        }
+    }
+    Ok(problems.is_empty())
+}
+
+fn create_schema(args: impl Iterator<Item = String>) -> Result<bool> {
+    let args = Args::parse(args, &["did", "name", "version", "seq-no"], &[])?;
+    let seq_no = match args.optional("seq-no")? {
+        Some(seq_no) => match seq_no.parse() {
+            Ok(seq_no) => Some(seq_no),
+            Err(_) => return usage("Invalid sequence number"),
+        },
+        None => None,
+    };
+    if args.positional.is_empty() {
+        return usage("Missing attribute names");
+    }
+    let schema = issuer::create_schema(
+        &DidValue::from(args.required("did")?.to_string()),
+        args.required("name")?,
+        args.required("version")?,
+        AttributeNames::from(args.positional.clone()),
+        seq_no,
+    )?;
+    print_json(&schema)?;
+    Ok(true)
+}
+
+fn create_cred_def(args: impl Iterator<Item = String>) -> Result<bool> {
+    let args = Args::parse(args, &["did", "schema", "tag"], &["support-revocation"])?;
+    if !args.positional.is_empty() {
+        return usage("Unexpected arguments");
+    }
+    let schema: Schema = read_json(args.required("schema")?)?;
+    let (cred_def, cred_def_private, key_correctness_proof) = issuer::create_credential_definition(
+        &DidValue::from(args.required("did")?.to_string()),
+        &schema,
+        args.optional("tag")?.unwrap_or("default"),
+        SignatureType::CL,
+        CredentialDefinitionConfig::new(args.flag("support-revocation")),
+    )?;
+    print_json(&serde_json::json!({
+        "credDef": cred_def,
+        "credDefPrivate": cred_def_private,
+        "keyCorrectnessProof": key_correctness_proof,
+    }))?;
+    Ok(true)
+}
+
+fn encode(args: impl Iterator<Item = String>) -> Result<bool> {
+    let args = Args::parse(args, &[], &[])?;
+    if args.positional.is_empty() {
+        return usage("Missing values to encode");
+    }
+    for raw in args.positional.iter() {
+        println!("{}", encode_credential_attribute(raw)?);
+    }
+    Ok(true)
+}
+
+fn verify(args: impl Iterator<Item = String>) -> Result<bool> {
+    let args = Args::parse(
+        args,
+        &[
+            "presentation",
+            "request",
+            "schema",
+            "cred-def",
+            "rev-reg-def",
+            "rev-reg",
+        ],
+        &[],
+    )?;
+    if !args.positional.is_empty() {
+        return usage("Unexpected arguments");
+    }
+    let presentation: Presentation = read_json(args.required("presentation")?)?;
+    let pres_req: PresentationRequest = read_json(args.required("request")?)?;
+    let schemas = args
+        .values("schema")
+        .iter()
+        .map(|path| read_json::<Schema>(path))
+        .collect::<Result<Vec<_>>>()?;
+    let cred_defs = args
+        .values("cred-def")
+        .iter()
+        .map(|path| read_json::<CredentialDefinition>(path))
+        .collect::<Result<Vec<_>>>()?;
+    let rev_reg_defs = args
+        .values("rev-reg-def")
+        .iter()
+        .map(|path| read_json::<RevocationRegistryDefinition>(path))
+        .collect::<Result<Vec<_>>>()?;
+    let mut rev_regs = Vec::new();
+    for arg in args.values("rev-reg") {
+        let parsed = arg.split_once('=').and_then(|(key, path)| {
+            let (id, timestamp) = key.rsplit_once('@')?;
+            Some((id, timestamp.parse::<u64>().ok()?, path))
+        });
+        match parsed {
+            Some((id, timestamp, path)) => rev_regs.push((
+                RevocationRegistryId::from(id.to_string()),
+                timestamp,
+                read_json::<RevocationRegistry>(path)?,
+            )),
+            None => return usage(format!("Invalid revocation registry: {}", arg)),
+        }
+    }
+
+    let rev_reg_defs = rev_reg_defs
+        .iter()
+        .map(|def| (def.id().clone(), def))
+        .collect::<HashMap<_, _>>();
+    let mut rev_reg_map = HashMap::new();
+    for (id, timestamp, rev_reg) in rev_regs.iter() {
+        rev_reg_map
+            .entry(id.clone())
+            .or_insert_with(HashMap::new)
+            .insert(*timestamp, rev_reg);
+    }
+    let verified = verifier::verify_presentation(
+        &presentation,
+        &pres_req,
+        &schemas.iter().map(|s| (s.id().clone(), s)).collect(),
+        &cred_defs.iter().map(|c| (c.id().clone(), c)).collect(),
+        Some(&rev_reg_defs).filter(|defs| !defs.is_empty()),
+        Some(&rev_reg_map).filter(|regs| !regs.is_empty()),
+    )?;
+    println!(
+        "{}",
+        if verified {
+            "Presentation verified"
+        } else {
+            "Presentation not verified"
+        }
+    );
+    Ok(verified)
+}
+
+fn check_tails(args: impl Iterator<Item = String>) -> Result<bool> {
+    let args = Args::parse(args, &["rev-reg-def", "hash"], &[])?;
+    let path = args.single_positional("tails file name")?;
+    let expected = match (args.optional("rev-reg-def")?, args.optional("hash")?) {
+        (Some(_), Some(_)) => return usage("Only one of --rev-reg-def and --hash may be given"),
+        (Some(rev_reg_def), None) => {
+            let rev_reg_def: RevocationRegistryDefinition = read_json(rev_reg_def)?;
+            let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(v1) = rev_reg_def;
+            Some(v1.value.tails_hash)
+        }
+        (None, hash) => hash.map(str::to_string),
+    };
+    let reader = TailsFileReader::new(path);
+    println!("Tails hash: {}", reader.hash()?);
+    match expected {
+        Some(expected) => match reader.verify_hash(&expected) {
+            Ok(()) => {
+                println!("Tails hash matches");
+                Ok(true)
+            }
+            Err(err) => {
+                println!("{}", err);
+                Ok(false)
+            }
+        },
+        None => Ok(true),
+    }
+}
@@ -0,0 +1,304 @@
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock, RwLockWriteGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+
+use crate::audit::SharedAuditSink;
+use crate::encoders::EncoderRegistry;
+use crate::error::Result;
+use crate::types::SerializationProfile;
+
+static CONFIG: Lazy<RwLock<Arc<Config>>> = Lazy::new(|| RwLock::new(Arc::new(Config::default())));
+
+/// A source of the current time, as seconds since the Unix epoch
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> u64;
+}
+
+/// The system clock
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+}
+
+/// A clock which always reports the same time, for deterministic tests
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedClock(pub u64);
+
+impl Clock for FixedClock {
+    fn now(&self) -> u64 {
+        self.0
+    }
+}
+
+/// The system clock adjusted by a number of seconds, for devices with a
+/// known clock skew
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OffsetClock(pub i64);
+
+impl Clock for OffsetClock {
+    fn now(&self) -> u64 {
+        let now = SystemClock.now() as i64;
+        now.saturating_add(self.0).max(0) as u64
+    }
+}
+
+/// A shared reference to a clock implementation
+#[derive(Clone, Debug)]
+pub struct SharedClock(Arc<dyn Clock>);
+
+impl SharedClock {
+    pub fn new(clock: impl Clock + 'static) -> Self {
+        Self(Arc::new(clock))
+    }
+}
+
+impl Clock for SharedClock {
+    fn now(&self) -> u64 {
+        self.0.now()
+    }
+}
+
+impl PartialEq for SharedClock {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedClock {}
+
+/// Limits on the size of presentations accepted for verification, so that
+/// verifiers handling untrusted input can reject oversized proofs before
+/// parsing and verifying them. Each limit is disabled when unset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VerificationLimits {
+    /// The maximum number of referents in the requested proof, counting
+    /// attributes, attribute groups and predicates
+    pub max_referents: Option<usize>,
+    /// The maximum number of credentials from which the proof is derived
+    pub max_credentials: Option<usize>,
+    /// The maximum size in bytes of a presentation loaded from JSON
+    pub max_json_size: Option<usize>,
+}
+
+impl VerificationLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_referents(mut self, max: Option<usize>) -> Self {
+        self.max_referents = max;
+        self
+    }
+
+    pub fn max_credentials(mut self, max: Option<usize>) -> Self {
+        self.max_credentials = max;
+        self
+    }
+
+    pub fn max_json_size(mut self, max: Option<usize>) -> Self {
+        self.max_json_size = max;
+        self
+    }
+}
+
+/// Library-wide runtime configuration
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    /// The maximum number of worker threads used by batch operations. Tails
+    /// generation and batch issuance only use worker threads when the
+    /// `parallel` feature is enabled, while batch verification always does.
+    /// When unset, the number of available CPUs is used.
+    pub thread_pool_size: Option<usize>,
+    /// The clock used wherever the current time is required, such as when
+    /// checking the freshness of non-revocation proofs. When unset, the
+    /// system clock is used.
+    pub clock: Option<SharedClock>,
+    /// The sink receiving records of credential issuance and revocation
+    pub audit_sink: Option<SharedAuditSink>,
+    /// The field naming convention of objects serialized through the FFI
+    pub serialization_profile: SerializationProfile,
+    /// The limits applied to presentations before verification
+    pub verification_limits: VerificationLimits,
+    /// The encoders of raw attribute values registered by schema and
+    /// attribute name
+    pub attribute_encoders: EncoderRegistry,
+    /// The key used to decrypt encrypted tails files opened by path, when no
+    /// key is given to the tails reader.
+    #[cfg(feature = "tails_encryption")]
+    pub tails_key: Option<crate::tails_encryption::TailsKey>,
+    /// The executor running the CPU-bound work of async operations. When
+    /// unset, each task runs on a new thread.
+    #[cfg(feature = "async")]
+    pub blocking_executor: Option<crate::asynchronous::SharedBlockingExecutor>,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn thread_pool_size(mut self, size: Option<usize>) -> Self {
+        self.thread_pool_size = size;
+        self
+    }
+
+    pub fn clock(mut self, clock: Option<SharedClock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn audit_sink(mut self, sink: Option<SharedAuditSink>) -> Self {
+        self.audit_sink = sink;
+        self
+    }
+
+    pub fn serialization_profile(mut self, profile: SerializationProfile) -> Self {
+        self.serialization_profile = profile;
+        self
+    }
+
+    pub fn verification_limits(mut self, limits: VerificationLimits) -> Self {
+        self.verification_limits = limits;
+        self
+    }
+
+    pub fn attribute_encoders(mut self, encoders: EncoderRegistry) -> Self {
+        self.attribute_encoders = encoders;
+        self
+    }
+
+    #[cfg(feature = "tails_encryption")]
+    pub fn tails_key(mut self, key: Option<crate::tails_encryption::TailsKey>) -> Self {
+        self.tails_key = key;
+        self
+    }
+
+    #[cfg(feature = "async")]
+    pub fn blocking_executor(
+        mut self,
+        executor: Option<crate::asynchronous::SharedBlockingExecutor>,
+    ) -> Self {
+        self.blocking_executor = executor;
+        self
+    }
+
+    /// The current time according to the configured clock
+    pub fn now(&self) -> u64 {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => SystemClock.now(),
+        }
+    }
+
+    /// The number of worker threads to be used by parallel operations
+    pub fn effective_thread_pool_size(&self) -> usize {
+        self.thread_pool_size.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(usize::from)
+                .unwrap_or(1)
+        })
+    }
+}
+
+impl Config {
+    fn check(&self) -> Result<()> {
+        if self.thread_pool_size == Some(0) {
+            return Err(err_msg!("Thread pool size must be greater than zero"));
+        }
+        Ok(())
+    }
+}
+
+fn write_config() -> Result<RwLockWriteGuard<'static, Arc<Config>>> {
+    CONFIG
+        .write()
+        .map_err(|_| err_msg!(Unexpected, "Error updating library configuration"))
+}
+
+/// Replace the current library configuration
+pub fn set_config(config: Config) -> Result<()> {
+    config.check()?;
+    *write_config()? = Arc::new(config);
+    Ok(())
+}
+
+/// Modify the current library configuration. The configuration is locked
+/// while `update` runs, so that concurrent updates to different settings
+/// are not lost.
+pub fn update_config(update: impl FnOnce(Config) -> Config) -> Result<()> {
+    let mut current = write_config()?;
+    let config = update(Config::clone(&current));
+    config.check()?;
+    *current = Arc::new(config);
+    Ok(())
+}
+
+/// Fetch the current library configuration. The configuration is shared
+/// until it is replaced, and is not affected by later updates.
+pub fn get_config() -> Arc<Config> {
+    match CONFIG.read() {
+        Ok(config) => config.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thread_pool_size() {
+        assert!(Config::new().effective_thread_pool_size() >= 1);
+        assert_eq!(
+            Config::new()
+                .thread_pool_size(Some(2))
+                .effective_thread_pool_size(),
+            2
+        );
+        assert_kind!(Input, set_config(Config::new().thread_pool_size(Some(0))));
+        assert_kind!(
+            Input,
+            update_config(|config| config.thread_pool_size(Some(0)))
+        );
+    }
+
+    #[test]
+    fn concurrent_updates() {
+        let limits = VerificationLimits::new().max_json_size(Some(1 << 20));
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        update_config(|config| {
+                            let limits = config.verification_limits;
+                            config.verification_limits(limits)
+                        })
+                        .unwrap();
+                    }
+                });
+            }
+            scope.spawn(|| update_config(|config| config.verification_limits(limits)).unwrap());
+        });
+        assert_eq!(get_config().verification_limits, limits);
+        update_config(|config| config.verification_limits(Default::default())).unwrap();
+    }
+
+    #[test]
+    fn clock() {
+        assert!(Config::new().now() > 0);
+        let config = Config::new().clock(Some(SharedClock::new(FixedClock(1234))));
+        assert_eq!(config.now(), 1234);
+        assert_eq!(config, config.clone());
+        let skewed = OffsetClock(-3600).now();
+        assert!(SystemClock.now() - skewed >= 3600);
+    }
+}
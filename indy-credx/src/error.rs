@@ -18,6 +18,10 @@ pub enum ErrorKind {
     InvalidUserRevocId,
     ProofRejected,
     RevocationRegistryFull,
+    LimitExceeded,
+    // Control flow
+    Cancelled,
+    HandleEvicted,
 }
 
 impl ErrorKind {
@@ -31,6 +35,9 @@ impl ErrorKind {
             Self::InvalidUserRevocId => "Invalid revocation accumulator index",
             Self::ProofRejected => "Proof rejected",
             Self::RevocationRegistryFull => "Revocation registry full",
+            Self::LimitExceeded => "Limit exceeded",
+            Self::Cancelled => "Operation cancelled",
+            Self::HandleEvicted => "Object handle evicted",
         }
     }
 }
@@ -128,6 +135,20 @@ impl From<std::io::Error> for Error {
     }
 }
 
+#[cfg(feature = "askar")]
+impl From<aries_askar::Error> for Error {
+    fn from(err: aries_askar::Error) -> Self {
+        Error::from(ErrorKind::IOError).with_cause(err)
+    }
+}
+
+#[cfg(feature = "store")]
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::from(ErrorKind::IOError).with_cause(err)
+    }
+}
+
 impl From<serde_json::Error> for Error {
     fn from(err: serde_json::Error) -> Self {
         // FIXME could be input or output...
@@ -183,6 +204,7 @@ macro_rules! err_map {
     };
 }
 
+#[allow(dead_code)]
 pub trait ResultExt<T, E> {
     fn map_err_string(self) -> StdResult<T, String>;
     fn map_input_err<F, M>(self, mapfn: F) -> Result<T>
@@ -0,0 +1,265 @@
+//! Tracking of the strings and byte buffers returned to FFI callers.
+//!
+//! The strings and non-empty buffers handed out by the library are counted
+//! until they are released. Debug builds also record each pointer, so that
+//! freeing an unknown or previously freed pointer is reported through the
+//! error API rather than corrupting the heap. Release builds do not validate
+//! frees, which avoids taking a global lock on every allocation.
+
+use std::collections::BTreeMap;
+#[cfg(debug_assertions)]
+use std::collections::{HashMap, HashSet};
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(debug_assertions)]
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use ffi_support::{rust_string_to_c, ByteBuffer};
+#[cfg(debug_assertions)]
+use once_cell::sync::Lazy;
+use zeroize::Zeroize;
+
+use super::error::{catch_error, ErrorCode};
+use super::object::{ObjectHandle, FFI_OBJECTS};
+use super::util::FfiList;
+use crate::error::{ErrorKind, Result};
+
+static STRINGS: AtomicUsize = AtomicUsize::new(0);
+static BUFFERS: AtomicUsize = AtomicUsize::new(0);
+static BUFFER_BYTES: AtomicUsize = AtomicUsize::new(0);
+static DEBUG: AtomicBool = AtomicBool::new(false);
+
+#[cfg(debug_assertions)]
+static ALLOCATIONS: Lazy<Mutex<Allocations>> = Lazy::new(|| Mutex::new(Allocations::default()));
+
+#[cfg(debug_assertions)]
+#[derive(Debug, Default)]
+struct Allocations {
+    strings: HashSet<usize>,
+    buffers: HashMap<usize, usize>,
+}
+
+/// Lock the allocation registry. The registry is only updated by single
+/// insertions and removals, so it remains consistent after a panic.
+#[cfg(debug_assertions)]
+fn allocations() -> MutexGuard<'static, Allocations> {
+    ALLOCATIONS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn debug_enabled() -> bool {
+    DEBUG.load(Ordering::Relaxed)
+}
+
+fn release(counter: &AtomicUsize, amount: usize) {
+    // an unvalidated invalid free must not wrap the count
+    let _ = counter.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+        Some(count.saturating_sub(amount))
+    });
+}
+
+#[derive(Debug, Serialize)]
+struct AllocationReport {
+    strings: usize,
+    buffers: usize,
+    buffer_bytes: usize,
+    objects: BTreeMap<&'static str, usize>,
+}
+
+/// Allocate a C string to be released with `credx_string_free`
+pub(crate) fn alloc_string(value: impl Into<String>) -> *mut c_char {
+    let ptr = rust_string_to_c(value);
+    STRINGS.fetch_add(1, Ordering::SeqCst);
+    #[cfg(debug_assertions)]
+    allocations().strings.insert(ptr as usize);
+    if debug_enabled() {
+        debug!("Allocated string: {:p}", ptr);
+    }
+    ptr
+}
+
+/// Allocate a byte buffer to be released with `credx_buffer_free`
+pub(crate) fn alloc_buffer(value: Vec<u8>) -> ByteBuffer {
+    let buffer = ByteBuffer::from_vec(value);
+    let (ptr, len) = buffer_parts(&buffer);
+    // empty buffers own no memory and may share a dangling pointer
+    if len > 0 {
+        BUFFERS.fetch_add(1, Ordering::SeqCst);
+        BUFFER_BYTES.fetch_add(len, Ordering::SeqCst);
+        #[cfg(debug_assertions)]
+        allocations().buffers.insert(ptr, len);
+        if debug_enabled() {
+            debug!("Allocated buffer: {:#x} [{} bytes]", ptr, len);
+        }
+    }
+    buffer
+}
+
+fn buffer_parts(buffer: &ByteBuffer) -> (usize, usize) {
+    let slice = buffer.as_slice();
+    (slice.as_ptr() as usize, slice.len())
+}
+
+fn free_string(value: *mut c_char) -> Result<()> {
+    if value.is_null() {
+        return Ok(());
+    }
+    #[cfg(debug_assertions)]
+    if !allocations().strings.remove(&(value as usize)) {
+        if debug_enabled() {
+            warn!("Rejected free of unknown string: {:p}", value);
+        }
+        return Err(err_msg!(
+            InvalidState,
+            "String was not allocated by the library or was already freed"
+        ));
+    }
+    release(&STRINGS, 1);
+    unsafe { ffi_support::destroy_c_string(value) };
+    Ok(())
+}
+
+/// Remove a non-empty buffer from the registry, failing if it was not
+/// allocated by the library or was already freed
+#[cfg(debug_assertions)]
+fn untrack_buffer(buffer: &ByteBuffer) -> Result<()> {
+    let (ptr, len) = buffer_parts(buffer);
+    let mut allocs = allocations();
+    if allocs.buffers.get(&ptr) != Some(&len) {
+        if debug_enabled() {
+            warn!("Rejected free of unknown buffer: {:#x}", ptr);
+        }
+        return Err(err_msg!(
+            InvalidState,
+            "Buffer was not allocated by the library or was already freed"
+        ));
+    }
+    allocs.buffers.remove(&ptr);
+    Ok(())
+}
+
+fn free_buffer(buffer: ByteBuffer) -> Result<()> {
+    let len = buffer.as_slice().len();
+    if len > 0 {
+        #[cfg(debug_assertions)]
+        untrack_buffer(&buffer)?;
+        release(&BUFFERS, 1);
+        release(&BUFFER_BYTES, len);
+    }
+    buffer.destroy_into_vec().zeroize();
+    Ok(())
+}
+
+/// Release a string returned by the library. Debug builds report an error
+/// for unknown or previously freed pointers.
+#[no_mangle]
+pub extern "C" fn credx_string_free_checked(value: *mut c_char) -> ErrorCode {
+    catch_error(|| free_string(value))
+}
+
+/// Release a string returned by the library. Invalid frees are ignored and
+/// recorded as the current error.
+#[no_mangle]
+pub extern "C" fn credx_string_free(value: *mut c_char) {
+    credx_string_free_checked(value);
+}
+
+/// Release a byte buffer returned by the library. Debug builds report an
+/// error for unknown or previously freed buffers.
+#[no_mangle]
+pub extern "C" fn credx_buffer_free_checked(buffer: ByteBuffer) -> ErrorCode {
+    catch_error(|| free_buffer(buffer))
+}
+
+/// Release a byte buffer returned by the library. Invalid frees are ignored
+/// and recorded as the current error.
+#[no_mangle]
+pub extern "C" fn credx_buffer_free(buffer: ByteBuffer) {
+    credx_buffer_free_checked(buffer);
+}
+
+/// Release an object handle, reporting an error for unknown or previously
+/// freed handles. Releasing an evicted handle is not an error.
+#[no_mangle]
+pub extern "C" fn credx_object_free_checked(handle: ObjectHandle) -> ErrorCode {
+    catch_error(|| match handle.remove() {
+        Err(err) if err.kind() != ErrorKind::HandleEvicted => Err(err),
+        _ => Ok(()),
+    })
+}
+
+/// Release a list of object handles. Every handle is released even when
+/// some are invalid, in which case an error is returned.
+#[no_mangle]
+pub extern "C" fn credx_object_list_free(handles: FfiList<ObjectHandle>) -> ErrorCode {
+    catch_error(|| {
+        let invalid = handles
+            .as_slice()
+            .iter()
+            .filter(|handle| {
+                matches!(handle.remove(), Err(err) if err.kind() != ErrorKind::HandleEvicted)
+            })
+            .count();
+        if invalid > 0 {
+            return Err(err_msg!(
+                InvalidState,
+                "{} handles were invalid or already freed",
+                invalid
+            ));
+        }
+        Ok(())
+    })
+}
+
+/// Log each allocation and any rejected free, for diagnosing leaks and
+/// double frees in wrappers
+#[no_mangle]
+pub extern "C" fn credx_set_allocation_debug(enabled: i8) -> ErrorCode {
+    catch_error(|| {
+        DEBUG.store(enabled != 0, Ordering::Relaxed);
+        Ok(())
+    })
+}
+
+/// Report the strings, buffers and objects which have not yet been freed.
+/// The returned string itself is not included in the counts.
+#[no_mangle]
+pub extern "C" fn credx_get_allocation_report(report_p: *mut *const c_char) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(report_p);
+        let report = {
+            let mut objects = BTreeMap::new();
+            for obj in FFI_OBJECTS
+                .lock()
+                .map_err(|_| err_msg!("Error locking object store"))?
+                .objects()
+            {
+                *objects.entry(obj.type_name()).or_insert(0) += 1;
+            }
+            AllocationReport {
+                strings: STRINGS.load(Ordering::SeqCst),
+                buffers: BUFFERS.load(Ordering::SeqCst),
+                buffer_bytes: BUFFER_BYTES.load(Ordering::SeqCst),
+                objects,
+            }
+        };
+        unsafe { *report_p = alloc_string(serde_json::to_string(&report)?) };
+        Ok(())
+    })
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn free_string_recovers_poisoned_registry() {
+        let _ = std::thread::spawn(|| {
+            let _allocs = allocations();
+            panic!("poison the allocation registry");
+        })
+        .join();
+        let value = alloc_string("value");
+        free_string(value).unwrap();
+        assert_kind!(InvalidState, free_string(value));
+    }
+}
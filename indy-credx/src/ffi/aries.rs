@@ -0,0 +1,206 @@
+use std::convert::TryInto;
+use std::os::raw::c_char;
+
+use ffi_support::FfiStr;
+
+use super::alloc::alloc_string;
+use super::attachment::decode_attachment_object;
+use super::error::{catch_error, ErrorCode};
+use super::object::ObjectHandle;
+use super::util::FfiStrList;
+use crate::services::aries::{
+    attachment::{find_attachment, Attachment, AttachmentFormatDescriptor},
+    issue_credential::{CredentialPreview, IssueCredential, OfferCredential, RequestCredential},
+    present_proof::{PresentationMessage, RequestPresentation},
+    revocation_notification::RevocationNotification,
+};
+use crate::services::types::RevocationRegistryId;
+
+#[no_mangle]
+pub extern "C" fn credx_aries_offer_credential(
+    cred_offer: ObjectHandle,
+    preview_names: FfiStrList,
+    preview_values: FfiStrList,
+    comment: FfiStr,
+    message_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(message_p);
+        if preview_names.len() != preview_values.len() {
+            return Err(err_msg!(
+                "Mismatch between length of preview names and values"
+            ));
+        }
+        let mut preview = CredentialPreview::new();
+        for (name, value) in preview_names
+            .as_slice()
+            .iter()
+            .zip(preview_values.as_slice())
+        {
+            let name = name
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing preview attribute name"))?;
+            let value = value
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing preview attribute value"))?;
+            preview = preview.attribute(name, value);
+        }
+        let mut message = OfferCredential::new(cred_offer.load()?.cast_ref()?, preview)?;
+        if let Some(comment) = comment.as_opt_str() {
+            message = message.comment(comment);
+        }
+        let message = serde_json::to_string(&message)?;
+        unsafe { *message_p = alloc_string(message) };
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn credx_aries_request_credential(
+    cred_request: ObjectHandle,
+    thread_id: FfiStr,
+    comment: FfiStr,
+    message_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(message_p);
+        let thread_id = thread_id
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing thread ID"))?;
+        let mut message = RequestCredential::new(cred_request.load()?.cast_ref()?, thread_id)?;
+        if let Some(comment) = comment.as_opt_str() {
+            message = message.comment(comment);
+        }
+        let message = serde_json::to_string(&message)?;
+        unsafe { *message_p = alloc_string(message) };
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn credx_aries_issue_credential(
+    cred: ObjectHandle,
+    thread_id: FfiStr,
+    comment: FfiStr,
+    message_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(message_p);
+        let thread_id = thread_id
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing thread ID"))?;
+        let mut message = IssueCredential::new(cred.load()?.cast_ref()?, thread_id)?;
+        if let Some(comment) = comment.as_opt_str() {
+            message = message.comment(comment);
+        }
+        let message = serde_json::to_string(&message)?;
+        unsafe { *message_p = alloc_string(message) };
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn credx_aries_request_presentation(
+    pres_req: ObjectHandle,
+    comment: FfiStr,
+    message_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(message_p);
+        let mut message = RequestPresentation::new(pres_req.load()?.cast_ref()?)?;
+        if let Some(comment) = comment.as_opt_str() {
+            message = message.comment(comment);
+        }
+        let message = serde_json::to_string(&message)?;
+        unsafe { *message_p = alloc_string(message) };
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn credx_aries_presentation(
+    presentation: ObjectHandle,
+    thread_id: FfiStr,
+    comment: FfiStr,
+    message_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(message_p);
+        let thread_id = thread_id
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing thread ID"))?;
+        let mut message = PresentationMessage::new(presentation.load()?.cast_ref()?, thread_id)?;
+        if let Some(comment) = comment.as_opt_str() {
+            message = message.comment(comment);
+        }
+        let message = serde_json::to_string(&message)?;
+        unsafe { *message_p = alloc_string(message) };
+        Ok(())
+    })
+}
+
+/// Build the revocation notification of the credential at an index of a
+/// revocation registry
+#[no_mangle]
+pub extern "C" fn credx_aries_revocation_notification(
+    rev_reg_id: FfiStr,
+    cred_rev_idx: i64,
+    comment: FfiStr,
+    message_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(message_p);
+        let rev_reg_id = rev_reg_id
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing revocation registry ID"))?;
+        let cred_rev_idx = cred_rev_idx
+            .try_into()
+            .map_err(|_| err_msg!("Invalid registry index"))?;
+        let mut message = RevocationNotification::new(
+            &RevocationRegistryId::from(rev_reg_id.to_string()),
+            cred_rev_idx,
+        );
+        if let Some(comment) = comment.as_opt_str() {
+            message = message.comment(comment);
+        }
+        let message = serde_json::to_string(&message)?;
+        unsafe { *message_p = alloc_string(message) };
+        Ok(())
+    })
+}
+
+/// Extract the object attached to an issue-credential or present-proof
+/// message in the given format
+#[no_mangle]
+pub extern "C" fn credx_aries_message_get_attachment(
+    message: FfiStr,
+    format: FfiStr,
+    handle_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(handle_p);
+        let format = format
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing attachment format"))?;
+        let message: serde_json::Map<String, serde_json::Value> = serde_json::from_str(
+            message
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing message"))?,
+        )?;
+        let formats: Vec<AttachmentFormatDescriptor> = match message.get("formats") {
+            Some(formats) => serde_json::from_value(formats.clone())?,
+            None => return Err(err_msg!("Message does not define attachment formats")),
+        };
+        let mut attachments: Vec<Attachment> = vec![];
+        for (key, value) in message.iter() {
+            if key.ends_with("~attach") {
+                attachments.extend(serde_json::from_value::<Vec<Attachment>>(value.clone())?);
+            }
+        }
+        let attachment = find_attachment(&formats, &attachments, format)
+            .ok_or_else(|| err_msg!("No attachment found for format: {}", format))?;
+        let handle = decode_attachment_object(attachment, format)?;
+        unsafe { *handle_p = handle };
+        Ok(())
+    })
+}
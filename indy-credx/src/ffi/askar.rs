@@ -0,0 +1,119 @@
+use std::os::raw::c_char;
+use std::ptr;
+
+use ffi_support::{ByteBuffer, FfiStr};
+
+use super::alloc::alloc_string;
+use super::error::{catch_error, ErrorCode};
+use super::object::{AnyIndyObject, IndyObject, ObjectHandle};
+use crate::error::Result;
+use crate::services::askar::{StorageEntry, StorageObject};
+use crate::services::types::{
+    Credential, CredentialDefinition, CredentialDefinitionPrivate, CredentialKeyCorrectnessProof,
+    MasterSecret, RevocationRegistry, RevocationRegistryDefinition,
+    RevocationRegistryDefinitionPrivate, Schema,
+};
+
+macro_rules! dispatch_storage_type {
+    ($name:expr, $func:ident, $($args:expr),*) => {
+        match $name {
+            Schema::CATEGORY => $func::<Schema>($($args),*),
+            CredentialDefinition::CATEGORY => $func::<CredentialDefinition>($($args),*),
+            CredentialDefinitionPrivate::CATEGORY => {
+                $func::<CredentialDefinitionPrivate>($($args),*)
+            }
+            CredentialKeyCorrectnessProof::CATEGORY => {
+                $func::<CredentialKeyCorrectnessProof>($($args),*)
+            }
+            RevocationRegistryDefinition::CATEGORY => {
+                $func::<RevocationRegistryDefinition>($($args),*)
+            }
+            RevocationRegistryDefinitionPrivate::CATEGORY => {
+                $func::<RevocationRegistryDefinitionPrivate>($($args),*)
+            }
+            RevocationRegistry::CATEGORY => $func::<RevocationRegistry>($($args),*),
+            Credential::CATEGORY => $func::<Credential>($($args),*),
+            MasterSecret::CATEGORY => $func::<MasterSecret>($($args),*),
+            other => Err(err_msg!("Unsupported entry category: {}", other)),
+        }
+    };
+}
+
+fn category_of(obj: &IndyObject) -> Result<&'static str> {
+    Ok(match obj.type_name() {
+        "Schema" => Schema::CATEGORY,
+        "CredentialDefinition" => CredentialDefinition::CATEGORY,
+        "CredentialDefinitionPrivate" => CredentialDefinitionPrivate::CATEGORY,
+        "KeyCorrectnessProof" => CredentialKeyCorrectnessProof::CATEGORY,
+        "RevocationRegistryDefinition" => RevocationRegistryDefinition::CATEGORY,
+        "RevocationRegistryDefinitionPrivate" => RevocationRegistryDefinitionPrivate::CATEGORY,
+        "RevocationRegistry" => RevocationRegistry::CATEGORY,
+        "Credential" => Credential::CATEGORY,
+        "MasterSecret" => MasterSecret::CATEGORY,
+        other => return Err(err_msg!("Object type cannot be stored: {}", other)),
+    })
+}
+
+fn entry_info<T: StorageObject + AnyIndyObject + 'static>(
+    obj: &IndyObject,
+) -> Result<(Option<String>, std::collections::BTreeMap<String, String>)> {
+    let obj = obj.cast_ref::<T>()?;
+    Ok((obj.entry_name(), obj.entry_tags()?))
+}
+
+fn load_entry<T: StorageObject + AnyIndyObject + 'static>(
+    entry: &StorageEntry,
+) -> Result<ObjectHandle> {
+    ObjectHandle::create(T::from_entry(entry)?)
+}
+
+/// Get the Askar category, default entry name and tags for an object.
+/// The entry value is the JSON of the object. The name is null for objects
+/// without a ledger identifier.
+#[no_mangle]
+pub extern "C" fn credx_object_get_entry(
+    handle: ObjectHandle,
+    category_p: *mut *const c_char,
+    name_p: *mut *const c_char,
+    tags_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(category_p);
+        check_useful_c_ptr!(name_p);
+        check_useful_c_ptr!(tags_p);
+        let obj = handle.load()?;
+        let category = category_of(&obj)?;
+        let (name, tags) = dispatch_storage_type!(category, entry_info, &obj)?;
+        let tags = serde_json::to_string(&tags)?;
+        unsafe {
+            *category_p = alloc_string(category);
+            *name_p = name.map(alloc_string).unwrap_or(ptr::null_mut());
+            *tags_p = alloc_string(tags);
+        }
+        Ok(())
+    })
+}
+
+/// Load an object from the category and value of an Askar entry
+#[no_mangle]
+pub extern "C" fn credx_object_from_entry(
+    category: FfiStr,
+    value: ByteBuffer,
+    result_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let category = category
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing entry category"))?;
+        let entry = StorageEntry {
+            category: category.to_string(),
+            name: String::new(),
+            value: value.as_slice().to_vec(),
+            tags: Default::default(),
+        };
+        let handle = dispatch_storage_type!(category, load_entry, &entry)?;
+        unsafe { *result_p = handle };
+        Ok(())
+    })
+}
@@ -0,0 +1,101 @@
+use std::os::raw::c_char;
+
+use ffi_support::FfiStr;
+
+use super::alloc::alloc_string;
+use super::error::{catch_error, ErrorCode};
+use super::object::{AnyIndyObject, IndyObject, ObjectHandle};
+use crate::error::Result;
+use crate::services::{
+    aries::attachment::{Attachment, AttachmentFormat},
+    types::{Credential, CredentialOffer, CredentialRequest, Presentation, PresentationRequest},
+};
+
+fn encode_attachment<T: AttachmentFormat + AnyIndyObject + 'static>(
+    obj: &IndyObject,
+    attach_id: &str,
+) -> Result<(Attachment, &'static str)> {
+    Ok((
+        Attachment::encode(attach_id, obj.cast_ref::<T>()?)?,
+        T::FORMAT,
+    ))
+}
+
+fn decode_attachment<T: AttachmentFormat + AnyIndyObject + 'static>(
+    attachment: &Attachment,
+) -> Result<ObjectHandle> {
+    ObjectHandle::create(attachment.decode::<T>()?)
+}
+
+/// Decode an attachment of a registered format into a new object handle
+pub(crate) fn decode_attachment_object(
+    attachment: &Attachment,
+    format: &str,
+) -> Result<ObjectHandle> {
+    match format {
+        CredentialOffer::FORMAT => decode_attachment::<CredentialOffer>(attachment),
+        CredentialRequest::FORMAT => decode_attachment::<CredentialRequest>(attachment),
+        Credential::FORMAT => decode_attachment::<Credential>(attachment),
+        PresentationRequest::FORMAT => decode_attachment::<PresentationRequest>(attachment),
+        Presentation::FORMAT => decode_attachment::<Presentation>(attachment),
+        other => Err(err_msg!("Unsupported attachment format: {}", other)),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn credx_attachment_encode(
+    handle: ObjectHandle,
+    attach_id: FfiStr,
+    attachment_p: *mut *const c_char,
+    format_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(attachment_p);
+        check_useful_c_ptr!(format_p);
+        let attach_id = attach_id
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing attachment ID"))?;
+        let obj = handle.load()?;
+        let (attachment, format) = match obj.type_name() {
+            "CredentialOffer" => encode_attachment::<CredentialOffer>(&obj, attach_id)?,
+            "CredentialRequest" => encode_attachment::<CredentialRequest>(&obj, attach_id)?,
+            "Credential" => encode_attachment::<Credential>(&obj, attach_id)?,
+            "PresentationRequest" => encode_attachment::<PresentationRequest>(&obj, attach_id)?,
+            "Presentation" => encode_attachment::<Presentation>(&obj, attach_id)?,
+            other => {
+                return Err(err_msg!(
+                    "No attachment format registered for object type: {}",
+                    other
+                ))
+            }
+        };
+        let attachment = serde_json::to_string(&attachment)?;
+        unsafe {
+            *attachment_p = alloc_string(attachment);
+            *format_p = alloc_string(format);
+        }
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn credx_attachment_decode(
+    attachment: FfiStr,
+    format: FfiStr,
+    handle_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(handle_p);
+        let attachment: Attachment = serde_json::from_str(
+            attachment
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing attachment"))?,
+        )?;
+        let format = format
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing attachment format"))?;
+        let handle = decode_attachment_object(&attachment, format)?;
+        unsafe { *handle_p = handle };
+        Ok(())
+    })
+}
@@ -0,0 +1,59 @@
+use super::error::{catch_error, ErrorCode};
+use super::object::ObjectHandle;
+use crate::error::Result;
+use crate::services::types::CancellationToken;
+
+#[no_mangle]
+pub extern "C" fn credx_create_cancellation_token(token_p: *mut ObjectHandle) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(token_p);
+        let token = ObjectHandle::create(CancellationToken::new())?;
+        unsafe { *token_p = token };
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn credx_cancellation_token_cancel(token: ObjectHandle) -> ErrorCode {
+    catch_error(|| {
+        token.load()?.cast_ref::<CancellationToken>()?.cancel();
+        Ok(())
+    })
+}
+
+// A copy starts with the same state but is cancelled independently
+impl_indy_object!(
+    CancellationToken,
+    "CancellationToken",
+    |token| {
+        let copy = CancellationToken::new();
+        if token.is_cancelled() {
+            copy.cancel();
+        }
+        Ok(copy)
+    },
+    mutable
+);
+
+/// Load an optional cancellation token, where a zero handle indicates none
+pub(crate) fn load_cancellation_token(token: ObjectHandle) -> Result<Option<CancellationToken>> {
+    token
+        .opt_load()?
+        .map(|obj| obj.cast_ref::<CancellationToken>().cloned())
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_json_reflects_cancellation() {
+        let token = ObjectHandle::create(CancellationToken::new()).unwrap();
+        let before = token.load_json().unwrap();
+        assert_eq!(credx_cancellation_token_cancel(token), ErrorCode::Success);
+        let after = token.load_json().unwrap();
+        assert_ne!(before, after);
+        token.remove().unwrap();
+    }
+}
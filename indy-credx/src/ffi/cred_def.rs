@@ -1,12 +1,16 @@
 use std::os::raw::c_char;
 
-use ffi_support::{rust_string_to_c, FfiStr};
+#[cfg(feature = "test_seeded_keys")]
+use ffi_support::ByteBuffer;
+use ffi_support::FfiStr;
 use indy_utils::Qualifiable;
 
+use super::alloc::alloc_string;
+use super::cancel::load_cancellation_token;
 use super::error::{catch_error, ErrorCode};
 use super::object::{IndyObjectId, ObjectHandle};
 use crate::services::{
-    issuer::create_credential_definition,
+    issuer::create_credential_definition_cancellable,
     types::{
         CredentialDefinition, CredentialDefinitionConfig, CredentialDefinitionId,
         CredentialDefinitionPrivate, CredentialKeyCorrectnessProof as KeyCorrectnessProof,
@@ -24,6 +28,33 @@ pub extern "C" fn credx_create_credential_definition(
     cred_def_p: *mut ObjectHandle,
     cred_def_pvt_p: *mut ObjectHandle,
     key_proof_p: *mut ObjectHandle,
+) -> ErrorCode {
+    credx_create_credential_definition_cancellable(
+        origin_did,
+        schema,
+        tag,
+        signature_type,
+        support_revocation,
+        ObjectHandle::default(),
+        cred_def_p,
+        cred_def_pvt_p,
+        key_proof_p,
+    )
+}
+
+/// Create a credential definition, aborting if the cancellation token is
+/// cancelled. The token is checked throughout key generation.
+#[no_mangle]
+pub extern "C" fn credx_create_credential_definition_cancellable(
+    origin_did: FfiStr,
+    schema: ObjectHandle,
+    tag: FfiStr,
+    signature_type: FfiStr,
+    support_revocation: i8,
+    cancel_token: ObjectHandle,
+    cred_def_p: *mut ObjectHandle,
+    cred_def_pvt_p: *mut ObjectHandle,
+    key_proof_p: *mut ObjectHandle,
 ) -> ErrorCode {
     catch_error(|| {
         check_useful_c_ptr!(cred_def_p);
@@ -42,7 +73,8 @@ pub extern "C" fn credx_create_credential_definition(
                 .ok_or_else(|| err_msg!("Missing signature type"))?;
             SignatureType::from_str(stype).map_err(err_map!(Input))?
         };
-        let (cred_def, cred_def_pvt, key_proof) = create_credential_definition(
+        let cancel = load_cancellation_token(cancel_token)?.unwrap_or_default();
+        let (cred_def, cred_def_pvt, key_proof) = create_credential_definition_cancellable(
             &origin_did,
             schema.load()?.cast_ref()?,
             tag,
@@ -50,6 +82,7 @@ pub extern "C" fn credx_create_credential_definition(
             CredentialDefinitionConfig {
                 support_revocation: support_revocation != 0,
             },
+            &cancel,
         )?;
         let cred_def = ObjectHandle::create(cred_def)?;
         let cred_def_pvt = ObjectHandle::create(cred_def_pvt)?;
@@ -63,6 +96,58 @@ pub extern "C" fn credx_create_credential_definition(
     })
 }
 
+/// Create a credential definition without revocation support, deriving the
+/// keys from `seed`. The generated keys are insecure and must only be used in
+/// tests.
+#[cfg(feature = "test_seeded_keys")]
+#[no_mangle]
+pub extern "C" fn credx_create_credential_definition_from_seed(
+    origin_did: FfiStr,
+    schema: ObjectHandle,
+    tag: FfiStr,
+    signature_type: FfiStr,
+    seed: ByteBuffer,
+    cred_def_p: *mut ObjectHandle,
+    cred_def_pvt_p: *mut ObjectHandle,
+    key_proof_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(cred_def_p);
+        check_useful_c_ptr!(cred_def_pvt_p);
+        check_useful_c_ptr!(key_proof_p);
+        let origin_did = {
+            let did = origin_did
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing origin DID"))?;
+            DidValue::from_str(did)?
+        };
+        let tag = tag.as_opt_str().ok_or_else(|| err_msg!("Missing tag"))?;
+        let signature_type = {
+            let stype = signature_type
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing signature type"))?;
+            SignatureType::from_str(stype).map_err(err_map!(Input))?
+        };
+        let (cred_def, cred_def_pvt, key_proof) =
+            crate::services::seeded_keys::create_credential_definition_from_seed(
+                &origin_did,
+                schema.load()?.cast_ref()?,
+                tag,
+                signature_type,
+                seed.as_slice(),
+            )?;
+        let cred_def = ObjectHandle::create(cred_def)?;
+        let cred_def_pvt = ObjectHandle::create(cred_def_pvt)?;
+        let key_proof = ObjectHandle::create(key_proof)?;
+        unsafe {
+            *cred_def_p = cred_def;
+            *cred_def_pvt_p = cred_def_pvt;
+            *key_proof_p = key_proof;
+        }
+        Ok(())
+    })
+}
+
 impl_indy_object!(CredentialDefinition, "CredentialDefinition");
 impl_indy_object_from_json!(CredentialDefinition, credx_credential_definition_from_json);
 
@@ -85,17 +170,17 @@ pub extern "C" fn credx_credential_definition_get_attribute(
     catch_error(|| {
         check_useful_c_ptr!(result_p);
         let cred_def = handle.load()?;
-        let cred_def = cred_def.cast_ref::<CredentialDefinition>()?;
+        let CredentialDefinition::CredentialDefinitionV1(cred_def) =
+            cred_def.cast_ref::<CredentialDefinition>()?;
         let val = match name.as_opt_str().unwrap_or_default() {
-            "id" => cred_def.get_id().to_string(),
-            "schema_id" => match cred_def {
-                CredentialDefinition::CredentialDefinitionV1(cred_def) => {
-                    cred_def.schema_id.to_string()
-                }
-            },
+            "id" => cred_def.id.to_string(),
+            "schema_id" => cred_def.schema_id.to_string(),
+            "signature_type" => cred_def.signature_type.to_str().to_string(),
+            "tag" => cred_def.tag.clone(),
+            "supports_revocation" => cred_def.value.revocation.is_some().to_string(),
             s => return Err(err_msg!("Unsupported attribute: {}", s)),
         };
-        unsafe { *result_p = rust_string_to_c(val) };
+        unsafe { *result_p = alloc_string(val) };
         Ok(())
     })
 }
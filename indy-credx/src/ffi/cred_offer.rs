@@ -1,10 +1,16 @@
+use std::convert::TryInto;
+use std::os::raw::c_char;
+use std::ptr;
+
 use ffi_support::FfiStr;
 use indy_utils::Qualifiable;
 
+use super::alloc::alloc_string;
 use super::error::{catch_error, ErrorCode};
 use super::object::ObjectHandle;
+use crate::error::Result;
 use crate::services::{
-    issuer::create_credential_offer,
+    issuer::{create_credential_offer, create_credential_offers},
     types::{CredentialOffer, SchemaId},
 };
 
@@ -34,5 +40,67 @@ pub extern "C" fn credx_create_credential_offer(
     })
 }
 
+/// Create `count` credential offers with distinct nonces. `cred_offers_p`
+/// must point to an array with room for `count` handles.
+#[no_mangle]
+pub extern "C" fn credx_create_credential_offers(
+    schema_id: FfiStr,
+    cred_def: ObjectHandle,
+    key_proof: ObjectHandle,
+    count: i64,
+    cred_offers_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(cred_offers_p);
+        let schema_id = {
+            let sid = schema_id
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing schema ID"))?;
+            SchemaId::from_str(sid)?
+        };
+        let count = count
+            .try_into()
+            .map_err(|_| err_msg!("Invalid credential offer count"))?;
+        let handles = create_credential_offers(
+            &schema_id,
+            cred_def.load()?.cast_ref()?,
+            key_proof.load()?.cast_ref()?,
+            count,
+        )?
+        .into_iter()
+        .map(ObjectHandle::create)
+        .collect::<Result<Vec<_>>>()?;
+        let output = unsafe { std::slice::from_raw_parts_mut(cred_offers_p, handles.len()) };
+        output.copy_from_slice(&handles);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn credx_credential_offer_get_attribute(
+    handle: ObjectHandle,
+    name: FfiStr,
+    result_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let cred_offer = handle.load()?;
+        let cred_offer = cred_offer.cast_ref::<CredentialOffer>()?;
+        let val = match name.as_opt_str().unwrap_or_default() {
+            "schema_id" => alloc_string(cred_offer.schema_id.to_string()),
+            "cred_def_id" => alloc_string(cred_offer.cred_def_id.to_string()),
+            "nonce" => alloc_string(cred_offer.nonce.to_string()),
+            "method_name" => cred_offer
+                .method_name
+                .as_ref()
+                .map(|s| alloc_string(s.as_str()))
+                .unwrap_or(ptr::null_mut()),
+            s => return Err(err_msg!("Unsupported attribute: {}", s)),
+        };
+        unsafe { *result_p = val };
+        Ok(())
+    })
+}
+
 impl_indy_object!(CredentialOffer, "CredentialOffer");
 impl_indy_object_from_json!(CredentialOffer, credx_credential_offer_from_json);
@@ -1,11 +1,27 @@
+use std::os::raw::c_char;
+
+#[cfg(feature = "metadata_encryption")]
+use ffi_support::ByteBuffer;
 use ffi_support::FfiStr;
 use indy_utils::Qualifiable;
 
+use super::alloc::alloc_string;
 use super::error::{catch_error, ErrorCode};
 use super::object::ObjectHandle;
+use super::util::FfiStrList;
+#[cfg(feature = "metadata_encryption")]
+use crate::services::metadata_encryption::{
+    unwrap_request_metadata, wrap_request_metadata, WalletKey, WrappedRequestMetadata,
+};
 use crate::services::{
-    prover::create_credential_request,
-    types::{CredentialRequest, CredentialRequestMetadata, DidValue},
+    prover::{
+        create_committed_credential_request, create_credential_request,
+        rename_master_secret_in_metadata,
+    },
+    types::{
+        CredentialOffer, CredentialRequest, CredentialRequestMetadata, DidValue,
+        MakeCredentialValues,
+    },
 };
 
 #[no_mangle]
@@ -47,6 +63,175 @@ pub extern "C" fn credx_create_credential_request(
     })
 }
 
+/// Create a credential request committing to the raw values of the given
+/// attributes, which the issuer signs without learning them
+#[no_mangle]
+pub extern "C" fn credx_create_committed_credential_request(
+    prover_did: FfiStr,
+    cred_def: ObjectHandle,
+    master_secret: ObjectHandle,
+    master_secret_id: FfiStr,
+    cred_offer: ObjectHandle,
+    attr_names: FfiStrList,
+    attr_raw_values: FfiStrList,
+    cred_req_p: *mut ObjectHandle,
+    cred_req_meta_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(cred_req_p);
+        check_useful_c_ptr!(cred_req_meta_p);
+        if attr_names.len() != attr_raw_values.len() {
+            return Err(err_msg!(
+                "Mismatch between length of attribute names and raw values"
+            ));
+        }
+        let prover_did = {
+            let did = prover_did
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing prover DID"))?;
+            DidValue::from_str(did)?
+        };
+        let master_secret_id = master_secret_id
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing master secret ID"))?;
+        let cred_offer = cred_offer.load()?;
+        let cred_offer = cred_offer.cast_ref::<CredentialOffer>()?;
+        let mut committed = MakeCredentialValues::for_schema(&cred_offer.schema_id);
+        for (name, raw) in attr_names.as_slice().iter().zip(attr_raw_values.as_slice()) {
+            let name = name
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing attribute name"))?;
+            let raw = raw
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing attribute raw value"))?;
+            committed.add_raw(name, raw)?;
+        }
+        let (cred_req, cred_req_metadata) = create_committed_credential_request(
+            &prover_did,
+            cred_def.load()?.cast_ref()?,
+            master_secret.load()?.cast_ref()?,
+            master_secret_id,
+            cred_offer,
+            committed.into(),
+        )?;
+        let cred_req = ObjectHandle::create(cred_req)?;
+        let cred_req_metadata = ObjectHandle::create(cred_req_metadata)?;
+        unsafe {
+            *cred_req_p = cred_req;
+            *cred_req_meta_p = cred_req_metadata;
+        };
+        Ok(())
+    })
+}
+
+/// Rename the master secret in credential request metadata, keeping the
+/// previous name as an alias
+#[no_mangle]
+pub extern "C" fn credx_credential_request_metadata_rename_master_secret(
+    cred_req_metadata: ObjectHandle,
+    master_secret_id: FfiStr,
+    cred_req_meta_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(cred_req_meta_p);
+        let master_secret_id = master_secret_id
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing master secret ID"))?;
+        let cred_req_metadata = rename_master_secret_in_metadata(
+            cred_req_metadata.load()?.cast_ref()?,
+            master_secret_id,
+        )?;
+        let cred_req_metadata = ObjectHandle::create(cred_req_metadata)?;
+        unsafe { *cred_req_meta_p = cred_req_metadata };
+        Ok(())
+    })
+}
+
+/// Check whether credential request metadata refers to a master secret by
+/// the given name, including any previous name
+#[no_mangle]
+pub extern "C" fn credx_credential_request_metadata_has_master_secret(
+    cred_req_metadata: ObjectHandle,
+    master_secret_id: FfiStr,
+    result_p: *mut i8,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let master_secret_id = master_secret_id
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing master secret ID"))?;
+        let matched = cred_req_metadata
+            .load()?
+            .cast_ref::<CredentialRequestMetadata>()?
+            .has_master_secret_name(master_secret_id);
+        unsafe { *result_p = matched as i8 };
+        Ok(())
+    })
+}
+
+/// Encrypt credential request metadata under a 32-byte wallet key, writing
+/// the JSON form of the wrapped metadata to `wrapped_p`
+#[cfg(feature = "metadata_encryption")]
+#[no_mangle]
+pub extern "C" fn credx_credential_request_metadata_wrap(
+    cred_req_metadata: ObjectHandle,
+    key: ByteBuffer,
+    wrapped_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(wrapped_p);
+        let key = WalletKey::new(key.as_slice())?;
+        let wrapped = wrap_request_metadata(cred_req_metadata.load()?.cast_ref()?, &key)?;
+        let wrapped = serde_json::to_string(&wrapped)?;
+        unsafe { *wrapped_p = alloc_string(wrapped) };
+        Ok(())
+    })
+}
+
+/// Decrypt credential request metadata wrapped under a 32-byte wallet key
+#[cfg(feature = "metadata_encryption")]
+#[no_mangle]
+pub extern "C" fn credx_credential_request_metadata_unwrap(
+    wrapped: FfiStr,
+    key: ByteBuffer,
+    cred_req_meta_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(cred_req_meta_p);
+        let wrapped: WrappedRequestMetadata = serde_json::from_str(
+            wrapped
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing wrapped metadata"))?,
+        )
+        .map_err(err_map!("Invalid wrapped metadata"))?;
+        let key = WalletKey::new(key.as_slice())?;
+        let cred_req_metadata = ObjectHandle::create(unwrap_request_metadata(&wrapped, &key)?)?;
+        unsafe { *cred_req_meta_p = cred_req_metadata };
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn credx_credential_request_get_attribute(
+    handle: ObjectHandle,
+    name: FfiStr,
+    result_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let cred_req = handle.load()?;
+        let cred_req = cred_req.cast_ref::<CredentialRequest>()?;
+        let val = match name.as_opt_str().unwrap_or_default() {
+            "prover_did" => cred_req.prover_did.to_string(),
+            "cred_def_id" => cred_req.cred_def_id.to_string(),
+            "nonce" => cred_req.nonce.to_string(),
+            s => return Err(err_msg!("Unsupported attribute: {}", s)),
+        };
+        unsafe { *result_p = alloc_string(val) };
+        Ok(())
+    })
+}
+
 impl_indy_object!(CredentialRequest, "CredentialRequest");
 impl_indy_object_from_json!(CredentialRequest, credx_credential_request_from_json);
 
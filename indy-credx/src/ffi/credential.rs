@@ -1,19 +1,26 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::convert::TryInto;
 use std::os::raw::c_char;
 use std::ptr;
 
-use ffi_support::{rust_string_to_c, FfiStr};
+use ffi_support::FfiStr;
 
+use super::alloc::alloc_string;
 use super::error::{catch_error, ErrorCode};
 use super::object::{IndyObject, ObjectHandle};
 use super::util::{FfiList, FfiStrList};
 use crate::error::Result;
 use crate::services::{
-    issuer::create_credential,
-    prover::process_credential,
-    tails::TailsFileReader,
-    types::{Credential, CredentialRevocationConfig, MakeCredentialValues},
+    issuer::{create_credential, derive_credential_values},
+    prover::{
+        check_credential_encoding, credential_attribute, credential_tags,
+        process_and_extract_credential, process_credential,
+    },
+    tails::{TailsFileReader, TailsGeneratorReader},
+    types::{
+        Credential, CredentialDefinition, CredentialDisplay, CredentialOffer,
+        CredentialRevocationConfig, MakeCredentialValues,
+    },
     utils::encode_credential_attribute,
 };
 
@@ -34,18 +41,28 @@ struct RevocationConfig {
     registry: IndyObject,
     reg_idx: u32,
     reg_used: HashSet<u32>,
-    tails_path: String,
+    tails_path: Option<String>,
 }
 
 impl RevocationConfig {
-    pub fn as_ref_config(&self) -> Result<CredentialRevocationConfig> {
+    pub fn as_ref_config(
+        &self,
+        cred_def: &CredentialDefinition,
+    ) -> Result<CredentialRevocationConfig<'_>> {
+        let reg_def = self.reg_def.cast_ref()?;
+        let reg_def_private = self.reg_def_private.cast_ref()?;
+        // without a tails file, the tails are derived from the registry private key
+        let tails_reader = match self.tails_path.as_ref() {
+            Some(path) => TailsFileReader::new(path.as_str()),
+            None => TailsGeneratorReader::new(cred_def, reg_def, reg_def_private)?,
+        };
         Ok(CredentialRevocationConfig {
-            reg_def: self.reg_def.cast_ref()?,
-            reg_def_private: self.reg_def_private.cast_ref()?,
+            reg_def,
+            reg_def_private,
             registry: self.registry.cast_ref()?,
             registry_idx: self.reg_idx,
             registry_used: &self.reg_used,
-            tails_reader: TailsFileReader::new(self.tails_path.as_str()),
+            tails_reader,
         })
     }
 }
@@ -75,12 +92,14 @@ pub extern "C" fn credx_create_credential(
             ));
         }
         let enc_values = attr_enc_values.as_slice();
-        let mut cred_values = MakeCredentialValues::default();
-        let mut attr_idx = 0;
-        for (name, raw) in attr_names
+        let cred_offer = cred_offer.load()?;
+        let mut cred_values =
+            MakeCredentialValues::for_schema(&cred_offer.cast_ref::<CredentialOffer>()?.schema_id);
+        for (attr_idx, (name, raw)) in attr_names
             .as_slice()
-            .into_iter()
+            .iter()
             .zip(attr_raw_values.as_slice())
+            .enumerate()
         {
             let name = name
                 .as_opt_str()
@@ -100,15 +119,10 @@ pub extern "C" fn credx_create_credential(
             } else {
                 cred_values.add_raw(name, raw)?;
             }
-            attr_idx += 1;
         }
         let revocation_config = if !revocation.is_null() {
             let revocation = unsafe { &*revocation };
-            let tails_path = revocation
-                .tails_path
-                .as_opt_str()
-                .ok_or_else(|| err_msg!("Missing tails file path"))?
-                .to_string();
+            let tails_path = revocation.tails_path.as_opt_str().map(str::to_string);
             let mut reg_used = HashSet::new();
             for reg_idx in revocation.reg_used.as_slice() {
                 reg_used.insert(
@@ -131,15 +145,17 @@ pub extern "C" fn credx_create_credential(
         } else {
             None
         };
+        let cred_def = cred_def.load()?;
+        let cred_def = cred_def.cast_ref()?;
         let (cred, rev_reg, rev_delta) = create_credential(
-            cred_def.load()?.cast_ref()?,
+            cred_def,
             cred_def_private.load()?.cast_ref()?,
-            cred_offer.load()?.cast_ref()?,
+            cred_offer.cast_ref()?,
             cred_request.load()?.cast_ref()?,
             cred_values.into(),
             revocation_config
                 .as_ref()
-                .map(RevocationConfig::as_ref_config)
+                .map(|config| config.as_ref_config(cred_def))
                 .transpose()?,
         )?;
         let cred = ObjectHandle::create(cred)?;
@@ -178,7 +194,7 @@ pub extern "C" fn credx_encode_credential_attributes(
             }
             result.push_str(enc_val.as_str());
         }
-        unsafe { *result_p = rust_string_to_c(result) };
+        unsafe { *result_p = alloc_string(result) };
         Ok(())
     })
 }
@@ -216,6 +232,135 @@ pub extern "C" fn credx_process_credential(
     })
 }
 
+/// Process a received credential, checking it against the credential offer,
+/// and return the processed credential along with a JSON object mapping
+/// attribute names to raw values.
+#[no_mangle]
+pub extern "C" fn credx_process_and_extract_credential(
+    cred: ObjectHandle,
+    cred_offer: ObjectHandle,
+    cred_req_metadata: ObjectHandle,
+    master_secret: ObjectHandle,
+    cred_def: ObjectHandle,
+    rev_reg_def: ObjectHandle,
+    cred_p: *mut ObjectHandle,
+    attributes_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(cred_p);
+        check_useful_c_ptr!(attributes_p);
+        let (cred, attributes) = process_and_extract_credential(
+            cred.load()?.cast_ref()?,
+            cred_offer.load()?.cast_ref()?,
+            cred_req_metadata.load()?.cast_ref()?,
+            master_secret.load()?.cast_ref()?,
+            cred_def.load()?.cast_ref()?,
+            rev_reg_def
+                .opt_load()?
+                .as_ref()
+                .map(IndyObject::cast_ref)
+                .transpose()?,
+        )?;
+        let attributes = serde_json::to_string(&attributes)?;
+        let cred = ObjectHandle::create(cred)?;
+        unsafe {
+            *cred_p = cred;
+            *attributes_p = alloc_string(attributes);
+        };
+        Ok(())
+    })
+}
+
+/// Extract the values of a subset of a credential's attributes for issuing
+/// a derived credential, as a JSON object mapping each attribute name to its
+/// raw and encoded values.
+#[no_mangle]
+pub extern "C" fn credx_credential_derive_values(
+    cred: ObjectHandle,
+    attr_names: FfiStrList,
+    result_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let cred = cred.load()?;
+        let cred = cred.cast_ref::<Credential>()?;
+        let names = attr_names
+            .as_slice()
+            .iter()
+            .map(|name| {
+                name.as_opt_str()
+                    .ok_or_else(|| err_msg!("Missing attribute name"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let values = derive_credential_values(&cred.values, names)?;
+        let values = serde_json::to_string(&values)?;
+        unsafe { *result_p = alloc_string(values) };
+        Ok(())
+    })
+}
+
+/// Attach display hints to a copy of a credential, given as a JSON object
+/// mapping attribute names to hints. The hints are not covered by the
+/// signature. An empty string removes any existing hints.
+#[no_mangle]
+pub extern "C" fn credx_credential_set_display(
+    cred: ObjectHandle,
+    display_json: FfiStr,
+    cred_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(cred_p);
+        let display = match display_json.as_opt_str().filter(|s| !s.is_empty()) {
+            Some(json) => Some(
+                serde_json::from_str::<CredentialDisplay>(json)
+                    .map_err(err_map!("Invalid display hints"))?,
+            ),
+            None => None,
+        };
+        let mut cred = cred
+            .load()?
+            .cast_ref::<Credential>()?
+            .try_clone()
+            .map_err(err_map!(Unexpected, "Error copying credential"))?;
+        cred.set_display(display)?;
+        let cred = ObjectHandle::create(cred)?;
+        unsafe { *cred_p = cred };
+        Ok(())
+    })
+}
+
+/// Build the wallet search tags of a credential as a JSON object of tag
+/// names and values
+#[no_mangle]
+pub extern "C" fn credx_credential_tags(
+    cred: ObjectHandle,
+    tags_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(tags_p);
+        let cred = cred.load()?;
+        let tags = credential_tags(cred.cast_ref::<Credential>()?)?;
+        unsafe { *tags_p = alloc_string(serde_json::to_string(&tags)?) };
+        Ok(())
+    })
+}
+
+/// Recompute the encodings of the raw values of a credential, returning a
+/// JSON array of the names of attributes with mismatched encoded values
+#[no_mangle]
+pub extern "C" fn credx_check_credential_encoding(
+    cred: ObjectHandle,
+    mismatched_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(mismatched_p);
+        let cred = cred.load()?;
+        let mismatched = check_credential_encoding(cred.cast_ref::<Credential>()?)?;
+        unsafe { *mismatched_p = alloc_string(serde_json::to_string(&mismatched)?) };
+        Ok(())
+    })
+}
+
 impl_indy_object!(Credential, "Credential");
 impl_indy_object_from_json!(Credential, credx_credential_from_json);
 
@@ -230,17 +375,42 @@ pub extern "C" fn credx_credential_get_attribute(
         let cred = handle.load()?;
         let cred = cred.cast_ref::<Credential>()?;
         let val = match name.as_opt_str().unwrap_or_default() {
-            "schema_id" => rust_string_to_c(cred.schema_id.to_string()),
-            "cred_def_id" => rust_string_to_c(cred.cred_def_id.to_string()),
+            "schema_id" => alloc_string(cred.schema_id.to_string()),
+            "cred_def_id" => alloc_string(cred.cred_def_id.to_string()),
             "rev_reg_id" => cred
                 .rev_reg_id
                 .as_ref()
-                .map(|s| rust_string_to_c(s.to_string()))
+                .map(|s| alloc_string(s.to_string()))
                 .unwrap_or(ptr::null_mut()),
-            "rev_reg_index" => cred
+            "rev_reg_index" | "cred_rev_id" => cred
                 .signature
                 .extract_index()
-                .map(|s| rust_string_to_c(s.to_string()))
+                .map(|s| alloc_string(s.to_string()))
+                .unwrap_or(ptr::null_mut()),
+            "values" => {
+                let values: BTreeMap<&String, &String> = cred
+                    .values
+                    .0
+                    .iter()
+                    .map(|(name, value)| (name, &value.raw))
+                    .collect();
+                alloc_string(serde_json::to_string(&values)?)
+            }
+            "encoded_values" => {
+                let values: BTreeMap<&String, &String> = cred
+                    .values
+                    .0
+                    .iter()
+                    .map(|(name, value)| (name, &value.encoded))
+                    .collect();
+                alloc_string(serde_json::to_string(&values)?)
+            }
+            "display" => cred
+                .display
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?
+                .map(alloc_string)
                 .unwrap_or(ptr::null_mut()),
             s => return Err(err_msg!("Unsupported attribute: {}", s)),
         };
@@ -248,3 +418,46 @@ pub extern "C" fn credx_credential_get_attribute(
         Ok(())
     })
 }
+
+/// List the names of the attributes of a credential as a sorted JSON array
+#[no_mangle]
+pub extern "C" fn credx_credential_get_attribute_names(
+    handle: ObjectHandle,
+    names_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(names_p);
+        let cred = handle.load()?;
+        let cred = cred.cast_ref::<Credential>()?;
+        let mut names: Vec<&String> = cred.values.0.keys().collect();
+        names.sort();
+        unsafe { *names_p = alloc_string(serde_json::to_string(&names)?) };
+        Ok(())
+    })
+}
+
+/// Read the value of a credential attribute, with the name normalized as in
+/// presentation requests. The encoded value is only returned when
+/// `encoded_p` is not null.
+#[no_mangle]
+pub extern "C" fn credx_credential_get_attribute_value(
+    handle: ObjectHandle,
+    name: FfiStr,
+    raw_p: *mut *const c_char,
+    encoded_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(raw_p);
+        let name = name
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing attribute name"))?;
+        let cred = handle.load()?;
+        let values = credential_attribute(cred.cast_ref::<Credential>()?, name)
+            .ok_or_else(|| err_msg!("Credential attribute not found: {}", name))?;
+        unsafe { *raw_p = alloc_string(values.raw.as_str()) };
+        if !encoded_p.is_null() {
+            unsafe { *encoded_p = alloc_string(values.encoded.as_str()) };
+        }
+        Ok(())
+    })
+}
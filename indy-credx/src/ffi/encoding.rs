@@ -0,0 +1,124 @@
+use std::os::raw::c_char;
+
+use ffi_support::{ByteBuffer, FfiStr};
+use indy_utils::{base58, base64, multibase};
+
+use super::alloc::{alloc_buffer, alloc_string};
+use super::error::{catch_error, ErrorCode};
+use crate::error::Result;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Encoding {
+    Base58,
+    Base64,
+    Base64Url,
+}
+
+impl Encoding {
+    fn parse(encoding: FfiStr) -> Result<Self> {
+        match encoding.as_opt_str() {
+            Some("base58") => Ok(Self::Base58),
+            Some("base64") => Ok(Self::Base64),
+            Some("base64url") => Ok(Self::Base64Url),
+            Some(other) => Err(err_msg!("Unsupported encoding: {}", other)),
+            None => Err(err_msg!("Missing encoding")),
+        }
+    }
+}
+
+/// Encode binary data as `base58`, `base64` or `base64url` (padded)
+#[no_mangle]
+pub extern "C" fn credx_encode_data(
+    data: ByteBuffer,
+    encoding: FfiStr,
+    result_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let data = data.as_slice();
+        let encoded = match Encoding::parse(encoding)? {
+            Encoding::Base58 => base58::encode(data),
+            Encoding::Base64 => base64::encode(data),
+            Encoding::Base64Url => base64::encode_urlsafe(data),
+        };
+        unsafe { *result_p = alloc_string(encoded) };
+        Ok(())
+    })
+}
+
+/// Decode `base58`, `base64` or `base64url` data, returning an error when
+/// the value is not valid in the encoding
+#[no_mangle]
+pub extern "C" fn credx_decode_data(
+    value: FfiStr,
+    encoding: FfiStr,
+    result_p: *mut ByteBuffer,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let value = value
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing value to decode"))?;
+        let decoded = match Encoding::parse(encoding)? {
+            Encoding::Base58 => base58::decode(value),
+            Encoding::Base64 => base64::decode(value),
+            Encoding::Base64Url => base64::decode_urlsafe(value),
+        }
+        .map_err(err_map!("Invalid encoded data"))?;
+        unsafe { *result_p = alloc_buffer(decoded) };
+        Ok(())
+    })
+}
+
+/// Encode binary data with a multibase prefix. The base is given by its
+/// prefix character: `z` (base58btc), `m`, `M`, `u` or `U` (base64 variants).
+#[no_mangle]
+pub extern "C" fn credx_multibase_encode(
+    data: ByteBuffer,
+    base: FfiStr,
+    result_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let base = match base.as_opt_str() {
+            Some(code) => {
+                let mut chars = code.chars();
+                match (
+                    chars.next().and_then(multibase::Base::from_code),
+                    chars.next(),
+                ) {
+                    (Some(base), None) => base,
+                    _ => return Err(err_msg!("Unsupported multibase encoding: {}", code)),
+                }
+            }
+            None => multibase::Base::Base58Btc,
+        };
+        let encoded = multibase::encode(base, data.as_slice());
+        unsafe { *result_p = alloc_string(encoded) };
+        Ok(())
+    })
+}
+
+/// Decode multibase data, returning the prefix character of its base in
+/// `base_p`
+#[no_mangle]
+pub extern "C" fn credx_multibase_decode(
+    value: FfiStr,
+    base_p: *mut *const c_char,
+    result_p: *mut ByteBuffer,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(base_p);
+        check_useful_c_ptr!(result_p);
+        let value = value
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing value to decode"))?;
+        let (base, decoded) =
+            multibase::decode(value).map_err(err_map!("Invalid multibase data"))?;
+        unsafe {
+            *base_p = alloc_string(base.code().to_string());
+            *result_p = alloc_buffer(decoded);
+        }
+        Ok(())
+    })
+}
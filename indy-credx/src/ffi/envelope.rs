@@ -0,0 +1,44 @@
+use std::os::raw::c_char;
+
+use ffi_support::{ByteBuffer, FfiStr};
+
+use super::alloc::{alloc_buffer, alloc_string};
+use super::error::{catch_error, ErrorCode};
+use super::object::{ObjectHandle, ToJson};
+use crate::services::envelope::{decrypt, encrypt, JweEnvelope};
+
+#[no_mangle]
+pub extern "C" fn credx_envelope_seal(
+    handle: ObjectHandle,
+    recipient_key: ByteBuffer,
+    kid: FfiStr,
+    envelope_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(envelope_p);
+        let payload = handle.load()?.to_json()?;
+        let envelope = encrypt(&payload, recipient_key.as_slice(), kid.as_opt_str())?;
+        let envelope = serde_json::to_string(&envelope)?;
+        unsafe { *envelope_p = alloc_string(envelope) };
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn credx_envelope_open(
+    envelope: FfiStr,
+    recipient_secret: ByteBuffer,
+    result_p: *mut ByteBuffer,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let envelope: JweEnvelope = serde_json::from_str(
+            envelope
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing envelope"))?,
+        )?;
+        let payload = decrypt(&envelope, recipient_secret.as_slice())?;
+        unsafe { *result_p = alloc_buffer(payload) };
+        Ok(())
+    })
+}
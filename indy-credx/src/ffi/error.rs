@@ -4,10 +4,10 @@ use std::os::raw::c_char;
 use std::panic::{catch_unwind, UnwindSafe};
 use std::sync::RwLock;
 
-use ffi_support::rust_string_to_c;
-
 use once_cell::sync::Lazy;
 
+use super::alloc::alloc_string;
+
 static LAST_ERROR: Lazy<RwLock<Option<Error>>> = Lazy::new(|| RwLock::new(None));
 
 #[derive(Debug, PartialEq, Copy, Clone, Serialize)]
@@ -22,6 +22,9 @@ pub enum ErrorCode {
     InvalidUserRevocId = 6,
     ProofRejected = 7,
     RevocationRegistryFull = 8,
+    Cancelled = 9,
+    HandleEvicted = 10,
+    LimitExceeded = 11,
 }
 
 impl From<ErrorKind> for ErrorCode {
@@ -35,6 +38,9 @@ impl From<ErrorKind> for ErrorCode {
             ErrorKind::InvalidUserRevocId => ErrorCode::InvalidUserRevocId,
             ErrorKind::ProofRejected => ErrorCode::ProofRejected,
             ErrorKind::RevocationRegistryFull => ErrorCode::RevocationRegistryFull,
+            ErrorKind::Cancelled => ErrorCode::Cancelled,
+            ErrorKind::HandleEvicted => ErrorCode::HandleEvicted,
+            ErrorKind::LimitExceeded => ErrorCode::LimitExceeded,
         }
     }
 }
@@ -52,7 +58,7 @@ impl<T> From<Result<T>> for ErrorCode {
 pub extern "C" fn credx_get_current_error(error_json_p: *mut *const c_char) -> ErrorCode {
     trace!("credx_get_current_error");
 
-    let error = rust_string_to_c(get_current_error_json());
+    let error = alloc_string(get_current_error_json());
     unsafe { *error_json_p = error };
 
     ErrorCode::Success
@@ -70,6 +76,7 @@ where
         }
         Err(_) => {
             // panic error
+            super::fatal::report_caught_panic();
             let err = err_msg!(Unexpected, "Panic during execution");
             set_last_error(Some(err))
         }
@@ -0,0 +1,145 @@
+//! Reporting of panics caught at the FFI boundary.
+//!
+//! When a fatal handler is registered, a panic hook records the message,
+//! location and backtrace of each panic on the panicking thread. If the panic
+//! is then caught by `catch_error`, the record is delivered to the handler as
+//! a JSON crash report, before the call returns an `Unexpected` error.
+//! Panics on worker threads are resumed on the calling thread along with
+//! their records, so they are reported in the same way.
+
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::sync::{Once, RwLock};
+
+use once_cell::sync::Lazy;
+
+use super::error::{catch_error, ErrorCode};
+
+/// The maximum number of backtrace lines included in a crash report
+const MAX_BACKTRACE_LINES: usize = 64;
+
+/// A callback receiving the JSON crash report of a caught panic. The report
+/// is only valid for the duration of the call.
+pub type FatalHandler = extern "C" fn(report_json: *const c_char);
+
+static FATAL_HANDLER: Lazy<RwLock<Option<FatalHandler>>> = Lazy::new(|| RwLock::new(None));
+static INSTALL_HOOK: Once = Once::new();
+
+thread_local! {
+    static LAST_PANIC: RefCell<Option<CrashReport>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Serialize)]
+struct CrashReport {
+    /// The name of the FFI function in which the panic occurred, when it
+    /// could be read from the backtrace
+    operation: Option<String>,
+    message: String,
+    location: Option<String>,
+    backtrace: Vec<String>,
+    backtrace_truncated: bool,
+}
+
+/// Register a handler to receive a crash report for each panic caught at the
+/// FFI boundary, or remove the handler when `callback` is null
+#[no_mangle]
+pub extern "C" fn credx_set_fatal_handler(callback: Option<FatalHandler>) -> ErrorCode {
+    catch_error(|| {
+        if callback.is_some() {
+            INSTALL_HOOK.call_once(install_hook);
+        }
+        *FATAL_HANDLER
+            .write()
+            .map_err(|_| err_msg!(Unexpected, "Error updating fatal handler"))? = callback;
+        debug!("Set fatal handler: {}", callback.is_some());
+        Ok(())
+    })
+}
+
+fn install_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if handler().is_some() {
+            let message = if let Some(message) = info.payload().downcast_ref::<&str>() {
+                message.to_string()
+            } else if let Some(message) = info.payload().downcast_ref::<String>() {
+                message.clone()
+            } else {
+                "Unknown panic".to_string()
+            };
+            let location = info.location().map(|loc| loc.to_string());
+            let report = build_report(message, location);
+            LAST_PANIC.with(|last| last.replace(Some(report)));
+        }
+        previous(info);
+    }));
+}
+
+fn handler() -> Option<FatalHandler> {
+    FATAL_HANDLER.read().ok().and_then(|handler| *handler)
+}
+
+fn build_report(message: String, location: Option<String>) -> CrashReport {
+    let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+    let operation = backtrace.lines().find_map(|line| {
+        line.trim()
+            .split("::")
+            .find(|segment| segment.starts_with("credx_"))
+            .map(str::to_string)
+    });
+    // skip the frames of the panic hook and the panic machinery, which
+    // precede the frames of the library
+    let lines: Vec<&str> = backtrace.lines().collect();
+    let limit = lines
+        .iter()
+        .position(|line| line.contains("::credx_"))
+        .unwrap_or(lines.len());
+    let start = lines[..limit]
+        .iter()
+        .rposition(|line| line.contains("panicking::"))
+        .map(|pos| pos + 1)
+        .unwrap_or(0);
+    let mut lines = lines[start..]
+        .iter()
+        .skip_while(|line| line.trim_start().starts_with("at "))
+        .map(|line| line.to_string());
+    let backtrace: Vec<String> = lines.by_ref().take(MAX_BACKTRACE_LINES).collect();
+    CrashReport {
+        operation,
+        message,
+        location,
+        backtrace,
+        backtrace_truncated: lines.next().is_some(),
+    }
+}
+
+/// The record of a panic, which may be moved to the thread on which the
+/// panic is resumed
+pub(crate) struct PanicReport(CrashReport);
+
+/// Take the record of the last panic on this thread
+pub(crate) fn take_panic_report() -> Option<PanicReport> {
+    LAST_PANIC
+        .with(|last| last.borrow_mut().take())
+        .map(PanicReport)
+}
+
+/// Record a panic on this thread, before resuming a panic caught on a worker
+/// thread
+pub(crate) fn restore_panic_report(report: PanicReport) {
+    LAST_PANIC.with(|last| last.replace(Some(report.0)));
+}
+
+/// Deliver the report of a panic caught on this thread to the fatal handler
+pub(crate) fn report_caught_panic() {
+    let report = LAST_PANIC.with(|last| last.borrow_mut().take());
+    if let (Some(handler), Some(report)) = (handler(), report) {
+        let report = serde_json::to_string(&report)
+            .ok()
+            .and_then(|json| CString::new(json).ok());
+        if let Some(report) = report {
+            handler(report.as_ptr());
+        }
+    }
+}
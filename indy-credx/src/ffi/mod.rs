@@ -1,19 +1,10 @@
+use std::convert::TryInto;
 use std::os::raw::c_char;
 
-use ffi_support::{rust_string_to_c, ByteBuffer};
-use zeroize::Zeroize;
+use ffi_support::FfiStr;
 
 pub static LIB_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-ffi_support::define_string_destructor!(credx_string_free);
-
-#[no_mangle]
-pub extern "C" fn credx_buffer_free(buffer: ByteBuffer) {
-    ffi_support::abort_on_panic::with_abort_on_panic(|| {
-        drop(buffer.destroy_into_vec().zeroize());
-    })
-}
-
 #[macro_use]
 mod macros;
 
@@ -25,15 +16,37 @@ mod object;
 
 mod util;
 
+mod alloc;
+use self::alloc::alloc_string;
+
+mod aries;
+#[cfg(feature = "askar")]
+mod askar;
+mod attachment;
+mod cancel;
 mod cred_def;
 mod cred_offer;
 mod cred_req;
 mod credential;
+mod encoding;
+#[cfg(feature = "jwe")]
+mod envelope;
+pub(crate) mod fatal;
 mod master_secret;
+#[cfg(feature = "msgpack")]
+mod msgpack;
 mod pres_req;
 mod presentation;
+#[cfg(feature = "proto")]
+mod proto;
 mod revocation;
 mod schema;
+mod search;
+#[cfg(feature = "stats")]
+mod stats;
+mod tails;
+mod template;
+mod validation;
 
 #[no_mangle]
 pub extern "C" fn credx_set_default_logger() -> ErrorCode {
@@ -44,7 +57,150 @@ pub extern "C" fn credx_set_default_logger() -> ErrorCode {
     })
 }
 
+#[no_mangle]
+pub extern "C" fn credx_set_thread_pool_size(size: i64) -> ErrorCode {
+    catch_error(|| {
+        let size = match size {
+            0 => None,
+            s => Some(
+                s.try_into()
+                    .map_err(|_| err_msg!("Invalid thread pool size"))?,
+            ),
+        };
+        crate::config::update_config(|config| config.thread_pool_size(size))?;
+        debug!("Set thread pool size: {:?}", size);
+        Ok(())
+    })
+}
+
+/// Replace the clock used by the library. A positive `fixed_time` freezes
+/// the clock at that Unix timestamp, otherwise the system clock is used.
+/// Either is adjusted by `offset_secs`, which may be negative.
+#[no_mangle]
+pub extern "C" fn credx_set_clock(fixed_time: i64, offset_secs: i64) -> ErrorCode {
+    catch_error(|| {
+        use crate::config::{FixedClock, OffsetClock, SharedClock};
+        let clock = match (fixed_time, offset_secs) {
+            (t, _) if t < 0 => return Err(err_msg!("Invalid fixed time")),
+            (0, 0) => None,
+            (0, offset) => Some(SharedClock::new(OffsetClock(offset))),
+            (t, offset) => {
+                let time = t
+                    .checked_add(offset)
+                    .filter(|time| *time >= 0)
+                    .ok_or_else(|| err_msg!("Invalid clock offset"))?;
+                Some(SharedClock::new(FixedClock(time as u64)))
+            }
+        };
+        debug!("Set clock: {:?}", clock);
+        crate::config::update_config(|config| config.clock(clock))
+    })
+}
+
+/// Select the field naming convention of serialized objects: `native`,
+/// `snake_case` or `camel_case`. A null profile restores the native names.
+/// Objects accept either convention when loaded from JSON.
+#[no_mangle]
+pub extern "C" fn credx_set_serialization_profile(profile: FfiStr) -> ErrorCode {
+    catch_error(|| {
+        let profile = match profile.as_opt_str() {
+            Some(profile) => profile
+                .parse()
+                .map_err(err_map!("Invalid serialization profile"))?,
+            None => Default::default(),
+        };
+        debug!("Set serialization profile: {}", profile);
+        crate::config::update_config(|config| config.serialization_profile(profile))
+    })
+}
+
+/// Limit the size of presentations accepted for verification. Each limit is
+/// disabled when zero. Presentations exceeding a limit are rejected with the
+/// `LimitExceeded` error when they are loaded or verified.
+#[no_mangle]
+pub extern "C" fn credx_set_verification_limits(
+    max_referents: i64,
+    max_credentials: i64,
+    max_json_size: i64,
+) -> ErrorCode {
+    catch_error(|| {
+        let limit = |value: i64| -> crate::error::Result<Option<usize>> {
+            match value {
+                0 => Ok(None),
+                v => Ok(Some(
+                    v.try_into()
+                        .map_err(|_| err_msg!("Invalid verification limit"))?,
+                )),
+            }
+        };
+        let limits = crate::config::VerificationLimits::new()
+            .max_referents(limit(max_referents)?)
+            .max_credentials(limit(max_credentials)?)
+            .max_json_size(limit(max_json_size)?);
+        debug!("Set verification limits: {:?}", limits);
+        crate::config::update_config(|config| config.verification_limits(limits))
+    })
+}
+
+/// Register an encoder for the raw values of the attributes matching a
+/// `LIKE` pattern, either `standard` or `dateint`. The rule applies under
+/// the given schema, or under any schema when the schema ID is null. Rules
+/// for a schema take precedence, otherwise the earliest registered applies.
+#[no_mangle]
+pub extern "C" fn credx_register_attribute_encoder(
+    schema_id: FfiStr,
+    pattern: FfiStr,
+    encoder: FfiStr,
+) -> ErrorCode {
+    catch_error(|| {
+        use crate::encoders::{DateIntEncoder, SharedAttributeEncoder, StandardEncoder};
+        let pattern = pattern
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing attribute name pattern"))?;
+        let encoder = match encoder.as_opt_str() {
+            Some("standard") => SharedAttributeEncoder::new(StandardEncoder),
+            Some("dateint") => SharedAttributeEncoder::new(DateIntEncoder),
+            Some(other) => return Err(err_msg!("Unsupported attribute encoder: {}", other)),
+            None => return Err(err_msg!("Missing attribute encoder")),
+        };
+        let schema_id = schema_id
+            .as_opt_str()
+            .map(|id| crate::types::SchemaId::from(id.to_string()));
+        debug!(
+            "Register attribute encoder: {:?} {} {:?}",
+            schema_id, pattern, encoder
+        );
+        crate::config::update_config(|config| {
+            let encoders = config
+                .attribute_encoders
+                .clone()
+                .register(schema_id, pattern, encoder);
+            config.attribute_encoders(encoders)
+        })
+    })
+}
+
+/// Remove every registered attribute encoder, restoring the standard
+/// encoding of raw attribute values
+#[no_mangle]
+pub extern "C" fn credx_clear_attribute_encoders() -> ErrorCode {
+    catch_error(|| {
+        crate::config::update_config(|config| config.attribute_encoders(Default::default()))
+    })
+}
+
+/// Fetch the current Unix timestamp according to the library clock
+#[no_mangle]
+pub extern "C" fn credx_get_current_time(time_p: *mut i64) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(time_p);
+        let now = crate::config::get_config().now();
+        unsafe { *time_p = now as i64 };
+        Ok(())
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn credx_version() -> *mut c_char {
-    rust_string_to_c(LIB_VERSION.to_owned())
+    alloc_string(LIB_VERSION)
 }
@@ -0,0 +1,76 @@
+use ffi_support::{ByteBuffer, FfiStr};
+use serde::de::DeserializeOwned;
+
+use super::alloc::alloc_buffer;
+use super::error::{catch_error, ErrorCode};
+use super::object::{AnyIndyObject, ObjectHandle, ToJson};
+use crate::error::Result;
+use crate::services::msgpack::{decode_value, encode_value};
+use crate::services::types::{
+    Credential, CredentialDefinition, CredentialDefinitionPrivate, CredentialKeyCorrectnessProof,
+    CredentialOffer, CredentialRequest, CredentialRequestMetadata, CredentialRevocationState,
+    MasterSecret, Presentation, PresentationRequest, RevocationRegistry,
+    RevocationRegistryDefinition, RevocationRegistryDefinitionPrivate, RevocationRegistryDelta,
+    Schema,
+};
+
+fn load_value<T: DeserializeOwned + AnyIndyObject + 'static>(
+    value: serde_json::Value,
+) -> Result<ObjectHandle> {
+    ObjectHandle::create(serde_json::from_value::<T>(value)?)
+}
+
+/// Encode an object as MessagePack, using the same field names as its JSON
+/// serialization
+#[no_mangle]
+pub extern "C" fn credx_object_get_msgpack(
+    handle: ObjectHandle,
+    result_p: *mut ByteBuffer,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let json = handle.load()?.to_json()?;
+        let value: serde_json::Value = serde_json::from_slice(&json)?;
+        unsafe { *result_p = alloc_buffer(encode_value(&value)?) };
+        Ok(())
+    })
+}
+
+/// Load an object of the named type from its MessagePack encoding
+#[no_mangle]
+pub extern "C" fn credx_object_from_msgpack(
+    type_name: FfiStr,
+    data: ByteBuffer,
+    result_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let type_name = type_name
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing object type"))?;
+        let value = decode_value(data.as_slice())?;
+        let handle = match type_name {
+            "Schema" => load_value::<Schema>(value),
+            "CredentialDefinition" => load_value::<CredentialDefinition>(value),
+            "CredentialDefinitionPrivate" => load_value::<CredentialDefinitionPrivate>(value),
+            "KeyCorrectnessProof" => load_value::<CredentialKeyCorrectnessProof>(value),
+            "RevocationRegistryDefinition" => load_value::<RevocationRegistryDefinition>(value),
+            "RevocationRegistryDefinitionPrivate" => {
+                load_value::<RevocationRegistryDefinitionPrivate>(value)
+            }
+            "RevocationRegistry" => load_value::<RevocationRegistry>(value),
+            "RevocationRegistryDelta" => load_value::<RevocationRegistryDelta>(value),
+            "CredentialRevocationState" => load_value::<CredentialRevocationState>(value),
+            "CredentialOffer" => load_value::<CredentialOffer>(value),
+            "CredentialRequest" => load_value::<CredentialRequest>(value),
+            "CredentialRequestMetadata" => load_value::<CredentialRequestMetadata>(value),
+            "Credential" => load_value::<Credential>(value),
+            "MasterSecret" => load_value::<MasterSecret>(value),
+            "PresentationRequest" => load_value::<PresentationRequest>(value),
+            "Presentation" => load_value::<Presentation>(value),
+            other => Err(err_msg!("Unsupported object type: {}", other)),
+        }?;
+        unsafe { *result_p = handle };
+        Ok(())
+    })
+}
@@ -1,67 +1,358 @@
 use std::any::TypeId;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
 use std::ops::{Deref, DerefMut};
 use std::os::raw::c_char;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use ffi_support::{rust_string_to_c, ByteBuffer};
+use ffi_support::{ByteBuffer, FfiStr};
 use once_cell::sync::Lazy;
 use serde::Serialize;
 
+use super::alloc::{alloc_buffer, alloc_string};
 use super::error::{catch_error, ErrorCode};
 use crate::error::Result;
+use crate::services::types::{SerializationProfile, WithProfile};
 
-pub(crate) static FFI_OBJECTS: Lazy<Mutex<BTreeMap<ObjectHandle, IndyObject>>> =
-    Lazy::new(|| Mutex::new(BTreeMap::new()));
+pub(crate) static FFI_OBJECTS: Lazy<Mutex<ObjectStore>> =
+    Lazy::new(|| Mutex::new(ObjectStore::default()));
 
-indy_utils::new_handle_type!(ObjectHandle, FFI_OBJECT_COUNTER);
+/// A handle to an object in the store. Handles are 64-bit in the C ABI on
+/// every platform, so that wrappers declaring them as `int64_t` are not
+/// truncated on 32-bit targets. Zero is the null handle, and negative values
+/// are never issued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct ObjectHandle(pub i64);
 
 impl ObjectHandle {
     pub(crate) fn create<O: AnyIndyObject + 'static>(value: O) -> Result<Self> {
-        let handle = Self::next();
         FFI_OBJECTS
             .lock()
             .map_err(|_| err_msg!("Error locking object store"))?
-            .insert(handle, IndyObject::new(value));
-        Ok(handle)
+            .insert(IndyObject::new(value))
     }
 
     pub(crate) fn load(&self) -> Result<IndyObject> {
+        self.check()?;
         FFI_OBJECTS
             .lock()
             .map_err(|_| err_msg!("Error locking object store"))?
-            .get(self)
-            .cloned()
-            .ok_or_else(|| err_msg!("Invalid object handle"))
+            .get(*self)
     }
 
     pub(crate) fn opt_load(&self) -> Result<Option<IndyObject>> {
         if self.0 != 0 {
-            Some(
-                FFI_OBJECTS
-                    .lock()
-                    .map_err(|_| err_msg!("Error locking object store"))?
-                    .get(self)
-                    .cloned()
-                    .ok_or_else(|| err_msg!("Invalid object handle")),
-            )
-            .transpose()
+            self.load().map(Some)
         } else {
             Ok(None)
         }
     }
 
+    /// Serialize the object, reusing the JSON produced by a previous call
+    /// with the same serialization profile. Most objects are immutable once
+    /// stored, so the cached form is only released along with the handle;
+    /// objects with interior state are serialized on every call.
+    pub(crate) fn load_json(&self) -> Result<Arc<[u8]>> {
+        let obj = self.load()?;
+        if !obj.0.cacheable() {
+            return Ok(obj.to_json()?.into());
+        }
+        let profile = crate::config::get_config().serialization_profile;
+        if let Some(json) = FFI_OBJECTS
+            .lock()
+            .map_err(|_| err_msg!("Error locking object store"))?
+            .cached_json(*self, profile)
+        {
+            return Ok(json);
+        }
+        // serialize without holding the lock on the store
+        let json: Arc<[u8]> = obj.to_json()?.into();
+        if crate::config::get_config().serialization_profile == profile {
+            FFI_OBJECTS
+                .lock()
+                .map_err(|_| err_msg!("Error locking object store"))?
+                .cache_json(*self, profile, json.clone());
+        }
+        Ok(json)
+    }
+
     pub(crate) fn remove(&self) -> Result<IndyObject> {
+        self.check()?;
         FFI_OBJECTS
             .lock()
             .map_err(|_| err_msg!("Error locking object store"))?
-            .remove(self)
+            .remove(*self)
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.0 <= 0 {
+            Err(err_msg!("Invalid object handle: {}", self.0))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl std::fmt::Display for ObjectHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ObjectHandle({})", self.0)
+    }
+}
+
+/// Limits on how long an object may remain in the handle store
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct HandleLifetime {
+    /// The maximum time since the object was created
+    pub ttl: Option<Duration>,
+    /// The maximum time since the handle was last loaded
+    pub idle: Option<Duration>,
+}
+
+impl HandleLifetime {
+    fn is_unlimited(&self) -> bool {
+        self.ttl.is_none() && self.idle.is_none()
+    }
+
+    fn is_expired(&self, entry: &StoredObject, now: Instant) -> bool {
+        self.ttl
+            .map(|ttl| now.duration_since(entry.created) >= ttl)
+            .unwrap_or(false)
+            || self
+                .idle
+                .map(|idle| now.duration_since(entry.accessed) >= idle)
+                .unwrap_or(false)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct StoredObject {
+    pub object: IndyObject,
+    created: Instant,
+    accessed: Instant,
+    /// Opaque data attached to the handle by the caller
+    tag: Option<Vec<u8>>,
+    /// The serialized object, with the profile used to serialize it
+    json: Option<(SerializationProfile, Arc<[u8]>)>,
+}
+
+/// The handles of the most recently evicted objects, so that further access
+/// reports the eviction. Older evictions are forgotten, and their handles are
+/// then reported as invalid.
+#[derive(Debug)]
+struct EvictedHandles {
+    capacity: usize,
+    order: VecDeque<ObjectHandle>,
+    handles: BTreeSet<ObjectHandle>,
+}
+
+impl EvictedHandles {
+    const CAPACITY: usize = 65536;
+
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::new(),
+            handles: BTreeSet::new(),
+        }
+    }
+
+    fn insert(&mut self, handle: ObjectHandle) {
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.handles.remove(&oldest);
+            }
+        }
+        self.order.push_back(handle);
+        self.handles.insert(handle);
+    }
+
+    fn contains(&self, handle: &ObjectHandle) -> bool {
+        self.handles.contains(handle)
+    }
+
+    /// Forget a freed handle. Its position in the eviction order is kept
+    /// until it is displaced, as handles are never reissued.
+    fn remove(&mut self, handle: &ObjectHandle) -> bool {
+        self.handles.remove(handle)
+    }
+}
+
+impl Default for EvictedHandles {
+    fn default() -> Self {
+        Self::with_capacity(Self::CAPACITY)
+    }
+}
+
+/// The objects referenced by active handles. Objects may be evicted after a
+/// configured lifetime, in which case the handles of recent evictions are
+/// remembered until freed so that further access reports the eviction.
+#[derive(Debug, Default)]
+pub(crate) struct ObjectStore {
+    /// The last handle issued, which is never reused
+    last_handle: i64,
+    objects: BTreeMap<ObjectHandle, StoredObject>,
+    default_lifetime: HandleLifetime,
+    type_lifetimes: HashMap<String, HandleLifetime>,
+    evicted: EvictedHandles,
+    last_sweep: Option<Instant>,
+}
+
+impl ObjectStore {
+    const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn objects(&self) -> impl Iterator<Item = &IndyObject> {
+        self.objects.values().map(|entry| &entry.object)
+    }
+
+    /// Set the lifetime for objects of a given type, or the default for all
+    /// types without a specific lifetime
+    pub fn set_lifetime(&mut self, type_name: Option<&str>, lifetime: HandleLifetime) {
+        match type_name {
+            Some(name) if lifetime.is_unlimited() => {
+                self.type_lifetimes.remove(name);
+            }
+            Some(name) => {
+                self.type_lifetimes.insert(name.to_string(), lifetime);
+            }
+            None => self.default_lifetime = lifetime,
+        }
+    }
+
+    fn lifetime(&self, type_name: &str) -> HandleLifetime {
+        self.type_lifetimes
+            .get(type_name)
+            .copied()
+            .unwrap_or(self.default_lifetime)
+    }
+
+    fn has_lifetimes(&self) -> bool {
+        !self.default_lifetime.is_unlimited() || !self.type_lifetimes.is_empty()
+    }
+
+    fn insert(&mut self, object: IndyObject) -> Result<ObjectHandle> {
+        let handle = self
+            .last_handle
+            .checked_add(1)
+            .map(ObjectHandle)
+            .ok_or_else(|| err_msg!(Unexpected, "Object handles exhausted"))?;
+        self.last_handle = handle.0;
+        let now = Instant::now();
+        if self.has_lifetimes()
+            && self
+                .last_sweep
+                .map(|last| now.duration_since(last) >= Self::SWEEP_INTERVAL)
+                .unwrap_or(true)
+        {
+            self.evict_expired(now);
+        }
+        self.objects.insert(
+            handle,
+            StoredObject {
+                object,
+                created: now,
+                accessed: now,
+                tag: None,
+                json: None,
+            },
+        );
+        Ok(handle)
+    }
+
+    fn get(&mut self, handle: ObjectHandle) -> Result<IndyObject> {
+        let now = Instant::now();
+        let lifetime = match self.objects.get(&handle) {
+            Some(entry) => self.lifetime(entry.object.type_name()),
+            None if self.evicted.contains(&handle) => {
+                return Err(err_msg!(HandleEvicted, "{} exceeded its lifetime", handle))
+            }
+            None => return Err(err_msg!("Invalid object handle")),
+        };
+        let entry = self.objects.get_mut(&handle).unwrap();
+        if lifetime.is_expired(entry, now) {
+            self.objects.remove(&handle);
+            self.evicted.insert(handle);
+            return Err(err_msg!(HandleEvicted, "{} exceeded its lifetime", handle));
+        }
+        entry.accessed = now;
+        Ok(entry.object.clone())
+    }
+
+    fn entry_mut(&mut self, handle: ObjectHandle) -> Result<&mut StoredObject> {
+        if self.evicted.contains(&handle) {
+            return Err(err_msg!(HandleEvicted, "{} exceeded its lifetime", handle));
+        }
+        self.objects
+            .get_mut(&handle)
             .ok_or_else(|| err_msg!("Invalid object handle"))
     }
+
+    fn set_tag(&mut self, handle: ObjectHandle, tag: Option<Vec<u8>>) -> Result<()> {
+        self.entry_mut(handle)?.tag = tag;
+        Ok(())
+    }
+
+    fn get_tag(&mut self, handle: ObjectHandle) -> Result<Option<Vec<u8>>> {
+        Ok(self.entry_mut(handle)?.tag.clone())
+    }
+
+    fn cached_json(
+        &self,
+        handle: ObjectHandle,
+        profile: SerializationProfile,
+    ) -> Option<Arc<[u8]>> {
+        match self.objects.get(&handle)?.json.as_ref() {
+            Some((cached, json)) if *cached == profile => Some(json.clone()),
+            _ => None,
+        }
+    }
+
+    fn cache_json(&mut self, handle: ObjectHandle, profile: SerializationProfile, json: Arc<[u8]>) {
+        if let Some(entry) = self.objects.get_mut(&handle) {
+            entry.json.replace((profile, json));
+        }
+    }
+
+    fn remove(&mut self, handle: ObjectHandle) -> Result<IndyObject> {
+        match self.objects.remove(&handle) {
+            Some(entry) => Ok(entry.object),
+            None if self.evicted.remove(&handle) => {
+                Err(err_msg!(HandleEvicted, "{} exceeded its lifetime", handle))
+            }
+            None => Err(err_msg!("Invalid object handle")),
+        }
+    }
+
+    /// Evict all objects which have exceeded their lifetime, returning the
+    /// number of evicted objects
+    pub fn evict_expired(&mut self, now: Instant) -> usize {
+        self.last_sweep.replace(now);
+        if !self.has_lifetimes() {
+            return 0;
+        }
+        let expired: Vec<ObjectHandle> = self
+            .objects
+            .iter()
+            .filter(|(_, entry)| {
+                self.lifetime(entry.object.type_name())
+                    .is_expired(entry, now)
+            })
+            .map(|(handle, _)| *handle)
+            .collect();
+        for handle in expired.iter() {
+            self.objects.remove(handle);
+            self.evicted.insert(*handle);
+        }
+        if !expired.is_empty() {
+            debug!("Evicted {} expired object handles", expired.len());
+        }
+        expired.len()
+    }
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for ObjectHandle {
     fn default() -> Self {
         Self(0)
@@ -125,13 +416,24 @@ where
     T: Serialize,
 {
     fn to_json(&self) -> Result<Vec<u8>> {
-        serde_json::to_vec(self).map_err(err_map!("Error serializing object"))
+        let profile = crate::config::get_config().serialization_profile;
+        serde_json::to_vec(&WithProfile::new(self, profile))
+            .map_err(err_map!("Error serializing object"))
     }
 }
 
 pub(crate) trait AnyIndyObject: Debug + ToJson + Send + Sync {
     fn type_name(&self) -> &'static str;
 
+    /// Create an independent copy of the object
+    fn deep_copy(&self) -> Result<IndyObject>;
+
+    /// Whether the serialized object may be cached on its handle, which
+    /// does not hold for objects whose state changes after creation
+    fn cacheable(&self) -> bool {
+        true
+    }
+
     #[doc(hidden)]
     fn type_id(&self) -> TypeId
     where
@@ -141,12 +443,51 @@ pub(crate) trait AnyIndyObject: Debug + ToJson + Send + Sync {
     }
 }
 
+/// Copy an object which is not `Clone` by converting it to a JSON value
+/// and back, as the CL types do not implement `Clone`
+pub(crate) fn copy_by_serde<T>(obj: &T) -> Result<T>
+where
+    T: Serialize + serde::de::DeserializeOwned,
+{
+    let value = serde_json::to_value(obj).map_err(err_map!("Error serializing object"))?;
+    serde_json::from_value(value).map_err(err_map!(Unexpected, "Error copying object"))
+}
+
+pub(crate) fn copy_by_clone<T: Clone>(obj: &T) -> Result<T> {
+    Ok(obj.clone())
+}
+
 macro_rules! impl_indy_object {
     ($ident:path, $name:expr) => {
+        impl_indy_object!($ident, $name, $crate::ffi::object::copy_by_serde);
+    };
+    ($ident:path, $name:expr, $copy:expr) => {
         impl $crate::ffi::object::AnyIndyObject for $ident {
             fn type_name(&self) -> &'static str {
                 $name
             }
+
+            fn deep_copy(&self) -> $crate::error::Result<$crate::ffi::object::IndyObject> {
+                let copy: fn(&$ident) -> $crate::error::Result<$ident> = $copy;
+                Ok($crate::ffi::object::IndyObject::new(copy(self)?))
+            }
+        }
+    };
+    // For objects with interior mutability, which are not cached as JSON
+    ($ident:path, $name:expr, $copy:expr, mutable) => {
+        impl $crate::ffi::object::AnyIndyObject for $ident {
+            fn type_name(&self) -> &'static str {
+                $name
+            }
+
+            fn deep_copy(&self) -> $crate::error::Result<$crate::ffi::object::IndyObject> {
+                let copy: fn(&$ident) -> $crate::error::Result<$ident> = $copy;
+                Ok($crate::ffi::object::IndyObject::new(copy(self)?))
+            }
+
+            fn cacheable(&self) -> bool {
+                false
+            }
         }
     };
 }
@@ -169,6 +510,9 @@ macro_rules! impl_indy_object_from_json {
     };
 }
 
+/// Serialize an object to JSON. The JSON is cached on the handle for the
+/// current serialization profile, so repeated calls return the same bytes
+/// without serializing the object again.
 #[no_mangle]
 pub extern "C" fn credx_object_get_json(
     handle: ObjectHandle,
@@ -176,9 +520,37 @@ pub extern "C" fn credx_object_get_json(
 ) -> ErrorCode {
     catch_error(|| {
         check_useful_c_ptr!(result_p);
-        let obj = handle.load()?;
-        let json = obj.to_json()?;
-        unsafe { *result_p = ByteBuffer::from_vec(json) };
+        let json = handle.load_json()?;
+        unsafe { *result_p = alloc_buffer(json.to_vec()) };
+        Ok(())
+    })
+}
+
+/// Serialize an object into a buffer provided by the caller. The length of
+/// the JSON is written to `required_len`; when `buf` is null only the length
+/// is computed, and when the buffer is too small an error is returned and the
+/// buffer contents are unspecified.
+#[no_mangle]
+pub extern "C" fn credx_object_get_json_into(
+    handle: ObjectHandle,
+    buf: *mut u8,
+    buf_len: usize,
+    required_len: *mut usize,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(required_len);
+        let json = handle.load_json()?;
+        unsafe { *required_len = json.len() };
+        if buf.is_null() {
+            return Ok(());
+        }
+        if json.len() > buf_len {
+            return Err(err_msg!(
+                "Buffer too small for serialized object: {} bytes required",
+                json.len()
+            ));
+        }
+        unsafe { std::slice::from_raw_parts_mut(buf, json.len()) }.copy_from_slice(&json);
         Ok(())
     })
 }
@@ -192,14 +564,110 @@ pub extern "C" fn credx_object_get_type_name(
         check_useful_c_ptr!(result_p);
         let obj = handle.load()?;
         let name = obj.type_name();
-        unsafe { *result_p = rust_string_to_c(name) };
+        unsafe { *result_p = alloc_string(name) };
+        Ok(())
+    })
+}
+
+/// Create a new handle referencing an independent copy of an object, which
+/// must be released separately from the original
+#[no_mangle]
+pub extern "C" fn credx_object_copy(
+    handle: ObjectHandle,
+    new_handle_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(new_handle_p);
+        let copy = handle.load()?.0.deep_copy()?;
+        let new_handle = FFI_OBJECTS
+            .lock()
+            .map_err(|_| err_msg!("Error locking object store"))?
+            .insert(copy)?;
+        unsafe { *new_handle_p = new_handle };
+        Ok(())
+    })
+}
+
+/// Attach an opaque tag to a handle, replacing any previous tag. The tag is
+/// released along with the handle, and is not shared with copies of the
+/// object. An empty tag removes the tag from the handle.
+#[no_mangle]
+pub extern "C" fn credx_object_set_tag(handle: ObjectHandle, tag: ByteBuffer) -> ErrorCode {
+    catch_error(|| {
+        handle.check()?;
+        let tag = tag.as_slice();
+        FFI_OBJECTS
+            .lock()
+            .map_err(|_| err_msg!("Error locking object store"))?
+            .set_tag(handle, Some(tag.to_vec()).filter(|tag| !tag.is_empty()))
+    })
+}
+
+/// Fetch the tag attached to a handle, or an empty buffer if there is none
+#[no_mangle]
+pub extern "C" fn credx_object_get_tag(handle: ObjectHandle, tag_p: *mut ByteBuffer) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(tag_p);
+        handle.check()?;
+        let tag = FFI_OBJECTS
+            .lock()
+            .map_err(|_| err_msg!("Error locking object store"))?
+            .get_tag(handle)?;
+        unsafe { *tag_p = alloc_buffer(tag.unwrap_or_default()) };
         Ok(())
     })
 }
 
 #[no_mangle]
 pub extern "C" fn credx_object_free(handle: ObjectHandle) {
-    handle.remove().ok();
+    super::alloc::credx_object_free_checked(handle);
+}
+
+/// Evict objects of type `type_name` from the handle store once they reach
+/// an age of `ttl_secs` or have not been accessed for `idle_secs`. A null type
+/// name sets the lifetime for all types without a specific lifetime, and zero
+/// disables either limit. The handles of the last 65536 evicted objects report
+/// the `HandleEvicted` error, and older evicted handles are reported as
+/// invalid.
+#[no_mangle]
+pub extern "C" fn credx_set_handle_lifetime(
+    type_name: FfiStr,
+    ttl_secs: i64,
+    idle_secs: i64,
+) -> ErrorCode {
+    catch_error(|| {
+        let to_duration = |secs: i64| -> Result<Option<Duration>> {
+            match secs {
+                0 => Ok(None),
+                s if s > 0 => Ok(Some(Duration::from_secs(s as u64))),
+                _ => Err(err_msg!("Handle lifetime must not be negative")),
+            }
+        };
+        let lifetime = HandleLifetime {
+            ttl: to_duration(ttl_secs)?,
+            idle: to_duration(idle_secs)?,
+        };
+        FFI_OBJECTS
+            .lock()
+            .map_err(|_| err_msg!("Error locking object store"))?
+            .set_lifetime(type_name.as_opt_str(), lifetime);
+        Ok(())
+    })
+}
+
+/// Immediately evict all objects which have exceeded their lifetime,
+/// instead of waiting for the next periodic sweep
+#[no_mangle]
+pub extern "C" fn credx_evict_expired_handles(count_p: *mut i64) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(count_p);
+        let count = FFI_OBJECTS
+            .lock()
+            .map_err(|_| err_msg!("Error locking object store"))?
+            .evict_expired(Instant::now());
+        unsafe { *count_p = count as i64 };
+        Ok(())
+    })
 }
 
 pub(crate) trait IndyObjectId: AnyIndyObject {
@@ -214,7 +682,7 @@ pub(crate) struct IndyObjectList(Vec<IndyObject>);
 impl IndyObjectList {
     pub fn load(handles: &[ObjectHandle]) -> Result<Self> {
         let loaded = handles
-            .into_iter()
+            .iter()
             .map(ObjectHandle::load)
             .collect::<Result<_>>()?;
         Ok(Self(loaded))
@@ -260,3 +728,46 @@ impl DerefMut for IndyObjectList {
         &mut self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicted_handles_are_bounded() {
+        let mut evicted = EvictedHandles::with_capacity(3);
+        for handle in 1..=5 {
+            evicted.insert(ObjectHandle(handle));
+        }
+        assert_eq!(evicted.order.len(), 3);
+        assert!(!evicted.contains(&ObjectHandle(2)));
+        assert!(evicted.contains(&ObjectHandle(3)));
+        assert!(evicted.remove(&ObjectHandle(3)));
+        assert!(!evicted.contains(&ObjectHandle(3)));
+
+        evicted.insert(ObjectHandle(6));
+        assert_eq!(evicted.order.len(), 3);
+        assert_eq!(evicted.handles.len(), 3);
+        assert!(evicted.contains(&ObjectHandle(4)));
+    }
+
+    #[test]
+    fn evicted_handle_reports_eviction() {
+        let mut store = ObjectStore::default();
+        store.set_lifetime(
+            None,
+            HandleLifetime {
+                ttl: Some(Duration::ZERO),
+                idle: None,
+            },
+        );
+        let handle = store
+            .insert(IndyObject::new(
+                crate::services::prover::create_master_secret().unwrap(),
+            ))
+            .unwrap();
+        assert_kind!(HandleEvicted, store.get(handle));
+        assert_kind!(HandleEvicted, store.remove(handle));
+        assert_kind!(Input, store.get(handle));
+    }
+}
@@ -1,9 +1,16 @@
 use std::os::raw::c_char;
+use std::ptr;
 
-use ffi_support::rust_string_to_c;
+use ffi_support::FfiStr;
 
+use super::alloc::alloc_string;
 use super::error::{catch_error, ErrorCode};
-use crate::services::{types::PresentationRequest, verifier::generate_nonce};
+use super::object::ObjectHandle;
+use crate::services::{
+    prover::requested_referents,
+    types::{PresentationRequest, PresentationRequestVersion},
+    verifier::generate_nonce,
+};
 
 impl_indy_object!(PresentationRequest, "PresentationRequest");
 impl_indy_object_from_json!(PresentationRequest, credx_presentation_request_from_json);
@@ -13,7 +20,67 @@ pub extern "C" fn credx_generate_nonce(nonce_p: *mut *const c_char) -> ErrorCode
     catch_error(|| {
         check_useful_c_ptr!(nonce_p);
         let nonce = generate_nonce()?.to_string();
-        unsafe { *nonce_p = rust_string_to_c(nonce) };
+        unsafe { *nonce_p = alloc_string(nonce) };
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn credx_presentation_request_get_attribute(
+    handle: ObjectHandle,
+    name: FfiStr,
+    result_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let pres_req = handle.load()?;
+        let pres_req = pres_req.cast_ref::<PresentationRequest>()?;
+        let payload = pres_req.value();
+        let val = match name.as_opt_str().unwrap_or_default() {
+            "name" => alloc_string(payload.name.clone()),
+            "version" => alloc_string(payload.version.clone()),
+            "nonce" => alloc_string(payload.nonce.to_string()),
+            "ver" => alloc_string(pres_req.version().as_str()),
+            "requested_attributes" => {
+                alloc_string(serde_json::to_string(&payload.requested_attributes)?)
+            }
+            "requested_predicates" => {
+                alloc_string(serde_json::to_string(&payload.requested_predicates)?)
+            }
+            "non_revoked" => match payload.non_revoked.as_ref() {
+                Some(interval) => alloc_string(serde_json::to_string(interval)?),
+                None => ptr::null_mut(),
+            },
+            "referents" => alloc_string(serde_json::to_string(&requested_referents(pres_req))?),
+            s => return Err(err_msg!("Unsupported attribute: {}", s)),
+        };
+        unsafe { *result_p = val };
+        Ok(())
+    })
+}
+
+/// Convert a presentation request to another version, given as the value of
+/// its `ver` property. Converting to version 1.0 replaces any fully qualified
+/// identifiers in the restrictions with unqualified ones.
+#[no_mangle]
+pub extern "C" fn credx_presentation_request_to_version(
+    handle: ObjectHandle,
+    ver: FfiStr,
+    pres_req_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(pres_req_p);
+        let version = ver
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing presentation request version"))?
+            .parse::<PresentationRequestVersion>()
+            .map_err(err_map!("Invalid presentation request version"))?;
+        let pres_req = handle.load()?;
+        let pres_req: PresentationRequest = serde_json::from_value(serde_json::to_value(
+            pres_req.cast_ref::<PresentationRequest>()?,
+        )?)?;
+        let pres_req = ObjectHandle::create(pres_req.into_version(version))?;
+        unsafe { *pres_req_p = pres_req };
         Ok(())
     })
 }
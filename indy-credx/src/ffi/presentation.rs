@@ -1,20 +1,81 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryInto;
+use std::os::raw::c_char;
 
-use ffi_support::FfiStr;
+use ffi_support::{ByteBuffer, FfiStr};
 
+use super::alloc::alloc_string;
 use super::error::{catch_error, ErrorCode};
 use super::object::{IndyObject, IndyObjectId, IndyObjectList, ObjectHandle};
 use super::util::{FfiList, FfiStrList};
+use crate::config::get_config;
 use crate::error::Result;
 use crate::services::{
-    prover::create_presentation,
-    types::{PresentCredentials, Presentation, RevocationRegistryDefinition},
-    verifier::verify_presentation,
+    prover::{create_auditable_presentation, create_presentation, select_revocation_state},
+    types::{
+        CredentialRevocationState, PresentCredentials, Presentation, PresentationRequest,
+        RevocationRegistry, RevocationRegistryDefinition, RevocationRegistryId, VerificationPolicy,
+    },
+    verifier::{
+        check_accepted_issuers, presentation_from_json, verify_presentation_with_policy,
+        verify_presentations,
+    },
 };
 
 impl_indy_object!(Presentation, "Presentation");
-impl_indy_object_from_json!(Presentation, credx_presentation_from_json);
+
+/// Load a presentation from JSON, enforcing the configured verification
+/// limits before the proof is parsed
+#[no_mangle]
+pub extern "C" fn credx_presentation_from_json(
+    json: ByteBuffer,
+    result_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let limits = get_config().verification_limits;
+        let presentation = presentation_from_json(json.as_slice(), &limits)?;
+        let handle = ObjectHandle::create(presentation)?;
+        unsafe { *result_p = handle };
+        Ok(())
+    })
+}
+
+/// Read a property of a presentation. The `identifiers` of the credentials
+/// are returned as a JSON array in the order of the sub-proofs, and each of
+/// `revealed_attrs`, `revealed_attr_groups` and `self_attested_attrs` as a
+/// JSON object keyed by referent.
+#[no_mangle]
+pub extern "C" fn credx_presentation_get_attribute(
+    handle: ObjectHandle,
+    name: FfiStr,
+    result_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let presentation = handle.load()?;
+        let presentation = presentation.cast_ref::<Presentation>()?;
+        let proof = &presentation.requested_proof;
+        let val = match name.as_opt_str().unwrap_or_default() {
+            "identifiers" => serde_json::to_string(&presentation.identifiers)?,
+            "revealed_attrs" => {
+                serde_json::to_string(&proof.revealed_attrs.iter().collect::<BTreeMap<_, _>>())?
+            }
+            "revealed_attr_groups" => serde_json::to_string(
+                &proof
+                    .revealed_attr_groups
+                    .iter()
+                    .collect::<BTreeMap<_, _>>(),
+            )?,
+            "self_attested_attrs" => serde_json::to_string(
+                &proof.self_attested_attrs.iter().collect::<BTreeMap<_, _>>(),
+            )?,
+            s => return Err(err_msg!("Unsupported attribute: {}", s)),
+        };
+        unsafe { *result_p = alloc_string(val) };
+        Ok(())
+    })
+}
 
 #[derive(Debug)]
 #[repr(C)]
@@ -67,6 +128,99 @@ pub extern "C" fn credx_create_presentation(
     schemas: FfiList<ObjectHandle>,
     cred_defs: FfiList<ObjectHandle>,
     presentation_p: *mut ObjectHandle,
+) -> ErrorCode {
+    build_presentation(
+        pres_req,
+        credentials,
+        credentials_prove,
+        self_attest_names,
+        self_attest_values,
+        master_secret,
+        schemas,
+        cred_defs,
+        PresentationMode::Standard,
+        presentation_p,
+    )
+}
+
+/// Create a presentation which also reveals the values of the attributes
+/// used in predicates
+#[no_mangle]
+pub extern "C" fn credx_create_auditable_presentation(
+    pres_req: ObjectHandle,
+    credentials: FfiList<FfiCredentialEntry>,
+    credentials_prove: FfiList<FfiCredentialProve>,
+    self_attest_names: FfiStrList,
+    self_attest_values: FfiStrList,
+    master_secret: ObjectHandle,
+    schemas: FfiList<ObjectHandle>,
+    cred_defs: FfiList<ObjectHandle>,
+    presentation_p: *mut ObjectHandle,
+) -> ErrorCode {
+    build_presentation(
+        pres_req,
+        credentials,
+        credentials_prove,
+        self_attest_names,
+        self_attest_values,
+        master_secret,
+        schemas,
+        cred_defs,
+        PresentationMode::Auditable,
+        presentation_p,
+    )
+}
+
+/// Create a presentation using random values derived from `seed`, for
+/// golden-file tests. The presentation reveals the hidden attributes and
+/// link secret to anyone who knows the seed, and must only be used in tests.
+#[cfg(feature = "test_seeded_keys")]
+#[no_mangle]
+pub extern "C" fn credx_create_presentation_from_seed(
+    pres_req: ObjectHandle,
+    credentials: FfiList<FfiCredentialEntry>,
+    credentials_prove: FfiList<FfiCredentialProve>,
+    self_attest_names: FfiStrList,
+    self_attest_values: FfiStrList,
+    master_secret: ObjectHandle,
+    schemas: FfiList<ObjectHandle>,
+    cred_defs: FfiList<ObjectHandle>,
+    seed: ffi_support::ByteBuffer,
+    presentation_p: *mut ObjectHandle,
+) -> ErrorCode {
+    build_presentation(
+        pres_req,
+        credentials,
+        credentials_prove,
+        self_attest_names,
+        self_attest_values,
+        master_secret,
+        schemas,
+        cred_defs,
+        PresentationMode::Seeded(seed),
+        presentation_p,
+    )
+}
+
+enum PresentationMode {
+    Standard,
+    Auditable,
+    #[cfg(feature = "test_seeded_keys")]
+    Seeded(ffi_support::ByteBuffer),
+}
+
+#[allow(clippy::too_many_arguments)]
+fn build_presentation(
+    pres_req: ObjectHandle,
+    credentials: FfiList<FfiCredentialEntry>,
+    credentials_prove: FfiList<FfiCredentialProve>,
+    self_attest_names: FfiStrList,
+    self_attest_values: FfiStrList,
+    master_secret: ObjectHandle,
+    schemas: FfiList<ObjectHandle>,
+    cred_defs: FfiList<ObjectHandle>,
+    mode: PresentationMode,
+    presentation_p: *mut ObjectHandle,
 ) -> ErrorCode {
     catch_error(|| {
         check_useful_c_ptr!(presentation_p);
@@ -78,7 +232,7 @@ pub extern "C" fn credx_create_presentation(
 
         let entries = {
             let credentials = credentials.as_slice();
-            credentials.into_iter().try_fold(
+            credentials.iter().try_fold(
                 Vec::with_capacity(credentials.len()),
                 |mut r, ffi_entry| {
                     r.push(ffi_entry.load()?);
@@ -94,7 +248,7 @@ pub extern "C" fn credx_create_presentation(
             let mut self_attested = HashMap::new();
             for (name, raw) in self_attest_names
                 .as_slice()
-                .into_iter()
+                .iter()
                 .zip(self_attest_values.as_slice())
             {
                 let name = name
@@ -147,20 +301,93 @@ pub extern "C" fn credx_create_presentation(
             }
         }
 
-        let presentation = create_presentation(
-            pres_req.load()?.cast_ref()?,
-            present_creds,
-            self_attested,
-            master_secret.load()?.cast_ref()?,
-            &schemas.refs_map()?,
-            &cred_defs.refs_map()?,
-        )?;
+        let pres_req = pres_req.load()?;
+        let master_secret = master_secret.load()?;
+        let (pres_req, master_secret) = (pres_req.cast_ref()?, master_secret.cast_ref()?);
+        let (schemas, cred_defs) = (&schemas.refs_map()?, &cred_defs.refs_map()?);
+        let presentation = match &mode {
+            PresentationMode::Standard => create_presentation(
+                pres_req,
+                present_creds,
+                self_attested,
+                master_secret,
+                schemas,
+                cred_defs,
+            ),
+            PresentationMode::Auditable => create_auditable_presentation(
+                pres_req,
+                present_creds,
+                self_attested,
+                master_secret,
+                schemas,
+                cred_defs,
+            ),
+            #[cfg(feature = "test_seeded_keys")]
+            PresentationMode::Seeded(seed) => {
+                crate::services::seeded_proofs::create_presentation_from_seed(
+                    pres_req,
+                    present_creds,
+                    self_attested,
+                    master_secret,
+                    schemas,
+                    cred_defs,
+                    seed.as_slice(),
+                )
+            }
+        }?;
         let presentation = ObjectHandle::create(presentation)?;
         unsafe { *presentation_p = presentation };
         Ok(())
     })
 }
 
+/// Select the latest of a list of revocation states with which to present a
+/// credential for the given referents, according to the `non_revoked`
+/// intervals of the request. The index of the selected state and its
+/// timestamp are returned, or -1 for both when no non-revocation proof is
+/// required.
+#[no_mangle]
+pub extern "C" fn credx_select_revocation_state(
+    pres_req: ObjectHandle,
+    attr_referents: FfiStrList,
+    predicate_referents: FfiStrList,
+    rev_states: FfiList<ObjectHandle>,
+    index_p: *mut i64,
+    timestamp_p: *mut i64,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(index_p);
+        check_useful_c_ptr!(timestamp_p);
+        let referents = |list: FfiStrList| {
+            list.as_slice()
+                .iter()
+                .map(|r| {
+                    r.as_opt_str()
+                        .map(str::to_string)
+                        .ok_or_else(|| err_msg!("Missing referent"))
+                })
+                .collect::<Result<_>>()
+        };
+        let rev_states = IndyObjectList::load(rev_states.as_slice())?;
+        let rev_states = rev_states.refs::<CredentialRevocationState>()?;
+        let selected = select_revocation_state(
+            pres_req.load()?.cast_ref()?,
+            referents(attr_referents)?,
+            referents(predicate_referents)?,
+            &rev_states,
+        )?;
+        let (index, timestamp) = match selected {
+            Some(idx) => (idx as i64, rev_states[idx].timestamp as i64),
+            None => (-1, -1),
+        };
+        unsafe {
+            *index_p = index;
+            *timestamp_p = timestamp;
+        }
+        Ok(())
+    })
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct FfiRevocationEntry {
@@ -193,43 +420,159 @@ pub extern "C" fn credx_verify_presentation(
     rev_reg_defs: FfiList<ObjectHandle>,
     rev_reg_entries: FfiList<FfiRevocationEntry>,
     result_p: *mut i8,
+) -> ErrorCode {
+    credx_verify_presentation_with_policy(
+        presentation,
+        pres_req,
+        schemas,
+        cred_defs,
+        rev_reg_defs,
+        rev_reg_entries,
+        unsafe { FfiStr::from_raw(std::ptr::null()) },
+        result_p,
+    )
+}
+
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn credx_verify_presentation_with_policy(
+    presentation: ObjectHandle,
+    pres_req: ObjectHandle,
+    schemas: FfiList<ObjectHandle>,
+    cred_defs: FfiList<ObjectHandle>,
+    rev_reg_defs: FfiList<ObjectHandle>,
+    rev_reg_entries: FfiList<FfiRevocationEntry>,
+    policy_json: FfiStr,
+    result_p: *mut i8,
 ) -> ErrorCode {
     catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let policy = load_policy(policy_json)?;
         let schemas = IndyObjectList::load(schemas.as_slice())?;
         let cred_defs = IndyObjectList::load(cred_defs.as_slice())?;
         let rev_reg_defs = IndyObjectList::load(rev_reg_defs.as_slice())?;
-        let rev_reg_entries = {
-            let entries = rev_reg_entries.as_slice();
-            entries.into_iter().try_fold(
-                Vec::with_capacity(entries.len()),
-                |mut r, ffi_entry| {
-                    r.push(ffi_entry.load()?);
-                    Result::Ok(r)
-                },
-            )?
-        };
-        let mut rev_regs = HashMap::new();
-        for (idx, entry, timestamp) in rev_reg_entries.iter() {
-            if *idx > rev_reg_defs.len() {
-                return Err(err_msg!("Invalid revocation registry entry index"));
-            }
-            let id = rev_reg_defs[*idx]
-                .cast_ref::<RevocationRegistryDefinition>()?
-                .get_id();
-            rev_regs
-                .entry(id)
-                .or_insert_with(HashMap::new)
-                .insert(*timestamp, entry.cast_ref()?);
-        }
-        let verify = verify_presentation(
+        let rev_reg_entries = rev_reg_entries.try_collect(FfiRevocationEntry::load)?;
+        let rev_regs = load_rev_regs(&rev_reg_defs, &rev_reg_entries)?;
+        let verify = verify_presentation_with_policy(
             presentation.load()?.cast_ref()?,
             pres_req.load()?.cast_ref()?,
             &schemas.refs_map()?,
             &cred_defs.refs_map()?,
             Some(&rev_reg_defs.refs_map()?),
             Some(&rev_regs),
+            &policy,
         )?;
         unsafe { *result_p = verify as i8 };
         Ok(())
     })
 }
+
+/// Verify a batch of presentations against their requests, sharing the
+/// ledger objects between them and dividing the batch between the threads of
+/// the configured pool. The results are returned as a JSON array in the order
+/// of the presentations, with each entry holding the `verified` flag and, when
+/// verification failed with an error, the `error` code and message.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn credx_verify_presentations_batch(
+    presentations: FfiList<ObjectHandle>,
+    pres_reqs: FfiList<ObjectHandle>,
+    schemas: FfiList<ObjectHandle>,
+    cred_defs: FfiList<ObjectHandle>,
+    rev_reg_defs: FfiList<ObjectHandle>,
+    rev_reg_entries: FfiList<FfiRevocationEntry>,
+    policy_json: FfiStr,
+    results_json_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(results_json_p);
+        if presentations.len() != pres_reqs.len() {
+            return Err(err_msg!(
+                "Mismatch between the number of presentations and presentation requests"
+            ));
+        }
+        let policy = load_policy(policy_json)?;
+        let presentations = IndyObjectList::load(presentations.as_slice())?;
+        let pres_reqs = IndyObjectList::load(pres_reqs.as_slice())?;
+        let schemas = IndyObjectList::load(schemas.as_slice())?;
+        let cred_defs = IndyObjectList::load(cred_defs.as_slice())?;
+        let rev_reg_defs = IndyObjectList::load(rev_reg_defs.as_slice())?;
+        let rev_reg_entries = rev_reg_entries.try_collect(FfiRevocationEntry::load)?;
+        let rev_regs = load_rev_regs(&rev_reg_defs, &rev_reg_entries)?;
+        let batch = presentations
+            .refs::<Presentation>()?
+            .into_iter()
+            .zip(pres_reqs.refs::<PresentationRequest>()?)
+            .collect::<Vec<_>>();
+        let results = verify_presentations(
+            &batch,
+            &schemas.refs_map()?,
+            &cred_defs.refs_map()?,
+            Some(&rev_reg_defs.refs_map()?),
+            Some(&rev_regs),
+            &policy,
+        )
+        .into_iter()
+        .map(|result| match result {
+            Ok(verified) => serde_json::json!({ "verified": verified }),
+            Err(err) => serde_json::json!({
+                "verified": false,
+                "error": {
+                    "code": ErrorCode::from(err.kind()) as usize,
+                    "message": err.to_string(),
+                },
+            }),
+        })
+        .collect::<Vec<_>>();
+        let results = serde_json::to_string(&results)?;
+        unsafe { *results_json_p = alloc_string(results) };
+        Ok(())
+    })
+}
+
+fn load_policy(policy_json: FfiStr) -> Result<VerificationPolicy> {
+    Ok(match policy_json.as_opt_str() {
+        Some(json) => serde_json::from_str::<VerificationPolicy>(json)?,
+        None => VerificationPolicy::default(),
+    })
+}
+
+fn load_rev_regs<'e>(
+    rev_reg_defs: &IndyObjectList,
+    rev_reg_entries: &'e [(usize, IndyObject, u64)],
+) -> Result<HashMap<RevocationRegistryId, HashMap<u64, &'e RevocationRegistry>>> {
+    let mut rev_regs = HashMap::new();
+    for (idx, entry, timestamp) in rev_reg_entries.iter() {
+        if *idx >= rev_reg_defs.len() {
+            return Err(err_msg!("Invalid revocation registry entry index"));
+        }
+        let id = rev_reg_defs[*idx]
+            .cast_ref::<RevocationRegistryDefinition>()?
+            .get_id();
+        rev_regs
+            .entry(id)
+            .or_insert_with(HashMap::new)
+            .insert(*timestamp, entry.cast_ref()?);
+    }
+    Ok(rev_regs)
+}
+
+#[no_mangle]
+pub extern "C" fn credx_presentation_check_accepted_issuers(
+    presentation: ObjectHandle,
+    policy_json: FfiStr,
+    mismatches_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(mismatches_p);
+        let policy = serde_json::from_str::<VerificationPolicy>(
+            policy_json
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing verification policy"))?,
+        )?;
+        let mismatches = check_accepted_issuers(presentation.load()?.cast_ref()?, &policy)?;
+        let mismatches = serde_json::to_string(&mismatches)?;
+        unsafe { *mismatches_p = alloc_string(mismatches) };
+        Ok(())
+    })
+}
@@ -0,0 +1,81 @@
+use std::os::raw::c_char;
+
+use ffi_support::{ByteBuffer, FfiStr};
+
+use super::alloc::{alloc_buffer, alloc_string};
+use super::error::{catch_error, ErrorCode};
+use super::object::{AnyIndyObject, IndyObject, ObjectHandle};
+use crate::error::Result;
+use crate::services::proto::{ProtoMessage, PROTO_DEFINITIONS};
+use crate::services::types::{Credential, CredentialDefinition, Presentation, Schema};
+
+fn encode<T: ProtoMessage + AnyIndyObject + 'static>(obj: &IndyObject) -> Result<Vec<u8>> {
+    obj.cast_ref::<T>()?.to_proto()
+}
+
+fn load<T: ProtoMessage + AnyIndyObject + 'static>(data: &[u8]) -> Result<ObjectHandle> {
+    ObjectHandle::create(T::from_proto(data)?)
+}
+
+/// Encode a schema, credential definition, credential or presentation as a
+/// protocol buffer message
+#[no_mangle]
+pub extern "C" fn credx_object_get_proto(
+    handle: ObjectHandle,
+    result_p: *mut ByteBuffer,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let obj = handle.load()?;
+        let encoded = match obj.type_name() {
+            "Schema" => encode::<Schema>(&obj),
+            "CredentialDefinition" => encode::<CredentialDefinition>(&obj),
+            "Credential" => encode::<Credential>(&obj),
+            "Presentation" => encode::<Presentation>(&obj),
+            other => Err(err_msg!(
+                "Protobuf encoding is not supported for {} objects",
+                other
+            )),
+        }?;
+        unsafe { *result_p = alloc_buffer(encoded) };
+        Ok(())
+    })
+}
+
+/// Load an object of the named type from a protocol buffer message
+#[no_mangle]
+pub extern "C" fn credx_object_from_proto(
+    type_name: FfiStr,
+    data: ByteBuffer,
+    result_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let type_name = type_name
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing object type"))?;
+        let data = data.as_slice();
+        let handle = match type_name {
+            "Schema" => load::<Schema>(data),
+            "CredentialDefinition" => load::<CredentialDefinition>(data),
+            "Credential" => load::<Credential>(data),
+            "Presentation" => load::<Presentation>(data),
+            other => Err(err_msg!(
+                "Protobuf encoding is not supported for {} objects",
+                other
+            )),
+        }?;
+        unsafe { *result_p = handle };
+        Ok(())
+    })
+}
+
+/// Get the protocol buffer definitions of the supported objects
+#[no_mangle]
+pub extern "C" fn credx_proto_definitions(result_p: *mut *const c_char) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        unsafe { *result_p = alloc_string(PROTO_DEFINITIONS) };
+        Ok(())
+    })
+}
@@ -1,26 +1,32 @@
 use std::collections::BTreeSet;
 use std::convert::TryInto;
 use std::os::raw::c_char;
+use std::ptr;
 
-use ffi_support::{rust_string_to_c, FfiStr};
+use ffi_support::{ByteBuffer, FfiStr};
 use indy_utils::Qualifiable;
 
+use super::alloc::{alloc_buffer, alloc_string};
+use super::cancel::load_cancellation_token;
 use super::error::{catch_error, ErrorCode};
-use super::object::{IndyObject, IndyObjectId, ObjectHandle};
+use super::object::{copy_by_clone, IndyObject, IndyObjectId, ObjectHandle};
 use super::util::FfiList;
 use crate::error::Result;
 use crate::services::{
     issuer::{
-        create_revocation_registry, merge_revocation_registry_deltas, revoke_credential,
-        update_revocation_registry,
+        complete_revocation_registry_tails, create_revocation_registry,
+        create_revocation_registry_lazy, extend_revocation_registry_tails,
+        merge_revocation_registry_deltas, revoke_credential, update_revocation_registry,
+        write_revocation_registry_tails,
     },
-    prover::create_or_update_revocation_state,
-    tails::{TailsFileReader, TailsFileWriter},
+    prover::{create_or_update_revocation_state, create_or_update_revocation_states},
+    tails::{TailsBufferWriter, TailsChunkedWriter, TailsFileReader, TailsFileWriter, TailsWriter},
     types::{
         CredentialRevocationState, DidValue, IssuanceType, RegistryType, RevocationRegistry,
         RevocationRegistryDefinition, RevocationRegistryDefinitionPrivate, RevocationRegistryDelta,
         RevocationRegistryId,
     },
+    verifier::{revocation_registry_from_deltas, revocation_registry_to_delta},
 };
 
 #[no_mangle]
@@ -36,6 +42,73 @@ pub extern "C" fn credx_create_revocation_registry(
     reg_def_private_p: *mut ObjectHandle,
     reg_entry_p: *mut ObjectHandle,
     reg_init_delta_p: *mut ObjectHandle,
+) -> ErrorCode {
+    credx_create_revocation_registry_cancellable(
+        origin_did,
+        cred_def,
+        tag,
+        rev_reg_type,
+        issuance_type,
+        max_cred_num,
+        tails_dir_path,
+        ObjectHandle::default(),
+        reg_def_p,
+        reg_def_private_p,
+        reg_entry_p,
+        reg_init_delta_p,
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn credx_create_revocation_registry_cancellable(
+    origin_did: FfiStr,
+    cred_def: ObjectHandle,
+    tag: FfiStr,
+    rev_reg_type: FfiStr,
+    issuance_type: FfiStr,
+    max_cred_num: i64,
+    tails_dir_path: FfiStr,
+    cancel_token: ObjectHandle,
+    reg_def_p: *mut ObjectHandle,
+    reg_def_private_p: *mut ObjectHandle,
+    reg_entry_p: *mut ObjectHandle,
+    reg_init_delta_p: *mut ObjectHandle,
+) -> ErrorCode {
+    credx_create_revocation_registry_chunked(
+        origin_did,
+        cred_def,
+        tag,
+        rev_reg_type,
+        issuance_type,
+        max_cred_num,
+        tails_dir_path,
+        0,
+        cancel_token,
+        reg_def_p,
+        reg_def_private_p,
+        reg_entry_p,
+        reg_init_delta_p,
+    )
+}
+
+/// Create a revocation registry, writing the tails in the chunked layout
+/// with `tails_chunk_size` tails per chunk file. A chunk size of zero
+/// writes a single contiguous tails file.
+#[no_mangle]
+pub extern "C" fn credx_create_revocation_registry_chunked(
+    origin_did: FfiStr,
+    cred_def: ObjectHandle,
+    tag: FfiStr,
+    rev_reg_type: FfiStr,
+    issuance_type: FfiStr,
+    max_cred_num: i64,
+    tails_dir_path: FfiStr,
+    tails_chunk_size: i64,
+    cancel_token: ObjectHandle,
+    reg_def_p: *mut ObjectHandle,
+    reg_def_private_p: *mut ObjectHandle,
+    reg_entry_p: *mut ObjectHandle,
+    reg_init_delta_p: *mut ObjectHandle,
 ) -> ErrorCode {
     catch_error(|| {
         check_useful_c_ptr!(reg_def_p);
@@ -59,7 +132,11 @@ pub extern "C" fn credx_create_revocation_registry(
             Some(s) => IssuanceType::from_str(s).map_err(err_map!(Input))?,
             None => IssuanceType::default(),
         };
-        let mut tails_writer = TailsFileWriter::new(tails_dir_path.into_opt_string());
+        let mut tails_writer = make_tails_writer(
+            tails_dir_path.into_opt_string(),
+            tails_chunk_size,
+            cancel_token,
+        )?;
         let (reg_def, reg_def_private, reg_entry, reg_init_delta) = create_revocation_registry(
             &origin_did,
             cred_def.load()?.cast_ref()?,
@@ -69,7 +146,7 @@ pub extern "C" fn credx_create_revocation_registry(
             max_cred_num
                 .try_into()
                 .map_err(|_| err_msg!("Invalid maximum credential count"))?,
-            &mut tails_writer,
+            tails_writer.as_mut(),
         )?;
         let reg_def = ObjectHandle::create(reg_def)?;
         let reg_def_private = ObjectHandle::create(reg_def_private)?;
@@ -85,6 +162,232 @@ pub extern "C" fn credx_create_revocation_registry(
     })
 }
 
+/// Create a revocation registry without generating its tails. The tails
+/// must be written with `credx_write_revocation_registry_tails` before the
+/// registry definition is published.
+#[no_mangle]
+pub extern "C" fn credx_create_revocation_registry_lazy(
+    origin_did: FfiStr,
+    cred_def: ObjectHandle,
+    tag: FfiStr,
+    rev_reg_type: FfiStr,
+    issuance_type: FfiStr,
+    max_cred_num: i64,
+    reg_def_p: *mut ObjectHandle,
+    reg_def_private_p: *mut ObjectHandle,
+    reg_entry_p: *mut ObjectHandle,
+    reg_init_delta_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(reg_def_p);
+        check_useful_c_ptr!(reg_def_private_p);
+        check_useful_c_ptr!(reg_entry_p);
+        check_useful_c_ptr!(reg_init_delta_p);
+        let origin_did = {
+            let did = origin_did
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing origin DID"))?;
+            DidValue::from_str(did)?
+        };
+        let tag = tag.as_opt_str().ok_or_else(|| err_msg!("Missing tag"))?;
+        let rev_reg_type = {
+            let rtype = rev_reg_type
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing registry type"))?;
+            RegistryType::from_str(rtype).map_err(err_map!(Input))?
+        };
+        let issuance_type = match issuance_type.as_opt_str() {
+            Some(s) => IssuanceType::from_str(s).map_err(err_map!(Input))?,
+            None => IssuanceType::default(),
+        };
+        let (reg_def, reg_def_private, reg_entry, reg_init_delta) =
+            create_revocation_registry_lazy(
+                &origin_did,
+                cred_def.load()?.cast_ref()?,
+                tag,
+                rev_reg_type,
+                issuance_type,
+                max_cred_num
+                    .try_into()
+                    .map_err(|_| err_msg!("Invalid maximum credential count"))?,
+            )?;
+        let reg_def = ObjectHandle::create(reg_def)?;
+        let reg_def_private = ObjectHandle::create(reg_def_private)?;
+        let reg_entry = ObjectHandle::create(reg_entry)?;
+        let reg_init_delta = ObjectHandle::create(reg_init_delta)?;
+        unsafe {
+            *reg_def_p = reg_def;
+            *reg_def_private_p = reg_def_private;
+            *reg_entry_p = reg_entry;
+            *reg_init_delta_p = reg_init_delta;
+        };
+        Ok(())
+    })
+}
+
+/// Write the tails for a revocation registry and return the registry
+/// definition updated with the tails location and hash. A chunk size of
+/// zero writes a single contiguous tails file.
+#[no_mangle]
+pub extern "C" fn credx_write_revocation_registry_tails(
+    cred_def: ObjectHandle,
+    rev_reg_def: ObjectHandle,
+    rev_reg_def_private: ObjectHandle,
+    tails_dir_path: FfiStr,
+    tails_chunk_size: i64,
+    cancel_token: ObjectHandle,
+    rev_reg_def_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(rev_reg_def_p);
+        let mut tails_writer = make_tails_writer(
+            tails_dir_path.into_opt_string(),
+            tails_chunk_size,
+            cancel_token,
+        )?;
+        let rev_reg_def = write_revocation_registry_tails(
+            cred_def.load()?.cast_ref()?,
+            rev_reg_def.load()?.cast_ref()?,
+            rev_reg_def_private.load()?.cast_ref()?,
+            tails_writer.as_mut(),
+        )?;
+        let rev_reg_def = ObjectHandle::create(rev_reg_def)?;
+        unsafe { *rev_reg_def_p = rev_reg_def };
+        Ok(())
+    })
+}
+
+/// Write the tails for a revocation registry into a buffer instead of the
+/// filesystem. The tails location of the updated registry definition is
+/// `tails_location` with any `{hash}` placeholder replaced by the tails
+/// hash, defaulting to the tails hash.
+#[no_mangle]
+pub extern "C" fn credx_write_revocation_registry_tails_to_buffer(
+    cred_def: ObjectHandle,
+    rev_reg_def: ObjectHandle,
+    rev_reg_def_private: ObjectHandle,
+    tails_location: FfiStr,
+    cancel_token: ObjectHandle,
+    rev_reg_def_p: *mut ObjectHandle,
+    tails_p: *mut ByteBuffer,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(rev_reg_def_p);
+        check_useful_c_ptr!(tails_p);
+        let mut tails_writer = TailsBufferWriter::new(tails_location.into_opt_string());
+        if let Some(cancel) = load_cancellation_token(cancel_token)? {
+            tails_writer = tails_writer.with_cancellation(cancel);
+        }
+        let rev_reg_def = write_revocation_registry_tails(
+            cred_def.load()?.cast_ref()?,
+            rev_reg_def.load()?.cast_ref()?,
+            rev_reg_def_private.load()?.cast_ref()?,
+            &mut tails_writer,
+        )?;
+        let rev_reg_def = ObjectHandle::create(rev_reg_def)?;
+        unsafe {
+            *rev_reg_def_p = rev_reg_def;
+            *tails_p = alloc_buffer(tails_writer.into_bytes());
+        }
+        Ok(())
+    })
+}
+
+/// Generate up to `count` more tails for a revocation registry, appending
+/// them to the partial tails file at `partial_path`. The number of tails
+/// still to be generated is written to `remaining_p`.
+#[no_mangle]
+pub extern "C" fn credx_extend_revocation_registry_tails(
+    cred_def: ObjectHandle,
+    rev_reg_def: ObjectHandle,
+    rev_reg_def_private: ObjectHandle,
+    partial_path: FfiStr,
+    count: i64,
+    remaining_p: *mut i64,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(remaining_p);
+        let partial_path = partial_path
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing partial tails path"))?;
+        let remaining = extend_revocation_registry_tails(
+            cred_def.load()?.cast_ref()?,
+            rev_reg_def.load()?.cast_ref()?,
+            rev_reg_def_private.load()?.cast_ref()?,
+            partial_path,
+            count
+                .try_into()
+                .map_err(|_| err_msg!("Invalid tails count"))?,
+        )?;
+        unsafe { *remaining_p = remaining as i64 };
+        Ok(())
+    })
+}
+
+/// Write the tails for a revocation registry like
+/// `credx_write_revocation_registry_tails`, reusing the tails already
+/// generated into the partial tails file at `partial_path`.
+#[no_mangle]
+pub extern "C" fn credx_complete_revocation_registry_tails(
+    cred_def: ObjectHandle,
+    rev_reg_def: ObjectHandle,
+    rev_reg_def_private: ObjectHandle,
+    partial_path: FfiStr,
+    tails_dir_path: FfiStr,
+    tails_chunk_size: i64,
+    cancel_token: ObjectHandle,
+    rev_reg_def_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(rev_reg_def_p);
+        let partial_path = partial_path
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing partial tails path"))?;
+        let mut tails_writer = make_tails_writer(
+            tails_dir_path.into_opt_string(),
+            tails_chunk_size,
+            cancel_token,
+        )?;
+        let rev_reg_def = complete_revocation_registry_tails(
+            cred_def.load()?.cast_ref()?,
+            rev_reg_def.load()?.cast_ref()?,
+            rev_reg_def_private.load()?.cast_ref()?,
+            partial_path,
+            tails_writer.as_mut(),
+        )?;
+        let rev_reg_def = ObjectHandle::create(rev_reg_def)?;
+        unsafe { *rev_reg_def_p = rev_reg_def };
+        Ok(())
+    })
+}
+
+fn make_tails_writer(
+    tails_dir_path: Option<String>,
+    tails_chunk_size: i64,
+    cancel_token: ObjectHandle,
+) -> Result<Box<dyn TailsWriter>> {
+    let cancel = load_cancellation_token(cancel_token)?;
+    Ok(match tails_chunk_size {
+        0 => {
+            let mut writer = TailsFileWriter::new(tails_dir_path);
+            if let Some(cancel) = cancel {
+                writer = writer.with_cancellation(cancel);
+            }
+            Box::new(writer)
+        }
+        size => {
+            let size = size
+                .try_into()
+                .map_err(|_| err_msg!("Invalid tails chunk size"))?;
+            let mut writer = TailsChunkedWriter::new(tails_dir_path, Some(size));
+            if let Some(cancel) = cancel {
+                writer = writer.with_cancellation(cancel);
+            }
+            Box::new(writer)
+        }
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn credx_update_revocation_registry(
     rev_reg_def: ObjectHandle,
@@ -98,8 +401,8 @@ pub extern "C" fn credx_update_revocation_registry(
     catch_error(|| {
         check_useful_c_ptr!(rev_reg_p);
         check_useful_c_ptr!(rev_reg_delta_p);
-        let issued = registry_indices_to_set(issued.as_slice().into_iter().cloned())?;
-        let revoked = registry_indices_to_set(revoked.as_slice().into_iter().cloned())?;
+        let issued = registry_indices_to_set(issued.as_slice().iter().cloned())?;
+        let revoked = registry_indices_to_set(revoked.as_slice().iter().cloned())?;
         let tails_reader = TailsFileReader::new(
             tails_path
                 .as_opt_str()
@@ -167,7 +470,11 @@ fn registry_indices_to_set(indices: impl Iterator<Item = i64>) -> Result<BTreeSe
     })
 }
 
-impl_indy_object!(RevocationRegistryDefinition, "RevocationRegistryDefinition");
+impl_indy_object!(
+    RevocationRegistryDefinition,
+    "RevocationRegistryDefinition",
+    copy_by_clone
+);
 impl_indy_object_from_json!(
     RevocationRegistryDefinition,
     credx_revocation_registry_definition_from_json
@@ -212,7 +519,7 @@ pub extern "C" fn credx_revocation_registry_definition_get_attribute(
             },
             s => return Err(err_msg!("Unsupported attribute: {}", s)),
         };
-        unsafe { *result_p = rust_string_to_c(val) };
+        unsafe { *result_p = alloc_string(val) };
         Ok(())
     })
 }
@@ -226,7 +533,7 @@ impl_indy_object_from_json!(
     credx_revocation_registry_definition_private_from_json
 );
 
-impl_indy_object!(RevocationRegistry, "RevocationRegistry");
+impl_indy_object!(RevocationRegistry, "RevocationRegistry", copy_by_clone);
 impl_indy_object_from_json!(RevocationRegistry, credx_revocation_registry_from_json);
 
 #[no_mangle]
@@ -249,12 +556,131 @@ pub extern "C" fn credx_merge_revocation_registry_deltas(
     })
 }
 
-impl_indy_object!(RevocationRegistryDelta, "RevocationRegistryDelta");
+#[derive(Debug)]
+#[repr(C)]
+pub struct FfiRevocationDeltaEntry {
+    delta: ObjectHandle,
+    timestamp: i64,
+}
+
+impl FfiRevocationDeltaEntry {
+    fn load(&self) -> Result<(u64, IndyObject)> {
+        let timestamp = self
+            .timestamp
+            .try_into()
+            .map_err(|_| err_msg!("Invalid timestamp for revocation delta"))?;
+        Ok((timestamp, self.delta.load()?))
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn credx_revocation_registry_from_deltas(
+    deltas: FfiList<FfiRevocationDeltaEntry>,
+    timestamp: i64,
+    rev_reg_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(rev_reg_p);
+        let entries = deltas.try_collect(FfiRevocationDeltaEntry::load)?;
+        let deltas = entries
+            .iter()
+            .map(|(timestamp, delta)| Ok((*timestamp, delta.cast_ref()?)))
+            .collect::<Result<Vec<_>>>()?;
+        let rev_reg = revocation_registry_from_deltas(
+            &deltas,
+            timestamp
+                .try_into()
+                .map_err(|_| err_msg!("Invalid timestamp"))?,
+        )?;
+        let rev_reg = ObjectHandle::create(rev_reg)?;
+        unsafe { *rev_reg_p = rev_reg };
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn credx_revocation_registry_to_delta(
+    rev_reg: ObjectHandle,
+    prev_rev_reg: ObjectHandle,
+    issued: FfiList<i64>,
+    revoked: FfiList<i64>,
+    rev_reg_delta_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(rev_reg_delta_p);
+        let prev_rev_reg = prev_rev_reg.opt_load()?;
+        let issued = registry_indices_to_set(issued.as_slice().iter().cloned())?;
+        let revoked = registry_indices_to_set(revoked.as_slice().iter().cloned())?;
+        let rev_reg_delta = revocation_registry_to_delta(
+            rev_reg.load()?.cast_ref()?,
+            prev_rev_reg
+                .as_ref()
+                .map(IndyObject::cast_ref)
+                .transpose()?,
+            &issued,
+            &revoked,
+        );
+        let rev_reg_delta = ObjectHandle::create(rev_reg_delta)?;
+        unsafe { *rev_reg_delta_p = rev_reg_delta };
+        Ok(())
+    })
+}
+
+impl_indy_object!(
+    RevocationRegistryDelta,
+    "RevocationRegistryDelta",
+    copy_by_clone
+);
 impl_indy_object_from_json!(
     RevocationRegistryDelta,
     credx_revocation_registry_delta_from_json
 );
 
+/// Read a property of a revocation registry delta. The `issued` and
+/// `revoked` indices are returned as sorted JSON arrays, and `prev_accum` is
+/// null for a delta from the initial registry state.
+#[no_mangle]
+pub extern "C" fn credx_revocation_registry_delta_get_attribute(
+    handle: ObjectHandle,
+    name: FfiStr,
+    result_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(result_p);
+        let delta = handle.load()?;
+        let RevocationRegistryDelta::RevocationRegistryDeltaV1(delta) =
+            delta.cast_ref::<RevocationRegistryDelta>()?;
+        // the fields of the CL delta are only accessible in serialized form
+        let value = serde_json::to_value(&delta.value)?;
+        let indices = |field: &str| -> Result<String> {
+            let indices: BTreeSet<u32> = match value.get(field) {
+                Some(indices) => serde_json::from_value(indices.clone())?,
+                None => BTreeSet::new(),
+            };
+            Ok(serde_json::to_string(&indices)?)
+        };
+        let accum = |field: &str| -> Option<String> {
+            value
+                .get(field)
+                .and_then(serde_json::Value::as_str)
+                .map(str::to_string)
+        };
+        let val = match name.as_opt_str().unwrap_or_default() {
+            "issued" => alloc_string(indices("issued")?),
+            "revoked" => alloc_string(indices("revoked")?),
+            "accum" => alloc_string(
+                accum("accum").ok_or_else(|| err_msg!(Unexpected, "Missing accumulator"))?,
+            ),
+            "prev_accum" => accum("prevAccum")
+                .map(alloc_string)
+                .unwrap_or(ptr::null_mut()),
+            s => return Err(err_msg!("Unsupported attribute: {}", s)),
+        };
+        unsafe { *result_p = val };
+        Ok(())
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn credx_create_or_update_revocation_state(
     rev_reg_def: ObjectHandle,
@@ -294,5 +720,70 @@ pub extern "C" fn credx_create_or_update_revocation_state(
     })
 }
 
-impl_indy_object!(CredentialRevocationState, "CredentialRevocationState");
+/// Create or update the revocation states for several credentials in one
+/// registry. `rev_states` may be empty, or else must contain one (possibly
+/// zero) handle per revocation index. `rev_states_p` must point to an array
+/// with room for one handle per revocation index.
+#[no_mangle]
+pub extern "C" fn credx_create_or_update_revocation_states(
+    rev_reg_def: ObjectHandle,
+    rev_reg_delta: ObjectHandle,
+    rev_reg_indices: FfiList<i64>,
+    rev_states: FfiList<ObjectHandle>,
+    timestamp: i64,
+    tails_path: FfiStr,
+    rev_states_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(rev_states_p);
+        if !rev_states.is_empty() && rev_states.len() != rev_reg_indices.len() {
+            return Err(err_msg!(
+                "Mismatch between length of revocation indices and previous states"
+            ));
+        }
+        let prev_rev_states = rev_states.try_collect(|handle| handle.opt_load())?;
+        let prev_rev_states = prev_rev_states
+            .iter()
+            .map(|state| state.as_ref().map(IndyObject::cast_ref).transpose())
+            .collect::<Result<Vec<_>>>()?;
+        let entries = rev_reg_indices
+            .as_slice()
+            .iter()
+            .enumerate()
+            .map(|(idx, rev_reg_index)| {
+                let rev_reg_index = (*rev_reg_index)
+                    .try_into()
+                    .map_err(|_| err_msg!("Invalid credential revocation index"))?;
+                Ok((rev_reg_index, prev_rev_states.get(idx).cloned().flatten()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let tails_reader = TailsFileReader::new(
+            tails_path
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing tails file path"))?,
+        );
+        let results = create_or_update_revocation_states(
+            tails_reader,
+            rev_reg_def.load()?.cast_ref()?,
+            rev_reg_delta.load()?.cast_ref()?,
+            &entries,
+            timestamp
+                .try_into()
+                .map_err(|_| err_msg!("Invalid timestamp"))?,
+        )?;
+        let handles = results
+            .into_iter()
+            .map(ObjectHandle::create)
+            .collect::<Result<Vec<_>>>()?;
+        let output = unsafe { std::slice::from_raw_parts_mut(rev_states_p, handles.len()) };
+        output.copy_from_slice(&handles);
+        Ok(())
+    })
+}
+
+impl_indy_object!(
+    CredentialRevocationState,
+    "CredentialRevocationState",
+    copy_by_clone
+);
 impl_indy_object_from_json!(CredentialRevocationState, credx_revocation_state_from_json);
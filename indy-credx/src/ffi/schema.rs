@@ -1,10 +1,12 @@
 use std::os::raw::c_char;
+use std::ptr;
 
-use ffi_support::{rust_string_to_c, FfiStr};
+use ffi_support::FfiStr;
 use indy_utils::Qualifiable;
 
+use super::alloc::alloc_string;
 use super::error::{catch_error, ErrorCode};
-use super::object::{IndyObjectId, ObjectHandle};
+use super::object::{copy_by_clone, IndyObjectId, ObjectHandle};
 use super::util::FfiStrList;
 use crate::services::{
     issuer::create_schema,
@@ -60,17 +62,28 @@ pub extern "C" fn credx_schema_get_attribute(
     catch_error(|| {
         check_useful_c_ptr!(result_p);
         let schema = handle.load()?;
-        let schema = schema.cast_ref::<Schema>()?;
+        let Schema::SchemaV1(schema) = schema.cast_ref::<Schema>()?;
         let val = match name.as_opt_str().unwrap_or_default() {
-            "id" => schema.get_id().to_string(),
+            "id" => alloc_string(schema.id.to_string()),
+            "name" => alloc_string(schema.name.clone()),
+            "version" => alloc_string(schema.version.clone()),
+            "attr_names" => {
+                let mut names: Vec<&String> = schema.attr_names.0.iter().collect();
+                names.sort();
+                alloc_string(serde_json::to_string(&names)?)
+            }
+            "seq_no" => schema
+                .seq_no
+                .map(|s| alloc_string(s.to_string()))
+                .unwrap_or(ptr::null_mut()),
             s => return Err(err_msg!("Unsupported attribute: {}", s)),
         };
-        unsafe { *result_p = rust_string_to_c(val) };
+        unsafe { *result_p = val };
         Ok(())
     })
 }
 
-impl_indy_object!(Schema, "Schema");
+impl_indy_object!(Schema, "Schema", copy_by_clone);
 impl_indy_object_from_json!(Schema, credx_schema_from_json);
 
 impl IndyObjectId for Schema {
@@ -0,0 +1,165 @@
+use std::convert::TryInto;
+use std::sync::Mutex;
+
+use ffi_support::FfiStr;
+
+use super::error::{catch_error, ErrorCode};
+use super::object::{IndyObject, ObjectHandle};
+use super::util::FfiList;
+use crate::error::Result;
+use crate::services::{
+    prover::credential_matches_referent,
+    types::{Credential, PresentationRequest},
+};
+
+/// An incremental search for the credentials matching a referent of a
+/// presentation request. The credentials are loaded when the search is
+/// opened, so they remain available to the search if their handles are
+/// freed.
+#[derive(Debug)]
+pub(crate) struct CredentialSearch {
+    pres_req: IndyObject,
+    referent: String,
+    credentials: Vec<(ObjectHandle, IndyObject)>,
+    position: Mutex<usize>,
+}
+
+impl CredentialSearch {
+    fn lock_position(&self) -> Result<std::sync::MutexGuard<'_, usize>> {
+        self.position
+            .lock()
+            .map_err(|_| err_msg!(Unexpected, "Error locking credential search"))
+    }
+
+    /// Find up to `limit` further matching credentials
+    fn fetch(&self, limit: usize) -> Result<Vec<ObjectHandle>> {
+        let pres_req = self.pres_req.cast_ref::<PresentationRequest>()?;
+        let mut position = self.lock_position()?;
+        let mut found = Vec::new();
+        while found.len() < limit && *position < self.credentials.len() {
+            let (handle, credential) = &self.credentials[*position];
+            *position += 1;
+            if credential_matches_referent(
+                pres_req,
+                &self.referent,
+                credential.cast_ref::<Credential>()?,
+            )? {
+                found.push(*handle);
+            }
+        }
+        Ok(found)
+    }
+}
+
+impl serde::Serialize for CredentialSearch {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::{Error, SerializeStruct};
+        let position = *self.lock_position().map_err(S::Error::custom)?;
+        let mut state = serializer.serialize_struct("CredentialSearch", 3)?;
+        state.serialize_field("referent", &self.referent)?;
+        state.serialize_field("count", &self.credentials.len())?;
+        state.serialize_field("position", &position)?;
+        state.end()
+    }
+}
+
+// A copy continues from the same position independently
+impl_indy_object!(
+    CredentialSearch,
+    "CredentialSearch",
+    |search| {
+        Ok(CredentialSearch {
+            pres_req: search.pres_req.clone(),
+            referent: search.referent.clone(),
+            credentials: search.credentials.clone(),
+            position: Mutex::new(*search.lock_position()?),
+        })
+    },
+    mutable
+);
+
+/// Open a search over a set of credentials for those which may be used for
+/// an attribute or predicate referent of a presentation request
+#[no_mangle]
+pub extern "C" fn credx_credential_search_open(
+    pres_req: ObjectHandle,
+    referent: FfiStr,
+    credentials: FfiList<ObjectHandle>,
+    search_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(search_p);
+        let referent = referent
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing referent"))?;
+        let pres_req = pres_req.load()?;
+        let pres_req_val = pres_req.cast_ref::<PresentationRequest>()?.value();
+        if !pres_req_val.requested_attributes.contains_key(referent)
+            && !pres_req_val.requested_predicates.contains_key(referent)
+            && !pres_req_val
+                .requested_range_disclosures
+                .contains_key(referent)
+        {
+            return Err(err_msg!(
+                "Referent not found in presentation request: {}",
+                referent
+            ));
+        }
+        let credentials = credentials.try_collect(|handle| {
+            let credential = handle.load()?;
+            credential.cast_ref::<Credential>()?;
+            Ok((*handle, credential))
+        })?;
+        let search = ObjectHandle::create(CredentialSearch {
+            pres_req,
+            referent: referent.to_string(),
+            credentials,
+            position: Mutex::new(0),
+        })?;
+        unsafe { *search_p = search };
+        Ok(())
+    })
+}
+
+/// Fetch up to `limit` further matching credentials from a search, writing
+/// their handles to `handles_p` and the number found to `count_p`. The
+/// handles are those passed when opening the search. A count of zero
+/// indicates that the search is complete.
+#[no_mangle]
+pub extern "C" fn credx_credential_search_fetch(
+    search: ObjectHandle,
+    limit: i64,
+    handles_p: *mut ObjectHandle,
+    count_p: *mut i64,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(count_p);
+        let limit: usize = limit.try_into().map_err(|_| err_msg!("Invalid limit"))?;
+        if limit > 0 {
+            check_useful_c_ptr!(handles_p);
+        }
+        let found = search
+            .load()?
+            .cast_ref::<CredentialSearch>()?
+            .fetch(limit)?;
+        if !found.is_empty() {
+            let output = unsafe { std::slice::from_raw_parts_mut(handles_p, found.len()) };
+            output.copy_from_slice(&found);
+        }
+        unsafe { *count_p = found.len() as i64 };
+        Ok(())
+    })
+}
+
+/// Close a search, releasing its references to the credentials
+#[no_mangle]
+pub extern "C" fn credx_credential_search_free(search: ObjectHandle) -> ErrorCode {
+    catch_error(|| {
+        search.load()?.cast_ref::<CredentialSearch>()?;
+        search.remove()?;
+        Ok(())
+    })
+}
@@ -0,0 +1,24 @@
+use std::os::raw::c_char;
+
+use super::alloc::alloc_string;
+use super::error::{catch_error, ErrorCode};
+use crate::services::stats::{operation_stats, reset_operation_stats};
+
+/// Report the call count and the total, average and maximum durations in
+/// milliseconds of each operation called since the library was loaded or
+/// the statistics were reset, as a JSON object keyed by operation name
+#[no_mangle]
+pub extern "C" fn credx_get_operation_stats(stats_p: *mut *const c_char) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(stats_p);
+        let stats = serde_json::to_string(&operation_stats()?)?;
+        unsafe { *stats_p = alloc_string(stats) };
+        Ok(())
+    })
+}
+
+/// Discard the operation statistics collected so far
+#[no_mangle]
+pub extern "C" fn credx_reset_operation_stats() -> ErrorCode {
+    catch_error(reset_operation_stats)
+}
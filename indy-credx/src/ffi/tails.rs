@@ -0,0 +1,116 @@
+use std::os::raw::c_char;
+
+use ffi_support::{ByteBuffer, FfiStr};
+
+use super::alloc::alloc_string;
+use super::error::{catch_error, ErrorCode};
+use crate::error::{ErrorKind, Result};
+use crate::services::tails::{TailsBufferReader, TailsFileReader, TailsReader};
+#[cfg(feature = "tails_encryption")]
+use crate::services::tails_encryption::{encrypt_tails_file, TailsKey};
+
+fn verify_tails(
+    reader: TailsReader,
+    expected_hash: FfiStr,
+    valid_p: *mut i8,
+    hash_p: *mut *const c_char,
+) -> Result<()> {
+    check_useful_c_ptr!(valid_p);
+    check_useful_c_ptr!(hash_p);
+    let expected_hash = expected_hash
+        .as_opt_str()
+        .ok_or_else(|| err_msg!("Missing expected tails hash"))?;
+    let hash = reader.hash()?;
+    let valid = match reader.verify_hash(expected_hash) {
+        Ok(()) => true,
+        Err(err) if err.kind() == ErrorKind::InvalidState => false,
+        Err(err) => return Err(err),
+    };
+    unsafe {
+        *valid_p = valid as i8;
+        *hash_p = alloc_string(hash);
+    }
+    Ok(())
+}
+
+/// Verify a tails file against the tails hash of a registry definition. The
+/// recomputed hash is written to `hash_p`, and `valid_p` is set to zero if
+/// the hash does not match or a part of a chunked tails file is corrupt.
+#[no_mangle]
+pub extern "C" fn credx_tails_verify(
+    tails_path: FfiStr,
+    expected_hash: FfiStr,
+    valid_p: *mut i8,
+    hash_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        let tails_path = tails_path
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing tails file path"))?;
+        verify_tails(
+            TailsFileReader::new(tails_path),
+            expected_hash,
+            valid_p,
+            hash_p,
+        )
+    })
+}
+
+/// Verify a contiguous tails file held in memory against the tails hash of a
+/// registry definition, as for `credx_tails_verify`
+#[no_mangle]
+pub extern "C" fn credx_tails_verify_buffer(
+    tails: ByteBuffer,
+    expected_hash: FfiStr,
+    valid_p: *mut i8,
+    hash_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        verify_tails(
+            TailsBufferReader::new(tails.as_slice().to_vec()),
+            expected_hash,
+            valid_p,
+            hash_p,
+        )
+    })
+}
+
+/// Set the key used to decrypt encrypted tails files, or clear it when the
+/// key is empty
+#[cfg(feature = "tails_encryption")]
+#[no_mangle]
+pub extern "C" fn credx_set_tails_encryption_key(key: ByteBuffer) -> ErrorCode {
+    catch_error(|| {
+        let key = match key.as_slice() {
+            [] => None,
+            key => Some(TailsKey::new(key)?),
+        };
+        debug!("Set tails encryption key: {}", key.is_some());
+        crate::config::update_config(|config| config.tails_key(key))
+    })
+}
+
+/// Encrypt a contiguous tails file with a 32-byte key, writing the tails
+/// hash of the plaintext to `hash_p`
+#[cfg(feature = "tails_encryption")]
+#[no_mangle]
+pub extern "C" fn credx_encrypt_tails_file(
+    source_path: FfiStr,
+    dest_path: FfiStr,
+    key: ByteBuffer,
+    hash_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(hash_p);
+        let source_path = source_path
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing source tails path"))?;
+        let dest_path = dest_path
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing destination tails path"))?;
+        let key = TailsKey::new(key.as_slice())?;
+        let hash = encrypt_tails_file(source_path, dest_path, &key)?;
+        unsafe { *hash_p = alloc_string(hash) };
+        Ok(())
+    })
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use ffi_support::FfiStr;
+use once_cell::sync::Lazy;
+
+use super::error::{catch_error, ErrorCode};
+use super::object::{IndyObjectList, ObjectHandle};
+use super::util::FfiList;
+use crate::services::{
+    templates::{merge_presentation_requests, ProofRequestTemplate, TemplateRegistry},
+    types::PresentationRequest,
+};
+
+static TEMPLATES: Lazy<RwLock<TemplateRegistry>> =
+    Lazy::new(|| RwLock::new(TemplateRegistry::new()));
+
+#[no_mangle]
+pub extern "C" fn credx_register_proof_request_template(
+    name: FfiStr,
+    template_json: FfiStr,
+) -> ErrorCode {
+    catch_error(|| {
+        let name = name
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing template name"))?;
+        let template = ProofRequestTemplate::from_json(
+            template_json
+                .as_opt_str()
+                .ok_or_else(|| err_msg!("Missing template JSON"))?,
+        )?;
+        TEMPLATES
+            .write()
+            .map_err(|_| err_msg!(Unexpected, "Error updating proof request templates"))?
+            .register(name, template);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn credx_remove_proof_request_template(name: FfiStr) -> ErrorCode {
+    catch_error(|| {
+        let name = name
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing template name"))?;
+        TEMPLATES
+            .write()
+            .map_err(|_| err_msg!(Unexpected, "Error updating proof request templates"))?
+            .remove(name)
+            .ok_or_else(|| err_msg!("Unknown proof request template: {}", name))?;
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn credx_create_presentation_request_from_template(
+    name: FfiStr,
+    values_json: FfiStr,
+    pres_req_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(pres_req_p);
+        let name = name
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing template name"))?;
+        let values: HashMap<String, serde_json::Value> = match values_json.as_opt_str() {
+            Some(json) => serde_json::from_str(json)?,
+            None => HashMap::new(),
+        };
+        let pres_req = TEMPLATES
+            .read()
+            .map_err(|_| err_msg!(Unexpected, "Error reading proof request templates"))?
+            .instantiate(name, &values)?;
+        let pres_req = ObjectHandle::create(pres_req)?;
+        unsafe { *pres_req_p = pres_req };
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn credx_merge_presentation_requests(
+    name: FfiStr,
+    version: FfiStr,
+    pres_reqs: FfiList<ObjectHandle>,
+    pres_req_p: *mut ObjectHandle,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(pres_req_p);
+        let name = name
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing presentation request name"))?;
+        let version = version
+            .as_opt_str()
+            .ok_or_else(|| err_msg!("Missing presentation request version"))?;
+        let pres_reqs = IndyObjectList::load(pres_reqs.as_slice())?;
+        let pres_req =
+            merge_presentation_requests(name, version, &pres_reqs.refs::<PresentationRequest>()?)?;
+        let pres_req = ObjectHandle::create(pres_req)?;
+        unsafe { *pres_req_p = pres_req };
+        Ok(())
+    })
+}
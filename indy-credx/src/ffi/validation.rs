@@ -0,0 +1,86 @@
+use std::os::raw::c_char;
+
+use super::alloc::alloc_string;
+use super::error::{catch_error, ErrorCode};
+use super::object::{IndyObject, ObjectHandle};
+use crate::error::Result;
+use crate::services::{
+    types::{
+        Credential, CredentialDefinition, CredentialOffer, CredentialRequest,
+        CredentialRequestMetadata, CredentialRevocationState, Presentation, PresentationRequest,
+        RevocationRegistryDefinition, RevocationRegistryDelta, Schema,
+    },
+    validation::{
+        validate_credential, validate_credential_definition, validate_object,
+        validate_revocation_registry_definition, validate_schema, ValidationProblem,
+    },
+};
+
+fn validate(obj: &IndyObject) -> Result<Vec<ValidationProblem>> {
+    Ok(match obj.type_name() {
+        "Schema" => validate_schema(obj.cast_ref::<Schema>()?),
+        "CredentialDefinition" => {
+            validate_credential_definition(obj.cast_ref::<CredentialDefinition>()?, None)?
+        }
+        "RevocationRegistryDefinition" => {
+            validate_revocation_registry_definition(obj.cast_ref::<RevocationRegistryDefinition>()?)
+        }
+        "Credential" => validate_credential(obj.cast_ref::<Credential>()?)?,
+        "CredentialOffer" => validate_object(obj.cast_ref::<CredentialOffer>()?),
+        "CredentialRequest" => validate_object(obj.cast_ref::<CredentialRequest>()?),
+        "CredentialRequestMetadata" => {
+            validate_object(obj.cast_ref::<CredentialRequestMetadata>()?)
+        }
+        "CredentialRevocationState" => {
+            validate_object(obj.cast_ref::<CredentialRevocationState>()?)
+        }
+        "Presentation" => validate_object(obj.cast_ref::<Presentation>()?),
+        "PresentationRequest" => validate_object(obj.cast_ref::<PresentationRequest>()?),
+        "RevocationRegistryDelta" => validate_object(obj.cast_ref::<RevocationRegistryDelta>()?),
+        other => {
+            return Err(err_msg!(
+                "Validation is not supported for {} objects",
+                other
+            ))
+        }
+    })
+}
+
+/// Check an object for structural and semantic problems. The report is a
+/// JSON list of problems, each with a `message` and an optional `field`,
+/// which is empty when no problems are found.
+#[no_mangle]
+pub extern "C" fn credx_object_validate(
+    handle: ObjectHandle,
+    report_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(report_p);
+        let problems = validate(&handle.load()?)?;
+        let report = serde_json::to_string(&problems)?;
+        unsafe { *report_p = alloc_string(report) };
+        Ok(())
+    })
+}
+
+/// Check a credential definition as for `credx_object_validate`, and check
+/// that it matches the schema it was created from
+#[no_mangle]
+pub extern "C" fn credx_credential_definition_validate(
+    cred_def: ObjectHandle,
+    schema: ObjectHandle,
+    report_p: *mut *const c_char,
+) -> ErrorCode {
+    catch_error(|| {
+        check_useful_c_ptr!(report_p);
+        let cred_def = cred_def.load()?;
+        let schema = schema.load()?;
+        let problems = validate_credential_definition(
+            cred_def.cast_ref::<CredentialDefinition>()?,
+            Some(schema.cast_ref::<Schema>()?),
+        )?;
+        let report = serde_json::to_string(&problems)?;
+        unsafe { *report_p = alloc_string(report) };
+        Ok(())
+    })
+}
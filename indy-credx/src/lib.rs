@@ -16,6 +16,8 @@ mod error;
 pub use self::error::Result;
 pub use self::error::{Error, ErrorKind};
 
+pub mod config;
+
 mod services;
 pub use services::*;
 
@@ -21,3 +21,11 @@ macro_rules! secret {
         "_"
     }};
 }
+
+/// Record the duration of the enclosing block in the operation statistics
+macro_rules! time_operation {
+    ($name:expr) => {
+        #[cfg(feature = "stats")]
+        let _timer = $crate::services::stats::OperationTimer::start($name);
+    };
+}
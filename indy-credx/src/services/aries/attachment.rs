@@ -0,0 +1,168 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::error::Result;
+use crate::services::types::{
+    Credential, CredentialOffer, CredentialRequest, Presentation, PresentationRequest,
+};
+use indy_utils::base64;
+
+pub const CRED_ABSTRACT_FORMAT: &str = "hlindy/cred-abstract@v2.0";
+pub const CRED_REQUEST_FORMAT: &str = "hlindy/cred-req@v2.0";
+pub const CRED_FORMAT: &str = "hlindy/cred@v2.0";
+pub const PROOF_REQUEST_FORMAT: &str = "hlindy/proof-req@v2.0";
+pub const PROOF_FORMAT: &str = "hlindy/proof@v2.0";
+
+const JSON_MIME_TYPE: &str = "application/json";
+
+/// An object with a registered Aries attachment format identifier
+pub trait AttachmentFormat: Serialize + DeserializeOwned {
+    const FORMAT: &'static str;
+}
+
+impl AttachmentFormat for CredentialOffer {
+    const FORMAT: &'static str = CRED_ABSTRACT_FORMAT;
+}
+
+impl AttachmentFormat for CredentialRequest {
+    const FORMAT: &'static str = CRED_REQUEST_FORMAT;
+}
+
+impl AttachmentFormat for Credential {
+    const FORMAT: &'static str = CRED_FORMAT;
+}
+
+impl AttachmentFormat for PresentationRequest {
+    const FORMAT: &'static str = PROOF_REQUEST_FORMAT;
+}
+
+impl AttachmentFormat for Presentation {
+    const FORMAT: &'static str = PROOF_FORMAT;
+}
+
+/// An Aries message attachment (RFC 0017) carrying an encoded object
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attachment {
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(rename = "mime-type", default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    pub data: AttachmentData,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachmentData {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base64: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json: Option<Value>,
+}
+
+/// An entry of the `formats` list of a message, associating an attachment
+/// identifier with its format
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachmentFormatDescriptor {
+    pub attach_id: String,
+    pub format: String,
+}
+
+impl Attachment {
+    /// Wrap an object in a base64-encoded JSON attachment
+    pub fn encode<T: AttachmentFormat>(id: impl Into<String>, object: &T) -> Result<Self> {
+        Ok(Self {
+            id: id.into(),
+            mime_type: Some(JSON_MIME_TYPE.to_string()),
+            data: AttachmentData {
+                base64: Some(base64::encode(serde_json::to_vec(object)?)),
+                json: None,
+            },
+        })
+    }
+
+    /// Extract the object contained in the attachment, accepting either
+    /// base64 or inline JSON data
+    pub fn decode<T: AttachmentFormat>(&self) -> Result<T> {
+        match (&self.data.base64, &self.data.json) {
+            (Some(data), _) => {
+                let bytes = base64::decode(data)
+                    .or_else(|_| base64::decode_urlsafe(data))
+                    .map_err(err_map!("Invalid base64 attachment data"))?;
+                Ok(serde_json::from_slice(&bytes)?)
+            }
+            (None, Some(json)) => Ok(T::deserialize(json)?),
+            (None, None) => Err(err_msg!("Attachment {} contains no data", self.id)),
+        }
+    }
+
+    /// Create the format descriptor for an attachment of type `T`
+    pub fn format_descriptor<T: AttachmentFormat>(&self) -> AttachmentFormatDescriptor {
+        AttachmentFormatDescriptor {
+            attach_id: self.id.clone(),
+            format: T::FORMAT.to_string(),
+        }
+    }
+}
+
+/// Locate the attachment associated with `format` by the descriptors of a
+/// message
+pub fn find_attachment<'a>(
+    formats: &[AttachmentFormatDescriptor],
+    attachments: &'a [Attachment],
+    format: &str,
+) -> Option<&'a Attachment> {
+    formats
+        .iter()
+        .filter(|desc| desc.format == format)
+        .find_map(|desc| attachments.iter().find(|att| att.id == desc.attach_id))
+}
+
+/// Locate and decode the attachment of type `T` by the descriptors of a
+/// message
+pub fn decode_attachment<T: AttachmentFormat>(
+    formats: &[AttachmentFormatDescriptor],
+    attachments: &[Attachment],
+) -> Result<T> {
+    find_attachment(formats, attachments, T::FORMAT)
+        .ok_or_else(|| err_msg!("No attachment found for format: {}", T::FORMAT))?
+        .decode()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn attachment_round_trip() {
+        let pres_req: PresentationRequest = serde_json::from_value(json!({
+            "nonce": "1234",
+            "name": "proof",
+            "version": "1.0",
+            "requested_attributes": {"attr1": {"name": "name"}}
+        }))
+        .unwrap();
+
+        let attachment = Attachment::encode("libindy-request-presentation-0", &pres_req).unwrap();
+        let formats = vec![attachment.format_descriptor::<PresentationRequest>()];
+        assert_eq!(formats[0].format, PROOF_REQUEST_FORMAT);
+
+        let json = serde_json::to_value(&attachment).unwrap();
+        assert_eq!(json["@id"], "libindy-request-presentation-0");
+        assert_eq!(json["mime-type"], "application/json");
+
+        let attachments = vec![attachment];
+        let decoded: PresentationRequest = decode_attachment(&formats, &attachments).unwrap();
+        assert_eq!(decoded, pres_req);
+        assert!(decode_attachment::<Presentation>(&formats, &attachments).is_err());
+
+        let inline = Attachment {
+            id: "inline".to_string(),
+            mime_type: None,
+            data: AttachmentData {
+                base64: None,
+                json: Some(serde_json::to_value(&pres_req).unwrap()),
+            },
+        };
+        assert_eq!(inline.decode::<PresentationRequest>().unwrap(), pres_req);
+    }
+}
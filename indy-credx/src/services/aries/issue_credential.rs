@@ -0,0 +1,266 @@
+//! Message bodies for the Aries issue-credential 2.0 protocol (RFC 0453)
+
+use super::attachment::{decode_attachment, Attachment, AttachmentFormatDescriptor};
+use super::{new_message_id, Thread};
+use crate::error::Result;
+use crate::services::types::{Credential, CredentialOffer, CredentialRequest, CredentialValues};
+
+pub const OFFER_CREDENTIAL_TYPE: &str = "https://didcomm.org/issue-credential/2.0/offer-credential";
+pub const REQUEST_CREDENTIAL_TYPE: &str =
+    "https://didcomm.org/issue-credential/2.0/request-credential";
+pub const ISSUE_CREDENTIAL_TYPE: &str = "https://didcomm.org/issue-credential/2.0/issue-credential";
+pub const CREDENTIAL_PREVIEW_TYPE: &str =
+    "https://didcomm.org/issue-credential/2.0/credential-preview";
+
+const OFFER_ATTACH_ID: &str = "libindy-cred-offer-0";
+const REQUEST_ATTACH_ID: &str = "libindy-cred-request-0";
+const CREDENTIAL_ATTACH_ID: &str = "libindy-cred-0";
+
+/// The attribute values of a credential offered by an issuer
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CredentialPreview {
+    #[serde(rename = "@type")]
+    pub type_: String,
+    pub attributes: Vec<CredentialPreviewAttribute>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CredentialPreviewAttribute {
+    pub name: String,
+    #[serde(rename = "mime-type", default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    pub value: String,
+}
+
+impl CredentialPreview {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.push(CredentialPreviewAttribute {
+            name: name.into(),
+            mime_type: None,
+            value: value.into(),
+        });
+        self
+    }
+
+    pub fn attribute_with_mime_type(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+        mime_type: impl Into<String>,
+    ) -> Self {
+        self.attributes.push(CredentialPreviewAttribute {
+            name: name.into(),
+            mime_type: Some(mime_type.into()),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Build a preview from the raw values of a credential, ordered by name
+    pub fn from_values(values: &CredentialValues) -> Self {
+        let mut names: Vec<&String> = values.0.keys().collect();
+        names.sort();
+        names.into_iter().fold(Self::new(), |preview, name| {
+            preview.attribute(name.as_str(), values.0[name].raw.as_str())
+        })
+    }
+}
+
+impl Default for CredentialPreview {
+    fn default() -> Self {
+        Self {
+            type_: CREDENTIAL_PREVIEW_TYPE.to_string(),
+            attributes: vec![],
+        }
+    }
+}
+
+/// The body of an `offer-credential` message
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OfferCredential {
+    #[serde(rename = "@type")]
+    pub type_: String,
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub credential_preview: CredentialPreview,
+    pub formats: Vec<AttachmentFormatDescriptor>,
+    #[serde(rename = "offers~attach")]
+    pub offers_attach: Vec<Attachment>,
+    #[serde(rename = "~thread", default, skip_serializing_if = "Option::is_none")]
+    pub thread: Option<Thread>,
+}
+
+impl OfferCredential {
+    pub fn new(cred_offer: &CredentialOffer, preview: CredentialPreview) -> Result<Self> {
+        let attachment = Attachment::encode(OFFER_ATTACH_ID, cred_offer)?;
+        Ok(Self {
+            type_: OFFER_CREDENTIAL_TYPE.to_string(),
+            id: new_message_id(),
+            comment: None,
+            credential_preview: preview,
+            formats: vec![attachment.format_descriptor::<CredentialOffer>()],
+            offers_attach: vec![attachment],
+            thread: None,
+        })
+    }
+
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Set the thread when the offer responds to a `propose-credential` message
+    pub fn thread(mut self, thread: Thread) -> Self {
+        self.thread = Some(thread);
+        self
+    }
+
+    /// The identifier of the protocol thread started or continued by this message
+    pub fn thread_id(&self) -> &str {
+        self.thread
+            .as_ref()
+            .map(|t| t.thid.as_str())
+            .unwrap_or(&self.id)
+    }
+
+    pub fn credential_offer(&self) -> Result<CredentialOffer> {
+        decode_attachment(&self.formats, &self.offers_attach)
+    }
+}
+
+/// The body of a `request-credential` message
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestCredential {
+    #[serde(rename = "@type")]
+    pub type_: String,
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub formats: Vec<AttachmentFormatDescriptor>,
+    #[serde(rename = "requests~attach")]
+    pub requests_attach: Vec<Attachment>,
+    #[serde(rename = "~thread")]
+    pub thread: Thread,
+}
+
+impl RequestCredential {
+    pub fn new(cred_request: &CredentialRequest, thread_id: impl Into<String>) -> Result<Self> {
+        let attachment = Attachment::encode(REQUEST_ATTACH_ID, cred_request)?;
+        Ok(Self {
+            type_: REQUEST_CREDENTIAL_TYPE.to_string(),
+            id: new_message_id(),
+            comment: None,
+            formats: vec![attachment.format_descriptor::<CredentialRequest>()],
+            requests_attach: vec![attachment],
+            thread: Thread::new(thread_id),
+        })
+    }
+
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    pub fn credential_request(&self) -> Result<CredentialRequest> {
+        decode_attachment(&self.formats, &self.requests_attach)
+    }
+}
+
+/// The body of an `issue-credential` message
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssueCredential {
+    #[serde(rename = "@type")]
+    pub type_: String,
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub formats: Vec<AttachmentFormatDescriptor>,
+    #[serde(rename = "credentials~attach")]
+    pub credentials_attach: Vec<Attachment>,
+    #[serde(rename = "~thread")]
+    pub thread: Thread,
+}
+
+impl IssueCredential {
+    pub fn new(credential: &Credential, thread_id: impl Into<String>) -> Result<Self> {
+        let attachment = Attachment::encode(CREDENTIAL_ATTACH_ID, credential)?;
+        Ok(Self {
+            type_: ISSUE_CREDENTIAL_TYPE.to_string(),
+            id: new_message_id(),
+            comment: None,
+            formats: vec![attachment.format_descriptor::<Credential>()],
+            credentials_attach: vec![attachment],
+            thread: Thread::new(thread_id),
+        })
+    }
+
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    pub fn credential(&self) -> Result<Credential> {
+        decode_attachment(&self.formats, &self.credentials_attach)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_utils::{credential_values, CredDefFixture, HolderFixture};
+    use crate::services::{issuer, prover};
+
+    #[test]
+    fn issue_credential_messages_work() {
+        let fixture = CredDefFixture::with_attributes(vec!["name", "age"]).unwrap();
+        let cred_offer = issuer::create_credential_offer(
+            fixture.schema.id(),
+            &fixture.cred_def,
+            &fixture.key_proof,
+        )
+        .unwrap();
+
+        let values = credential_values(&[("name", "Alex"), ("age", "28")]).unwrap();
+        let preview = CredentialPreview::from_values(&values);
+        assert_eq!(preview.attributes[0].name, "age");
+
+        let offer = OfferCredential::new(&cred_offer, preview)
+            .unwrap()
+            .comment("gvt credential");
+        let json = serde_json::to_value(&offer).unwrap();
+        assert_eq!(json["@type"], OFFER_CREDENTIAL_TYPE);
+        assert_eq!(json["formats"][0]["format"], "hlindy/cred-abstract@v2.0");
+        assert_eq!(json["credential_preview"]["attributes"][1]["value"], "Alex");
+
+        let received: OfferCredential = serde_json::from_value(json).unwrap();
+        let received_offer = received.credential_offer().unwrap();
+        assert_eq!(received_offer.nonce, cred_offer.nonce);
+
+        let holder = HolderFixture::new().unwrap();
+        let (cred_request, _) = prover::create_credential_request(
+            &holder.did,
+            &fixture.cred_def,
+            &holder.master_secret,
+            "default",
+            &received_offer,
+        )
+        .unwrap();
+        let request = RequestCredential::new(&cred_request, received.thread_id()).unwrap();
+        assert_eq!(request.thread.thid, offer.id);
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["~thread"]["thid"], offer.id.as_str());
+        assert!(json["requests~attach"][0]["data"]["base64"].is_string());
+        assert_eq!(
+            request.credential_request().unwrap().nonce,
+            cred_request.nonce
+        );
+    }
+}
@@ -0,0 +1,42 @@
+//! Helpers for exchanging indy-credx objects within Aries protocol messages
+
+use rand::RngCore;
+
+pub mod attachment;
+pub mod issue_credential;
+pub mod present_proof;
+pub mod revocation_notification;
+
+/// A reference to the thread of a message which replies to another
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Thread {
+    pub thid: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pthid: Option<String>,
+}
+
+impl Thread {
+    pub fn new(thid: impl Into<String>) -> Self {
+        Self {
+            thid: thid.into(),
+            pthid: None,
+        }
+    }
+}
+
+/// Generate a random (version 4) UUID for use as a message identifier
+pub(crate) fn new_message_id() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
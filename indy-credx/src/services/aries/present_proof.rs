@@ -0,0 +1,147 @@
+//! Message bodies for the Aries present-proof 2.0 protocol (RFC 0454)
+
+use super::attachment::{decode_attachment, Attachment, AttachmentFormatDescriptor};
+use super::{new_message_id, Thread};
+use crate::error::Result;
+use crate::services::types::{Presentation, PresentationRequest};
+
+pub const REQUEST_PRESENTATION_TYPE: &str =
+    "https://didcomm.org/present-proof/2.0/request-presentation";
+pub const PRESENTATION_TYPE: &str = "https://didcomm.org/present-proof/2.0/presentation";
+
+const REQUEST_ATTACH_ID: &str = "libindy-request-presentation-0";
+const PRESENTATION_ATTACH_ID: &str = "libindy-presentation-0";
+
+/// The body of a `request-presentation` message
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestPresentation {
+    #[serde(rename = "@type")]
+    pub type_: String,
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub will_confirm: Option<bool>,
+    pub formats: Vec<AttachmentFormatDescriptor>,
+    #[serde(rename = "request_presentations~attach")]
+    pub request_presentations_attach: Vec<Attachment>,
+    #[serde(rename = "~thread", default, skip_serializing_if = "Option::is_none")]
+    pub thread: Option<Thread>,
+}
+
+impl RequestPresentation {
+    pub fn new(pres_req: &PresentationRequest) -> Result<Self> {
+        let attachment = Attachment::encode(REQUEST_ATTACH_ID, pres_req)?;
+        Ok(Self {
+            type_: REQUEST_PRESENTATION_TYPE.to_string(),
+            id: new_message_id(),
+            comment: None,
+            will_confirm: None,
+            formats: vec![attachment.format_descriptor::<PresentationRequest>()],
+            request_presentations_attach: vec![attachment],
+            thread: None,
+        })
+    }
+
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Ask the prover to wait for an acknowledgement of the presentation
+    pub fn will_confirm(mut self, will_confirm: bool) -> Self {
+        self.will_confirm = Some(will_confirm);
+        self
+    }
+
+    /// Set the thread when the request responds to a `propose-presentation` message
+    pub fn thread(mut self, thread: Thread) -> Self {
+        self.thread = Some(thread);
+        self
+    }
+
+    /// The identifier of the protocol thread started or continued by this message
+    pub fn thread_id(&self) -> &str {
+        self.thread
+            .as_ref()
+            .map(|t| t.thid.as_str())
+            .unwrap_or(&self.id)
+    }
+
+    pub fn presentation_request(&self) -> Result<PresentationRequest> {
+        decode_attachment(&self.formats, &self.request_presentations_attach)
+    }
+}
+
+/// The body of a `presentation` message
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresentationMessage {
+    #[serde(rename = "@type")]
+    pub type_: String,
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub formats: Vec<AttachmentFormatDescriptor>,
+    #[serde(rename = "presentations~attach")]
+    pub presentations_attach: Vec<Attachment>,
+    #[serde(rename = "~thread")]
+    pub thread: Thread,
+}
+
+impl PresentationMessage {
+    pub fn new(presentation: &Presentation, thread_id: impl Into<String>) -> Result<Self> {
+        let attachment = Attachment::encode(PRESENTATION_ATTACH_ID, presentation)?;
+        Ok(Self {
+            type_: PRESENTATION_TYPE.to_string(),
+            id: new_message_id(),
+            comment: None,
+            formats: vec![attachment.format_descriptor::<Presentation>()],
+            presentations_attach: vec![attachment],
+            thread: Thread::new(thread_id),
+        })
+    }
+
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    pub fn presentation(&self) -> Result<Presentation> {
+        decode_attachment(&self.formats, &self.presentations_attach)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn request_presentation_works() {
+        let pres_req: PresentationRequest = serde_json::from_value(json!({
+            "nonce": "1234",
+            "name": "proof",
+            "version": "1.0",
+            "requested_attributes": {"attr1": {"name": "name"}}
+        }))
+        .unwrap();
+
+        let request = RequestPresentation::new(&pres_req)
+            .unwrap()
+            .will_confirm(true);
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["@type"], REQUEST_PRESENTATION_TYPE);
+        assert_eq!(json["will_confirm"], true);
+        assert_eq!(json["formats"][0]["format"], "hlindy/proof-req@v2.0");
+        assert_eq!(
+            json["formats"][0]["attach_id"],
+            json["request_presentations~attach"][0]["@id"]
+        );
+
+        let received: RequestPresentation = serde_json::from_value(json).unwrap();
+        assert_eq!(received.thread_id(), request.id);
+        assert_eq!(received.presentation_request().unwrap(), pres_req);
+    }
+}
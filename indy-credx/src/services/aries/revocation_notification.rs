@@ -0,0 +1,159 @@
+//! Message bodies for the Aries revocation notification 2.0 protocol (RFC 0721)
+
+use std::collections::BTreeSet;
+
+use super::new_message_id;
+use crate::error::Result;
+use crate::services::types::{RevocationRegistryDefinition, RevocationRegistryId};
+
+pub const REVOKE_TYPE: &str = "https://didcomm.org/revocation_notification/2.0/revoke";
+
+/// The revocation format of credentials in indy revocation registries
+pub const INDY_REVOCATION_FORMAT: &str = "indy-anoncreds";
+
+/// The prefix of the thread identifiers of revocation notification 1.0
+const V1_THREAD_PREFIX: &str = "indy::";
+
+/// An event upon which the holder is asked to acknowledge a notification
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AckOn {
+    Receipt,
+    Outcome,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PleaseAck {
+    pub on: Vec<AckOn>,
+}
+
+/// The body of a `revoke` message, informing a holder that a credential was
+/// revoked
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationNotification {
+    #[serde(rename = "@type")]
+    pub type_: String,
+    #[serde(rename = "@id")]
+    pub id: String,
+    pub revocation_format: String,
+    pub credential_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(
+        rename = "~please_ack",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub please_ack: Option<PleaseAck>,
+}
+
+impl RevocationNotification {
+    /// Notify the revocation of the credential at an index of a revocation
+    /// registry
+    pub fn new(rev_reg_id: &RevocationRegistryId, cred_rev_idx: u32) -> Self {
+        Self {
+            type_: REVOKE_TYPE.to_string(),
+            id: new_message_id(),
+            revocation_format: INDY_REVOCATION_FORMAT.to_string(),
+            credential_id: credential_id(rev_reg_id, cred_rev_idx),
+            comment: None,
+            please_ack: None,
+        }
+    }
+
+    /// Notify the revocation of each credential revoked by an update of a
+    /// revocation registry, in the order of their indices
+    pub fn for_revoked(
+        rev_reg_def: &RevocationRegistryDefinition,
+        revoked: &BTreeSet<u32>,
+    ) -> Vec<Self> {
+        revoked
+            .iter()
+            .map(|cred_rev_idx| Self::new(rev_reg_def.id(), *cred_rev_idx))
+            .collect()
+    }
+
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Ask the holder to acknowledge the notification
+    pub fn please_ack(mut self, on: Vec<AckOn>) -> Self {
+        self.please_ack = Some(PleaseAck { on });
+        self
+    }
+
+    /// The revocation registry and the index of the revoked credential
+    pub fn credential(&self) -> Result<(RevocationRegistryId, u32)> {
+        if self.revocation_format != INDY_REVOCATION_FORMAT {
+            return Err(err_msg!(
+                "Unsupported revocation format: {}",
+                self.revocation_format
+            ));
+        }
+        parse_credential_id(&self.credential_id)
+    }
+}
+
+/// The identifier of a revoked credential: the revocation registry
+/// identifier and the revocation index, separated by `::`
+pub fn credential_id(rev_reg_id: &RevocationRegistryId, cred_rev_idx: u32) -> String {
+    format!("{}::{}", rev_reg_id, cred_rev_idx)
+}
+
+/// The thread identifier under which revocation notification 1.0 reported
+/// the revocation of a credential
+pub fn v1_thread_id(rev_reg_id: &RevocationRegistryId, cred_rev_idx: u32) -> String {
+    format!(
+        "{}{}",
+        V1_THREAD_PREFIX,
+        credential_id(rev_reg_id, cred_rev_idx)
+    )
+}
+
+/// Parse the identifier of a revoked credential, or the thread identifier
+/// of a revocation notification 1.0
+pub fn parse_credential_id(value: &str) -> Result<(RevocationRegistryId, u32)> {
+    let value = value.strip_prefix(V1_THREAD_PREFIX).unwrap_or(value);
+    value
+        .rsplit_once("::")
+        .filter(|(rev_reg_id, _)| !rev_reg_id.is_empty())
+        .and_then(|(rev_reg_id, idx)| {
+            idx.parse::<u32>()
+                .ok()
+                .map(|idx| (RevocationRegistryId::from(rev_reg_id.to_string()), idx))
+        })
+        .ok_or_else(|| err_msg!("Invalid revoked credential identifier: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const REV_REG_ID: &str =
+        "NcYxiDXkpYi6ov5FcYDi1e:4:NcYxiDXkpYi6ov5FcYDi1e:3:CL:1:tag:CL_ACCUM:default";
+
+    #[test]
+    fn revocation_notification_works() {
+        let rev_reg_id = RevocationRegistryId::from(REV_REG_ID.to_string());
+        let notification = RevocationNotification::new(&rev_reg_id, 7)
+            .comment("Credential has been revoked")
+            .please_ack(vec![AckOn::Receipt]);
+        let json = serde_json::to_value(&notification).unwrap();
+        assert_eq!(json["@type"], REVOKE_TYPE);
+        assert_eq!(json["revocation_format"], "indy-anoncreds");
+        assert_eq!(json["credential_id"], format!("{}::7", REV_REG_ID));
+        assert_eq!(json["~please_ack"], json!({"on": ["RECEIPT"]}));
+
+        let received: RevocationNotification = serde_json::from_value(json).unwrap();
+        assert_eq!(received.credential().unwrap(), (rev_reg_id.clone(), 7));
+        assert_eq!(
+            parse_credential_id(&v1_thread_id(&rev_reg_id, 7)).unwrap(),
+            (rev_reg_id, 7)
+        );
+        assert!(parse_credential_id(REV_REG_ID).is_err());
+        assert!(parse_credential_id("::7").is_err());
+    }
+}
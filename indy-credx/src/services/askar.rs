@@ -0,0 +1,344 @@
+//! Conversion of objects to and from the entries of an Aries Askar store.
+//!
+//! The categories and tags follow those used by the credx-based issuer and
+//! holder of ACA-Py, so that records may be shared with agents built on it.
+//! Entry values are the JSON serialization of each object. A [`StorageEntry`]
+//! maps field-for-field onto an Askar [`Entry`], with all tags stored as
+//! encrypted tags, and objects are stored and fetched through an open Askar
+//! [`Session`] with [`insert_object`], [`fetch_object`] and the related
+//! functions.
+//!
+//! No Askar storage backend is enabled by this crate. The SQLite and Postgres
+//! backends are built on sqlx, which requires `num-traits` 0.2.14 or later,
+//! while ursa 0.3.6 pins `num-traits` to exactly 0.2.11. Sessions are
+//! therefore opened by an application which provides a backend, or entries
+//! are passed across the FFI from another Askar library, and the session
+//! functions cannot be tested against a store here.
+
+use std::collections::BTreeMap;
+
+use aries_askar::{
+    entry::{Entry, EntryKind, EntryTag, TagFilter},
+    Session,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::prover::credential_tags;
+use super::types::{
+    Credential, CredentialDefinition, CredentialDefinitionPrivate, CredentialKeyCorrectnessProof,
+    MasterSecret, RevocationRegistry, RevocationRegistryDefinition,
+    RevocationRegistryDefinitionPrivate, Schema,
+};
+use crate::error::Result;
+
+pub const CATEGORY_SCHEMA: &str = "schema";
+pub const CATEGORY_CRED_DEF: &str = "credential_def";
+pub const CATEGORY_CRED_DEF_PRIVATE: &str = "credential_def_private";
+pub const CATEGORY_CRED_DEF_KEY_PROOF: &str = "credential_def_key_proof";
+pub const CATEGORY_REV_REG_DEF: &str = "revocation_reg_def";
+pub const CATEGORY_REV_REG_DEF_PRIVATE: &str = "revocation_reg_def_private";
+pub const CATEGORY_REV_REG: &str = "revocation_reg";
+pub const CATEGORY_CREDENTIAL: &str = "credential";
+pub const CATEGORY_MASTER_SECRET: &str = "master_secret";
+
+/// A record of an Askar store
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageEntry {
+    pub category: String,
+    pub name: String,
+    pub value: Vec<u8>,
+    pub tags: BTreeMap<String, String>,
+}
+
+/// An object which may be stored as an Askar entry
+pub trait StorageObject: Serialize + DeserializeOwned {
+    /// The category of the entries holding objects of this type
+    const CATEGORY: &'static str;
+
+    /// The tags used to search for the object
+    fn entry_tags(&self) -> Result<BTreeMap<String, String>> {
+        Ok(BTreeMap::new())
+    }
+
+    /// The conventional entry name, for objects with a ledger identifier
+    fn entry_name(&self) -> Option<String> {
+        None
+    }
+
+    /// Create an entry for the object. The name defaults to the ledger
+    /// identifier of the object, if it has one.
+    fn to_entry(&self, name: Option<&str>) -> Result<StorageEntry> {
+        let name = name
+            .map(str::to_string)
+            .or_else(|| self.entry_name())
+            .ok_or_else(|| err_msg!("An entry name is required for {}", Self::CATEGORY))?;
+        Ok(StorageEntry {
+            category: Self::CATEGORY.to_string(),
+            name,
+            value: serde_json::to_vec(self)?,
+            tags: self.entry_tags()?,
+        })
+    }
+
+    /// Load an object from an entry, checking its category
+    fn from_entry(entry: &StorageEntry) -> Result<Self> {
+        object_from_value(&entry.category, &entry.value)
+    }
+
+    /// Load an object from an Askar entry, checking its category
+    fn from_askar_entry(entry: &Entry) -> Result<Self> {
+        object_from_value(&entry.category, &entry.value)
+    }
+}
+
+fn object_from_value<T: StorageObject>(category: &str, value: &[u8]) -> Result<T> {
+    if category != T::CATEGORY {
+        return Err(err_msg!(
+            "Expected an entry of category {}, found: {}",
+            T::CATEGORY,
+            category
+        ));
+    }
+    Ok(serde_json::from_slice(value)?)
+}
+
+impl StorageEntry {
+    fn askar_tags(&self) -> Vec<EntryTag> {
+        self.tags
+            .iter()
+            .map(|(name, value)| EntryTag::Encrypted(name.clone(), value.clone()))
+            .collect()
+    }
+}
+
+impl From<StorageEntry> for Entry {
+    fn from(entry: StorageEntry) -> Self {
+        let tags = entry.askar_tags();
+        Entry::new(
+            EntryKind::Item,
+            entry.category,
+            entry.name,
+            entry.value,
+            tags,
+        )
+    }
+}
+
+impl From<&Entry> for StorageEntry {
+    fn from(entry: &Entry) -> Self {
+        Self {
+            category: entry.category.clone(),
+            name: entry.name.clone(),
+            value: entry.value.to_vec(),
+            tags: entry
+                .tags
+                .iter()
+                .map(|tag| (tag.name().to_string(), tag.value().to_string()))
+                .collect(),
+        }
+    }
+}
+
+/// Add an object to an Askar store, returning the entry name. The name
+/// defaults to the ledger identifier of the object, if it has one.
+pub async fn insert_object<T: StorageObject>(
+    session: &mut Session,
+    object: &T,
+    name: Option<&str>,
+) -> Result<String> {
+    let entry = object.to_entry(name)?;
+    session
+        .insert(
+            &entry.category,
+            &entry.name,
+            &entry.value,
+            Some(&entry.askar_tags()),
+            None,
+        )
+        .await?;
+    Ok(entry.name)
+}
+
+/// Replace the value and tags of a stored object
+pub async fn replace_object<T: StorageObject>(
+    session: &mut Session,
+    object: &T,
+    name: Option<&str>,
+) -> Result<()> {
+    let entry = object.to_entry(name)?;
+    session
+        .replace(
+            &entry.category,
+            &entry.name,
+            &entry.value,
+            Some(&entry.askar_tags()),
+            None,
+        )
+        .await?;
+    Ok(())
+}
+
+/// Fetch an object by its entry name
+pub async fn fetch_object<T: StorageObject>(
+    session: &mut Session,
+    name: &str,
+) -> Result<Option<T>> {
+    session
+        .fetch(T::CATEGORY, name, false)
+        .await?
+        .map(|entry| T::from_askar_entry(&entry))
+        .transpose()
+}
+
+/// Fetch the objects of a type whose tags match a filter, with their entry
+/// names
+pub async fn fetch_all_objects<T: StorageObject>(
+    session: &mut Session,
+    tag_filter: Option<TagFilter>,
+    limit: Option<i64>,
+) -> Result<Vec<(String, T)>> {
+    session
+        .fetch_all(Some(T::CATEGORY), tag_filter, limit, None, false, false)
+        .await?
+        .into_iter()
+        .map(|entry| Ok((entry.name.clone(), T::from_askar_entry(&entry)?)))
+        .collect()
+}
+
+/// Remove a stored object by its entry name
+pub async fn remove_object<T: StorageObject>(session: &mut Session, name: &str) -> Result<()> {
+    session.remove(T::CATEGORY, name).await?;
+    Ok(())
+}
+
+impl StorageObject for Schema {
+    const CATEGORY: &'static str = CATEGORY_SCHEMA;
+
+    fn entry_tags(&self) -> Result<BTreeMap<String, String>> {
+        let Schema::SchemaV1(schema) = self;
+        let mut tags = BTreeMap::new();
+        tags.insert("schema_id".to_string(), schema.id.0.clone());
+        if let Some((_, issuer_did, _, _)) = schema.id.parts() {
+            tags.insert("schema_issuer_did".to_string(), issuer_did.0);
+        }
+        tags.insert("schema_name".to_string(), schema.name.clone());
+        tags.insert("schema_version".to_string(), schema.version.clone());
+        Ok(tags)
+    }
+
+    fn entry_name(&self) -> Option<String> {
+        Some(self.id().0.clone())
+    }
+}
+
+impl StorageObject for CredentialDefinition {
+    const CATEGORY: &'static str = CATEGORY_CRED_DEF;
+
+    fn entry_tags(&self) -> Result<BTreeMap<String, String>> {
+        let CredentialDefinition::CredentialDefinitionV1(cred_def) = self;
+        let mut tags = BTreeMap::new();
+        tags.insert("cred_def_id".to_string(), cred_def.id.0.clone());
+        tags.insert("schema_id".to_string(), cred_def.schema_id.0.clone());
+        if let Some(issuer_did) = cred_def.id.issuer_did() {
+            tags.insert("issuer_did".to_string(), issuer_did.0);
+        }
+        tags.insert("tag".to_string(), cred_def.tag.clone());
+        Ok(tags)
+    }
+
+    fn entry_name(&self) -> Option<String> {
+        Some(self.id().0.clone())
+    }
+}
+
+impl StorageObject for RevocationRegistryDefinition {
+    const CATEGORY: &'static str = CATEGORY_REV_REG_DEF;
+
+    fn entry_tags(&self) -> Result<BTreeMap<String, String>> {
+        let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) = self;
+        let mut tags = BTreeMap::new();
+        tags.insert("rev_reg_id".to_string(), rev_reg_def.id.0.clone());
+        tags.insert("cred_def_id".to_string(), rev_reg_def.cred_def_id.0.clone());
+        Ok(tags)
+    }
+
+    fn entry_name(&self) -> Option<String> {
+        Some(self.id().0.clone())
+    }
+}
+
+impl StorageObject for Credential {
+    const CATEGORY: &'static str = CATEGORY_CREDENTIAL;
+
+    fn entry_tags(&self) -> Result<BTreeMap<String, String>> {
+        credential_tags(self)
+    }
+}
+
+impl StorageObject for CredentialDefinitionPrivate {
+    const CATEGORY: &'static str = CATEGORY_CRED_DEF_PRIVATE;
+}
+
+impl StorageObject for CredentialKeyCorrectnessProof {
+    const CATEGORY: &'static str = CATEGORY_CRED_DEF_KEY_PROOF;
+}
+
+impl StorageObject for RevocationRegistryDefinitionPrivate {
+    const CATEGORY: &'static str = CATEGORY_REV_REG_DEF_PRIVATE;
+}
+
+impl StorageObject for RevocationRegistry {
+    const CATEGORY: &'static str = CATEGORY_REV_REG;
+}
+
+impl StorageObject for MasterSecret {
+    const CATEGORY: &'static str = CATEGORY_MASTER_SECRET;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{issuer, types::*};
+
+    #[test]
+    fn entry_round_trip() {
+        let did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+        let schema =
+            issuer::create_schema(&did, "gvt", "1.0", vec!["name".to_string()].into(), None)
+                .unwrap();
+        let entry = schema.to_entry(None).unwrap();
+        assert_eq!(entry.category, "schema");
+        assert_eq!(entry.name, "NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0");
+        assert_eq!(entry.tags["schema_issuer_did"], "NcYxiDXkpYi6ov5FcYDi1e");
+        assert_eq!(entry.tags["schema_name"], "gvt");
+        assert_eq!(Schema::from_entry(&entry).unwrap().id(), schema.id());
+        assert!(CredentialDefinition::from_entry(&entry).is_err());
+
+        let master_secret = crate::prover::create_master_secret().unwrap();
+        assert!(master_secret.to_entry(None).is_err());
+        let entry = master_secret.to_entry(Some("default")).unwrap();
+        assert_eq!(entry.category, "master_secret");
+        assert!(entry.tags.is_empty());
+        MasterSecret::from_entry(&entry).unwrap();
+    }
+
+    #[test]
+    fn askar_entry_round_trip() {
+        let did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+        let schema =
+            issuer::create_schema(&did, "gvt", "1.0", vec!["name".to_string()].into(), None)
+                .unwrap();
+        let entry = schema.to_entry(None).unwrap();
+        let askar_entry = Entry::from(entry.clone());
+        assert_eq!(askar_entry.category, "schema");
+        assert!(askar_entry
+            .tags
+            .contains(&EntryTag::Encrypted("schema_name".into(), "gvt".into())));
+        assert_eq!(StorageEntry::from(&askar_entry), entry);
+        assert_eq!(
+            Schema::from_askar_entry(&askar_entry).unwrap().id(),
+            schema.id()
+        );
+        assert!(CredentialDefinition::from_askar_entry(&askar_entry).is_err());
+    }
+}
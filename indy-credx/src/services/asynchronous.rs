@@ -0,0 +1,366 @@
+//! Async variants of the expensive issuer, prover and verifier operations.
+//!
+//! Each operation runs on a [`BlockingExecutor`], so that the cryptography
+//! does not stall the threads of the async runtime driving the caller. The
+//! executor is set in the library configuration, and by default each task
+//! runs on a new thread. Runtimes with a pool for blocking work, such as
+//! `tokio::task::spawn_blocking`, may be used by implementing the trait.
+//!
+//! The inputs of the tasks are moved to the executor, so they are taken by
+//! value, with the types which do not implement `Clone` shared through an
+//! `Arc`. Other operations may be run with [`spawn_blocking`].
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use super::resolver::ObjectResolver;
+use super::types::*;
+use super::verifier::ResolvedObjects;
+use super::{issuer, prover};
+use crate::config::get_config;
+use crate::error::Result;
+
+/// A task to be run away from the async runtime
+pub type BlockingJob = Box<dyn FnOnce() + Send + 'static>;
+
+/// Runs tasks which block the calling thread
+pub trait BlockingExecutor: Debug + Send + Sync {
+    fn spawn_blocking(&self, job: BlockingJob);
+}
+
+/// Runs each task on a new thread
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ThreadExecutor;
+
+impl BlockingExecutor for ThreadExecutor {
+    fn spawn_blocking(&self, job: BlockingJob) {
+        let spawned = std::thread::Builder::new()
+            .name("credx-blocking".to_string())
+            .spawn(job);
+        if let Err(err) = spawned {
+            // the job is dropped along with its completion, failing the task
+            warn!("Error spawning blocking task: {}", err);
+        }
+    }
+}
+
+/// A shared reference to a blocking executor implementation
+#[derive(Clone, Debug)]
+pub struct SharedBlockingExecutor(Arc<dyn BlockingExecutor>);
+
+impl SharedBlockingExecutor {
+    pub fn new(executor: impl BlockingExecutor + 'static) -> Self {
+        Self(Arc::new(executor))
+    }
+}
+
+impl BlockingExecutor for SharedBlockingExecutor {
+    fn spawn_blocking(&self, job: BlockingJob) {
+        self.0.spawn_blocking(job)
+    }
+}
+
+impl PartialEq for SharedBlockingExecutor {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedBlockingExecutor {}
+
+#[derive(Debug)]
+struct TaskState<T> {
+    result: Option<Result<T>>,
+    waker: Option<Waker>,
+}
+
+/// The result of a task run on the configured blocking executor
+#[derive(Debug)]
+pub struct BlockingTask<T> {
+    state: Arc<Mutex<TaskState<T>>>,
+}
+
+impl<T> Future for BlockingTask<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker.replace(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Delivers the result of a task, or an error if the task is dropped by the
+/// executor without being run
+struct Completion<T> {
+    state: Option<Arc<Mutex<TaskState<T>>>>,
+}
+
+impl<T> Completion<T> {
+    fn complete(&mut self, result: Result<T>) {
+        if let Some(state) = self.state.take() {
+            let waker = {
+                let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                state.result.replace(result);
+                state.waker.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<T> Drop for Completion<T> {
+    fn drop(&mut self) {
+        self.complete(Err(err_msg!(
+            Unexpected,
+            "Blocking task was dropped before completion"
+        )));
+    }
+}
+
+/// Run a task on the configured blocking executor. A panic in the task is
+/// reported as an `Unexpected` error.
+pub fn spawn_blocking<T, F>(task: F) -> BlockingTask<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let state = Arc::new(Mutex::new(TaskState {
+        result: None,
+        waker: None,
+    }));
+    let mut completion = Completion {
+        state: Some(state.clone()),
+    };
+    let job: BlockingJob = Box::new(move || {
+        let result = catch_unwind(AssertUnwindSafe(task))
+            .unwrap_or_else(|_| Err(err_msg!(Unexpected, "Panic in blocking task")));
+        completion.complete(result);
+    });
+    match get_config().blocking_executor.as_ref() {
+        Some(executor) => executor.spawn_blocking(job),
+        None => ThreadExecutor.spawn_blocking(job),
+    }
+    BlockingTask { state }
+}
+
+/// Create a credential definition and its private key, see
+/// [`issuer::create_credential_definition`]
+pub async fn create_credential_definition(
+    origin_did: DidValue,
+    schema: Schema,
+    tag: String,
+    signature_type: SignatureType,
+    config: CredentialDefinitionConfig,
+) -> Result<(
+    CredentialDefinition,
+    CredentialDefinitionPrivate,
+    CredentialKeyCorrectnessProof,
+)> {
+    spawn_blocking(move || {
+        issuer::create_credential_definition(&origin_did, &schema, &tag, signature_type, config)
+    })
+    .await
+}
+
+/// Issue a credential which does not support revocation, see
+/// [`issuer::create_credential`]
+pub async fn create_credential(
+    cred_def: Arc<CredentialDefinition>,
+    cred_def_private: Arc<CredentialDefinitionPrivate>,
+    cred_offer: CredentialOffer,
+    cred_request: CredentialRequest,
+    cred_values: CredentialValues,
+) -> Result<Credential> {
+    spawn_blocking(move || {
+        issuer::create_credential(
+            &cred_def,
+            &cred_def_private,
+            &cred_offer,
+            &cred_request,
+            cred_values,
+            None,
+        )
+        .map(|(credential, _, _)| credential)
+    })
+    .await
+}
+
+/// Create a credential request, see [`prover::create_credential_request`]
+pub async fn create_credential_request(
+    prover_did: DidValue,
+    cred_def: Arc<CredentialDefinition>,
+    master_secret: Arc<MasterSecret>,
+    master_secret_id: String,
+    cred_offer: CredentialOffer,
+) -> Result<(CredentialRequest, CredentialRequestMetadata)> {
+    spawn_blocking(move || {
+        prover::create_credential_request(
+            &prover_did,
+            &cred_def,
+            &master_secret,
+            &master_secret_id,
+            &cred_offer,
+        )
+    })
+    .await
+}
+
+/// Process a received credential, returning it once its signature has been
+/// checked and completed, see [`prover::process_credential`]
+pub async fn process_credential(
+    mut credential: Credential,
+    cred_request_metadata: CredentialRequestMetadata,
+    master_secret: Arc<MasterSecret>,
+    cred_def: Arc<CredentialDefinition>,
+    rev_reg_def: Option<Arc<RevocationRegistryDefinition>>,
+) -> Result<Credential> {
+    spawn_blocking(move || {
+        prover::process_credential(
+            &mut credential,
+            &cred_request_metadata,
+            &master_secret,
+            &cred_def,
+            rev_reg_def.as_deref(),
+        )?;
+        Ok(credential)
+    })
+    .await
+}
+
+/// Verify a presentation, fetching the objects it references from the
+/// resolver on the calling task before verifying it on the blocking
+/// executor, see [`verifier::verify_presentation_with_resolver`](super::verifier::verify_presentation_with_resolver)
+pub async fn verify_presentation(
+    presentation: Arc<Presentation>,
+    pres_req: Arc<PresentationRequest>,
+    resolver: &dyn ObjectResolver,
+    policy: VerificationPolicy,
+) -> Result<bool> {
+    let objects = ResolvedObjects::resolve(&presentation, resolver).await?;
+    spawn_blocking(move || objects.verify(&presentation, &pres_req, &policy)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::resolver::InMemoryResolver;
+
+    #[test]
+    fn async_issuance_and_verification() {
+        async_global_executor::block_on(async {
+            let did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+            let schema =
+                issuer::create_schema(&did, "gvt", "1.0", vec!["name".to_string()].into(), None)
+                    .unwrap();
+            let (cred_def, cred_def_private, key_proof) = create_credential_definition(
+                did.clone(),
+                schema.clone(),
+                "tag".to_string(),
+                SignatureType::CL,
+                CredentialDefinitionConfig::new(false),
+            )
+            .await
+            .unwrap();
+            let (cred_def, cred_def_private) = (Arc::new(cred_def), Arc::new(cred_def_private));
+            let master_secret = Arc::new(prover::create_master_secret().unwrap());
+            let offer =
+                issuer::create_credential_offer(schema.id(), &cred_def, &key_proof).unwrap();
+            let (request, metadata) = create_credential_request(
+                did.clone(),
+                cred_def.clone(),
+                master_secret.clone(),
+                "default".to_string(),
+                copy_offer(&offer),
+            )
+            .await
+            .unwrap();
+            let mut values = MakeCredentialValues::default();
+            values.add_raw("name", "Alex").unwrap();
+            let credential = create_credential(
+                cred_def.clone(),
+                cred_def_private,
+                offer,
+                request,
+                values.into(),
+            )
+            .await
+            .unwrap();
+            let credential = process_credential(
+                credential,
+                metadata,
+                master_secret.clone(),
+                cred_def.clone(),
+                None,
+            )
+            .await
+            .unwrap();
+
+            let pres_req: PresentationRequest = serde_json::from_value(serde_json::json!({
+                "nonce": "1234567890",
+                "name": "async",
+                "version": "1.0",
+                "requested_attributes": {"name": {"name": "name"}},
+                "requested_predicates": {}
+            }))
+            .unwrap();
+            let mut schemas = std::collections::HashMap::new();
+            schemas.insert(schema.id().clone(), &schema);
+            let mut cred_defs = std::collections::HashMap::new();
+            cred_defs.insert(cred_def.id().clone(), &*cred_def);
+            let mut present = PresentCredentials::default();
+            present
+                .add_credential(&credential, None, None)
+                .add_requested_attribute("name", true);
+            let presentation = prover::create_presentation(
+                &pres_req,
+                present,
+                None,
+                &master_secret,
+                &schemas,
+                &cred_defs,
+            )
+            .unwrap();
+
+            let mut resolver = InMemoryResolver::new();
+            resolver
+                .add_schema(schema.clone())
+                .add_cred_def(copy_cred_def(&cred_def));
+            assert!(verify_presentation(
+                Arc::new(presentation),
+                Arc::new(pres_req),
+                &resolver,
+                VerificationPolicy::default(),
+            )
+            .await
+            .unwrap());
+        });
+    }
+
+    #[test]
+    fn spawn_blocking_reports_panics() {
+        let result = async_global_executor::block_on(spawn_blocking::<(), _>(|| {
+            panic!("blocking task panic")
+        }));
+        assert_kind!(Unexpected, result);
+    }
+
+    fn copy_offer(offer: &CredentialOffer) -> CredentialOffer {
+        serde_json::from_value(serde_json::to_value(offer).unwrap()).unwrap()
+    }
+
+    fn copy_cred_def(cred_def: &CredentialDefinition) -> CredentialDefinition {
+        serde_json::from_value(serde_json::to_value(cred_def).unwrap()).unwrap()
+    }
+}
@@ -0,0 +1,183 @@
+//! Audit records of credential issuance and revocation.
+//!
+//! When an [`AuditSink`] is set in the library configuration, a record is
+//! passed to it for each credential issued and each change to the revocation
+//! status of a credential. The operation fails if the sink returns an error,
+//! so that no credential is issued or revoked without being recorded. Records
+//! contain a digest of the credential values rather than the values
+//! themselves.
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use indy_utils::base58;
+use sha2::{Digest, Sha256};
+
+use super::types::{
+    CredentialDefinitionId, CredentialValues, RevocationRegistryDefinition, RevocationRegistryId,
+    SchemaId,
+};
+use crate::error::Result;
+
+/// The kind of operation recorded
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// A credential was issued
+    Issued,
+    /// A credential was revoked
+    Revoked,
+    /// A revoked credential was restored to the registry
+    Restored,
+}
+
+/// A record of an operation on a credential
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub event: AuditEvent,
+    /// The time of the operation according to the library clock
+    pub timestamp: u64,
+    pub cred_def_id: CredentialDefinitionId,
+    /// The schema of the credential, which is only known on issuance
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema_id: Option<SchemaId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rev_reg_id: Option<RevocationRegistryId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rev_idx: Option<u32>,
+    /// The base58 SHA-256 digest of the credential values, on issuance
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values_hash: Option<String>,
+}
+
+impl AuditRecord {
+    /// The base58 SHA-256 digest of the JSON form of the record, for sinks
+    /// which chain records together
+    pub fn digest(&self) -> Result<String> {
+        Ok(base58::encode(Sha256::digest(&serde_json::to_vec(self)?)))
+    }
+}
+
+/// A destination for audit records
+pub trait AuditSink: Debug + Send + Sync {
+    fn record(&self, record: &AuditRecord) -> Result<()>;
+}
+
+/// A shared reference to an audit sink implementation
+#[derive(Clone, Debug)]
+pub struct SharedAuditSink(Arc<dyn AuditSink>);
+
+impl SharedAuditSink {
+    pub fn new(sink: impl AuditSink + 'static) -> Self {
+        Self(Arc::new(sink))
+    }
+}
+
+impl AuditSink for SharedAuditSink {
+    fn record(&self, record: &AuditRecord) -> Result<()> {
+        self.0.record(record)
+    }
+}
+
+impl PartialEq for SharedAuditSink {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedAuditSink {}
+
+/// Compute the digest of credential values over the attribute names, raw
+/// and encoded values in sorted order
+pub fn credential_values_hash(values: &CredentialValues) -> Result<String> {
+    let sorted: BTreeMap<&str, (&str, &str)> = values
+        .0
+        .iter()
+        .map(|(name, value)| (name.as_str(), (value.raw.as_str(), value.encoded.as_str())))
+        .collect();
+    Ok(base58::encode(Sha256::digest(&serde_json::to_vec(
+        &sorted,
+    )?)))
+}
+
+/// Pass a record to the configured audit sink, if any
+pub(crate) fn audit(record: impl FnOnce(u64) -> Result<AuditRecord>) -> Result<()> {
+    let config = crate::config::get_config();
+    if let Some(sink) = config.audit_sink.as_ref() {
+        sink.record(&record(config.now())?)?;
+    }
+    Ok(())
+}
+
+/// Record changes to the revocation status of credentials in a registry
+pub(crate) fn audit_revocation(
+    rev_reg_def: &RevocationRegistryDefinition,
+    event: AuditEvent,
+    indices: impl IntoIterator<Item = u32>,
+) -> Result<()> {
+    let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) = rev_reg_def;
+    for rev_idx in indices {
+        audit(|timestamp| {
+            Ok(AuditRecord {
+                event,
+                timestamp,
+                cred_def_id: rev_reg_def.cred_def_id.clone(),
+                schema_id: None,
+                rev_reg_id: Some(rev_reg_def.id.clone()),
+                rev_idx: Some(rev_idx),
+                values_hash: None,
+            })
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::config::update_config;
+    use crate::services::flows::issue_credential;
+    use crate::services::test_utils::{credential_values, CredDefFixture, HolderFixture};
+
+    #[derive(Debug, Default)]
+    struct CollectSink(Mutex<Vec<AuditRecord>>);
+
+    impl AuditSink for Arc<CollectSink> {
+        fn record(&self, record: &AuditRecord) -> Result<()> {
+            self.0.lock().unwrap().push(record.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn audit_issuance() {
+        let fixture = CredDefFixture::with_attributes(vec!["name"]).unwrap();
+        let holder = HolderFixture::new().unwrap();
+        let values = credential_values(&[("name", "Alex")]).unwrap();
+        let expected_hash = credential_values_hash(&values).unwrap();
+
+        let sink = Arc::new(CollectSink::default());
+        update_config(|config| config.audit_sink(Some(SharedAuditSink::new(sink.clone()))))
+            .unwrap();
+        let issued = issue_credential(fixture.issuer_objects(), holder.holder_objects(), values);
+        update_config(|config| config.audit_sink(None)).unwrap();
+        issued.unwrap();
+
+        let records: Vec<AuditRecord> = sink
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| record.cred_def_id == *fixture.cred_def.id())
+            .cloned()
+            .collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].event, AuditEvent::Issued);
+        assert_eq!(records[0].schema_id.as_ref(), Some(fixture.schema.id()));
+        assert_eq!(records[0].rev_idx, None);
+        assert_eq!(records[0].values_hash, Some(expected_hash));
+    }
+}
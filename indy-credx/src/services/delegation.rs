@@ -0,0 +1,383 @@
+//! Experimental support for delegation chains of credential issuers.
+//!
+//! A delegation credential is a CL credential whose subject is itself an
+//! issuer: besides any role attributes, it names the DID of the delegate and
+//! the credential definition the delegate issues under. The delegate proves
+//! possession of its delegation credential once, revealing these attributes
+//! in a presentation bound to its credential definition, and publishes the
+//! resulting link together with the links of its own delegator. A holder of
+//! a credential issued by the delegate passes the chain to the verifier, who
+//! checks each link back to a trusted root credential definition.
+//!
+//! The links are presentations of the delegates and do not involve the link
+//! secret of the holder. They identify the delegates, which as issuers are
+//! public entities, but reveal nothing about the holder.
+
+use std::collections::HashMap;
+
+use indy_utils::hash::{Digest, SHA256};
+use serde_json::json;
+
+use super::issuer;
+use super::prover::create_presentation;
+use super::types::{
+    AttributeNames, Credential, CredentialDefinition, CredentialDefinitionId, DidValue,
+    MakeCredentialValues, MasterSecret, PresentCredentials, Presentation, PresentationRequest,
+    Schema, SchemaId,
+};
+use super::verifier::verify_presentation;
+use crate::error::Result;
+
+/// The attribute naming the DID of the delegate
+pub const DELEGATE_DID_ATTRIBUTE: &str = "delegate_did";
+
+/// The attribute naming the credential definition of the delegate
+pub const DELEGATE_CRED_DEF_ATTRIBUTE: &str = "delegate_cred_def_id";
+
+/// The referent of the delegation attributes in a link presentation
+const DELEGATION_REFERENT: &str = "delegation";
+
+/// The domain separation tag of the nonce of a link presentation
+const LINK_NONCE_TAG: &[u8] = b"indy-credx/delegation-link-nonce/v1";
+
+/// A presentation of a delegation credential, by which a delegate proves
+/// that its credential definition was delegated by the issuer of the
+/// delegation credential
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DelegationLink {
+    pub delegate_cred_def_id: CredentialDefinitionId,
+    pub presentation: Presentation,
+}
+
+/// The links leading from a root credential definition to the credential
+/// definition of the last delegate
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DelegationChain {
+    /// The links in order of delegation, starting from the root
+    pub links: Vec<DelegationLink>,
+}
+
+impl DelegationChain {
+    /// The credential definition at the end of the chain, if any
+    pub fn delegate_cred_def_id(&self) -> Option<&CredentialDefinitionId> {
+        self.links.last().map(|link| &link.delegate_cred_def_id)
+    }
+}
+
+/// Create a schema for delegation credentials, adding the delegation
+/// attributes to any role attributes
+pub fn create_delegation_schema(
+    origin_did: &DidValue,
+    schema_name: &str,
+    schema_version: &str,
+    attr_names: AttributeNames,
+    seq_no: Option<u32>,
+) -> Result<Schema> {
+    let mut attr_names = attr_names;
+    attr_names.0.insert(DELEGATE_DID_ATTRIBUTE.to_string());
+    attr_names.0.insert(DELEGATE_CRED_DEF_ATTRIBUTE.to_string());
+    issuer::create_schema(origin_did, schema_name, schema_version, attr_names, seq_no)
+}
+
+/// Add the delegation attributes for a delegate to the values of a
+/// delegation credential
+pub fn add_delegation_values(
+    values: &mut MakeCredentialValues,
+    delegate_did: &DidValue,
+    delegate_cred_def_id: &CredentialDefinitionId,
+) -> Result<()> {
+    values.add_raw(DELEGATE_DID_ATTRIBUTE, delegate_did.to_string())?;
+    values.add_raw(
+        DELEGATE_CRED_DEF_ATTRIBUTE,
+        delegate_cred_def_id.to_string(),
+    )?;
+    Ok(())
+}
+
+/// Prove possession of a delegation credential, extending the chain of the
+/// delegator with a link to the credential definition of the delegate. The
+/// chain of a delegate of the root credential definition is empty.
+pub fn create_delegation_link(
+    chain: DelegationChain,
+    credential: &Credential,
+    master_secret: &MasterSecret,
+    schema: &Schema,
+    cred_def: &CredentialDefinition,
+) -> Result<DelegationChain> {
+    let delegate_cred_def_id = credential
+        .values
+        .0
+        .get(DELEGATE_CRED_DEF_ATTRIBUTE)
+        .map(|value| CredentialDefinitionId::from(value.raw.clone()))
+        .ok_or_else(|| err_msg!(Input, "Credential is not a delegation credential"))?;
+    if let Some(parent) = chain.delegate_cred_def_id() {
+        if *parent != credential.cred_def_id {
+            return Err(err_msg!(
+                Input,
+                "Delegation credential is not issued by the end of the chain"
+            ));
+        }
+    }
+
+    let pres_req = link_request(&credential.cred_def_id, &delegate_cred_def_id)?;
+    let mut present = PresentCredentials::default();
+    present
+        .add_credential(credential, None, None)
+        .add_requested_attribute(DELEGATION_REFERENT, true);
+    let schemas = HashMap::from([(credential.schema_id.clone(), schema)]);
+    let cred_defs = HashMap::from([(credential.cred_def_id.clone(), cred_def)]);
+    let presentation = create_presentation(
+        &pres_req,
+        present,
+        None,
+        master_secret,
+        &schemas,
+        &cred_defs,
+    )?;
+
+    let mut chain = chain;
+    chain.links.push(DelegationLink {
+        delegate_cred_def_id,
+        presentation,
+    });
+    Ok(chain)
+}
+
+/// Verify that a chain delegates from the root credential definition to the
+/// credential definition `cred_def_id`. The schemas and credential
+/// definitions of every link must be provided.
+pub fn verify_delegation_chain(
+    chain: &DelegationChain,
+    root_cred_def_id: &CredentialDefinitionId,
+    cred_def_id: &CredentialDefinitionId,
+    schemas: &HashMap<SchemaId, &Schema>,
+    cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+) -> Result<bool> {
+    if chain.delegate_cred_def_id() != Some(cred_def_id) {
+        return Ok(false);
+    }
+    let mut delegator = root_cred_def_id;
+    for link in chain.links.iter() {
+        if !verify_link(link, delegator, schemas, cred_defs)? {
+            return Ok(false);
+        }
+        delegator = &link.delegate_cred_def_id;
+    }
+    Ok(true)
+}
+
+/// Verify a presentation in which every credential is issued under the root
+/// credential definition or a credential definition delegated from it by one
+/// of the chains
+pub fn verify_delegated_presentation(
+    presentation: &Presentation,
+    pres_req: &PresentationRequest,
+    chains: &[DelegationChain],
+    root_cred_def_id: &CredentialDefinitionId,
+    schemas: &HashMap<SchemaId, &Schema>,
+    cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+) -> Result<bool> {
+    for identifier in presentation.identifiers.iter() {
+        if identifier.cred_def_id == *root_cred_def_id {
+            continue;
+        }
+        let chain = chains
+            .iter()
+            .find(|chain| chain.delegate_cred_def_id() == Some(&identifier.cred_def_id));
+        let valid = match chain {
+            Some(chain) => verify_delegation_chain(
+                chain,
+                root_cred_def_id,
+                &identifier.cred_def_id,
+                schemas,
+                cred_defs,
+            )?,
+            None => false,
+        };
+        if !valid {
+            trace!(
+                "No valid delegation chain for credential definition: {}",
+                identifier.cred_def_id
+            );
+            return Ok(false);
+        }
+    }
+    verify_presentation(presentation, pres_req, schemas, cred_defs, None, None)
+}
+
+fn verify_link(
+    link: &DelegationLink,
+    delegator: &CredentialDefinitionId,
+    schemas: &HashMap<SchemaId, &Schema>,
+    cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+) -> Result<bool> {
+    let presentation = &link.presentation;
+    match presentation.identifiers.as_slice() {
+        [identifier] if identifier.cred_def_id == *delegator => (),
+        _ => return Ok(false),
+    }
+    let revealed = match presentation
+        .requested_proof
+        .revealed_attr_groups
+        .get(DELEGATION_REFERENT)
+    {
+        Some(revealed) => &revealed.values,
+        None => return Ok(false),
+    };
+    let revealed_raw = |name: &str| revealed.get(name).map(|value| value.raw.as_str());
+    if revealed_raw(DELEGATE_CRED_DEF_ATTRIBUTE) != Some(&*link.delegate_cred_def_id) {
+        return Ok(false);
+    }
+    // the delegate must be the issuer of the delegated credential definition
+    let issuer_did = link.delegate_cred_def_id.issuer_did();
+    if issuer_did.as_ref().map(|did| did.to_string()).as_deref()
+        != revealed_raw(DELEGATE_DID_ATTRIBUTE)
+    {
+        return Ok(false);
+    }
+    let pres_req = link_request(delegator, &link.delegate_cred_def_id)?;
+    verify_presentation(presentation, &pres_req, schemas, cred_defs, None, None)
+}
+
+/// The presentation request of a link, with a nonce bound to the delegated
+/// credential definition
+fn link_request(
+    delegator: &CredentialDefinitionId,
+    delegate_cred_def_id: &CredentialDefinitionId,
+) -> Result<PresentationRequest> {
+    let mut hasher = SHA256::DigestType::new();
+    hasher.update(LINK_NONCE_TAG);
+    hasher.update(delegate_cred_def_id.as_bytes());
+    let digest = hasher.finalize();
+    let mut nonce = [0u8; 16];
+    nonce[6..].copy_from_slice(&digest[..10]);
+    Ok(serde_json::from_value(json!({
+        "nonce": u128::from_be_bytes(nonce).to_string(),
+        "name": "delegation",
+        "version": "1.0",
+        "requested_attributes": {
+            DELEGATION_REFERENT: {
+                "names": [DELEGATE_DID_ATTRIBUTE, DELEGATE_CRED_DEF_ATTRIBUTE],
+                "restrictions": {"cred_def_id": delegator},
+            }
+        },
+        "requested_predicates": {},
+    }))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::flows::issue_credential;
+    use crate::services::helpers::new_nonce;
+    use crate::services::test_utils::{CredDefFixture, HolderFixture};
+
+    #[test]
+    fn delegated_presentation_requires_chain() {
+        let root_did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+        let delegation_schema = create_delegation_schema(
+            &root_did,
+            "delegation",
+            "1.0",
+            vec!["role".to_string()].into(),
+            None,
+        )
+        .unwrap();
+        let root = CredDefFixture::new(root_did.clone(), delegation_schema, false).unwrap();
+        let delegate_did = DidValue::new("VsKV7grR1BUE29mG2Fm2kX", None);
+        let leaf_schema = issuer::create_schema(
+            &delegate_did,
+            "employee",
+            "1.0",
+            vec!["name".to_string()].into(),
+            None,
+        )
+        .unwrap();
+        let delegate = CredDefFixture::new(delegate_did, leaf_schema, false).unwrap();
+
+        // the root delegates to the issuer of the leaf credential definition
+        let delegate_holder = HolderFixture::new().unwrap();
+        let mut values = MakeCredentialValues::default();
+        values.add_raw("role", "hr").unwrap();
+        add_delegation_values(&mut values, &delegate.issuer_did, delegate.cred_def.id()).unwrap();
+        let delegation_cred = issue_credential(
+            root.issuer_objects(),
+            delegate_holder.holder_objects(),
+            values.into(),
+        )
+        .unwrap()
+        .credential;
+        let chain = create_delegation_link(
+            DelegationChain::default(),
+            &delegation_cred,
+            &delegate_holder.master_secret,
+            &root.schema,
+            &root.cred_def,
+        )
+        .unwrap();
+        assert_eq!(chain.delegate_cred_def_id(), Some(delegate.cred_def.id()));
+
+        let holder = HolderFixture::new().unwrap();
+        let credential = delegate.issue(&holder, &[("name", "Alex")]).unwrap();
+
+        let schemas = HashMap::from([
+            (root.schema.id().clone(), &root.schema),
+            (delegate.schema.id().clone(), &delegate.schema),
+        ]);
+        let cred_defs = HashMap::from([
+            (root.cred_def.id().clone(), &root.cred_def),
+            (delegate.cred_def.id().clone(), &delegate.cred_def),
+        ]);
+        let pres_req: PresentationRequest = serde_json::from_value(json!({
+            "nonce": new_nonce().unwrap(),
+            "name": "proof_req_1",
+            "version": "0.1",
+            "requested_attributes": {"name": {"name": "name"}},
+        }))
+        .unwrap();
+        let mut present = PresentCredentials::default();
+        present
+            .add_credential(&credential, None, None)
+            .add_requested_attribute("name", true);
+        let presentation = create_presentation(
+            &pres_req,
+            present,
+            None,
+            &holder.master_secret,
+            &schemas,
+            &cred_defs,
+        )
+        .unwrap();
+        let verify = |chains: &[DelegationChain], root_cred_def_id| {
+            verify_delegated_presentation(
+                &presentation,
+                &pres_req,
+                chains,
+                root_cred_def_id,
+                &schemas,
+                &cred_defs,
+            )
+            .unwrap()
+        };
+
+        let mut chains = vec![chain];
+        assert!(verify(&chains, root.cred_def.id()));
+        assert!(!verify(&[], root.cred_def.id()));
+        // a chain only proves delegation from its own root
+        let other_root =
+            CredentialDefinitionId::new(&root_did, &SchemaId("1".to_string()), "CL", "other");
+        assert!(!verify(&chains, &other_root));
+
+        // the delegation credential cannot be linked to another credential definition
+        let mut forged = chains.remove(0);
+        forged.links[0].delegate_cred_def_id = root.cred_def.id().clone();
+        assert!(!verify_delegation_chain(
+            &forged,
+            root.cred_def.id(),
+            root.cred_def.id(),
+            &schemas,
+            &cred_defs
+        )
+        .unwrap());
+    }
+}
@@ -0,0 +1,215 @@
+//! Encoders of raw attribute values, registered by schema and attribute name.
+//!
+//! An ecosystem may codify its encoding conventions, such as dates issued as
+//! `dateint` values, by registering encoders in the library configuration.
+//! Each rule applies to the attributes whose names match a `LIKE` pattern,
+//! either under a single schema or under any schema. Raw values added to
+//! `MakeCredentialValues`, the credentials created through the FFI and the
+//! encoding checks of received credentials all consult the registry, with
+//! the standard encoding applied to attributes matched by no rule.
+
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use super::helpers::{attr_common_view, encode_credential_attribute, like_matches, SignedRange};
+use super::types::SchemaId;
+use crate::config::get_config;
+use crate::error::Result;
+
+/// Computes the encoded value of a raw attribute value
+pub trait AttributeEncoder: Debug + Send + Sync {
+    fn encode(&self, raw: &str) -> Result<String>;
+}
+
+/// The standard encoding: 32-bit integers are encoded as themselves, other
+/// values as the decimal SHA-256 digest of the value
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StandardEncoder;
+
+impl AttributeEncoder for StandardEncoder {
+    fn encode(&self, raw: &str) -> Result<String> {
+        encode_credential_attribute(raw)
+    }
+}
+
+/// Encodes a calendar date, given as `YYYY-MM-DD` or `YYYYMMDD`, as the
+/// integer `YYYYMMDD`, so that dates may be compared by predicates
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DateIntEncoder;
+
+impl AttributeEncoder for DateIntEncoder {
+    fn encode(&self, raw: &str) -> Result<String> {
+        if !raw.is_ascii() {
+            return Err(err_msg!("Invalid date value: {}", raw));
+        }
+        let digits: String = match raw.len() {
+            10 if raw.as_bytes()[4] == b'-' && raw.as_bytes()[7] == b'-' => {
+                [&raw[0..4], &raw[5..7], &raw[8..10]].concat()
+            }
+            8 => raw.to_string(),
+            _ => return Err(err_msg!("Invalid date value: {}", raw)),
+        };
+        if !digits.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(err_msg!("Invalid date value: {}", raw));
+        }
+        let year: u32 = digits[0..4].parse().unwrap_or_default();
+        let month: u32 = digits[4..6].parse().unwrap_or_default();
+        let day: u32 = digits[6..8].parse().unwrap_or_default();
+        let leap =
+            year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+        let days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => 0,
+        };
+        if day == 0 || day > days {
+            return Err(err_msg!("Invalid date value: {}", raw));
+        }
+        Ok((year * 10000 + month * 100 + day).to_string())
+    }
+}
+
+/// Encodes an integer within the range as its offset from the minimum
+impl AttributeEncoder for SignedRange {
+    fn encode(&self, raw: &str) -> Result<String> {
+        let value = raw
+            .parse::<i64>()
+            .map_err(|_| err_msg!("Invalid integer value: {}", raw))?;
+        SignedRange::encode(self, value)
+    }
+}
+
+/// A shared reference to an attribute encoder implementation
+#[derive(Clone, Debug)]
+pub struct SharedAttributeEncoder(Arc<dyn AttributeEncoder>);
+
+impl SharedAttributeEncoder {
+    pub fn new(encoder: impl AttributeEncoder + 'static) -> Self {
+        Self(Arc::new(encoder))
+    }
+}
+
+impl AttributeEncoder for SharedAttributeEncoder {
+    fn encode(&self, raw: &str) -> Result<String> {
+        self.0.encode(raw)
+    }
+}
+
+impl PartialEq for SharedAttributeEncoder {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedAttributeEncoder {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct EncoderRule {
+    schema_id: Option<SchemaId>,
+    pattern: String,
+    encoder: SharedAttributeEncoder,
+}
+
+/// The attribute encoders registered by schema and attribute name
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EncoderRegistry {
+    rules: Vec<EncoderRule>,
+}
+
+impl EncoderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an encoder for the attributes matching a `LIKE` pattern,
+    /// where `%` matches any sequence of characters and `_` any single
+    /// character. Names are compared in lowercase without spaces. The rule
+    /// applies under the given schema, or under any schema when none is
+    /// given.
+    pub fn register(
+        mut self,
+        schema_id: Option<SchemaId>,
+        pattern: &str,
+        encoder: SharedAttributeEncoder,
+    ) -> Self {
+        self.rules.push(EncoderRule {
+            schema_id,
+            pattern: attr_common_view(pattern),
+            encoder,
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Find the encoder of an attribute. The rules of the schema take
+    /// precedence over the rules for any schema, and otherwise the earliest
+    /// registered rule applies.
+    pub fn encoder_for(
+        &self,
+        schema_id: Option<&SchemaId>,
+        name: &str,
+    ) -> Option<&SharedAttributeEncoder> {
+        let name = attr_common_view(name);
+        let matching = |rule: &&EncoderRule| like_matches(&rule.pattern, &name);
+        schema_id
+            .and_then(|schema_id| {
+                self.rules
+                    .iter()
+                    .filter(|rule| rule.schema_id.as_ref() == Some(schema_id))
+                    .find(matching)
+            })
+            .or_else(|| {
+                self.rules
+                    .iter()
+                    .filter(|rule| rule.schema_id.is_none())
+                    .find(matching)
+            })
+            .map(|rule| &rule.encoder)
+    }
+}
+
+/// Encode a raw attribute value with the encoder registered for the
+/// attribute, or with the standard encoding
+pub fn encode_attribute(schema_id: Option<&SchemaId>, name: &str, raw: &str) -> Result<String> {
+    let config = get_config();
+    match config.attribute_encoders.encoder_for(schema_id, name) {
+        Some(encoder) => encoder.encode(raw),
+        None => encode_credential_attribute(raw),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_precedence_and_dateint() {
+        let schema_id = SchemaId::from("NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0".to_string());
+        let other_id = SchemaId::from("NcYxiDXkpYi6ov5FcYDi1e:2:other:1.0".to_string());
+        let registry = EncoderRegistry::new()
+            .register(None, "%_date", SharedAttributeEncoder::new(DateIntEncoder))
+            .register(
+                Some(schema_id.clone()),
+                "birth%",
+                SharedAttributeEncoder::new(SignedRange::new(-100, 100).unwrap()),
+            );
+
+        let encoder = registry
+            .encoder_for(Some(&schema_id), "Birth Date")
+            .unwrap();
+        assert_eq!(encoder.encode("-100").unwrap(), "0");
+        let encoder = registry.encoder_for(Some(&other_id), "Birth Date").unwrap();
+        assert_eq!(encoder.encode("2000-02-29").unwrap(), "20000229");
+        assert!(registry.encoder_for(None, "name").is_none());
+
+        assert_eq!(DateIntEncoder.encode("19991231").unwrap(), "19991231");
+        assert!(DateIntEncoder.encode("2001-02-29").is_err());
+        assert!(DateIntEncoder.encode("2001-13-01").is_err());
+        assert!(DateIntEncoder.encode("Jan 1 2001").is_err());
+    }
+}
@@ -0,0 +1,228 @@
+//! Encryption of serialized objects to a recipient X25519 key as a JWE.
+//!
+//! Envelopes use the flattened JSON serialization with the `ECDH-ES+A256KW`
+//! key agreement algorithm and `A256GCM` content encryption, as used for
+//! anonymous encryption in Aries. The ephemeral sender key is carried in the
+//! protected header.
+
+use openssl::aes::{unwrap_key, wrap_key, AesKey};
+use openssl::derive::Deriver;
+use openssl::pkey::{Id, PKey, Private};
+use openssl::rand::rand_bytes;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::Result;
+use indy_utils::base64;
+
+const KEY_ALG: &str = "ECDH-ES+A256KW";
+const CONTENT_ENC: &str = "A256GCM";
+
+/// A JWE using the flattened JSON serialization
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JweEnvelope {
+    pub protected: String,
+    pub encrypted_key: String,
+    pub iv: String,
+    pub ciphertext: String,
+    pub tag: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ProtectedHeader {
+    alg: String,
+    enc: String,
+    epk: EphemeralKey,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EphemeralKey {
+    kty: String,
+    crv: String,
+    x: String,
+}
+
+/// Generate a new X25519 key pair, returning the raw (secret, public) keys
+pub fn generate_recipient_key() -> Result<(Vec<u8>, Vec<u8>)> {
+    let key = PKey::generate_x25519().map_err(err_map!(Unexpected))?;
+    Ok((
+        key.raw_private_key().map_err(err_map!(Unexpected))?,
+        key.raw_public_key().map_err(err_map!(Unexpected))?,
+    ))
+}
+
+/// Encrypt `plaintext` to the raw X25519 public key of the recipient
+pub fn encrypt(plaintext: &[u8], recipient_key: &[u8], kid: Option<&str>) -> Result<JweEnvelope> {
+    let recipient = PKey::public_key_from_raw_bytes(recipient_key, Id::X25519)
+        .map_err(err_map!("Invalid recipient key"))?;
+    let ephemeral = PKey::generate_x25519().map_err(err_map!(Unexpected))?;
+
+    let header = ProtectedHeader {
+        alg: KEY_ALG.to_string(),
+        enc: CONTENT_ENC.to_string(),
+        epk: EphemeralKey {
+            kty: "OKP".to_string(),
+            crv: "X25519".to_string(),
+            x: encode_b64(&ephemeral.raw_public_key().map_err(err_map!(Unexpected))?),
+        },
+        kid: kid.map(str::to_string),
+    };
+    let protected = encode_b64(&serde_json::to_vec(&header)?);
+
+    let kek = derive_kek(&ephemeral, &recipient)?;
+    let mut cek = [0u8; 32];
+    let mut iv = [0u8; 12];
+    rand_bytes(&mut cek).map_err(err_map!(Unexpected))?;
+    rand_bytes(&mut iv).map_err(err_map!(Unexpected))?;
+
+    let wrapping_key =
+        AesKey::new_encrypt(&kek).map_err(|_| err_msg!(Unexpected, "Invalid wrapping key"))?;
+    let mut encrypted_key = [0u8; 40];
+    wrap_key(&wrapping_key, None, &mut encrypted_key, &cek)
+        .map_err(|_| err_msg!(Unexpected, "Error wrapping content key"))?;
+
+    let mut tag = [0u8; 16];
+    let ciphertext = encrypt_aead(
+        Cipher::aes_256_gcm(),
+        &cek,
+        Some(&iv),
+        protected.as_bytes(),
+        plaintext,
+        &mut tag,
+    )
+    .map_err(err_map!(Unexpected, "Error encrypting payload"))?;
+
+    Ok(JweEnvelope {
+        protected,
+        encrypted_key: encode_b64(&encrypted_key),
+        iv: encode_b64(&iv),
+        ciphertext: encode_b64(&ciphertext),
+        tag: encode_b64(&tag),
+    })
+}
+
+/// Decrypt an envelope using the raw X25519 secret key of the recipient
+pub fn decrypt(envelope: &JweEnvelope, recipient_secret: &[u8]) -> Result<Vec<u8>> {
+    let header: ProtectedHeader = serde_json::from_slice(&decode_b64(&envelope.protected)?)?;
+    if header.alg != KEY_ALG || header.enc != CONTENT_ENC {
+        return Err(err_msg!(
+            "Unsupported envelope algorithm: {}/{}",
+            header.alg,
+            header.enc
+        ));
+    }
+    if header.epk.kty != "OKP" || header.epk.crv != "X25519" {
+        return Err(err_msg!("Unsupported ephemeral key type"));
+    }
+    let recipient = PKey::private_key_from_raw_bytes(recipient_secret, Id::X25519)
+        .map_err(err_map!("Invalid recipient key"))?;
+    let ephemeral = PKey::public_key_from_raw_bytes(&decode_b64(&header.epk.x)?, Id::X25519)
+        .map_err(err_map!("Invalid ephemeral key"))?;
+
+    let kek = derive_kek(&recipient, &ephemeral)?;
+    let unwrapping_key =
+        AesKey::new_decrypt(&kek).map_err(|_| err_msg!(Unexpected, "Invalid wrapping key"))?;
+    let encrypted_key = decode_b64(&envelope.encrypted_key)?;
+    if encrypted_key.len() != 40 {
+        return Err(err_msg!("Invalid encrypted key length"));
+    }
+    let mut cek = [0u8; 32];
+    unwrap_key(&unwrapping_key, None, &mut cek, &encrypted_key)
+        .map_err(|_| err_msg!("Error unwrapping content key"))?;
+
+    decrypt_aead(
+        Cipher::aes_256_gcm(),
+        &cek,
+        Some(&decode_b64(&envelope.iv)?),
+        envelope.protected.as_bytes(),
+        &decode_b64(&envelope.ciphertext)?,
+        &decode_b64(&envelope.tag)?,
+    )
+    .map_err(err_map!("Error decrypting payload"))
+}
+
+/// Serialize an object and encrypt it to the recipient
+pub fn seal<T: Serialize>(
+    object: &T,
+    recipient_key: &[u8],
+    kid: Option<&str>,
+) -> Result<JweEnvelope> {
+    encrypt(&serde_json::to_vec(object)?, recipient_key, kid)
+}
+
+/// Decrypt an envelope and deserialize the contained object
+pub fn open<T: DeserializeOwned>(envelope: &JweEnvelope, recipient_secret: &[u8]) -> Result<T> {
+    Ok(serde_json::from_slice(&decrypt(
+        envelope,
+        recipient_secret,
+    )?)?)
+}
+
+fn derive_kek<T>(private: &PKey<Private>, peer: &PKey<T>) -> Result<[u8; 32]>
+where
+    T: openssl::pkey::HasPublic,
+{
+    let mut deriver = Deriver::new(private).map_err(err_map!(Unexpected))?;
+    deriver.set_peer(peer).map_err(err_map!(Unexpected))?;
+    let shared = deriver.derive_to_vec().map_err(err_map!(Unexpected))?;
+
+    // Concat KDF (NIST SP 800-56A) with no party info, as specified by RFC 7518
+    let mut hasher = Sha256::new();
+    hasher.update(1u32.to_be_bytes());
+    hasher.update(&shared);
+    hasher.update((KEY_ALG.len() as u32).to_be_bytes());
+    hasher.update(KEY_ALG.as_bytes());
+    hasher.update(0u32.to_be_bytes());
+    hasher.update(0u32.to_be_bytes());
+    hasher.update(256u32.to_be_bytes());
+    Ok(hasher.finalize().into())
+}
+
+fn encode_b64(data: &[u8]) -> String {
+    base64::encode_urlsafe(data)
+        .trim_end_matches('=')
+        .to_string()
+}
+
+fn decode_b64(data: &str) -> Result<Vec<u8>> {
+    let mut padded = data.to_string();
+    match data.len() % 4 {
+        2 => padded.push_str("=="),
+        3 => padded.push('='),
+        _ => (),
+    }
+    base64::decode_urlsafe(padded).map_err(err_map!("Invalid base64 data"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{issuer, types::*};
+
+    #[test]
+    fn envelope_round_trip() {
+        let (secret, public) = generate_recipient_key().unwrap();
+        let schema = issuer::create_schema(
+            &DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None),
+            "gvt",
+            "1.0",
+            vec!["name".to_string()].into(),
+            None,
+        )
+        .unwrap();
+
+        let envelope = seal(&schema, &public, Some("recipient")).unwrap();
+        let opened: Schema = open(&envelope, &secret).unwrap();
+        assert_eq!(opened.id(), schema.id());
+
+        let (other_secret, _) = generate_recipient_key().unwrap();
+        assert!(open::<Schema>(&envelope, &other_secret).is_err());
+
+        let mut tampered = envelope;
+        tampered.ciphertext = encode_b64(b"tampered");
+        assert!(open::<Schema>(&tampered, &secret).is_err());
+    }
+}
@@ -0,0 +1,513 @@
+//! Verifiable encryption of hidden attribute values for designated auditors.
+//!
+//! A presentation request may ask for the value of an attribute, presented
+//! unrevealed, to be encrypted to the Paillier key of an auditor. The holder
+//! attaches the ciphertext with a proof that it encrypts the encoded value
+//! committed in the CL sub-proof: the proof reuses the blinding factor of the
+//! attribute and the challenge of the CL proof, and its commitment is bound to
+//! the challenge by deriving the nonce of the CL proof from the nonce of the
+//! request and the escrowed ciphertexts. The verifier checks the proof against
+//! the blinded value of the attribute without learning the value, which only
+//! the auditor holding the private key may decrypt.
+//!
+//! The CL library draws the blinding factors internally, so the holder first
+//! finalizes a proof for a random nonce to recover them. That proof is never
+//! disclosed, as together with the presentation it would reveal the hidden
+//! values. The auditor recovers the encoded value of the attribute: values
+//! other than 32-bit integers are encoded as digests, and may only be matched
+//! against known candidates.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use indy_data_types::anoncreds::{
+    nonce::Nonce, pres_request::PresentationRequestPayload, presentation::EscrowedAttributeInfo,
+    presentation::RequestedProof,
+};
+use indy_utils::hash::{Digest, SHA256};
+
+use super::helpers::attr_common_view;
+use crate::error::Result;
+use crate::ursa::bn::{BigNumber, BigNumberContext};
+use crate::ursa::cl::Proof;
+
+/// The domain separation tag of the nonce of a CL proof with escrows
+const ESCROW_NONCE_TAG: &[u8] = b"indy-credx/escrow-nonce/v1";
+
+/// The size of each prime factor of an auditor key
+const AUDITOR_PRIME_BITS: usize = 1024;
+
+/// The Paillier key pair of an auditor
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditorKey {
+    n: String,
+    p: String,
+    q: String,
+}
+
+impl fmt::Debug for AuditorKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuditorKey")
+            .field("n", &self.n)
+            .field("p", &"<hidden>")
+            .field("q", &"<hidden>")
+            .finish()
+    }
+}
+
+impl AuditorKey {
+    /// Generate a new key pair with a 2048-bit modulus
+    pub fn new() -> Result<Self> {
+        loop {
+            let p = BigNumber::generate_prime(AUDITOR_PRIME_BITS)?;
+            let q = BigNumber::generate_prime(AUDITOR_PRIME_BITS)?;
+            if p != q {
+                let n = p.mul(&q, None)?;
+                return Ok(Self {
+                    n: n.to_dec()?,
+                    p: p.to_dec()?,
+                    q: q.to_dec()?,
+                });
+            }
+        }
+    }
+
+    /// The public key of the auditor, to be given as the `auditor_key` of
+    /// an escrowed attribute in a presentation request
+    pub fn public_key(&self) -> &str {
+        &self.n
+    }
+
+    /// Decrypt an escrowed attribute, returning its encoded value
+    pub fn decrypt(&self, escrowed: &EscrowedAttributeInfo) -> Result<String> {
+        let mut ctx = BigNumber::new_context()?;
+        let n = parse_number(&self.n, "auditor key")?;
+        let p = parse_number(&self.p, "auditor key")?;
+        let q = parse_number(&self.q, "auditor key")?;
+        let n2 = n.sqr(Some(&mut ctx))?;
+        let ciphertext = parse_element(&escrowed.ciphertext, &n2, "ciphertext")?;
+        let phi = p.decrement()?.mul(&q.decrement()?, Some(&mut ctx))?;
+        let l = ciphertext
+            .mod_exp(&phi, &n2, Some(&mut ctx))?
+            .decrement()?
+            .div(&n, Some(&mut ctx))?;
+        let value = l.mod_mul(&phi.inverse(&n, Some(&mut ctx))?, &n, Some(&mut ctx))?;
+        // values above half of the modulus are negative integer encodings
+        if value > n.rshift1()? {
+            Ok(value.sub(&n)?.to_dec()?)
+        } else {
+            Ok(value.to_dec()?)
+        }
+    }
+}
+
+/// The value of an attribute to be escrowed, from its sub-proof
+#[derive(Debug)]
+pub(crate) struct EscrowValue {
+    pub referent: String,
+    pub sub_proof_index: u32,
+    pub name: String,
+    pub encoded: String,
+}
+
+/// An escrow committed to before the CL proof is finalized
+pub(crate) struct PendingEscrow<'a> {
+    referent: String,
+    info: EscrowedAttributeInfo,
+    value: &'a EscrowValue,
+    n2: BigNumber,
+    m: BigNumber,
+    m_tilde: BigNumber,
+    r: BigNumber,
+    r_tilde: BigNumber,
+}
+
+impl PendingEscrow<'_> {
+    pub fn entry(&self) -> (&String, &EscrowedAttributeInfo) {
+        (&self.referent, &self.info)
+    }
+}
+
+/// Encrypt the values of the escrowed attributes, committing to their
+/// blinding factors as recovered from a probe of the CL proof. Every
+/// escrowed referent must have a value.
+pub(crate) fn commit_escrows<'a>(
+    pres_req: &PresentationRequestPayload,
+    probe: &serde_json::Value,
+    values: &'a [EscrowValue],
+) -> Result<Vec<PendingEscrow<'a>>> {
+    let mut ctx = BigNumber::new_context()?;
+    let mut pending = Vec::with_capacity(values.len());
+    for (referent, escrow) in pres_req.escrowed_attributes.iter() {
+        let value = values
+            .iter()
+            .find(|value| value.referent == *referent)
+            .ok_or_else(|| {
+                err_msg!(
+                    "Escrowed attribute must be presented unrevealed: {}",
+                    referent
+                )
+            })?;
+        let n = parse_number(&escrow.auditor_key, "auditor key")?;
+        let n2 = n.sqr(Some(&mut ctx))?;
+        let m = BigNumber::from_dec(&value.encoded)?;
+        let (c, m_hat) = blinded_value(probe, value.sub_proof_index, &value.name)?;
+        let m_tilde = m_hat.sub(&c.mul(&m, Some(&mut ctx))?)?;
+
+        let r = random_unit(&n)?;
+        let r_tilde = random_unit(&n)?;
+        let ciphertext = encrypt(&n, &n2, &m, &r, &mut ctx)?;
+        let commitment = encrypt(&n, &n2, &m_tilde, &r_tilde, &mut ctx)?;
+        pending.push(PendingEscrow {
+            referent: referent.clone(),
+            info: EscrowedAttributeInfo {
+                sub_proof_index: value.sub_proof_index,
+                ciphertext: ciphertext.to_dec()?,
+                commitment: commitment.to_dec()?,
+                response: String::new(),
+            },
+            value,
+            n2,
+            m,
+            m_tilde,
+            r,
+            r_tilde,
+        });
+    }
+    Ok(pending)
+}
+
+/// Compute the responses of the escrows for the challenge of the final CL
+/// proof
+pub(crate) fn complete_escrows(
+    pending: Vec<PendingEscrow<'_>>,
+    proof: &serde_json::Value,
+) -> Result<HashMap<String, EscrowedAttributeInfo>> {
+    let mut ctx = BigNumber::new_context()?;
+    let mut escrowed = HashMap::with_capacity(pending.len());
+    for mut escrow in pending {
+        let (c, m_hat) = blinded_value(proof, escrow.value.sub_proof_index, &escrow.value.name)?;
+        if m_hat != escrow.m_tilde.add(&c.mul(&escrow.m, Some(&mut ctx))?)? {
+            return Err(err_msg!(
+                Unexpected,
+                "Blinding factor not recovered for escrowed attribute: {}",
+                escrow.referent
+            ));
+        }
+        let response = escrow.r_tilde.mod_mul(
+            &escrow.r.mod_exp(&c, &escrow.n2, Some(&mut ctx))?,
+            &escrow.n2,
+            Some(&mut ctx),
+        )?;
+        escrow.info.response = response.to_dec()?;
+        escrowed.insert(escrow.referent, escrow.info);
+    }
+    Ok(escrowed)
+}
+
+/// Check that the escrowed attributes of a presentation are those of the
+/// request, and that each ciphertext encrypts the value of its attribute in
+/// the CL proof
+pub(crate) fn check_escrowed_attributes(
+    pres_req: &PresentationRequestPayload,
+    requested_proof: &RequestedProof,
+    proof: &Proof,
+) -> Result<()> {
+    if pres_req.escrowed_attributes.len() != requested_proof.escrowed_attrs.len()
+        || pres_req
+            .escrowed_attributes
+            .keys()
+            .any(|referent| !requested_proof.escrowed_attrs.contains_key(referent))
+    {
+        return Err(err_msg!(
+            ProofRejected,
+            "Requested escrowed attributes {:?} do not correspond to received {:?}",
+            pres_req.escrowed_attributes.keys().collect::<Vec<_>>(),
+            requested_proof.escrowed_attrs.keys().collect::<Vec<_>>()
+        ));
+    }
+    if pres_req.escrowed_attributes.is_empty() {
+        return Ok(());
+    }
+
+    let mut ctx = BigNumber::new_context()?;
+    let proof = serde_json::to_value(proof)?;
+    for (referent, escrow) in pres_req.escrowed_attributes.iter() {
+        let info = &requested_proof.escrowed_attrs[referent];
+        if requested_proof
+            .unrevealed_attrs
+            .get(referent)
+            .map(|attr| attr.sub_proof_index)
+            != Some(info.sub_proof_index)
+        {
+            return Err(err_msg!(
+                ProofRejected,
+                "Escrowed attribute is not presented unrevealed: {}",
+                referent
+            ));
+        }
+        let name = pres_req
+            .requested_attributes
+            .get(referent)
+            .and_then(|attr| attr.name.as_deref())
+            .map(attr_common_view)
+            .unwrap_or_default();
+        let (c, m_hat) = blinded_value(&proof, info.sub_proof_index, &name)?;
+
+        let n = parse_number(&escrow.auditor_key, "auditor key")?;
+        let n2 = n.sqr(Some(&mut ctx))?;
+        let ciphertext = parse_element(&info.ciphertext, &n2, "ciphertext")?;
+        let commitment = parse_element(&info.commitment, &n2, "commitment")?;
+        let response = parse_element(&info.response, &n2, "response")?;
+        let expected = encrypt(&n, &n2, &m_hat, &response, &mut ctx)?;
+        let received = commitment.mod_mul(
+            &ciphertext.mod_exp(&c, &n2, Some(&mut ctx))?,
+            &n2,
+            Some(&mut ctx),
+        )?;
+        if expected != received {
+            return Err(err_msg!(
+                ProofRejected,
+                "Invalid escrow of attribute: {}",
+                referent
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Derive the nonce of a CL proof from the nonce of the request and the
+/// escrowed ciphertexts and commitments, in the order of their referents.
+/// Without escrows, the nonce of the request is used.
+pub(crate) fn escrow_nonce<'a>(
+    pres_req: &PresentationRequestPayload,
+    escrowed: impl Iterator<Item = (&'a String, &'a EscrowedAttributeInfo)>,
+) -> Result<Nonce> {
+    let escrowed: BTreeMap<&String, &EscrowedAttributeInfo> = escrowed.collect();
+    if escrowed.is_empty() {
+        return pres_req.nonce.try_clone().map_err(err_map!(Unexpected));
+    }
+    let mut hasher = SHA256::DigestType::new();
+    let mut update = |value: &[u8]| {
+        hasher.update((value.len() as u32).to_be_bytes());
+        hasher.update(value);
+    };
+    update(ESCROW_NONCE_TAG);
+    update(pres_req.nonce.as_bytes());
+    for (referent, info) in escrowed {
+        let auditor_key = pres_req
+            .escrowed_attributes
+            .get(referent)
+            .map(|escrow| escrow.auditor_key.as_str())
+            .ok_or_else(|| {
+                err_msg!(ProofRejected, "Unexpected escrowed attribute: {}", referent)
+            })?;
+        update(referent.as_bytes());
+        update(&info.sub_proof_index.to_be_bytes());
+        update(auditor_key.as_bytes());
+        update(info.ciphertext.as_bytes());
+        update(info.commitment.as_bytes());
+    }
+    let digest = BigNumber::from_bytes(&hasher.finalize())?;
+    Nonce::from_native(digest).map_err(err_map!(Unexpected))
+}
+
+/// The challenge of a CL proof and the blinded value of an attribute of one
+/// of its sub-proofs. The CL library has no accessors for these values, so
+/// they are read from the serialized proof, whose layout is pinned by the
+/// exact version dependency on `ursa`.
+pub(crate) fn blinded_value(
+    proof: &serde_json::Value,
+    sub_proof_index: u32,
+    name: &str,
+) -> Result<(BigNumber, BigNumber)> {
+    let challenge = proof["aggregated_proof"]["c_hash"]
+        .as_str()
+        .ok_or_else(|| err_msg!(ProofRejected, "Challenge not found in proof"))?;
+    let blinded = proof["proofs"][sub_proof_index as usize]["primary_proof"]["eq_proof"]["m"][name]
+        .as_str()
+        .ok_or_else(|| {
+            err_msg!(
+                ProofRejected,
+                "Blinded value not found for attribute: {}",
+                name
+            )
+        })?;
+    Ok((
+        BigNumber::from_dec(challenge)?,
+        BigNumber::from_dec(blinded)?,
+    ))
+}
+
+/// The Paillier encryption `(1 + n)^m * r^n mod n^2`
+fn encrypt(
+    n: &BigNumber,
+    n2: &BigNumber,
+    m: &BigNumber,
+    r: &BigNumber,
+    ctx: &mut BigNumberContext,
+) -> Result<BigNumber> {
+    // (1 + n)^m = 1 + m * n mod n^2
+    let g_m = m.modulus(n, Some(ctx))?.mul(n, Some(ctx))?.increment()?;
+    Ok(g_m.mod_mul(&r.mod_exp(n, n2, Some(ctx))?, n2, Some(ctx))?)
+}
+
+fn random_unit(n: &BigNumber) -> Result<BigNumber> {
+    loop {
+        let r = n.rand_range()?;
+        if BigNumber::gcd(&r, n, None)? == BigNumber::from_u32(1)? {
+            return Ok(r);
+        }
+    }
+}
+
+fn parse_number(value: &str, label: &str) -> Result<BigNumber> {
+    if value.is_empty() || !value.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(err_msg!("Invalid {}: {}", label, value));
+    }
+    Ok(BigNumber::from_dec(value)?)
+}
+
+/// Parse a non-zero element of the group modulo `n^2`
+fn parse_element(value: &str, n2: &BigNumber, label: &str) -> Result<BigNumber> {
+    let element = parse_number(value, label).ok();
+    match element {
+        Some(element) if element != BigNumber::new()? && element < *n2 => Ok(element),
+        _ => Err(err_msg!(ProofRejected, "Invalid {}: {}", label, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::services::helpers::encode_credential_attribute;
+    use crate::services::test_utils::{CredDefFixture, HolderFixture};
+    use crate::services::types::*;
+    use crate::services::{prover, verifier};
+
+    #[test]
+    fn escrowed_attributes_round_trip() {
+        let holder = HolderFixture::new().unwrap();
+        let fixture = CredDefFixture::with_attributes(vec!["name", "age"]).unwrap();
+        let credential = fixture
+            .issue(&holder, &[("name", "Alex"), ("age", "-7")])
+            .unwrap();
+
+        let auditor = AuditorKey::new().unwrap();
+        let pres_req: PresentationRequest = serde_json::from_value(serde_json::json!({
+            "nonce": "123432421212",
+            "name": "proof_req_1",
+            "version": "0.1",
+            "requested_attributes": {
+                "name": {"name": "name"},
+                "age": {"name": "age"}
+            },
+            "escrowed_attributes": {
+                "name": {"auditor_key": auditor.public_key()},
+                "age": {"auditor_key": auditor.public_key()}
+            }
+        }))
+        .unwrap();
+        let (schemas, cred_defs) = (fixture.schemas(), fixture.cred_defs());
+        let present = |revealed: bool| {
+            let mut present = PresentCredentials::new();
+            {
+                let mut add = present.add_credential(&credential, None, None);
+                add.add_requested_attribute("name", false);
+                add.add_requested_attribute("age", revealed);
+            }
+            prover::create_presentation(
+                &pres_req,
+                present,
+                None,
+                &holder.master_secret,
+                &schemas,
+                &cred_defs,
+            )
+        };
+        let verify = |presentation: &Presentation| {
+            verifier::verify_presentation(presentation, &pres_req, &schemas, &cred_defs, None, None)
+        };
+
+        let mut presentation = present(false).unwrap();
+        assert!(verify(&presentation).unwrap());
+        let escrowed = &presentation.requested_proof.escrowed_attrs;
+        assert_eq!(auditor.decrypt(&escrowed["age"]).unwrap(), "-7");
+        assert_eq!(
+            auditor.decrypt(&escrowed["name"]).unwrap(),
+            encode_credential_attribute("Alex").unwrap()
+        );
+
+        // a ciphertext of another value is rejected
+        let other = escrowed["name"].ciphertext.clone();
+        presentation
+            .requested_proof
+            .escrowed_attrs
+            .get_mut("age")
+            .unwrap()
+            .ciphertext = other;
+        assert_kind!(ProofRejected, verify(&presentation));
+        presentation.requested_proof.escrowed_attrs.clear();
+        assert_kind!(ProofRejected, verify(&presentation));
+
+        assert_kind!(Input, present(true));
+    }
+
+    #[test]
+    fn blinded_values_of_proof() {
+        let holder = HolderFixture::new().unwrap();
+        let fixture = CredDefFixture::with_attributes(vec!["name", "age"]).unwrap();
+        let credential = fixture
+            .issue(&holder, &[("name", "Alex"), ("age", "28")])
+            .unwrap();
+        let pres_req: PresentationRequest = serde_json::from_value(serde_json::json!({
+            "nonce": "123432421212",
+            "name": "proof_req_1",
+            "version": "0.1",
+            "requested_attributes": {
+                "name": {"name": "name"},
+                "age": {"name": "age"}
+            }
+        }))
+        .unwrap();
+        let mut present = PresentCredentials::new();
+        {
+            let mut add = present.add_credential(&credential, None, None);
+            add.add_requested_attribute("name", true);
+            add.add_requested_attribute("age", false);
+        }
+        let presentation = prover::create_presentation(
+            &pres_req,
+            present,
+            None,
+            &holder.master_secret,
+            &fixture.schemas(),
+            &fixture.cred_defs(),
+        )
+        .unwrap();
+
+        // the serialized layout of a CL proof of the pinned version of ursa
+        let proof = serde_json::to_value(&presentation.proof).unwrap();
+        let c_hash = proof["aggregated_proof"]["c_hash"].as_str().unwrap();
+        let blinded = proof["proofs"][0]["primary_proof"]["eq_proof"]["m"]
+            .as_object()
+            .unwrap();
+        assert_eq!(
+            blinded.keys().map(String::as_str).collect::<BTreeSet<_>>(),
+            BTreeSet::from(["age", "master_secret"])
+        );
+
+        let (c, age) = blinded_value(&proof, 0, "age").unwrap();
+        assert_eq!(c, BigNumber::from_dec(c_hash).unwrap());
+        assert_eq!(
+            age,
+            BigNumber::from_dec(blinded["age"].as_str().unwrap()).unwrap()
+        );
+        assert!(blinded_value(&proof, 0, "master_secret").is_ok());
+        // revealed attributes have no blinded value
+        assert_kind!(ProofRejected, blinded_value(&proof, 0, "name"));
+        assert_kind!(ProofRejected, blinded_value(&proof, 1, "age"));
+    }
+}
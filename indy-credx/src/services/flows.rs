@@ -0,0 +1,108 @@
+//! Complete protocol flows run in memory, for tests and agents which hold
+//! the objects of both parties
+
+use super::issuer::{create_credential, create_credential_offer};
+use super::prover::{create_credential_request, process_credential};
+use super::types::{
+    Credential, CredentialDefinition, CredentialDefinitionPrivate, CredentialKeyCorrectnessProof,
+    CredentialOffer, CredentialRequest, CredentialRequestMetadata, CredentialRevocationConfig,
+    CredentialValues, DidValue, MasterSecret, RevocationRegistry, RevocationRegistryDelta,
+    SchemaId,
+};
+use crate::error::Result;
+
+/// The objects used by the issuer of a credential
+#[derive(Debug)]
+pub struct IssuerObjects<'a> {
+    pub schema_id: &'a SchemaId,
+    pub cred_def: &'a CredentialDefinition,
+    pub cred_def_private: &'a CredentialDefinitionPrivate,
+    pub key_correctness_proof: &'a CredentialKeyCorrectnessProof,
+    pub revocation: Option<CredentialRevocationConfig<'a>>,
+}
+
+/// The objects used by the holder of a credential
+#[derive(Debug)]
+pub struct HolderObjects<'a> {
+    pub prover_did: &'a DidValue,
+    pub master_secret: &'a MasterSecret,
+    pub master_secret_id: &'a str,
+}
+
+/// The artifacts produced by each step of an issuance
+#[derive(Debug)]
+pub struct IssuedCredential {
+    pub offer: CredentialOffer,
+    pub request: CredentialRequest,
+    pub request_metadata: CredentialRequestMetadata,
+    /// The credential after processing by the holder
+    pub credential: Credential,
+    pub rev_reg: Option<RevocationRegistry>,
+    pub rev_reg_delta: Option<RevocationRegistryDelta>,
+}
+
+/// Run the offer, request, issue and process steps of an issuance
+pub fn issue_credential(
+    issuer: IssuerObjects,
+    holder: HolderObjects,
+    cred_values: CredentialValues,
+) -> Result<IssuedCredential> {
+    let offer = create_credential_offer(
+        issuer.schema_id,
+        issuer.cred_def,
+        issuer.key_correctness_proof,
+    )?;
+    let (request, request_metadata) = create_credential_request(
+        holder.prover_did,
+        issuer.cred_def,
+        holder.master_secret,
+        holder.master_secret_id,
+        &offer,
+    )?;
+    let rev_reg_def = issuer.revocation.as_ref().map(|config| config.reg_def);
+    let (mut credential, rev_reg, rev_reg_delta) = create_credential(
+        issuer.cred_def,
+        issuer.cred_def_private,
+        &offer,
+        &request,
+        cred_values,
+        issuer.revocation,
+    )?;
+    process_credential(
+        &mut credential,
+        &request_metadata,
+        holder.master_secret,
+        issuer.cred_def,
+        rev_reg_def,
+    )?;
+    Ok(IssuedCredential {
+        offer,
+        request,
+        request_metadata,
+        credential,
+        rev_reg,
+        rev_reg_delta,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_utils::{credential_values, CredDefFixture, HolderFixture};
+
+    #[test]
+    fn issue_credential_works() {
+        let fixture = CredDefFixture::with_attributes(vec!["name", "age"]).unwrap();
+        let holder = HolderFixture::new().unwrap();
+        let issued = issue_credential(
+            fixture.issuer_objects(),
+            holder.holder_objects(),
+            credential_values(&[("name", "Alex"), ("age", "28")]).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(issued.offer.cred_def_id, *fixture.cred_def.id());
+        assert_eq!(issued.request_metadata.master_secret_name, "default");
+        assert_eq!(issued.credential.values.0["name"].raw, "Alex");
+        assert!(issued.rev_reg.is_none());
+    }
+}
@@ -1,9 +1,13 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use indy_data_types::anoncreds::{
     credential::AttributeValues,
     nonce::Nonce,
-    pres_request::{AttributeInfo, NonRevocedInterval, PredicateInfo},
+    pres_request::{
+        AttributeInfo, NonRevocedInterval, PredicateInfo, PredicateTypes,
+        PresentationRequestPayload,
+    },
+    presentation::RequestedProof,
 };
 use indy_utils::hash::SHA256;
 
@@ -12,7 +16,7 @@ use crate::ursa::{
     bn::BigNumber,
     cl::{
         issuer, verifier, CredentialSchema, CredentialValues as CryptoCredentialValues,
-        MasterSecret as CryptoMasterSecret, NonCredentialSchema, SubProofRequest,
+        MasterSecret as CryptoMasterSecret, NonCredentialSchema, Proof, SubProofRequest,
     },
 };
 
@@ -70,17 +74,52 @@ pub fn build_credential_values(
     Ok(res)
 }
 
+/// Match a value against an SQL `LIKE` pattern, where `%` matches any
+/// sequence of characters and `_` matches any single character
+pub(crate) fn like_matches(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    // positions in the pattern reachable after consuming each value prefix
+    let mut reachable = vec![false; pattern.len() + 1];
+    reachable[0] = true;
+    for p in 0..pattern.len() {
+        if pattern[p] == '%' && reachable[p] {
+            reachable[p + 1] = true;
+        }
+    }
+    for c in value {
+        let mut next = vec![false; pattern.len() + 1];
+        for p in 0..pattern.len() {
+            if !reachable[p] {
+                continue;
+            }
+            match pattern[p] {
+                '%' => {
+                    next[p] = true;
+                    next[p + 1] = true;
+                }
+                '_' => next[p + 1] = true,
+                pc if pc == c => next[p + 1] = true,
+                _ => (),
+            }
+        }
+        for p in 0..pattern.len() {
+            if pattern[p] == '%' && next[p] {
+                next[p + 1] = true;
+            }
+        }
+        reachable = next;
+    }
+    reachable[pattern.len()]
+}
+
 pub fn encode_credential_attribute(raw_value: &str) -> Result<String> {
     if let Ok(val) = raw_value.parse::<i32>() {
         Ok(val.to_string())
     } else {
+        // The digest is read as a big-endian integer on every platform, so
+        // the encoding does not depend on the byte order of the host
         let digest = SHA256::digest(raw_value.as_bytes());
-        #[cfg(target_endian = "big")]
-        let digest = {
-            let mut d = digest;
-            d.reverse();
-            d
-        };
         Ok(BigNumber::from_bytes(&digest)?.to_dec()?)
     }
 }
@@ -118,6 +157,7 @@ pub fn build_sub_proof_request(
     }
 
     for predicate in predicates_for_credential {
+        check_predicate_bound(predicate)?;
         let p_type = format!("{}", predicate.p_type);
 
         sub_proof_request_builder.add_predicate(
@@ -134,6 +174,149 @@ pub fn build_sub_proof_request(
     Ok(res)
 }
 
+/// Check that the bound of a predicate may be adjusted for a strict
+/// comparison without leaving the 32-bit range of CL predicate values
+pub fn check_predicate_bound(predicate: &PredicateInfo) -> Result<()> {
+    match (&predicate.p_type, predicate.p_value) {
+        (PredicateTypes::GT, i32::MAX) | (PredicateTypes::LT, i32::MIN) => Err(err_msg!(
+            "Predicate on attribute \"{}\" can never be satisfied: {} {}",
+            predicate.name,
+            predicate.p_type,
+            predicate.p_value
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Check that a predicate may be proven for an encoded attribute value.
+/// Predicate proofs operate on signed 32-bit integers, and the difference
+/// between the value and the bound must also fit within that range; wider
+/// ranges of values should be encoded relative to an offset with
+/// `SignedRange`.
+pub fn check_predicate_value(predicate: &PredicateInfo, encoded: &str) -> Result<()> {
+    check_predicate_bound(predicate)?;
+    let value = encoded.parse::<i32>().map_err(|_| {
+        err_msg!(
+            "Value of attribute \"{}\" is not a 32-bit integer and cannot be used in a predicate",
+            predicate.name
+        )
+    })? as i64;
+    let bound = predicate.p_value as i64;
+    let delta = match predicate.p_type {
+        PredicateTypes::GE => value - bound,
+        PredicateTypes::GT => value - bound - 1,
+        PredicateTypes::LE => bound - value,
+        PredicateTypes::LT => bound - value - 1,
+    };
+    if delta < 0 {
+        Err(err_msg!(
+            "Predicate on attribute \"{}\" is not satisfied",
+            predicate.name
+        ))
+    } else if delta > i32::MAX as i64 {
+        Err(err_msg!(
+            "Predicate on attribute \"{}\" compares values which differ by more than 2^31-1, \
+            they must be encoded relative to an offset",
+            predicate.name
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// An offset encoding for signed attribute values, mapping the values
+/// `min..=max` onto `0..=max-min` so that any value in the range may be
+/// compared with any bound in the range by a predicate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SignedRange {
+    min: i64,
+    max: i64,
+}
+
+impl SignedRange {
+    pub fn new(min: i64, max: i64) -> Result<Self> {
+        if min > max {
+            return Err(err_msg!("Invalid range: {} > {}", min, max));
+        }
+        match max.checked_sub(min) {
+            Some(span) if span <= i32::MAX as i64 => Ok(Self { min, max }),
+            _ => Err(err_msg!(
+                "Range {}..={} is too wide to be compared by predicates",
+                min,
+                max
+            )),
+        }
+    }
+
+    pub fn min(&self) -> i64 {
+        self.min
+    }
+
+    pub fn max(&self) -> i64 {
+        self.max
+    }
+
+    fn offset(&self, value: i64) -> Result<i32> {
+        if value < self.min || value > self.max {
+            return Err(err_msg!(
+                "Value {} is outside of the range {}..={}",
+                value,
+                self.min,
+                self.max
+            ));
+        }
+        Ok((value - self.min) as i32)
+    }
+
+    /// The raw attribute value to issue for a value in the range
+    pub fn encode(&self, value: i64) -> Result<String> {
+        Ok(self.offset(value)?.to_string())
+    }
+
+    /// Recover a value from a raw attribute value issued with `encode`
+    pub fn decode(&self, raw: &str) -> Result<i64> {
+        let offset = raw
+            .parse::<i32>()
+            .ok()
+            .filter(|offset| *offset >= 0 && (*offset as i64) <= self.max - self.min)
+            .ok_or_else(|| err_msg!("Invalid offset-encoded value: {}", raw))?;
+        Ok(self.min + offset as i64)
+    }
+
+    /// Build a predicate comparing an offset-encoded attribute to a bound
+    /// within the range
+    pub fn predicate(
+        &self,
+        name: impl Into<String>,
+        p_type: PredicateTypes,
+        bound: i64,
+    ) -> Result<PredicateInfo> {
+        Ok(PredicateInfo {
+            name: name.into(),
+            p_type,
+            p_value: self.offset(bound)?,
+            restrictions: None,
+            non_revoked: None,
+        })
+    }
+}
+
+/// The range predicates proving that the value of an attribute lies within
+/// a run of a requested set, from its lower to its upper bound inclusive
+pub fn range_disclosure_predicates(name: &str, lower: i32, upper: i32) -> [PredicateInfo; 2] {
+    let predicate = |p_type, p_value| PredicateInfo {
+        name: name.to_string(),
+        p_type,
+        p_value,
+        restrictions: None,
+        non_revoked: None,
+    };
+    [
+        predicate(PredicateTypes::GE, lower),
+        predicate(PredicateTypes::LE, upper),
+    ]
+}
+
 pub fn get_non_revoc_interval(
     global_interval: &Option<NonRevocedInterval>,
     local_interval: &Option<NonRevocedInterval>,
@@ -153,6 +336,224 @@ pub fn get_non_revoc_interval(
     interval
 }
 
+/// Combine the intervals of several referents into the interval satisfying
+/// all of them
+pub fn intersect_non_revoc_intervals(
+    intervals: impl IntoIterator<Item = NonRevocedInterval>,
+) -> Option<NonRevocedInterval> {
+    intervals.into_iter().reduce(|a, b| NonRevocedInterval {
+        from: a.from.max(b.from),
+        to: match (a.to, b.to) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        },
+    })
+}
+
+/// Find the index of the latest timestamp within `interval`, where an
+/// interval without an upper bound extends to `now`
+pub fn select_latest_timestamp(
+    timestamps: impl IntoIterator<Item = u64>,
+    interval: &NonRevocedInterval,
+    now: u64,
+) -> Option<usize> {
+    let to = interval.to.unwrap_or(now);
+    timestamps
+        .into_iter()
+        .enumerate()
+        .filter(|(_, ts)| *ts <= to && interval.from.map(|from| *ts >= from).unwrap_or(true))
+        .max_by_key(|(_, ts)| *ts)
+        .map(|(idx, _)| idx)
+}
+
+/// Check that the referents of each same credential group in a presentation
+/// request are satisfied by a single credential of the requested proof
+pub fn check_same_credential(
+    pres_req: &PresentationRequestPayload,
+    requested_proof: &RequestedProof,
+) -> Result<()> {
+    let sub_proof_index = |referent: &str| {
+        requested_proof
+            .revealed_attrs
+            .get(referent)
+            .map(|info| info.sub_proof_index)
+            .or_else(|| {
+                requested_proof
+                    .revealed_attr_groups
+                    .get(referent)
+                    .map(|info| info.sub_proof_index)
+            })
+            .or_else(|| {
+                requested_proof
+                    .unrevealed_attrs
+                    .get(referent)
+                    .map(|info| info.sub_proof_index)
+            })
+            .or_else(|| {
+                requested_proof
+                    .predicates
+                    .get(referent)
+                    .map(|info| info.sub_proof_index)
+            })
+            .or_else(|| {
+                requested_proof
+                    .range_disclosures
+                    .get(referent)
+                    .map(|info| info.sub_proof_index)
+            })
+    };
+    for group in pres_req.same_credential.iter() {
+        let mut expected = None;
+        for referent in group {
+            let index = sub_proof_index(referent).ok_or_else(|| {
+                err_msg!(
+                    ProofRejected,
+                    "Referent of a same credential group is not satisfied by a credential: {}",
+                    referent
+                )
+            })?;
+            if *expected.get_or_insert(index) != index {
+                return Err(err_msg!(
+                    ProofRejected,
+                    "Referents must be satisfied by the same credential: {}",
+                    group.join(", ")
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The normalized attribute names of the equal attributes groups of a
+/// presentation request, whose blinding factors are shared between the
+/// sub-proofs of a presentation
+pub fn equal_attribute_names(pres_req: &PresentationRequestPayload) -> BTreeSet<String> {
+    pres_req
+        .equal_attributes
+        .iter()
+        .filter_map(|group| group.first())
+        .filter_map(|referent| pres_req.requested_attributes.get(referent))
+        .filter_map(|info| info.name.as_deref())
+        .map(attr_common_view)
+        .collect()
+}
+
+/// Check that the referents of each equal attributes group are presented
+/// unrevealed, and that no other credential holding an unrevealed attribute
+/// of the same name is presented, as its blinded value would be linked to
+/// the values of the group. `held` lists the sub-proof index and normalized
+/// name of each such attribute of the presented credentials.
+pub fn check_equal_attributes_presented(
+    pres_req: &PresentationRequestPayload,
+    requested_proof: &RequestedProof,
+    held: &[(u32, String)],
+) -> Result<()> {
+    let mut revealed = HashSet::new();
+    for (referent, info) in requested_proof.revealed_attrs.iter() {
+        if let Some(name) = pres_req
+            .requested_attributes
+            .get(referent)
+            .and_then(|attr| attr.name.as_deref())
+        {
+            revealed.insert((info.sub_proof_index, attr_common_view(name)));
+        }
+    }
+    for info in requested_proof.revealed_attr_groups.values() {
+        for name in info.values.keys() {
+            revealed.insert((info.sub_proof_index, attr_common_view(name)));
+        }
+    }
+    let mut linked = HashSet::new();
+    for group in pres_req.equal_attributes.iter() {
+        for referent in group {
+            let name = pres_req
+                .requested_attributes
+                .get(referent)
+                .and_then(|attr| attr.name.as_deref())
+                .map(attr_common_view)
+                .ok_or_else(|| {
+                    err_msg!("Unknown referent in equal attributes group: {}", referent)
+                })?;
+            let index = requested_proof
+                .unrevealed_attrs
+                .get(referent)
+                .map(|info| info.sub_proof_index)
+                .filter(|index| !revealed.contains(&(*index, name.clone())))
+                .ok_or_else(|| {
+                    err_msg!(
+                        "Referent of an equal attributes group must be presented unrevealed: {}",
+                        referent
+                    )
+                })?;
+            linked.insert((index, name));
+        }
+    }
+    for (index, name) in held {
+        if !linked.contains(&(*index, name.clone())) && !revealed.contains(&(*index, name.clone()))
+        {
+            return Err(err_msg!(
+                "Attribute \"{}\" of a presented credential would be linked to an equal attributes group",
+                name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check that the blinded values of the referents of each equal attributes
+/// group are the same in each of their sub-proofs. As the blinded values are
+/// computed with a single challenge for the whole proof, they can only match
+/// when the attribute values are equal.
+pub fn check_equal_attributes(
+    pres_req: &PresentationRequestPayload,
+    requested_proof: &RequestedProof,
+    proof: &Proof,
+) -> Result<()> {
+    if pres_req.equal_attributes.is_empty() {
+        return Ok(());
+    }
+    let proof = serde_json::to_value(proof)?;
+    for group in pres_req.equal_attributes.iter() {
+        let mut expected = None;
+        for referent in group {
+            let index = requested_proof
+                .unrevealed_attrs
+                .get(referent)
+                .map(|info| info.sub_proof_index)
+                .ok_or_else(|| {
+                    err_msg!(
+                        ProofRejected,
+                        "Referent of an equal attributes group is not presented unrevealed: {}",
+                        referent
+                    )
+                })?;
+            let name = pres_req
+                .requested_attributes
+                .get(referent)
+                .and_then(|attr| attr.name.as_deref())
+                .map(attr_common_view)
+                .unwrap_or_default();
+            let blinded = proof["proofs"][index as usize]["primary_proof"]["eq_proof"]["m"]
+                .get(&name)
+                .ok_or_else(|| {
+                    err_msg!(
+                        ProofRejected,
+                        "Blinded value not found for attribute referent: {}",
+                        referent
+                    )
+                })?;
+            if *expected.get_or_insert(blinded) != blinded {
+                return Err(err_msg!(
+                    ProofRejected,
+                    "Attributes are not proven equal: {}",
+                    group.join(", ")
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
 pub fn new_nonce() -> Result<Nonce> {
     Nonce::new().map_err(err_map!(Unexpected))
 }
@@ -160,6 +561,45 @@ pub fn new_nonce() -> Result<Nonce> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::services::types::MasterSecret;
+
+    #[test]
+    fn like_matches_works() {
+        assert!(like_matches("gvt", "gvt"));
+        assert!(like_matches("g%", "gvt"));
+        assert!(like_matches("%v%", "gvt"));
+        assert!(like_matches("g_t", "gvt"));
+        assert!(like_matches("%", ""));
+        assert!(!like_matches("g_", "gvt"));
+        assert!(!like_matches("%x%", "gvt"));
+    }
+
+    #[test]
+    fn big_number_serialization_is_portable() {
+        // Byte strings are read most significant byte first, and numbers
+        // are serialized as decimal strings, on every platform
+        let bn = BigNumber::from_bytes(&[0x01, 0x02, 0x03]).unwrap();
+        assert_eq!(bn.to_dec().unwrap(), "66051");
+        assert_eq!(bn.to_bytes().unwrap(), [0x01, 0x02, 0x03]);
+        let json = serde_json::to_string(&bn).unwrap();
+        assert_eq!(json, "\"66051\"");
+        let decoded: BigNumber = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.to_bytes().unwrap(), [0x01, 0x02, 0x03]);
+
+        let digest = SHA256::digest(b"Alex");
+        assert_eq!(
+            BigNumber::from_bytes(&digest).unwrap().to_bytes().unwrap(),
+            digest.to_vec()
+        );
+        assert_eq!(
+            encode_credential_attribute("Alex").unwrap(),
+            "99262857098057710338306967609588410025648622308394250666849665532448612202874"
+        );
+
+        let json = r#"{"value":{"ms":"54509316649379706740273435412344062475578384476960788968860526001710008397749"}}"#;
+        let master_secret: MasterSecret = serde_json::from_str(json).unwrap();
+        assert_eq!(serde_json::to_string(&master_secret).unwrap(), json);
+    }
 
     fn _interval() -> NonRevocedInterval {
         NonRevocedInterval {
@@ -185,4 +625,126 @@ mod tests {
         let res = get_non_revoc_interval(&None, &None);
         assert_eq!(None, res);
     }
+
+    #[test]
+    fn intersect_non_revoc_intervals_works() {
+        let res = intersect_non_revoc_intervals(vec![
+            NonRevocedInterval {
+                from: Some(10),
+                to: None,
+            },
+            NonRevocedInterval {
+                from: None,
+                to: Some(100),
+            },
+            NonRevocedInterval {
+                from: Some(20),
+                to: Some(200),
+            },
+        ]);
+        assert_eq!(
+            res,
+            Some(NonRevocedInterval {
+                from: Some(20),
+                to: Some(100)
+            })
+        );
+        assert_eq!(intersect_non_revoc_intervals(vec![]), None);
+    }
+
+    #[test]
+    fn select_latest_timestamp_works() {
+        let open = NonRevocedInterval {
+            from: None,
+            to: None,
+        };
+        assert_eq!(
+            select_latest_timestamp(vec![10, 30, 20], &open, 100),
+            Some(1)
+        );
+        assert_eq!(
+            select_latest_timestamp(vec![10, 300, 20], &open, 100),
+            Some(2)
+        );
+        assert_eq!(
+            select_latest_timestamp(vec![10, 30, 20], &_interval(), 0),
+            Some(1)
+        );
+        let from = NonRevocedInterval {
+            from: Some(40),
+            to: None,
+        };
+        assert_eq!(select_latest_timestamp(vec![10, 30, 20], &from, 100), None);
+    }
+
+    fn _predicate(p_type: PredicateTypes, p_value: i32) -> PredicateInfo {
+        PredicateInfo {
+            name: "age".to_string(),
+            p_type,
+            p_value,
+            restrictions: None,
+            non_revoked: None,
+        }
+    }
+
+    #[test]
+    fn check_predicate_value_works_for_negative_values() {
+        check_predicate_value(&_predicate(PredicateTypes::GE, -10), "-5").unwrap();
+        check_predicate_value(&_predicate(PredicateTypes::LT, -4), "-5").unwrap();
+        assert!(check_predicate_value(&_predicate(PredicateTypes::GT, -5), "-5").is_err());
+    }
+
+    #[test]
+    fn check_predicate_value_rejects_overflow() {
+        assert!(check_predicate_value(&_predicate(PredicateTypes::LE, i32::MAX), "-5").is_err());
+        assert!(check_predicate_value(&_predicate(PredicateTypes::GT, i32::MAX), "0").is_err());
+        assert!(check_predicate_value(&_predicate(PredicateTypes::GE, 0), "4294967296").is_err());
+    }
+
+    #[test]
+    fn signed_range_works() {
+        let range = SignedRange::new(-3_000_000_000, -1_000_000_000).unwrap();
+        let raw = range.encode(-2_000_000_000).unwrap();
+        assert_eq!(raw, "1000000000");
+        assert_eq!(range.decode(&raw).unwrap(), -2_000_000_000);
+        let predicate = range
+            .predicate("age", PredicateTypes::GE, -2_500_000_000)
+            .unwrap();
+        check_predicate_value(&predicate, &encode_credential_attribute(&raw).unwrap()).unwrap();
+        assert!(range.encode(0).is_err());
+        assert!(SignedRange::new(i32::MIN as i64, i32::MAX as i64).is_err());
+    }
+
+    #[test]
+    fn check_same_credential_works() {
+        let pres_req: PresentationRequestPayload = serde_json::from_value(serde_json::json!({
+            "nonce": "1234",
+            "name": "proof",
+            "version": "1.0",
+            "requested_attributes": {
+                "ident": {"names": ["name", "photo"]},
+                "other": {"name": "city"}
+            },
+            "requested_predicates": {
+                "adult": {"name": "age", "p_type": ">=", "p_value": 18}
+            },
+            "same_credential": [["ident", "adult"]]
+        }))
+        .unwrap();
+        let requested_proof = |adult_index: u32| -> RequestedProof {
+            serde_json::from_value(serde_json::json!({
+                "revealed_attrs": {},
+                "revealed_attr_groups": {
+                    "ident": {"sub_proof_index": 0, "values": {}}
+                },
+                "self_attested_attrs": {"other": "Paris"},
+                "unrevealed_attrs": {},
+                "predicates": {"adult": {"sub_proof_index": adult_index}}
+            }))
+            .unwrap()
+        };
+        check_same_credential(&pres_req, &requested_proof(0)).unwrap();
+        let err = check_same_credential(&pres_req, &requested_proof(1)).unwrap_err();
+        assert_eq!(err.kind(), crate::error::ErrorKind::ProofRejected);
+    }
 }
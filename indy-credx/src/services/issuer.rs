@@ -5,8 +5,10 @@ use super::types::*;
 use crate::error::Result;
 use crate::services::helpers::*;
 use crate::ursa::cl::{
-    issuer::Issuer as CryptoIssuer, RevocationRegistryDelta as CryptoRevocationRegistryDelta,
-    Witness,
+    issuer::Issuer as CryptoIssuer, CredentialKeyCorrectnessProof as CryptoKeyCorrectnessProof,
+    CredentialPrivateKey as CryptoCredentialPrivateKey,
+    CredentialPublicKey as CryptoCredentialPublicKey,
+    RevocationRegistryDelta as CryptoRevocationRegistryDelta, Witness,
 };
 use indy_data_types::anoncreds::{
     cred_def::{CredentialDefinitionData, CredentialDefinitionV1},
@@ -20,7 +22,12 @@ use indy_data_types::anoncreds::{
 };
 use indy_utils::{Qualifiable, Validatable};
 
-use super::tails::{TailsFileReader, TailsReader, TailsWriter};
+use super::audit::{audit, audit_revocation, credential_values_hash, AuditEvent, AuditRecord};
+use super::keygen::{generate_keys, key_attribute_names, SystemKeyRng};
+use super::tails::{
+    extend_partial_tails, open_partial_tails, RegistryTailsKey, TailsBatches, TailsFileReader,
+    TailsGeneratorReader, TailsReader, TailsWriter,
+};
 
 pub fn create_schema(
     origin_did: &DidValue,
@@ -33,7 +40,7 @@ pub fn create_schema(
         origin_did, schema_name, schema_version, attr_names);
 
     origin_did.validate()?;
-    let schema_id = SchemaId::new(&origin_did, schema_name, schema_version);
+    let schema_id = SchemaId::new(origin_did, schema_name, schema_version);
     let schema = SchemaV1 {
         id: schema_id,
         name: schema_name.to_string(),
@@ -66,7 +73,7 @@ pub fn make_credential_definition_id(
     Ok(CredentialDefinitionId::new(
         origin_did,
         &schema_infix_id,
-        &signature_type.to_str(),
+        signature_type.to_str(),
         tag,
     ))
 }
@@ -82,17 +89,92 @@ pub fn create_credential_definition(
     CredentialDefinitionPrivate,
     CredentialKeyCorrectnessProof,
 )> {
+    create_credential_definition_cancellable(
+        origin_did,
+        schema,
+        tag,
+        signature_type,
+        config,
+        &CancellationToken::new(),
+    )
+}
+
+/// Create a credential definition, aborting with a `Cancelled` error if the
+/// token is cancelled.
+///
+/// The token is checked between the candidates of the search for the primes
+/// of the key, so a cancellation takes effect shortly after it is requested.
+pub fn create_credential_definition_cancellable(
+    origin_did: &DidValue,
+    schema: &Schema,
+    tag: &str,
+    signature_type: SignatureType,
+    config: CredentialDefinitionConfig,
+    cancel: &CancellationToken,
+) -> Result<(
+    CredentialDefinition,
+    CredentialDefinitionPrivate,
+    CredentialKeyCorrectnessProof,
+)> {
+    time_operation!("create_credential_definition");
     trace!(
         "create_credential_definition >>> schema: {:?}, config: {:?}",
         schema,
         config
     );
 
-    let schema = match schema {
-        Schema::SchemaV1(s) => s,
-    };
-    let cred_def_id =
-        make_credential_definition_id(origin_did, &schema.id, schema.seq_no, tag, signature_type)?;
+    let Schema::SchemaV1(schema_v1) = schema;
+    let cred_def_id = make_credential_definition_id(
+        origin_did,
+        &schema_v1.id,
+        schema_v1.seq_no,
+        tag,
+        signature_type,
+    )?;
+    let attr_names = key_attribute_names(&schema_v1.attr_names.0)?;
+
+    cancel.check()?;
+    let (credential_public_key, credential_private_key, correctness_proof) = generate_keys(
+        &mut SystemKeyRng,
+        &attr_names,
+        config.support_revocation,
+        cancel,
+    )?;
+
+    let (cred_def, cred_def_private, cred_key_proof) = assemble_credential_definition(
+        cred_def_id,
+        schema,
+        tag,
+        signature_type,
+        credential_public_key,
+        credential_private_key,
+        correctness_proof,
+    )?;
+    trace!(
+        "create_credential_definition <<< cred_def: {:?}, cred_def: {:?}, key_correctness_proof: {:?}",
+        cred_def,
+        secret!(&cred_def_private),
+        cred_key_proof
+    );
+
+    Ok((cred_def, cred_def_private, cred_key_proof))
+}
+
+/// Wrap generated CL keys in the credential definition objects for a schema
+pub(crate) fn assemble_credential_definition(
+    cred_def_id: CredentialDefinitionId,
+    schema: &Schema,
+    tag: &str,
+    signature_type: SignatureType,
+    credential_public_key: CryptoCredentialPublicKey,
+    credential_private_key: CryptoCredentialPrivateKey,
+    correctness_proof: CryptoKeyCorrectnessProof,
+) -> Result<(
+    CredentialDefinition,
+    CredentialDefinitionPrivate,
+    CredentialKeyCorrectnessProof,
+)> {
+    let Schema::SchemaV1(schema) = schema;
 
     // Indy-Node requires the published schema ID field is the schema sequence number
     let schema_id = SchemaId(
@@ -103,16 +185,6 @@ pub fn create_credential_definition(
             .unwrap_or(schema.id.0.clone()),
     );
 
-    let credential_schema = build_credential_schema(&schema.attr_names.0)?;
-    let non_credential_schema = build_non_credential_schema()?;
-
-    let (credential_public_key, credential_private_key, correctness_proof) =
-        CryptoIssuer::new_credential_def(
-            &credential_schema,
-            &non_credential_schema,
-            config.support_revocation,
-        )?;
-
     let cred_def = CredentialDefinition::CredentialDefinitionV1(CredentialDefinitionV1 {
         id: cred_def_id,
         schema_id,
@@ -123,20 +195,12 @@ pub fn create_credential_definition(
             revocation: credential_public_key.get_revocation_key()?.clone(),
         },
     });
-
     let cred_def_private = CredentialDefinitionPrivate {
         value: credential_private_key,
     };
     let cred_key_proof = CredentialKeyCorrectnessProof {
         value: correctness_proof,
     };
-    trace!(
-        "create_credential_definition <<< cred_def: {:?}, cred_def: {:?}, key_correctness_proof: {:?}",
-        cred_def,
-        secret!(&cred_def_private),
-        cred_key_proof
-    );
-
     Ok((cred_def, cred_def_private, cred_key_proof))
 }
 
@@ -146,9 +210,7 @@ pub fn make_revocation_registry_id(
     tag: &str,
     rev_reg_type: RegistryType,
 ) -> Result<RevocationRegistryId> {
-    let cred_def = match cred_def {
-        CredentialDefinition::CredentialDefinitionV1(c) => c,
-    };
+    let CredentialDefinition::CredentialDefinitionV1(cred_def) = cred_def;
 
     let origin_did = match (origin_did.get_method(), cred_def.id.get_method()) {
         (None, Some(_)) => {
@@ -161,9 +223,9 @@ pub fn make_revocation_registry_id(
     };
 
     Ok(RevocationRegistryId::new(
-        &origin_did,
+        origin_did,
         &cred_def.id,
-        &rev_reg_type.to_str(),
+        rev_reg_type.to_str(),
         tag,
     ))
 }
@@ -183,16 +245,170 @@ pub fn create_revocation_registry<TW>(
     RevocationRegistryDelta,
 )>
 where
-    TW: TailsWriter,
+    TW: TailsWriter + ?Sized,
+{
+    build_revocation_registry(
+        origin_did,
+        cred_def,
+        tag,
+        rev_reg_type,
+        issuance_type,
+        max_cred_num,
+        Some(&mut |batches: &mut TailsBatches| tails_writer.write_batches(batches)),
+    )
+}
+
+/// Create a revocation registry without generating the tails upfront.
+///
+/// Tails are derived on demand from the registry private key using a
+/// `TailsGeneratorReader`, so credentials may be issued immediately. The
+/// returned definition has an empty tails location and hash: the tails
+/// must be written with `write_revocation_registry_tails` before the
+/// definition is published for holders. They may first be generated in
+/// steps with `extend_revocation_registry_tails`, and then written with
+/// `complete_revocation_registry_tails`. Registries using
+/// `ISSUANCE_BY_DEFAULT` still touch every tail when the initial registry
+/// is computed, so this mainly benefits `ISSUANCE_ON_DEMAND` registries.
+pub fn create_revocation_registry_lazy(
+    origin_did: &DidValue,
+    cred_def: &CredentialDefinition,
+    tag: &str,
+    rev_reg_type: RegistryType,
+    issuance_type: IssuanceType,
+    max_cred_num: u32,
+) -> Result<(
+    RevocationRegistryDefinition,
+    RevocationRegistryDefinitionPrivate,
+    RevocationRegistry,
+    RevocationRegistryDelta,
+)> {
+    build_revocation_registry(
+        origin_did,
+        cred_def,
+        tag,
+        rev_reg_type,
+        issuance_type,
+        max_cred_num,
+        None,
+    )
+}
+
+/// Write the tails for a revocation registry, deriving them from the
+/// registry private key, and return the definition updated with the tails
+/// location and hash.
+pub fn write_revocation_registry_tails<TW>(
+    cred_def: &CredentialDefinition,
+    rev_reg_def: &RevocationRegistryDefinition,
+    rev_reg_def_private: &RevocationRegistryDefinitionPrivate,
+    tails_writer: &mut TW,
+) -> Result<RevocationRegistryDefinition>
+where
+    TW: TailsWriter + ?Sized,
 {
+    write_registry_tails(
+        cred_def,
+        rev_reg_def,
+        rev_reg_def_private,
+        None,
+        tails_writer,
+    )
+}
+
+/// Generate up to `count` more tails for a revocation registry, appending
+/// them to the partial tails file at `partial_path`, which is created when
+/// missing. Returns the number of tails still to be generated. This allows
+/// the tails of a registry created with `create_revocation_registry_lazy`
+/// to be generated in steps, before they are written with
+/// `complete_revocation_registry_tails`.
+pub fn extend_revocation_registry_tails(
+    cred_def: &CredentialDefinition,
+    rev_reg_def: &RevocationRegistryDefinition,
+    rev_reg_def_private: &RevocationRegistryDefinitionPrivate,
+    partial_path: &str,
+    count: u32,
+) -> Result<u32> {
+    let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) = rev_reg_def;
+    let key = RegistryTailsKey::new(
+        cred_def,
+        rev_reg_def_private,
+        rev_reg_def.value.max_cred_num,
+    )?;
+    extend_partial_tails(&key, partial_path, count)
+}
+
+/// Write the tails for a revocation registry like
+/// `write_revocation_registry_tails`, reading the tails already generated
+/// into the partial tails file at `partial_path` and deriving only the rest.
+/// The partial tails file is left in place.
+pub fn complete_revocation_registry_tails<TW>(
+    cred_def: &CredentialDefinition,
+    rev_reg_def: &RevocationRegistryDefinition,
+    rev_reg_def_private: &RevocationRegistryDefinitionPrivate,
+    partial_path: &str,
+    tails_writer: &mut TW,
+) -> Result<RevocationRegistryDefinition>
+where
+    TW: TailsWriter + ?Sized,
+{
+    write_registry_tails(
+        cred_def,
+        rev_reg_def,
+        rev_reg_def_private,
+        Some(partial_path),
+        tails_writer,
+    )
+}
+
+fn write_registry_tails<TW>(
+    cred_def: &CredentialDefinition,
+    rev_reg_def: &RevocationRegistryDefinition,
+    rev_reg_def_private: &RevocationRegistryDefinitionPrivate,
+    partial_path: Option<&str>,
+    tails_writer: &mut TW,
+) -> Result<RevocationRegistryDefinition>
+where
+    TW: TailsWriter + ?Sized,
+{
+    let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) = rev_reg_def;
+    let key = RegistryTailsKey::new(
+        cred_def,
+        rev_reg_def_private,
+        rev_reg_def.value.max_cred_num,
+    )?;
+    let stored = partial_path
+        .map(|path| open_partial_tails(path, &key))
+        .transpose()?;
+    let mut batches = TailsBatches::from_registry(&key, 0, key.tail_count(), stored);
+    let (tails_location, tails_hash) = tails_writer.write_batches(&mut batches)?;
+    let mut rev_reg_def = rev_reg_def.clone();
+    rev_reg_def.value.tails_location = tails_location;
+    rev_reg_def.value.tails_hash = tails_hash;
+    Ok(RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def))
+}
+
+#[allow(clippy::type_complexity)]
+fn build_revocation_registry(
+    origin_did: &DidValue,
+    cred_def: &CredentialDefinition,
+    tag: &str,
+    rev_reg_type: RegistryType,
+    issuance_type: IssuanceType,
+    max_cred_num: u32,
+    write_tails: Option<&mut dyn FnMut(&mut TailsBatches) -> Result<(String, String)>>,
+) -> Result<(
+    RevocationRegistryDefinition,
+    RevocationRegistryDefinitionPrivate,
+    RevocationRegistry,
+    RevocationRegistryDelta,
+)> {
+    time_operation!("create_revocation_registry");
     trace!("create_revocation_registry >>> origin_did: {:?}, cred_def: {:?}, tag: {:?}, max_cred_num: {:?}, rev_reg_type: {:?}, issuance_type: {:?}",
             origin_did, cred_def, tag, max_cred_num, rev_reg_type, issuance_type);
 
     let rev_reg_id = make_revocation_registry_id(origin_did, cred_def, tag, rev_reg_type)?;
 
-    let cred_def = match cred_def {
-        CredentialDefinition::CredentialDefinitionV1(c) => c,
-    };
+    let full_cred_def = cred_def;
+    let CredentialDefinition::CredentialDefinitionV1(cred_def) = cred_def;
     let credential_pub_key = cred_def.get_public_key().map_err(err_map!(
         Unexpected,
         "Error fetching public key from credential definition"
@@ -207,7 +423,19 @@ where
         accum_key: revoc_key_pub,
     };
 
-    let (tails_location, tails_hash) = tails_writer.write(&mut rev_tails_generator)?;
+    let revoc_def_priv = RevocationRegistryDefinitionPrivate {
+        value: revoc_key_priv,
+    };
+
+    let (tails_location, tails_hash) = match write_tails {
+        Some(write_tails) => {
+            let key = RegistryTailsKey::new(full_cred_def, &revoc_def_priv, max_cred_num)?;
+            let mut batches = TailsBatches::from_registry(&key, 0, key.tail_count(), None)
+                .with_generator(&mut rev_tails_generator);
+            write_tails(&mut batches)?
+        }
+        None => (String::new(), String::new()),
+    };
 
     let revoc_reg_def_value = RevocationRegistryDefinitionValue {
         max_cred_num,
@@ -233,9 +461,13 @@ where
 
     // now update registry to reflect issuance-by-default
     let (revoc_reg, revoc_init_delta) = if issuance_type == IssuanceType::ISSUANCE_BY_DEFAULT {
-        let tails_reader = TailsFileReader::new(&tails_location);
-        let issued = BTreeSet::from_iter((1..=max_cred_num).into_iter());
-        update_revocation_registry(
+        let tails_reader = if tails_location.is_empty() {
+            TailsGeneratorReader::new(full_cred_def, &revoc_reg_def, &revoc_def_priv)?
+        } else {
+            TailsFileReader::new(&tails_location)
+        };
+        let issued = BTreeSet::from_iter(1..=max_cred_num);
+        update_registry(
             &revoc_reg_def,
             &revoc_reg,
             issued,
@@ -247,10 +479,6 @@ where
         (revoc_reg, delta)
     };
 
-    let revoc_def_priv = RevocationRegistryDefinitionPrivate {
-        value: revoc_key_priv,
-    };
-
     trace!(
         "create_revocation_registry <<< revoc_reg_def: {:?}, private: {:?}, revoc_reg: {:?}",
         revoc_reg_def,
@@ -268,9 +496,23 @@ pub fn update_revocation_registry(
     revoked: BTreeSet<u32>,
     tails_reader: &TailsReader,
 ) -> Result<(RevocationRegistry, RevocationRegistryDelta)> {
-    let rev_reg_def = match rev_reg_def {
-        RevocationRegistryDefinition::RevocationRegistryDefinitionV1(v1) => v1,
-    };
+    let restored = issued.clone();
+    let revoked_idx = revoked.clone();
+    let result = update_registry(rev_reg_def, rev_reg, issued, revoked, tails_reader)?;
+    audit_revocation(rev_reg_def, AuditEvent::Restored, restored)?;
+    audit_revocation(rev_reg_def, AuditEvent::Revoked, revoked_idx)?;
+    Ok(result)
+}
+
+fn update_registry(
+    rev_reg_def: &RevocationRegistryDefinition,
+    rev_reg: &RevocationRegistry,
+    issued: BTreeSet<u32>,
+    revoked: BTreeSet<u32>,
+    tails_reader: &TailsReader,
+) -> Result<(RevocationRegistry, RevocationRegistryDelta)> {
+    time_operation!("update_revocation_registry");
+    let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) = rev_reg_def;
     let mut rev_reg = match rev_reg {
         RevocationRegistry::RevocationRegistryV1(v1) => v1.value.clone(),
     };
@@ -295,13 +537,12 @@ pub fn create_credential_offer(
     cred_def: &CredentialDefinition,
     correctness_proof: &CredentialKeyCorrectnessProof,
 ) -> Result<CredentialOffer> {
+    time_operation!("create_credential_offer");
     trace!("create_credential_offer >>> cred_def: {:?}", cred_def);
 
     let nonce = Nonce::new().map_err(err_map!(Unexpected, "Error creating nonce"))?;
 
-    let cred_def = match cred_def {
-        CredentialDefinition::CredentialDefinitionV1(c) => c,
-    };
+    let CredentialDefinition::CredentialDefinitionV1(cred_def) = cred_def;
 
     let key_correctness_proof = correctness_proof
         .try_clone()
@@ -318,6 +559,47 @@ pub fn create_credential_offer(
     Ok(credential_offer)
 }
 
+/// Create `count` credential offers for the same credential definition, each
+/// with a distinct nonce
+pub fn create_credential_offers(
+    schema_id: &SchemaId,
+    cred_def: &CredentialDefinition,
+    correctness_proof: &CredentialKeyCorrectnessProof,
+    count: usize,
+) -> Result<Vec<CredentialOffer>> {
+    (0..count)
+        .map(|_| create_credential_offer(schema_id, cred_def, correctness_proof))
+        .collect()
+}
+
+/// Prepare the values for a derived credential containing a subset of the
+/// attributes of an existing credential. Raw values and encodings are copied
+/// unchanged so that the derived credential is consistent with the original.
+/// Attribute names are matched using the same normalization as presentation
+/// requests, and are output as given.
+pub fn derive_credential_values<'a>(
+    values: &CredentialValues,
+    attr_names: impl IntoIterator<Item = &'a str>,
+) -> Result<CredentialValues> {
+    let mut derived = CredentialValues::default();
+    for name in attr_names {
+        let common = attr_common_view(name);
+        let value = values
+            .0
+            .iter()
+            .find(|(attr, _)| attr_common_view(attr) == common)
+            .map(|(_, value)| value)
+            .ok_or_else(|| err_msg!("Credential attribute not found: {}", name))?;
+        derived.0.insert(name.to_string(), value.clone());
+    }
+    if derived.0.is_empty() {
+        return Err(err_msg!(
+            "Cannot derive credential values with no attribute"
+        ));
+    }
+    Ok(derived)
+}
+
 pub fn create_credential(
     cred_def: &CredentialDefinition,
     cred_def_private: &CredentialDefinitionPrivate,
@@ -330,6 +612,7 @@ pub fn create_credential(
     Option<RevocationRegistry>,
     Option<RevocationRegistryDelta>,
 )> {
+    time_operation!("create_credential");
     trace!("create_credential >>> cred_def: {:?}, cred_def_private: {:?}, cred_offer.nonce: {:?}, cred_request: {:?},\
             cred_values: {:?}, revocation_config: {:?}",
             cred_def, secret!(&cred_def_private), &cred_offer.nonce, &cred_request, secret!(&cred_values), revocation_config,
@@ -343,7 +626,25 @@ pub fn create_credential(
             ))?
         }
     };
+    let committed = cred_request.committed_attributes().map_err(err_map!(
+        Input,
+        "Invalid blinded secrets in credential request"
+    ))?;
+    if let Some(attr) = cred_values
+        .0
+        .keys()
+        .find(|attr| committed.contains(&attr_common_view(attr)))
+    {
+        return Err(err_msg!(
+            Input,
+            "A value was provided for the attribute committed by the holder: {}",
+            attr
+        ));
+    }
     let credential_values = build_credential_values(&cred_values.0, None)?;
+    let revocation_idx = revocation_config
+        .as_ref()
+        .map(|revocation| revocation.registry_idx);
 
     let (
         credential_signature,
@@ -381,7 +682,7 @@ pub fn create_credential(
                 )?;
 
             let cred_rev_reg_id = match cred_offer.method_name.as_ref() {
-                Some(ref _method_name) => Some(reg_reg_id.to_unqualified()),
+                Some(_method_name) => Some(reg_reg_id.to_unqualified()),
                 _ => Some(reg_reg_id.clone()),
             };
             let witness = {
@@ -425,6 +726,19 @@ pub fn create_credential(
         }
     };
 
+    let rev_idx = rev_reg_id.as_ref().and(revocation_idx);
+    audit(|timestamp| {
+        Ok(AuditRecord {
+            event: AuditEvent::Issued,
+            timestamp,
+            cred_def_id: cred_offer.cred_def_id.clone(),
+            schema_id: Some(cred_offer.schema_id.clone()),
+            rev_reg_id: rev_reg_id.clone(),
+            rev_idx,
+            values_hash: Some(credential_values_hash(&cred_values)?),
+        })
+    })?;
+
     let credential = Credential {
         schema_id: cred_offer.schema_id.clone(),
         cred_def_id: cred_offer.cred_def_id.clone(),
@@ -434,6 +748,7 @@ pub fn create_credential(
         signature_correctness_proof,
         rev_reg: rev_reg.clone(),
         witness,
+        display: None,
     };
 
     let rev_reg = rev_reg
@@ -453,12 +768,35 @@ pub fn create_credential(
     Ok((credential, rev_reg, rev_reg_delta))
 }
 
+/// Issue a batch of credentials which do not support revocation, returning
+/// the credential for each offer, request and values in the order of the
+/// inputs. With the `parallel` feature the batch is divided between worker
+/// threads.
+pub fn create_credentials(
+    cred_def: &CredentialDefinition,
+    cred_def_private: &CredentialDefinitionPrivate,
+    requests: &[(&CredentialOffer, &CredentialRequest, CredentialValues)],
+) -> Vec<Result<Credential>> {
+    super::parallel::try_map(requests, |(cred_offer, cred_request, cred_values)| {
+        create_credential(
+            cred_def,
+            cred_def_private,
+            cred_offer,
+            cred_request,
+            cred_values.clone(),
+            None,
+        )
+        .map(|(credential, _, _)| credential)
+    })
+}
+
 pub fn revoke_credential(
     rev_reg_def: &RevocationRegistryDefinition,
     rev_reg: &RevocationRegistry,
     cred_rev_idx: u32,
     tails_reader: &TailsReader,
 ) -> Result<(RevocationRegistry, RevocationRegistryDelta)> {
+    time_operation!("revoke_credential");
     trace!(
         "revoke >>> rev_reg_def: {:?}, rev_reg: {:?}, cred_rev_idx: {:?}",
         rev_reg_def,
@@ -475,6 +813,8 @@ pub fn revoke_credential(
     let rev_reg_delta =
         CryptoIssuer::revoke_credential(&mut rev_reg, max_cred_num, cred_rev_idx, tails_reader)?;
 
+    audit_revocation(rev_reg_def, AuditEvent::Revoked, Some(cred_rev_idx))?;
+
     let new_rev_reg =
         RevocationRegistry::RevocationRegistryV1(RevocationRegistryV1 { value: rev_reg });
     let delta = RevocationRegistryDelta::RevocationRegistryDeltaV1(RevocationRegistryDeltaV1 {
@@ -492,6 +832,7 @@ pub fn recover_credential(
     cred_rev_idx: u32,
     tails_reader: &TailsReader,
 ) -> Result<(RevocationRegistry, RevocationRegistryDelta)> {
+    time_operation!("recover_credential");
     trace!(
         "recover >>> rev_reg_def: {:?}, rev_reg: {:?}, cred_rev_idx: {:?}",
         rev_reg_def,
@@ -508,6 +849,8 @@ pub fn recover_credential(
     let rev_reg_delta =
         CryptoIssuer::recovery_credential(&mut rev_reg, max_cred_num, cred_rev_idx, tails_reader)?;
 
+    audit_revocation(rev_reg_def, AuditEvent::Restored, Some(cred_rev_idx))?;
+
     let new_rev_reg =
         RevocationRegistry::RevocationRegistryV1(RevocationRegistryV1 { value: rev_reg });
     let delta = RevocationRegistryDelta::RevocationRegistryDeltaV1(RevocationRegistryDeltaV1 {
@@ -605,4 +948,161 @@ mod tests {
             "99398763056634537812744552006896172984671876672520535998211840060697129507206"
         );
     }
+
+    #[test]
+    fn test_create_credential_definition_cancelled() {
+        let origin_did = DidValue::new("55GkHamhTU1ZbTbV2ab9DE", None);
+        let schema = create_schema(
+            &origin_did,
+            "schema",
+            "1.0",
+            AttributeNames::from(vec!["name".to_owned()]),
+            None,
+        )
+        .unwrap();
+        let cancel = CancellationToken::new();
+        cancel.clone().cancel();
+        assert!(cancel.is_cancelled());
+        assert_kind!(
+            Cancelled,
+            create_credential_definition_cancellable(
+                &origin_did,
+                &schema,
+                "tag",
+                SignatureType::CL,
+                CredentialDefinitionConfig::new(false),
+                &cancel,
+            )
+        );
+    }
+
+    #[test]
+    fn test_create_credential_offers() {
+        use crate::services::test_utils::CredDefFixture;
+
+        let CredDefFixture {
+            schema,
+            cred_def,
+            key_proof,
+            ..
+        } = CredDefFixture::with_attributes(vec!["name"]).unwrap();
+        let Schema::SchemaV1(schema) = schema;
+        let offers = create_credential_offers(&schema.id, &cred_def, &key_proof, 3).unwrap();
+        assert_eq!(offers.len(), 3);
+        let nonces = offers
+            .iter()
+            .map(|offer| &offer.nonce)
+            .collect::<HashSet<_>>();
+        assert_eq!(nonces.len(), 3);
+    }
+
+    #[test]
+    fn test_create_credentials() {
+        use crate::services::prover;
+        use crate::services::test_utils::{credential_values, CredDefFixture, HolderFixture};
+
+        let fixture = CredDefFixture::with_attributes(vec!["name"]).unwrap();
+        let (cred_def, key_proof) = (&fixture.cred_def, &fixture.key_proof);
+        let holder = HolderFixture::new().unwrap();
+        let names = ["Alex", "Sam", "Kim"];
+        let offers =
+            create_credential_offers(fixture.schema.id(), cred_def, key_proof, names.len())
+                .unwrap();
+        let issuance = offers
+            .iter()
+            .zip(names.iter())
+            .map(|(offer, name)| {
+                let (request, _) = prover::create_credential_request(
+                    &holder.did,
+                    cred_def,
+                    &holder.master_secret,
+                    "default",
+                    offer,
+                )
+                .unwrap();
+                (
+                    offer,
+                    request,
+                    credential_values(&[("name", name)]).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let requests = issuance
+            .iter()
+            .map(|(offer, request, values)| (*offer, request, values.clone()))
+            .collect::<Vec<_>>();
+        let credentials = create_credentials(cred_def, &fixture.cred_def_private, &requests);
+        assert_eq!(credentials.len(), names.len());
+        for (credential, name) in credentials.into_iter().zip(names.iter()) {
+            let credential = credential.unwrap();
+            assert_eq!(credential.values.0["name"].raw, *name);
+            assert!(credential.rev_reg_id.is_none());
+        }
+    }
+
+    #[test]
+    fn test_committed_attributes() {
+        use crate::services::prover;
+        use crate::services::test_utils::{credential_values, CredDefFixture, HolderFixture};
+
+        let fixture = CredDefFixture::with_attributes(vec!["name", "age"]).unwrap();
+        let holder = HolderFixture::new().unwrap();
+        let offer =
+            create_credential_offer(fixture.schema.id(), &fixture.cred_def, &fixture.key_proof)
+                .unwrap();
+        let (request, _) = prover::create_committed_credential_request(
+            &holder.did,
+            &fixture.cred_def,
+            &holder.master_secret,
+            "default",
+            &offer,
+            credential_values(&[("age", "28")]).unwrap(),
+        )
+        .unwrap();
+
+        // the committed attributes are read from the serialized blinded
+        // secrets, whose layout is fixed by the pinned version of ursa
+        let blinded_ms = serde_json::to_value(&request.blinded_ms).unwrap();
+        let fields = blinded_ms
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(
+            fields,
+            BTreeSet::from(["u", "ur", "hidden_attributes", "committed_attributes"])
+        );
+        assert_eq!(
+            request.committed_attributes().unwrap(),
+            BTreeSet::from(["age".to_string()])
+        );
+
+        let (request, _) = prover::create_credential_request(
+            &holder.did,
+            &fixture.cred_def,
+            &holder.master_secret,
+            "default",
+            &offer,
+        )
+        .unwrap();
+        assert!(request.committed_attributes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_derive_credential_values() {
+        let mut values = MakeCredentialValues::default();
+        values.add_raw("First Name", "Alex").unwrap();
+        values.add_raw("age", "28").unwrap();
+        values.add_raw("height", "175").unwrap();
+        let values = CredentialValues::from(values);
+
+        let derived = derive_credential_values(&values, vec!["firstname", "age"]).unwrap();
+        assert_eq!(derived.0.len(), 2);
+        assert_eq!(derived.0["firstname"], values.0["First Name"]);
+        assert_eq!(derived.0["age"], values.0["age"]);
+
+        assert!(derive_credential_values(&values, vec!["sex"]).is_err());
+        assert!(derive_credential_values(&values, vec![]).is_err());
+    }
 }
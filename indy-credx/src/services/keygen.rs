@@ -0,0 +1,290 @@
+//! Generation of CL credential definition keys.
+//!
+//! The keys are generated as the CL library would generate them, but the
+//! search for the safe primes of the modulus checks a cancellation token
+//! between candidates. A cancelled credential definition therefore stops at
+//! the next candidate, rather than once the search has finished. The random
+//! values are taken from a `KeyRng`: the system generator when creating a
+//! credential definition, or a seeded generator for test fixtures.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use rand::{rngs::OsRng, RngCore};
+
+use super::helpers::attr_common_view;
+use super::types::CancellationToken;
+use crate::error::Result;
+use crate::ursa::{
+    bn::{BigNumber, BigNumberContext},
+    cl::{
+        hash::get_hash_as_int, CredentialKeyCorrectnessProof as CryptoKeyCorrectnessProof,
+        CredentialPrimaryPublicKey, CredentialPrivateKey as CryptoCredentialPrivateKey,
+        CredentialPublicKey as CryptoCredentialPublicKey, CredentialRevocationPublicKey,
+    },
+    pair::{GroupOrderElement, PointG1, PointG2},
+};
+
+/// The size in bytes of each safe prime factor of the modulus
+const PRIME_BYTES: usize = 128;
+
+/// The small primes used to sieve safe prime candidates
+const SIEVE_LIMIT: u32 = 2048;
+
+/// A source of the random bytes used in key generation
+pub(crate) trait KeyRng {
+    fn fill(&mut self, out: &mut [u8]);
+}
+
+/// The system random number generator
+pub(crate) struct SystemKeyRng;
+
+impl KeyRng for SystemKeyRng {
+    fn fill(&mut self, out: &mut [u8]) {
+        OsRng.fill_bytes(out);
+    }
+}
+
+/// A number in the range `[0, bound)`, with negligible bias
+fn below<R: KeyRng>(
+    rng: &mut R,
+    bound: &BigNumber,
+    ctx: &mut BigNumberContext,
+) -> Result<BigNumber> {
+    let mut bytes = vec![0u8; (bound.num_bits()? as usize).div_ceil(8) + 16];
+    rng.fill(&mut bytes);
+    Ok(BigNumber::from_bytes(&bytes)?.modulus(bound, Some(ctx))?)
+}
+
+/// A safe prime `2p + 1` of `PRIME_BYTES` bytes, returned with `p`. The
+/// token is checked before each candidate which passes the sieve is tested
+/// for primality.
+fn safe_prime<R: KeyRng>(
+    rng: &mut R,
+    sieve: &[u32],
+    ctx: &mut BigNumberContext,
+    cancel: &CancellationToken,
+) -> Result<(BigNumber, BigNumber)> {
+    loop {
+        // the top two bits of the safe prime are set so that the
+        // modulus has the full length
+        let mut bytes = [0u8; PRIME_BYTES];
+        rng.fill(&mut bytes);
+        bytes[0] = (bytes[0] & 0x7f) | 0x60;
+        bytes[PRIME_BYTES - 1] |= 1;
+        let residues: Vec<u32> = sieve
+            .iter()
+            .map(|m| {
+                bytes
+                    .iter()
+                    .fold(0u32, |rem, byte| (rem * 256 + u32::from(*byte)) % m)
+            })
+            .collect();
+        let base = BigNumber::from_bytes(&bytes)?;
+        for offset in (0..u32::MAX / 4).step_by(2) {
+            let sieved = sieve.iter().zip(residues.iter()).all(|(m, rem)| {
+                let rem = (u64::from(*rem) + u64::from(offset)) % u64::from(*m);
+                rem != 0 && (rem * 2 + 1) % u64::from(*m) != 0
+            });
+            if !sieved {
+                continue;
+            }
+            cancel.check()?;
+            let mut half = base.try_clone()?;
+            half.add_word(offset)?;
+            if half.num_bits()? != PRIME_BYTES as i32 * 8 - 1 {
+                break;
+            }
+            if !half.is_prime(Some(ctx))? {
+                continue;
+            }
+            let mut prime = half.lshift1()?;
+            prime.add_word(1)?;
+            if prime.is_prime(Some(ctx))? {
+                return Ok((prime, half));
+            }
+        }
+    }
+}
+
+/// The odd primes below `SIEVE_LIMIT`
+fn sieve_primes() -> Vec<u32> {
+    (3..SIEVE_LIMIT)
+        .step_by(2)
+        .filter(|n| {
+            (3..)
+                .step_by(2)
+                .take_while(|d| d * d <= *n)
+                .all(|d| n % d != 0)
+        })
+        .collect()
+}
+
+/// The names of the attributes signed under the keys of a schema, including
+/// the master secret
+pub(crate) fn key_attribute_names(attr_names: &HashSet<String>) -> Result<BTreeSet<String>> {
+    let mut names: BTreeSet<String> = attr_names
+        .iter()
+        .map(|attr| attr_common_view(attr))
+        .collect();
+    if names.is_empty() {
+        return Err(err_msg!("List of attributes is empty"));
+    }
+    names.insert("master_secret".to_string());
+    Ok(names)
+}
+
+/// Generate the keys and key correctness proof of a credential definition,
+/// taking each random value of the primary keys from `rng` in turn.
+/// Revocation keys are always drawn from the system generator.
+pub(crate) fn generate_keys<R: KeyRng>(
+    rng: &mut R,
+    attr_names: &BTreeSet<String>,
+    support_revocation: bool,
+    cancel: &CancellationToken,
+) -> Result<(
+    CryptoCredentialPublicKey,
+    CryptoCredentialPrivateKey,
+    CryptoKeyCorrectnessProof,
+)> {
+    let mut ctx = BigNumber::new_context()?;
+    let sieve = sieve_primes();
+
+    let (p_safe, p) = safe_prime(rng, &sieve, &mut ctx, cancel)?;
+    let (q_safe, q) = safe_prime(rng, &sieve, &mut ctx, cancel)?;
+    let n = p_safe.mul(&q_safe, Some(&mut ctx))?;
+    let s = below(rng, &n, &mut ctx)?
+        .sqr(Some(&mut ctx))?
+        .modulus(&n, Some(&mut ctx))?;
+
+    let mut x_bound = p.mul(&q, Some(&mut ctx))?;
+    x_bound.sub_word(3)?;
+    let mut gen_x = |ctx: &mut BigNumberContext| -> Result<BigNumber> {
+        let mut x = below(rng, &x_bound, ctx)?;
+        x.add_word(2)?;
+        Ok(x)
+    };
+
+    let xz = gen_x(&mut ctx)?;
+    let mut xr = Vec::with_capacity(attr_names.len());
+    for name in attr_names {
+        xr.push((name, gen_x(&mut ctx)?));
+    }
+    let z = s.mod_exp(&xz, &n, Some(&mut ctx))?;
+    let mut r = Vec::with_capacity(xr.len());
+    for (name, x) in xr.iter() {
+        r.push((name, s.mod_exp(x, &n, Some(&mut ctx))?));
+    }
+    let rctxt = s.mod_exp(&gen_x(&mut ctx)?, &n, Some(&mut ctx))?;
+
+    let xz_tilda = gen_x(&mut ctx)?;
+    let mut xr_tilda = Vec::with_capacity(xr.len());
+    for _ in xr.iter() {
+        xr_tilda.push(gen_x(&mut ctx)?);
+    }
+    let z_tilda = s.mod_exp(&xz_tilda, &n, Some(&mut ctx))?;
+    let mut values = z.to_bytes()?;
+    for (_, value) in r.iter() {
+        values.extend_from_slice(&value.to_bytes()?);
+    }
+    values.extend_from_slice(&z_tilda.to_bytes()?);
+    for x in xr_tilda.iter() {
+        values.extend_from_slice(&s.mod_exp(x, &n, Some(&mut ctx))?.to_bytes()?);
+    }
+    let c = get_hash_as_int(&[values])?;
+    let xz_cap = c.mul(&xz, Some(&mut ctx))?.add(&xz_tilda)?;
+    let mut xr_cap = Vec::with_capacity(xr.len());
+    for ((name, x), x_tilda) in xr.iter().zip(xr_tilda.iter()) {
+        xr_cap.push((name, c.mul(x, Some(&mut ctx))?.add(x_tilda)?.to_dec()?));
+    }
+
+    let primary: CredentialPrimaryPublicKey = serde_json::from_value(serde_json::json!({
+        "n": n.to_dec()?,
+        "s": s.to_dec()?,
+        "r": r
+            .iter()
+            .map(|(name, value)| Ok((name.to_string(), value.to_dec()?)))
+            .collect::<Result<BTreeMap<String, String>>>()?,
+        "rctxt": rctxt.to_dec()?,
+        "z": z.to_dec()?,
+    }))
+    .map_err(err_map!(Unexpected, "Error building public key"))?;
+    let (revocation, r_key) = if support_revocation {
+        let (public, private) = revocation_keys()?;
+        (Some(public), private)
+    } else {
+        (None, serde_json::Value::Null)
+    };
+    let public_key = CryptoCredentialPublicKey::build_from_parts(&primary, revocation.as_ref())?;
+    let private_key = serde_json::from_value(serde_json::json!({
+        "p_key": {"p": p.to_dec()?, "q": q.to_dec()?},
+        "r_key": r_key,
+    }))
+    .map_err(err_map!(Unexpected, "Error building private key"))?;
+    let correctness_proof = serde_json::from_value(serde_json::json!({
+        "c": c.to_dec()?,
+        "xz_cap": xz_cap.to_dec()?,
+        "xr_cap": xr_cap,
+    }))
+    .map_err(err_map!(Unexpected, "Error building key correctness proof"))?;
+    Ok((public_key, private_key, correctness_proof))
+}
+
+/// Generate revocation keys as the CL library does, returning the private
+/// key in its serialized form
+fn revocation_keys() -> Result<(CredentialRevocationPublicKey, serde_json::Value)> {
+    let h = PointG1::new()?;
+    let h0 = PointG1::new()?;
+    let h1 = PointG1::new()?;
+    let h2 = PointG1::new()?;
+    let htilde = PointG1::new()?;
+    let g = PointG1::new()?;
+    let u = PointG2::new()?;
+    let h_cap = PointG2::new()?;
+    let x = GroupOrderElement::new()?;
+    let sk = GroupOrderElement::new()?;
+    let g_dash = PointG2::new()?;
+    let pk = g.mul(&sk)?;
+    let y = h_cap.mul(&x)?;
+
+    let public = serde_json::from_value(serde_json::json!({
+        "g": g,
+        "g_dash": g_dash,
+        "h": h,
+        "h0": h0,
+        "h1": h1,
+        "h2": h2,
+        "htilde": htilde,
+        "h_cap": h_cap,
+        "u": u,
+        "pk": pk,
+        "y": y,
+    }))
+    .map_err(err_map!(Unexpected, "Error building revocation public key"))?;
+    Ok((public, serde_json::json!({ "x": x, "sk": sk })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cancels the token once the first candidate has been drawn
+    struct CancellingRng(CancellationToken);
+
+    impl KeyRng for CancellingRng {
+        fn fill(&mut self, out: &mut [u8]) {
+            SystemKeyRng.fill(out);
+            self.0.cancel();
+        }
+    }
+
+    #[test]
+    fn safe_prime_search_observes_cancellation() {
+        let cancel = CancellationToken::new();
+        let mut rng = CancellingRng(cancel.clone());
+        let mut ctx = BigNumber::new_context().unwrap();
+        assert_kind!(
+            Cancelled,
+            safe_prime(&mut rng, &sieve_primes(), &mut ctx, &cancel)
+        );
+    }
+}
@@ -0,0 +1,689 @@
+//! Conversion of Indy ledger read replies into credential objects.
+//!
+//! The replies to `GET_SCHEMA`, `GET_CRED_DEF`, `GET_REVOC_REG_DEF` and
+//! `GET_REVOC_REG_DELTA` requests describe the objects in the ledger
+//! transaction format rather than the format used by this library. A
+//! [`LedgerResolver`] performs the conversion for replies obtained by a
+//! [`LedgerReader`], such as a connection to an indy-vdr pool. Passing a
+//! `LedgerResolver` to `verify_presentation_with_resolver` fetches every
+//! object a presentation refers to from the ledger.
+//!
+//! TODO: provide a `LedgerReader` for indy-vdr pools behind a `vdr` feature,
+//! with a `verify_presentation_resolved` taking a pool. indy-vdr 0.4 depends
+//! on a CL signatures crate which requires a newer `num-integer` than the
+//! exact version pinned by ursa 0.3.6, so the two cannot be built together.
+//! Until then, applications implement `LedgerReader` over their own pool.
+//!
+//! In the other direction, a [`LedgerRequest`] is a `SCHEMA` or `CRED_DEF`
+//! write request for an object created by this library. An issuer without
+//! write permission on the ledger names an endorser: the author and the
+//! endorser each sign the same signature input, and the signatures are
+//! merged into the request submitted by the endorser. Where the ledger
+//! enforces a transaction author agreement, the request must carry a
+//! [`TxnAuthorAgreementAcceptance`] before it is signed.
+
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use indy_utils::{base58, hash::SHA256, txn_signature::serialize_signature};
+use serde_json::{json, Value};
+
+use super::resolver::{ObjectResolver, ResolverFuture};
+use crate::error::Result;
+use crate::services::types::{
+    CredentialDefinition, CredentialDefinitionId, DidValue, RevocationRegistryDefinition,
+    RevocationRegistryDelta, RevocationRegistryId, Schema, SchemaId,
+};
+
+/// A ledger read request for an object
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LedgerQuery<'a> {
+    Schema(&'a SchemaId),
+    CredentialDefinition(&'a CredentialDefinitionId),
+    RevocationRegistryDefinition(&'a RevocationRegistryId),
+    RevocationRegistryDelta {
+        id: &'a RevocationRegistryId,
+        from: Option<u64>,
+        to: u64,
+    },
+}
+
+/// A connection to a ledger which submits read requests, returning the
+/// reply JSON
+pub trait LedgerReader: Send + Sync {
+    fn read<'a>(&'a self, query: LedgerQuery<'a>) -> ResolverFuture<'a, String>;
+}
+
+/// An object resolver which reads from an Indy ledger
+#[derive(Debug)]
+pub struct LedgerResolver<R> {
+    reader: R,
+}
+
+impl<R: LedgerReader> LedgerResolver<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    pub fn reader(&self) -> &R {
+        &self.reader
+    }
+}
+
+impl<R: LedgerReader> ObjectResolver for LedgerResolver<R> {
+    fn get_schema<'a>(&'a self, id: &'a SchemaId) -> ResolverFuture<'a, Schema> {
+        Box::pin(
+            async move { schema_from_reply(&self.reader.read(LedgerQuery::Schema(id)).await?) },
+        )
+    }
+
+    fn get_cred_def<'a>(
+        &'a self,
+        id: &'a CredentialDefinitionId,
+    ) -> ResolverFuture<'a, CredentialDefinition> {
+        Box::pin(async move {
+            cred_def_from_reply(
+                &self
+                    .reader
+                    .read(LedgerQuery::CredentialDefinition(id))
+                    .await?,
+            )
+        })
+    }
+
+    fn get_rev_reg_def<'a>(
+        &'a self,
+        id: &'a RevocationRegistryId,
+    ) -> ResolverFuture<'a, RevocationRegistryDefinition> {
+        Box::pin(async move {
+            rev_reg_def_from_reply(
+                &self
+                    .reader
+                    .read(LedgerQuery::RevocationRegistryDefinition(id))
+                    .await?,
+            )
+        })
+    }
+
+    fn get_rev_reg_delta<'a>(
+        &'a self,
+        id: &'a RevocationRegistryId,
+        from: Option<u64>,
+        to: u64,
+    ) -> ResolverFuture<'a, (RevocationRegistryDelta, u64)> {
+        Box::pin(async move {
+            rev_reg_delta_from_reply(
+                &self
+                    .reader
+                    .read(LedgerQuery::RevocationRegistryDelta { id, from, to })
+                    .await?,
+            )
+        })
+    }
+}
+
+/// Convert the reply to a `GET_SCHEMA` request
+pub fn schema_from_reply(reply: &str) -> Result<Schema> {
+    let result = reply_result(reply)?;
+    let data = reply_data(&result, "Schema")?;
+    let dest = DidValue::new(str_field(&result, "dest")?, None);
+    let name = str_field(data, "name")?;
+    let version = str_field(data, "version")?;
+    Ok(serde_json::from_value(json!({
+        "ver": "1.0",
+        "id": SchemaId::new(&dest, name, version),
+        "name": name,
+        "version": version,
+        "attrNames": data.get("attr_names").cloned().unwrap_or_else(|| json!([])),
+        "seqNo": result.get("seqNo"),
+    }))?)
+}
+
+/// Convert the reply to a `GET_CRED_DEF` request
+pub fn cred_def_from_reply(reply: &str) -> Result<CredentialDefinition> {
+    let result = reply_result(reply)?;
+    let data = reply_data(&result, "Credential definition")?;
+    let origin = DidValue::new(str_field(&result, "origin")?, None);
+    let schema_ref = result
+        .get("ref")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| err_msg!("Missing schema sequence number in ledger reply"))?
+        .to_string();
+    let signature_type = str_field(&result, "signature_type")?;
+    let tag = result
+        .get("tag")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    Ok(serde_json::from_value(json!({
+        "ver": "1.0",
+        "id": CredentialDefinitionId::new(
+            &origin,
+            &SchemaId(schema_ref.clone()),
+            signature_type,
+            tag,
+        ),
+        "schemaId": schema_ref,
+        "type": signature_type,
+        "tag": tag,
+        "value": data,
+    }))?)
+}
+
+/// Convert the reply to a `GET_REVOC_REG_DEF` request
+pub fn rev_reg_def_from_reply(reply: &str) -> Result<RevocationRegistryDefinition> {
+    let result = reply_result(reply)?;
+    let mut data = reply_data(&result, "Revocation registry definition")?.clone();
+    if let Some(data) = data.as_object_mut() {
+        data.entry("ver").or_insert_with(|| json!("1.0"));
+    }
+    Ok(serde_json::from_value(data)?)
+}
+
+/// Convert the reply to a `GET_REVOC_REG_DELTA` request, returning the delta
+/// and the timestamp of the resulting registry state
+pub fn rev_reg_delta_from_reply(reply: &str) -> Result<(RevocationRegistryDelta, u64)> {
+    let result = reply_result(reply)?;
+    let value = reply_data(&result, "Revocation registry delta")?
+        .get("value")
+        .ok_or_else(|| err_msg!("Missing revocation registry delta value in ledger reply"))?;
+    let accum_to = value
+        .get("accum_to")
+        .ok_or_else(|| err_msg!("Missing accumulator in ledger reply"))?;
+    let timestamp = accum_to
+        .get("txnTime")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| err_msg!("Missing accumulator timestamp in ledger reply"))?;
+    let mut delta = json!({
+        "accum": accum_to["value"]["accum"],
+        "issued": value.get("issued").cloned().unwrap_or_else(|| json!([])),
+        "revoked": value.get("revoked").cloned().unwrap_or_else(|| json!([])),
+    });
+    if let Some(prev_accum) = value
+        .get("accum_from")
+        .and_then(|accum_from| accum_from["value"].get("accum"))
+    {
+        delta["prevAccum"] = prev_accum.clone();
+    }
+    Ok((
+        serde_json::from_value(json!({"ver": "1.0", "value": delta}))?,
+        timestamp,
+    ))
+}
+
+const SCHEMA: &str = "101";
+const CRED_DEF: &str = "102";
+const PROTOCOL_VERSION: u64 = 2;
+const SECS_PER_DAY: u64 = 86400;
+
+/// The acceptance of a transaction author agreement by the author of a
+/// request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxnAuthorAgreementAcceptance {
+    pub mechanism: String,
+    pub taa_digest: String,
+    pub time: u64,
+}
+
+impl TxnAuthorAgreementAcceptance {
+    /// Accept the agreement with a digest, through a mechanism of the
+    /// acceptance mechanism list, at a timestamp in seconds. The time is
+    /// rounded down to the day, as the ledger rejects acceptance times of
+    /// greater precision.
+    pub fn new(taa_digest: &str, mechanism: &str, time: u64) -> Result<Self> {
+        if taa_digest.len() != 64 || !taa_digest.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(err_msg!(
+                "Invalid transaction author agreement digest: {}",
+                taa_digest
+            ));
+        }
+        if mechanism.is_empty() {
+            return Err(err_msg!(
+                "Missing transaction author agreement acceptance mechanism"
+            ));
+        }
+        Ok(Self {
+            mechanism: mechanism.to_string(),
+            taa_digest: taa_digest.to_ascii_lowercase(),
+            time: taa_acceptance_time(time),
+        })
+    }
+
+    /// Accept the agreement with a text and a version
+    pub fn for_agreement(text: &str, version: &str, mechanism: &str, time: u64) -> Result<Self> {
+        Self::new(&taa_digest(text, version), mechanism, time)
+    }
+}
+
+/// The digest identifying a transaction author agreement: the hex SHA-256
+/// digest of the version followed by the text
+pub fn taa_digest(text: &str, version: &str) -> String {
+    SHA256::digest([version.as_bytes(), text.as_bytes()].concat())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Round a timestamp in seconds down to the start of its day (UTC)
+pub fn taa_acceptance_time(time: u64) -> u64 {
+    time / SECS_PER_DAY * SECS_PER_DAY
+}
+
+/// An unsigned or partially signed ledger write request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LedgerRequest(Value);
+
+impl LedgerRequest {
+    /// Build a `SCHEMA` request publishing a schema authored by a DID
+    pub fn schema(author: &DidValue, schema: &Schema) -> Result<Self> {
+        let Schema::SchemaV1(schema) = schema;
+        Ok(Self::new(
+            author,
+            json!({
+                "type": SCHEMA,
+                "data": {
+                    "name": schema.name,
+                    "version": schema.version,
+                    "attr_names": schema.attr_names,
+                },
+            }),
+        ))
+    }
+
+    /// Build a `CRED_DEF` request publishing a credential definition
+    /// authored by a DID. The credential definition must reference its
+    /// schema by sequence number.
+    pub fn cred_def(author: &DidValue, cred_def: &CredentialDefinition) -> Result<Self> {
+        let CredentialDefinition::CredentialDefinitionV1(cred_def) = cred_def;
+        let schema_ref = cred_def.schema_id.0.parse::<u64>().map_err(|_| {
+            err_msg!(
+                "Credential definition must reference its schema by sequence number: {}",
+                cred_def.schema_id
+            )
+        })?;
+        Ok(Self::new(
+            author,
+            json!({
+                "type": CRED_DEF,
+                "ref": schema_ref,
+                "signature_type": cred_def.signature_type.to_str(),
+                "tag": cred_def.tag,
+                "data": cred_def.value,
+            }),
+        ))
+    }
+
+    fn new(author: &DidValue, operation: Value) -> Self {
+        let req_id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or_default();
+        Self(json!({
+            "identifier": author.to_short().0,
+            "reqId": req_id,
+            "protocolVersion": PROTOCOL_VERSION,
+            "operation": operation,
+        }))
+    }
+
+    /// Parse a request, such as one received from the author by an endorser
+    pub fn from_json(json: &str) -> Result<Self> {
+        let request: Value =
+            serde_json::from_str(json).map_err(err_map!("Invalid ledger request"))?;
+        str_field(&request, "identifier")?;
+        if !request.get("operation").is_some_and(Value::is_object) {
+            return Err(err_msg!("Missing operation in ledger request"));
+        }
+        Ok(Self(request))
+    }
+
+    pub fn to_json(&self) -> String {
+        self.0.to_string()
+    }
+
+    pub fn author(&self) -> DidValue {
+        DidValue::new(self.author_str(), None)
+    }
+
+    pub fn endorser(&self) -> Option<DidValue> {
+        self.0
+            .get("endorser")
+            .and_then(Value::as_str)
+            .map(|did| DidValue::new(did, None))
+    }
+
+    /// Name the endorser which submits the request to the ledger. Changing
+    /// the endorser discards the signatures collected so far.
+    pub fn set_endorser(&mut self, endorser: &DidValue) -> Result<()> {
+        let endorser = endorser.to_short().0;
+        if endorser == self.author_str() {
+            return Err(err_msg!("The endorser of a request cannot be its author"));
+        }
+        if let Some(request) = self.0.as_object_mut() {
+            if request.get("endorser").and_then(Value::as_str) != Some(endorser.as_str()) {
+                request.remove("signatures");
+            }
+            request.insert("endorser".to_string(), json!(endorser));
+        }
+        Ok(())
+    }
+
+    pub fn txn_author_agreement_acceptance(&self) -> Option<TxnAuthorAgreementAcceptance> {
+        self.0
+            .get("taaAcceptance")
+            .and_then(|acceptance| serde_json::from_value(acceptance.clone()).ok())
+    }
+
+    /// Record the acceptance of the transaction author agreement. The
+    /// acceptance is signed with the request, so the signatures collected
+    /// so far are discarded.
+    pub fn set_txn_author_agreement_acceptance(
+        &mut self,
+        acceptance: &TxnAuthorAgreementAcceptance,
+    ) -> Result<()> {
+        self.0["taaAcceptance"] = serde_json::to_value(acceptance)?;
+        if let Some(request) = self.0.as_object_mut() {
+            request.remove("signatures");
+        }
+        Ok(())
+    }
+
+    /// The normalized form of the request which the author and the endorser
+    /// sign with their DID verification keys
+    pub fn signature_input(&self) -> Result<String> {
+        Ok(serialize_signature(&self.0)?)
+    }
+
+    /// Add the signature of the author or the endorser over the signature
+    /// input
+    pub fn add_signature(&mut self, signer: &DidValue, signature: &[u8]) -> Result<()> {
+        let signer = signer.to_short().0;
+        if signer != self.author_str() && Some(signer.as_str()) != self.endorser_str() {
+            return Err(err_msg!(
+                "Signer is neither the author nor the endorser of the request: {}",
+                signer
+            ));
+        }
+        let mut signatures = self.signatures();
+        signatures.insert(signer, base58::encode(signature));
+        self.0["signatures"] = json!(signatures);
+        Ok(())
+    }
+
+    /// Merge the signatures of another copy of the request, such as the
+    /// endorser's signature into the author's request
+    pub fn merge_signatures(&mut self, other: &LedgerRequest) -> Result<()> {
+        if self.signature_input()? != other.signature_input()? {
+            return Err(err_msg!(
+                "Cannot merge the signatures of different requests"
+            ));
+        }
+        let mut signatures = self.signatures();
+        for (signer, signature) in other.signatures() {
+            match signatures.get(&signer) {
+                Some(existing) if *existing != signature => {
+                    return Err(err_msg!(
+                        "Conflicting signatures of the request by {}",
+                        signer
+                    ))
+                }
+                _ => {
+                    signatures.insert(signer, signature);
+                }
+            }
+        }
+        if !signatures.is_empty() {
+            self.0["signatures"] = json!(signatures);
+        }
+        Ok(())
+    }
+
+    /// The base58 signatures of the request, by signer
+    pub fn signatures(&self) -> BTreeMap<String, String> {
+        self.0
+            .get("signatures")
+            .and_then(Value::as_object)
+            .map(|signatures| {
+                signatures
+                    .iter()
+                    .filter_map(|(signer, sig)| {
+                        sig.as_str().map(|sig| (signer.clone(), sig.to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether the request carries the signatures of the author and of the
+    /// endorser, if any, so that it may be submitted
+    pub fn is_fully_signed(&self) -> bool {
+        let signatures = self.signatures();
+        signatures.contains_key(self.author_str())
+            && self
+                .endorser_str()
+                .is_none_or(|endorser| signatures.contains_key(endorser))
+    }
+
+    fn author_str(&self) -> &str {
+        self.0["identifier"].as_str().unwrap_or_default()
+    }
+
+    fn endorser_str(&self) -> Option<&str> {
+        self.0.get("endorser").and_then(Value::as_str)
+    }
+}
+
+fn reply_result(reply: &str) -> Result<Value> {
+    let mut reply: Value = serde_json::from_str(reply).map_err(err_map!("Invalid ledger reply"))?;
+    match reply.get("op").and_then(Value::as_str) {
+        Some("REPLY") | None => (),
+        Some(op) => {
+            return Err(err_msg!(
+                "Ledger request failed ({}): {}",
+                op,
+                reply
+                    .get("reason")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+            ))
+        }
+    }
+    match reply.get_mut("result").map(Value::take) {
+        Some(result) if result.is_object() => Ok(result),
+        _ => Err(err_msg!("Missing result in ledger reply")),
+    }
+}
+
+fn reply_data<'r>(result: &'r Value, desc: &str) -> Result<&'r Value> {
+    match result.get("data") {
+        Some(data) if data.is_object() => Ok(data),
+        _ => Err(err_msg!("{} not found on the ledger", desc)),
+    }
+}
+
+fn str_field<'r>(value: &'r Value, name: &str) -> Result<&'r str> {
+    value
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| err_msg!("Missing field in ledger reply: {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{issuer, tails::TailsFileWriter, test_utils::CredDefFixture, types::*};
+    use async_global_executor::block_on;
+
+    struct StaticReader(Vec<String>);
+
+    impl LedgerReader for StaticReader {
+        fn read<'a>(&'a self, query: LedgerQuery<'a>) -> ResolverFuture<'a, String> {
+            let idx = match query {
+                LedgerQuery::Schema(_) => 0,
+                LedgerQuery::CredentialDefinition(_) => 1,
+                LedgerQuery::RevocationRegistryDefinition(_) => 2,
+                LedgerQuery::RevocationRegistryDelta { .. } => 3,
+            };
+            Box::pin(async move { Ok(self.0[idx].clone()) })
+        }
+    }
+
+    #[test]
+    fn ledger_resolver_works() {
+        let did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+        let schema = issuer::create_schema(
+            &did,
+            "gvt",
+            "1.0",
+            vec!["name".to_string()].into(),
+            Some(14),
+        )
+        .unwrap();
+        let cred_def_id =
+            CredentialDefinitionId::new(&did, &SchemaId("14".to_string()), "CL", "default");
+        let cred_def = CredDefFixture::new(did.clone(), schema, true)
+            .unwrap()
+            .cred_def;
+        assert_eq!(cred_def.id(), &cred_def_id);
+        let (rev_reg_def, _, rev_reg, _) = issuer::create_revocation_registry(
+            &did,
+            &cred_def,
+            "tag",
+            RegistryType::CL_ACCUM,
+            IssuanceType::ISSUANCE_BY_DEFAULT,
+            5,
+            &mut TailsFileWriter::new(None),
+        )
+        .unwrap();
+
+        let CredentialDefinition::CredentialDefinitionV1(cred_def_v1) = &cred_def;
+        let mut rev_reg_def_json = serde_json::to_value(&rev_reg_def).unwrap();
+        rev_reg_def_json.as_object_mut().unwrap().remove("ver");
+        let accum = serde_json::to_value(&rev_reg).unwrap()["value"]["accum"].clone();
+        let reader = StaticReader(vec![
+            json!({"op": "REPLY", "result": {
+                "type": "107",
+                "dest": did.0,
+                "seqNo": 14,
+                "data": {"name": "gvt", "version": "1.0", "attr_names": ["name"]}
+            }})
+            .to_string(),
+            json!({"op": "REPLY", "result": {
+                "type": "108",
+                "origin": did.0,
+                "ref": 14,
+                "signature_type": "CL",
+                "tag": "default",
+                "data": cred_def_v1.value
+            }})
+            .to_string(),
+            json!({"op": "REPLY", "result": {"type": "115", "data": rev_reg_def_json}}).to_string(),
+            json!({"op": "REPLY", "result": {"type": "117", "data": {
+                "revocDefType": "CL_ACCUM",
+                "revocRegDefId": rev_reg_def.id(),
+                "value": {"accum_to": {"txnTime": 1000, "value": {"accum": accum}}}
+            }}})
+            .to_string(),
+        ]);
+        let resolver = LedgerResolver::new(reader);
+
+        block_on(async {
+            let schema = resolver
+                .get_schema(&SchemaId::new(&did, "gvt", "1.0"))
+                .await
+                .unwrap();
+            assert_eq!(schema.id(), &SchemaId::new(&did, "gvt", "1.0"));
+            let resolved = resolver.get_cred_def(&cred_def_id).await.unwrap();
+            assert_eq!(resolved.id(), &cred_def_id);
+            assert_eq!(
+                serde_json::to_value(&resolved).unwrap(),
+                serde_json::to_value(&cred_def).unwrap()
+            );
+            let resolved = resolver.get_rev_reg_def(rev_reg_def.id()).await.unwrap();
+            assert_eq!(resolved.id(), rev_reg_def.id());
+            let (delta, timestamp) = resolver
+                .get_rev_reg_delta(rev_reg_def.id(), None, 2000)
+                .await
+                .unwrap();
+            assert_eq!(timestamp, 1000);
+            assert_eq!(
+                serde_json::to_value(delta.to_registry()).unwrap(),
+                serde_json::to_value(&rev_reg).unwrap()
+            );
+        });
+
+        let err = schema_from_reply(&json!({"op": "REPLY", "result": {"data": null}}).to_string())
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"));
+        assert!(schema_from_reply(
+            &json!({"op": "REQNACK", "reason": "client request invalid"}).to_string()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn endorsed_cred_def_request() {
+        let author = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", Some("sov"));
+        let endorser = DidValue::new("V4SGRU86Z58d6TV7PBUe6f", None);
+        let schema = issuer::create_schema(
+            &author,
+            "gvt",
+            "1.0",
+            vec!["name".to_string()].into(),
+            Some(14),
+        )
+        .unwrap();
+        let cred_def = CredDefFixture::new(author.clone(), schema, false)
+            .unwrap()
+            .cred_def;
+
+        let mut request = LedgerRequest::cred_def(&author, &cred_def).unwrap();
+        assert_eq!(
+            request.author(),
+            DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None)
+        );
+        assert!(request.set_endorser(&author).is_err());
+        request.set_endorser(&endorser).unwrap();
+        let acceptance = TxnAuthorAgreementAcceptance::for_agreement(
+            "Terms of use",
+            "1.0",
+            "on_file",
+            1_600_000_123,
+        )
+        .unwrap();
+        assert_eq!(acceptance.time, 1_599_955_200);
+        assert_eq!(acceptance.taa_digest, taa_digest("Terms of use", "1.0"));
+        assert!(TxnAuthorAgreementAcceptance::new("abc", "on_file", 0).is_err());
+        request
+            .set_txn_author_agreement_acceptance(&acceptance)
+            .unwrap();
+        assert_eq!(request.txn_author_agreement_acceptance(), Some(acceptance));
+        let input = request.signature_input().unwrap();
+        assert!(input.contains("endorser:V4SGRU86Z58d6TV7PBUe6f"));
+        assert!(input.contains("taaAcceptance:mechanism:on_file|taaDigest:"));
+        assert!(input.contains("ref:14"));
+
+        // the endorser signs its own copy of the request from the author
+        let mut endorsed = LedgerRequest::from_json(&request.to_json()).unwrap();
+        request.add_signature(&author, &[1; 64]).unwrap();
+        assert!(!request.is_fully_signed());
+        endorsed.add_signature(&endorser, &[2; 64]).unwrap();
+        assert!(endorsed
+            .add_signature(
+                &DidValue::new("CnEDk9HrMnmiHXEV1WFgbVCRteYnPqsJwrTdcZaNhFVW", None),
+                &[3; 64]
+            )
+            .is_err());
+        assert_eq!(endorsed.signature_input().unwrap(), input);
+        request.merge_signatures(&endorsed).unwrap();
+        assert!(request.is_fully_signed());
+        assert_eq!(request.signatures().len(), 2);
+
+        let mut conflicting = endorsed.clone();
+        conflicting.add_signature(&endorser, &[4; 64]).unwrap();
+        assert!(request.merge_signatures(&conflicting).is_err());
+        let other = LedgerRequest::cred_def(&author, &cred_def).unwrap();
+        assert!(request.merge_signatures(&other).is_err());
+    }
+}
@@ -0,0 +1,97 @@
+//! Access to the link (master) secret of a prover held outside of the
+//! library, such as in a hardware keystore or secure enclave.
+//!
+//! The CL operations which use the link secret, to blind a credential
+//! request, process a credential and build a presentation, each need the
+//! secret value itself. A provider releases the secret for the duration of a
+//! single operation, so that it is not held by the library or loaded into an
+//! object handle between operations. Providers may unseal the secret on each
+//! call and discard it when the operation returns.
+
+use super::types::MasterSecret;
+use crate::error::Result;
+
+/// A source of the link secret for prover operations
+pub trait LinkSecretProvider {
+    /// Call `op` with the link secret. The secret must not be retained by
+    /// `op`, and should be discarded by the provider when it returns.
+    fn with_link_secret(&self, op: &mut dyn FnMut(&MasterSecret) -> Result<()>) -> Result<()>;
+}
+
+impl LinkSecretProvider for MasterSecret {
+    fn with_link_secret(&self, op: &mut dyn FnMut(&MasterSecret) -> Result<()>) -> Result<()> {
+        op(self)
+    }
+}
+
+/// Perform an operation with the link secret of a provider, returning its
+/// result
+pub fn with_link_secret<R>(
+    provider: &dyn LinkSecretProvider,
+    op: impl FnOnce(&MasterSecret) -> Result<R>,
+) -> Result<R> {
+    let mut op = Some(op);
+    let mut result = None;
+    provider.with_link_secret(&mut |secret| {
+        let op = op
+            .take()
+            .ok_or_else(|| err_msg!(InvalidState, "Link secret requested more than once"))?;
+        result.replace(op(secret)?);
+        Ok(())
+    })?;
+    result.ok_or_else(|| {
+        err_msg!(
+            InvalidState,
+            "Link secret provider did not call the operation"
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::services::prover::create_master_secret;
+
+    struct SealedSecret {
+        secret: MasterSecret,
+        unsealed: Cell<usize>,
+    }
+
+    impl LinkSecretProvider for SealedSecret {
+        fn with_link_secret(&self, op: &mut dyn FnMut(&MasterSecret) -> Result<()>) -> Result<()> {
+            self.unsealed.set(self.unsealed.get() + 1);
+            let secret = self.secret.try_clone().map_err(err_map!(Unexpected))?;
+            op(&secret)
+        }
+    }
+
+    struct Refusing;
+
+    impl LinkSecretProvider for Refusing {
+        fn with_link_secret(&self, _op: &mut dyn FnMut(&MasterSecret) -> Result<()>) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn link_secret_provider_scopes_access() {
+        let provider = SealedSecret {
+            secret: create_master_secret().unwrap(),
+            unsealed: Cell::new(0),
+        };
+        let value = with_link_secret(&provider, |secret| {
+            Ok(secret.value.value().unwrap().to_dec().unwrap())
+        })
+        .unwrap();
+        assert_eq!(
+            value,
+            provider.secret.value.value().unwrap().to_dec().unwrap()
+        );
+        assert_eq!(provider.unsealed.get(), 1);
+
+        let err = with_link_secret(&Refusing, |_| Ok(())).unwrap_err();
+        assert_eq!(err.kind(), crate::ErrorKind::InvalidState);
+    }
+}
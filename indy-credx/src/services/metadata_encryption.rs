@@ -0,0 +1,149 @@
+//! Encryption of credential request metadata under a wallet key.
+//!
+//! The metadata holds the blinding factors of the credential request, which
+//! must remain secret until the credential is processed. Wrappers passing the
+//! metadata through storage which is not otherwise protected may wrap it
+//! before it leaves the library, and unwrap it when the credential arrives.
+//! The JSON form of the metadata is encrypted with `AES-256-GCM`, using a
+//! random IV and the format version as associated data.
+
+use std::fmt;
+
+use openssl::rand::rand_bytes;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+
+use super::types::CredentialRequestMetadata;
+use crate::error::Result;
+use indy_utils::base64;
+
+const WRAPPED_VERSION: u32 = 1;
+const WRAPPED_AAD: &[u8] = b"indy-credx-request-metadata:1";
+
+/// A 256-bit key for wrapping credential request metadata
+#[derive(Clone, PartialEq, Eq)]
+pub struct WalletKey([u8; 32]);
+
+impl WalletKey {
+    /// Create a key from 32 raw bytes
+    pub fn new(key: &[u8]) -> Result<Self> {
+        if key.len() != 32 {
+            return Err(err_msg!("Wallet keys must be 32 bytes"));
+        }
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(key);
+        Ok(Self(bytes))
+    }
+
+    /// Generate a new random key
+    pub fn generate() -> Result<Self> {
+        let mut bytes = [0u8; 32];
+        rand_bytes(&mut bytes).map_err(err_map!(Unexpected))?;
+        Ok(Self(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for WalletKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WalletKey(..)")
+    }
+}
+
+/// Credential request metadata encrypted under a wallet key
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WrappedRequestMetadata {
+    pub version: u32,
+    pub iv: String,
+    pub ciphertext: String,
+    pub tag: String,
+}
+
+/// Encrypt credential request metadata under a wallet key
+pub fn wrap_request_metadata(
+    metadata: &CredentialRequestMetadata,
+    key: &WalletKey,
+) -> Result<WrappedRequestMetadata> {
+    let plaintext = serde_json::to_vec(metadata)?;
+    let mut iv = [0u8; 12];
+    let mut tag = [0u8; 16];
+    rand_bytes(&mut iv).map_err(err_map!(Unexpected))?;
+    let ciphertext = encrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key.0,
+        Some(&iv),
+        WRAPPED_AAD,
+        &plaintext,
+        &mut tag,
+    )
+    .map_err(err_map!(
+        Unexpected,
+        "Error encrypting credential request metadata"
+    ))?;
+    Ok(WrappedRequestMetadata {
+        version: WRAPPED_VERSION,
+        iv: base64::encode(iv),
+        ciphertext: base64::encode(ciphertext),
+        tag: base64::encode(tag),
+    })
+}
+
+/// Decrypt credential request metadata wrapped under a wallet key
+pub fn unwrap_request_metadata(
+    wrapped: &WrappedRequestMetadata,
+    key: &WalletKey,
+) -> Result<CredentialRequestMetadata> {
+    if wrapped.version != WRAPPED_VERSION {
+        return Err(err_msg!(
+            "Unsupported wrapped metadata version: {}",
+            wrapped.version
+        ));
+    }
+    let plaintext = decrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key.0,
+        Some(&decode_b64(&wrapped.iv)?),
+        WRAPPED_AAD,
+        &decode_b64(&wrapped.ciphertext)?,
+        &decode_b64(&wrapped.tag)?,
+    )
+    .map_err(|_| err_msg!("Error decrypting credential request metadata, check the wallet key"))?;
+    serde_json::from_slice(&plaintext).map_err(err_map!("Invalid credential request metadata"))
+}
+
+fn decode_b64(data: &str) -> Result<Vec<u8>> {
+    base64::decode(data).map_err(err_map!("Invalid wrapped metadata encoding"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::flows::issue_credential;
+    use crate::services::test_utils::{credential_values, CredDefFixture, HolderFixture};
+
+    #[test]
+    fn wrap_metadata_round_trip() {
+        let holder = HolderFixture::new().unwrap();
+        let fixture = CredDefFixture::with_attributes(vec!["name"]).unwrap();
+        let metadata = issue_credential(
+            fixture.issuer_objects(),
+            holder.holder_objects(),
+            credential_values(&[("name", "Alex")]).unwrap(),
+        )
+        .unwrap()
+        .request_metadata;
+
+        let key = WalletKey::generate().unwrap();
+        let wrapped = wrap_request_metadata(&metadata, &key).unwrap();
+        let unwrapped = unwrap_request_metadata(&wrapped, &key).unwrap();
+        assert_eq!(
+            serde_json::to_value(&unwrapped).unwrap(),
+            serde_json::to_value(&metadata).unwrap()
+        );
+
+        let other = WalletKey::generate().unwrap();
+        assert!(unwrap_request_metadata(&wrapped, &other).is_err());
+    }
+}
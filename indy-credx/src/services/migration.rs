@@ -0,0 +1,177 @@
+//! Migration of objects using legacy Indy identifiers to AnonCreds
+//! identifier URIs.
+//!
+//! Legacy identifiers such as `NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0` are
+//! rewritten as `did:indy` object URIs in a given ledger namespace, such as
+//! `did:indy:sovrin:NcYxiDXkpYi6ov5FcYDi1e/anoncreds/v0/SCHEMA/gvt/1.0`.
+//! The identifiers are not covered by the CL signatures, so credentials
+//! remain verifiable as long as every object referenced by a presentation is
+//! migrated to the same namespace. Identifiers which are already URIs are
+//! left unchanged, so each function may be applied more than once.
+//!
+//! Credential definitions must reference their schema by sequence number to
+//! be expressed as URIs, as they are when published to an Indy ledger.
+//! Credential request metadata and revocation states carry no identifiers
+//! and do not need to be migrated.
+
+use super::types::{
+    Credential, CredentialDefinition, CredentialDefinitionId, CredentialOffer, CredentialRequest,
+    RevocationRegistryDefinition, RevocationRegistryId, RevocationStatusList, Schema, SchemaId,
+};
+use crate::error::Result;
+use indy_data_types::anoncreds::{
+    cred_def::CredentialDefinitionV1, rev_reg_def::RevocationRegistryDefinitionV1, schema::SchemaV1,
+};
+
+/// Rewrite a legacy schema identifier as an AnonCreds URI
+pub fn migrate_schema_id(schema_id: &SchemaId, namespace: &str) -> Result<SchemaId> {
+    schema_id.to_uri(namespace).ok_or_else(|| {
+        err_msg!(
+            Input,
+            "Schema ID `{}` cannot be migrated to namespace `{}`",
+            schema_id,
+            namespace
+        )
+    })
+}
+
+/// Rewrite a legacy credential definition identifier as an AnonCreds URI
+pub fn migrate_cred_def_id(
+    cred_def_id: &CredentialDefinitionId,
+    namespace: &str,
+) -> Result<CredentialDefinitionId> {
+    cred_def_id.to_uri(namespace).ok_or_else(|| {
+        err_msg!(
+            Input,
+            "Credential definition ID `{}` cannot be migrated to namespace `{}`",
+            cred_def_id,
+            namespace
+        )
+    })
+}
+
+/// Rewrite a legacy revocation registry identifier as an AnonCreds URI
+pub fn migrate_rev_reg_id(
+    rev_reg_id: &RevocationRegistryId,
+    namespace: &str,
+) -> Result<RevocationRegistryId> {
+    rev_reg_id.to_uri(namespace).ok_or_else(|| {
+        err_msg!(
+            Input,
+            "Revocation registry ID `{}` cannot be migrated to namespace `{}`",
+            rev_reg_id,
+            namespace
+        )
+    })
+}
+
+pub fn migrate_schema(schema: Schema, namespace: &str) -> Result<Schema> {
+    let Schema::SchemaV1(schema) = schema;
+    Ok(Schema::SchemaV1(SchemaV1 {
+        id: migrate_schema_id(&schema.id, namespace)?,
+        ..schema
+    }))
+}
+
+/// Migrate a credential definition. A schema reference by sequence number
+/// is retained, as it is part of the credential definition URI.
+pub fn migrate_credential_definition(
+    cred_def: CredentialDefinition,
+    namespace: &str,
+) -> Result<CredentialDefinition> {
+    let CredentialDefinition::CredentialDefinitionV1(cred_def) = cred_def;
+    let schema_id = if cred_def.schema_id.0.parse::<u32>().is_ok() {
+        cred_def.schema_id.clone()
+    } else {
+        migrate_schema_id(&cred_def.schema_id, namespace)?
+    };
+    Ok(CredentialDefinition::CredentialDefinitionV1(
+        CredentialDefinitionV1 {
+            id: migrate_cred_def_id(&cred_def.id, namespace)?,
+            schema_id,
+            ..cred_def
+        },
+    ))
+}
+
+pub fn migrate_revocation_registry_definition(
+    rev_reg_def: RevocationRegistryDefinition,
+    namespace: &str,
+) -> Result<RevocationRegistryDefinition> {
+    let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) = rev_reg_def;
+    Ok(
+        RevocationRegistryDefinition::RevocationRegistryDefinitionV1(
+            RevocationRegistryDefinitionV1 {
+                id: migrate_rev_reg_id(&rev_reg_def.id, namespace)?,
+                cred_def_id: migrate_cred_def_id(&rev_reg_def.cred_def_id, namespace)?,
+                ..rev_reg_def
+            },
+        ),
+    )
+}
+
+pub fn migrate_revocation_status_list(
+    status_list: RevocationStatusList,
+    namespace: &str,
+) -> Result<RevocationStatusList> {
+    let rev_reg_def_id = migrate_rev_reg_id(&status_list.rev_reg_def_id, namespace)?;
+    let (issuer_id, _, _, _) = rev_reg_def_id
+        .parts()
+        .ok_or_else(|| err_msg!(Unexpected, "Invalid migrated revocation registry ID"))?;
+    Ok(RevocationStatusList {
+        rev_reg_def_id,
+        issuer_id,
+        ..status_list
+    })
+}
+
+pub fn migrate_credential_offer(
+    cred_offer: CredentialOffer,
+    namespace: &str,
+) -> Result<CredentialOffer> {
+    Ok(CredentialOffer {
+        schema_id: migrate_schema_id(&cred_offer.schema_id, namespace)?,
+        cred_def_id: migrate_cred_def_id(&cred_offer.cred_def_id, namespace)?,
+        ..cred_offer
+    })
+}
+
+/// Migrate a credential request. The prover DID is not a ledger identifier
+/// and is left unchanged.
+pub fn migrate_credential_request(
+    cred_request: CredentialRequest,
+    namespace: &str,
+) -> Result<CredentialRequest> {
+    Ok(CredentialRequest {
+        cred_def_id: migrate_cred_def_id(&cred_request.cred_def_id, namespace)?,
+        ..cred_request
+    })
+}
+
+pub fn migrate_credential(credential: Credential, namespace: &str) -> Result<Credential> {
+    Ok(Credential {
+        schema_id: migrate_schema_id(&credential.schema_id, namespace)?,
+        cred_def_id: migrate_cred_def_id(&credential.cred_def_id, namespace)?,
+        rev_reg_id: credential
+            .rev_reg_id
+            .as_ref()
+            .map(|rev_reg_id| migrate_rev_reg_id(rev_reg_id, namespace))
+            .transpose()?,
+        ..credential
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_ids_rejects_unmigratable() {
+        let cred_def_id = CredentialDefinitionId(
+            "NcYxiDXkpYi6ov5FcYDi1e:3:CL:NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0:tag".into(),
+        );
+        let err = migrate_cred_def_id(&cred_def_id, "sovrin").unwrap_err();
+        assert_eq!(err.kind(), crate::ErrorKind::Input);
+        assert!(migrate_schema_id(&SchemaId("1".to_string()), "sovrin").is_err());
+    }
+}
@@ -1,11 +1,58 @@
 mod helpers;
+mod keygen;
+mod parallel;
 
+pub mod aries;
+#[cfg(feature = "askar")]
+pub mod askar;
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod audit;
+#[cfg(feature = "delegation")]
+pub mod delegation;
+pub mod encoders;
+#[cfg(feature = "jwe")]
+pub mod envelope;
+pub mod escrow;
+pub mod flows;
 pub mod issuer;
+pub mod ledger;
+pub mod link_secret;
+#[cfg(feature = "metadata_encryption")]
+pub mod metadata_encryption;
+pub mod migration;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod presentation_builder;
+#[cfg(feature = "advanced")]
+pub mod primitives;
+#[cfg(feature = "proto")]
+pub mod proto;
+#[cfg(feature = "protocol")]
+pub mod protocol;
 pub mod prover;
+pub mod pseudonym;
+pub mod resolver;
+#[cfg(feature = "test_seeded_keys")]
+pub mod seeded_keys;
+#[cfg(feature = "test_seeded_keys")]
+pub mod seeded_proofs;
+#[cfg(feature = "stats")]
+pub mod stats;
+#[cfg(feature = "store")]
+pub mod store;
 pub mod tails;
+#[cfg(feature = "tails_encryption")]
+pub mod tails_encryption;
+pub mod templates;
+#[cfg(any(test, feature = "test_utils"))]
+pub mod test_utils;
+#[cfg(feature = "test_vectors")]
+pub mod test_vectors;
 pub mod types;
+pub mod validation;
 pub mod verifier;
 
 pub mod utils {
-    pub use super::helpers::encode_credential_attribute;
+    pub use super::helpers::{encode_credential_attribute, SignedRange};
 }
@@ -0,0 +1,90 @@
+//! MessagePack encoding of objects.
+//!
+//! Objects are encoded from the same data model as their JSON form, so that
+//! either may be converted to the other without loss. Only the MessagePack
+//! types with a JSON equivalent are supported: binary and extension values
+//! are rejected when decoding.
+
+use std::io::Cursor;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::Result;
+
+/// The maximum nesting of arrays and maps accepted when decoding
+const MAX_DEPTH: usize = 128;
+
+/// Encode an object as MessagePack
+pub fn to_msgpack<T: Serialize + ?Sized>(obj: &T) -> Result<Vec<u8>> {
+    let value = serde_json::to_value(obj)?;
+    encode_value(&value)
+}
+
+/// Decode an object from MessagePack
+pub fn from_msgpack<T: DeserializeOwned>(data: &[u8]) -> Result<T> {
+    Ok(serde_json::from_value(decode_value(data)?)?)
+}
+
+/// Encode a JSON value as MessagePack
+pub fn encode_value(value: &Value) -> Result<Vec<u8>> {
+    rmp_serde::to_vec(value).map_err(err_map!(Unexpected, "Error encoding MessagePack"))
+}
+
+/// Decode a MessagePack value into its JSON equivalent
+pub fn decode_value(data: &[u8]) -> Result<Value> {
+    let mut deserializer = rmp_serde::Deserializer::new(Cursor::new(data));
+    // the limit is reached by the innermost value rather than its container
+    deserializer.set_max_depth(MAX_DEPTH + 1);
+    let value =
+        Value::deserialize(&mut deserializer).map_err(err_map!("Invalid MessagePack data"))?;
+    if deserializer.position() != data.len() as u64 {
+        return Err(err_msg!("Trailing data after MessagePack value"));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{issuer, types::*};
+
+    #[test]
+    fn msgpack_round_trip() {
+        let value = serde_json::json!({
+            "null": null,
+            "flags": [true, false],
+            "ints": [0, 127, 128, 65536, -1, -33, -129, -40000, u64::MAX, i64::MIN],
+            "float": 1.5,
+            "long": "x".repeat(300),
+            "nested": {"list": (0..20).collect::<Vec<u32>>()}
+        });
+        assert_eq!(decode_value(&encode_value(&value).unwrap()).unwrap(), value);
+        assert_eq!(
+            encode_value(&serde_json::json!({"a": [1, -1, "b"]})).unwrap(),
+            vec![0x81, 0xa1, b'a', 0x93, 0x01, 0xff, 0xa1, b'b']
+        );
+
+        let did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+        let schema =
+            issuer::create_schema(&did, "gvt", "1.0", vec!["name".to_string()].into(), None)
+                .unwrap();
+        let decoded: Schema = from_msgpack(&to_msgpack(&schema).unwrap()).unwrap();
+        assert_eq!(decoded.id(), schema.id());
+    }
+
+    #[test]
+    fn msgpack_rejects_invalid() {
+        assert!(decode_value(&[]).is_err());
+        assert!(decode_value(&[0xc0, 0xc0]).is_err());
+        assert!(decode_value(&[0xc4, 0x01, 0x00]).is_err());
+        assert!(decode_value(&[0xd4, 0x01, 0x00]).is_err());
+        assert!(decode_value(&[0x81, 0x01, 0x01]).is_err());
+        assert!(decode_value(&[0xdd, 0xff, 0xff, 0xff, 0xff]).is_err());
+        let mut nested = vec![0x91; MAX_DEPTH];
+        nested.push(0xc0);
+        assert!(decode_value(&nested).is_ok());
+        nested.insert(0, 0x91);
+        assert!(decode_value(&nested).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! Division of independent work between worker threads.
+//!
+//! With the `parallel` feature, batch operations such as tails generation and
+//! batch issuance run on a rayon thread pool of the configured thread pool
+//! size. Without the feature these batches are processed in order on the
+//! calling thread, so that builds for constrained platforms stay free of the
+//! rayon dependency. Batch verification exists to process presentations
+//! concurrently, and divides its batch between scoped worker threads when the
+//! feature is disabled.
+//!
+//! A panic in a worker is resumed on the calling thread, along with the
+//! record of the panic kept for the fatal handler of the FFI.
+
+use std::any::Any;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+#[cfg(feature = "parallel")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "parallel")]
+use once_cell::sync::Lazy;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::error::Result;
+
+/// The number of workers between which a batch is divided
+pub(crate) fn worker_count() -> usize {
+    #[cfg(feature = "parallel")]
+    {
+        crate::config::get_config().effective_thread_pool_size()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        1
+    }
+}
+
+/// A panic caught on a worker thread
+struct WorkerPanic {
+    payload: Box<dyn Any + Send>,
+    #[cfg(feature = "ffi")]
+    report: Option<crate::ffi::fatal::PanicReport>,
+}
+
+/// Run `f` on a worker thread, catching a panic to be resumed on the calling
+/// thread
+fn catch_worker_panic<R>(f: impl FnOnce() -> R) -> std::result::Result<R, WorkerPanic> {
+    catch_unwind(AssertUnwindSafe(f)).map_err(|payload| WorkerPanic {
+        payload,
+        #[cfg(feature = "ffi")]
+        report: crate::ffi::fatal::take_panic_report(),
+    })
+}
+
+/// Resume a panic caught on a worker thread, moving its record to this thread
+fn resume_worker_panic(panic: WorkerPanic) -> ! {
+    #[cfg(feature = "ffi")]
+    if let Some(report) = panic.report {
+        crate::ffi::fatal::restore_panic_report(report);
+    }
+    resume_unwind(panic.payload)
+}
+
+/// Collect the results of the workers, resuming the first panic
+fn collect_worker_results<R>(
+    results: impl IntoIterator<Item = std::result::Result<R, WorkerPanic>>,
+) -> Vec<R> {
+    results
+        .into_iter()
+        .map(|result| result.unwrap_or_else(|panic| resume_worker_panic(panic)))
+        .collect()
+}
+
+/// The thread pool for the configured thread pool size, which is rebuilt
+/// when the size is changed
+#[cfg(feature = "parallel")]
+fn thread_pool(size: usize) -> Result<Arc<rayon::ThreadPool>> {
+    static POOL: Lazy<Mutex<Option<Arc<rayon::ThreadPool>>>> = Lazy::new(|| Mutex::new(None));
+    let mut pool = POOL.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(pool) = &*pool {
+        if pool.current_num_threads() == size {
+            return Ok(pool.clone());
+        }
+    }
+    let created = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(size)
+            .thread_name(|idx| format!("credx-worker-{}", idx))
+            .build()
+            .map_err(err_map!(Unexpected, "Error creating thread pool"))?,
+    );
+    *pool = Some(created.clone());
+    Ok(created)
+}
+
+/// Apply `f` to each item, returning the results in the order of the items
+pub(crate) fn try_map<T, R, F>(items: &[T], f: F) -> Vec<Result<R>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Result<R> + Send + Sync,
+{
+    let workers = worker_count().min(items.len());
+    if workers <= 1 {
+        return items.iter().map(f).collect();
+    }
+    #[cfg(feature = "parallel")]
+    {
+        match thread_pool(worker_count()) {
+            Ok(pool) => collect_worker_results(pool.install(|| {
+                items
+                    .par_iter()
+                    .map(|item| catch_worker_panic(|| f(item)))
+                    .collect::<Vec<_>>()
+            })),
+            Err(err) => {
+                warn!("{}", err);
+                items.iter().map(f).collect()
+            }
+        }
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.iter().map(f).collect()
+    }
+}
+
+/// Apply `f` to each item like [`try_map`], using worker threads even when
+/// the `parallel` feature is disabled
+pub(crate) fn try_map_concurrent<T, R, F>(items: &[T], f: F) -> Vec<Result<R>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Result<R> + Send + Sync,
+{
+    #[cfg(feature = "parallel")]
+    {
+        try_map(items, f)
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        let workers = crate::config::get_config()
+            .effective_thread_pool_size()
+            .min(items.len());
+        scoped_map(workers, items, f)
+    }
+}
+
+/// Divide the items between `workers` scoped threads
+#[cfg(any(test, not(feature = "parallel")))]
+fn scoped_map<T, R, F>(workers: usize, items: &[T], f: F) -> Vec<Result<R>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Result<R> + Sync,
+{
+    if workers <= 1 {
+        return items.iter().map(f).collect();
+    }
+    let chunk_size = items.len().div_ceil(workers);
+    let f = &f;
+    let chunks = std::thread::scope(|scope| {
+        let workers: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || catch_worker_panic(|| chunk.iter().map(f).collect::<Vec<_>>()))
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|payload| resume_unwind(payload))
+            })
+            .collect::<Vec<_>>()
+    });
+    collect_worker_results(chunks)
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_order(results: Vec<Result<u32>>, items: &[u32]) {
+        assert_eq!(results.len(), items.len());
+        for (n, result) in items.iter().zip(results) {
+            match result {
+                Ok(doubled) => assert_eq!(doubled, n * 2),
+                Err(_) => assert_eq!(n % 10, 9),
+            }
+        }
+    }
+
+    fn double(n: &u32) -> Result<u32> {
+        if n % 10 == 9 {
+            Err(err_msg!("Rejected {}", n))
+        } else {
+            Ok(n * 2)
+        }
+    }
+
+    #[test]
+    fn try_map_preserves_order() {
+        let items: Vec<u32> = (0..37).collect();
+        check_order(try_map(&items, double), &items);
+        check_order(try_map_concurrent(&items, double), &items);
+        check_order(scoped_map(4, &items, double), &items);
+    }
+
+    #[test]
+    fn batch_verification_is_concurrent() {
+        // two items can only meet at the barrier if they run on separate threads
+        let barrier = std::sync::Barrier::new(2);
+        let results = scoped_map(2, &[0u32, 1], |_| {
+            barrier.wait();
+            Ok(std::thread::current().id())
+        });
+        let ids: Vec<_> = results.into_iter().map(Result::unwrap).collect();
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn worker_panic_is_resumed() {
+        let items: Vec<u32> = (0..8).collect();
+        let result = std::panic::catch_unwind(|| {
+            scoped_map(4, &items, |n| {
+                if *n == 5 {
+                    panic!("worker failed");
+                }
+                Ok(*n)
+            })
+        });
+        let payload = result.unwrap_err();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"worker failed"));
+    }
+
+    #[cfg(feature = "ffi")]
+    #[test]
+    fn worker_panic_is_reported() {
+        use crate::ffi::fatal::{credx_set_fatal_handler, report_caught_panic};
+        use std::ffi::CStr;
+        use std::os::raw::c_char;
+        use std::sync::Mutex;
+
+        static REPORTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        extern "C" fn record(report_json: *const c_char) {
+            let report = unsafe { CStr::from_ptr(report_json) };
+            REPORTS
+                .lock()
+                .unwrap()
+                .push(report.to_string_lossy().into_owned());
+        }
+
+        credx_set_fatal_handler(Some(record));
+        let items: Vec<u32> = (0..8).collect();
+        let result = catch_unwind(|| {
+            scoped_map(4, &items, |n| {
+                if *n == 5 {
+                    panic!("worker report");
+                }
+                Ok(*n)
+            })
+        });
+        assert!(result.is_err());
+        // as when the panic is caught at the FFI boundary
+        report_caught_panic();
+        credx_set_fatal_handler(None);
+        assert!(REPORTS
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|report| report.contains("worker report")));
+    }
+}
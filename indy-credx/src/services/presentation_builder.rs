@@ -0,0 +1,316 @@
+//! Step-by-step construction of a presentation.
+//!
+//! `create_presentation` takes every choice of the holder at once, as maps of
+//! credentials to referents which are only checked against the presentation
+//! request when the proof is generated. The builder collects the same
+//! choices one at a time: each credential is added and then assigned to the
+//! attribute and predicate referents it satisfies, a revocation state may be
+//! attached to it, and self-attested values are given for the remaining
+//! attributes. Each step is checked against the request as it is taken, and
+//! the presentation is only created once every referent has been resolved.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::prover::{create_presentation, credential_matches_referent};
+use super::types::*;
+use crate::error::Result;
+use indy_data_types::anoncreds::pres_request::AttributeInfo;
+use indy_utils::Validatable;
+
+/// A presentation assembled step by step against a presentation request
+#[derive(Debug)]
+pub struct PresentationBuilder<'p> {
+    pres_req: &'p PresentationRequest,
+    credentials: PresentCredentials<'p>,
+    self_attested: HashMap<String, String>,
+    resolved_attributes: HashSet<String>,
+    resolved_predicates: HashSet<String>,
+}
+
+impl<'p> PresentationBuilder<'p> {
+    pub fn new(pres_req: &'p PresentationRequest) -> Self {
+        Self {
+            pres_req,
+            credentials: PresentCredentials::new(),
+            self_attested: HashMap::new(),
+            resolved_attributes: HashSet::new(),
+            resolved_predicates: HashSet::new(),
+        }
+    }
+
+    /// Add a credential to the presentation, returning the step in which its
+    /// referents and revocation state are chosen
+    pub fn add_credential(&mut self, cred: &'p Credential) -> CredentialStep<'_, 'p> {
+        self.credentials.add_credential(cred, None, None);
+        let index = self.credentials.0.len() - 1;
+        CredentialStep {
+            builder: self,
+            index,
+        }
+    }
+
+    /// Provide a self-attested value for an attribute referent, which must
+    /// request a single attribute without restrictions
+    pub fn add_self_attested(
+        &mut self,
+        referent: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<&mut Self> {
+        let referent = referent.into();
+        let info = self.attribute_info(&referent)?;
+        if info.name.is_none() || info.restrictions.is_some() {
+            return Err(err_msg!(
+                "Attribute referent cannot be self-attested: {}",
+                referent
+            ));
+        }
+        if !self.resolved_attributes.insert(referent.clone()) {
+            return Err(err_msg!(
+                "Attribute referent already resolved: {}",
+                referent
+            ));
+        }
+        self.self_attested.insert(referent, value.into());
+        Ok(self)
+    }
+
+    /// The attribute, predicate and range disclosure referents of the request
+    /// which have not yet been resolved, in order
+    pub fn unresolved_referents(&self) -> Vec<&str> {
+        let pres_req = self.pres_req.value();
+        let attributes = pres_req
+            .requested_attributes
+            .keys()
+            .filter(|referent| !self.resolved_attributes.contains(*referent));
+        let predicates = pres_req
+            .requested_predicates
+            .keys()
+            .chain(pres_req.requested_range_disclosures.keys())
+            .filter(|referent| !self.resolved_predicates.contains(*referent));
+        attributes
+            .chain(predicates)
+            .map(String::as_str)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Create the presentation, once every referent of the request has been
+    /// resolved
+    pub fn build(
+        self,
+        master_secret: &MasterSecret,
+        schemas: &HashMap<SchemaId, &Schema>,
+        cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+    ) -> Result<Presentation> {
+        let unresolved = self.unresolved_referents();
+        if !unresolved.is_empty() {
+            return Err(err_msg!(
+                InvalidState,
+                "Presentation referents not resolved: {}",
+                unresolved.join(", ")
+            ));
+        }
+        create_presentation(
+            self.pres_req,
+            self.credentials,
+            Some(self.self_attested),
+            master_secret,
+            schemas,
+            cred_defs,
+        )
+    }
+
+    fn attribute_info(&self, referent: &str) -> Result<&'p AttributeInfo> {
+        self.pres_req
+            .value()
+            .requested_attributes
+            .get(referent)
+            .ok_or_else(|| {
+                err_msg!(
+                    "Attribute referent not found in presentation request: {}",
+                    referent
+                )
+            })
+    }
+}
+
+/// The choices made for a credential added to a `PresentationBuilder`
+#[derive(Debug)]
+pub struct CredentialStep<'b, 'p> {
+    builder: &'b mut PresentationBuilder<'p>,
+    index: usize,
+}
+
+impl<'p> CredentialStep<'_, 'p> {
+    /// Present an attribute referent from the credential, revealing its
+    /// value when `revealed` is set. The credential must contain the
+    /// requested attributes and satisfy the restrictions of the referent.
+    pub fn add_attribute(
+        &mut self,
+        referent: impl Into<String>,
+        revealed: bool,
+    ) -> Result<&mut Self> {
+        let referent = referent.into();
+        self.builder.attribute_info(&referent)?;
+        if self.builder.resolved_attributes.contains(&referent) {
+            return Err(err_msg!(
+                "Attribute referent already resolved: {}",
+                referent
+            ));
+        }
+        if !credential_matches_referent(self.builder.pres_req, &referent, self.credential())? {
+            return Err(err_msg!(
+                "Credential does not satisfy the attribute referent: {}",
+                referent
+            ));
+        }
+        self.builder.resolved_attributes.insert(referent.clone());
+        self.builder.credentials.0[self.index]
+            .requested_attributes
+            .insert((referent, revealed));
+        Ok(self)
+    }
+
+    /// Prove a predicate or range disclosure referent from the credential.
+    /// The value of the attribute must satisfy the predicate or belong to the
+    /// set, and the credential must satisfy the restrictions of the referent.
+    pub fn add_predicate(&mut self, referent: impl Into<String>) -> Result<&mut Self> {
+        let referent = referent.into();
+        let pres_req = self.builder.pres_req.value();
+        if !pres_req.requested_predicates.contains_key(&referent)
+            && !pres_req.requested_range_disclosures.contains_key(&referent)
+        {
+            return Err(err_msg!(
+                "Predicate referent not found in presentation request: {}",
+                referent
+            ));
+        }
+        if self.builder.resolved_predicates.contains(&referent) {
+            return Err(err_msg!(
+                "Predicate referent already resolved: {}",
+                referent
+            ));
+        }
+        if !credential_matches_referent(self.builder.pres_req, &referent, self.credential())? {
+            return Err(err_msg!(
+                "Credential does not satisfy the predicate referent: {}",
+                referent
+            ));
+        }
+        self.builder.resolved_predicates.insert(referent.clone());
+        self.builder.credentials.0[self.index]
+            .requested_predicates
+            .insert(referent);
+        Ok(self)
+    }
+
+    /// Attach the revocation state used to prove that the credential was not
+    /// revoked at the time of the state
+    pub fn revocation_state(
+        &mut self,
+        rev_state: &'p CredentialRevocationState,
+    ) -> Result<&mut Self> {
+        let present = &mut self.builder.credentials.0[self.index];
+        if present.cred.rev_reg_id.is_none() {
+            return Err(err_msg!("Credential does not support revocation"));
+        }
+        if present.rev_state.is_some() {
+            return Err(err_msg!("Credential revocation state already attached"));
+        }
+        rev_state.validate()?;
+        present.timestamp = Some(rev_state.timestamp);
+        present.rev_state = Some(rev_state);
+        Ok(self)
+    }
+
+    fn credential(&self) -> &'p Credential {
+        self.builder.credentials.0[self.index].cred
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_utils::{CredDefFixture, HolderFixture};
+    use crate::services::verifier;
+
+    #[test]
+    fn presentation_builder_validates_steps() {
+        let fixture = CredDefFixture::with_attributes(vec!["name", "age"]).unwrap();
+        let holder = HolderFixture::new().unwrap();
+        let credential = fixture
+            .issue(&holder, &[("name", "Alex"), ("age", "28")])
+            .unwrap();
+
+        let pres_req: PresentationRequest = serde_json::from_value(serde_json::json!({
+            "nonce": "1234567890",
+            "name": "builder",
+            "version": "1.0",
+            "requested_attributes": {
+                "name": {"name": "name"},
+                "phone": {"name": "phone"}
+            },
+            "requested_predicates": {
+                "adult": {"name": "age", "p_type": ">=", "p_value": 18},
+                "senior": {"name": "age", "p_type": ">=", "p_value": 65}
+            }
+        }))
+        .unwrap();
+        let (schemas, cred_defs) = (fixture.schemas(), fixture.cred_defs());
+
+        let mut builder = PresentationBuilder::new(&pres_req);
+        {
+            let mut step = builder.add_credential(&credential);
+            step.add_attribute("name", true).unwrap();
+            assert_kind!(Input, step.add_attribute("name", false));
+            assert_kind!(Input, step.add_attribute("phone", true));
+            assert_kind!(Input, step.add_attribute("email", true));
+            step.add_predicate("adult").unwrap();
+            assert_kind!(Input, step.add_predicate("senior"));
+        }
+        assert_eq!(builder.unresolved_referents(), vec!["phone", "senior"]);
+        builder.add_self_attested("phone", "555-1234").unwrap();
+        assert_kind!(Input, builder.add_self_attested("name", "Sam"));
+
+        let incomplete = PresentationBuilder::new(&pres_req);
+        assert_kind!(
+            InvalidState,
+            incomplete.build(&holder.master_secret, &schemas, &cred_defs)
+        );
+
+        let pres_req_met: PresentationRequest = {
+            let mut value = serde_json::to_value(&pres_req).unwrap();
+            value["requested_predicates"]
+                .as_object_mut()
+                .unwrap()
+                .remove("senior");
+            serde_json::from_value(value).unwrap()
+        };
+        let mut builder = PresentationBuilder::new(&pres_req_met);
+        builder
+            .add_credential(&credential)
+            .add_attribute("name", true)
+            .unwrap()
+            .add_predicate("adult")
+            .unwrap();
+        builder.add_self_attested("phone", "555-1234").unwrap();
+        assert!(builder.unresolved_referents().is_empty());
+        let presentation = builder
+            .build(&holder.master_secret, &schemas, &cred_defs)
+            .unwrap();
+        assert_eq!(
+            presentation.requested_proof.self_attested_attrs["phone"],
+            "555-1234"
+        );
+        assert!(verifier::verify_presentation(
+            &presentation,
+            &pres_req_met,
+            &schemas,
+            &cred_defs,
+            None,
+            None
+        )
+        .unwrap());
+    }
+}
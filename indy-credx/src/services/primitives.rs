@@ -0,0 +1,306 @@
+//! Access to the CL primitives underlying the credential objects.
+//!
+//! The ursa structures used by issuance and presentation are re-exported
+//! here together with wrappers for the steps most easily misused when
+//! driving the CL library directly: attribute names are normalized the same
+//! way as in the credential schema, the link secret is added as the hidden
+//! `master_secret` attribute, predicate bounds are checked, and failures are
+//! reported as library errors. The wrappers accept the objects of the
+//! regular services, so that prototypes of new protocols may reuse existing
+//! schemas, credential definitions and credentials.
+
+use std::collections::HashSet;
+
+use super::helpers::{
+    attr_common_view, build_credential_schema, build_credential_values,
+    build_non_credential_schema, check_predicate_bound,
+};
+use super::types::{
+    CredentialDefinition, CredentialOffer, CredentialRevocationState, RevocationRegistry,
+    RevocationRegistryDefinition, Schema,
+};
+use crate::error::Result;
+use crate::ursa::cl::{
+    prover::{ProofBuilder as CryptoProofBuilder, Prover as CryptoProver},
+    verifier::{ProofVerifier as CryptoProofVerifier, Verifier as CryptoVerifier},
+    SubProofRequestBuilder as CryptoSubProofRequestBuilder,
+};
+use indy_data_types::anoncreds::{
+    nonce::Nonce,
+    pres_request::{PredicateInfo, PredicateTypes},
+};
+
+pub use crate::ursa::cl::{
+    BlindedCredentialSecrets, BlindedCredentialSecretsCorrectnessProof,
+    CredentialKeyCorrectnessProof, CredentialPrivateKey, CredentialPublicKey, CredentialSchema,
+    CredentialSecretsBlindingFactors, CredentialSignature, CredentialValues, MasterSecret,
+    NonCredentialSchema, Proof, RevocationKeyPublic, SignatureCorrectnessProof, SubProofRequest,
+    Witness,
+};
+
+/// The hidden attribute of each credential holding the link secret
+pub const MASTER_SECRET_ATTRIBUTE: &str = "master_secret";
+
+/// Build the CL schema of the attributes of a credential schema
+pub fn credential_schema(schema: &Schema) -> Result<CredentialSchema> {
+    let Schema::SchemaV1(schema) = schema;
+    build_credential_schema(&schema.attr_names.0)
+}
+
+/// Build the CL schema of the hidden attributes, consisting of the link
+/// secret
+pub fn non_credential_schema() -> Result<NonCredentialSchema> {
+    build_non_credential_schema()
+}
+
+/// Build the CL values of a credential, with the link secret as a hidden
+/// value when provided
+pub fn credential_values(
+    values: &super::types::CredentialValues,
+    master_secret: Option<&super::types::MasterSecret>,
+) -> Result<CredentialValues> {
+    build_credential_values(&values.0, master_secret.map(|ms| &ms.value))
+}
+
+/// Fetch the CL public key of a credential definition
+pub fn public_key(cred_def: &CredentialDefinition) -> Result<CredentialPublicKey> {
+    let CredentialDefinition::CredentialDefinitionV1(cred_def) = cred_def;
+    Ok(CredentialPublicKey::build_from_parts(
+        &cred_def.value.primary,
+        cred_def.value.revocation.as_ref(),
+    )?)
+}
+
+/// Blind the link secret for a credential offer, checking the key
+/// correctness proof of the offer
+pub fn blind_master_secret(
+    cred_def: &CredentialDefinition,
+    master_secret: &super::types::MasterSecret,
+    cred_offer: &CredentialOffer,
+) -> Result<(
+    BlindedCredentialSecrets,
+    CredentialSecretsBlindingFactors,
+    BlindedCredentialSecretsCorrectnessProof,
+)> {
+    let credential_pub_key = public_key(cred_def)?;
+    let mut values = crate::ursa::cl::issuer::Issuer::new_credential_values_builder()?;
+    values.add_value_hidden(MASTER_SECRET_ATTRIBUTE, &master_secret.value.value()?)?;
+    Ok(CryptoProver::blind_credential_secrets(
+        &credential_pub_key,
+        &cred_offer.key_correctness_proof,
+        &values.finalize()?,
+        cred_offer.nonce.as_native(),
+    )?)
+}
+
+/// The attributes revealed and predicates proven for a single credential
+#[derive(Debug)]
+pub struct SubProofRequestBuilder {
+    inner: CryptoSubProofRequestBuilder,
+    names: HashSet<String>,
+}
+
+impl SubProofRequestBuilder {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            inner: CryptoVerifier::new_sub_proof_request_builder()?,
+            names: HashSet::new(),
+        })
+    }
+
+    /// Reveal the value of an attribute
+    pub fn reveal(&mut self, name: &str) -> Result<&mut Self> {
+        let name = self.add_name(name)?;
+        self.inner.add_revealed_attr(&name)?;
+        Ok(self)
+    }
+
+    /// Prove a predicate on the value of an attribute
+    pub fn predicate(
+        &mut self,
+        name: &str,
+        p_type: PredicateTypes,
+        p_value: i32,
+    ) -> Result<&mut Self> {
+        let predicate = PredicateInfo {
+            name: name.to_string(),
+            p_type,
+            p_value,
+            restrictions: None,
+            non_revoked: None,
+        };
+        check_predicate_bound(&predicate)?;
+        let name = self.add_name(name)?;
+        self.inner
+            .add_predicate(&name, &predicate.p_type.to_string(), p_value)?;
+        Ok(self)
+    }
+
+    pub fn finalize(self) -> Result<SubProofRequest> {
+        Ok(self.inner.finalize()?)
+    }
+
+    fn add_name(&mut self, name: &str) -> Result<String> {
+        let name = attr_common_view(name);
+        if name == MASTER_SECRET_ATTRIBUTE {
+            return Err(err_msg!("The link secret cannot be revealed or proven"));
+        }
+        if !self.names.insert(name.clone()) {
+            return Err(err_msg!("Attribute already requested: {}", name));
+        }
+        Ok(name)
+    }
+}
+
+/// A CL proof over credentials sharing a link secret
+#[derive(Debug)]
+pub struct ProofBuilder<'a> {
+    inner: CryptoProofBuilder,
+    non_credential_schema: NonCredentialSchema,
+    master_secret: &'a super::types::MasterSecret,
+}
+
+impl<'a> ProofBuilder<'a> {
+    pub fn new(master_secret: &'a super::types::MasterSecret) -> Result<Self> {
+        let mut inner = CryptoProver::new_proof_builder()?;
+        inner.add_common_attribute(MASTER_SECRET_ATTRIBUTE)?;
+        Ok(Self {
+            inner,
+            non_credential_schema: build_non_credential_schema()?,
+            master_secret,
+        })
+    }
+
+    /// Add a sub-proof for a credential, with the revocation state used to
+    /// prove that the credential was not revoked
+    pub fn add_credential(
+        &mut self,
+        sub_proof_request: &SubProofRequest,
+        schema: &Schema,
+        cred_def: &CredentialDefinition,
+        credential: &super::types::Credential,
+        rev_state: Option<&CredentialRevocationState>,
+    ) -> Result<&mut Self> {
+        if credential.cred_def_id != *cred_def.id() {
+            return Err(err_msg!(
+                "Credential was not issued under the credential definition: {}",
+                cred_def.id()
+            ));
+        }
+        if rev_state.is_some() && credential.rev_reg_id.is_none() {
+            return Err(err_msg!("Credential does not support revocation"));
+        }
+        let credential_values = credential_values(&credential.values, Some(self.master_secret))?;
+        self.inner.add_sub_proof_request(
+            sub_proof_request,
+            &credential_schema(schema)?,
+            &self.non_credential_schema,
+            &credential.signature,
+            &credential_values,
+            &public_key(cred_def)?,
+            rev_state.map(|state| &state.rev_reg),
+            rev_state.map(|state| &state.witness),
+        )?;
+        Ok(self)
+    }
+
+    pub fn finalize(self, nonce: &Nonce) -> Result<Proof> {
+        Ok(self.inner.finalize(nonce.as_native())?)
+    }
+}
+
+/// The verification of a CL proof created by a `ProofBuilder`
+#[derive(Debug)]
+pub struct ProofVerifier {
+    inner: CryptoProofVerifier,
+    non_credential_schema: NonCredentialSchema,
+}
+
+impl ProofVerifier {
+    pub fn new() -> Result<Self> {
+        let mut inner = CryptoVerifier::new_proof_verifier()?;
+        inner.add_common_attribute(MASTER_SECRET_ATTRIBUTE)?;
+        Ok(Self {
+            inner,
+            non_credential_schema: build_non_credential_schema()?,
+        })
+    }
+
+    /// Add the expected sub-proof for a credential, in the order in which
+    /// the credentials were added to the proof. The revocation registry and
+    /// its definition are required for credentials proven not to be revoked.
+    pub fn add_credential(
+        &mut self,
+        sub_proof_request: &SubProofRequest,
+        schema: &Schema,
+        cred_def: &CredentialDefinition,
+        rev_reg_def: Option<&RevocationRegistryDefinition>,
+        rev_reg: Option<&RevocationRegistry>,
+    ) -> Result<&mut Self> {
+        let rev_key_pub = rev_reg_def.map(|rev_reg_def| match rev_reg_def {
+            RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) => {
+                &rev_reg_def.value.public_keys.accum_key
+            }
+        });
+        let rev_reg = rev_reg.map(|rev_reg| match rev_reg {
+            RevocationRegistry::RevocationRegistryV1(rev_reg) => &rev_reg.value,
+        });
+        self.inner.add_sub_proof_request(
+            sub_proof_request,
+            &credential_schema(schema)?,
+            &self.non_credential_schema,
+            &public_key(cred_def)?,
+            rev_key_pub,
+            rev_reg,
+        )?;
+        Ok(self)
+    }
+
+    pub fn verify(self, proof: &Proof, nonce: &Nonce) -> Result<bool> {
+        let mut inner = self.inner;
+        Ok(inner.verify(proof, nonce.as_native())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::helpers::new_nonce;
+    use crate::services::test_utils::{CredDefFixture, HolderFixture};
+
+    #[test]
+    fn primitive_proof_round_trip() {
+        let fixture = CredDefFixture::with_attributes(vec!["name", "age"]).unwrap();
+        let (schema, cred_def) = (&fixture.schema, &fixture.cred_def);
+        let holder = HolderFixture::new().unwrap();
+        let credential = fixture
+            .issue(&holder, &[("name", "Alex"), ("age", "28")])
+            .unwrap();
+
+        let mut request = SubProofRequestBuilder::new().unwrap();
+        request
+            .reveal("Name")
+            .unwrap()
+            .predicate("age", PredicateTypes::GE, 18)
+            .unwrap();
+        assert!(request.reveal("name").is_err());
+        assert!(request.reveal("master_secret").is_err());
+        assert!(request
+            .predicate("age", PredicateTypes::GT, i32::MAX)
+            .is_err());
+        let sub_proof_request = request.finalize().unwrap();
+
+        let nonce = new_nonce().unwrap();
+        let mut builder = ProofBuilder::new(&holder.master_secret).unwrap();
+        builder
+            .add_credential(&sub_proof_request, schema, cred_def, &credential, None)
+            .unwrap();
+        let proof = builder.finalize(&nonce).unwrap();
+
+        let mut verifier = ProofVerifier::new().unwrap();
+        verifier
+            .add_credential(&sub_proof_request, schema, cred_def, None, None)
+            .unwrap();
+        assert!(verifier.verify(&proof, &nonce).unwrap());
+    }
+}
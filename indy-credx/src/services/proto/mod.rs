@@ -0,0 +1,545 @@
+//! Protocol buffer encoding of the core objects, following the message
+//! definitions in `proto/indy_credx.proto`.
+//!
+//! The messages are generated by prost from the definitions. Identifiers and
+//! attribute values are encoded as protocol buffer fields, while the CL
+//! cryptographic values are carried as their JSON serialization in `bytes`
+//! fields. Map entries and repeated fields are written in sorted order, so
+//! that equal objects have equal encodings.
+
+use std::collections::{BTreeMap, HashMap};
+
+use indy_data_types::anoncreds::{
+    cred_def::{CredentialDefinitionData, CredentialDefinitionV1},
+    presentation::{
+        AttributeValue, Identifier, RequestedProof, RevealedAttributeGroupInfo,
+        RevealedAttributeInfo, SubProofReferent,
+    },
+    schema::SchemaV1,
+};
+use prost::Message;
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::types::{
+    AttributeDisplay, AttributeValues, Credential, CredentialDefinition, CredentialDisplay,
+    CredentialValues, Presentation, Schema, SignatureType,
+};
+use crate::error::Result;
+
+/// The messages generated from the protocol buffer definitions
+pub mod v1 {
+    include!(concat!(env!("OUT_DIR"), "/indy.credx.v1.rs"));
+}
+
+/// The protocol buffer definitions of the supported objects
+pub const PROTO_DEFINITIONS: &str = include_str!("../../../proto/indy_credx.proto");
+
+/// An object with a protocol buffer encoding
+pub trait ProtoMessage: Sized {
+    /// The generated message for the object
+    type Message: Message + Default;
+
+    /// Convert the object to its protocol buffer message
+    fn to_message(&self) -> Result<Self::Message>;
+
+    /// Convert the object from its protocol buffer message
+    fn from_message(message: Self::Message) -> Result<Self>;
+
+    /// Encode the object as a protocol buffer message
+    fn to_proto(&self) -> Result<Vec<u8>> {
+        Ok(self.to_message()?.encode_to_vec())
+    }
+
+    /// Decode the object from a protocol buffer message
+    fn from_proto(data: &[u8]) -> Result<Self> {
+        let message = Self::Message::decode(data).map_err(err_map!("Invalid protobuf message"))?;
+        Self::from_message(message)
+    }
+}
+
+/// The JSON serialization of a value carried in a `bytes` field, with the
+/// object keys in sorted order
+pub(crate) fn to_json_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    Ok(serde_json::to_vec(&serde_json::to_value(value)?)?)
+}
+
+pub(crate) fn from_json_bytes<T: DeserializeOwned>(data: &[u8]) -> Result<T> {
+    Ok(serde_json::from_slice(data)?)
+}
+
+fn attribute_value(raw: &str, encoded: &str) -> v1::AttributeValue {
+    v1::AttributeValue {
+        raw: raw.to_string(),
+        encoded: encoded.to_string(),
+    }
+}
+
+impl ProtoMessage for Schema {
+    type Message = v1::Schema;
+
+    fn to_message(&self) -> Result<v1::Schema> {
+        let Schema::SchemaV1(schema) = self;
+        let mut attr_names: Vec<String> = schema.attr_names.0.iter().cloned().collect();
+        attr_names.sort();
+        Ok(v1::Schema {
+            id: schema.id.to_string(),
+            name: schema.name.clone(),
+            version: schema.version.clone(),
+            attr_names,
+            seq_no: schema.seq_no,
+        })
+    }
+
+    fn from_message(message: v1::Schema) -> Result<Self> {
+        Ok(Schema::SchemaV1(SchemaV1 {
+            id: message.id.into(),
+            name: message.name,
+            version: message.version,
+            attr_names: message.attr_names.into(),
+            seq_no: message.seq_no,
+        }))
+    }
+}
+
+impl ProtoMessage for CredentialDefinition {
+    type Message = v1::CredentialDefinition;
+
+    fn to_message(&self) -> Result<v1::CredentialDefinition> {
+        let CredentialDefinition::CredentialDefinitionV1(cred_def) = self;
+        Ok(v1::CredentialDefinition {
+            id: cred_def.id.to_string(),
+            schema_id: cred_def.schema_id.to_string(),
+            signature_type: cred_def.signature_type.to_str().to_string(),
+            tag: cred_def.tag.clone(),
+            primary: to_json_bytes(&cred_def.value.primary)?,
+            revocation: cred_def
+                .value
+                .revocation
+                .as_ref()
+                .map(to_json_bytes)
+                .transpose()?,
+        })
+    }
+
+    fn from_message(message: v1::CredentialDefinition) -> Result<Self> {
+        if message.primary.is_empty() {
+            return Err(err_msg!("Missing protobuf field: primary"));
+        }
+        Ok(CredentialDefinition::CredentialDefinitionV1(
+            CredentialDefinitionV1 {
+                id: message.id.into(),
+                schema_id: message.schema_id.into(),
+                signature_type: SignatureType::from_str(&message.signature_type)
+                    .map_err(err_map!("Invalid signature type"))?,
+                tag: message.tag,
+                value: CredentialDefinitionData {
+                    primary: from_json_bytes(&message.primary)?,
+                    revocation: message
+                        .revocation
+                        .as_deref()
+                        .map(from_json_bytes)
+                        .transpose()?,
+                },
+            },
+        ))
+    }
+}
+
+fn display_to_message(display: &CredentialDisplay) -> v1::CredentialDisplay {
+    v1::CredentialDisplay {
+        attributes: display
+            .0
+            .iter()
+            .map(|(name, attr)| {
+                let message = v1::AttributeDisplay {
+                    labels: attr
+                        .labels
+                        .iter()
+                        .map(|(lang, label)| (lang.clone(), label.clone()))
+                        .collect(),
+                    mime_type: attr.mime_type.clone(),
+                    format: attr.format.clone(),
+                };
+                (name.clone(), message)
+            })
+            .collect(),
+    }
+}
+
+fn display_from_message(message: v1::CredentialDisplay) -> CredentialDisplay {
+    let mut display = CredentialDisplay::default();
+    for (name, attr) in message.attributes {
+        display.0.insert(
+            name,
+            AttributeDisplay {
+                labels: attr.labels.into_iter().collect(),
+                mime_type: attr.mime_type,
+                format: attr.format,
+            },
+        );
+    }
+    display
+}
+
+impl ProtoMessage for Credential {
+    type Message = v1::Credential;
+
+    fn to_message(&self) -> Result<v1::Credential> {
+        Ok(v1::Credential {
+            schema_id: self.schema_id.to_string(),
+            cred_def_id: self.cred_def_id.to_string(),
+            rev_reg_id: self.rev_reg_id.as_ref().map(|id| id.to_string()),
+            values: self
+                .values
+                .0
+                .iter()
+                .map(|(name, value)| (name.clone(), attribute_value(&value.raw, &value.encoded)))
+                .collect(),
+            signature: to_json_bytes(&self.signature)?,
+            signature_correctness_proof: to_json_bytes(&self.signature_correctness_proof)?,
+            rev_reg: self.rev_reg.as_ref().map(to_json_bytes).transpose()?,
+            witness: self.witness.as_ref().map(to_json_bytes).transpose()?,
+            display: self.display.as_ref().map(display_to_message),
+        })
+    }
+
+    fn from_message(message: v1::Credential) -> Result<Self> {
+        if message.signature.is_empty() {
+            return Err(err_msg!("Missing protobuf field: signature"));
+        }
+        if message.signature_correctness_proof.is_empty() {
+            return Err(err_msg!(
+                "Missing protobuf field: signature_correctness_proof"
+            ));
+        }
+        Ok(Credential {
+            schema_id: message.schema_id.into(),
+            cred_def_id: message.cred_def_id.into(),
+            rev_reg_id: message.rev_reg_id.map(Into::into),
+            values: CredentialValues(
+                message
+                    .values
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let value = AttributeValues {
+                            raw: value.raw,
+                            encoded: value.encoded,
+                        };
+                        (name, value)
+                    })
+                    .collect(),
+            ),
+            signature: from_json_bytes(&message.signature)?,
+            signature_correctness_proof: from_json_bytes(&message.signature_correctness_proof)?,
+            rev_reg: message
+                .rev_reg
+                .as_deref()
+                .map(from_json_bytes)
+                .transpose()?,
+            witness: message
+                .witness
+                .as_deref()
+                .map(from_json_bytes)
+                .transpose()?,
+            display: message.display.map(display_from_message),
+        })
+    }
+}
+
+fn revealed_attribute_to_message(info: &RevealedAttributeInfo) -> v1::RevealedAttribute {
+    v1::RevealedAttribute {
+        sub_proof_index: info.sub_proof_index,
+        raw: info.raw.clone(),
+        encoded: info.encoded.clone(),
+    }
+}
+
+fn revealed_attribute_from_message(message: v1::RevealedAttribute) -> RevealedAttributeInfo {
+    RevealedAttributeInfo {
+        sub_proof_index: message.sub_proof_index,
+        raw: message.raw,
+        encoded: message.encoded,
+    }
+}
+
+fn sub_proof_referents_to_message(
+    referents: &HashMap<String, SubProofReferent>,
+) -> BTreeMap<String, v1::SubProofReferent> {
+    referents
+        .iter()
+        .map(|(referent, info)| {
+            let message = v1::SubProofReferent {
+                sub_proof_index: info.sub_proof_index,
+            };
+            (referent.clone(), message)
+        })
+        .collect()
+}
+
+fn sub_proof_referents_from_message(
+    referents: BTreeMap<String, v1::SubProofReferent>,
+) -> HashMap<String, SubProofReferent> {
+    referents
+        .into_iter()
+        .map(|(referent, info)| {
+            let info = SubProofReferent {
+                sub_proof_index: info.sub_proof_index,
+            };
+            (referent, info)
+        })
+        .collect()
+}
+
+fn requested_proof_to_message(proof: &RequestedProof) -> v1::RequestedProof {
+    v1::RequestedProof {
+        revealed_attrs: proof
+            .revealed_attrs
+            .iter()
+            .map(|(referent, info)| (referent.clone(), revealed_attribute_to_message(info)))
+            .collect(),
+        revealed_attr_groups: proof
+            .revealed_attr_groups
+            .iter()
+            .map(|(referent, group)| {
+                let message = v1::RevealedAttributeGroup {
+                    sub_proof_index: group.sub_proof_index,
+                    values: group
+                        .values
+                        .iter()
+                        .map(|(name, value)| {
+                            (name.clone(), attribute_value(&value.raw, &value.encoded))
+                        })
+                        .collect(),
+                };
+                (referent.clone(), message)
+            })
+            .collect(),
+        self_attested_attrs: proof
+            .self_attested_attrs
+            .iter()
+            .map(|(referent, value)| (referent.clone(), value.clone()))
+            .collect(),
+        unrevealed_attrs: sub_proof_referents_to_message(&proof.unrevealed_attrs),
+        predicates: sub_proof_referents_to_message(&proof.predicates),
+        revealed_predicates: proof
+            .revealed_predicates
+            .iter()
+            .map(|(referent, info)| (referent.clone(), revealed_attribute_to_message(info)))
+            .collect(),
+    }
+}
+
+fn requested_proof_from_message(message: v1::RequestedProof) -> RequestedProof {
+    RequestedProof {
+        revealed_attrs: message
+            .revealed_attrs
+            .into_iter()
+            .map(|(referent, info)| (referent, revealed_attribute_from_message(info)))
+            .collect(),
+        revealed_attr_groups: message
+            .revealed_attr_groups
+            .into_iter()
+            .map(|(referent, group)| {
+                let group = RevealedAttributeGroupInfo {
+                    sub_proof_index: group.sub_proof_index,
+                    values: group
+                        .values
+                        .into_iter()
+                        .map(|(name, value)| {
+                            let value = AttributeValue {
+                                raw: value.raw,
+                                encoded: value.encoded,
+                            };
+                            (name, value)
+                        })
+                        .collect(),
+                };
+                (referent, group)
+            })
+            .collect(),
+        self_attested_attrs: message.self_attested_attrs.into_iter().collect(),
+        unrevealed_attrs: sub_proof_referents_from_message(message.unrevealed_attrs),
+        predicates: sub_proof_referents_from_message(message.predicates),
+        revealed_predicates: message
+            .revealed_predicates
+            .into_iter()
+            .map(|(referent, info)| (referent, revealed_attribute_from_message(info)))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+impl ProtoMessage for Presentation {
+    type Message = v1::Presentation;
+
+    fn to_message(&self) -> Result<v1::Presentation> {
+        let mut identifiers: Vec<v1::Identifier> = self
+            .identifiers
+            .iter()
+            .map(|id| v1::Identifier {
+                schema_id: id.schema_id.to_string(),
+                cred_def_id: id.cred_def_id.to_string(),
+                rev_reg_id: id.rev_reg_id.as_ref().map(|id| id.to_string()),
+                timestamp: id.timestamp,
+            })
+            .collect();
+        identifiers.sort_by(|a, b| {
+            let key = |id: &v1::Identifier| {
+                (
+                    id.schema_id.clone(),
+                    id.cred_def_id.clone(),
+                    id.rev_reg_id.clone(),
+                    id.timestamp,
+                )
+            };
+            key(a).cmp(&key(b))
+        });
+        Ok(v1::Presentation {
+            proof: to_json_bytes(&self.proof)?,
+            requested_proof: Some(requested_proof_to_message(&self.requested_proof)),
+            identifiers,
+        })
+    }
+
+    fn from_message(message: v1::Presentation) -> Result<Self> {
+        if message.proof.is_empty() {
+            return Err(err_msg!("Missing protobuf field: proof"));
+        }
+        Ok(Presentation {
+            proof: from_json_bytes(&message.proof)?,
+            requested_proof: message
+                .requested_proof
+                .map(requested_proof_from_message)
+                .unwrap_or_default(),
+            identifiers: message
+                .identifiers
+                .into_iter()
+                .map(|id| Identifier {
+                    schema_id: id.schema_id.into(),
+                    cred_def_id: id.cred_def_id.into(),
+                    rev_reg_id: id.rev_reg_id.map(Into::into),
+                    timestamp: id.timestamp,
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{
+        flows::issue_credential,
+        issuer, prover,
+        test_utils::{credential_values, did, CredDefFixture, HolderFixture},
+        types::*,
+        verifier,
+    };
+
+    fn assert_round_trip<T: ProtoMessage + serde::Serialize>(obj: &T) -> T {
+        let decoded = assert_canonical(obj);
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(obj).unwrap()
+        );
+        decoded
+    }
+
+    /// Decode the encoding of `obj`, checking that it encodes back to the
+    /// same bytes
+    fn assert_canonical<T: ProtoMessage>(obj: &T) -> T {
+        let encoded = obj.to_proto().unwrap();
+        let decoded = T::from_proto(&encoded).unwrap();
+        assert_eq!(decoded.to_proto().unwrap(), encoded);
+        decoded
+    }
+
+    #[test]
+    fn proto_round_trip() {
+        let did = did();
+        let schema = issuer::create_schema(
+            &did,
+            "gvt",
+            "1.0",
+            vec!["name".to_string(), "age".to_string()].into(),
+            Some(15),
+        )
+        .unwrap();
+        let fixture = CredDefFixture::new(did, schema, false).unwrap();
+        // the attribute names are a set, which serializes in no fixed order
+        let (Schema::SchemaV1(decoded), Schema::SchemaV1(schema)) =
+            (assert_canonical(&fixture.schema), &fixture.schema);
+        assert_eq!(decoded.attr_names.0, schema.attr_names.0);
+        assert_eq!(
+            (&decoded.id, &decoded.name, &decoded.version, decoded.seq_no),
+            (&schema.id, &schema.name, &schema.version, schema.seq_no)
+        );
+        assert_round_trip(&fixture.cred_def);
+
+        let holder = HolderFixture::new().unwrap();
+        let mut issued = issue_credential(
+            fixture.issuer_objects(),
+            holder.holder_objects(),
+            credential_values(&[("name", "Alex"), ("age", "28")]).unwrap(),
+        )
+        .unwrap();
+        assert_round_trip(&issued.credential);
+        let mut display = CredentialDisplay::default();
+        display
+            .label("name", "en", "Name")
+            .mime_type("name", "text/plain");
+        issued.credential.display = Some(display);
+        let credential = assert_round_trip(&issued.credential);
+
+        let pres_req: PresentationRequest = serde_json::from_value(serde_json::json!({
+            "nonce": verifier::generate_nonce().unwrap(),
+            "name": "proto",
+            "version": "1.0",
+            "requested_attributes": {"name": {"name": "name"}},
+            "requested_predicates": {
+                "age_ge": {"name": "age", "p_type": ">=", "p_value": 18}
+            },
+        }))
+        .unwrap();
+        let mut present = PresentCredentials::default();
+        {
+            let mut add = present.add_credential(&credential, None, None);
+            add.add_requested_attribute("name", true);
+            add.add_requested_predicate("age_ge");
+        }
+        let (schemas, cred_defs) = (fixture.schemas(), fixture.cred_defs());
+        let presentation = prover::create_presentation(
+            &pres_req,
+            present,
+            None,
+            &holder.master_secret,
+            &schemas,
+            &cred_defs,
+        )
+        .unwrap();
+        let presentation = assert_round_trip(&presentation);
+        assert!(verifier::verify_presentation(
+            &presentation,
+            &pres_req,
+            &schemas,
+            &cred_defs,
+            None,
+            None,
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn proto_rejects_invalid() {
+        assert!(Schema::from_proto(&[0x0a, 0x05, b'a']).is_err());
+        assert!(Schema::from_proto(&[0x0b]).is_err());
+        assert!(Schema::from_proto(&[0x0a, 0x01, 0xff]).is_err());
+        assert!(Credential::from_proto(&[]).is_err());
+        // unknown fields are skipped
+        let schema =
+            Schema::from_proto(&[0x12, 0x01, b'a', 0x30, 0x01, 0x39, 0, 0, 0, 0, 0, 0, 0, 0])
+                .unwrap();
+        let Schema::SchemaV1(schema) = schema;
+        assert_eq!(schema.name, "a");
+    }
+}
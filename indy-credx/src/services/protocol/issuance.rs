@@ -0,0 +1,264 @@
+//! Issuance of a credential: offer, request, then issue
+
+use super::check_state;
+use crate::error::Result;
+use crate::services::issuer::{create_credential, create_credential_offer};
+use crate::services::prover::{create_credential_request, process_credential};
+use crate::services::types::{
+    Credential, CredentialDefinition, CredentialDefinitionPrivate, CredentialKeyCorrectnessProof,
+    CredentialOffer, CredentialRequest, CredentialRequestMetadata, CredentialRevocationConfig,
+    CredentialValues, DidValue, MasterSecret, RevocationRegistry, RevocationRegistryDefinition,
+    RevocationRegistryDelta, SchemaId,
+};
+
+/// The progress of an issuance exchange
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IssuanceState {
+    OfferSent,
+    OfferReceived,
+    RequestSent,
+    RequestReceived,
+    CredentialIssued,
+    CredentialReceived,
+}
+
+/// The issuer side of an issuance exchange
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssuerExchange {
+    state: IssuanceState,
+    offer: CredentialOffer,
+    request: Option<CredentialRequest>,
+    credential: Option<Credential>,
+}
+
+impl IssuerExchange {
+    /// Start an exchange by creating a credential offer
+    pub fn offer(
+        schema_id: &SchemaId,
+        cred_def: &CredentialDefinition,
+        correctness_proof: &CredentialKeyCorrectnessProof,
+    ) -> Result<Self> {
+        Ok(Self {
+            state: IssuanceState::OfferSent,
+            offer: create_credential_offer(schema_id, cred_def, correctness_proof)?,
+            request: None,
+            credential: None,
+        })
+    }
+
+    pub fn state(&self) -> IssuanceState {
+        self.state
+    }
+
+    pub fn credential_offer(&self) -> &CredentialOffer {
+        &self.offer
+    }
+
+    pub fn credential_request(&self) -> Option<&CredentialRequest> {
+        self.request.as_ref()
+    }
+
+    pub fn credential(&self) -> Option<&Credential> {
+        self.credential.as_ref()
+    }
+
+    /// Accept the request of the holder in response to the offer
+    pub fn receive_request(&mut self, request: CredentialRequest) -> Result<()> {
+        check_state(self.state, IssuanceState::OfferSent, "receive a request")?;
+        if request.cred_def_id != self.offer.cred_def_id {
+            return Err(err_msg!(
+                "Credential request does not correspond to the offered credential definition: {}",
+                request.cred_def_id
+            ));
+        }
+        self.request.replace(request);
+        self.state = IssuanceState::RequestReceived;
+        Ok(())
+    }
+
+    /// Issue the credential, returning any update to the revocation registry
+    pub fn issue(
+        &mut self,
+        cred_def: &CredentialDefinition,
+        cred_def_private: &CredentialDefinitionPrivate,
+        cred_values: CredentialValues,
+        revocation_config: Option<CredentialRevocationConfig>,
+    ) -> Result<(Option<RevocationRegistry>, Option<RevocationRegistryDelta>)> {
+        check_state(self.state, IssuanceState::RequestReceived, "issue")?;
+        let request = self
+            .request
+            .as_ref()
+            .ok_or_else(|| err_msg!(InvalidState, "Missing credential request"))?;
+        let (credential, rev_reg, rev_reg_delta) = create_credential(
+            cred_def,
+            cred_def_private,
+            &self.offer,
+            request,
+            cred_values,
+            revocation_config,
+        )?;
+        self.credential.replace(credential);
+        self.state = IssuanceState::CredentialIssued;
+        Ok((rev_reg, rev_reg_delta))
+    }
+}
+
+/// The holder side of an issuance exchange
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HolderExchange {
+    state: IssuanceState,
+    offer: CredentialOffer,
+    request: Option<CredentialRequest>,
+    request_metadata: Option<CredentialRequestMetadata>,
+    credential: Option<Credential>,
+}
+
+impl HolderExchange {
+    /// Start an exchange upon receiving a credential offer
+    pub fn receive_offer(offer: CredentialOffer) -> Self {
+        Self {
+            state: IssuanceState::OfferReceived,
+            offer,
+            request: None,
+            request_metadata: None,
+            credential: None,
+        }
+    }
+
+    pub fn state(&self) -> IssuanceState {
+        self.state
+    }
+
+    pub fn credential_offer(&self) -> &CredentialOffer {
+        &self.offer
+    }
+
+    pub fn credential_request(&self) -> Option<&CredentialRequest> {
+        self.request.as_ref()
+    }
+
+    pub fn credential(&self) -> Option<&Credential> {
+        self.credential.as_ref()
+    }
+
+    /// Take the processed credential, ending the exchange
+    pub fn into_credential(self) -> Option<Credential> {
+        self.credential
+    }
+
+    /// Create the credential request to be sent in response to the offer
+    pub fn request(
+        &mut self,
+        prover_did: &DidValue,
+        cred_def: &CredentialDefinition,
+        master_secret: &MasterSecret,
+        master_secret_id: &str,
+    ) -> Result<&CredentialRequest> {
+        check_state(self.state, IssuanceState::OfferReceived, "send a request")?;
+        if cred_def.id() != &self.offer.cred_def_id {
+            return Err(err_msg!(
+                "Credential definition does not correspond to the offer: {}",
+                cred_def.id()
+            ));
+        }
+        let (request, metadata) = create_credential_request(
+            prover_did,
+            cred_def,
+            master_secret,
+            master_secret_id,
+            &self.offer,
+        )?;
+        self.request_metadata.replace(metadata);
+        self.state = IssuanceState::RequestSent;
+        Ok(self.request.get_or_insert(request))
+    }
+
+    /// Accept and process the credential issued in response to the request
+    pub fn receive_credential(
+        &mut self,
+        mut credential: Credential,
+        master_secret: &MasterSecret,
+        cred_def: &CredentialDefinition,
+        rev_reg_def: Option<&RevocationRegistryDefinition>,
+    ) -> Result<&Credential> {
+        check_state(
+            self.state,
+            IssuanceState::RequestSent,
+            "receive a credential",
+        )?;
+        let metadata = self
+            .request_metadata
+            .as_ref()
+            .ok_or_else(|| err_msg!(InvalidState, "Missing credential request metadata"))?;
+        process_credential(
+            &mut credential,
+            metadata,
+            master_secret,
+            cred_def,
+            rev_reg_def,
+        )?;
+        self.state = IssuanceState::CredentialReceived;
+        Ok(self.credential.get_or_insert(credential))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::services::test_utils::{credential_values, CredDefFixture, HolderFixture};
+
+    #[test]
+    fn issuance_exchange_works() {
+        let fixture = CredDefFixture::with_attributes(vec!["name", "age"]).unwrap();
+        let (cred_def, cred_def_private) = (&fixture.cred_def, &fixture.cred_def_private);
+        let holder = HolderFixture::new().unwrap();
+        let (did, master_secret) = (&holder.did, &holder.master_secret);
+
+        let mut issuer_ex =
+            IssuerExchange::offer(fixture.schema.id(), cred_def, &fixture.key_proof).unwrap();
+        assert_eq!(issuer_ex.state(), IssuanceState::OfferSent);
+        let offer = serde_json::to_string(issuer_ex.credential_offer()).unwrap();
+
+        let mut holder_ex = HolderExchange::receive_offer(serde_json::from_str(&offer).unwrap());
+        let request = holder_ex
+            .request(did, cred_def, master_secret, "default")
+            .unwrap();
+        let request = serde_json::to_string(request).unwrap();
+        assert_eq!(holder_ex.state(), IssuanceState::RequestSent);
+        let err = holder_ex
+            .request(did, cred_def, master_secret, "default")
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidState);
+
+        let values = || credential_values(&[("name", "Alex"), ("age", "28")]).unwrap();
+        let err = issuer_ex
+            .issue(cred_def, cred_def_private, values(), None)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidState);
+
+        issuer_ex
+            .receive_request(serde_json::from_str(&request).unwrap())
+            .unwrap();
+        issuer_ex
+            .issue(cred_def, cred_def_private, values(), None)
+            .unwrap();
+        assert_eq!(issuer_ex.state(), IssuanceState::CredentialIssued);
+
+        let credential = serde_json::to_string(issuer_ex.credential().unwrap()).unwrap();
+        holder_ex
+            .receive_credential(
+                serde_json::from_str(&credential).unwrap(),
+                master_secret,
+                cred_def,
+                None,
+            )
+            .unwrap();
+        assert_eq!(holder_ex.state(), IssuanceState::CredentialReceived);
+        assert_eq!(
+            holder_ex.into_credential().unwrap().values.0["name"].raw,
+            "Alex"
+        );
+    }
+}
@@ -0,0 +1,27 @@
+//! State machines for the credential exchange protocols.
+//!
+//! Each participant in an exchange is represented by a type which records the
+//! objects sent and received so far, performs the corresponding service call
+//! for each step, and rejects steps taken out of order with an
+//! `InvalidState` error. Exchanges may be serialized to persist them between
+//! messages.
+
+pub mod issuance;
+pub mod presentation;
+
+fn check_state<S>(state: S, expected: S, action: &str) -> crate::error::Result<()>
+where
+    S: Copy + PartialEq + std::fmt::Debug,
+{
+    if state == expected {
+        Ok(())
+    } else {
+        Err(err_msg!(
+            InvalidState,
+            "Cannot {} in state {:?}, expected {:?}",
+            action,
+            state,
+            expected
+        ))
+    }
+}
@@ -0,0 +1,240 @@
+//! Presentation of credentials: request, present, then verify
+
+use std::collections::HashMap;
+
+use super::check_state;
+use crate::error::Result;
+use crate::services::prover::create_presentation;
+use crate::services::types::{
+    CredentialDefinition, CredentialDefinitionId, MasterSecret, PresentCredentials, Presentation,
+    PresentationRequest, RevocationRegistry, RevocationRegistryDefinition, RevocationRegistryId,
+    Schema, SchemaId, VerificationPolicy,
+};
+use crate::services::verifier::verify_presentation_with_policy;
+
+/// The progress of a presentation exchange
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PresentationState {
+    RequestSent,
+    RequestReceived,
+    PresentationSent,
+    PresentationReceived,
+    Verified,
+}
+
+/// The verifier side of a presentation exchange
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifierExchange {
+    state: PresentationState,
+    request: PresentationRequest,
+    presentation: Option<Presentation>,
+    verified: Option<bool>,
+}
+
+impl VerifierExchange {
+    /// Start an exchange by sending a presentation request
+    pub fn request(request: PresentationRequest) -> Self {
+        Self {
+            state: PresentationState::RequestSent,
+            request,
+            presentation: None,
+            verified: None,
+        }
+    }
+
+    pub fn state(&self) -> PresentationState {
+        self.state
+    }
+
+    pub fn presentation_request(&self) -> &PresentationRequest {
+        &self.request
+    }
+
+    pub fn presentation(&self) -> Option<&Presentation> {
+        self.presentation.as_ref()
+    }
+
+    /// The outcome of the verification, once performed
+    pub fn verified(&self) -> Option<bool> {
+        self.verified
+    }
+
+    /// Accept the presentation sent in response to the request
+    pub fn receive_presentation(&mut self, presentation: Presentation) -> Result<()> {
+        check_state(
+            self.state,
+            PresentationState::RequestSent,
+            "receive a presentation",
+        )?;
+        self.presentation.replace(presentation);
+        self.state = PresentationState::PresentationReceived;
+        Ok(())
+    }
+
+    /// Verify the received presentation against the request
+    pub fn verify(
+        &mut self,
+        schemas: &HashMap<SchemaId, &Schema>,
+        cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+        rev_reg_defs: Option<&HashMap<RevocationRegistryId, &RevocationRegistryDefinition>>,
+        rev_regs: Option<&HashMap<RevocationRegistryId, HashMap<u64, &RevocationRegistry>>>,
+    ) -> Result<bool> {
+        self.verify_with_policy(
+            schemas,
+            cred_defs,
+            rev_reg_defs,
+            rev_regs,
+            &VerificationPolicy::default(),
+        )
+    }
+
+    /// Verify the received presentation against the request and a policy
+    pub fn verify_with_policy(
+        &mut self,
+        schemas: &HashMap<SchemaId, &Schema>,
+        cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+        rev_reg_defs: Option<&HashMap<RevocationRegistryId, &RevocationRegistryDefinition>>,
+        rev_regs: Option<&HashMap<RevocationRegistryId, HashMap<u64, &RevocationRegistry>>>,
+        policy: &VerificationPolicy,
+    ) -> Result<bool> {
+        check_state(
+            self.state,
+            PresentationState::PresentationReceived,
+            "verify",
+        )?;
+        let presentation = self
+            .presentation
+            .as_ref()
+            .ok_or_else(|| err_msg!(InvalidState, "Missing presentation"))?;
+        let valid = verify_presentation_with_policy(
+            presentation,
+            &self.request,
+            schemas,
+            cred_defs,
+            rev_reg_defs,
+            rev_regs,
+            policy,
+        )?;
+        self.verified.replace(valid);
+        self.state = PresentationState::Verified;
+        Ok(valid)
+    }
+}
+
+/// The prover side of a presentation exchange
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProverExchange {
+    state: PresentationState,
+    request: PresentationRequest,
+    presentation: Option<Presentation>,
+}
+
+impl ProverExchange {
+    /// Start an exchange upon receiving a presentation request
+    pub fn receive_request(request: PresentationRequest) -> Self {
+        Self {
+            state: PresentationState::RequestReceived,
+            request,
+            presentation: None,
+        }
+    }
+
+    pub fn state(&self) -> PresentationState {
+        self.state
+    }
+
+    pub fn presentation_request(&self) -> &PresentationRequest {
+        &self.request
+    }
+
+    pub fn presentation(&self) -> Option<&Presentation> {
+        self.presentation.as_ref()
+    }
+
+    /// Create the presentation to be sent in response to the request
+    pub fn present(
+        &mut self,
+        credentials: PresentCredentials,
+        self_attested: Option<HashMap<String, String>>,
+        master_secret: &MasterSecret,
+        schemas: &HashMap<SchemaId, &Schema>,
+        cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+    ) -> Result<&Presentation> {
+        check_state(
+            self.state,
+            PresentationState::RequestReceived,
+            "send a presentation",
+        )?;
+        let presentation = create_presentation(
+            &self.request,
+            credentials,
+            self_attested,
+            master_secret,
+            schemas,
+            cred_defs,
+        )?;
+        self.state = PresentationState::PresentationSent;
+        Ok(self.presentation.get_or_insert(presentation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::services::test_utils::{CredDefFixture, HolderFixture};
+    use crate::services::{types::*, verifier};
+
+    #[test]
+    fn presentation_exchange_works() {
+        let fixture = CredDefFixture::with_attributes(vec!["name", "age"]).unwrap();
+        let holder = HolderFixture::new().unwrap();
+        let credential = fixture
+            .issue(&holder, &[("name", "Alex"), ("age", "28")])
+            .unwrap();
+
+        let pres_req: PresentationRequest = serde_json::from_value(serde_json::json!({
+            "nonce": verifier::generate_nonce().unwrap(),
+            "name": "proof",
+            "version": "1.0",
+            "requested_attributes": {"attr1": {"name": "name"}},
+            "requested_predicates": {"pred1": {"name": "age", "p_type": ">=", "p_value": 18}}
+        }))
+        .unwrap();
+        let mut verifier_ex = VerifierExchange::request(pres_req);
+        let (schemas, cred_defs) = (fixture.schemas(), fixture.cred_defs());
+        let err = verifier_ex
+            .verify(&schemas, &cred_defs, None, None)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidState);
+
+        let pres_req = serde_json::to_string(verifier_ex.presentation_request()).unwrap();
+        let mut prover_ex =
+            ProverExchange::receive_request(serde_json::from_str(&pres_req).unwrap());
+        let mut present = PresentCredentials::default();
+        {
+            let mut cred = present.add_credential(&credential, None, None);
+            cred.add_requested_attribute("attr1", true);
+            cred.add_requested_predicate("pred1");
+        }
+        let presentation = prover_ex
+            .present(present, None, &holder.master_secret, &schemas, &cred_defs)
+            .unwrap();
+        let presentation = serde_json::to_string(presentation).unwrap();
+        assert_eq!(prover_ex.state(), PresentationState::PresentationSent);
+
+        verifier_ex
+            .receive_presentation(serde_json::from_str(&presentation).unwrap())
+            .unwrap();
+        assert!(verifier_ex
+            .verify(&schemas, &cred_defs, None, None)
+            .unwrap());
+        assert_eq!(verifier_ex.state(), PresentationState::Verified);
+        assert_eq!(verifier_ex.verified(), Some(true));
+        let err = verifier_ex
+            .receive_presentation(serde_json::from_str(&presentation).unwrap())
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidState);
+    }
+}
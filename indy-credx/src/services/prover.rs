@@ -1,24 +1,39 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use super::types::*;
+use crate::config::get_config;
 use crate::error::Result;
+use crate::services::encoders::encode_attribute;
+use crate::services::escrow::{
+    commit_escrows, complete_escrows, escrow_nonce, EscrowValue, PendingEscrow,
+};
 use crate::services::helpers::*;
+use crate::services::pseudonym::{commit_pseudonym, pseudonym_nonce};
 use crate::ursa::cl::{
-    issuer::Issuer as CryptoIssuer, prover::Prover as CryptoProver,
-    verifier::Verifier as CryptoVerifier, CredentialPublicKey,
-    RevocationRegistry as CryptoRevocationRegistry, SubProofRequest, Witness,
+    issuer::Issuer as CryptoIssuer,
+    prover::{ProofBuilder as CryptoProofBuilder, Prover as CryptoProver},
+    verifier::Verifier as CryptoVerifier,
+    CredentialPublicKey, Proof as CryptoProof, RevocationRegistry as CryptoRevocationRegistry,
+    SubProofRequest, Witness,
 };
 use indy_data_types::anoncreds::{
     credential::AttributeValues,
-    pres_request::{PresentationRequestPayload, RequestedAttributeInfo, RequestedPredicateInfo},
+    pres_request::{
+        NonRevocedInterval, PredicateInfo, PredicateTypes, PresentationRequestPayload,
+        RangeDisclosureInfo, RequestedAttributeInfo, RequestedPredicateInfo,
+    },
     presentation::{
-        AttributeValue, Identifier, RequestedProof, RevealedAttributeGroupInfo,
-        RevealedAttributeInfo, SubProofReferent,
+        AttributeValue, DomainPseudonym, EscrowedAttributeInfo, Identifier,
+        RangeDisclosureReferent, RequestedProof, RevealedAttributeGroupInfo, RevealedAttributeInfo,
+        SubProofReferent,
     },
 };
+use indy_utils::wql::Query;
 use indy_utils::{Qualifiable, Validatable};
 
-use super::tails::TailsReader;
+use super::link_secret::{with_link_secret, LinkSecretProvider};
+use super::tails::{TailsBuffer, TailsReader};
+use crate::ursa::cl::RevocationTailsAccessor;
 
 pub fn create_master_secret() -> Result<MasterSecret> {
     MasterSecret::new().map_err(err_map!(Unexpected))
@@ -31,22 +46,50 @@ pub fn create_credential_request(
     master_secret_id: &str,
     credential_offer: &CredentialOffer,
 ) -> Result<(CredentialRequest, CredentialRequestMetadata)> {
+    create_committed_credential_request(
+        prover_did,
+        cred_def,
+        master_secret,
+        master_secret_id,
+        credential_offer,
+        CredentialValues::default(),
+    )
+}
+
+/// Create a credential request committing to the values of some attributes.
+/// The issuer signs the committed values blindly, without learning them, and
+/// provides the values of the remaining attributes.
+pub fn create_committed_credential_request(
+    prover_did: &DidValue,
+    cred_def: &CredentialDefinition,
+    master_secret: &MasterSecret,
+    master_secret_id: &str,
+    credential_offer: &CredentialOffer,
+    committed_values: CredentialValues,
+) -> Result<(CredentialRequest, CredentialRequestMetadata)> {
+    time_operation!("create_credential_request");
     trace!(
-        "create_credential_request >>> cred_def: {:?}, master_secret: {:?}, credential_offer: {:?}",
+        "create_credential_request >>> cred_def: {:?}, master_secret: {:?}, credential_offer: {:?}, committed_values: {:?}",
         cred_def,
         secret!(&master_secret),
-        credential_offer
+        credential_offer,
+        secret!(&committed_values)
     );
 
-    let cred_def = match cred_def {
-        CredentialDefinition::CredentialDefinitionV1(cd) => cd,
-    };
+    let CredentialDefinition::CredentialDefinitionV1(cred_def) = cred_def;
     let credential_pub_key = CredentialPublicKey::build_from_parts(
         &cred_def.value.primary,
         cred_def.value.revocation.as_ref(),
     )?;
     let mut credential_values_builder = CryptoIssuer::new_credential_values_builder()?;
     credential_values_builder.add_value_hidden("master_secret", &master_secret.value.value()?)?;
+    for (attr, values) in committed_values.0.iter() {
+        let attr = attr_common_view(attr);
+        if attr == "master_secret" {
+            return Err(err_msg!(Input, "The master secret cannot be committed"));
+        }
+        credential_values_builder.add_dec_hidden(&attr, &values.encoded)?;
+    }
     let cred_values = credential_values_builder.finalize()?;
 
     let nonce = new_nonce()?;
@@ -72,6 +115,12 @@ pub fn create_credential_request(
         master_secret_blinding_data,
         nonce: nonce_copy,
         master_secret_name: master_secret_id.to_string(),
+        master_secret_aliases: vec![],
+        committed_values: if committed_values.0.is_empty() {
+            None
+        } else {
+            Some(committed_values)
+        },
     };
 
     trace!(
@@ -83,6 +132,38 @@ pub fn create_credential_request(
     Ok((credential_request, credential_request_metadata))
 }
 
+/// Create a credential request using the link secret of a provider
+pub fn create_credential_request_with_provider(
+    prover_did: &DidValue,
+    cred_def: &CredentialDefinition,
+    provider: &dyn LinkSecretProvider,
+    master_secret_id: &str,
+    credential_offer: &CredentialOffer,
+) -> Result<(CredentialRequest, CredentialRequestMetadata)> {
+    with_link_secret(provider, |master_secret| {
+        create_credential_request(
+            prover_did,
+            cred_def,
+            master_secret,
+            master_secret_id,
+            credential_offer,
+        )
+    })
+}
+
+/// Return a copy of the credential request metadata with the master secret
+/// renamed, so that credentials issued for the previous name remain usable
+/// after a wallet renames its link secret
+pub fn rename_master_secret_in_metadata(
+    cred_request_metadata: &CredentialRequestMetadata,
+    master_secret_id: &str,
+) -> Result<CredentialRequestMetadata> {
+    let mut metadata: CredentialRequestMetadata =
+        serde_json::from_value(serde_json::to_value(cred_request_metadata)?)?;
+    metadata.rename_master_secret(master_secret_id);
+    Ok(metadata)
+}
+
 pub fn process_credential(
     credential: &mut Credential,
     cred_request_metadata: &CredentialRequestMetadata,
@@ -90,18 +171,31 @@ pub fn process_credential(
     cred_def: &CredentialDefinition,
     rev_reg_def: Option<&RevocationRegistryDefinition>,
 ) -> Result<()> {
+    time_operation!("process_credential");
     trace!("process_credential >>> credential: {:?}, cred_request_metadata: {:?}, master_secret: {:?}, cred_def: {:?}, rev_reg_def: {:?}",
             credential, cred_request_metadata, secret!(&master_secret), cred_def, rev_reg_def);
 
-    let cred_def = match cred_def {
-        CredentialDefinition::CredentialDefinitionV1(cd) => cd,
-    };
+    let CredentialDefinition::CredentialDefinitionV1(cred_def) = cred_def;
     let credential_pub_key = CredentialPublicKey::build_from_parts(
         &cred_def.value.primary,
         cred_def.value.revocation.as_ref(),
     )?;
-    let credential_values =
-        build_credential_values(&credential.values.0, Some(&master_secret.value))?;
+    // the committed values are signed blindly and added to the credential
+    let mut values = credential.values.clone();
+    if let Some(committed) = cred_request_metadata.committed_values.as_ref() {
+        for (attr, value) in committed.0.iter() {
+            let name = attr_common_view(attr);
+            if values.0.keys().any(|key| attr_common_view(key) == name) {
+                return Err(err_msg!(
+                    InvalidState,
+                    "Credential contains a value for the committed attribute: {}",
+                    attr
+                ));
+            }
+            values.0.insert(attr.clone(), value.clone());
+        }
+    }
+    let credential_values = build_credential_values(&values.0, Some(&master_secret.value))?;
     let rev_pub_key = match rev_reg_def {
         Some(RevocationRegistryDefinition::RevocationRegistryDefinitionV1(def)) => {
             Some(&def.value.public_keys.accum_key)
@@ -120,12 +214,351 @@ pub fn process_credential(
         credential.rev_reg.as_ref(),
         credential.witness.as_ref(),
     )?;
+    credential.values = values;
 
     trace!("process_credential <<< ");
 
     Ok(())
 }
 
+/// Process a received credential using the link secret of a provider
+pub fn process_credential_with_provider(
+    credential: &mut Credential,
+    cred_request_metadata: &CredentialRequestMetadata,
+    provider: &dyn LinkSecretProvider,
+    cred_def: &CredentialDefinition,
+    rev_reg_def: Option<&RevocationRegistryDefinition>,
+) -> Result<()> {
+    with_link_secret(provider, |master_secret| {
+        process_credential(
+            credential,
+            cred_request_metadata,
+            master_secret,
+            cred_def,
+            rev_reg_def,
+        )
+    })
+}
+
+/// Process a received credential and check that it was issued for the given
+/// offer and credential definition, returning the processed credential
+/// along with its raw attribute values.
+pub fn process_and_extract_credential(
+    credential: &Credential,
+    cred_offer: &CredentialOffer,
+    cred_request_metadata: &CredentialRequestMetadata,
+    master_secret: &MasterSecret,
+    cred_def: &CredentialDefinition,
+    rev_reg_def: Option<&RevocationRegistryDefinition>,
+) -> Result<(Credential, HashMap<String, String>)> {
+    let CredentialDefinition::CredentialDefinitionV1(cred_def_v1) = cred_def;
+    if credential.cred_def_id.to_unqualified() != cred_offer.cred_def_id.to_unqualified()
+        || cred_def_v1.id.to_unqualified() != cred_offer.cred_def_id.to_unqualified()
+    {
+        return Err(err_msg!(
+            "Credential definition ID does not match the credential offer: {}",
+            credential.cred_def_id
+        ));
+    }
+    if credential.schema_id.to_unqualified() != cred_offer.schema_id.to_unqualified() {
+        return Err(err_msg!(
+            "Schema ID does not match the credential offer: {}",
+            credential.schema_id
+        ));
+    }
+    if let Some(RevocationRegistryDefinition::RevocationRegistryDefinitionV1(def)) = rev_reg_def {
+        if credential
+            .rev_reg_id
+            .as_ref()
+            .map(Qualifiable::to_unqualified)
+            != Some(def.id.to_unqualified())
+        {
+            return Err(err_msg!(
+                "Revocation registry ID does not match the credential: {}",
+                def.id
+            ));
+        }
+    }
+
+    let mut credential = credential
+        .try_clone()
+        .map_err(err_map!(Unexpected, "Error copying credential"))?;
+    process_credential(
+        &mut credential,
+        cred_request_metadata,
+        master_secret,
+        cred_def,
+        rev_reg_def,
+    )?;
+    let attributes = credential
+        .values
+        .0
+        .iter()
+        .map(|(name, value)| (name.clone(), value.raw.clone()))
+        .collect();
+    Ok((credential, attributes))
+}
+
+/// Recompute the encoding of each raw attribute value of a credential, with
+/// the encoders registered for its schema or the standard encoding,
+/// returning the sorted names of the attributes whose encoded values differ.
+/// Presentations revealing these attributes may be rejected by verifiers
+/// which check the encodings.
+pub fn check_credential_encoding(credential: &Credential) -> Result<Vec<String>> {
+    let mut mismatched = Vec::new();
+    for (name, value) in credential.values.0.iter() {
+        let expected = encode_attribute(Some(&credential.schema_id), name, &value.raw)?;
+        if value.encoded_value().ok().as_ref() != Some(&expected) {
+            mismatched.push(name.clone());
+        }
+    }
+    mismatched.sort();
+    Ok(mismatched)
+}
+
+/// Look up the raw and encoded values of a credential attribute, where the
+/// name is normalized as in presentation requests
+pub fn credential_attribute<'c>(
+    credential: &'c Credential,
+    name: &str,
+) -> Option<&'c AttributeValues> {
+    let name = attr_common_view(name);
+    credential
+        .values
+        .0
+        .iter()
+        .find(|(key, _)| attr_common_view(key) == name)
+        .map(|(_, values)| values)
+}
+
+/// Build the searchable tags of a credential, matching those stored by the
+/// libindy wallet. Besides the identifiers of the credential, each attribute
+/// is tagged with `attr::<name>::marker` and `attr::<name>::value`, where the
+/// name is normalized as in presentation requests. For credentials with fully
+/// qualified identifiers, the unqualified forms are added with a `_short`
+/// suffix.
+pub fn credential_tags(credential: &Credential) -> Result<BTreeMap<String, String>> {
+    let (_, schema_issuer_did, schema_name, schema_version) =
+        credential.schema_id.parts().ok_or_else(|| {
+            err_msg!(
+                "Invalid Schema ID `{}`: wrong number of parts",
+                credential.schema_id.0
+            )
+        })?;
+    let issuer_did = credential.cred_def_id.issuer_did().ok_or_else(|| {
+        err_msg!(
+            "Invalid Credential Definition ID `{}`: wrong number of parts",
+            credential.cred_def_id.0
+        )
+    })?;
+
+    let mut tags = BTreeMap::new();
+    tags.insert("schema_id".to_string(), credential.schema_id.0.clone());
+    tags.insert("schema_issuer_did".to_string(), schema_issuer_did.0.clone());
+    tags.insert("schema_name".to_string(), schema_name);
+    tags.insert("schema_version".to_string(), schema_version);
+    tags.insert("issuer_did".to_string(), issuer_did.0.clone());
+    tags.insert("cred_def_id".to_string(), credential.cred_def_id.0.clone());
+    tags.insert(
+        "rev_reg_id".to_string(),
+        credential
+            .rev_reg_id
+            .as_ref()
+            .map(|id| id.0.clone())
+            .unwrap_or_else(|| "None".to_string()),
+    );
+
+    if credential.cred_def_id.is_fully_qualified() {
+        tags.insert(
+            "schema_id_short".to_string(),
+            credential.schema_id.to_unqualified().0,
+        );
+        tags.insert(
+            "schema_issuer_did_short".to_string(),
+            schema_issuer_did.to_unqualified().0,
+        );
+        tags.insert(
+            "issuer_did_short".to_string(),
+            issuer_did.to_unqualified().0,
+        );
+        tags.insert(
+            "cred_def_id_short".to_string(),
+            credential.cred_def_id.to_unqualified().0,
+        );
+        tags.insert(
+            "rev_reg_id_short".to_string(),
+            credential
+                .rev_reg_id
+                .as_ref()
+                .map(|id| id.to_unqualified().0)
+                .unwrap_or_else(|| "None".to_string()),
+        );
+    }
+
+    for (name, value) in credential.values.0.iter() {
+        let name = attr_common_view(name);
+        tags.insert(format!("attr::{}::marker", name), "1".to_string());
+        tags.insert(format!("attr::{}::value", name), value.raw.clone());
+    }
+    Ok(tags)
+}
+
+/// Check whether a credential may be used for an attribute or predicate
+/// referent of a presentation request. The credential must contain the
+/// requested attributes, satisfy any restrictions on its tags, and for a
+/// predicate have a value satisfying the predicate. Revocation status is not
+/// checked.
+pub fn credential_matches_referent(
+    pres_req: &PresentationRequest,
+    referent: &str,
+    credential: &Credential,
+) -> Result<bool> {
+    let pres_req_val = pres_req.value();
+    let (names, restrictions) = if let Some(info) = pres_req_val.requested_attributes.get(referent)
+    {
+        let names = match (info.name.as_ref(), info.names.as_ref()) {
+            (Some(name), _) => vec![name.as_str()],
+            (None, Some(names)) => names.iter().map(String::as_str).collect(),
+            (None, None) => vec![],
+        };
+        (names, info.restrictions.as_ref())
+    } else if let Some(info) = pres_req_val.requested_predicates.get(referent) {
+        match get_credential_values_for_attribute(&credential.values.0, &info.name) {
+            Some(values) if check_predicate_value(info, &values.encoded).is_ok() => (),
+            _ => return Ok(false),
+        }
+        (vec![info.name.as_str()], info.restrictions.as_ref())
+    } else if let Some(info) = pres_req_val.requested_range_disclosures.get(referent) {
+        if select_disclosed_range(info, &credential.values.0).is_err() {
+            return Ok(false);
+        }
+        (vec![info.name.as_str()], info.restrictions.as_ref())
+    } else {
+        return Err(err_msg!(
+            "Referent not found in presentation request: {}",
+            referent
+        ));
+    };
+
+    if names
+        .iter()
+        .any(|name| get_credential_values_for_attribute(&credential.values.0, name).is_none())
+    {
+        return Ok(false);
+    }
+    let restrictions = match restrictions {
+        Some(restrictions) => restrictions,
+        None => return Ok(true),
+    };
+
+    let mut tags = credential_tags(credential)?;
+    if pres_req.version() == PresentationRequestVersion::V1 {
+        // version 1.0 requests use unqualified identifiers
+        for tag in Credential::QUALIFIABLE_TAGS.iter() {
+            if let Some(short) = tags.remove(&format!("{}_short", tag)) {
+                tags.insert(tag.to_string(), short);
+            }
+        }
+    }
+    Ok(tags_match(restrictions, &tags))
+}
+
+/// Whether a referent of a presentation request is for attributes or a
+/// predicate
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferentKind {
+    Attribute,
+    Predicate,
+}
+
+/// A referent of a presentation request, as listed for the holder
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RequestedReferent {
+    pub referent: String,
+    pub kind: ReferentKind,
+    /// The requested attribute names, or the name of the attribute in the
+    /// predicate
+    pub names: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p_type: Option<PredicateTypes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p_value: Option<i32>,
+    pub restrictions: Option<Query>,
+    /// The interval of the referent, or of the whole request when the
+    /// referent does not specify one
+    pub non_revoked: Option<NonRevocedInterval>,
+}
+
+/// List the attribute and predicate referents of a presentation request,
+/// attributes first and each ordered by referent
+pub fn requested_referents(pres_req: &PresentationRequest) -> Vec<RequestedReferent> {
+    let pres_req = pres_req.value();
+    let attributes = pres_req
+        .requested_attributes
+        .iter()
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .map(|(referent, info)| RequestedReferent {
+            referent: referent.clone(),
+            kind: ReferentKind::Attribute,
+            names: match (info.name.as_ref(), info.names.as_ref()) {
+                (Some(name), _) => vec![name.clone()],
+                (None, Some(names)) => names.clone(),
+                (None, None) => vec![],
+            },
+            p_type: None,
+            p_value: None,
+            restrictions: info.restrictions.clone(),
+            non_revoked: get_non_revoc_interval(&pres_req.non_revoked, &info.non_revoked),
+        });
+    let predicates = pres_req
+        .requested_predicates
+        .iter()
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .map(|(referent, info)| RequestedReferent {
+            referent: referent.clone(),
+            kind: ReferentKind::Predicate,
+            names: vec![info.name.clone()],
+            p_type: Some(info.p_type.clone()),
+            p_value: Some(info.p_value),
+            restrictions: info.restrictions.clone(),
+            non_revoked: get_non_revoc_interval(&pres_req.non_revoked, &info.non_revoked),
+        });
+    attributes.chain(predicates).collect()
+}
+
+/// Evaluate a query against the tags of a credential, in the manner of a
+/// wallet search over unencrypted tags
+pub(crate) fn tags_match(query: &Query, tags: &BTreeMap<String, String>) -> bool {
+    let tag = |name: &str| -> Option<&String> {
+        match name
+            .strip_prefix("attr::")
+            .and_then(|rest| rest.rsplit_once("::"))
+        {
+            Some((attr, suffix)) => {
+                tags.get(&format!("attr::{}::{}", attr_common_view(attr), suffix))
+            }
+            None => tags.get(name),
+        }
+    };
+    match query {
+        Query::And(queries) => queries.iter().all(|q| tags_match(q, tags)),
+        Query::Or(queries) => queries.iter().any(|q| tags_match(q, tags)),
+        Query::Not(query) => !tags_match(query, tags),
+        Query::Eq(name, value) => tag(name) == Some(value),
+        Query::Neq(name, value) => tag(name).is_some_and(|found| found != value),
+        Query::Gt(name, value) => tag(name).is_some_and(|found| found > value),
+        Query::Gte(name, value) => tag(name).is_some_and(|found| found >= value),
+        Query::Lt(name, value) => tag(name).is_some_and(|found| found < value),
+        Query::Lte(name, value) => tag(name).is_some_and(|found| found <= value),
+        Query::Like(name, pattern) => tag(name).is_some_and(|found| like_matches(pattern, found)),
+        Query::In(name, values) => tag(name).is_some_and(|found| values.contains(found)),
+        Query::Exist(names) => names.iter().all(|name| tag(name).is_some()),
+    }
+}
+
 pub fn create_presentation(
     pres_req: &PresentationRequest,
     credentials: PresentCredentials,
@@ -134,6 +567,106 @@ pub fn create_presentation(
     schemas: &HashMap<SchemaId, &Schema>,
     cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
 ) -> Result<Presentation> {
+    build_presentation(
+        pres_req,
+        credentials,
+        self_attested,
+        master_secret,
+        schemas,
+        cred_defs,
+        false,
+        false,
+    )
+}
+
+/// Create a presentation using the link secret of a provider
+pub fn create_presentation_with_provider(
+    pres_req: &PresentationRequest,
+    credentials: PresentCredentials,
+    self_attested: Option<HashMap<String, String>>,
+    provider: &dyn LinkSecretProvider,
+    schemas: &HashMap<SchemaId, &Schema>,
+    cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+) -> Result<Presentation> {
+    with_link_secret(provider, |master_secret| {
+        create_presentation(
+            pres_req,
+            credentials,
+            self_attested,
+            master_secret,
+            schemas,
+            cred_defs,
+        )
+    })
+}
+
+/// Create a presentation which also reveals the values of the attributes
+/// used in predicates, for flows which require an audit trail. The values
+/// are listed under `revealed_predicates` in the requested proof and are
+/// proven in the same way as revealed attributes, with the verifier checking
+/// that each value satisfies its predicate.
+pub fn create_auditable_presentation(
+    pres_req: &PresentationRequest,
+    credentials: PresentCredentials,
+    self_attested: Option<HashMap<String, String>>,
+    master_secret: &MasterSecret,
+    schemas: &HashMap<SchemaId, &Schema>,
+    cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+) -> Result<Presentation> {
+    build_presentation(
+        pres_req,
+        credentials,
+        self_attested,
+        master_secret,
+        schemas,
+        cred_defs,
+        true,
+        false,
+    )
+}
+
+/// Create a presentation in which the blinding factors of every hidden
+/// attribute are drawn in a fixed order, so that the presentation depends
+/// only on the inputs and the sequence of random values.
+///
+/// Every attribute is added to the proof as a common attribute, so each
+/// blinding factor is shared between all credentials with an attribute of
+/// the same name. Unlike a presentation from `create_presentation`, this
+/// reveals to the verifier whether hidden attributes of the same name are
+/// equal across credentials, so it is only suitable for tests.
+#[cfg(feature = "test_seeded_keys")]
+pub(crate) fn create_presentation_with_fixed_blinding(
+    pres_req: &PresentationRequest,
+    credentials: PresentCredentials,
+    self_attested: Option<HashMap<String, String>>,
+    master_secret: &MasterSecret,
+    schemas: &HashMap<SchemaId, &Schema>,
+    cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+) -> Result<Presentation> {
+    build_presentation(
+        pres_req,
+        credentials,
+        self_attested,
+        master_secret,
+        schemas,
+        cred_defs,
+        false,
+        true,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn build_presentation(
+    pres_req: &PresentationRequest,
+    credentials: PresentCredentials,
+    self_attested: Option<HashMap<String, String>>,
+    master_secret: &MasterSecret,
+    schemas: &HashMap<SchemaId, &Schema>,
+    cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+    reveal_predicates: bool,
+    fixed_blinding: bool,
+) -> Result<Presentation> {
+    time_operation!("create_presentation");
     trace!("create_proof >>> credentials: {:?}, pres_req: {:?}, credentials: {:?}, self_attested: {:?}, master_secret: {:?}, schemas: {:?}, cred_defs: {:?}",
             credentials, pres_req, credentials, &self_attested, secret!(&master_secret), schemas, cred_defs);
 
@@ -153,10 +686,33 @@ pub fn create_presentation(
     let pres_req_val = pres_req.value();
     let mut proof_builder = CryptoProver::new_proof_builder()?;
     proof_builder.add_common_attribute("master_secret")?;
+    if fixed_blinding {
+        // the CL library draws the blinding factors of the remaining hidden
+        // attributes in an arbitrary order, so they are all drawn here, in
+        // order, as common attributes
+        let attr_names: BTreeSet<String> = credentials
+            .0
+            .iter()
+            .flat_map(|present| present.cred.values.0.keys())
+            .map(|name| attr_common_view(name))
+            .collect();
+        for name in attr_names {
+            proof_builder.add_common_attribute(&name)?;
+        }
+    }
+    // the attributes proven equal share their blinding factors between the
+    // sub-proofs of the presentation
+    let equal_names = equal_attribute_names(pres_req_val);
+    for name in equal_names.iter() {
+        proof_builder.add_common_attribute(name)?;
+    }
+    let mut equal_held = Vec::new();
+    let mut escrow_values = Vec::with_capacity(pres_req_val.escrowed_attributes.len());
 
-    let mut requested_proof = RequestedProof::default();
-
-    requested_proof.self_attested_attrs = self_attested.unwrap_or_default();
+    let mut requested_proof = RequestedProof {
+        self_attested_attrs: self_attested.unwrap_or_default(),
+        ..Default::default()
+    };
 
     let mut sub_proof_index = 0;
     let non_credential_schema = build_non_credential_schema()?;
@@ -171,9 +727,7 @@ pub fn create_presentation(
         let schema = *schemas
             .get(&credential.schema_id)
             .ok_or_else(|| err_msg!("Schema not provided for ID: {}", credential.schema_id))?;
-        let schema = match schema {
-            Schema::SchemaV1(schema) => schema,
-        };
+        let Schema::SchemaV1(schema) = schema;
 
         let cred_def = *cred_defs.get(&credential.cred_def_id).ok_or_else(|| {
             err_msg!(
@@ -181,9 +735,7 @@ pub fn create_presentation(
                 credential.cred_def_id
             )
         })?;
-        let cred_def = match cred_def {
-            CredentialDefinition::CredentialDefinitionV1(cd) => cd,
-        };
+        let CredentialDefinition::CredentialDefinitionV1(cred_def) = cred_def;
 
         let credential_pub_key = CredentialPublicKey::build_from_parts(
             &cred_def.value.primary,
@@ -193,12 +745,83 @@ pub fn create_presentation(
         let credential_schema = build_credential_schema(&schema.attr_names.0)?;
         let credential_values =
             build_credential_values(&credential.values.0, Some(&master_secret.value))?;
+        equal_held.extend(
+            credential
+                .values
+                .0
+                .keys()
+                .map(|name| attr_common_view(name))
+                .filter(|name| equal_names.contains(name))
+                .map(|name| (sub_proof_index, name)),
+        );
+        let mut requested_predicates = present.requested_predicates;
+        let req_disclosures = take_range_disclosures(&mut requested_predicates, pres_req_val);
         let (req_attrs, req_predicates) = prepare_credential_for_proving(
             present.requested_attributes,
-            present.requested_predicates,
+            requested_predicates,
             pres_req_val,
         )?;
-        let sub_proof_request = build_sub_proof_request(&req_attrs, &req_predicates)?;
+        let (timestamp, rev_state) = if present.rev_states.is_empty() {
+            (present.timestamp, present.rev_state)
+        } else {
+            match select_latest_state(
+                &present.rev_states,
+                pres_req_val,
+                &req_attrs,
+                &req_predicates,
+                &req_disclosures,
+            )? {
+                Some(idx) => (
+                    Some(present.rev_states[idx].timestamp),
+                    Some(present.rev_states[idx]),
+                ),
+                None => (None, None),
+            }
+        };
+        if !reveal_predicates {
+            for predicate in req_predicates.iter() {
+                let info = &predicate.predicate_info;
+                let values = get_credential_values_for_attribute(&credential.values.0, &info.name)
+                    .ok_or_else(|| {
+                        err_msg!(
+                            "Credential attribute not found for predicate: {}",
+                            info.name
+                        )
+                    })?;
+                check_predicate_value(info, &values.encoded)?;
+            }
+        }
+        let mut disclosure_predicates = Vec::with_capacity(req_disclosures.len() * 2);
+        let mut disclosed_ranges = Vec::with_capacity(req_disclosures.len());
+        for disclosure in req_disclosures {
+            let (lower, upper) = select_disclosed_range(&disclosure.info, &credential.values.0)?;
+            let width = i64::from(upper) - i64::from(lower) + 1;
+            if width < i64::from(RangeDisclosureInfo::MIN_RUN_WIDTH)
+                && !present
+                    .narrow_range_disclosures
+                    .contains(&disclosure.referent)
+            {
+                return Err(err_msg!(
+                    "The run of the set containing the value of attribute \"{}\" has {} members, \
+                    which would narrow the value disclosed to the verifier. It may only be presented \
+                    as a narrow range disclosure.",
+                    disclosure.info.name,
+                    width
+                ));
+            }
+            disclosure_predicates.extend(range_disclosure_predicates(
+                &disclosure.info.name,
+                lower,
+                upper,
+            ));
+            disclosed_ranges.push((disclosure.referent, lower, upper));
+        }
+        let sub_proof_request = build_sub_proof_request(
+            &req_attrs,
+            &req_predicates,
+            &disclosure_predicates,
+            reveal_predicates,
+        )?;
 
         proof_builder.add_sub_proof_request(
             &sub_proof_request,
@@ -207,8 +830,8 @@ pub fn create_presentation(
             &credential.signature,
             &credential_values,
             &credential_pub_key,
-            present.rev_state.as_ref().map(|r_info| &r_info.rev_reg),
-            present.rev_state.as_ref().map(|r_info| &r_info.witness),
+            rev_state.map(|r_info| &r_info.rev_reg),
+            rev_state.map(|r_info| &r_info.witness),
         )?;
 
         let identifier = match pres_req {
@@ -216,31 +839,70 @@ pub fn create_presentation(
                 schema_id: credential.schema_id.to_unqualified(),
                 cred_def_id: credential.cred_def_id.to_unqualified(),
                 rev_reg_id: credential.rev_reg_id.as_ref().map(|id| id.to_unqualified()),
-                timestamp: present.timestamp,
+                timestamp,
             },
             PresentationRequest::PresentationRequestV2(_) => Identifier {
                 schema_id: credential.schema_id.clone(),
                 cred_def_id: credential.cred_def_id.clone(),
                 rev_reg_id: credential.rev_reg_id.clone(),
-                timestamp: present.timestamp,
+                timestamp,
             },
         };
 
         identifiers.push(identifier);
 
+        for attr in req_attrs.iter().filter(|attr| {
+            !attr.revealed
+                && pres_req_val
+                    .escrowed_attributes
+                    .contains_key(&attr.attr_referent)
+        }) {
+            let name = attr.attr_info.name.as_deref().unwrap_or_default();
+            let values = get_credential_values_for_attribute(&credential.values.0, name)
+                .ok_or_else(|| err_msg!("Credential attribute not found for escrow: {}", name))?;
+            escrow_values.push(EscrowValue {
+                referent: attr.attr_referent.clone(),
+                sub_proof_index,
+                name: attr_common_view(name),
+                encoded: values.encoded,
+            });
+        }
+
         update_requested_proof(
             req_attrs,
             req_predicates,
             pres_req_val,
             credential,
             sub_proof_index,
+            reveal_predicates,
             &mut requested_proof,
         )?;
+        for (referent, lower, upper) in disclosed_ranges {
+            requested_proof.range_disclosures.insert(
+                referent,
+                RangeDisclosureReferent {
+                    sub_proof_index,
+                    lower,
+                    upper,
+                },
+            );
+        }
 
         sub_proof_index += 1;
     }
 
-    let proof = proof_builder.finalize(pres_req_val.nonce.as_native())?;
+    check_same_credential(pres_req_val, &requested_proof)?;
+    check_equal_attributes_presented(pres_req_val, &requested_proof, &equal_held)?;
+
+    if pres_req_val.pseudonym_domain.is_some() && identifiers.is_empty() {
+        return Err(err_msg!(
+            "A pseudonym may only be presented along with a credential"
+        ));
+    }
+    let (proof, escrowed_attrs, pseudonym) =
+        finalize_proof(&proof_builder, pres_req_val, &escrow_values, master_secret)?;
+    requested_proof.escrowed_attrs = escrowed_attrs;
+    requested_proof.pseudonym = pseudonym;
 
     let full_proof = Presentation {
         proof,
@@ -253,6 +915,44 @@ pub fn create_presentation(
     Ok(full_proof)
 }
 
+/// Finalize the CL proof of a presentation with the requested escrows and
+/// pseudonym. Their proofs reuse the blinding factors of the CL proof, which
+/// are drawn internally by the CL library, so they are recovered from a
+/// probe proof for a random nonce. The probe is discarded, as together with
+/// the presentation it would disclose the hidden values. The commitments are
+/// bound to the challenge of the final proof through its nonce.
+fn finalize_proof(
+    proof_builder: &CryptoProofBuilder,
+    pres_req: &PresentationRequestPayload,
+    escrow_values: &[EscrowValue],
+    master_secret: &MasterSecret,
+) -> Result<(
+    CryptoProof,
+    HashMap<String, EscrowedAttributeInfo>,
+    Option<DomainPseudonym>,
+)> {
+    if pres_req.escrowed_attributes.is_empty() && pres_req.pseudonym_domain.is_none() {
+        let proof = proof_builder.finalize(pres_req.nonce.as_native())?;
+        return Ok((proof, HashMap::new(), None));
+    }
+
+    let probe = serde_json::to_value(proof_builder.finalize(new_nonce()?.as_native())?)?;
+    let escrows = commit_escrows(pres_req, &probe, escrow_values)?;
+    let pseudonym = pres_req
+        .pseudonym_domain
+        .as_ref()
+        .map(|domain| commit_pseudonym(domain, master_secret, &probe))
+        .transpose()?;
+    let nonce = pseudonym_nonce(
+        escrow_nonce(pres_req, escrows.iter().map(PendingEscrow::entry))?,
+        pseudonym.as_ref(),
+    )?;
+
+    let proof = proof_builder.finalize(nonce.as_native())?;
+    let escrowed = complete_escrows(escrows, &serde_json::to_value(&proof)?)?;
+    Ok((proof, escrowed, pseudonym))
+}
+
 pub fn create_or_update_revocation_state(
     tails_reader: TailsReader,
     revoc_reg_def: &RevocationRegistryDefinition,
@@ -272,12 +972,75 @@ rev_reg_delta: {:?}, rev_reg_idx: {}, timestamp: {:?}, rev_state: {:?}",
         rev_state
     );
 
-    let revoc_reg_def = match revoc_reg_def {
-        RevocationRegistryDefinition::RevocationRegistryDefinitionV1(v1) => v1,
-    };
-    let rev_reg_delta = match rev_reg_delta {
-        RevocationRegistryDelta::RevocationRegistryDeltaV1(v1) => v1,
+    compute_revocation_state(
+        &tails_reader,
+        revoc_reg_def,
+        rev_reg_delta,
+        rev_reg_idx,
+        timestamp,
+        rev_state,
+    )
+}
+
+/// Create or update the revocation states for several credentials issued
+/// from the same revocation registry. The tails are read once into memory and
+/// shared between all of the witness calculations, rather than being
+/// re-read for each credential.
+///
+/// Each entry consists of the credential revocation index and the previous
+/// revocation state for the credential, if any. The resulting states are
+/// returned in the same order as the entries.
+pub fn create_or_update_revocation_states(
+    tails_reader: TailsReader,
+    revoc_reg_def: &RevocationRegistryDefinition,
+    rev_reg_delta: &RevocationRegistryDelta,
+    entries: &[(u32, Option<&CredentialRevocationState>)],
+    timestamp: u64,
+) -> Result<Vec<CredentialRevocationState>> {
+    trace!(
+        "create_or_update_revocation_states >>> , tails_reader: {:?}, revoc_reg_def: {:?}, \
+rev_reg_delta: {:?}, entries: {:?}, timestamp: {:?}",
+        tails_reader,
+        revoc_reg_def,
+        rev_reg_delta,
+        entries,
+        timestamp,
+    );
+
+    if entries.is_empty() {
+        return Ok(vec![]);
+    }
+    let max_cred_num = match revoc_reg_def {
+        RevocationRegistryDefinition::RevocationRegistryDefinitionV1(def) => def.value.max_cred_num,
     };
+    let tails = TailsBuffer::load(&tails_reader, max_cred_num)?;
+
+    entries
+        .iter()
+        .map(|(rev_reg_idx, rev_state)| {
+            compute_revocation_state(
+                &tails,
+                revoc_reg_def,
+                rev_reg_delta,
+                *rev_reg_idx,
+                timestamp,
+                *rev_state,
+            )
+        })
+        .collect()
+}
+
+fn compute_revocation_state<RTA: RevocationTailsAccessor>(
+    tails_accessor: &RTA,
+    revoc_reg_def: &RevocationRegistryDefinition,
+    rev_reg_delta: &RevocationRegistryDelta,
+    rev_reg_idx: u32,
+    timestamp: u64,
+    rev_state: Option<&CredentialRevocationState>,
+) -> Result<CredentialRevocationState> {
+    time_operation!("create_revocation_state");
+    let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(revoc_reg_def) = revoc_reg_def;
+    let RevocationRegistryDelta::RevocationRegistryDeltaV1(rev_reg_delta) = rev_reg_delta;
 
     let witness = match rev_state {
         None => Witness::new(
@@ -285,7 +1048,7 @@ rev_reg_delta: {:?}, rev_reg_idx: {}, timestamp: {:?}, rev_state: {:?}",
             revoc_reg_def.value.max_cred_num,
             revoc_reg_def.value.issuance_type.to_bool(),
             &rev_reg_delta.value,
-            &tails_reader,
+            tails_accessor,
         )?,
         Some(source_rev_state) => {
             let mut witness = source_rev_state.witness.clone();
@@ -293,7 +1056,7 @@ rev_reg_delta: {:?}, rev_reg_idx: {}, timestamp: {:?}, rev_state: {:?}",
                 rev_reg_idx,
                 revoc_reg_def.value.max_cred_num,
                 &rev_reg_delta.value,
-                &tails_reader,
+                tails_accessor,
             )?;
             witness
         }
@@ -306,6 +1069,129 @@ rev_reg_delta: {:?}, rev_reg_idx: {}, timestamp: {:?}, rev_state: {:?}",
     })
 }
 
+/// Select the latest of a set of cached revocation states with which to
+/// present a credential for the given referents, returning its index. No
+/// state is selected when the referents do not require a non-revocation
+/// proof, and an open-ended interval extends to the current time of the
+/// configured clock.
+pub fn select_revocation_state(
+    pres_req: &PresentationRequest,
+    attr_referents: HashSet<String>,
+    predicate_referents: HashSet<String>,
+    rev_states: &[&CredentialRevocationState],
+) -> Result<Option<usize>> {
+    let pres_req_val = pres_req.value();
+    let mut predicate_referents = predicate_referents;
+    let req_disclosures = take_range_disclosures(&mut predicate_referents, pres_req_val);
+    let (req_attrs, req_predicates) = prepare_credential_for_proving(
+        attr_referents.into_iter().map(|r| (r, true)).collect(),
+        predicate_referents,
+        pres_req_val,
+    )?;
+    select_latest_state(
+        rev_states,
+        pres_req_val,
+        &req_attrs,
+        &req_predicates,
+        &req_disclosures,
+    )
+}
+
+fn select_latest_state(
+    rev_states: &[&CredentialRevocationState],
+    pres_req: &PresentationRequestPayload,
+    req_attrs: &[RequestedAttributeInfo],
+    req_predicates: &[RequestedPredicateInfo],
+    req_disclosures: &[RequestedRangeDisclosure],
+) -> Result<Option<usize>> {
+    let intervals = req_attrs
+        .iter()
+        .map(|attr| &attr.attr_info.non_revoked)
+        .chain(
+            req_predicates
+                .iter()
+                .map(|pred| &pred.predicate_info.non_revoked),
+        )
+        .chain(req_disclosures.iter().map(|m| &m.info.non_revoked))
+        .filter_map(|local| get_non_revoc_interval(&pres_req.non_revoked, local));
+    let interval = match intersect_non_revoc_intervals(intervals) {
+        Some(interval) => interval,
+        None => return Ok(None),
+    };
+    select_latest_timestamp(
+        rev_states.iter().map(|state| state.timestamp),
+        &interval,
+        get_config().now(),
+    )
+    .map(Some)
+    .ok_or_else(|| {
+        err_msg!(
+            "No revocation state found within the requested interval: {:?}",
+            interval
+        )
+    })
+}
+
+/// A range disclosure requested of a credential
+#[derive(Debug)]
+struct RequestedRangeDisclosure {
+    referent: String,
+    info: RangeDisclosureInfo,
+}
+
+/// Separate the referents of range disclosures, which are presented in the
+/// same way as predicates, from the referents of requested predicates
+fn take_range_disclosures(
+    requested_predicates: &mut HashSet<String>,
+    pres_req: &PresentationRequestPayload,
+) -> Vec<RequestedRangeDisclosure> {
+    let mut disclosures = Vec::new();
+    requested_predicates.retain(|referent| {
+        match pres_req.requested_range_disclosures.get(referent) {
+            Some(info) if !pres_req.requested_predicates.contains_key(referent) => {
+                disclosures.push(RequestedRangeDisclosure {
+                    referent: referent.clone(),
+                    info: info.clone(),
+                });
+                false
+            }
+            _ => true,
+        }
+    });
+    disclosures
+}
+
+/// Find the run of a requested set containing the value of the credential
+/// attribute, checking that it may be proven by range predicates
+fn select_disclosed_range(
+    info: &RangeDisclosureInfo,
+    credential_attrs: &HashMap<String, AttributeValues>,
+) -> Result<(i32, i32)> {
+    let values =
+        get_credential_values_for_attribute(credential_attrs, &info.name).ok_or_else(|| {
+            err_msg!(
+                "Credential attribute not found for range disclosure: {}",
+                info.name
+            )
+        })?;
+    let value = values.encoded.parse::<i32>().map_err(|_| {
+        err_msg!(
+            "Value of attribute \"{}\" is not a 32-bit integer, so no range containing it can be disclosed",
+            info.name
+        )
+    })?;
+    let (lower, upper) = info.range_containing(value as i64).ok_or_else(|| {
+        err_msg!(
+            "Value of attribute \"{}\" is not a member of the requested set",
+            info.name
+        )
+    })?;
+    for predicate in range_disclosure_predicates(&info.name, lower, upper).iter() {
+        check_predicate_value(predicate, &values.encoded)?;
+    }
+    Ok((lower, upper))
+}
+
 fn prepare_credential_for_proving(
     requested_attributes: HashSet<(String, bool)>,
     requested_predicates: HashSet<String>,
@@ -377,7 +1263,7 @@ fn get_credential_values_for_attribute(
 
     let res = credential_attrs
         .iter()
-        .find(|&(ref key, _)| attr_common_view(key) == attr_common_view(&requested_attr))
+        .find(|&(key, _)| attr_common_view(key) == attr_common_view(requested_attr))
         .map(|(_, values)| values.clone());
 
     trace!(
@@ -394,6 +1280,7 @@ fn update_requested_proof(
     proof_req: &PresentationRequestPayload,
     credential: &Credential,
     sub_proof_index: u32,
+    reveal_predicates: bool,
     requested_proof: &mut RequestedProof,
 ) -> Result<()> {
     trace!("_update_requested_proof >>> req_attrs_for_credential: {:?}, req_predicates_for_credential: {:?}, proof_req: {:?}, credential: {:?}, \
@@ -406,7 +1293,7 @@ fn update_requested_proof(
 
             if let Some(name) = &attribute.name {
                 let attribute_values =
-                    get_credential_values_for_attribute(&credential.values.0, &name).ok_or_else(
+                    get_credential_values_for_attribute(&credential.values.0, name).ok_or_else(
                         || err_msg!("Credential value not found for attribute {:?}", name),
                     )?;
 
@@ -422,7 +1309,7 @@ fn update_requested_proof(
                 let mut value_map: HashMap<String, AttributeValue> = HashMap::new();
                 for name in names {
                     let attr_value =
-                        get_credential_values_for_attribute(&credential.values.0, &name)
+                        get_credential_values_for_attribute(&credential.values.0, name)
                             .ok_or_else(|| {
                                 err_msg!("Credential value not found for attribute {:?}", name)
                             })?;
@@ -451,6 +1338,19 @@ fn update_requested_proof(
     }
 
     for predicate_info in req_predicates_for_credential {
+        if reveal_predicates {
+            let name = &predicate_info.predicate_info.name;
+            let attribute_values = get_credential_values_for_attribute(&credential.values.0, name)
+                .ok_or_else(|| err_msg!("Credential value not found for attribute {:?}", name))?;
+            requested_proof.revealed_predicates.insert(
+                predicate_info.predicate_referent.clone(),
+                RevealedAttributeInfo {
+                    sub_proof_index,
+                    raw: attribute_values.raw,
+                    encoded: attribute_values.encoded,
+                },
+            );
+        }
         requested_proof.predicates.insert(
             predicate_info.predicate_referent,
             SubProofReferent { sub_proof_index },
@@ -465,6 +1365,8 @@ fn update_requested_proof(
 fn build_sub_proof_request(
     req_attrs_for_credential: &[RequestedAttributeInfo],
     req_predicates_for_credential: &[RequestedPredicateInfo],
+    disclosure_predicates: &[PredicateInfo],
+    reveal_predicates: bool,
 ) -> Result<SubProofRequest> {
     trace!("_build_sub_proof_request <<< req_attrs_for_credential: {:?}, req_predicates_for_credential: {:?}",
            req_attrs_for_credential, req_predicates_for_credential);
@@ -484,6 +1386,13 @@ fn build_sub_proof_request(
     }
 
     for predicate in req_predicates_for_credential {
+        if reveal_predicates {
+            // an attribute cannot be both revealed and used in a predicate within
+            // one sub-proof, so the revealed value stands in for the predicate
+            sub_proof_request_builder
+                .add_revealed_attr(&attr_common_view(&predicate.predicate_info.name))?;
+            continue;
+        }
         let p_type = format!("{}", predicate.predicate_info.p_type);
 
         sub_proof_request_builder.add_predicate(
@@ -493,6 +1402,16 @@ fn build_sub_proof_request(
         )?;
     }
 
+    // range disclosures are always proven by their range predicates, as the
+    // values behind them are never revealed
+    for predicate in disclosure_predicates {
+        sub_proof_request_builder.add_predicate(
+            &attr_common_view(&predicate.name),
+            &predicate.p_type.to_string(),
+            predicate.p_value,
+        )?;
+    }
+
     let sub_proof_request = sub_proof_request_builder.finalize()?;
 
     trace!(
@@ -571,7 +1490,12 @@ mod tests {
                 requested_predicates: hashmap!(
                     PREDICATE_REFERENT.to_string() => _predicate_info()
                 ),
+                requested_range_disclosures: HashMap::new(),
                 non_revoked: None,
+                same_credential: vec![],
+                equal_attributes: vec![],
+                escrowed_attributes: HashMap::new(),
+                pseudonym_domain: None,
             }
         }
 
@@ -700,4 +1624,347 @@ mod tests {
             assert_eq!(_attr_values(), res);
         }
     }
+
+    mod credential_tags {
+        use super::*;
+        use crate::services::test_utils::{CredDefFixture, HolderFixture, SchemaBuilder};
+
+        #[test]
+        fn credential_tags_works() {
+            let did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+            let schema = SchemaBuilder::new()
+                .issuer_did(did.clone())
+                .name("gvt")
+                .attributes(vec!["First Name", "age"])
+                .build()
+                .unwrap();
+            let fixture = CredDefFixture::new(did, schema, false).unwrap();
+            let credential = fixture
+                .issue(
+                    &HolderFixture::new().unwrap(),
+                    &[("First Name", "Alex"), ("age", "28")],
+                )
+                .unwrap();
+
+            let tags = credential_tags(&credential).unwrap();
+            assert_eq!(tags["schema_id"], "NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0");
+            assert_eq!(tags["schema_issuer_did"], "NcYxiDXkpYi6ov5FcYDi1e");
+            assert_eq!(tags["schema_name"], "gvt");
+            assert_eq!(tags["schema_version"], "1.0");
+            assert_eq!(tags["issuer_did"], "NcYxiDXkpYi6ov5FcYDi1e");
+            assert_eq!(tags["cred_def_id"], fixture.cred_def.id().0);
+            assert_eq!(tags["rev_reg_id"], "None");
+            assert_eq!(tags["attr::firstname::marker"], "1");
+            assert_eq!(tags["attr::firstname::value"], "Alex");
+            assert_eq!(tags["attr::age::value"], "28");
+            assert!(!tags.contains_key("cred_def_id_short"));
+            assert_eq!(tags.len(), 11);
+        }
+    }
+
+    mod equal_attributes {
+        use super::*;
+        use crate::services::test_utils::{did, CredDefFixture, HolderFixture, SchemaBuilder};
+        use crate::services::verifier;
+
+        fn issue(
+            name: &str,
+            values: &[(&str, &str)],
+            holder: &HolderFixture,
+        ) -> (CredDefFixture, Credential) {
+            let schema = SchemaBuilder::new()
+                .name(name)
+                .attributes(values.iter().map(|(attr, _)| *attr))
+                .build()
+                .unwrap();
+            let fixture = CredDefFixture::new(did(), schema, false).unwrap();
+            let credential = fixture.issue(holder, values).unwrap();
+            (fixture, credential)
+        }
+
+        #[test]
+        fn equal_attributes_are_proven() {
+            let holder = HolderFixture::new().unwrap();
+            let tax = issue("tax", &[("ssn", "123-45-6789"), ("name", "Alex")], &holder);
+            let job = issue(
+                "job",
+                &[("ssn", "123-45-6789"), ("employer", "Acme")],
+                &holder,
+            );
+            let other = issue(
+                "job",
+                &[("ssn", "987-65-4321"), ("employer", "Acme")],
+                &holder,
+            );
+
+            let pres_req: PresentationRequest = serde_json::from_value(serde_json::json!({
+                "nonce": "123432421212",
+                "name": "proof_req_1",
+                "version": "0.1",
+                "requested_attributes": {
+                    "tax_ssn": {"name": "ssn", "restrictions": {"schema_name": "tax"}},
+                    "job_ssn": {"name": "ssn", "restrictions": {"schema_name": "job"}},
+                },
+                "equal_attributes": [["tax_ssn", "job_ssn"]]
+            }))
+            .unwrap();
+
+            let prove = |(job, job_credential): &(CredDefFixture, Credential), revealed: bool| {
+                let (tax, tax_credential) = &tax;
+                let mut schemas = tax.schemas();
+                schemas.extend(job.schemas());
+                let mut cred_defs = tax.cred_defs();
+                cred_defs.extend(job.cred_defs());
+                let mut present = PresentCredentials::new();
+                present
+                    .add_credential(tax_credential, None, None)
+                    .add_requested_attribute("tax_ssn", revealed);
+                present
+                    .add_credential(job_credential, None, None)
+                    .add_requested_attribute("job_ssn", false);
+                create_presentation(
+                    &pres_req,
+                    present,
+                    None,
+                    &holder.master_secret,
+                    &schemas,
+                    &cred_defs,
+                )
+                .map(|presentation| {
+                    verifier::verify_presentation(
+                        &presentation,
+                        &pres_req,
+                        &schemas,
+                        &cred_defs,
+                        None,
+                        None,
+                    )
+                })
+            };
+
+            assert!(prove(&job, false).unwrap().unwrap());
+            assert_kind!(ProofRejected, prove(&other, false).unwrap());
+            assert_kind!(Input, prove(&job, true));
+        }
+    }
+
+    mod range_disclosure {
+        use super::*;
+        use crate::services::test_utils::{did, CredDefFixture, HolderFixture, SchemaBuilder};
+        use crate::services::verifier;
+
+        #[test]
+        fn range_disclosure_is_proven() {
+            let issuer_did = did();
+            let schema = SchemaBuilder::new()
+                .issuer_did(issuer_did.clone())
+                .name("gvt")
+                .attributes(vec!["name", "age"])
+                .build()
+                .unwrap();
+            let fixture = CredDefFixture::new(issuer_did, schema, false).unwrap();
+            let holder = HolderFixture::new().unwrap();
+            let issue = |age: &str| {
+                fixture
+                    .issue(&holder, &[("name", "Alex"), ("age", age)])
+                    .unwrap()
+            };
+
+            let pres_req: PresentationRequest = serde_json::from_value(serde_json::json!({
+                "nonce": "123432421212",
+                "name": "proof_req_1",
+                "version": "0.1",
+                "requested_attributes": {"name": {"name": "name"}},
+                "requested_range_disclosures": {
+                    "age_set": {
+                        "name": "age",
+                        "values": [18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 65, 66],
+                        "restrictions": {"schema_name": "gvt"}
+                    }
+                }
+            }))
+            .unwrap();
+            let (schemas, cred_defs) = (fixture.schemas(), fixture.cred_defs());
+            let present = |credential: &Credential, narrow: bool| {
+                let mut present = PresentCredentials::new();
+                {
+                    let mut add = present.add_credential(credential, None, None);
+                    add.add_requested_attribute("name", true);
+                    if narrow {
+                        add.add_narrow_range_disclosure("age_set");
+                    } else {
+                        add.add_requested_predicate("age_set");
+                    }
+                }
+                create_presentation(
+                    &pres_req,
+                    present,
+                    None,
+                    &holder.master_secret,
+                    &schemas,
+                    &cred_defs,
+                )
+            };
+            let verify = |presentation: &Presentation| {
+                verifier::verify_presentation(
+                    presentation,
+                    &pres_req,
+                    &schemas,
+                    &cred_defs,
+                    None,
+                    None,
+                )
+            };
+
+            let credential = issue("21");
+            assert!(credential_matches_referent(&pres_req, "age_set", &credential).unwrap());
+            let mut presentation = present(&credential, false).unwrap();
+            let disclosure = &presentation.requested_proof.range_disclosures["age_set"];
+            assert_eq!((disclosure.lower, disclosure.upper), (18, 30));
+            assert!(verify(&presentation).unwrap());
+
+            // the proof does not hold for another run of the set, and a range
+            // which is not a run of the set is rejected
+            presentation
+                .requested_proof
+                .range_disclosures
+                .get_mut("age_set")
+                .unwrap()
+                .lower = 65;
+            presentation
+                .requested_proof
+                .range_disclosures
+                .get_mut("age_set")
+                .unwrap()
+                .upper = 66;
+            assert!(!verify(&presentation).unwrap_or(false));
+            presentation
+                .requested_proof
+                .range_disclosures
+                .get_mut("age_set")
+                .unwrap()
+                .upper = 70;
+            assert_kind!(ProofRejected, verify(&presentation));
+
+            // a narrow run would disclose the value, and needs the consent
+            // of the holder
+            let credential = issue("65");
+            assert_kind!(Input, present(&credential, false));
+            let presentation = present(&credential, true).unwrap();
+            let disclosure = &presentation.requested_proof.range_disclosures["age_set"];
+            assert_eq!((disclosure.lower, disclosure.upper), (65, 66));
+            assert!(verify(&presentation).unwrap());
+
+            let credential = issue("40");
+            assert!(!credential_matches_referent(&pres_req, "age_set", &credential).unwrap());
+            assert_kind!(Input, present(&credential, false));
+        }
+    }
+
+    mod credential_matches_referent {
+        use super::*;
+        use crate::services::test_utils::{did, CredDefFixture, HolderFixture, SchemaBuilder};
+
+        #[test]
+        fn credential_matches_referent_works() {
+            let issuer_did = did();
+            let schema = SchemaBuilder::new()
+                .issuer_did(issuer_did.clone())
+                .name("gvt")
+                .attributes(vec!["name", "age"])
+                .build()
+                .unwrap();
+            let credential = CredDefFixture::new(issuer_did, schema, false)
+                .unwrap()
+                .issue(
+                    &HolderFixture::new().unwrap(),
+                    &[("name", "Alex"), ("age", "28")],
+                )
+                .unwrap();
+
+            let pres_req: PresentationRequest = serde_json::from_value(serde_json::json!({
+                "nonce": "123432421212",
+                "name": "proof_req_1",
+                "version": "0.1",
+                "requested_attributes": {
+                    "name": {"name": "name", "restrictions": {"schema_name": "gvt"}},
+                    "other": {"name": "name", "restrictions": {"schema_name": "other"}},
+                    "first": {"name": "First Name"},
+                    "valued": {"name": "Name", "restrictions": {"attr::name::value": "Alex"}},
+                },
+                "requested_predicates": {
+                    "adult": {"name": "age", "p_type": ">=", "p_value": 18},
+                    "senior": {"name": "age", "p_type": ">=", "p_value": 65},
+                },
+            }))
+            .unwrap();
+            let matches = |referent| credential_matches_referent(&pres_req, referent, &credential);
+            assert!(matches("name").unwrap());
+            assert!(!matches("other").unwrap());
+            assert!(!matches("first").unwrap());
+            assert!(matches("valued").unwrap());
+            assert!(matches("adult").unwrap());
+            assert!(!matches("senior").unwrap());
+            assert!(matches("unknown").is_err());
+        }
+    }
+
+    mod requested_referents {
+        use super::*;
+
+        #[test]
+        fn requested_referents_works() {
+            let pres_req: PresentationRequest = serde_json::from_value(serde_json::json!({
+                "nonce": "123432421212",
+                "name": "proof_req_1",
+                "version": "0.1",
+                "requested_attributes": {
+                    "name": {"name": "name", "restrictions": {"schema_name": "gvt"}},
+                    "address": {
+                        "names": ["street", "city"],
+                        "non_revoked": {"from": 10, "to": 20}
+                    },
+                },
+                "requested_predicates": {
+                    "adult": {"name": "age", "p_type": ">=", "p_value": 18},
+                },
+                "non_revoked": {"to": 30},
+            }))
+            .unwrap();
+            let referents = requested_referents(&pres_req);
+            assert_eq!(
+                referents
+                    .iter()
+                    .map(|r| (r.referent.as_str(), r.kind))
+                    .collect::<Vec<_>>(),
+                vec![
+                    ("address", ReferentKind::Attribute),
+                    ("name", ReferentKind::Attribute),
+                    ("adult", ReferentKind::Predicate),
+                ]
+            );
+            assert_eq!(referents[0].names, vec!["street", "city"]);
+            assert_eq!(
+                referents[0].non_revoked,
+                Some(NonRevocedInterval {
+                    from: Some(10),
+                    to: Some(20)
+                })
+            );
+            assert_eq!(
+                referents[1].restrictions,
+                Some(Query::Eq("schema_name".to_string(), "gvt".to_string()))
+            );
+            assert_eq!(
+                referents[1].non_revoked,
+                Some(NonRevocedInterval {
+                    from: None,
+                    to: Some(30)
+                })
+            );
+            assert_eq!(referents[2].names, vec!["age"]);
+            assert_eq!(referents[2].p_type, Some(PredicateTypes::GE));
+            assert_eq!(referents[2].p_value, Some(18));
+        }
+    }
 }
@@ -0,0 +1,292 @@
+//! Domain pseudonyms derived from the link secret.
+//!
+//! A presentation request may name a pseudonym domain, such as an identifier
+//! of the verifier, for which the holder presents the pseudonym `g^ms mod p`.
+//! Here `ms` is the link secret and `g` is derived from the domain by hashing
+//! into the quadratic residues of the 2048-bit MODP group of RFC 3526. A
+//! holder always presents the same pseudonym within a domain, so a verifier
+//! may recognize a returning holder, while the pseudonyms of a holder in
+//! different domains cannot be linked without the link secret.
+//!
+//! The holder proves that the pseudonym is derived from the link secret of
+//! the presented credentials. As for escrowed attributes, the proof reuses
+//! the blinded value of the link secret and the challenge of the CL proof,
+//! with its commitment bound to the challenge through the nonce of the CL
+//! proof. These values are read from the serialized CL proof, whose layout
+//! is pinned by the exact version dependency on `ursa`.
+
+use indy_data_types::anoncreds::{
+    nonce::Nonce, pres_request::PresentationRequestPayload, presentation::DomainPseudonym,
+    presentation::RequestedProof,
+};
+use indy_utils::hash::{Digest, SHA256};
+use once_cell::sync::Lazy;
+
+use super::escrow::blinded_value;
+use super::types::MasterSecret;
+use crate::error::Result;
+use crate::ursa::bn::{BigNumber, BigNumberContext};
+use crate::ursa::cl::Proof;
+
+/// The domain separation tag of the generator of a pseudonym domain
+const GENERATOR_TAG: &[u8] = b"indy-credx/pseudonym-generator/v1";
+
+/// The domain separation tag of the nonce of a CL proof with a pseudonym
+const PSEUDONYM_NONCE_TAG: &[u8] = b"indy-credx/pseudonym-nonce/v1";
+
+/// The name of the link secret in the CL proof
+const MASTER_SECRET_ATTRIBUTE: &str = "master_secret";
+
+/// The 2048-bit MODP group prime of RFC 3526, a safe prime
+const GROUP_PRIME: &str = "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD1\
+    29024E088A67CC74020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437\
+    4FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7EDEE386BFB5A899FA5\
+    AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF0598DA48361C55D39A69163FA8FD24CF5F\
+    83655D23DCA3AD961C62F356208552BB9ED529077096966D670C354E4ABC9804F1746C08CA18217C\
+    32905E462E36CE3BE39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF695581718\
+    3995497CEA956AE515D2261898FA051015728E5A8AACAA68FFFFFFFFFFFFFFFF";
+
+static GROUP: Lazy<(BigNumber, BigNumber)> = Lazy::new(|| {
+    let p = BigNumber::from_hex(GROUP_PRIME).expect("Invalid group prime");
+    let q = p
+        .decrement()
+        .and_then(|p| p.rshift1())
+        .expect("Invalid group prime");
+    (p, q)
+});
+
+/// Compute the pseudonym of a link secret in a domain
+pub fn create_pseudonym(master_secret: &MasterSecret, domain: &str) -> Result<String> {
+    let mut ctx = BigNumber::new_context()?;
+    let (p, _) = &*GROUP;
+    let nym = domain_generator(domain, &mut ctx)?.mod_exp(
+        &master_secret.value.value()?,
+        p,
+        Some(&mut ctx),
+    )?;
+    Ok(nym.to_dec()?)
+}
+
+/// Compute the pseudonym of the holder in the requested domain, committing
+/// to the blinding factor of the link secret as recovered from a probe of
+/// the CL proof
+pub(crate) fn commit_pseudonym(
+    domain: &str,
+    master_secret: &MasterSecret,
+    probe: &serde_json::Value,
+) -> Result<DomainPseudonym> {
+    let mut ctx = BigNumber::new_context()?;
+    let (p, _) = &*GROUP;
+    let ms = master_secret.value.value()?;
+    let (c, ms_hat) = blinded_value(probe, 0, MASTER_SECRET_ATTRIBUTE)?;
+    let ms_tilde = ms_hat.sub(&c.mul(&ms, Some(&mut ctx))?)?;
+    let generator = domain_generator(domain, &mut ctx)?;
+    Ok(DomainPseudonym {
+        domain: domain.to_string(),
+        nym: generator.mod_exp(&ms, p, Some(&mut ctx))?.to_dec()?,
+        commitment: generator.mod_exp(&ms_tilde, p, Some(&mut ctx))?.to_dec()?,
+    })
+}
+
+/// Derive the nonce of a CL proof from a nonce and the presented pseudonym,
+/// if any
+pub(crate) fn pseudonym_nonce(nonce: Nonce, pseudonym: Option<&DomainPseudonym>) -> Result<Nonce> {
+    let pseudonym = match pseudonym {
+        Some(pseudonym) => pseudonym,
+        None => return Ok(nonce),
+    };
+    let mut hasher = SHA256::DigestType::new();
+    let mut update = |value: &[u8]| {
+        hasher.update((value.len() as u32).to_be_bytes());
+        hasher.update(value);
+    };
+    update(PSEUDONYM_NONCE_TAG);
+    update(nonce.as_bytes());
+    update(pseudonym.domain.as_bytes());
+    update(pseudonym.nym.as_bytes());
+    update(pseudonym.commitment.as_bytes());
+    let digest = BigNumber::from_bytes(&hasher.finalize())?;
+    Nonce::from_native(digest).map_err(err_map!(Unexpected))
+}
+
+/// Check that a presentation includes a pseudonym in the requested domain,
+/// derived from the link secret of the CL proof
+pub(crate) fn check_pseudonym(
+    pres_req: &PresentationRequestPayload,
+    requested_proof: &RequestedProof,
+    proof: &Proof,
+) -> Result<()> {
+    let pseudonym = match (&pres_req.pseudonym_domain, &requested_proof.pseudonym) {
+        (None, None) => return Ok(()),
+        (Some(domain), Some(pseudonym)) if *domain == pseudonym.domain => pseudonym,
+        (requested, received) => {
+            return Err(err_msg!(
+                ProofRejected,
+                "Requested pseudonym domain {:?} does not correspond to received {:?}",
+                requested,
+                received.as_ref().map(|pseudonym| &pseudonym.domain)
+            ))
+        }
+    };
+
+    let mut ctx = BigNumber::new_context()?;
+    let (p, _) = &*GROUP;
+    let proof = serde_json::to_value(proof)?;
+    let (c, ms_hat) = blinded_value(&proof, 0, MASTER_SECRET_ATTRIBUTE)?;
+    let nym = parse_element(&pseudonym.nym, "pseudonym", &mut ctx)?;
+    let commitment = parse_element(&pseudonym.commitment, "pseudonym commitment", &mut ctx)?;
+    let expected =
+        domain_generator(&pseudonym.domain, &mut ctx)?.mod_exp(&ms_hat, p, Some(&mut ctx))?;
+    let received = commitment.mod_mul(&nym.mod_exp(&c, p, Some(&mut ctx))?, p, Some(&mut ctx))?;
+    if expected != received {
+        return Err(err_msg!(
+            ProofRejected,
+            "Invalid pseudonym for domain: {}",
+            pseudonym.domain
+        ));
+    }
+    Ok(())
+}
+
+/// Hash a domain to a generator of the quadratic residues of the group
+fn domain_generator(domain: &str, ctx: &mut BigNumberContext) -> Result<BigNumber> {
+    let (p, _) = &*GROUP;
+    // hash to 2560 bits, so that the value reduced modulo the prime is
+    // close to uniform
+    let mut bytes = Vec::with_capacity(320);
+    for counter in 0u32..10 {
+        let mut hasher = SHA256::DigestType::new();
+        hasher.update(GENERATOR_TAG);
+        hasher.update(counter.to_be_bytes());
+        hasher.update(domain.as_bytes());
+        bytes.extend_from_slice(&hasher.finalize());
+    }
+    let hash = BigNumber::from_bytes(&bytes)?.modulus(p, Some(ctx))?;
+    let generator = hash.mod_mul(&hash, p, Some(ctx))?;
+    if generator <= BigNumber::from_u32(1)? {
+        return Err(err_msg!(
+            Unexpected,
+            "Invalid generator for domain: {}",
+            domain
+        ));
+    }
+    Ok(generator)
+}
+
+/// Parse an element of the quadratic residues of the group
+fn parse_element(value: &str, label: &str, ctx: &mut BigNumberContext) -> Result<BigNumber> {
+    let (p, q) = &*GROUP;
+    if !value.is_empty() && value.bytes().all(|c| c.is_ascii_digit()) {
+        let element = BigNumber::from_dec(value)?;
+        if element > BigNumber::new()?
+            && element < *p
+            && element.mod_exp(q, p, Some(ctx))? == BigNumber::from_u32(1)?
+        {
+            return Ok(element);
+        }
+    }
+    Err(err_msg!(ProofRejected, "Invalid {}: {}", label, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::helpers::new_nonce;
+    use crate::services::test_utils::{CredDefFixture, HolderFixture};
+    use crate::services::types::*;
+    use crate::services::{prover, verifier};
+
+    #[test]
+    fn pseudonym_is_stable_per_domain() {
+        let holder = HolderFixture::new().unwrap();
+        let master_secret = &holder.master_secret;
+        let fixture = CredDefFixture::with_attributes(vec!["name"]).unwrap();
+        let credential = fixture.issue(&holder, &[("name", "Alex")]).unwrap();
+
+        let (schemas, cred_defs) = (fixture.schemas(), fixture.cred_defs());
+        let request = |domain: &str| -> PresentationRequest {
+            serde_json::from_value(serde_json::json!({
+                "nonce": new_nonce().unwrap(),
+                "name": "proof_req_1",
+                "version": "0.1",
+                "requested_attributes": {"name": {"name": "name"}},
+                "pseudonym_domain": domain
+            }))
+            .unwrap()
+        };
+        let present = |pres_req: &PresentationRequest| {
+            let mut present = PresentCredentials::new();
+            present
+                .add_credential(&credential, None, None)
+                .add_requested_attribute("name", false);
+            prover::create_presentation(
+                pres_req,
+                present,
+                None,
+                master_secret,
+                &schemas,
+                &cred_defs,
+            )
+            .unwrap()
+        };
+        let verify = |presentation: &Presentation, pres_req: &PresentationRequest| {
+            verifier::verify_presentation(presentation, pres_req, &schemas, &cred_defs, None, None)
+        };
+
+        let (first_req, second_req) = (request("verifier-a"), request("verifier-a"));
+        let first = present(&first_req);
+        let mut second = present(&second_req);
+        assert!(verify(&first, &first_req).unwrap());
+        assert!(verify(&second, &second_req).unwrap());
+        let nym = &first.requested_proof.pseudonym.as_ref().unwrap().nym;
+        assert_eq!(second.requested_proof.pseudonym.as_ref().unwrap().nym, *nym);
+        assert_eq!(create_pseudonym(master_secret, "verifier-a").unwrap(), *nym);
+        let other_req = request("verifier-b");
+        let other = present(&other_req);
+        assert!(verify(&other, &other_req).unwrap());
+        assert_ne!(other.requested_proof.pseudonym.as_ref().unwrap().nym, *nym);
+        let other_secret = prover::create_master_secret().unwrap();
+        assert_ne!(create_pseudonym(&other_secret, "verifier-a").unwrap(), *nym);
+
+        // the pseudonym of another link secret, or of another domain, is
+        // rejected
+        second.requested_proof.pseudonym.as_mut().unwrap().nym =
+            create_pseudonym(&other_secret, "verifier-a").unwrap();
+        assert_kind!(ProofRejected, verify(&second, &second_req));
+        assert_kind!(ProofRejected, verify(&other, &first_req));
+        second.requested_proof.pseudonym = None;
+        assert_kind!(ProofRejected, verify(&second, &second_req));
+    }
+
+    #[test]
+    fn pseudonym_commitment_from_blinded_link_secret() {
+        let holder = HolderFixture::new().unwrap();
+        let fixture = CredDefFixture::with_attributes(vec!["name"]).unwrap();
+        let credential = fixture.issue(&holder, &[("name", "Alex")]).unwrap();
+        let pres_req: PresentationRequest = serde_json::from_value(serde_json::json!({
+            "nonce": new_nonce().unwrap(),
+            "name": "proof_req_1",
+            "version": "0.1",
+            "requested_attributes": {"name": {"name": "name"}},
+            "pseudonym_domain": "verifier-a"
+        }))
+        .unwrap();
+        let presentation = holder.present(&credential, &fixture, &pres_req).unwrap();
+        let pseudonym = presentation.requested_proof.pseudonym.as_ref().unwrap();
+
+        // in a CL proof of the pinned version of ursa, the blinded value of
+        // the link secret is its blinding factor plus the challenge times
+        // the link secret, which the commitment of the pseudonym is built on
+        let proof = serde_json::to_value(&presentation.proof).unwrap();
+        let (c, ms_hat) = blinded_value(&proof, 0, MASTER_SECRET_ATTRIBUTE).unwrap();
+        let mut ctx = BigNumber::new_context().unwrap();
+        let (p, _) = &*GROUP;
+        let ms = holder.master_secret.value.value().unwrap();
+        let ms_tilde = ms_hat.sub(&c.mul(&ms, Some(&mut ctx)).unwrap()).unwrap();
+        let commitment = domain_generator("verifier-a", &mut ctx)
+            .unwrap()
+            .mod_exp(&ms_tilde, p, Some(&mut ctx))
+            .unwrap();
+        assert_eq!(pseudonym.commitment, commitment.to_dec().unwrap());
+    }
+}
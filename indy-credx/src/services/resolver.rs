@@ -0,0 +1,349 @@
+//! Lookup of the published objects needed to create and verify presentations.
+//!
+//! An [`ObjectResolver`] fetches schemas, credential definitions and
+//! revocation registry data by identifier, typically from a ledger. The
+//! methods return boxed futures so that resolvers may be used as trait
+//! objects and driven by any async runtime.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::error::Result;
+use crate::services::types::{
+    CredentialDefinition, CredentialDefinitionId, RevocationRegistryDefinition,
+    RevocationRegistryDelta, RevocationRegistryId, Schema, SchemaId,
+};
+use crate::services::verifier::merge_delta_history;
+
+/// The future returned by the methods of an [`ObjectResolver`]
+pub type ResolverFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// A source of published objects, looked up by identifier
+pub trait ObjectResolver: Send + Sync {
+    fn get_schema<'a>(&'a self, id: &'a SchemaId) -> ResolverFuture<'a, Schema>;
+
+    fn get_cred_def<'a>(
+        &'a self,
+        id: &'a CredentialDefinitionId,
+    ) -> ResolverFuture<'a, CredentialDefinition>;
+
+    fn get_rev_reg_def<'a>(
+        &'a self,
+        id: &'a RevocationRegistryId,
+    ) -> ResolverFuture<'a, RevocationRegistryDefinition>;
+
+    /// Fetch the changes to a revocation registry after `from`, or since its
+    /// creation, up to and including `to`. The timestamp of the latest state
+    /// included in the delta is returned alongside it.
+    fn get_rev_reg_delta<'a>(
+        &'a self,
+        id: &'a RevocationRegistryId,
+        from: Option<u64>,
+        to: u64,
+    ) -> ResolverFuture<'a, (RevocationRegistryDelta, u64)>;
+}
+
+/// A resolver for objects held in memory, such as in tests or when the
+/// objects have already been fetched by other means
+#[derive(Debug, Default)]
+pub struct InMemoryResolver {
+    schemas: HashMap<SchemaId, Schema>,
+    cred_defs: HashMap<CredentialDefinitionId, CredentialDefinition>,
+    rev_reg_defs: HashMap<RevocationRegistryId, RevocationRegistryDefinition>,
+    rev_reg_deltas: HashMap<RevocationRegistryId, Vec<(u64, RevocationRegistryDelta)>>,
+}
+
+impl InMemoryResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_schema(&mut self, schema: Schema) -> &mut Self {
+        self.schemas.insert(schema.id().clone(), schema);
+        self
+    }
+
+    pub fn add_cred_def(&mut self, cred_def: CredentialDefinition) -> &mut Self {
+        self.cred_defs.insert(cred_def.id().clone(), cred_def);
+        self
+    }
+
+    pub fn add_rev_reg_def(&mut self, rev_reg_def: RevocationRegistryDefinition) -> &mut Self {
+        self.rev_reg_defs
+            .insert(rev_reg_def.id().clone(), rev_reg_def);
+        self
+    }
+
+    /// Record a change to a revocation registry published at `timestamp`
+    pub fn add_rev_reg_delta(
+        &mut self,
+        rev_reg_id: &RevocationRegistryId,
+        timestamp: u64,
+        delta: RevocationRegistryDelta,
+    ) -> &mut Self {
+        let deltas = self.rev_reg_deltas.entry(rev_reg_id.clone()).or_default();
+        deltas.push((timestamp, delta));
+        deltas.sort_by_key(|(delta_ts, _)| *delta_ts);
+        self
+    }
+
+    fn find_rev_reg_delta(
+        &self,
+        id: &RevocationRegistryId,
+        from: Option<u64>,
+        to: u64,
+    ) -> Result<(RevocationRegistryDelta, u64)> {
+        let history = self
+            .rev_reg_deltas
+            .get(id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|(delta_ts, _)| from.is_none_or(|from| *delta_ts > from) && *delta_ts <= to)
+            .collect::<Vec<_>>();
+        let timestamp = history.last().map(|(delta_ts, _)| *delta_ts);
+        match (
+            merge_delta_history(history.into_iter().map(|(_, delta)| delta))?,
+            timestamp,
+        ) {
+            (Some(delta), Some(timestamp)) => Ok((delta, timestamp)),
+            _ => Err(err_msg!(
+                "No revocation registry delta found for {} between {:?} and {}",
+                id,
+                from,
+                to
+            )),
+        }
+    }
+}
+
+impl ObjectResolver for InMemoryResolver {
+    fn get_schema<'a>(&'a self, id: &'a SchemaId) -> ResolverFuture<'a, Schema> {
+        Box::pin(async move {
+            self.schemas
+                .get(id)
+                .cloned()
+                .ok_or_else(|| err_msg!("Schema not found: {}", id))
+        })
+    }
+
+    fn get_cred_def<'a>(
+        &'a self,
+        id: &'a CredentialDefinitionId,
+    ) -> ResolverFuture<'a, CredentialDefinition> {
+        Box::pin(async move {
+            copy_cred_def(
+                self.cred_defs
+                    .get(id)
+                    .ok_or_else(|| err_msg!("Credential definition not found: {}", id))?,
+            )
+        })
+    }
+
+    fn get_rev_reg_def<'a>(
+        &'a self,
+        id: &'a RevocationRegistryId,
+    ) -> ResolverFuture<'a, RevocationRegistryDefinition> {
+        Box::pin(async move {
+            self.rev_reg_defs
+                .get(id)
+                .cloned()
+                .ok_or_else(|| err_msg!("Revocation registry definition not found: {}", id))
+        })
+    }
+
+    fn get_rev_reg_delta<'a>(
+        &'a self,
+        id: &'a RevocationRegistryId,
+        from: Option<u64>,
+        to: u64,
+    ) -> ResolverFuture<'a, (RevocationRegistryDelta, u64)> {
+        Box::pin(async move { self.find_rev_reg_delta(id, from, to) })
+    }
+}
+
+/// A resolver which retains the objects returned by another resolver, so
+/// that each object is only fetched once. Revocation registry deltas are
+/// retained for each requested interval, and should only be requested for
+/// intervals which have already elapsed.
+#[derive(Debug)]
+pub struct CachingResolver<R> {
+    inner: R,
+    schemas: Mutex<HashMap<SchemaId, Schema>>,
+    cred_defs: Mutex<HashMap<CredentialDefinitionId, CredentialDefinition>>,
+    rev_reg_defs: Mutex<HashMap<RevocationRegistryId, RevocationRegistryDefinition>>,
+    rev_reg_deltas: Mutex<RevocationDeltaCache>,
+}
+
+type RevocationDeltaCache =
+    HashMap<(RevocationRegistryId, Option<u64>, u64), (RevocationRegistryDelta, u64)>;
+
+impl<R: ObjectResolver> CachingResolver<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            schemas: Mutex::default(),
+            cred_defs: Mutex::default(),
+            rev_reg_defs: Mutex::default(),
+            rev_reg_deltas: Mutex::default(),
+        }
+    }
+
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Discard all retained objects
+    pub fn clear(&self) {
+        lock_cache(&self.schemas).clear();
+        lock_cache(&self.cred_defs).clear();
+        lock_cache(&self.rev_reg_defs).clear();
+        lock_cache(&self.rev_reg_deltas).clear();
+    }
+}
+
+impl<R: ObjectResolver> ObjectResolver for CachingResolver<R> {
+    fn get_schema<'a>(&'a self, id: &'a SchemaId) -> ResolverFuture<'a, Schema> {
+        Box::pin(async move {
+            if let Some(schema) = lock_cache(&self.schemas).get(id) {
+                return Ok(schema.clone());
+            }
+            let schema = self.inner.get_schema(id).await?;
+            lock_cache(&self.schemas).insert(id.clone(), schema.clone());
+            Ok(schema)
+        })
+    }
+
+    fn get_cred_def<'a>(
+        &'a self,
+        id: &'a CredentialDefinitionId,
+    ) -> ResolverFuture<'a, CredentialDefinition> {
+        Box::pin(async move {
+            if let Some(cred_def) = lock_cache(&self.cred_defs).get(id) {
+                return copy_cred_def(cred_def);
+            }
+            let cred_def = self.inner.get_cred_def(id).await?;
+            lock_cache(&self.cred_defs).insert(id.clone(), copy_cred_def(&cred_def)?);
+            Ok(cred_def)
+        })
+    }
+
+    fn get_rev_reg_def<'a>(
+        &'a self,
+        id: &'a RevocationRegistryId,
+    ) -> ResolverFuture<'a, RevocationRegistryDefinition> {
+        Box::pin(async move {
+            if let Some(rev_reg_def) = lock_cache(&self.rev_reg_defs).get(id) {
+                return Ok(rev_reg_def.clone());
+            }
+            let rev_reg_def = self.inner.get_rev_reg_def(id).await?;
+            lock_cache(&self.rev_reg_defs).insert(id.clone(), rev_reg_def.clone());
+            Ok(rev_reg_def)
+        })
+    }
+
+    fn get_rev_reg_delta<'a>(
+        &'a self,
+        id: &'a RevocationRegistryId,
+        from: Option<u64>,
+        to: u64,
+    ) -> ResolverFuture<'a, (RevocationRegistryDelta, u64)> {
+        Box::pin(async move {
+            let key = (id.clone(), from, to);
+            if let Some(found) = lock_cache(&self.rev_reg_deltas).get(&key) {
+                return Ok(found.clone());
+            }
+            let found = self.inner.get_rev_reg_delta(id, from, to).await?;
+            lock_cache(&self.rev_reg_deltas).insert(key, found.clone());
+            Ok(found)
+        })
+    }
+}
+
+fn lock_cache<T>(cache: &Mutex<T>) -> MutexGuard<'_, T> {
+    // the caches hold no invariants which a panic could break
+    cache.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// the CL public key does not implement Clone
+fn copy_cred_def(cred_def: &CredentialDefinition) -> Result<CredentialDefinition> {
+    Ok(serde_json::from_value(serde_json::to_value(cred_def)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{issuer, tails::TailsFileWriter, test_utils::CredDefFixture, types::*};
+    use async_global_executor::block_on;
+
+    #[test]
+    fn in_memory_resolver_works() {
+        let did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+        let schema =
+            issuer::create_schema(&did, "gvt", "1.0", vec!["name".to_string()].into(), None)
+                .unwrap();
+        let CredDefFixture {
+            schema, cred_def, ..
+        } = CredDefFixture::new(did.clone(), schema, true).unwrap();
+        let (rev_reg_def, _, rev_reg, rev_reg_delta) = issuer::create_revocation_registry(
+            &did,
+            &cred_def,
+            "tag",
+            RegistryType::CL_ACCUM,
+            IssuanceType::ISSUANCE_BY_DEFAULT,
+            5,
+            &mut TailsFileWriter::new(None),
+        )
+        .unwrap();
+        let schema_id = schema.id().clone();
+        let cred_def_id = cred_def.id().clone();
+        let rev_reg_id = rev_reg_def.id().clone();
+
+        let mut resolver = InMemoryResolver::new();
+        resolver
+            .add_schema(schema)
+            .add_cred_def(cred_def)
+            .add_rev_reg_def(rev_reg_def)
+            .add_rev_reg_delta(&rev_reg_id, 1000, rev_reg_delta);
+        let resolver: &dyn ObjectResolver = &resolver;
+
+        block_on(async {
+            assert_eq!(
+                resolver.get_schema(&schema_id).await.unwrap().id(),
+                &schema_id
+            );
+            assert_eq!(
+                resolver.get_cred_def(&cred_def_id).await.unwrap().id(),
+                &cred_def_id
+            );
+            assert_eq!(
+                resolver.get_rev_reg_def(&rev_reg_id).await.unwrap().id(),
+                &rev_reg_id
+            );
+            assert!(resolver
+                .get_schema(&SchemaId::new(&did, "other", "1.0"))
+                .await
+                .is_err());
+
+            let (delta, timestamp) = resolver
+                .get_rev_reg_delta(&rev_reg_id, None, 2000)
+                .await
+                .unwrap();
+            assert_eq!(timestamp, 1000);
+            assert_eq!(
+                serde_json::to_value(delta.to_registry()).unwrap(),
+                serde_json::to_value(&rev_reg).unwrap()
+            );
+            assert!(resolver
+                .get_rev_reg_delta(&rev_reg_id, None, 500)
+                .await
+                .is_err());
+            assert!(resolver
+                .get_rev_reg_delta(&rev_reg_id, Some(1000), 2000)
+                .await
+                .is_err());
+        });
+    }
+}
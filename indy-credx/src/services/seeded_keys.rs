@@ -0,0 +1,177 @@
+//! Generation of credential definition keys from a seed, for test fixtures.
+//!
+//! The CL library draws key material from the system random number generator,
+//! so a credential definition cannot normally be reproduced. Test suites which
+//! share fixtures or compare against golden files may instead derive the keys
+//! from a seed, so that the same seed and schema always produce the same
+//! credential definition, private key and key correctness proof.
+//!
+//! Anyone who knows the seed can recover the private key, and the derivation
+//! has not been reviewed for production use. These keys must only be used in
+//! tests. Revocation keys are not supported.
+
+use sha2::{Digest, Sha256};
+
+use super::issuer::{assemble_credential_definition, make_credential_definition_id};
+use super::keygen::{generate_keys, key_attribute_names, KeyRng};
+use super::types::*;
+use crate::error::Result;
+
+const SEED_DOMAIN: &[u8] = b"indy-credx-seeded-keys:1";
+
+/// A deterministic byte generator, producing SHA-256 blocks of the seed
+/// and a counter
+pub(crate) struct SeededBytes {
+    seed: [u8; 32],
+    counter: u64,
+}
+
+impl SeededBytes {
+    pub(crate) fn new(domain: &[u8], seed: &[u8]) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(domain);
+        hasher.update(seed);
+        Self {
+            seed: hasher.finalize().into(),
+            counter: 0,
+        }
+    }
+
+    pub(crate) fn fill(&mut self, out: &mut [u8]) {
+        for chunk in out.chunks_mut(32) {
+            let mut hasher = Sha256::new();
+            hasher.update(self.seed);
+            hasher.update(self.counter.to_be_bytes());
+            self.counter += 1;
+            let block = hasher.finalize();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+    }
+}
+
+impl KeyRng for SeededBytes {
+    fn fill(&mut self, out: &mut [u8]) {
+        SeededBytes::fill(self, out)
+    }
+}
+
+/// Create a credential definition without revocation support, deriving the
+/// keys from `seed`. The generated keys are insecure and must only be used in
+/// tests.
+pub fn create_credential_definition_from_seed(
+    origin_did: &DidValue,
+    schema: &Schema,
+    tag: &str,
+    signature_type: SignatureType,
+    seed: &[u8],
+) -> Result<(
+    CredentialDefinition,
+    CredentialDefinitionPrivate,
+    CredentialKeyCorrectnessProof,
+)> {
+    trace!(
+        "create_credential_definition_from_seed >>> schema: {:?}",
+        schema
+    );
+    if seed.is_empty() {
+        return Err(err_msg!("Key seed must not be empty"));
+    }
+    warn!("Deriving credential definition keys from a seed: these keys must only be used in tests");
+
+    let Schema::SchemaV1(schema_v1) = schema;
+    let cred_def_id = make_credential_definition_id(
+        origin_did,
+        &schema_v1.id,
+        schema_v1.seq_no,
+        tag,
+        signature_type,
+    )?;
+    let attr_names = key_attribute_names(&schema_v1.attr_names.0)?;
+
+    let (public_key, private_key, correctness_proof) = generate_keys(
+        &mut SeededBytes::new(SEED_DOMAIN, seed),
+        &attr_names,
+        false,
+        &CancellationToken::new(),
+    )?;
+    let (cred_def, cred_def_private, cred_key_proof) = assemble_credential_definition(
+        cred_def_id,
+        schema,
+        tag,
+        signature_type,
+        public_key,
+        private_key,
+        correctness_proof,
+    )?;
+    trace!(
+        "create_credential_definition_from_seed <<< cred_def: {:?}, cred_def: {:?}, key_correctness_proof: {:?}",
+        cred_def,
+        secret!(&cred_def_private),
+        cred_key_proof
+    );
+
+    Ok((cred_def, cred_def_private, cred_key_proof))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{issuer, prover};
+
+    #[test]
+    fn seeded_credential_definition_is_reproducible() {
+        let did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+        let schema = issuer::create_schema(
+            &did,
+            "gvt",
+            "1.0",
+            vec!["name".to_string(), "age".to_string()].into(),
+            None,
+        )
+        .unwrap();
+        let create = || {
+            create_credential_definition_from_seed(
+                &did,
+                &schema,
+                "tag",
+                SignatureType::CL,
+                b"fixture",
+            )
+            .unwrap()
+        };
+        let (cred_def, cred_def_pvt, key_proof) = create();
+        let (cred_def_2, cred_def_pvt_2, key_proof_2) = create();
+        assert_eq!(
+            serde_json::to_value(&cred_def).unwrap(),
+            serde_json::to_value(&cred_def_2).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(&cred_def_pvt).unwrap(),
+            serde_json::to_value(&cred_def_pvt_2).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(&key_proof).unwrap(),
+            serde_json::to_value(&key_proof_2).unwrap()
+        );
+
+        let offer = issuer::create_credential_offer(schema.id(), &cred_def, &key_proof).unwrap();
+        let master_secret = prover::create_master_secret().unwrap();
+        let (request, metadata) =
+            prover::create_credential_request(&did, &cred_def, &master_secret, "default", &offer)
+                .unwrap();
+        let mut values = MakeCredentialValues::default();
+        values.add_raw("name", "Alex").unwrap();
+        values.add_raw("age", "28").unwrap();
+        let (mut credential, _, _) = issuer::create_credential(
+            &cred_def,
+            &cred_def_pvt,
+            &offer,
+            &request,
+            values.into(),
+            None,
+        )
+        .unwrap();
+        prover::process_credential(&mut credential, &metadata, &master_secret, &cred_def, None)
+            .unwrap();
+    }
+}
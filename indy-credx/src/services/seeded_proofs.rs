@@ -0,0 +1,310 @@
+//! Creation of presentations from a seed, for golden-file tests of the
+//! verification path.
+//!
+//! The CL library draws the random values of a proof from OpenSSL. While a
+//! seeded presentation is built, the random values requested by the current
+//! thread are instead derived from the seed, and the blinding factors are
+//! drawn in a fixed order, so that the same seed, credentials and request
+//! always produce the same presentation. Requests from other threads are
+//! passed on to the default OpenSSL generator. The maps within the proof are
+//! serialized in an arbitrary order, so golden files should be compared as
+//! parsed JSON values.
+//!
+//! The seeded generator replaces the process-wide OpenSSL generator only for
+//! the duration of each call, and the default generator is restored before
+//! it returns. Calls are serialized with each other, but not with code that
+//! replaces the OpenSSL generator by other means.
+//!
+//! Anyone who knows the seed can recover the hidden attribute values and the
+//! link secret from the presentation. Seeded presentations must only be used
+//! in tests, and the `test_seeded_keys` feature must not be enabled in
+//! release builds. Non-revocation proofs draw their random values from
+//! another source, so credentials may not be presented with a revocation
+//! state.
+//!
+//! A seeded presentation is structured differently from one created by
+//! `create_presentation`: every attribute is a common attribute of the proof,
+//! so hidden attributes with the same name in different credentials share a
+//! blinding factor, and the verifier can tell whether their values are
+//! equal. The presentation verifies as normal, but must not be used to
+//! test privacy properties.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::os::raw::{c_double, c_int, c_void};
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use super::prover::create_presentation_with_fixed_blinding;
+use super::seeded_keys::SeededBytes;
+use super::types::*;
+use crate::error::Result;
+
+const SEED_DOMAIN: &[u8] = b"indy-credx-seeded-proofs:1";
+
+/// The layout of an OpenSSL `RAND_METHOD`
+#[repr(C)]
+struct RandMethod {
+    seed: Option<unsafe extern "C" fn(buf: *const c_void, num: c_int) -> c_int>,
+    bytes: Option<unsafe extern "C" fn(buf: *mut u8, num: c_int) -> c_int>,
+    cleanup: Option<unsafe extern "C" fn()>,
+    add: Option<unsafe extern "C" fn(buf: *const c_void, num: c_int, entropy: c_double) -> c_int>,
+    pseudorand: Option<unsafe extern "C" fn(buf: *mut u8, num: c_int) -> c_int>,
+    status: Option<unsafe extern "C" fn() -> c_int>,
+}
+
+extern "C" {
+    fn RAND_get_rand_method() -> *const RandMethod;
+    fn RAND_set_rand_method(meth: *const RandMethod) -> c_int;
+}
+
+static SEEDED_METHOD: RandMethod = RandMethod {
+    seed: Some(seeded_seed),
+    bytes: Some(seeded_bytes),
+    cleanup: None,
+    add: Some(seeded_add),
+    pseudorand: Some(seeded_bytes),
+    status: Some(seeded_status),
+};
+static DEFAULT_METHOD: AtomicPtr<RandMethod> = AtomicPtr::new(std::ptr::null_mut());
+static INSTALL_LOCK: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    static THREAD_SEED: RefCell<Option<SeededBytes>> = const { RefCell::new(None) };
+}
+
+fn default_method() -> Option<&'static RandMethod> {
+    unsafe { DEFAULT_METHOD.load(Ordering::Acquire).as_ref() }
+}
+
+unsafe extern "C" fn seeded_seed(buf: *const c_void, num: c_int) -> c_int {
+    match default_method().and_then(|meth| meth.seed) {
+        Some(seed) => seed(buf, num),
+        None => 1,
+    }
+}
+
+unsafe extern "C" fn seeded_bytes(buf: *mut u8, num: c_int) -> c_int {
+    let seeded = THREAD_SEED.with(|seed| match seed.borrow_mut().as_mut() {
+        Some(seed) if num >= 0 => {
+            seed.fill(std::slice::from_raw_parts_mut(buf, num as usize));
+            true
+        }
+        _ => false,
+    });
+    if seeded {
+        return 1;
+    }
+    match default_method().and_then(|meth| meth.bytes) {
+        Some(bytes) => bytes(buf, num),
+        None => 0,
+    }
+}
+
+unsafe extern "C" fn seeded_add(buf: *const c_void, num: c_int, entropy: c_double) -> c_int {
+    match default_method().and_then(|meth| meth.add) {
+        Some(add) => add(buf, num, entropy),
+        None => 1,
+    }
+}
+
+unsafe extern "C" fn seeded_status() -> c_int {
+    match default_method().and_then(|meth| meth.status) {
+        Some(status) => status(),
+        None => 1,
+    }
+}
+
+/// The seeded generator, installed in place of the default OpenSSL generator
+/// while a seeded presentation is built. The default generator continues to
+/// serve threads without a seed, and is restored when this is dropped.
+struct SeededMethod {
+    default: *const RandMethod,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl SeededMethod {
+    fn install() -> Result<Self> {
+        // only one seeded presentation replaces the generator at a time
+        let lock = INSTALL_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let default = unsafe { RAND_get_rand_method() };
+        if default.is_null() {
+            return Err(err_msg!(
+                Unexpected,
+                "The OpenSSL random number generator could not be replaced"
+            ));
+        }
+        // the last default method remains set, for threads which fetched the
+        // seeded method before it is restored
+        DEFAULT_METHOD.store(default as *mut RandMethod, Ordering::Release);
+        if unsafe { RAND_set_rand_method(&SEEDED_METHOD) } != 1 {
+            return Err(err_msg!(
+                Unexpected,
+                "The OpenSSL random number generator could not be replaced"
+            ));
+        }
+        Ok(Self {
+            default,
+            _lock: lock,
+        })
+    }
+}
+
+impl Drop for SeededMethod {
+    fn drop(&mut self) {
+        if unsafe { RAND_set_rand_method(self.default) } != 1 {
+            error!("The default OpenSSL random number generator could not be restored");
+        }
+    }
+}
+
+/// Clears the seed of the current thread when dropped
+struct ThreadSeed;
+
+impl ThreadSeed {
+    fn set(seed: &[u8]) -> Self {
+        THREAD_SEED.with(|s| s.replace(Some(SeededBytes::new(SEED_DOMAIN, seed))));
+        Self
+    }
+}
+
+impl Drop for ThreadSeed {
+    fn drop(&mut self) {
+        THREAD_SEED.with(|s| s.replace(None));
+    }
+}
+
+/// Create a presentation using random values derived from `seed`. The
+/// presentation reveals the hidden attributes and link secret to anyone who
+/// knows the seed, and must only be used in tests.
+pub fn create_presentation_from_seed(
+    pres_req: &PresentationRequest,
+    credentials: PresentCredentials,
+    self_attested: Option<HashMap<String, String>>,
+    master_secret: &MasterSecret,
+    schemas: &HashMap<SchemaId, &Schema>,
+    cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+    seed: &[u8],
+) -> Result<Presentation> {
+    if seed.is_empty() {
+        return Err(err_msg!("Proof seed must not be empty"));
+    }
+    if credentials
+        .0
+        .iter()
+        .any(|present| present.rev_state.is_some() || !present.rev_states.is_empty())
+    {
+        return Err(err_msg!(
+            "Seeded presentations do not support revocation states"
+        ));
+    }
+    warn!("Creating a presentation from a seed: it must only be used in tests");
+    let _method = SeededMethod::install()?;
+    let _seed = ThreadSeed::set(seed);
+    create_presentation_with_fixed_blinding(
+        pres_req,
+        credentials,
+        self_attested,
+        master_secret,
+        schemas,
+        cred_defs,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::seeded_keys::create_credential_definition_from_seed;
+    use crate::services::{issuer, prover, verifier};
+
+    #[test]
+    fn seeded_presentation_is_reproducible() {
+        let did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+        let schema = issuer::create_schema(
+            &did,
+            "gvt",
+            "1.0",
+            vec!["name".to_string(), "age".to_string(), "sex".to_string()].into(),
+            None,
+        )
+        .unwrap();
+        let (cred_def, cred_def_pvt, key_proof) = create_credential_definition_from_seed(
+            &did,
+            &schema,
+            "tag",
+            SignatureType::CL,
+            b"keys",
+        )
+        .unwrap();
+        let offer = issuer::create_credential_offer(schema.id(), &cred_def, &key_proof).unwrap();
+        let master_secret = prover::create_master_secret().unwrap();
+        let (request, metadata) =
+            prover::create_credential_request(&did, &cred_def, &master_secret, "default", &offer)
+                .unwrap();
+        let mut values = MakeCredentialValues::default();
+        values.add_raw("name", "Alex").unwrap();
+        values.add_raw("age", "28").unwrap();
+        values.add_raw("sex", "F").unwrap();
+        let (mut credential, _, _) = issuer::create_credential(
+            &cred_def,
+            &cred_def_pvt,
+            &offer,
+            &request,
+            values.into(),
+            None,
+        )
+        .unwrap();
+        prover::process_credential(&mut credential, &metadata, &master_secret, &cred_def, None)
+            .unwrap();
+
+        let pres_req: PresentationRequest = serde_json::from_value(serde_json::json!({
+            "nonce": "1234567890",
+            "name": "seeded",
+            "version": "1.0",
+            "requested_attributes": {
+                "name": {"name": "name"}
+            },
+            "requested_predicates": {
+                "age": {"name": "age", "p_type": ">=", "p_value": 18}
+            }
+        }))
+        .unwrap();
+        let mut schemas = HashMap::new();
+        schemas.insert(schema.id().clone(), &schema);
+        let mut cred_defs = HashMap::new();
+        cred_defs.insert(cred_def.id().clone(), &cred_def);
+        let present = || {
+            let mut present = PresentCredentials::default();
+            {
+                let mut cred = present.add_credential(&credential, None, None);
+                cred.add_requested_attribute("name", true);
+                cred.add_requested_predicate("age");
+            }
+            create_presentation_from_seed(
+                &pres_req,
+                present,
+                None,
+                &master_secret,
+                &schemas,
+                &cred_defs,
+                b"proof",
+            )
+            .unwrap()
+        };
+        let first = present();
+        let second = present();
+        // the default generator is restored after each presentation
+        assert_eq!(
+            unsafe { RAND_get_rand_method() },
+            DEFAULT_METHOD.load(Ordering::Acquire) as *const RandMethod
+        );
+        assert_eq!(
+            serde_json::to_value(&first).unwrap(),
+            serde_json::to_value(&second).unwrap()
+        );
+        assert!(
+            verifier::verify_presentation(&first, &pres_req, &schemas, &cred_defs, None, None)
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! Cumulative counts and durations of the expensive library operations,
+//! for embedders monitoring performance without an external profiler.
+//!
+//! Each call of an operation is recorded when it returns, whether or not it
+//! succeeded. Operations which delegate to another, such as the batch
+//! verification of presentations, are recorded once per inner operation.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+
+use crate::error::Result;
+
+static STATS: Lazy<Mutex<BTreeMap<&'static str, Totals>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Totals {
+    count: u64,
+    total: Duration,
+    max: Duration,
+}
+
+/// The statistics of an operation
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OperationStats {
+    /// The number of calls
+    pub count: u64,
+    /// The total duration of the calls, in milliseconds
+    pub total_ms: f64,
+    /// The average duration of a call, in milliseconds
+    pub average_ms: f64,
+    /// The duration of the slowest call, in milliseconds
+    pub max_ms: f64,
+}
+
+impl From<&Totals> for OperationStats {
+    fn from(totals: &Totals) -> Self {
+        let total_ms = totals.total.as_secs_f64() * 1000.0;
+        Self {
+            count: totals.count,
+            total_ms,
+            average_ms: if totals.count == 0 {
+                0.0
+            } else {
+                total_ms / totals.count as f64
+            },
+            max_ms: totals.max.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+/// Fetch the statistics of each operation called since the library was
+/// loaded or the statistics were last reset, by operation name
+pub fn operation_stats() -> Result<BTreeMap<String, OperationStats>> {
+    let stats = STATS
+        .lock()
+        .map_err(|_| err_msg!(Unexpected, "Error locking operation statistics"))?;
+    Ok(stats
+        .iter()
+        .map(|(name, totals)| (name.to_string(), totals.into()))
+        .collect())
+}
+
+/// Discard the statistics collected so far
+pub fn reset_operation_stats() -> Result<()> {
+    STATS
+        .lock()
+        .map_err(|_| err_msg!(Unexpected, "Error locking operation statistics"))?
+        .clear();
+    Ok(())
+}
+
+/// Records the duration of an operation when dropped
+pub(crate) struct OperationTimer {
+    name: &'static str,
+    start: Instant,
+}
+
+impl OperationTimer {
+    pub(crate) fn start(name: &'static str) -> Self {
+        Self {
+            name,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for OperationTimer {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        // Statistics are dropped rather than failing the operation
+        if let Ok(mut stats) = STATS.lock() {
+            let totals = stats.entry(self.name).or_default();
+            totals.count += 1;
+            totals.total += elapsed;
+            totals.max = totals.max.max(elapsed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operation_timer_records() {
+        for _ in 0..2 {
+            let _timer = OperationTimer::start("stats_test");
+        }
+        let stats = operation_stats().unwrap();
+        let test_stats = &stats["stats_test"];
+        assert_eq!(test_stats.count, 2);
+        assert!(test_stats.max_ms <= test_stats.total_ms);
+        assert!((test_stats.average_ms * 2.0 - test_stats.total_ms).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,282 @@
+//! Encrypted persistence of credentials for agents without a separate wallet.
+//!
+//! A store is a SQLite database encrypted by SQLCipher under a key derived
+//! from a passphrase. SQLCipher derives the key with PBKDF2-HMAC-SHA512 using
+//! a random salt kept in the database file and an iteration count fixed by
+//! this library, so no key derivation parameter is taken from the file beyond
+//! the salt. Each credential is stored with the tags produced by
+//! [`credential_tags`](super::prover::credential_tags), and searches with WQL
+//! queries match the behaviour of a libindy wallet search. Changes are written
+//! to the database as they are made.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::prover::{credential_tags, tags_match};
+use super::types::Credential;
+use crate::error::Result;
+use indy_utils::wql::Query;
+
+/// The SQLCipher key derivation iterations for the passphrase
+const KDF_ITERATIONS: u32 = 256_000;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS credentials (
+        id TEXT PRIMARY KEY NOT NULL,
+        credential TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS tags (
+        credential_id TEXT NOT NULL REFERENCES credentials (id) ON DELETE CASCADE,
+        name TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (credential_id, name)
+    );
+";
+
+/// A passphrase-encrypted database of credentials, indexed by their search tags
+#[derive(Debug)]
+pub struct CredentialStore {
+    path: PathBuf,
+    conn: Connection,
+}
+
+impl CredentialStore {
+    /// Open the store at `path`, creating an empty store if the file does not
+    /// exist
+    pub fn open(path: impl AsRef<Path>, passphrase: &str) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let conn = Connection::open(&path)?;
+        conn.pragma_update(None, "key", passphrase)?;
+        conn.pragma_update(None, "kdf_iter", KDF_ITERATIONS)?;
+        conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+            .map_err(|_| err_msg!("Error decrypting credential store, check the passphrase"))?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { path, conn })
+    }
+
+    /// The path of the store file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The number of stored credentials
+    pub fn len(&self) -> Result<usize> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT count(*) FROM credentials", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// Check whether the store contains no credentials
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Add a credential, replacing any credential with the same identifier
+    pub fn insert(&mut self, id: &str, credential: &Credential) -> Result<()> {
+        let tags = credential_tags(credential)?;
+        let txn = self.conn.transaction()?;
+        txn.execute("DELETE FROM credentials WHERE id = ?1", params![id])?;
+        txn.execute(
+            "INSERT INTO credentials (id, credential) VALUES (?1, ?2)",
+            params![id, serde_json::to_string(credential)?],
+        )?;
+        {
+            let mut insert_tag =
+                txn.prepare("INSERT INTO tags (credential_id, name, value) VALUES (?1, ?2, ?3)")?;
+            for (name, value) in &tags {
+                insert_tag.execute(params![id, name, value])?;
+            }
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Fetch a credential by identifier
+    pub fn get(&self, id: &str) -> Result<Option<Credential>> {
+        let credential: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT credential FROM credentials WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        credential
+            .map(|json| serde_json::from_str(&json).map_err(err_map!("Invalid stored credential")))
+            .transpose()
+    }
+
+    /// Fetch the search tags of a credential by identifier
+    pub fn get_tags(&self, id: &str) -> Result<Option<BTreeMap<String, String>>> {
+        if !self.contains(id)? {
+            return Ok(None);
+        }
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name, value FROM tags WHERE credential_id = ?1")?;
+        let tags = stmt
+            .query_map(params![id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(Some(tags))
+    }
+
+    /// Check whether a credential is stored under an identifier
+    pub fn contains(&self, id: &str) -> Result<bool> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT 1 FROM credentials WHERE id = ?1",
+                params![id],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some())
+    }
+
+    /// Remove a credential, returning whether it was present
+    pub fn remove(&mut self, id: &str) -> Result<bool> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM credentials WHERE id = ?1", params![id])?
+            > 0)
+    }
+
+    /// The identifiers of the stored credentials, in sorted order
+    pub fn ids(&self) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM credentials ORDER BY id")?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(ids)
+    }
+
+    /// Find the credentials whose tags match a WQL query, in identifier order
+    pub fn search(&self, query: &Query) -> Result<Vec<(String, Credential)>> {
+        let mut tags: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        {
+            let mut stmt = self.conn.prepare(
+                "SELECT c.id, t.name, t.value FROM credentials c
+                LEFT JOIN tags t ON t.credential_id = c.id",
+            )?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let entry = tags.entry(row.get(0)?).or_default();
+                if let Some(name) = row.get::<_, Option<String>>(1)? {
+                    entry.insert(name, row.get(2)?);
+                }
+            }
+        }
+        let mut found = Vec::new();
+        for (id, tags) in tags {
+            if tags_match(query, &tags) {
+                if let Some(credential) = self.get(&id)? {
+                    found.push((id, credential));
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// Re-encrypt the store under a new passphrase
+    pub fn change_passphrase(&mut self, passphrase: &str) -> Result<()> {
+        self.conn.pragma_update(None, "rekey", passphrase)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::test_utils::{did, CredDefFixture, HolderFixture, SchemaBuilder};
+    use indy_utils::wql::QueryBuilder;
+
+    fn credentials() -> Vec<Credential> {
+        let issuer_did = did();
+        let schema = SchemaBuilder::new()
+            .issuer_did(issuer_did.clone())
+            .name("gvt")
+            .attributes(vec!["name", "age"])
+            .build()
+            .unwrap();
+        let fixture = CredDefFixture::new(issuer_did, schema, false).unwrap();
+        let holder = HolderFixture::new().unwrap();
+        [("Alex", "28"), ("Sam", "12")]
+            .iter()
+            .map(|(name, age)| {
+                fixture
+                    .issue(&holder, &[("name", name), ("age", age)])
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.db");
+        let mut store = CredentialStore::open(&path, "passphrase").unwrap();
+        assert!(store.is_empty().unwrap());
+        for (idx, credential) in credentials().iter().enumerate() {
+            store.insert(&format!("cred-{}", idx), credential).unwrap();
+        }
+        drop(store);
+
+        let mut store = CredentialStore::open(&path, "passphrase").unwrap();
+        assert_eq!(store.ids().unwrap(), vec!["cred-0", "cred-1"]);
+        assert_eq!(
+            store.get("cred-1").unwrap().unwrap().values.0["name"].raw,
+            "Sam"
+        );
+        assert_eq!(
+            store.get_tags("cred-0").unwrap().unwrap()["attr::age::value"],
+            "28"
+        );
+        assert!(store.get("cred-2").unwrap().is_none());
+        assert_eq!(store.get_tags("cred-2").unwrap(), None);
+
+        let query = QueryBuilder::new()
+            .eq("schema_name", "gvt")
+            .eq("attr::Name::value", "Alex")
+            .build()
+            .unwrap();
+        let found = store.search(&query).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, "cred-0");
+        assert_eq!(store.search(&Query::And(vec![])).unwrap().len(), 2);
+
+        assert!(store.remove("cred-0").unwrap());
+        assert!(!store.remove("cred-0").unwrap());
+        store.change_passphrase("new passphrase").unwrap();
+        drop(store);
+        assert_eq!(
+            CredentialStore::open(&path, "new passphrase")
+                .unwrap()
+                .len()
+                .unwrap(),
+            1
+        );
+
+        assert!(CredentialStore::open(&path, "passphrase").is_err());
+        assert!(CredentialStore::open(&path, "wrong").is_err());
+    }
+
+    #[test]
+    fn store_is_encrypted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.db");
+        let mut store = CredentialStore::open(&path, "passphrase").unwrap();
+        store.insert("cred-0", &credentials()[0]).unwrap();
+        drop(store);
+
+        let contents = std::fs::read(&path).unwrap();
+        for plain in [&b"SQLite format"[..], b"Alex", b"cred-0"].iter() {
+            assert!(!contents.windows(plain.len()).any(|w| w == *plain));
+        }
+    }
+}
@@ -1,37 +1,90 @@
-use std::cell::RefCell;
-use std::fs::File;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fs::{File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
+use std::sync::Mutex;
 
 use indy_utils::base58;
 use sha2::{Digest, Sha256};
 use tempfile;
+use zeroize::Zeroizing;
 
+use super::types::{
+    CancellationToken, CredentialDefinition, RevocationRegistryDefinition,
+    RevocationRegistryDefinitionPrivate,
+};
 use crate::error::Result;
 use crate::ursa::{
     cl::{RevocationTailsAccessor, RevocationTailsGenerator, Tail},
     errors::{UrsaCryptoError, UrsaCryptoErrorKind},
+    pair::{GroupOrderElement, PointG2},
 };
 
+#[cfg(feature = "tails_encryption")]
+use super::tails_encryption::{EncryptedTails, TailsKey};
+
 const TAILS_BLOB_TAG_SZ: u8 = 2;
 const TAIL_SIZE: usize = Tail::BYTES_REPR_SIZE;
 
+/// The magic value at the start of the index of a chunked tails file
+pub(crate) const TAILS_INDEX_MAGIC: &[u8; 4] = b"IDXT";
+/// The magic value at the start of an encrypted tails file
+pub(crate) const ENCRYPTED_TAILS_MAGIC: &[u8; 4] = b"ETLS";
+
+/// A source of tails, which may be shared between threads. Reads are
+/// serialized by an internal lock.
 #[derive(Debug)]
 pub struct TailsReader {
-    inner: Box<RefCell<dyn TailsReaderImpl>>,
+    inner: Box<Mutex<dyn TailsReaderImpl>>,
 }
 
 impl TailsReader {
     pub(crate) fn new<TR: TailsReaderImpl + 'static>(inner: TR) -> Self {
         Self {
-            inner: Box::new(RefCell::new(inner)),
+            inner: Box::new(Mutex::new(inner)),
+        }
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, dyn TailsReaderImpl + 'static>> {
+        self.inner
+            .lock()
+            .map_err(|_| err_msg!(Unexpected, "Error locking tails reader"))
+    }
+
+    /// Compute the tails hash in the base58 form recorded in a registry
+    /// definition
+    pub fn hash(&self) -> Result<String> {
+        Ok(base58::encode(self.lock()?.hash()?))
+    }
+
+    /// Check that the tails match the hash recorded in a registry definition.
+    /// The hash is recomputed from the tails source, and the parts of a
+    /// chunked tails file are each checked against its index.
+    pub fn verify_hash(&self, expected_hash: &str) -> Result<()> {
+        let mut inner = self.lock()?;
+        let hash = base58::encode(inner.hash()?);
+        if hash != expected_hash {
+            return Err(err_msg!(
+                InvalidState,
+                "Tails hash mismatch: expected {}, computed {}",
+                expected_hash,
+                hash
+            ));
         }
+        inner.verify_contents()
     }
 }
 
 pub trait TailsReaderImpl: std::fmt::Debug + Send {
     fn hash(&mut self) -> Result<Vec<u8>>;
     fn read(&mut self, size: usize, offset: usize) -> Result<Vec<u8>>;
+
+    /// Check any parts of the tails which are not covered directly by the
+    /// tails hash
+    fn verify_contents(&mut self) -> Result<()> {
+        Ok(())
+    }
 }
 
 impl RevocationTailsAccessor for TailsReader {
@@ -44,7 +97,13 @@ impl RevocationTailsAccessor for TailsReader {
 
         let tail_bytes = self
             .inner
-            .borrow_mut()
+            .lock()
+            .map_err(|_| {
+                UrsaCryptoError::from_msg(
+                    UrsaCryptoErrorKind::InvalidState,
+                    "Error locking tails reader",
+                )
+            })?
             .read(
                 TAIL_SIZE,
                 TAIL_SIZE * tail_id as usize + TAILS_BLOB_TAG_SZ as usize,
@@ -64,44 +123,314 @@ impl RevocationTailsAccessor for TailsReader {
     }
 }
 
+/// An in-memory copy of the tails for a registry, loaded in a single
+/// sequential pass so that many witnesses may be computed without
+/// re-reading the underlying tails source.
+#[derive(Debug)]
+pub(crate) struct TailsBuffer {
+    tails: Vec<Tail>,
+}
+
+impl TailsBuffer {
+    const READ_BATCH: usize = 1024;
+
+    pub fn load(reader: &TailsReader, max_cred_num: u32) -> Result<Self> {
+        let count = 2 * max_cred_num as usize + 1;
+        let mut tails = Vec::with_capacity(count);
+        let mut inner = reader.lock()?;
+        while tails.len() < count {
+            let batch = Self::READ_BATCH.min(count - tails.len());
+            let offset = TAIL_SIZE * tails.len() + TAILS_BLOB_TAG_SZ as usize;
+            let bytes = inner.read(TAIL_SIZE * batch, offset)?;
+            for tail_bytes in bytes.chunks_exact(TAIL_SIZE) {
+                tails.push(Tail::from_bytes(tail_bytes)?);
+            }
+        }
+        Ok(Self { tails })
+    }
+}
+
+impl RevocationTailsAccessor for TailsBuffer {
+    fn access_tail(
+        &self,
+        tail_id: u32,
+        accessor: &mut dyn FnMut(&Tail),
+    ) -> std::result::Result<(), UrsaCryptoError> {
+        let tail = self.tails.get(tail_id as usize).ok_or_else(|| {
+            UrsaCryptoError::from_msg(UrsaCryptoErrorKind::InvalidState, "Tail index out of range")
+        })?;
+        accessor(tail);
+        Ok(())
+    }
+}
+
+/// The index header of a chunked (v2) tails file.
+///
+/// The index is stored in the file referenced by the registry definition and
+/// lists the SHA-256 digest of each chunk file. The tails hash of a chunked
+/// registry is the digest of the serialized index, so every chunk is covered
+/// transitively.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TailsChunkIndex {
+    tail_count: u32,
+    chunk_tails: u32,
+    chunk_hashes: Vec<[u8; 32]>,
+}
+
+impl TailsChunkIndex {
+    const MAGIC: &'static [u8; 4] = TAILS_INDEX_MAGIC;
+    const VERSION: u16 = 2;
+    const HEADER_SZ: usize = 4 + 2 + 4 + 4 + 4;
+
+    fn chunk_count(&self) -> u32 {
+        self.chunk_hashes.len() as u32
+    }
+
+    fn chunk_len(&self, chunk: u32) -> usize {
+        let start = chunk as usize * self.chunk_tails as usize;
+        let end = (start + self.chunk_tails as usize).min(self.tail_count as usize);
+        (end - start) * TAIL_SIZE
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(Self::HEADER_SZ + 32 * self.chunk_hashes.len());
+        buf.extend_from_slice(Self::MAGIC);
+        buf.extend_from_slice(&Self::VERSION.to_be_bytes());
+        buf.extend_from_slice(&self.tail_count.to_be_bytes());
+        buf.extend_from_slice(&self.chunk_tails.to_be_bytes());
+        buf.extend_from_slice(&self.chunk_count().to_be_bytes());
+        for hash in self.chunk_hashes.iter() {
+            buf.extend_from_slice(hash);
+        }
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < Self::HEADER_SZ || &bytes[0..4] != Self::MAGIC {
+            return Err(err_msg!(InvalidState, "Invalid tails index header"));
+        }
+        let read_u32 = |pos: usize| {
+            let mut b = [0u8; 4];
+            b.copy_from_slice(&bytes[pos..pos + 4]);
+            u32::from_be_bytes(b)
+        };
+        let version = u16::from_be_bytes([bytes[4], bytes[5]]);
+        if version != Self::VERSION {
+            return Err(err_msg!(
+                InvalidState,
+                "Unsupported tails index version: {}",
+                version
+            ));
+        }
+        let tail_count = read_u32(6);
+        let chunk_tails = read_u32(10);
+        let chunk_count = read_u32(14) as usize;
+        if chunk_tails == 0
+            || bytes.len() != Self::HEADER_SZ + 32 * chunk_count
+            || (tail_count as usize).div_ceil(chunk_tails as usize) != chunk_count
+        {
+            return Err(err_msg!(InvalidState, "Inconsistent tails index header"));
+        }
+        let chunk_hashes = bytes[Self::HEADER_SZ..]
+            .chunks_exact(32)
+            .map(|h| {
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(h);
+                hash
+            })
+            .collect();
+        Ok(Self {
+            tail_count,
+            chunk_tails,
+            chunk_hashes,
+        })
+    }
+}
+
+fn tails_chunk_path(index_path: &str, chunk: u32) -> String {
+    format!("{}.{}", index_path, chunk)
+}
+
+#[derive(Debug)]
+struct ChunkedTails {
+    index: TailsChunkIndex,
+    hash: Vec<u8>,
+    chunk: Option<(u32, Vec<u8>)>,
+}
+
 #[derive(Debug)]
 pub struct TailsFileReader {
     path: String,
     file: Option<File>,
+    chunked: Option<ChunkedTails>,
+    #[cfg(feature = "tails_encryption")]
+    encrypted: Option<EncryptedTails>,
+    #[cfg(feature = "tails_encryption")]
+    key: Option<TailsKey>,
     hash: Option<Vec<u8>>,
 }
 
 impl TailsFileReader {
+    /// Create a reader for a tails file. Encrypted tails files are decrypted
+    /// with the tails key of the library configuration.
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(path: &str) -> TailsReader {
         TailsReader::new(Self {
             path: path.to_owned(),
             file: None,
+            chunked: None,
+            #[cfg(feature = "tails_encryption")]
+            encrypted: None,
+            #[cfg(feature = "tails_encryption")]
+            key: None,
             hash: None,
         })
     }
 
+    /// Create a reader for a tails file which may be encrypted with `key`
+    #[cfg(feature = "tails_encryption")]
+    pub fn new_encrypted(path: &str, key: TailsKey) -> TailsReader {
+        TailsReader::new(Self {
+            path: path.to_owned(),
+            file: None,
+            chunked: None,
+            encrypted: None,
+            key: Some(key),
+            hash: None,
+        })
+    }
+
+    fn is_open(&self) -> bool {
+        #[cfg(feature = "tails_encryption")]
+        if self.encrypted.is_some() {
+            return true;
+        }
+        self.file.is_some() || self.chunked.is_some()
+    }
+
+    #[cfg(feature = "tails_encryption")]
+    fn open_encrypted(&mut self, file: File) -> Result<()> {
+        let key = self
+            .key
+            .clone()
+            .or_else(|| crate::config::get_config().tails_key.clone())
+            .ok_or_else(|| {
+                err_msg!(
+                    InvalidState,
+                    "The tails file is encrypted and no tails key is set"
+                )
+            })?;
+        self.encrypted.replace(EncryptedTails::open(file, key)?);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "tails_encryption"))]
+    fn open_encrypted(&mut self, _file: File) -> Result<()> {
+        Err(err_msg!(
+            InvalidState,
+            "The tails file is encrypted, which is not supported by this build"
+        ))
+    }
+
+    /// Open the tails file, detecting whether it is a contiguous (v1) file,
+    /// the index of a chunked (v2) tails file, or an encrypted tails file
     pub fn open(&mut self) -> Result<()> {
-        if self.file.is_some() {
-            Ok(())
+        if self.is_open() {
+            return Ok(());
+        }
+        let mut file = File::open(self.path.clone())?;
+        let mut magic = Vec::with_capacity(4);
+        (&mut file).take(4).read_to_end(&mut magic)?;
+        if magic.as_slice() == ENCRYPTED_TAILS_MAGIC {
+            self.open_encrypted(file)?;
+        } else if magic.as_slice() == TailsChunkIndex::MAGIC {
+            let mut index_bytes = magic;
+            file.read_to_end(&mut index_bytes)?;
+            let index = TailsChunkIndex::from_bytes(&index_bytes)?;
+            self.chunked.replace(ChunkedTails {
+                index,
+                hash: Sha256::digest(&index_bytes).to_vec(),
+                chunk: None,
+            });
         } else {
-            let file = File::open(self.path.clone())?;
             self.file.replace(file);
-            Ok(())
         }
+        Ok(())
     }
 
     pub fn close(&mut self) {
         self.file.take();
+        self.chunked.take();
+        #[cfg(feature = "tails_encryption")]
+        self.encrypted.take();
+    }
+
+    fn load_chunk(path: &str, chunked: &mut ChunkedTails, chunk: u32) -> Result<()> {
+        if matches!(chunked.chunk, Some((idx, _)) if idx == chunk) {
+            return Ok(());
+        }
+        let expected = chunked
+            .index
+            .chunk_hashes
+            .get(chunk as usize)
+            .ok_or_else(|| err_msg!(InvalidState, "Tails chunk index out of range"))?;
+        let mut buf = Vec::with_capacity(chunked.index.chunk_len(chunk));
+        File::open(tails_chunk_path(path, chunk))?.read_to_end(&mut buf)?;
+        if buf.len() != chunked.index.chunk_len(chunk)
+            || Sha256::digest(&buf).as_slice() != &expected[..]
+        {
+            return Err(err_msg!(
+                InvalidState,
+                "Tails chunk {} does not match the tails index",
+                chunk
+            ));
+        }
+        chunked.chunk.replace((chunk, buf));
+        Ok(())
+    }
+
+    fn read_chunked(&mut self, size: usize, offset: usize) -> Result<Vec<u8>> {
+        let chunked = self.chunked.as_mut().unwrap();
+        // offsets are expressed relative to a contiguous tails file
+        let start = offset
+            .checked_sub(TAILS_BLOB_TAG_SZ as usize)
+            .ok_or_else(|| err_msg!(InvalidState, "Invalid tails offset"))?;
+        if start + size > chunked.index.tail_count as usize * TAIL_SIZE {
+            return Err(err_msg!(InvalidState, "Read past the end of the tails"));
+        }
+        let chunk_bytes = chunked.index.chunk_tails as usize * TAIL_SIZE;
+        let mut buf = Vec::with_capacity(size);
+        let mut pos = start;
+        while buf.len() < size {
+            let chunk = (pos / chunk_bytes) as u32;
+            Self::load_chunk(&self.path, chunked, chunk)?;
+            let data = &chunked.chunk.as_ref().unwrap().1;
+            let chunk_pos = pos % chunk_bytes;
+            let len = (size - buf.len()).min(data.len() - chunk_pos);
+            buf.extend_from_slice(&data[chunk_pos..chunk_pos + len]);
+            pos += len;
+        }
+        Ok(buf)
     }
 }
 
 impl TailsReaderImpl for TailsFileReader {
     fn hash(&mut self) -> Result<Vec<u8>> {
-        if self.hash.is_some() {
-            return Ok(self.hash.as_ref().unwrap().clone());
+        if let Some(hash) = self.hash.as_ref() {
+            return Ok(hash.clone());
         }
 
         self.open()?;
+        if let Some(chunked) = self.chunked.as_ref() {
+            self.hash = Some(chunked.hash.clone());
+            return Ok(chunked.hash.clone());
+        }
+        #[cfg(feature = "tails_encryption")]
+        if let Some(encrypted) = self.encrypted.as_mut() {
+            let hash = encrypted.hash()?;
+            self.hash = Some(hash.clone());
+            return Ok(hash);
+        }
         let file = self.file.as_mut().unwrap();
         file.seek(SeekFrom::Start(0))?;
         let mut hasher = Sha256::default();
@@ -117,10 +446,27 @@ impl TailsReaderImpl for TailsFileReader {
         }
     }
 
-    fn read(&mut self, size: usize, offset: usize) -> Result<Vec<u8>> {
-        let mut buf = vec![0u8; size];
+    fn verify_contents(&mut self) -> Result<()> {
+        self.open()?;
+        if let Some(chunked) = self.chunked.as_mut() {
+            for chunk in 0..chunked.index.chunk_count() {
+                Self::load_chunk(&self.path, chunked, chunk)?;
+            }
+        }
+        Ok(())
+    }
 
+    fn read(&mut self, size: usize, offset: usize) -> Result<Vec<u8>> {
         self.open()?;
+        if self.chunked.is_some() {
+            return self.read_chunked(size, offset);
+        }
+        #[cfg(feature = "tails_encryption")]
+        if let Some(encrypted) = self.encrypted.as_mut() {
+            return encrypted.read(size, offset);
+        }
+
+        let mut buf = vec![0u8; size];
         let file = self.file.as_mut().unwrap();
         file.seek(SeekFrom::Start(offset as u64))?;
         file.read_exact(buf.as_mut_slice())?;
@@ -129,13 +475,386 @@ impl TailsReaderImpl for TailsFileReader {
     }
 }
 
+/// Reads tails from a contiguous tails file held in memory, such as a
+/// download which has not yet been written to disk
+#[derive(Debug)]
+pub struct TailsBufferReader {
+    tails: Vec<u8>,
+}
+
+impl TailsBufferReader {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(tails: Vec<u8>) -> TailsReader {
+        TailsReader::new(Self { tails })
+    }
+}
+
+impl TailsReaderImpl for TailsBufferReader {
+    fn hash(&mut self) -> Result<Vec<u8>> {
+        Ok(Sha256::digest(&self.tails).to_vec())
+    }
+
+    fn read(&mut self, size: usize, offset: usize) -> Result<Vec<u8>> {
+        self.tails
+            .get(offset..offset + size)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| err_msg!(InvalidState, "Read past the end of the tails"))
+    }
+}
+
+/// The registry key from which the tails of a revocation registry are
+/// derived: tail `i` is `g_dash * gamma^i`. The private exponent `gamma` is
+/// kept as bytes which are zeroized on drop.
+pub(crate) struct RegistryTailsKey {
+    g_dash: PointG2,
+    gamma: Zeroizing<Vec<u8>>,
+    tail_count: u32,
+}
+
+impl RegistryTailsKey {
+    pub fn new(
+        cred_def: &CredentialDefinition,
+        rev_reg_def_private: &RevocationRegistryDefinitionPrivate,
+        max_cred_num: u32,
+    ) -> Result<Self> {
+        // Only the published serializations of the keys are relied upon:
+        // `g_dash` of the revocation key in the credential definition and
+        // `gamma` of the stored registry private key
+        #[derive(Deserialize)]
+        struct PublicKey {
+            g_dash: PointG2,
+        }
+        #[derive(Deserialize)]
+        struct PrivateKey<'a> {
+            gamma: &'a str,
+        }
+
+        let CredentialDefinition::CredentialDefinitionV1(cred_def) = cred_def;
+        let rev_key = cred_def
+            .value
+            .revocation
+            .as_ref()
+            .ok_or_else(|| err_msg!("Credential definition does not support revocation"))?;
+        let PublicKey { g_dash } = serde_json::from_value(serde_json::to_value(rev_key)?)?;
+        // the buffer is allocated upfront so that it is not copied as it grows
+        let mut private = Zeroizing::new(Vec::with_capacity(256));
+        serde_json::to_writer(&mut *private, &rev_reg_def_private.value)?;
+        let PrivateKey { gamma } = serde_json::from_slice(&private)?;
+        let gamma = Zeroizing::new(GroupOrderElement::from_string(gamma)?.to_bytes()?);
+        Ok(Self {
+            g_dash,
+            gamma,
+            tail_count: 2 * max_cred_num + 1,
+        })
+    }
+
+    /// The number of tails of the registry
+    pub fn tail_count(&self) -> u32 {
+        self.tail_count
+    }
+
+    /// Compute the serialized tails with indexes from `start` up to `end`
+    pub fn tails(&self, start: u32, end: u32) -> Result<Vec<u8>> {
+        let gamma = GroupOrderElement::from_bytes(&self.gamma)?;
+        let mut buf = Vec::with_capacity(end.saturating_sub(start) as usize * TAIL_SIZE);
+        for index in start..end {
+            let exp = gamma.pow_mod(&GroupOrderElement::from_bytes(&index.to_be_bytes())?)?;
+            buf.extend_from_slice(&self.g_dash.mul(&exp)?.to_bytes()?);
+        }
+        Ok(buf)
+    }
+}
+
+/// The tails of a registry derived from its key, starting with any tails
+/// already stored in a partial tails file
+struct RegistryTails<'g> {
+    key: &'g RegistryTailsKey,
+    stored: Option<(File, u32)>,
+    next: u32,
+    end: u32,
+}
+
+/// The tails to be written for a registry, produced in rounds. Tails derived
+/// from the registry key are computed in batches on separate workers when the
+/// `parallel` feature is enabled, while tails taken from a generator are
+/// computed one at a time.
+pub struct TailsBatches<'g> {
+    registry: Option<RegistryTails<'g>>,
+    generator: Option<&'g mut RevocationTailsGenerator>,
+}
+
+impl<'g> TailsBatches<'g> {
+    const BATCH_SIZE: u32 = 1024;
+
+    /// Produce the remaining tails of a generator
+    pub fn from_generator(generator: &'g mut RevocationTailsGenerator) -> Self {
+        Self {
+            registry: None,
+            generator: Some(generator),
+        }
+    }
+
+    /// Produce the tails of a registry with indexes from `start` up to
+    /// `end`. The tails stored in `stored`, a partial tails file positioned
+    /// at the first tail, are read back instead of being computed again.
+    pub(crate) fn from_registry(
+        key: &'g RegistryTailsKey,
+        start: u32,
+        end: u32,
+        stored: Option<(File, u32)>,
+    ) -> Self {
+        Self {
+            registry: Some(RegistryTails {
+                key,
+                stored,
+                next: start,
+                end,
+            }),
+            generator: None,
+        }
+    }
+
+    /// Keep a generator producing the same tails, for writers which only
+    /// accept a generator
+    pub(crate) fn with_generator(mut self, generator: &'g mut RevocationTailsGenerator) -> Self {
+        self.generator.replace(generator);
+        self
+    }
+
+    /// The generator of the tails, when one is available
+    pub fn generator(&mut self) -> Option<&mut RevocationTailsGenerator> {
+        self.generator.as_deref_mut()
+    }
+
+    /// Produce the serialized tails of the next round
+    pub fn next_round(&mut self) -> Result<Option<Vec<u8>>> {
+        if let Some(registry) = self.registry.as_mut() {
+            return registry.next_round();
+        }
+        let generator = match self.generator.as_mut() {
+            Some(generator) => generator,
+            None => return Ok(None),
+        };
+        let mut tails = Vec::new();
+        while tails.len() < Self::BATCH_SIZE as usize * TAIL_SIZE {
+            match generator.try_next()? {
+                Some(tail) => tails.extend_from_slice(&tail.to_bytes()?),
+                None => break,
+            }
+        }
+        Ok(if tails.is_empty() { None } else { Some(tails) })
+    }
+}
+
+impl RegistryTails<'_> {
+    fn next_round(&mut self) -> Result<Option<Vec<u8>>> {
+        if self.next >= self.end {
+            return Ok(None);
+        }
+        let workers = super::parallel::worker_count() as u32;
+        if let Some((file, count)) = self.stored.as_mut() {
+            if self.next < *count {
+                let take = (*count - self.next)
+                    .min(self.end - self.next)
+                    .min(TailsBatches::BATCH_SIZE.saturating_mul(workers));
+                let mut tails = vec![0u8; take as usize * TAIL_SIZE];
+                file.read_exact(&mut tails)?;
+                self.next += take;
+                return Ok(Some(tails));
+            }
+        }
+        let mut ranges = vec![];
+        for _ in 0..workers {
+            let start = ranges.last().map(|(_, end)| *end).unwrap_or(self.next);
+            let end = start.saturating_add(TailsBatches::BATCH_SIZE).min(self.end);
+            if start == end {
+                break;
+            }
+            ranges.push((start, end));
+        }
+        let key = self.key;
+        let batches = super::parallel::try_map(&ranges, |(start, end)| key.tails(*start, *end));
+        let mut tails = Vec::new();
+        for batch in batches {
+            tails.extend_from_slice(&batch?);
+        }
+        self.next = ranges.last().map(|(_, end)| *end).unwrap_or(self.end);
+        Ok(Some(tails))
+    }
+}
+
+/// Open (or create) a partial tails file for a registry, holding the version
+/// tag followed by the tails generated so far. A tail left incomplete by an
+/// interrupted write is discarded. Returns the file positioned at the first
+/// tail, along with the number of tails stored.
+pub(crate) fn open_partial_tails(path: &str, key: &RegistryTailsKey) -> Result<(File, u32)> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    let len = file.metadata()?.len();
+    if len == 0 {
+        file.write_all(&[0u8, TAILS_BLOB_TAG_SZ])?;
+        return Ok((file, 0));
+    }
+    let mut version = [0u8; TAILS_BLOB_TAG_SZ as usize];
+    file.read_exact(&mut version)
+        .map_err(err_map!("Invalid partial tails file"))?;
+    if version != [0u8, TAILS_BLOB_TAG_SZ] {
+        return Err(err_msg!("Invalid partial tails file"));
+    }
+    let count = (len - TAILS_BLOB_TAG_SZ as u64) / TAIL_SIZE as u64;
+    let count = u32::try_from(count)
+        .ok()
+        .filter(|count| *count <= key.tail_count())
+        .ok_or_else(|| err_msg!("The partial tails file holds too many tails"))?;
+    file.set_len(TAILS_BLOB_TAG_SZ as u64 + count as u64 * TAIL_SIZE as u64)?;
+    if count > 0 {
+        // check the last stored tail, so that the tails of another registry
+        // are not mistakenly completed
+        let mut last = vec![0u8; TAIL_SIZE];
+        file.seek(SeekFrom::Current((count as i64 - 1) * TAIL_SIZE as i64))?;
+        file.read_exact(&mut last)?;
+        if last != key.tails(count - 1, count)? {
+            return Err(err_msg!(
+                "The partial tails file does not belong to the revocation registry"
+            ));
+        }
+        file.seek(SeekFrom::Start(TAILS_BLOB_TAG_SZ as u64))?;
+    }
+    Ok((file, count))
+}
+
+/// Generate up to `count` more tails for a registry, appending them to its
+/// partial tails file. Returns the number of tails still to be generated.
+pub(crate) fn extend_partial_tails(key: &RegistryTailsKey, path: &str, count: u32) -> Result<u32> {
+    let (mut file, stored) = open_partial_tails(path, key)?;
+    file.seek(SeekFrom::End(0))?;
+    let end = stored.saturating_add(count).min(key.tail_count());
+    let mut batches = TailsBatches::from_registry(key, stored, end, None);
+    while let Some(tails) = batches.next_round()? {
+        file.write_all(&tails)?;
+    }
+    file.sync_data()?;
+    Ok(key.tail_count() - end)
+}
+
+/// Generates tails on demand from the registry private key instead of reading
+/// them from a file. Tails are produced in batches as they are accessed, so an
+/// issuer may issue and revoke credentials before (or without) the complete
+/// tails file being generated. Only the most recently used batches are kept.
+pub struct TailsGeneratorReader {
+    key: RegistryTailsKey,
+    batch_size: u32,
+    batches: VecDeque<(u32, Vec<u8>)>,
+}
+
+impl TailsGeneratorReader {
+    pub const DEFAULT_BATCH_SIZE: u32 = 1024;
+    /// The number of generated batches kept for further reads
+    pub const CACHED_BATCHES: usize = 16;
+
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        cred_def: &CredentialDefinition,
+        rev_reg_def: &RevocationRegistryDefinition,
+        rev_reg_def_private: &RevocationRegistryDefinitionPrivate,
+    ) -> Result<TailsReader> {
+        let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) = rev_reg_def;
+        Ok(TailsReader::new(Self {
+            key: RegistryTailsKey::new(
+                cred_def,
+                rev_reg_def_private,
+                rev_reg_def.value.max_cred_num,
+            )?,
+            batch_size: Self::DEFAULT_BATCH_SIZE,
+            batches: VecDeque::with_capacity(Self::CACHED_BATCHES),
+        }))
+    }
+
+    fn load_batch(&mut self, batch: u32) -> Result<&[u8]> {
+        match self.batches.iter().position(|(idx, _)| *idx == batch) {
+            Some(0) => (),
+            Some(pos) => {
+                let entry = self.batches.remove(pos).unwrap();
+                self.batches.push_front(entry);
+            }
+            None => {
+                let start = batch * self.batch_size;
+                let end = (start + self.batch_size).min(self.key.tail_count());
+                let tails = self.key.tails(start, end)?;
+                if self.batches.len() >= Self::CACHED_BATCHES {
+                    self.batches.pop_back();
+                }
+                self.batches.push_front((batch, tails));
+            }
+        }
+        Ok(self.batches[0].1.as_slice())
+    }
+}
+
+impl std::fmt::Debug for TailsGeneratorReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TailsGeneratorReader")
+            .field("tail_count", &self.key.tail_count())
+            .field("batch_size", &self.batch_size)
+            .finish()
+    }
+}
+
+impl TailsReaderImpl for TailsGeneratorReader {
+    fn hash(&mut self) -> Result<Vec<u8>> {
+        Err(err_msg!(
+            InvalidState,
+            "The tails hash is not available until the tails have been written"
+        ))
+    }
+
+    fn read(&mut self, size: usize, offset: usize) -> Result<Vec<u8>> {
+        let start = offset
+            .checked_sub(TAILS_BLOB_TAG_SZ as usize)
+            .ok_or_else(|| err_msg!(InvalidState, "Invalid tails offset"))?;
+        if start + size > self.key.tail_count() as usize * TAIL_SIZE {
+            return Err(err_msg!(InvalidState, "Read past the end of the tails"));
+        }
+        let batch_bytes = self.batch_size as usize * TAIL_SIZE;
+        let mut buf = Vec::with_capacity(size);
+        let mut pos = start;
+        while buf.len() < size {
+            let remain = size - buf.len();
+            let data = self.load_batch((pos / batch_bytes) as u32)?;
+            let batch_pos = pos % batch_bytes;
+            let len = remain.min(data.len() - batch_pos);
+            buf.extend_from_slice(&data[batch_pos..batch_pos + len]);
+            pos += len;
+        }
+        Ok(buf)
+    }
+}
+
 pub trait TailsWriter: std::fmt::Debug {
     fn write(&mut self, generator: &mut RevocationTailsGenerator) -> Result<(String, String)>;
+
+    /// Write the tails produced by `tails`. By default the tails are taken
+    /// from their generator, which is not available when completing the
+    /// tails of a registry created without them.
+    fn write_batches(&mut self, tails: &mut TailsBatches<'_>) -> Result<(String, String)> {
+        match tails.generator() {
+            Some(generator) => self.write(generator),
+            None => Err(err_msg!(
+                Unexpected,
+                "This tails writer requires a tails generator"
+            )),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct TailsFileWriter {
     root_path: PathBuf,
+    cancel: Option<CancellationToken>,
 }
 
 impl TailsFileWriter {
@@ -143,25 +862,38 @@ impl TailsFileWriter {
         Self {
             root_path: root_path
                 .map(PathBuf::from)
-                .unwrap_or_else(|| std::env::temp_dir()),
+                .unwrap_or_else(std::env::temp_dir),
+            cancel: None,
         }
     }
+
+    /// Abort tails generation when the given token is cancelled
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel.replace(cancel);
+        self
+    }
 }
 
 impl TailsWriter for TailsFileWriter {
     fn write(&mut self, generator: &mut RevocationTailsGenerator) -> Result<(String, String)> {
+        self.write_batches(&mut TailsBatches::from_generator(generator))
+    }
+
+    fn write_batches(&mut self, batches: &mut TailsBatches<'_>) -> Result<(String, String)> {
         let mut tempf = tempfile::NamedTempFile::new_in(self.root_path.clone())?;
         let file = tempf.as_file_mut();
         let mut hasher = Sha256::default();
         let version = &[0u8, 2u8];
-        file.write(version)?;
+        file.write_all(version)?;
         hasher.update(version);
-        while let Some(tail) = generator.try_next()? {
-            let tail_bytes = tail.to_bytes()?;
-            file.write(tail_bytes.as_slice())?;
-            hasher.update(tail_bytes);
+        while let Some(tails) = batches.next_round()? {
+            if let Some(cancel) = self.cancel.as_ref() {
+                cancel.check()?;
+            }
+            file.write_all(tails.as_slice())?;
+            hasher.update(tails);
         }
-        let tails_size = &file.seek(SeekFrom::Current(0))?;
+        let tails_size = &file.stream_position()?;
         let hash = base58::encode(hasher.finalize());
         let path = tempf.path().with_file_name(hash.clone());
         let _outf = match tempf.persist_noclobber(&path) {
@@ -178,3 +910,443 @@ impl TailsWriter for TailsFileWriter {
         Ok((path, hash))
     }
 }
+
+/// Writes tails in the chunked (v2) layout: an index file named by the tails
+/// hash, accompanied by chunk files `<hash>.0`, `<hash>.1`, ... each holding
+/// up to `chunk_size` tails. This avoids a single very large contiguous file
+/// for registries with a high `max_cred_num`.
+#[derive(Debug)]
+pub struct TailsChunkedWriter {
+    root_path: PathBuf,
+    chunk_size: u32,
+    cancel: Option<CancellationToken>,
+}
+
+impl TailsChunkedWriter {
+    pub const DEFAULT_CHUNK_SIZE: u32 = 65536;
+
+    pub fn new(root_path: Option<String>, chunk_size: Option<u32>) -> Self {
+        Self {
+            root_path: root_path
+                .map(PathBuf::from)
+                .unwrap_or_else(std::env::temp_dir),
+            chunk_size: chunk_size.unwrap_or(Self::DEFAULT_CHUNK_SIZE),
+            cancel: None,
+        }
+    }
+
+    /// Abort tails generation when the given token is cancelled
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel.replace(cancel);
+        self
+    }
+}
+
+impl TailsWriter for TailsChunkedWriter {
+    fn write(&mut self, generator: &mut RevocationTailsGenerator) -> Result<(String, String)> {
+        self.write_batches(&mut TailsBatches::from_generator(generator))
+    }
+
+    fn write_batches(&mut self, batches: &mut TailsBatches<'_>) -> Result<(String, String)> {
+        if self.chunk_size == 0 {
+            return Err(err_msg!("Tails chunk size must be positive"));
+        }
+        let mut chunks = vec![];
+        let mut chunk_hashes = vec![];
+        let mut current: Option<(tempfile::NamedTempFile, Sha256)> = None;
+        let mut chunk_count = 0u32;
+        let mut tail_count = 0u32;
+        while let Some(tails) = batches.next_round()? {
+            if let Some(cancel) = self.cancel.as_ref() {
+                cancel.check()?;
+            }
+            for tail_bytes in tails.chunks_exact(TAIL_SIZE) {
+                if current.is_none() {
+                    current.replace((
+                        tempfile::NamedTempFile::new_in(self.root_path.clone())?,
+                        Sha256::default(),
+                    ));
+                }
+                let (tempf, hasher) = current.as_mut().unwrap();
+                tempf.as_file_mut().write_all(tail_bytes)?;
+                hasher.update(tail_bytes);
+                tail_count += 1;
+                chunk_count += 1;
+                if chunk_count == self.chunk_size {
+                    let (tempf, hasher) = current.take().unwrap();
+                    chunks.push(tempf);
+                    chunk_hashes.push(hasher.finalize().into());
+                    chunk_count = 0;
+                }
+            }
+        }
+        if let Some((tempf, hasher)) = current.take() {
+            chunks.push(tempf);
+            chunk_hashes.push(hasher.finalize().into());
+        }
+
+        let index = TailsChunkIndex {
+            tail_count,
+            chunk_tails: self.chunk_size,
+            chunk_hashes,
+        }
+        .to_bytes();
+        let hash = base58::encode(Sha256::digest(&index));
+        let path = self.root_path.join(&hash).to_string_lossy().into_owned();
+
+        for (idx, tempf) in chunks.into_iter().enumerate() {
+            if let Err(err) = tempf.persist_noclobber(tails_chunk_path(&path, idx as u32)) {
+                return Err(err_msg!(IOError, "Error persisting tails chunk: {}", err));
+            }
+        }
+        let mut tempf = tempfile::NamedTempFile::new_in(self.root_path.clone())?;
+        tempf.as_file_mut().write_all(&index)?;
+        if let Err(err) = tempf.persist_noclobber(&path) {
+            return Err(err_msg!(IOError, "Error persisting tails index: {}", err));
+        }
+        debug!(
+            "TailsChunkedWriter: wrote tails index [{} tails]: {}",
+            tail_count, path
+        );
+        Ok((path, hash))
+    }
+}
+
+/// Collects the tails in memory, for callers which store or upload the tails
+/// themselves rather than writing them to the local filesystem.
+#[derive(Debug, Default)]
+pub struct TailsBufferWriter {
+    location: Option<String>,
+    cancel: Option<CancellationToken>,
+    tails: Vec<u8>,
+}
+
+impl TailsBufferWriter {
+    /// Create a new writer. The tails location recorded in the registry
+    /// definition is `location` with any `{hash}` placeholder replaced by the
+    /// tails hash, or just the tails hash when no location is given.
+    pub fn new(location: Option<String>) -> Self {
+        Self {
+            location,
+            ..Self::default()
+        }
+    }
+
+    /// Abort tails generation when the given token is cancelled
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel.replace(cancel);
+        self
+    }
+
+    /// Take the contents of the tails file written by the last call to `write`
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.tails
+    }
+}
+
+impl TailsWriter for TailsBufferWriter {
+    fn write(&mut self, generator: &mut RevocationTailsGenerator) -> Result<(String, String)> {
+        self.write_batches(&mut TailsBatches::from_generator(generator))
+    }
+
+    fn write_batches(&mut self, batches: &mut TailsBatches<'_>) -> Result<(String, String)> {
+        let mut tails = vec![0u8, 2u8];
+        while let Some(batch) = batches.next_round()? {
+            if let Some(cancel) = self.cancel.as_ref() {
+                cancel.check()?;
+            }
+            tails.extend_from_slice(batch.as_slice());
+        }
+        let hash = base58::encode(Sha256::digest(&tails));
+        let location = match self.location.as_ref() {
+            Some(location) => location.replace("{hash}", &hash),
+            None => hash.clone(),
+        };
+        debug!("TailsBufferWriter: wrote tails [size {}]", tails.len());
+        self.tails = tails;
+        Ok((location, hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tails_chunk_index_round_trip() {
+        let index = TailsChunkIndex {
+            tail_count: 11,
+            chunk_tails: 4,
+            chunk_hashes: vec![[1u8; 32], [2u8; 32], [3u8; 32]],
+        };
+        let bytes = index.to_bytes();
+        assert_eq!(TailsChunkIndex::from_bytes(&bytes).unwrap(), index);
+        assert_eq!(index.chunk_len(2), 3 * TAIL_SIZE);
+        assert!(TailsChunkIndex::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn verify_tails_hash() {
+        use crate::services::test_utils::{did, CredDefFixture, SchemaBuilder};
+        use crate::services::{issuer, types::*};
+
+        let did = did();
+        let schema = SchemaBuilder::new()
+            .issuer_did(did.clone())
+            .attribute("name")
+            .build()
+            .unwrap();
+        let cred_def = CredDefFixture::new(did.clone(), schema, true)
+            .unwrap()
+            .cred_def;
+        let (rev_reg_def, rev_reg_def_private, _, _) = issuer::create_revocation_registry_lazy(
+            &did,
+            &cred_def,
+            "tag",
+            RegistryType::CL_ACCUM,
+            IssuanceType::ISSUANCE_BY_DEFAULT,
+            5,
+        )
+        .unwrap();
+
+        let mut buffer_writer = TailsBufferWriter::new(None);
+        let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(written) =
+            issuer::write_revocation_registry_tails(
+                &cred_def,
+                &rev_reg_def,
+                &rev_reg_def_private,
+                &mut buffer_writer,
+            )
+            .unwrap();
+        let mut tails = buffer_writer.into_bytes();
+        let reader = TailsBufferReader::new(tails.clone());
+        assert_eq!(reader.hash().unwrap(), written.value.tails_hash);
+        reader.verify_hash(&written.value.tails_hash).unwrap();
+        tails[10] ^= 1;
+        assert_kind!(
+            InvalidState,
+            TailsBufferReader::new(tails).verify_hash(&written.value.tails_hash)
+        );
+
+        let tails_dir = tempfile::tempdir().unwrap();
+        let mut chunked_writer = TailsChunkedWriter::new(
+            Some(tails_dir.path().to_string_lossy().into_owned()),
+            Some(4),
+        );
+        let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(chunked) =
+            issuer::write_revocation_registry_tails(
+                &cred_def,
+                &rev_reg_def,
+                &rev_reg_def_private,
+                &mut chunked_writer,
+            )
+            .unwrap();
+        let location = chunked.value.tails_location.as_str();
+        TailsFileReader::new(location)
+            .verify_hash(&chunked.value.tails_hash)
+            .unwrap();
+        let chunk_path = tails_chunk_path(location, 1);
+        let mut chunk = std::fs::read(&chunk_path).unwrap();
+        chunk[0] ^= 1;
+        std::fs::write(&chunk_path, chunk).unwrap();
+        assert!(TailsFileReader::new(location)
+            .verify_hash(&chunked.value.tails_hash)
+            .is_err());
+    }
+
+    #[test]
+    fn tails_buffer_writer_matches_file_writer() {
+        use crate::services::test_utils::{did, CredDefFixture, SchemaBuilder};
+        use crate::services::{issuer, types::*};
+
+        let did = did();
+        let schema = SchemaBuilder::new()
+            .issuer_did(did.clone())
+            .attribute("name")
+            .build()
+            .unwrap();
+        let cred_def = CredDefFixture::new(did.clone(), schema, true)
+            .unwrap()
+            .cred_def;
+        let (rev_reg_def, rev_reg_def_private, _, _) = issuer::create_revocation_registry_lazy(
+            &did,
+            &cred_def,
+            "tag",
+            RegistryType::CL_ACCUM,
+            IssuanceType::ISSUANCE_BY_DEFAULT,
+            5,
+        )
+        .unwrap();
+
+        let mut buffer_writer = TailsBufferWriter::new(Some("https://tails/{hash}".to_string()));
+        let written = issuer::write_revocation_registry_tails(
+            &cred_def,
+            &rev_reg_def,
+            &rev_reg_def_private,
+            &mut buffer_writer,
+        )
+        .unwrap();
+        let tails_dir = tempfile::tempdir().unwrap();
+        let mut file_writer =
+            TailsFileWriter::new(Some(tails_dir.path().to_string_lossy().into_owned()));
+        let written_file = issuer::write_revocation_registry_tails(
+            &cred_def,
+            &rev_reg_def,
+            &rev_reg_def_private,
+            &mut file_writer,
+        )
+        .unwrap();
+
+        let (
+            RevocationRegistryDefinition::RevocationRegistryDefinitionV1(written),
+            RevocationRegistryDefinition::RevocationRegistryDefinitionV1(written_file),
+        ) = (written, written_file);
+        assert_eq!(written.value.tails_hash, written_file.value.tails_hash);
+        assert_eq!(
+            written.value.tails_location,
+            format!("https://tails/{}", written.value.tails_hash)
+        );
+        assert_eq!(
+            buffer_writer.into_bytes(),
+            std::fs::read(&written_file.value.tails_location).unwrap()
+        );
+    }
+
+    fn lazy_registry(
+        max_cred_num: u32,
+    ) -> (
+        CredentialDefinition,
+        RevocationRegistryDefinition,
+        RevocationRegistryDefinitionPrivate,
+    ) {
+        use crate::services::test_utils::{did, CredDefFixture, SchemaBuilder};
+        use crate::services::{issuer, types::*};
+
+        let did = did();
+        let schema = SchemaBuilder::new()
+            .issuer_did(did.clone())
+            .attribute("name")
+            .build()
+            .unwrap();
+        let cred_def = CredDefFixture::new(did.clone(), schema, true)
+            .unwrap()
+            .cred_def;
+        let (rev_reg_def, rev_reg_def_private, _, _) = issuer::create_revocation_registry_lazy(
+            &did,
+            &cred_def,
+            "tag",
+            RegistryType::CL_ACCUM,
+            IssuanceType::ISSUANCE_ON_DEMAND,
+            max_cred_num,
+        )
+        .unwrap();
+        (cred_def, rev_reg_def, rev_reg_def_private)
+    }
+
+    #[test]
+    fn registry_key_matches_generator() {
+        use crate::ursa::cl::issuer::Issuer as CryptoIssuer;
+
+        let (cred_def, _, _) = lazy_registry(1);
+        let CredentialDefinition::CredentialDefinitionV1(cred_def_v1) = &cred_def;
+        let (_, private, _, mut generator) = CryptoIssuer::new_revocation_registry_def(
+            &cred_def_v1.get_public_key().unwrap(),
+            5,
+            false,
+        )
+        .unwrap();
+        let key = RegistryTailsKey::new(
+            &cred_def,
+            &RevocationRegistryDefinitionPrivate { value: private },
+            5,
+        )
+        .unwrap();
+        let mut writer = TailsBufferWriter::new(None);
+        writer.write(&mut generator).unwrap();
+        assert_eq!(key.tail_count(), 11);
+        assert_eq!(writer.into_bytes()[2..], key.tails(0, 11).unwrap()[..]);
+    }
+
+    #[test]
+    fn tails_generator_reader_is_bounded() {
+        let (cred_def, _, rev_reg_def_private) = lazy_registry(1);
+        let key = RegistryTailsKey::new(&cred_def, &rev_reg_def_private, 20).unwrap();
+        let expected = key.tails(0, key.tail_count()).unwrap();
+        let mut reader = TailsGeneratorReader {
+            key,
+            batch_size: 1,
+            batches: VecDeque::new(),
+        };
+        for _ in 0..2 {
+            for idx in 0..41 {
+                let offset = TAILS_BLOB_TAG_SZ as usize + idx * TAIL_SIZE;
+                assert_eq!(
+                    reader.read(TAIL_SIZE, offset).unwrap(),
+                    expected[idx * TAIL_SIZE..(idx + 1) * TAIL_SIZE]
+                );
+                assert!(reader.batches.len() <= TailsGeneratorReader::CACHED_BATCHES);
+            }
+        }
+    }
+
+    #[test]
+    fn partial_tails_are_completed() {
+        use crate::services::issuer;
+
+        let (cred_def, rev_reg_def, rev_reg_def_private) = lazy_registry(5);
+        let tails_dir = tempfile::tempdir().unwrap();
+        let partial_path = tails_dir.path().join("partial");
+        let partial_path = partial_path.to_str().unwrap();
+        let extend = |count| {
+            issuer::extend_revocation_registry_tails(
+                &cred_def,
+                &rev_reg_def,
+                &rev_reg_def_private,
+                partial_path,
+                count,
+            )
+            .unwrap()
+        };
+        assert_eq!(extend(4), 7);
+        // a tail torn by an interrupted write is discarded
+        let mut file = OpenOptions::new().append(true).open(partial_path).unwrap();
+        file.write_all(&[1u8; 10]).unwrap();
+        drop(file);
+        assert_eq!(extend(3), 4);
+
+        let mut writer = TailsBufferWriter::new(None);
+        let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(completed) =
+            issuer::complete_revocation_registry_tails(
+                &cred_def,
+                &rev_reg_def,
+                &rev_reg_def_private,
+                partial_path,
+                &mut writer,
+            )
+            .unwrap();
+        let mut full_writer = TailsBufferWriter::new(None);
+        let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(written) =
+            issuer::write_revocation_registry_tails(
+                &cred_def,
+                &rev_reg_def,
+                &rev_reg_def_private,
+                &mut full_writer,
+            )
+            .unwrap();
+        assert_eq!(completed.value.tails_hash, written.value.tails_hash);
+        assert_eq!(writer.into_bytes(), full_writer.into_bytes());
+
+        assert_eq!(extend(100), 0);
+        let (other_cred_def, other_rev_reg_def, other_private) = lazy_registry(5);
+        assert_kind!(
+            Input,
+            issuer::extend_revocation_registry_tails(
+                &other_cred_def,
+                &other_rev_reg_def,
+                &other_private,
+                partial_path,
+                1,
+            )
+        );
+    }
+}
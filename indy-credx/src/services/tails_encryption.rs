@@ -0,0 +1,334 @@
+//! Encryption of locally cached tails files.
+//!
+//! An encrypted tails file begins with a header holding a magic value, the
+//! format version, the block size, the length of the plaintext tails file and
+//! a random nonce prefix. The plaintext is divided into blocks which are each
+//! encrypted with `AES-256-GCM`, using the nonce prefix followed by the block
+//! number as the IV and the header as associated data, so that tails may be
+//! read at random offsets by decrypting a single block. The tails hash of an
+//! encrypted file is that of the plaintext, as recorded in the registry
+//! definition.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use indy_utils::base58;
+use openssl::rand::rand_bytes;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use sha2::{Digest, Sha256};
+
+use super::tails::{ENCRYPTED_TAILS_MAGIC, TAILS_INDEX_MAGIC};
+use crate::error::Result;
+
+const VERSION: u8 = 1;
+const HEADER_SZ: usize = 4 + 1 + 4 + 8 + 8;
+const TAG_SZ: usize = 16;
+const DEFAULT_BLOCK_SIZE: u32 = 64 * 1024;
+
+/// A 256-bit key for the encryption of tails files
+#[derive(Clone, PartialEq, Eq)]
+pub struct TailsKey([u8; 32]);
+
+impl TailsKey {
+    /// Create a key from 32 raw bytes
+    pub fn new(key: &[u8]) -> Result<Self> {
+        if key.len() != 32 {
+            return Err(err_msg!("Tails encryption keys must be 32 bytes"));
+        }
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(key);
+        Ok(Self(bytes))
+    }
+
+    /// Generate a new random key
+    pub fn generate() -> Result<Self> {
+        let mut bytes = [0u8; 32];
+        rand_bytes(&mut bytes).map_err(err_map!(Unexpected))?;
+        Ok(Self(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for TailsKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TailsKey(..)")
+    }
+}
+
+fn block_iv(nonce_prefix: &[u8], block: u64) -> Result<[u8; 12]> {
+    let block: u32 = std::convert::TryInto::try_into(block)
+        .map_err(|_| err_msg!(InvalidState, "Tails block index out of range"))?;
+    let mut iv = [0u8; 12];
+    iv[..8].copy_from_slice(nonce_prefix);
+    iv[8..].copy_from_slice(&block.to_be_bytes());
+    Ok(iv)
+}
+
+/// An open encrypted tails file
+#[derive(Debug)]
+pub(crate) struct EncryptedTails {
+    file: File,
+    key: TailsKey,
+    header: [u8; HEADER_SZ],
+    block_size: u32,
+    plain_len: u64,
+    block: Option<(u64, Vec<u8>)>,
+}
+
+impl EncryptedTails {
+    /// Read the header of an encrypted tails file, positioned after the magic
+    pub fn open(mut file: File, key: TailsKey) -> Result<Self> {
+        let mut header = [0u8; HEADER_SZ];
+        header[..4].copy_from_slice(ENCRYPTED_TAILS_MAGIC);
+        file.read_exact(&mut header[4..])
+            .map_err(|_| err_msg!(InvalidState, "Invalid encrypted tails header"))?;
+        if header[4] != VERSION {
+            return Err(err_msg!(
+                InvalidState,
+                "Unsupported encrypted tails version: {}",
+                header[4]
+            ));
+        }
+        let mut block_size = [0u8; 4];
+        block_size.copy_from_slice(&header[5..9]);
+        let block_size = u32::from_be_bytes(block_size);
+        let mut plain_len = [0u8; 8];
+        plain_len.copy_from_slice(&header[9..17]);
+        let plain_len = u64::from_be_bytes(plain_len);
+        if block_size == 0 {
+            return Err(err_msg!(InvalidState, "Invalid encrypted tails header"));
+        }
+        let blocks = plain_len.div_ceil(block_size as u64);
+        let expected_len = HEADER_SZ as u64 + plain_len + blocks * TAG_SZ as u64;
+        if file.metadata()?.len() != expected_len {
+            return Err(err_msg!(
+                InvalidState,
+                "Encrypted tails file has an unexpected length"
+            ));
+        }
+        Ok(Self {
+            file,
+            key,
+            header,
+            block_size,
+            plain_len,
+            block: None,
+        })
+    }
+
+    fn block_len(&self, block: u64) -> usize {
+        let start = block * self.block_size as u64;
+        (self.plain_len - start).min(self.block_size as u64) as usize
+    }
+
+    fn load_block(&mut self, block: u64) -> Result<&[u8]> {
+        if !matches!(self.block, Some((idx, _)) if idx == block) {
+            let len = self.block_len(block);
+            let pos = HEADER_SZ as u64 + block * (self.block_size as u64 + TAG_SZ as u64);
+            let mut buf = vec![0u8; len + TAG_SZ];
+            self.file.seek(SeekFrom::Start(pos))?;
+            self.file.read_exact(&mut buf)?;
+            let (ciphertext, tag) = buf.split_at(len);
+            let plaintext = decrypt_aead(
+                Cipher::aes_256_gcm(),
+                self.key.as_bytes(),
+                Some(&block_iv(&self.header[17..], block)?),
+                &self.header,
+                ciphertext,
+                tag,
+            )
+            .map_err(|_| err_msg!(InvalidState, "Error decrypting tails block {}", block))?;
+            self.block.replace((block, plaintext));
+        }
+        Ok(&self.block.as_ref().unwrap().1)
+    }
+
+    /// Read a range of the plaintext tails file
+    pub fn read(&mut self, size: usize, offset: usize) -> Result<Vec<u8>> {
+        if offset as u64 + size as u64 > self.plain_len {
+            return Err(err_msg!(InvalidState, "Read past the end of the tails"));
+        }
+        let block_size = self.block_size as usize;
+        let mut buf = Vec::with_capacity(size);
+        let mut pos = offset;
+        while buf.len() < size {
+            let data = self.load_block((pos / block_size) as u64)?;
+            let block_pos = pos % block_size;
+            let len = (size - buf.len()).min(data.len() - block_pos);
+            buf.extend_from_slice(&data[block_pos..block_pos + len]);
+            pos += len;
+        }
+        Ok(buf)
+    }
+
+    /// Compute the SHA-256 digest of the plaintext tails file
+    pub fn hash(&mut self) -> Result<Vec<u8>> {
+        let mut hasher = Sha256::default();
+        let blocks = self.plain_len.div_ceil(self.block_size as u64);
+        for block in 0..blocks {
+            hasher.update(self.load_block(block)?);
+        }
+        Ok(hasher.finalize().to_vec())
+    }
+}
+
+/// Encrypt a contiguous tails file, writing the result to `dest`. Returns
+/// the tails hash of the plaintext, which is unchanged by the encryption.
+pub fn encrypt_tails_file(
+    source: impl AsRef<Path>,
+    dest: impl AsRef<Path>,
+    key: &TailsKey,
+) -> Result<String> {
+    write_encrypted(source.as_ref(), dest.as_ref(), key, DEFAULT_BLOCK_SIZE)
+}
+
+fn write_encrypted(source: &Path, dest: &Path, key: &TailsKey, block_size: u32) -> Result<String> {
+    let mut source = File::open(source)?;
+    let plain_len = source.metadata()?.len();
+    let mut magic = [0u8; 4];
+    if plain_len >= 4 {
+        source.read_exact(&mut magic)?;
+        if &magic == ENCRYPTED_TAILS_MAGIC || &magic == TAILS_INDEX_MAGIC {
+            return Err(err_msg!(
+                "Only contiguous plaintext tails files may be encrypted"
+            ));
+        }
+        source.seek(SeekFrom::Start(0))?;
+    }
+
+    let mut header = [0u8; HEADER_SZ];
+    header[..4].copy_from_slice(ENCRYPTED_TAILS_MAGIC);
+    header[4] = VERSION;
+    header[5..9].copy_from_slice(&block_size.to_be_bytes());
+    header[9..17].copy_from_slice(&plain_len.to_be_bytes());
+    rand_bytes(&mut header[17..]).map_err(err_map!(Unexpected))?;
+
+    let dir = match dest.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut tempf = tempfile::NamedTempFile::new_in(dir)?;
+    tempf.write_all(&header)?;
+    let mut hasher = Sha256::default();
+    let mut buf = vec![0u8; block_size as usize];
+    let mut remaining = plain_len;
+    let mut block = 0u64;
+    while remaining > 0 {
+        let len = remaining.min(block_size as u64) as usize;
+        source.read_exact(&mut buf[..len])?;
+        hasher.update(&buf[..len]);
+        let mut tag = [0u8; TAG_SZ];
+        let ciphertext = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            key.as_bytes(),
+            Some(&block_iv(&header[17..], block)?),
+            &header,
+            &buf[..len],
+            &mut tag,
+        )
+        .map_err(err_map!(Unexpected, "Error encrypting tails block"))?;
+        tempf.write_all(&ciphertext)?;
+        tempf.write_all(&tag)?;
+        remaining -= len as u64;
+        block += 1;
+    }
+    tempf.as_file().sync_all()?;
+    if let Err(err) = tempf.persist(dest) {
+        return Err(err_msg!(
+            IOError,
+            "Error persisting encrypted tails file: {}",
+            err
+        ));
+    }
+    Ok(base58::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::tails::{TailsFileReader, TailsFileWriter};
+    use crate::services::test_utils::{did, CredDefFixture, SchemaBuilder};
+    use crate::services::{issuer, types::*};
+    use crate::ursa::cl::RevocationTailsAccessor;
+
+    #[test]
+    fn encrypted_tails_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let plain_path = dir.path().join("tails");
+        let enc_path = dir.path().join("tails.enc");
+        let mut plain = vec![0u8, 2u8];
+        plain.extend((0..1000u32).map(|i| (i % 251) as u8));
+        std::fs::write(&plain_path, &plain).unwrap();
+
+        let key = TailsKey::generate().unwrap();
+        let hash = write_encrypted(&plain_path, &enc_path, &key, 100).unwrap();
+        assert_eq!(hash, base58::encode(Sha256::digest(&plain)));
+
+        let mut file = File::open(&enc_path).unwrap();
+        file.seek(SeekFrom::Start(4)).unwrap();
+        let mut encrypted = EncryptedTails::open(file, key.clone()).unwrap();
+        assert_eq!(encrypted.hash().unwrap(), Sha256::digest(&plain).to_vec());
+        assert_eq!(encrypted.read(250, 95).unwrap(), &plain[95..345]);
+        assert!(encrypted.read(10, plain.len() - 5).is_err());
+
+        let mut file = File::open(&enc_path).unwrap();
+        file.seek(SeekFrom::Start(4)).unwrap();
+        let mut wrong_key = EncryptedTails::open(file, TailsKey::generate().unwrap()).unwrap();
+        assert!(wrong_key.read(10, 0).is_err());
+    }
+
+    #[test]
+    fn encrypted_tails_reader() {
+        let did = did();
+        let schema = SchemaBuilder::new()
+            .issuer_did(did.clone())
+            .attribute("name")
+            .build()
+            .unwrap();
+        let cred_def = CredDefFixture::new(did.clone(), schema, true)
+            .unwrap()
+            .cred_def;
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = TailsFileWriter::new(Some(dir.path().to_string_lossy().into_owned()));
+        let (rev_reg_def, _, _, _) = issuer::create_revocation_registry(
+            &did,
+            &cred_def,
+            "tag",
+            RegistryType::CL_ACCUM,
+            IssuanceType::ISSUANCE_BY_DEFAULT,
+            5,
+            &mut writer,
+        )
+        .unwrap();
+        let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) = rev_reg_def;
+
+        let key = TailsKey::generate().unwrap();
+        let enc_path = dir.path().join("tails.enc");
+        let hash = encrypt_tails_file(&rev_reg_def.value.tails_location, &enc_path, &key).unwrap();
+        assert_eq!(hash, rev_reg_def.value.tails_hash);
+
+        let plain_reader = TailsFileReader::new(&rev_reg_def.value.tails_location);
+        let enc_reader = TailsFileReader::new_encrypted(enc_path.to_str().unwrap(), key);
+        for tail_id in [0, 5, 10] {
+            let mut expected = None;
+            plain_reader
+                .access_tail(tail_id, &mut |tail| {
+                    expected = Some(tail.to_bytes().unwrap())
+                })
+                .unwrap();
+            let mut found = None;
+            enc_reader
+                .access_tail(tail_id, &mut |tail| found = Some(tail.to_bytes().unwrap()))
+                .unwrap();
+            assert_eq!(found, expected);
+        }
+
+        let no_key_reader = TailsFileReader::new(enc_path.to_str().unwrap());
+        assert!(no_key_reader.access_tail(0, &mut |_| ()).is_err());
+    }
+}
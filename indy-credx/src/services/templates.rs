@@ -0,0 +1,608 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde_json::{Map, Value};
+
+use super::types::PresentationRequest;
+use super::verifier::generate_nonce;
+use crate::error::Result;
+use indy_data_types::anoncreds::pres_request::{
+    AttributeInfo, EscrowInfo, NonRevocedInterval, PredicateInfo, PredicateTypes,
+    PresentationRequestPayload, PresentationRequestVersion, RangeDisclosureInfo,
+};
+use indy_utils::Validatable;
+
+const PLACEHOLDER_START: &str = "{{";
+const PLACEHOLDER_END: &str = "}}";
+
+/// A reusable presentation request with named placeholders.
+///
+/// The template is the JSON form of a presentation request without a nonce.
+/// Any string value may contain placeholders of the form `{{name}}`. A string
+/// consisting of a single placeholder is replaced by the bound JSON value,
+/// allowing numeric parameters such as predicate thresholds, otherwise the
+/// bound value is substituted as text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofRequestTemplate {
+    template: Map<String, Value>,
+    parameters: BTreeSet<String>,
+}
+
+impl ProofRequestTemplate {
+    pub fn new(template: Value) -> Result<Self> {
+        let template = match template {
+            Value::Object(map) => map,
+            _ => return Err(err_msg!("Proof request template must be a JSON object")),
+        };
+        if template.contains_key("nonce") {
+            return Err(err_msg!("Proof request template must not define a nonce"));
+        }
+        let mut parameters = BTreeSet::new();
+        for value in template.values() {
+            collect_parameters(value, &mut parameters)?;
+        }
+        Ok(Self {
+            template,
+            parameters,
+        })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        Self::new(serde_json::from_str(json)?)
+    }
+
+    /// The names of the placeholders which must be bound on instantiation
+    pub fn parameters(&self) -> &BTreeSet<String> {
+        &self.parameters
+    }
+
+    /// Create a presentation request with a fresh nonce, replacing every
+    /// placeholder with its bound value
+    pub fn instantiate(&self, values: &HashMap<String, Value>) -> Result<PresentationRequest> {
+        if let Some(missing) = self.parameters.iter().find(|p| !values.contains_key(*p)) {
+            return Err(err_msg!(
+                "No value bound for template parameter: {}",
+                missing
+            ));
+        }
+        let mut request = Map::with_capacity(self.template.len() + 1);
+        for (key, value) in self.template.iter() {
+            request.insert(key.clone(), substitute(value, values)?);
+        }
+        request.insert(
+            "nonce".to_string(),
+            Value::String(generate_nonce()?.to_string()),
+        );
+        let request: PresentationRequest = serde_json::from_value(Value::Object(request))
+            .map_err(err_map!("Invalid presentation request from template"))?;
+        request.validate()?;
+        Ok(request)
+    }
+}
+
+/// A collection of named proof request templates
+#[derive(Clone, Debug, Default)]
+pub struct TemplateRegistry {
+    templates: HashMap<String, ProofRequestTemplate>,
+}
+
+impl TemplateRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a template, replacing any existing template with the same name
+    pub fn register(&mut self, name: impl Into<String>, template: ProofRequestTemplate) {
+        self.templates.insert(name.into(), template);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<ProofRequestTemplate> {
+        self.templates.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ProofRequestTemplate> {
+        self.templates.get(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.templates.keys().map(String::as_str)
+    }
+
+    pub fn instantiate(
+        &self,
+        name: &str,
+        values: &HashMap<String, Value>,
+    ) -> Result<PresentationRequest> {
+        self.get(name)
+            .ok_or_else(|| err_msg!("Unknown proof request template: {}", name))?
+            .instantiate(values)
+    }
+}
+
+/// Combine several presentation requests into a single request with a fresh
+/// nonce.
+///
+/// Referents which are identical in several requests are included once. A
+/// referent requesting the same attribute (or predicate of the same type) with
+/// the same restrictions in multiple requests is merged, using the strictest
+/// predicate value, the common values of requested sets and the intersection
+/// of the non-revocation intervals. Any other conflicting referent, including
+/// one with different restrictions, is renamed by appending a numeric suffix
+/// so that both are requested. An attribute merged from several requests may only be escrowed to
+/// a single auditor, and the requests may only ask for a pseudonym in a
+/// single domain. When the requests do not share the same `non_revoked` interval, it is
+/// applied to the individual referents of each request instead.
+pub fn merge_presentation_requests(
+    name: &str,
+    version: &str,
+    requests: &[&PresentationRequest],
+) -> Result<PresentationRequest> {
+    if requests.is_empty() {
+        return Err(err_msg!("No presentation requests to merge"));
+    }
+
+    let shared_interval = requests[0].value().non_revoked.clone();
+    let push_down = requests
+        .iter()
+        .any(|req| req.value().non_revoked != shared_interval);
+
+    let mut requested_attributes: HashMap<String, AttributeInfo> = HashMap::new();
+    let mut requested_predicates: HashMap<String, PredicateInfo> = HashMap::new();
+    let mut requested_range_disclosures: HashMap<String, RangeDisclosureInfo> = HashMap::new();
+    let mut same_credential: Vec<Vec<String>> = vec![];
+    let mut equal_attributes: Vec<Vec<String>> = vec![];
+    let mut escrowed_attributes: HashMap<String, EscrowInfo> = HashMap::new();
+    let mut pseudonym_domain: Option<String> = None;
+    for req in requests {
+        let req = req.value();
+        // the referents may be renamed when merged, so track the final names
+        // for the same credential and equal attributes groups of the request
+        let mut renamed: HashMap<&str, String> = HashMap::new();
+        for (referent, info) in req.requested_attributes.iter() {
+            let mut info = info.clone();
+            if push_down && info.non_revoked.is_none() {
+                info.non_revoked = req.non_revoked.clone();
+            }
+            let merged =
+                merge_referent(&mut requested_attributes, referent, info, merge_attribute)?;
+            renamed.insert(referent, merged);
+        }
+        for (referent, info) in req.requested_predicates.iter() {
+            let mut info = info.clone();
+            if push_down && info.non_revoked.is_none() {
+                info.non_revoked = req.non_revoked.clone();
+            }
+            let merged =
+                merge_referent(&mut requested_predicates, referent, info, merge_predicate)?;
+            renamed.insert(referent, merged);
+        }
+        for (referent, info) in req.requested_range_disclosures.iter() {
+            let mut info = info.clone();
+            if push_down && info.non_revoked.is_none() {
+                info.non_revoked = req.non_revoked.clone();
+            }
+            let merged = merge_referent(
+                &mut requested_range_disclosures,
+                referent,
+                info,
+                merge_range_disclosure,
+            )?;
+            renamed.insert(referent, merged);
+        }
+        for group in req.same_credential.iter() {
+            let group = group
+                .iter()
+                .map(|referent| {
+                    renamed.get(referent.as_str()).cloned().ok_or_else(|| {
+                        err_msg!("Unknown referent in same credential group: {}", referent)
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if !same_credential.contains(&group) {
+                same_credential.push(group);
+            }
+        }
+        for group in req.equal_attributes.iter() {
+            let group = group
+                .iter()
+                .map(|referent| {
+                    renamed.get(referent.as_str()).cloned().ok_or_else(|| {
+                        err_msg!("Unknown referent in equal attributes group: {}", referent)
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if !equal_attributes.contains(&group) {
+                equal_attributes.push(group);
+            }
+        }
+        if let Some(domain) = &req.pseudonym_domain {
+            match &pseudonym_domain {
+                Some(existing) if existing != domain => {
+                    return Err(err_msg!(
+                        "Conflicting pseudonym domains: {}, {}",
+                        existing,
+                        domain
+                    ));
+                }
+                _ => pseudonym_domain = Some(domain.clone()),
+            }
+        }
+        for (referent, escrow) in req.escrowed_attributes.iter() {
+            let referent = renamed
+                .get(referent.as_str())
+                .cloned()
+                .ok_or_else(|| err_msg!("Unknown referent in escrowed attributes: {}", referent))?;
+            match escrowed_attributes.get(&referent) {
+                Some(existing) if existing != escrow => {
+                    return Err(err_msg!(
+                        "Conflicting auditor keys for escrowed attribute: {}",
+                        referent
+                    ));
+                }
+                _ => {
+                    escrowed_attributes.insert(referent, escrow.clone());
+                }
+            }
+        }
+    }
+
+    let payload = PresentationRequestPayload {
+        nonce: generate_nonce()?,
+        name: name.to_string(),
+        version: version.to_string(),
+        requested_attributes,
+        requested_predicates,
+        requested_range_disclosures,
+        non_revoked: if push_down { None } else { shared_interval },
+        same_credential,
+        equal_attributes,
+        escrowed_attributes,
+        pseudonym_domain,
+    };
+    let request = if requests
+        .iter()
+        .any(|req| req.version() == PresentationRequestVersion::V2)
+    {
+        PresentationRequest::PresentationRequestV2(payload)
+    } else {
+        PresentationRequest::PresentationRequestV1(payload)
+    };
+    request.validate()?;
+    Ok(request)
+}
+
+fn merge_referent<T: PartialEq>(
+    target: &mut HashMap<String, T>,
+    referent: &str,
+    info: T,
+    merge: impl Fn(&T, &T) -> Result<Option<T>>,
+) -> Result<String> {
+    let mut candidate = referent.to_string();
+    let mut suffix = 1;
+    loop {
+        match target.get_mut(&candidate) {
+            None => {
+                target.insert(candidate.clone(), info);
+                return Ok(candidate);
+            }
+            Some(existing) if *existing == info => return Ok(candidate),
+            Some(existing) => {
+                if let Some(merged) = merge(existing, &info)? {
+                    *existing = merged;
+                    return Ok(candidate);
+                }
+            }
+        }
+        suffix += 1;
+        candidate = format!("{}_{}", referent, suffix);
+    }
+}
+
+fn merge_attribute(a: &AttributeInfo, b: &AttributeInfo) -> Result<Option<AttributeInfo>> {
+    if a.name != b.name || a.names != b.names || a.restrictions != b.restrictions {
+        return Ok(None);
+    }
+    Ok(Some(AttributeInfo {
+        name: a.name.clone(),
+        names: a.names.clone(),
+        restrictions: a.restrictions.clone(),
+        non_revoked: merge_intervals(&a.non_revoked, &b.non_revoked)?,
+    }))
+}
+
+fn merge_predicate(a: &PredicateInfo, b: &PredicateInfo) -> Result<Option<PredicateInfo>> {
+    if a.name != b.name || a.p_type != b.p_type || a.restrictions != b.restrictions {
+        return Ok(None);
+    }
+    let p_value = match a.p_type {
+        PredicateTypes::GE | PredicateTypes::GT => a.p_value.max(b.p_value),
+        PredicateTypes::LE | PredicateTypes::LT => a.p_value.min(b.p_value),
+    };
+    Ok(Some(PredicateInfo {
+        name: a.name.clone(),
+        p_type: a.p_type.clone(),
+        p_value,
+        restrictions: a.restrictions.clone(),
+        non_revoked: merge_intervals(&a.non_revoked, &b.non_revoked)?,
+    }))
+}
+
+fn merge_range_disclosure(
+    a: &RangeDisclosureInfo,
+    b: &RangeDisclosureInfo,
+) -> Result<Option<RangeDisclosureInfo>> {
+    if a.name != b.name || a.restrictions != b.restrictions {
+        return Ok(None);
+    }
+    let values: Vec<i32> = a
+        .values
+        .iter()
+        .filter(|value| b.values.contains(value))
+        .cloned()
+        .collect();
+    if values.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(RangeDisclosureInfo {
+        name: a.name.clone(),
+        values,
+        restrictions: a.restrictions.clone(),
+        non_revoked: merge_intervals(&a.non_revoked, &b.non_revoked)?,
+    }))
+}
+
+fn merge_intervals(
+    a: &Option<NonRevocedInterval>,
+    b: &Option<NonRevocedInterval>,
+) -> Result<Option<NonRevocedInterval>> {
+    let (a, b) = match (a, b) {
+        (Some(a), Some(b)) => (a, b),
+        (Some(i), None) | (None, Some(i)) => return Ok(Some(i.clone())),
+        (None, None) => return Ok(None),
+    };
+    let from = a.from.max(b.from);
+    let to = match (a.to, b.to) {
+        (Some(x), Some(y)) => Some(x.min(y)),
+        (x, y) => x.or(y),
+    };
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(err_msg!(
+                "Non-revocation intervals do not overlap: {:?}, {:?}",
+                a,
+                b
+            ));
+        }
+    }
+    Ok(Some(NonRevocedInterval { from, to }))
+}
+
+fn collect_parameters(value: &Value, parameters: &mut BTreeSet<String>) -> Result<()> {
+    match value {
+        Value::String(s) => {
+            for_each_placeholder(s, |name| {
+                parameters.insert(name.to_string());
+            })?;
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_parameters(item, parameters)?;
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map {
+                for_each_placeholder(key, |name| {
+                    parameters.insert(name.to_string());
+                })?;
+                collect_parameters(item, parameters)?;
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+fn substitute(value: &Value, values: &HashMap<String, Value>) -> Result<Value> {
+    Ok(match value {
+        Value::String(s) => match single_placeholder(s) {
+            Some(name) => values[name].clone(),
+            None => Value::String(substitute_str(s, values)?),
+        },
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| substitute(item, values))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(map) => {
+            let mut result = Map::with_capacity(map.len());
+            for (key, item) in map {
+                result.insert(substitute_str(key, values)?, substitute(item, values)?);
+            }
+            Value::Object(result)
+        }
+        other => other.clone(),
+    })
+}
+
+fn single_placeholder(s: &str) -> Option<&str> {
+    let name = s
+        .strip_prefix(PLACEHOLDER_START)?
+        .strip_suffix(PLACEHOLDER_END)?
+        .trim();
+    if name.contains(PLACEHOLDER_START) || name.contains(PLACEHOLDER_END) {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+fn substitute_str(s: &str, values: &HashMap<String, Value>) -> Result<String> {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find(PLACEHOLDER_START) {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + PLACEHOLDER_START.len()..];
+        let end = after
+            .find(PLACEHOLDER_END)
+            .ok_or_else(|| err_msg!("Unterminated template placeholder in: {}", s))?;
+        let name = after[..end].trim();
+        match &values[name] {
+            Value::String(text) => result.push_str(text),
+            other => result.push_str(&other.to_string()),
+        }
+        rest = &after[end + PLACEHOLDER_END.len()..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+fn for_each_placeholder(s: &str, mut f: impl FnMut(&str)) -> Result<()> {
+    let mut rest = s;
+    while let Some(start) = rest.find(PLACEHOLDER_START) {
+        let after = &rest[start + PLACEHOLDER_START.len()..];
+        let end = after
+            .find(PLACEHOLDER_END)
+            .ok_or_else(|| err_msg!("Unterminated template placeholder in: {}", s))?;
+        let name = after[..end].trim();
+        if name.is_empty() {
+            return Err(err_msg!("Empty template placeholder in: {}", s));
+        }
+        f(name);
+        rest = &after[end + PLACEHOLDER_END.len()..];
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indy_utils::wql::Query;
+    use serde_json::json;
+
+    fn age_template() -> ProofRequestTemplate {
+        ProofRequestTemplate::new(json!({
+            "name": "age check",
+            "version": "1.0",
+            "requested_attributes": {
+                "name": {
+                    "name": "name",
+                    "restrictions": {"cred_def_id": "{{cred_def_id}}"}
+                }
+            },
+            "requested_predicates": {
+                "age": {
+                    "name": "age",
+                    "p_type": ">=",
+                    "p_value": "{{min_age}}",
+                    "restrictions": {"issuer_did": "{{issuer}}"}
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn template_instantiate_works() {
+        let template = age_template();
+        assert_eq!(
+            template.parameters().iter().collect::<Vec<_>>(),
+            vec!["cred_def_id", "issuer", "min_age"]
+        );
+
+        let mut registry = TemplateRegistry::new();
+        registry.register("age", template);
+        let values: HashMap<String, Value> = serde_json::from_value(json!({
+            "cred_def_id": "NcYxiDXkpYi6ov5FcYDi1e:3:CL:1:tag",
+            "issuer": "NcYxiDXkpYi6ov5FcYDi1e",
+            "min_age": 18
+        }))
+        .unwrap();
+        let first = registry.instantiate("age", &values).unwrap();
+        let second = registry.instantiate("age", &values).unwrap();
+        assert_ne!(first.value().nonce, second.value().nonce);
+        assert_eq!(first.value().requested_predicates["age"].p_value, 18);
+    }
+
+    #[test]
+    fn template_instantiate_fails_for_missing_value() {
+        let values: HashMap<String, Value> =
+            serde_json::from_value(json!({"min_age": 18})).unwrap();
+        assert!(age_template().instantiate(&values).is_err());
+    }
+
+    #[test]
+    fn merge_presentation_requests_works() {
+        let identity: PresentationRequest = serde_json::from_value(json!({
+            "nonce": "1234",
+            "name": "identity",
+            "version": "1.0",
+            "requested_attributes": {
+                "attr1": {"name": "name", "restrictions": {"issuer_did": "A"}},
+                "attr2": {"name": "sex"}
+            },
+            "requested_predicates": {
+                "pred1": {"name": "age", "p_type": ">=", "p_value": 18}
+            },
+            "non_revoked": {"from": 10, "to": 100}
+        }))
+        .unwrap();
+        let address: PresentationRequest = serde_json::from_value(json!({
+            "nonce": "5678",
+            "name": "address",
+            "version": "1.0",
+            "requested_attributes": {
+                "attr1": {"name": "name", "restrictions": {"issuer_did": "B"}},
+                "attr2": {"name": "street"}
+            },
+            "requested_predicates": {
+                "pred1": {"name": "age", "p_type": ">=", "p_value": 21}
+            },
+            "non_revoked": {"from": 50, "to": 200}
+        }))
+        .unwrap();
+
+        let merged = merge_presentation_requests("kyc", "1.0", &[&identity, &address]).unwrap();
+        let merged = merged.value();
+        assert_eq!(merged.non_revoked, None);
+        // the restrictions differ, so both attributes are requested
+        assert_eq!(merged.requested_attributes.len(), 4);
+        assert_eq!(
+            merged.requested_attributes["attr1"].restrictions,
+            Some(Query::Eq("issuer_did".to_string(), "A".to_string()))
+        );
+        assert_eq!(
+            merged.requested_attributes["attr1_2"].restrictions,
+            Some(Query::Eq("issuer_did".to_string(), "B".to_string()))
+        );
+        assert_eq!(
+            merged.requested_attributes["attr1_2"].non_revoked,
+            Some(NonRevocedInterval {
+                from: Some(50),
+                to: Some(200)
+            })
+        );
+        assert_eq!(
+            merged.requested_attributes["attr2_2"].name.as_deref(),
+            Some("street")
+        );
+        assert_eq!(merged.requested_predicates.len(), 1);
+        assert_eq!(merged.requested_predicates["pred1"].p_value, 21);
+        assert_eq!(
+            merged.requested_predicates["pred1"].non_revoked,
+            Some(NonRevocedInterval {
+                from: Some(50),
+                to: Some(100)
+            })
+        );
+    }
+
+    #[test]
+    fn template_rejects_nonce() {
+        assert!(ProofRequestTemplate::new(json!({
+            "name": "test",
+            "version": "1.0",
+            "nonce": "1234"
+        }))
+        .is_err());
+    }
+}
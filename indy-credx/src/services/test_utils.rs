@@ -0,0 +1,374 @@
+//! Fixtures for tests of code built on this library.
+//!
+//! Generating the keys of a credential definition requires a search for large
+//! safe primes, which dominates the running time of most tests. The fixtures
+//! generate the keys once per process for each set of attribute names and
+//! revocation support, and reuse them for every credential definition created
+//! with the same attributes. The keys are therefore not independent between
+//! fixtures and must never be used outside of tests.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use rand::RngCore;
+
+use super::flows::{issue_credential, HolderObjects, IssuerObjects};
+use super::types::*;
+use super::{issuer, prover, verifier};
+use crate::error::Result;
+use indy_utils::base58;
+
+type KeyCacheEntry = (serde_json::Value, serde_json::Value, serde_json::Value);
+type KeyCache = HashMap<(BTreeSet<String>, bool), KeyCacheEntry>;
+
+static KEY_CACHE: Lazy<Mutex<KeyCache>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Generate a random unqualified DID
+pub fn did() -> DidValue {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    DidValue::new(&base58::encode(bytes), None)
+}
+
+/// A builder for a schema with a random name
+#[derive(Debug)]
+pub struct SchemaBuilder {
+    issuer_did: DidValue,
+    name: String,
+    version: String,
+    attr_names: Vec<String>,
+}
+
+impl SchemaBuilder {
+    pub fn new() -> Self {
+        let mut suffix = [0u8; 4];
+        rand::thread_rng().fill_bytes(&mut suffix);
+        Self {
+            issuer_did: did(),
+            name: format!("schema-{}", base58::encode(suffix)),
+            version: "1.0".to_string(),
+            attr_names: vec![],
+        }
+    }
+
+    pub fn issuer_did(mut self, did: DidValue) -> Self {
+        self.issuer_did = did;
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    pub fn attribute(mut self, name: impl Into<String>) -> Self {
+        self.attr_names.push(name.into());
+        self
+    }
+
+    pub fn attributes<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.attr_names.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn build(self) -> Result<Schema> {
+        issuer::create_schema(
+            &self.issuer_did,
+            &self.name,
+            &self.version,
+            self.attr_names.into(),
+            None,
+        )
+    }
+}
+
+impl Default for SchemaBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A credential definition and the objects held by its issuer
+#[derive(Debug)]
+pub struct CredDefFixture {
+    pub issuer_did: DidValue,
+    pub schema: Schema,
+    pub cred_def: CredentialDefinition,
+    pub cred_def_private: CredentialDefinitionPrivate,
+    pub key_proof: CredentialKeyCorrectnessProof,
+}
+
+impl CredDefFixture {
+    /// Create a credential definition for a schema, reusing any keys
+    /// generated for the same attribute names
+    pub fn new(issuer_did: DidValue, schema: Schema, support_revocation: bool) -> Result<Self> {
+        let Schema::SchemaV1(schema_v1) = &schema;
+        let (cred_def, cred_def_private, key_proof) =
+            cached_keys(&issuer_did, &schema, support_revocation)?;
+
+        let mut cred_def = serde_json::to_value(cred_def)?;
+        let cred_def_id = issuer::make_credential_definition_id(
+            &issuer_did,
+            &schema_v1.id,
+            schema_v1.seq_no,
+            "default",
+            SignatureType::CL,
+        )?;
+        cred_def["id"] = serde_json::to_value(&cred_def_id)?;
+        cred_def["schemaId"] = serde_json::to_value(
+            schema_v1
+                .seq_no
+                .map(|seq_no| SchemaId(seq_no.to_string()))
+                .unwrap_or_else(|| schema_v1.id.clone()),
+        )?;
+
+        Ok(Self {
+            issuer_did,
+            cred_def: serde_json::from_value(cred_def)?,
+            cred_def_private: serde_json::from_value(cred_def_private)?,
+            key_proof: serde_json::from_value(key_proof)?,
+            schema,
+        })
+    }
+
+    /// Create a schema with the given attribute names and a credential
+    /// definition without revocation support
+    pub fn with_attributes<I, S>(names: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let issuer_did = did();
+        let schema = SchemaBuilder::new()
+            .issuer_did(issuer_did.clone())
+            .attributes(names)
+            .build()?;
+        Self::new(issuer_did, schema, false)
+    }
+
+    /// The objects used to issue credentials with `flows::issue_credential`,
+    /// without revocation
+    pub fn issuer_objects(&self) -> IssuerObjects<'_> {
+        IssuerObjects {
+            schema_id: self.schema.id(),
+            cred_def: &self.cred_def,
+            cred_def_private: &self.cred_def_private,
+            key_correctness_proof: &self.key_proof,
+            revocation: None,
+        }
+    }
+
+    /// Issue a credential with raw attribute values to a holder
+    pub fn issue(&self, holder: &HolderFixture, values: &[(&str, &str)]) -> Result<Credential> {
+        Ok(issue_credential(
+            self.issuer_objects(),
+            holder.holder_objects(),
+            credential_values(values)?,
+        )?
+        .credential)
+    }
+
+    /// The schema, by identifier
+    pub fn schemas(&self) -> HashMap<SchemaId, &Schema> {
+        let mut schemas = HashMap::new();
+        schemas.insert(self.schema.id().clone(), &self.schema);
+        schemas
+    }
+
+    /// The credential definition, by identifier
+    pub fn cred_defs(&self) -> HashMap<CredentialDefinitionId, &CredentialDefinition> {
+        let mut cred_defs = HashMap::new();
+        cred_defs.insert(self.cred_def.id().clone(), &self.cred_def);
+        cred_defs
+    }
+}
+
+/// Build credential values from raw attribute values
+pub fn credential_values(values: &[(&str, &str)]) -> Result<CredentialValues> {
+    let mut cred_values = MakeCredentialValues::default();
+    for (name, raw) in values {
+        cred_values.add_raw(*name, *raw)?;
+    }
+    Ok(cred_values.into())
+}
+
+/// The objects held by the holder of credentials
+#[derive(Debug)]
+pub struct HolderFixture {
+    pub did: DidValue,
+    pub master_secret: MasterSecret,
+}
+
+impl HolderFixture {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            did: did(),
+            master_secret: prover::create_master_secret()?,
+        })
+    }
+
+    /// The objects used to receive credentials with
+    /// `flows::issue_credential`, naming the master secret `default`
+    pub fn holder_objects(&self) -> HolderObjects<'_> {
+        HolderObjects {
+            prover_did: &self.did,
+            master_secret: &self.master_secret,
+            master_secret_id: "default",
+        }
+    }
+
+    /// Present a single credential, revealing every requested attribute and
+    /// proving every requested predicate and range disclosure from it
+    pub fn present(
+        &self,
+        credential: &Credential,
+        fixture: &CredDefFixture,
+        pres_req: &PresentationRequest,
+    ) -> Result<Presentation> {
+        let pres_req_val = pres_req.value();
+        let mut present = PresentCredentials::default();
+        {
+            let mut add = present.add_credential(credential, None, None);
+            for referent in pres_req_val.requested_attributes.keys() {
+                add.add_requested_attribute(referent.as_str(), true);
+            }
+            for referent in pres_req_val
+                .requested_predicates
+                .keys()
+                .chain(pres_req_val.requested_range_disclosures.keys())
+            {
+                add.add_requested_predicate(referent.as_str());
+            }
+        }
+        prover::create_presentation(
+            pres_req,
+            present,
+            None,
+            &self.master_secret,
+            &fixture.schemas(),
+            &fixture.cred_defs(),
+        )
+    }
+}
+
+/// Create a presentation request with a fresh nonce for the given attribute
+/// names and `>=` predicates. The referent of each attribute is its name, and
+/// the referent of each predicate is the attribute name followed by `_ge`.
+pub fn presentation_request(
+    attr_names: &[&str],
+    predicates: &[(&str, i32)],
+) -> Result<PresentationRequest> {
+    let attrs: serde_json::Map<String, serde_json::Value> = attr_names
+        .iter()
+        .map(|name| (name.to_string(), serde_json::json!({ "name": name })))
+        .collect();
+    let preds: serde_json::Map<String, serde_json::Value> = predicates
+        .iter()
+        .map(|(name, value)| {
+            (
+                format!("{}_ge", name),
+                serde_json::json!({"name": name, "p_type": ">=", "p_value": value}),
+            )
+        })
+        .collect();
+    Ok(serde_json::from_value(serde_json::json!({
+        "nonce": verifier::generate_nonce()?,
+        "name": "test",
+        "version": "1.0",
+        "requested_attributes": attrs,
+        "requested_predicates": preds,
+    }))?)
+}
+
+fn cached_keys(
+    issuer_did: &DidValue,
+    schema: &Schema,
+    support_revocation: bool,
+) -> Result<KeyCacheEntry> {
+    let Schema::SchemaV1(schema_v1) = schema;
+    let key = (
+        schema_v1
+            .attr_names
+            .0
+            .iter()
+            .cloned()
+            .collect::<BTreeSet<_>>(),
+        support_revocation,
+    );
+    if let Some(found) = KEY_CACHE
+        .lock()
+        .map_err(|_| err_msg!(Unexpected, "Error locking key cache"))?
+        .get(&key)
+    {
+        return Ok(found.clone());
+    }
+    // generate outside of the lock, accepting duplicate work from a race
+    let (cred_def, cred_def_private, key_proof) = issuer::create_credential_definition(
+        issuer_did,
+        schema,
+        "default",
+        SignatureType::CL,
+        CredentialDefinitionConfig::new(support_revocation),
+    )?;
+    let entry = (
+        serde_json::to_value(cred_def)?,
+        serde_json::to_value(cred_def_private)?,
+        serde_json::to_value(key_proof)?,
+    );
+    KEY_CACHE
+        .lock()
+        .map_err(|_| err_msg!(Unexpected, "Error locking key cache"))?
+        .entry(key)
+        .or_insert_with(|| entry.clone());
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixtures_work() {
+        let holder = HolderFixture::new().unwrap();
+        let fixture = CredDefFixture::with_attributes(vec!["name", "age"]).unwrap();
+        let credential = fixture
+            .issue(&holder, &[("name", "Alex"), ("age", "28")])
+            .unwrap();
+
+        let other = CredDefFixture::with_attributes(vec!["age", "name"]).unwrap();
+        assert_ne!(other.cred_def.id(), fixture.cred_def.id());
+        let (
+            CredentialDefinition::CredentialDefinitionV1(cred_def),
+            CredentialDefinition::CredentialDefinitionV1(other_cred_def),
+        ) = (&fixture.cred_def, &other.cred_def);
+        assert_eq!(
+            serde_json::to_value(&cred_def.value).unwrap(),
+            serde_json::to_value(&other_cred_def.value).unwrap()
+        );
+        other
+            .issue(&holder, &[("name", "Sam"), ("age", "30")])
+            .unwrap();
+
+        let pres_req = presentation_request(&["name"], &[("age", 18)]).unwrap();
+        let presentation = holder.present(&credential, &fixture, &pres_req).unwrap();
+        assert!(verifier::verify_presentation(
+            &presentation,
+            &pres_req,
+            &fixture.schemas(),
+            &fixture.cred_defs(),
+            None,
+            None
+        )
+        .unwrap());
+    }
+}
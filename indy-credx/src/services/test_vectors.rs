@@ -0,0 +1,185 @@
+//! Generation of reference objects for checking interoperability with other
+//! AnonCreds implementations.
+//!
+//! Every non-cryptographic input (DIDs, names, attribute values and nonces) is
+//! derived from the provided seed, so the same seed always yields the same
+//! identifiers, credential values and request structure. The CL key material,
+//! blinding factors and signatures are produced by ursa from system randomness
+//! and will differ between runs; consumers should check that they can parse and
+//! verify the generated objects rather than compare them byte for byte.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use sha2::{Digest, Sha256};
+
+use super::types::*;
+use super::{issuer, prover, verifier};
+use crate::error::Result;
+use indy_data_types::anoncreds::nonce::Nonce;
+use indy_utils::base58;
+
+const FIRST_NAMES: &[&str] = &["Alex", "Sam", "Jordan", "Taylor", "Morgan", "Casey"];
+
+/// A complete set of public objects for a single issuance and presentation
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestVectors {
+    /// The hex-encoded seed used to derive the inputs
+    pub seed: String,
+    pub schema: Schema,
+    pub cred_def: CredentialDefinition,
+    pub cred_offer: CredentialOffer,
+    pub cred_request: CredentialRequest,
+    pub credential: Credential,
+    pub pres_req: PresentationRequest,
+    pub presentation: Presentation,
+}
+
+impl TestVectors {
+    /// Generate a new set of test vectors for `seed`
+    pub fn generate(seed: &[u8]) -> Result<Self> {
+        let derive = |label: &str| -> [u8; 32] {
+            let mut hasher = Sha256::new();
+            hasher.update(seed);
+            hasher.update(label.as_bytes());
+            hasher.finalize().into()
+        };
+        let derive_nonce = |label: &str| -> Result<Nonce> {
+            let bytes = derive(label);
+            let mut value = [0u8; 16];
+            value[6..].copy_from_slice(&bytes[..10]);
+            Nonce::try_from(u128::from_be_bytes(value)).map_err(err_map!(Unexpected))
+        };
+
+        let issuer_did = DidValue::new(&base58::encode(&derive("issuer_did")[..16]), None);
+        let prover_did = DidValue::new(&base58::encode(&derive("prover_did")[..16]), None);
+
+        let schema = issuer::create_schema(
+            &issuer_did,
+            "test-vectors",
+            "1.0",
+            vec!["name".to_string(), "age".to_string()].into(),
+            None,
+        )?;
+        let (cred_def, cred_def_private, key_proof) = issuer::create_credential_definition(
+            &issuer_did,
+            &schema,
+            "default",
+            SignatureType::CL,
+            CredentialDefinitionConfig::new(false),
+        )?;
+
+        let mut cred_offer = issuer::create_credential_offer(schema.id(), &cred_def, &key_proof)?;
+        cred_offer.nonce = derive_nonce("cred_offer_nonce")?;
+
+        let master_secret = prover::create_master_secret()?;
+        let (mut cred_request, mut cred_request_metadata) = prover::create_credential_request(
+            &prover_did,
+            &cred_def,
+            &master_secret,
+            "default",
+            &cred_offer,
+        )?;
+        cred_request.nonce = derive_nonce("cred_request_nonce")?;
+        cred_request_metadata.nonce = derive_nonce("cred_request_nonce")?;
+
+        let values = derive("values");
+        let name = FIRST_NAMES[values[0] as usize % FIRST_NAMES.len()];
+        let age = 18 + u32::from(values[1]) % 60;
+        let mut cred_values = MakeCredentialValues::default();
+        cred_values.add_raw("name", name)?;
+        cred_values.add_raw("age", age.to_string())?;
+        let (mut credential, _, _) = issuer::create_credential(
+            &cred_def,
+            &cred_def_private,
+            &cred_offer,
+            &cred_request,
+            cred_values.into(),
+            None,
+        )?;
+        prover::process_credential(
+            &mut credential,
+            &cred_request_metadata,
+            &master_secret,
+            &cred_def,
+            None,
+        )?;
+
+        let pres_req: PresentationRequest = serde_json::from_value(serde_json::json!({
+            "nonce": derive_nonce("pres_req_nonce")?,
+            "name": "test-vectors",
+            "version": "1.0",
+            "requested_attributes": {
+                "name": {"name": "name"}
+            },
+            "requested_predicates": {
+                "age": {"name": "age", "p_type": ">=", "p_value": 18}
+            }
+        }))?;
+
+        let mut present = PresentCredentials::default();
+        {
+            let mut cred = present.add_credential(&credential, None, None);
+            cred.add_requested_attribute("name", true);
+            cred.add_requested_predicate("age");
+        }
+        let mut schemas = HashMap::new();
+        schemas.insert(schema.id().clone(), &schema);
+        let mut cred_defs = HashMap::new();
+        cred_defs.insert(cred_def.id().clone(), &cred_def);
+        let presentation = prover::create_presentation(
+            &pres_req,
+            present,
+            None,
+            &master_secret,
+            &schemas,
+            &cred_defs,
+        )?;
+
+        Ok(Self {
+            seed: seed.iter().map(|b| format!("{:02x}", b)).collect(),
+            schema,
+            cred_def,
+            cred_offer,
+            cred_request,
+            credential,
+            pres_req,
+            presentation,
+        })
+    }
+
+    /// Verify the presentation contained in these test vectors
+    pub fn verify(&self) -> Result<bool> {
+        let mut schemas = HashMap::new();
+        schemas.insert(self.schema.id().clone(), &self.schema);
+        let mut cred_defs = HashMap::new();
+        cred_defs.insert(self.cred_def.id().clone(), &self.cred_def);
+        verifier::verify_presentation(
+            &self.presentation,
+            &self.pres_req,
+            &schemas,
+            &cred_defs,
+            None,
+            None,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors_are_reproducible() {
+        let first = TestVectors::generate(b"seed").unwrap();
+        let second = TestVectors::generate(b"seed").unwrap();
+        assert_eq!(first.schema.id(), second.schema.id());
+        assert_eq!(first.credential.values, second.credential.values);
+        assert_eq!(first.pres_req, second.pres_req);
+        assert!(first.verify().unwrap());
+
+        let json = serde_json::to_string(&first).unwrap();
+        let restored: TestVectors = serde_json::from_str(&json).unwrap();
+        assert!(restored.verify().unwrap());
+    }
+}
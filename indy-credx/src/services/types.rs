@@ -1,35 +1,43 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use super::tails::TailsReader;
 pub use indy_data_types::{
     anoncreds::{
         cred_def::{
-            CredentialDefinition, CredentialDefinitionPrivate, CredentialKeyCorrectnessProof,
+            CredentialDefinition, CredentialDefinitionKey, CredentialDefinitionKeyType,
+            CredentialDefinitionPrivate, CredentialDefinitionV2, CredentialKeyCorrectnessProof,
             SignatureType,
         },
         cred_offer::CredentialOffer,
         cred_request::{CredentialRequest, CredentialRequestMetadata},
-        credential::{AttributeValues, Credential, CredentialValues},
+        credential::{
+            AttributeDisplay, AttributeValues, Credential, CredentialDisplay, CredentialValues,
+        },
         master_secret::MasterSecret,
-        pres_request::PresentationRequest,
+        pres_request::{PresentationRequest, PresentationRequestVersion},
         presentation::Presentation,
+        profile::{SerializationProfile, WithProfile},
+        restrictions::RestrictionBuilder,
         rev_reg::{RevocationRegistry, RevocationRegistryDelta},
         rev_reg_def::{
             IssuanceType, RegistryType, RevocationRegistryDefinition,
             RevocationRegistryDefinitionPrivate,
         },
+        rev_status_list::RevocationStatusList,
         schema::{AttributeNames, Schema},
     },
     CredentialDefinitionId, RevocationRegistryId, SchemaId,
 };
 pub use indy_utils::did::DidValue;
-use indy_utils::{invalid, Validatable, ValidationError};
+use indy_utils::{invalid, Qualifiable, Validatable, ValidationError};
 
 use crate::error::Error;
-use crate::services::helpers::encode_credential_attribute;
+use crate::services::encoders::encode_attribute;
 use crate::ursa::cl::{RevocationRegistry as CryptoRevocationRegistry, Witness};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CredentialDefinitionConfig {
     pub support_revocation: bool,
 }
@@ -40,27 +48,157 @@ impl CredentialDefinitionConfig {
     }
 }
 
-impl Default for CredentialDefinitionConfig {
-    fn default() -> Self {
+impl Validatable for CredentialDefinitionConfig {}
+
+/// Additional checks applied by the verifier independently of the
+/// presentation request sent to the prover.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct VerificationPolicy {
+    /// Reject any credential issued under a revocable credential definition
+    /// which is presented without a non-revocation proof, even when the
+    /// request did not specify a `non_revoked` interval.
+    #[serde(default)]
+    pub require_non_revocation: bool,
+    /// The issuers trusted for each referent of the presentation request.
+    /// Referents without an entry accept any issuer.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub accepted_issuers: HashMap<String, AcceptedIssuers>,
+    /// The maximum age in seconds of the timestamp of a non-revocation
+    /// proof, relative to the configured clock
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_revocation_state_age: Option<u64>,
+    /// The number of seconds by which the timestamp of a non-revocation
+    /// proof may be ahead of the configured clock. When unset, timestamps
+    /// in the future are not rejected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_clock_skew: Option<u64>,
+}
+
+impl VerificationPolicy {
+    pub fn new(require_non_revocation: bool) -> Self {
         Self {
-            support_revocation: false,
+            require_non_revocation,
+            ..Default::default()
         }
     }
+
+    pub fn add_accepted_issuers(&mut self, referent: impl Into<String>, accepted: AcceptedIssuers) {
+        self.accepted_issuers.insert(referent.into(), accepted);
+    }
 }
 
-impl Validatable for CredentialDefinitionConfig {}
+impl Validatable for VerificationPolicy {}
+
+/// An allowlist of credential definitions and issuer DIDs. A credential is
+/// accepted when either its credential definition or its issuer is listed.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct AcceptedIssuers {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cred_def_ids: Vec<CredentialDefinitionId>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issuer_dids: Vec<DidValue>,
+}
+
+impl AcceptedIssuers {
+    pub fn accepts(&self, cred_def_id: &CredentialDefinitionId) -> bool {
+        let cred_def_id = cred_def_id.to_unqualified();
+        if self
+            .cred_def_ids
+            .iter()
+            .any(|id| id.to_unqualified() == cred_def_id)
+        {
+            return true;
+        }
+        match cred_def_id.issuer_did() {
+            Some(did) => self
+                .issuer_dids
+                .iter()
+                .any(|accepted| accepted.to_unqualified() == did),
+            None => false,
+        }
+    }
+}
+
+/// A referent of a presentation which was satisfied by a credential from an
+/// issuer outside of the accepted issuers for that referent. The identifiers
+/// are absent when the referent was self-attested.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IssuerMismatch {
+    pub referent: String,
+    pub cred_def_id: Option<CredentialDefinitionId>,
+    pub issuer_did: Option<DidValue>,
+}
+
+/// A shared flag used to abort a long-running operation such as credential
+/// definition or revocation registry creation. Clones of a token observe the
+/// same cancellation state. Operations check the token between steps of their
+/// work, so cancellation takes effect at the next check rather than
+/// immediately.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation of any operation observing this token
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
 
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Return a `Cancelled` error if cancellation has been requested
+    pub fn check(&self) -> crate::error::Result<()> {
+        if self.is_cancelled() {
+            Err(err_msg!(Cancelled))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl serde::Serialize for CancellationToken {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("CancellationToken", 1)?;
+        state.serialize_field("cancelled", &self.is_cancelled())?;
+        state.end()
+    }
+}
+
+/// The attribute values of a credential to be issued. Raw values are
+/// encoded with the encoders registered for the schema of the credential,
+/// or with the standard encoding.
 #[derive(Debug, Default)]
-pub struct MakeCredentialValues(pub(crate) CredentialValues);
+pub struct MakeCredentialValues {
+    values: CredentialValues,
+    schema_id: Option<SchemaId>,
+}
 
 impl MakeCredentialValues {
+    /// Collect the values of a credential issued under a schema, applying
+    /// the encoders registered for the schema
+    pub fn for_schema(schema_id: &SchemaId) -> Self {
+        Self {
+            values: CredentialValues::default(),
+            schema_id: Some(schema_id.clone()),
+        }
+    }
+
     pub fn add_encoded(
         &mut self,
         name: impl Into<String>,
         raw: impl Into<String>,
         encoded: String,
     ) {
-        self.0 .0.insert(
+        self.values.0.insert(
             name.into(),
             AttributeValues {
                 raw: raw.into(),
@@ -74,18 +212,16 @@ impl MakeCredentialValues {
         name: impl Into<String>,
         raw: impl Into<String>,
     ) -> Result<(), Error> {
-        let raw = raw.into();
-        let encoded = encode_credential_attribute(&raw)?;
-        self.0
-             .0
-            .insert(name.into(), AttributeValues { raw, encoded });
+        let (name, raw) = (name.into(), raw.into());
+        let encoded = encode_attribute(self.schema_id.as_ref(), &name, &raw)?;
+        self.values.0.insert(name, AttributeValues { raw, encoded });
         Ok(())
     }
 }
 
-impl Into<CredentialValues> for MakeCredentialValues {
-    fn into(self) -> CredentialValues {
-        self.0
+impl From<MakeCredentialValues> for CredentialValues {
+    fn from(val: MakeCredentialValues) -> Self {
+        val.values
     }
 }
 
@@ -109,14 +245,32 @@ impl<'p> PresentCredentials<'p> {
             cred,
             timestamp,
             rev_state,
+            rev_states: vec![],
             requested_attributes: HashSet::new(),
             requested_predicates: HashSet::new(),
+            narrow_range_disclosures: HashSet::new(),
         });
         AddCredential {
             present: &mut self.0[idx],
         }
     }
 
+    /// Add a credential to be presented with the most recent of a set of
+    /// cached revocation states. The state is selected when the presentation
+    /// is created, according to the `non_revoked` intervals of the requested
+    /// referents, where an open-ended interval extends to the current time of
+    /// the configured clock. No non-revocation proof is produced when the
+    /// referents do not require one.
+    pub fn add_credential_with_latest_state(
+        &mut self,
+        cred: &'p Credential,
+        rev_states: impl IntoIterator<Item = &'p CredentialRevocationState>,
+    ) -> AddCredential<'_, 'p> {
+        let add = self.add_credential(cred, None, None);
+        add.present.rev_states = rev_states.into_iter().collect();
+        add
+    }
+
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
@@ -160,8 +314,12 @@ pub(crate) struct PresentCredential<'p> {
     pub cred: &'p Credential,
     pub timestamp: Option<u64>,
     pub rev_state: Option<&'p CredentialRevocationState>,
+    /// Candidate revocation states, when the state is selected automatically
+    pub rev_states: Vec<&'p CredentialRevocationState>,
     pub requested_attributes: HashSet<(String, bool)>,
     pub requested_predicates: HashSet<String>,
+    /// Range disclosure referents which may be presented with a narrow run
+    pub narrow_range_disclosures: HashSet<String>,
 }
 
 impl PresentCredential<'_> {
@@ -183,12 +341,27 @@ impl<'a, 'p> AddCredential<'a, 'p> {
             .insert((referent.into(), revealed));
     }
 
+    /// Present a predicate referent, or a range disclosure referent, which is
+    /// presented in the same way
     pub fn add_requested_predicate(&mut self, referent: impl Into<String>) {
         self.present.requested_predicates.insert(referent.into());
     }
+
+    /// Present a range disclosure referent even when the run of the set
+    /// containing the value has fewer than `RangeDisclosureInfo::MIN_RUN_WIDTH`
+    /// members. The run is disclosed to the verifier, so this may disclose
+    /// the value of the attribute.
+    pub fn add_narrow_range_disclosure(&mut self, referent: impl Into<String>) {
+        let referent = referent.into();
+        self.present
+            .narrow_range_disclosures
+            .insert(referent.clone());
+        self.present.requested_predicates.insert(referent);
+    }
 }
 
 #[derive(Debug)]
+#[allow(dead_code)]
 pub(crate) struct RequestedAttribute<'a> {
     pub cred_id: String,
     pub timestamp: Option<u64>,
@@ -197,6 +370,7 @@ pub(crate) struct RequestedAttribute<'a> {
 }
 
 #[derive(Debug)]
+#[allow(dead_code)]
 pub(crate) struct RequestedPredicate<'a> {
     pub cred_id: String,
     pub timestamp: Option<u64>,
@@ -204,6 +378,7 @@ pub(crate) struct RequestedPredicate<'a> {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[allow(dead_code)]
 pub(crate) struct ProvingCredentialKey {
     pub cred_id: String,
     pub timestamp: Option<u64>,
@@ -249,3 +424,32 @@ impl<'a> std::fmt::Debug for CredentialRevocationConfig<'a> {
         )
     }
 }
+
+const fn assert_send_sync<T: Send + Sync>() {}
+
+// Objects are immutable once created, so all of the service types may be
+// shared between threads and used concurrently through shared references.
+const _: () = {
+    assert_send_sync::<Schema>();
+    assert_send_sync::<CredentialDefinition>();
+    assert_send_sync::<CredentialDefinitionPrivate>();
+    assert_send_sync::<CredentialKeyCorrectnessProof>();
+    assert_send_sync::<CredentialOffer>();
+    assert_send_sync::<CredentialRequest>();
+    assert_send_sync::<CredentialRequestMetadata>();
+    assert_send_sync::<Credential>();
+    assert_send_sync::<CredentialValues>();
+    assert_send_sync::<MasterSecret>();
+    assert_send_sync::<PresentationRequest>();
+    assert_send_sync::<Presentation>();
+    assert_send_sync::<RevocationRegistry>();
+    assert_send_sync::<RevocationRegistryDefinition>();
+    assert_send_sync::<RevocationRegistryDefinitionPrivate>();
+    assert_send_sync::<RevocationRegistryDelta>();
+    assert_send_sync::<CredentialRevocationState>();
+    assert_send_sync::<CredentialRevocationConfig<'static>>();
+    assert_send_sync::<PresentCredentials<'static>>();
+    assert_send_sync::<VerificationPolicy>();
+    assert_send_sync::<CancellationToken>();
+    assert_send_sync::<TailsReader>();
+};
@@ -0,0 +1,270 @@
+//! Semantic validation of objects, for agents checking objects fetched from
+//! an untrusted ledger or peer. Besides the structural checks performed by
+//! each `Validatable` implementation, the related fields of an object are
+//! checked for consistency. All problems found are reported, rather than
+//! only the first.
+
+use std::collections::BTreeSet;
+
+use indy_utils::{base58, Validatable, ValidationError};
+
+use super::helpers::attr_common_view;
+use super::prover::check_credential_encoding;
+use super::types::{Credential, CredentialDefinition, RevocationRegistryDefinition, Schema};
+use crate::error::Result;
+
+/// A problem found when validating an object
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationProblem {
+    /// The path of the field at fault, when the problem does not concern
+    /// the object as a whole
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+struct Problems(Vec<ValidationProblem>);
+
+impl Problems {
+    fn add(&mut self, field: Option<&str>, message: impl Into<String>) {
+        self.0.push(ValidationProblem {
+            field: field.map(str::to_string),
+            message: message.into(),
+        });
+    }
+
+    fn check(&mut self, result: std::result::Result<(), ValidationError>) {
+        if let Err(err) = result {
+            self.add(None, err.to_string());
+        }
+    }
+}
+
+/// Run the structural checks of an object without further semantic checks
+pub fn validate_object<T: Validatable>(obj: &T) -> Vec<ValidationProblem> {
+    let mut problems = Problems::default();
+    problems.check(obj.validate());
+    problems.0
+}
+
+/// Check a schema, including that no two attribute names are equivalent
+/// once normalized
+pub fn validate_schema(schema: &Schema) -> Vec<ValidationProblem> {
+    let mut problems = Problems::default();
+    problems.check(schema.validate());
+    let Schema::SchemaV1(schema) = schema;
+    let mut seen = BTreeSet::new();
+    for name in schema.attr_names.0.iter() {
+        let normalized = attr_common_view(name);
+        if normalized.is_empty() {
+            problems.add(Some("attrNames"), "Empty attribute name");
+        } else if !seen.insert(normalized) {
+            problems.add(
+                Some("attrNames"),
+                format!("Duplicate attribute name after normalization: {}", name),
+            );
+        }
+    }
+    problems.0
+}
+
+/// Check a credential definition. When the schema is given, the attributes
+/// of the primary public key are compared to those of the schema.
+pub fn validate_credential_definition(
+    cred_def: &CredentialDefinition,
+    schema: Option<&Schema>,
+) -> Result<Vec<ValidationProblem>> {
+    let mut problems = Problems::default();
+    problems.check(cred_def.validate());
+    let CredentialDefinition::CredentialDefinitionV1(cred_def) = cred_def;
+
+    if let Some((_, _, _, _, tag)) = cred_def.id.parts() {
+        if !tag.is_empty() && tag != cred_def.tag {
+            problems.add(
+                Some("tag"),
+                format!(
+                    "Inconsistent credential definition ID and tag: {} and {}",
+                    cred_def.id, cred_def.tag
+                ),
+            );
+        }
+    }
+    if let Err(err) = cred_def.get_public_key() {
+        problems.add(
+            Some("value"),
+            format!("Invalid credential public key: {}", err),
+        );
+    }
+
+    let primary = serde_json::to_value(&cred_def.value.primary)?;
+    let mut attrs: BTreeSet<String> = primary["r"]
+        .as_object()
+        .map(|r| r.keys().cloned().collect())
+        .unwrap_or_default();
+    if !attrs.remove("master_secret") {
+        problems.add(
+            Some("value.primary.r"),
+            "The primary public key has no master secret attribute",
+        );
+    }
+
+    if let Some(Schema::SchemaV1(schema)) = schema {
+        let seq_no = schema.seq_no.map(|seq_no| seq_no.to_string());
+        if cred_def.schema_id != schema.id && seq_no.as_ref() != Some(&cred_def.schema_id.0) {
+            problems.add(
+                Some("schemaId"),
+                format!(
+                    "The credential definition does not reference the schema {}",
+                    schema.id
+                ),
+            );
+        }
+        let expected: BTreeSet<String> = schema
+            .attr_names
+            .0
+            .iter()
+            .map(|name| attr_common_view(name))
+            .collect();
+        for missing in expected.difference(&attrs) {
+            problems.add(
+                Some("value.primary.r"),
+                format!("Missing key for schema attribute: {}", missing),
+            );
+        }
+        for extra in attrs.difference(&expected) {
+            problems.add(
+                Some("value.primary.r"),
+                format!("Key for attribute not in schema: {}", extra),
+            );
+        }
+    }
+
+    Ok(problems.0)
+}
+
+/// Check a revocation registry definition, including the format of the
+/// tails hash and the consistency of the registry ID with the definition
+pub fn validate_revocation_registry_definition(
+    rev_reg_def: &RevocationRegistryDefinition,
+) -> Vec<ValidationProblem> {
+    let mut problems = Problems::default();
+    problems.check(rev_reg_def.validate());
+    let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) = rev_reg_def;
+
+    if let Some((_, cred_def_id, _, tag)) = rev_reg_def.id.parts() {
+        if cred_def_id != rev_reg_def.cred_def_id {
+            problems.add(
+                Some("credDefId"),
+                format!(
+                    "Inconsistent revocation registry ID and credential definition ID: {} and {}",
+                    rev_reg_def.id, rev_reg_def.cred_def_id
+                ),
+            );
+        }
+        if tag != rev_reg_def.tag {
+            problems.add(
+                Some("tag"),
+                format!(
+                    "Inconsistent revocation registry ID and tag: {} and {}",
+                    rev_reg_def.id, rev_reg_def.tag
+                ),
+            );
+        }
+    }
+    if rev_reg_def.value.max_cred_num == 0 {
+        problems.add(
+            Some("value.maxCredNum"),
+            "The registry must hold at least one credential",
+        );
+    }
+    match base58::decode(&rev_reg_def.value.tails_hash) {
+        Ok(hash) if hash.len() == 32 => (),
+        Ok(_) => problems.add(
+            Some("value.tailsHash"),
+            "The tails hash is not a SHA-256 digest",
+        ),
+        Err(_) => problems.add(Some("value.tailsHash"), "The tails hash is not base58"),
+    }
+    if rev_reg_def.value.tails_location.is_empty() {
+        problems.add(Some("value.tailsLocation"), "Empty tails location");
+    }
+    problems.0
+}
+
+/// Check a credential, including that each encoded value matches its raw
+/// value and that a revocable credential has a witness
+pub fn validate_credential(credential: &Credential) -> Result<Vec<ValidationProblem>> {
+    let mut problems = Problems::default();
+    problems.check(credential.validate());
+    for name in check_credential_encoding(credential)? {
+        problems.add(
+            Some(&format!("values.{}", name)),
+            "The encoded value does not match the raw value",
+        );
+    }
+    match (&credential.rev_reg_id, &credential.witness) {
+        (Some(_), None) => problems.add(
+            Some("witness"),
+            "The credential is revocable but has no witness",
+        ),
+        (None, Some(_)) => problems.add(
+            Some("rev_reg_id"),
+            "The credential has a witness but no revocation registry",
+        ),
+        _ => (),
+    }
+    Ok(problems.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{issuer, test_utils::CredDefFixture, types::*};
+
+    #[test]
+    fn validate_cred_def_against_schema() {
+        let did = DidValue::new("NcYxiDXkpYi6ov5FcYDi1e", None);
+        let schema = issuer::create_schema(
+            &did,
+            "gvt",
+            "1.0",
+            vec!["name".to_string(), "age".to_string()].into(),
+            None,
+        )
+        .unwrap();
+        assert!(validate_schema(&schema).is_empty());
+        let fixture = CredDefFixture::new(did.clone(), schema, false).unwrap();
+        let cred_def = &fixture.cred_def;
+        assert!(
+            validate_credential_definition(cred_def, Some(&fixture.schema))
+                .unwrap()
+                .is_empty()
+        );
+
+        let other = issuer::create_schema(
+            &did,
+            "gvt",
+            "2.0",
+            vec!["name".to_string(), "height".to_string()].into(),
+            None,
+        )
+        .unwrap();
+        let problems = validate_credential_definition(cred_def, Some(&other)).unwrap();
+        let messages: Vec<&str> = problems.iter().map(|p| p.message.as_str()).collect();
+        assert_eq!(problems.len(), 3, "{:?}", messages);
+        assert_eq!(problems[0].field.as_deref(), Some("schemaId"));
+        assert_eq!(messages[1], "Missing key for schema attribute: height");
+        assert_eq!(messages[2], "Key for attribute not in schema: age");
+
+        let duplicate = issuer::create_schema(
+            &did,
+            "gvt",
+            "1.0",
+            vec!["First Name".to_string(), "firstname".to_string()].into(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(validate_schema(&duplicate).len(), 1);
+    }
+}
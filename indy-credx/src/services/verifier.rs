@@ -1,16 +1,27 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{hash_map::Entry, BTreeSet, HashMap, HashSet};
 
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde::de::IgnoredAny;
 
+use super::escrow::{check_escrowed_attributes, escrow_nonce};
 use super::helpers::*;
+use super::issuer::merge_revocation_registry_deltas;
+use super::pseudonym::{check_pseudonym, pseudonym_nonce};
+use super::resolver::ObjectResolver;
 use super::types::*;
+use crate::config::{get_config, VerificationLimits};
 use crate::error::Result;
 use crate::ursa::cl::{verifier::Verifier as CryptoVerifier, CredentialPublicKey};
 use indy_data_types::anoncreds::{
+    credential::normalize_encoded_value,
     nonce::Nonce,
-    pres_request::{AttributeInfo, NonRevocedInterval, PredicateInfo, PresentationRequestPayload},
+    pres_request::{
+        AttributeInfo, NonRevocedInterval, PredicateInfo, PredicateTypes,
+        PresentationRequestPayload,
+    },
     presentation::{Identifier, RequestedProof, RevealedAttributeInfo},
+    restrictions::RESTRICTION_TAGS,
 };
 use indy_utils::wql::Query;
 
@@ -35,16 +46,42 @@ pub fn verify_presentation(
     rev_reg_defs: Option<&HashMap<RevocationRegistryId, &RevocationRegistryDefinition>>,
     rev_regs: Option<&HashMap<RevocationRegistryId, HashMap<u64, &RevocationRegistry>>>,
 ) -> Result<bool> {
-    trace!("verify >>> presentation: {:?}, pres_req: {:?}, schemas: {:?}, cred_defs: {:?}, rev_reg_defs: {:?} rev_regs: {:?}",
-    presentation, pres_req, schemas, cred_defs, rev_reg_defs, rev_regs);
+    verify_presentation_with_policy(
+        presentation,
+        pres_req,
+        schemas,
+        cred_defs,
+        rev_reg_defs,
+        rev_regs,
+        &VerificationPolicy::default(),
+    )
+}
+
+/// Verify a presentation, additionally enforcing the checks in `policy`
+pub fn verify_presentation_with_policy(
+    presentation: &Presentation,
+    pres_req: &PresentationRequest,
+    schemas: &HashMap<SchemaId, &Schema>,
+    cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+    rev_reg_defs: Option<&HashMap<RevocationRegistryId, &RevocationRegistryDefinition>>,
+    rev_regs: Option<&HashMap<RevocationRegistryId, HashMap<u64, &RevocationRegistry>>>,
+    policy: &VerificationPolicy,
+) -> Result<bool> {
+    time_operation!("verify_presentation");
+    trace!("verify >>> presentation: {:?}, pres_req: {:?}, schemas: {:?}, cred_defs: {:?}, rev_reg_defs: {:?} rev_regs: {:?}, policy: {:?}",
+    presentation, pres_req, schemas, cred_defs, rev_reg_defs, rev_regs, policy);
+
+    check_presentation_limits(presentation, &get_config().verification_limits)?;
 
     let pres_req = pres_req.value();
     let received_revealed_attrs: HashMap<String, Identifier> =
-        received_revealed_attrs(&presentation)?;
+        received_revealed_attrs(presentation)?;
     let received_unrevealed_attrs: HashMap<String, Identifier> =
-        received_unrevealed_attrs(&presentation)?;
-    let received_predicates: HashMap<String, Identifier> = received_predicates(&presentation)?;
-    let received_self_attested_attrs: HashSet<String> = received_self_attested_attrs(&presentation);
+        received_unrevealed_attrs(presentation)?;
+    let received_predicates: HashMap<String, Identifier> = received_predicates(presentation)?;
+    let received_self_attested_attrs: HashSet<String> = received_self_attested_attrs(presentation);
+    let received_range_disclosures: HashMap<String, Identifier> =
+        received_range_disclosures(presentation)?;
 
     compare_attr_from_proof_and_request(
         pres_req,
@@ -54,10 +91,28 @@ pub fn verify_presentation(
         &received_predicates,
     )?;
 
-    verify_revealed_attribute_values(&pres_req, &presentation)?;
+    verify_revealed_attribute_values(pres_req, presentation)?;
+    verify_revealed_predicate_values(pres_req, presentation)?;
+    check_same_credential(pres_req, &presentation.requested_proof)?;
+    check_equal_attributes(pres_req, &presentation.requested_proof, &presentation.proof)?;
+    check_escrowed_attributes(pres_req, &presentation.requested_proof, &presentation.proof)?;
+    check_pseudonym(pres_req, &presentation.requested_proof, &presentation.proof)?;
+
+    let mismatches = check_accepted_issuers(presentation, policy)?;
+    if !mismatches.is_empty() {
+        return Err(err_msg!(
+            ProofRejected,
+            "Credential issuer not accepted for referents: {}",
+            mismatches
+                .iter()
+                .map(|m| m.referent.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
 
     verify_requested_restrictions(
-        &pres_req,
+        pres_req,
         &presentation.requested_proof,
         &received_revealed_attrs,
         &received_unrevealed_attrs,
@@ -73,27 +128,44 @@ pub fn verify_presentation(
         &received_predicates,
     )?;
 
+    verify_range_disclosures(
+        pres_req,
+        &presentation.requested_proof,
+        &received_range_disclosures,
+    )?;
+
     let mut proof_verifier = CryptoVerifier::new_proof_verifier()?;
     let non_credential_schema = build_non_credential_schema()?;
 
     for sub_proof_index in 0..presentation.identifiers.len() {
         let identifier = presentation.identifiers[sub_proof_index].clone();
 
-        let schema = match schemas
+        let Schema::SchemaV1(schema) = schemas
             .get(&identifier.schema_id)
-            .ok_or_else(|| err_msg!("Schema not provided for ID: {:?}", identifier.schema_id))?
-        {
-            Schema::SchemaV1(schema) => schema,
-        };
+            .ok_or_else(|| err_msg!("Schema not provided for ID: {:?}", identifier.schema_id))?;
 
-        let cred_def = match cred_defs.get(&identifier.cred_def_id).ok_or_else(|| {
-            err_msg!(
-                "Credential Definition not provided for ID: {:?}",
+        let CredentialDefinition::CredentialDefinitionV1(cred_def) =
+            cred_defs.get(&identifier.cred_def_id).ok_or_else(|| {
+                err_msg!(
+                    "Credential Definition not provided for ID: {:?}",
+                    identifier.cred_def_id
+                )
+            })?;
+
+        if policy.require_non_revocation
+            && cred_def.value.revocation.is_some()
+            && identifier.timestamp.is_none()
+        {
+            return Err(err_msg!(
+                ProofRejected,
+                "Non-revocation proof required for credential definition: {}",
                 identifier.cred_def_id
-            )
-        })? {
-            CredentialDefinition::CredentialDefinitionV1(cred_def) => cred_def,
-        };
+            ));
+        }
+
+        if let Some(timestamp) = identifier.timestamp {
+            check_timestamp_freshness(timestamp, policy, get_config().now())?;
+        }
 
         let (rev_reg_def, rev_reg) = if let Some(timestamp) = identifier.timestamp {
             let rev_reg_id = identifier.rev_reg_id.clone().ok_or_else(|| {
@@ -144,11 +216,16 @@ pub fn verify_presentation(
             &presentation.requested_proof,
             pres_req,
         )?;
-        let predicates_for_credential = get_predicates_for_credential(
+        let mut predicates_for_credential = get_predicates_for_credential(
             sub_proof_index,
             &presentation.requested_proof,
             pres_req,
         )?;
+        predicates_for_credential.extend(get_range_disclosure_predicates_for_credential(
+            sub_proof_index,
+            &presentation.requested_proof,
+            pres_req,
+        ));
 
         let credential_schema = build_credential_schema(&schema.attr_names.0)?;
         let sub_pres_request =
@@ -178,17 +255,329 @@ pub fn verify_presentation(
         )?;
     }
 
-    let valid = proof_verifier.verify(&presentation.proof, pres_req.nonce.as_native())?;
+    // the nonce of the proof also binds the escrows and the pseudonym
+    let requested_proof = &presentation.requested_proof;
+    let nonce = pseudonym_nonce(
+        escrow_nonce(pres_req, requested_proof.escrowed_attrs.iter())?,
+        requested_proof.pseudonym.as_ref(),
+    )?;
+    let valid = proof_verifier.verify(&presentation.proof, nonce.as_native())?;
 
     trace!("verify <<< valid: {:?}", valid);
 
     Ok(valid)
 }
 
+/// The outline of a presentation read to enforce the verification limits,
+/// without parsing the proof itself
+#[derive(Deserialize)]
+struct PresentationOutline {
+    #[serde(default)]
+    proof: ProofOutline,
+    #[serde(default, alias = "requestedProof")]
+    requested_proof: RequestedProofOutline,
+    #[serde(default)]
+    identifiers: Vec<IgnoredAny>,
+}
+
+#[derive(Default, Deserialize)]
+struct ProofOutline {
+    #[serde(default)]
+    proofs: Vec<IgnoredAny>,
+}
+
+#[derive(Default, Deserialize)]
+struct RequestedProofOutline {
+    #[serde(default, alias = "revealedAttrs")]
+    revealed_attrs: HashMap<String, IgnoredAny>,
+    #[serde(default, alias = "revealedAttrGroups")]
+    revealed_attr_groups: HashMap<String, IgnoredAny>,
+    #[serde(default, alias = "selfAttestedAttrs")]
+    self_attested_attrs: HashMap<String, IgnoredAny>,
+    #[serde(default, alias = "unrevealedAttrs")]
+    unrevealed_attrs: HashMap<String, IgnoredAny>,
+    #[serde(default)]
+    predicates: HashMap<String, IgnoredAny>,
+    #[serde(default, alias = "rangeDisclosures")]
+    range_disclosures: HashMap<String, IgnoredAny>,
+    #[serde(default, alias = "revealedPredicates")]
+    revealed_predicates: HashMap<String, IgnoredAny>,
+    #[serde(default, alias = "escrowedAttrs")]
+    escrowed_attrs: HashMap<String, IgnoredAny>,
+}
+
+impl RequestedProofOutline {
+    /// The number of referents in every map of the requested proof
+    fn referent_count(&self) -> usize {
+        self.revealed_attrs.len()
+            + self.revealed_attr_groups.len()
+            + self.self_attested_attrs.len()
+            + self.unrevealed_attrs.len()
+            + self.predicates.len()
+            + self.range_disclosures.len()
+            + self.revealed_predicates.len()
+            + self.escrowed_attrs.len()
+    }
+}
+
+fn check_limit(what: &str, count: usize, max: Option<usize>) -> Result<()> {
+    match max {
+        Some(max) if count > max => Err(err_msg!(
+            LimitExceeded,
+            "Presentation has {} {}, exceeding the limit of {}",
+            count,
+            what,
+            max
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Check the referent and credential counts of a presentation against the
+/// verification limits
+fn check_outline_limits(
+    requested_proof: &RequestedProofOutline,
+    credentials: usize,
+    limits: &VerificationLimits,
+) -> Result<()> {
+    check_limit(
+        "referents",
+        requested_proof.referent_count(),
+        limits.max_referents,
+    )?;
+    check_limit("credentials", credentials, limits.max_credentials)
+}
+
+/// Check a presentation against the configured verification limits
+pub fn check_presentation_limits(
+    presentation: &Presentation,
+    limits: &VerificationLimits,
+) -> Result<()> {
+    if limits.max_referents.is_none() && limits.max_credentials.is_none() {
+        return Ok(());
+    }
+    // the referents are counted from the outline of the requested proof, as
+    // for a presentation which has not been parsed
+    let requested_proof = serde_json::to_value(&presentation.requested_proof)
+        .and_then(serde_json::from_value)
+        .map_err(err_map!(Unexpected, "Error reading requested proof"))?;
+    check_outline_limits(&requested_proof, presentation.identifiers.len(), limits)
+}
+
+/// Load a presentation from JSON, first checking its size and outline
+/// against `limits` so that oversized presentations are rejected before the
+/// proof is parsed
+pub fn presentation_from_json(json: &[u8], limits: &VerificationLimits) -> Result<Presentation> {
+    check_limit("bytes", json.len(), limits.max_json_size)?;
+    if limits.max_referents.is_some() || limits.max_credentials.is_some() {
+        let outline: PresentationOutline =
+            serde_json::from_slice(json).map_err(err_map!("Invalid presentation"))?;
+        check_outline_limits(
+            &outline.requested_proof,
+            outline.identifiers.len().max(outline.proof.proofs.len()),
+            limits,
+        )?;
+    }
+    Ok(serde_json::from_slice(json)?)
+}
+
+/// Verify a batch of presentations concurrently, returning the result for
+/// each presentation in the order of the inputs.
+///
+/// The service types are `Send + Sync`, so `verify_presentation` may also be
+/// called from any number of threads sharing the same ledger objects. This
+/// helper divides the batch between at most the configured thread pool size
+/// of worker threads, using the rayon thread pool when the `parallel` feature
+/// is enabled.
+#[allow(clippy::type_complexity)]
+pub fn verify_presentations(
+    presentations: &[(&Presentation, &PresentationRequest)],
+    schemas: &HashMap<SchemaId, &Schema>,
+    cred_defs: &HashMap<CredentialDefinitionId, &CredentialDefinition>,
+    rev_reg_defs: Option<&HashMap<RevocationRegistryId, &RevocationRegistryDefinition>>,
+    rev_regs: Option<&HashMap<RevocationRegistryId, HashMap<u64, &RevocationRegistry>>>,
+    policy: &VerificationPolicy,
+) -> Vec<Result<bool>> {
+    super::parallel::try_map_concurrent(presentations, |(presentation, pres_req)| {
+        verify_presentation_with_policy(
+            presentation,
+            pres_req,
+            schemas,
+            cred_defs,
+            rev_reg_defs,
+            rev_regs,
+            policy,
+        )
+    })
+}
+
+/// Verify a presentation, resolving the schemas, credential definitions and
+/// revocation registry states referenced by its identifiers. Each object is
+/// fetched once, and the registry state used for each timestamp is rebuilt
+/// from the delta since the creation of the registry. Wrap the resolver in a
+/// [`CachingResolver`](super::resolver::CachingResolver) to reuse the objects
+/// between verifications.
+pub async fn verify_presentation_with_resolver(
+    presentation: &Presentation,
+    pres_req: &PresentationRequest,
+    resolver: &dyn ObjectResolver,
+    policy: &VerificationPolicy,
+) -> Result<bool> {
+    ResolvedObjects::resolve(presentation, resolver)
+        .await?
+        .verify(presentation, pres_req, policy)
+}
+
+/// The published objects referenced by the identifiers of a presentation
+#[derive(Debug, Default)]
+pub(crate) struct ResolvedObjects {
+    schemas: HashMap<SchemaId, Schema>,
+    cred_defs: HashMap<CredentialDefinitionId, CredentialDefinition>,
+    rev_reg_defs: HashMap<RevocationRegistryId, RevocationRegistryDefinition>,
+    rev_regs: HashMap<RevocationRegistryId, HashMap<u64, RevocationRegistry>>,
+}
+
+impl ResolvedObjects {
+    pub async fn resolve(
+        presentation: &Presentation,
+        resolver: &dyn ObjectResolver,
+    ) -> Result<Self> {
+        let mut objects = Self::default();
+        for identifier in presentation.identifiers.iter() {
+            if !objects.schemas.contains_key(&identifier.schema_id) {
+                let schema = resolver.get_schema(&identifier.schema_id).await?;
+                objects.schemas.insert(identifier.schema_id.clone(), schema);
+            }
+            if !objects.cred_defs.contains_key(&identifier.cred_def_id) {
+                let cred_def = resolver.get_cred_def(&identifier.cred_def_id).await?;
+                objects
+                    .cred_defs
+                    .insert(identifier.cred_def_id.clone(), cred_def);
+            }
+            if let Some(rev_reg_id) = identifier.rev_reg_id.as_ref() {
+                if !objects.rev_reg_defs.contains_key(rev_reg_id) {
+                    let rev_reg_def = resolver.get_rev_reg_def(rev_reg_id).await?;
+                    objects.rev_reg_defs.insert(rev_reg_id.clone(), rev_reg_def);
+                }
+                if let Some(timestamp) = identifier.timestamp {
+                    let states = objects.rev_regs.entry(rev_reg_id.clone()).or_default();
+                    if let Entry::Vacant(state) = states.entry(timestamp) {
+                        let (delta, _) = resolver
+                            .get_rev_reg_delta(rev_reg_id, None, timestamp)
+                            .await?;
+                        state.insert(delta.to_registry());
+                    }
+                }
+            }
+        }
+        Ok(objects)
+    }
+
+    pub fn verify(
+        &self,
+        presentation: &Presentation,
+        pres_req: &PresentationRequest,
+        policy: &VerificationPolicy,
+    ) -> Result<bool> {
+        verify_presentation_with_policy(
+            presentation,
+            pres_req,
+            &self.schemas.iter().map(|(id, s)| (id.clone(), s)).collect(),
+            &self
+                .cred_defs
+                .iter()
+                .map(|(id, c)| (id.clone(), c))
+                .collect(),
+            Some(
+                &self
+                    .rev_reg_defs
+                    .iter()
+                    .map(|(id, r)| (id.clone(), r))
+                    .collect(),
+            ),
+            Some(
+                &self
+                    .rev_regs
+                    .iter()
+                    .map(|(id, states)| {
+                        (id.clone(), states.iter().map(|(ts, r)| (*ts, r)).collect())
+                    })
+                    .collect(),
+            ),
+            policy,
+        )
+    }
+}
+
 pub fn generate_nonce() -> Result<Nonce> {
     new_nonce()
 }
 
+/// Reconstruct the full revocation registry state at `timestamp` from a
+/// history of timestamped deltas, such as those fetched from the ledger for
+/// an `ISSUANCE_BY_DEFAULT` registry. Deltas are applied in timestamp order,
+/// and each incremental delta must follow on from the preceding state.
+pub fn revocation_registry_from_deltas(
+    deltas: &[(u64, &RevocationRegistryDelta)],
+    timestamp: u64,
+) -> Result<RevocationRegistry> {
+    let mut history = deltas
+        .iter()
+        .filter(|(delta_ts, _)| *delta_ts <= timestamp)
+        .collect::<Vec<_>>();
+    history.sort_by_key(|(delta_ts, _)| *delta_ts);
+
+    merge_delta_history(history.into_iter().map(|(_, delta)| *delta))?
+        .map(|delta| delta.to_registry())
+        .ok_or_else(|| {
+            err_msg!(
+                "No revocation registry delta found at or before timestamp {}",
+                timestamp
+            )
+        })
+}
+
+/// Merge a sequence of deltas in timestamp order into a single delta,
+/// restarting from any delta which describes the complete registry state
+pub(crate) fn merge_delta_history<'d>(
+    history: impl IntoIterator<Item = &'d RevocationRegistryDelta>,
+) -> Result<Option<RevocationRegistryDelta>> {
+    let mut merged: Option<RevocationRegistryDelta> = None;
+    for delta in history {
+        merged = Some(match merged {
+            Some(prev) if !is_absolute_delta(delta)? => {
+                merge_revocation_registry_deltas(&prev, delta)?
+            }
+            _ => delta.clone(),
+        });
+    }
+    Ok(merged)
+}
+
+// A delta without a previous accumulator value describes the complete
+// registry state rather than a change to an earlier state. The accumulator
+// fields are not exposed by the CL library, so inspect the serialized form.
+fn is_absolute_delta(delta: &RevocationRegistryDelta) -> Result<bool> {
+    let RevocationRegistryDelta::RevocationRegistryDeltaV1(delta) = delta;
+    let value = serde_json::to_value(&delta.value)?;
+    Ok(value.get("prevAccum").is_none())
+}
+
+/// Convert a full revocation registry state into a delta, relative to a
+/// previous registry state when provided
+pub fn revocation_registry_to_delta(
+    rev_reg: &RevocationRegistry,
+    prev_rev_reg: Option<&RevocationRegistry>,
+    issued: &BTreeSet<u32>,
+    revoked: &BTreeSet<u32>,
+) -> RevocationRegistryDelta {
+    rev_reg.delta_from(
+        prev_rev_reg,
+        &issued.iter().cloned().collect(),
+        &revoked.iter().cloned().collect(),
+    )
+}
+
 fn get_revealed_attributes_for_credential(
     sub_proof_index: usize,
     requested_proof: &RequestedProof,
@@ -200,7 +589,7 @@ fn get_revealed_attributes_for_credential(
     let mut revealed_attrs_for_credential = requested_proof
         .revealed_attrs
         .iter()
-        .filter(|&(attr_referent, ref revealed_attr_info)| {
+        .filter(|&(attr_referent, revealed_attr_info)| {
             sub_proof_index == revealed_attr_info.sub_proof_index as usize
                 && pres_req.requested_attributes.contains_key(attr_referent)
         })
@@ -211,7 +600,7 @@ fn get_revealed_attributes_for_credential(
         &mut requested_proof
             .revealed_attr_groups
             .iter()
-            .filter(|&(attr_referent, ref revealed_attr_info)| {
+            .filter(|&(attr_referent, revealed_attr_info)| {
                 sub_proof_index == revealed_attr_info.sub_proof_index as usize
                     && pres_req.requested_attributes.contains_key(attr_referent)
             })
@@ -219,6 +608,29 @@ fn get_revealed_attributes_for_credential(
             .collect::<Vec<AttributeInfo>>(),
     );
 
+    // attributes behind predicates in auditable presentations are also revealed
+    revealed_attrs_for_credential.extend(
+        requested_proof
+            .revealed_predicates
+            .iter()
+            .filter(|&(predicate_referent, revealed_attr_info)| {
+                sub_proof_index == revealed_attr_info.sub_proof_index as usize
+                    && pres_req
+                        .requested_predicates
+                        .contains_key(predicate_referent)
+            })
+            .map(|(predicate_referent, _)| AttributeInfo {
+                name: Some(
+                    pres_req.requested_predicates[predicate_referent]
+                        .name
+                        .clone(),
+                ),
+                names: None,
+                restrictions: None,
+                non_revoked: None,
+            }),
+    );
+
     trace!(
         "_get_revealed_attributes_for_credential <<< revealed_attrs_for_credential: {:?}",
         revealed_attrs_for_credential
@@ -243,6 +655,9 @@ fn get_predicates_for_credential(
                 && pres_req
                     .requested_predicates
                     .contains_key(predicate_referent)
+                && !requested_proof
+                    .revealed_predicates
+                    .contains_key(predicate_referent)
         })
         .map(|(predicate_referent, _)| pres_req.requested_predicates[predicate_referent].clone())
         .collect::<Vec<PredicateInfo>>();
@@ -255,6 +670,83 @@ fn get_predicates_for_credential(
     Ok(predicates_for_credential)
 }
 
+fn get_range_disclosure_predicates_for_credential(
+    sub_proof_index: usize,
+    requested_proof: &RequestedProof,
+    pres_req: &PresentationRequestPayload,
+) -> Vec<PredicateInfo> {
+    requested_proof
+        .range_disclosures
+        .iter()
+        .filter(|(_, disclosure)| sub_proof_index == disclosure.sub_proof_index as usize)
+        .filter_map(|(referent, disclosure)| {
+            pres_req
+                .requested_range_disclosures
+                .get(referent)
+                .map(|info| {
+                    range_disclosure_predicates(&info.name, disclosure.lower, disclosure.upper)
+                })
+        })
+        .flat_map(|predicates| predicates.to_vec())
+        .collect()
+}
+
+/// Check the range disclosures of a presentation against the request: each
+/// proven run must be a run of the requested set, and the credential proving
+/// it must satisfy the restrictions and non-revocation interval of the
+/// referent
+fn verify_range_disclosures(
+    pres_req: &PresentationRequestPayload,
+    requested_proof: &RequestedProof,
+    received_range_disclosures: &HashMap<String, Identifier>,
+) -> Result<()> {
+    let requested: HashSet<&String> = pres_req.requested_range_disclosures.keys().collect();
+    let received: HashSet<&String> = received_range_disclosures.keys().collect();
+    if requested != received {
+        return Err(err_msg!(
+            "Requested range disclosures {:?} do not correspond to received {:?}",
+            requested,
+            received
+        ));
+    }
+
+    for (referent, info) in pres_req.requested_range_disclosures.iter() {
+        let disclosure = &requested_proof.range_disclosures[referent];
+        if !info
+            .ranges()
+            .contains(&(disclosure.lower, disclosure.upper))
+        {
+            return Err(err_msg!(
+                ProofRejected,
+                "Proven range {}..={} is not a run of the requested set: {}",
+                disclosure.lower,
+                disclosure.upper,
+                referent
+            ));
+        }
+
+        if let Some(ref query) = info.restrictions {
+            check_pattern_tags(query)?;
+            let filter = gather_filter_info(referent, received_range_disclosures)?;
+            let mut attr_value_map = HashMap::new();
+            attr_value_map.insert(info.name.to_string(), None);
+            process_operator(&attr_value_map, query, &filter).map_err(err_map!(
+                "Requested restriction validation failed for \"{}\" range disclosure",
+                &info.name
+            ))?;
+        }
+
+        validate_timestamp(
+            received_range_disclosures,
+            referent,
+            &pres_req.non_revoked,
+            &info.non_revoked,
+        )?;
+    }
+
+    Ok(())
+}
+
 fn compare_attr_from_proof_and_request(
     pres_req: &PresentationRequestPayload,
     received_revealed_attrs: &HashMap<String, Identifier>,
@@ -269,7 +761,7 @@ fn compare_attr_from_proof_and_request(
         .chain(received_unrevealed_attrs)
         .map(|(r, _)| r.to_string())
         .collect::<HashSet<String>>()
-        .union(&received_self_attested_attrs)
+        .union(received_self_attested_attrs)
         .cloned()
         .collect();
 
@@ -308,14 +800,14 @@ fn compare_timestamps_from_proof_and_request(
         .iter()
         .map(|(referent, info)| {
             validate_timestamp(
-                &received_revealed_attrs,
+                received_revealed_attrs,
                 referent,
                 &pres_req.non_revoked,
                 &info.non_revoked,
             )
             .or_else(|_| {
                 validate_timestamp(
-                    &received_unrevealed_attrs,
+                    received_unrevealed_attrs,
                     referent,
                     &pres_req.non_revoked,
                     &info.non_revoked,
@@ -346,6 +838,29 @@ fn compare_timestamps_from_proof_and_request(
     Ok(())
 }
 
+fn check_timestamp_freshness(timestamp: u64, policy: &VerificationPolicy, now: u64) -> Result<()> {
+    if let Some(max_age) = policy.max_revocation_state_age {
+        if timestamp < now.saturating_sub(max_age) {
+            return Err(err_msg!(
+                ProofRejected,
+                "Non-revocation proof timestamp {} is older than the maximum age of {} seconds",
+                timestamp,
+                max_age
+            ));
+        }
+    }
+    if let Some(skew) = policy.max_clock_skew {
+        if timestamp > now.saturating_add(skew) {
+            return Err(err_msg!(
+                ProofRejected,
+                "Non-revocation proof timestamp {} is in the future",
+                timestamp
+            ));
+        }
+    }
+    Ok(())
+}
+
 fn validate_timestamp(
     received_: &HashMap<String, Identifier>,
     referent: &str,
@@ -367,6 +882,50 @@ fn validate_timestamp(
     Ok(())
 }
 
+/// Compare the credential definition used for each referent of a presentation
+/// against the accepted issuers listed for that referent in `policy`
+pub fn check_accepted_issuers(
+    presentation: &Presentation,
+    policy: &VerificationPolicy,
+) -> Result<Vec<IssuerMismatch>> {
+    if policy.accepted_issuers.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut identifiers = received_revealed_attrs(presentation)?;
+    identifiers.extend(received_unrevealed_attrs(presentation)?);
+    identifiers.extend(received_predicates(presentation)?);
+    identifiers.extend(received_range_disclosures(presentation)?);
+    for (referent, info) in presentation.requested_proof.revealed_predicates.iter() {
+        identifiers.insert(
+            referent.to_string(),
+            get_proof_identifier(presentation, info.sub_proof_index)?,
+        );
+    }
+    let self_attested = received_self_attested_attrs(presentation);
+
+    let mut mismatches = vec![];
+    for (referent, accepted) in policy.accepted_issuers.iter() {
+        if let Some(identifier) = identifiers.get(referent) {
+            if !accepted.accepts(&identifier.cred_def_id) {
+                mismatches.push(IssuerMismatch {
+                    referent: referent.clone(),
+                    cred_def_id: Some(identifier.cred_def_id.clone()),
+                    issuer_did: identifier.cred_def_id.issuer_did(),
+                });
+            }
+        } else if self_attested.contains(referent) {
+            mismatches.push(IssuerMismatch {
+                referent: referent.clone(),
+                cred_def_id: None,
+                issuer_did: None,
+            });
+        }
+    }
+    mismatches.sort_by(|a, b| a.referent.cmp(&b.referent));
+    Ok(mismatches)
+}
+
 fn received_revealed_attrs(proof: &Presentation) -> Result<HashMap<String, Identifier>> {
     let mut revealed_identifiers: HashMap<String, Identifier> = HashMap::new();
     for (referent, info) in proof.requested_proof.revealed_attrs.iter() {
@@ -406,6 +965,17 @@ fn received_predicates(proof: &Presentation) -> Result<HashMap<String, Identifie
     Ok(predicate_identifiers)
 }
 
+fn received_range_disclosures(proof: &Presentation) -> Result<HashMap<String, Identifier>> {
+    let mut disclosure_identifiers: HashMap<String, Identifier> = HashMap::new();
+    for (referent, info) in proof.requested_proof.range_disclosures.iter() {
+        disclosure_identifiers.insert(
+            referent.to_string(),
+            get_proof_identifier(proof, info.sub_proof_index)?,
+        );
+    }
+    Ok(disclosure_identifiers)
+}
+
 fn received_self_attested_attrs(proof: &Presentation) -> HashSet<String> {
     proof
         .requested_proof
@@ -448,7 +1018,7 @@ fn verify_revealed_attribute_values(
                     attr_referent,
                 )
             })?;
-        verify_revealed_attribute_value(attr_name.as_str(), proof, &attr_info)?;
+        verify_revealed_attribute_value(attr_name.as_str(), proof, attr_info)?;
     }
 
     for (attr_referent, attr_infos) in proof.requested_proof.revealed_attr_groups.iter() {
@@ -496,16 +1066,76 @@ fn verify_revealed_attribute_values(
     Ok(())
 }
 
+fn verify_revealed_predicate_values(
+    pres_req: &PresentationRequestPayload,
+    proof: &Presentation,
+) -> Result<()> {
+    for (predicate_referent, attr_info) in proof.requested_proof.revealed_predicates.iter() {
+        let predicate = pres_req
+            .requested_predicates
+            .get(predicate_referent)
+            .ok_or_else(|| {
+                err_msg!(
+                    ProofRejected,
+                    "Predicate with referent \"{}\" not found in ProofRequests",
+                    predicate_referent
+                )
+            })?;
+        match proof.requested_proof.predicates.get(predicate_referent) {
+            Some(referent) if referent.sub_proof_index == attr_info.sub_proof_index => (),
+            _ => {
+                return Err(err_msg!(
+                    ProofRejected,
+                    "Revealed value for predicate \"{}\" does not match its sub-proof",
+                    predicate_referent
+                ))
+            }
+        }
+        verify_revealed_attribute_value(predicate.name.as_str(), proof, attr_info)?;
+
+        if encode_credential_attribute(&attr_info.raw)? != attr_info.encoded {
+            return Err(err_msg!(
+                ProofRejected,
+                "Encoded value for predicate \"{}\" does not match the raw value",
+                predicate_referent
+            ));
+        }
+        let value = attr_info.raw.parse::<i32>().map_err(|_| {
+            err_msg!(
+                ProofRejected,
+                "Revealed value for predicate \"{}\" is not an integer",
+                predicate_referent
+            )
+        })?;
+        let satisfied = match predicate.p_type {
+            PredicateTypes::GE => value >= predicate.p_value,
+            PredicateTypes::GT => value > predicate.p_value,
+            PredicateTypes::LE => value <= predicate.p_value,
+            PredicateTypes::LT => value < predicate.p_value,
+        };
+        if !satisfied {
+            return Err(err_msg!(
+                ProofRejected,
+                "Revealed value for predicate \"{}\" does not satisfy the predicate",
+                predicate_referent
+            ));
+        }
+    }
+    Ok(())
+}
+
 fn verify_revealed_attribute_value(
     attr_name: &str,
     proof: &Presentation,
     attr_info: &RevealedAttributeInfo,
 ) -> Result<()> {
-    let reveal_attr_encoded = attr_info.encoded.to_string();
-    let reveal_attr_encoded = Regex::new("^0*")
-        .unwrap()
-        .replace_all(&reveal_attr_encoded, "")
-        .to_owned();
+    let reveal_attr_encoded = normalize_encoded_value(&attr_info.encoded).map_err(|_| {
+        err_msg!(
+            ProofRejected,
+            "Encoded value for \"{}\" is not an integer",
+            attr_name
+        )
+    })?;
     let sub_proof_index = attr_info.sub_proof_index as usize;
 
     let crypto_proof_encoded = proof
@@ -521,7 +1151,7 @@ fn verify_revealed_attribute_value(
         })?
         .revealed_attrs()?
         .iter()
-        .find(|(key, _)| attr_common_view(attr_name) == attr_common_view(&key))
+        .find(|(key, _)| attr_common_view(attr_name) == attr_common_view(key))
         .map(|(_, val)| val.to_string())
         .ok_or_else(|| {
             err_msg!(
@@ -556,13 +1186,14 @@ fn verify_requested_restrictions(
     let requested_attrs: HashMap<String, AttributeInfo> = pres_req
         .requested_attributes
         .iter()
-        .filter(|&(referent, info)| !is_self_attested(&referent, &info, self_attested_attrs))
+        .filter(|&(referent, info)| !is_self_attested(referent, info, self_attested_attrs))
         .map(|(referent, info)| (referent.to_string(), info.clone()))
         .collect();
 
     for (referent, info) in requested_attrs.iter() {
         if let Some(ref query) = info.restrictions {
-            let filter = gather_filter_info(&referent, &proof_attr_identifiers)?;
+            check_pattern_tags(query)?;
+            let filter = gather_filter_info(referent, &proof_attr_identifiers)?;
 
             let attr_value_map: HashMap<String, Option<&str>> = if let Some(name) =
                 info.name.as_ref()
@@ -597,7 +1228,7 @@ fn verify_requested_restrictions(
                 ));
             };
 
-            process_operator(&attr_value_map, &query, &filter).map_err(err_map!(
+            process_operator(&attr_value_map, query, &filter).map_err(err_map!(
                 "Requested restriction validation failed for \"{:?}\" attributes",
                 &attr_value_map
             ))?;
@@ -606,7 +1237,8 @@ fn verify_requested_restrictions(
 
     for (referent, info) in pres_req.requested_predicates.iter() {
         if let Some(ref query) = info.restrictions {
-            let filter = gather_filter_info(&referent, received_predicates)?;
+            check_pattern_tags(query)?;
+            let filter = gather_filter_info(referent, received_predicates)?;
 
             // start with the predicate requested attribute, which is un-revealed
             let mut attr_value_map = HashMap::new();
@@ -637,12 +1269,12 @@ fn verify_requested_restrictions(
                 if pred_sub_proof_index == attr_sub_proof_index {
                     for name in attr_info.values.keys() {
                         let raw_val = attr_info.values.get(name).unwrap().raw.as_str();
-                        attr_value_map.insert(name.clone(), Some(raw_val.clone()));
+                        attr_value_map.insert(name.clone(), Some(raw_val));
                     }
                 }
             }
 
-            process_operator(&attr_value_map, &query, &filter).map_err(err_map!(
+            process_operator(&attr_value_map, query, &filter).map_err(err_map!(
                 "Requested restriction validation failed for \"{}\" predicate",
                 &info.name
             ))?;
@@ -707,14 +1339,14 @@ fn process_operator(
 ) -> Result<()> {
     match restriction_op {
         Query::Eq(ref tag_name, ref tag_value) => {
-            process_filter(attr_value_map, &tag_name, &tag_value, filter).map_err(err_map!(
+            process_filter(attr_value_map, tag_name, tag_value, filter).map_err(err_map!(
                 "$eq operator validation failed for tag: \"{}\", value: \"{}\"",
                 tag_name,
                 tag_value
             ))
         }
         Query::Neq(ref tag_name, ref tag_value) => {
-            if process_filter(attr_value_map, &tag_name, &tag_value, filter).is_err() {
+            if process_filter(attr_value_map, tag_name, tag_value, filter).is_err() {
                 Ok(())
             } else {
                 Err(err_msg!(ProofRejected,
@@ -724,7 +1356,7 @@ fn process_operator(
         Query::In(ref tag_name, ref tag_values) => {
             let res = tag_values
                 .iter()
-                .any(|val| process_filter(attr_value_map, &tag_name, &val, filter).is_ok());
+                .any(|val| process_filter(attr_value_map, tag_name, val, filter).is_ok());
             if res {
                 Ok(())
             } else {
@@ -756,7 +1388,7 @@ fn process_operator(
             }
         }
         Query::Not(ref operator) => {
-            if process_operator(attr_value_map, &*operator, filter).is_err() {
+            if process_operator(attr_value_map, operator, filter).is_err() {
                 Ok(())
             } else {
                 Err(err_msg!(
@@ -765,10 +1397,56 @@ fn process_operator(
                 ))
             }
         }
+        Query::Like(ref tag_name, ref pattern) => {
+            let value = filter_value(tag_name, filter).ok_or_else(|| {
+                err_msg!(
+                    "$like operator is only supported for identifier tags: \"{}\"",
+                    tag_name
+                )
+            })?;
+            if like_matches(pattern, value) {
+                Ok(())
+            } else {
+                Err(err_msg!(
+                    ProofRejected,
+                    "$like operator validation failed for tag: \"{}\", pattern: \"{}\"",
+                    tag_name,
+                    pattern
+                ))
+            }
+        }
         _ => Err(err_msg!(ProofRejected, "unsupported operator",)),
     }
 }
 
+/// Check that `$like` patterns are only applied to identifier tags, before
+/// evaluation where a failure under `$not` would be treated as a match
+fn check_pattern_tags(query: &Query) -> Result<()> {
+    match query {
+        Query::And(queries) | Query::Or(queries) => queries.iter().try_for_each(check_pattern_tags),
+        Query::Not(query) => check_pattern_tags(query),
+        Query::Like(tag_name, _) if !RESTRICTION_TAGS.contains(&tag_name.as_str()) => {
+            Err(err_msg!(
+                "$like operator is only supported for identifier tags: \"{}\"",
+                tag_name
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn filter_value<'f>(tag: &str, filter: &'f Filter) -> Option<&'f str> {
+    match tag {
+        "schema_id" => Some(&filter.schema_id),
+        "schema_issuer_did" => Some(&filter.schema_issuer_did),
+        "schema_name" => Some(&filter.schema_name),
+        "schema_version" => Some(&filter.schema_version),
+        "cred_def_id" => Some(&filter.cred_def_id),
+        "issuer_did" => Some(&filter.issuer_did),
+        _ => None,
+    }
+}
+
 fn process_filter(
     attr_value_map: &HashMap<String, Option<&str>>,
     tag: &str,
@@ -966,6 +1644,23 @@ mod tests {
         _process_operator("zip", &op, &filter, None).unwrap()
     }
 
+    #[test]
+    fn test_process_op_like() {
+        let filter = filter();
+        let mut op = Query::Like(cred_def_id_tag(), "34%".to_string());
+        _process_operator("zip", &op, &filter, None).unwrap();
+
+        op = Query::Like(issuer_did_tag(), "4_6".to_string());
+        _process_operator("zip", &op, &filter, None).unwrap();
+
+        op = Query::Like(issuer_did_tag(), "5%".to_string());
+        assert!(_process_operator("zip", &op, &filter, None).is_err());
+
+        op = Query::Not(Box::new(Query::Like(attr_tag_value(), "%".to_string())));
+        assert!(check_pattern_tags(&op).is_err());
+        check_pattern_tags(&Query::Like(issuer_did_tag(), "%".to_string())).unwrap();
+    }
+
     #[test]
     fn test_process_op_or() {
         let filter = filter();
@@ -1192,6 +1887,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn check_timestamp_freshness_works() {
+        let mut policy = VerificationPolicy::default();
+        check_timestamp_freshness(1000, &policy, 5000).unwrap();
+        policy.max_revocation_state_age = Some(3600);
+        check_timestamp_freshness(1000, &policy, 4600).unwrap();
+        assert_kind!(
+            ProofRejected,
+            check_timestamp_freshness(1000, &policy, 4601)
+        );
+        policy.max_clock_skew = Some(60);
+        check_timestamp_freshness(1060, &policy, 1000).unwrap();
+        assert_kind!(
+            ProofRejected,
+            check_timestamp_freshness(1061, &policy, 1000)
+        );
+    }
+
     #[test]
     fn validate_timestamp_works() {
         validate_timestamp(&_received(), "referent_1", &None, &None).unwrap();
@@ -1205,4 +1918,64 @@ mod tests {
         validate_timestamp(&_received(), "referent_2", &None, &Some(_interval())).unwrap_err();
         validate_timestamp(&_received(), "referent_3", &None, &Some(_interval())).unwrap_err();
     }
+
+    #[test]
+    fn presentation_from_json_enforces_limits() {
+        let json = serde_json::to_vec(&serde_json::json!({
+            "proof": {"proofs": [{}, {}], "aggregated_proof": {}},
+            "requested_proof": {
+                "revealed_attrs": {"a": {}, "b": {}},
+                "predicates": {"c": {}}
+            },
+            "identifiers": [{}]
+        }))
+        .unwrap();
+        let limits = VerificationLimits::new().max_json_size(Some(json.len() - 1));
+        assert_kind!(LimitExceeded, presentation_from_json(&json, &limits));
+        let limits = VerificationLimits::new().max_referents(Some(2));
+        assert_kind!(LimitExceeded, presentation_from_json(&json, &limits));
+        let limits = VerificationLimits::new().max_credentials(Some(1));
+        assert_kind!(LimitExceeded, presentation_from_json(&json, &limits));
+        // within the limits, the malformed proof is only rejected when parsed
+        let limits = VerificationLimits::new()
+            .max_json_size(Some(json.len()))
+            .max_referents(Some(3))
+            .max_credentials(Some(2));
+        assert_kind!(Input, presentation_from_json(&json, &limits));
+    }
+
+    #[test]
+    fn presentation_limits_count_every_referent_map() {
+        let json = serde_json::to_vec(&serde_json::json!({
+            "proof": {"proofs": [{}], "aggregated_proof": {}},
+            "requested_proof": {
+                "revealed_predicates": {"a": {}, "b": {}, "c": {}},
+                "escrowed_attrs": {"d": {}}
+            },
+            "identifiers": [{}]
+        }))
+        .unwrap();
+        let limits = VerificationLimits::new().max_referents(Some(2));
+        assert_kind!(LimitExceeded, presentation_from_json(&json, &limits));
+        let limits = VerificationLimits::new().max_referents(Some(3));
+        assert_kind!(LimitExceeded, presentation_from_json(&json, &limits));
+        let limits = VerificationLimits::new().max_referents(Some(4));
+        assert_kind!(Input, presentation_from_json(&json, &limits));
+
+        let mut requested_proof = RequestedProof::default();
+        for referent in ["a", "b", "c"].iter() {
+            requested_proof.revealed_predicates.insert(
+                referent.to_string(),
+                RevealedAttributeInfo {
+                    sub_proof_index: 0,
+                    raw: "28".to_string(),
+                    encoded: "28".to_string(),
+                },
+            );
+        }
+        // parsed presentations are counted from the same outline
+        let outline: RequestedProofOutline =
+            serde_json::from_value(serde_json::to_value(&requested_proof).unwrap()).unwrap();
+        assert_eq!(outline.referent_count(), 3);
+    }
 }
@@ -1,19 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use indy_credx::{
-    issuer, prover,
-    types::{CredentialDefinitionConfig, MakeCredentialValues, PresentCredentials, SignatureType},
-    verifier,
+    flows::{self, HolderObjects, IssuerObjects},
+    issuer, migration, prover,
+    resolver::{CachingResolver, InMemoryResolver},
+    tails::{TailsChunkedWriter, TailsFileReader, TailsFileWriter, TailsGeneratorReader},
+    types::{
+        AcceptedIssuers, CredentialDefinitionConfig, CredentialDefinitionKey,
+        CredentialDefinitionKeyType, CredentialDefinitionV2, CredentialRevocationConfig, DidValue,
+        IssuanceType, MakeCredentialValues, PresentCredentials, Presentation, RegistryType,
+        RevocationRegistryDefinition, RevocationStatusList, SignatureType, VerificationPolicy,
+    },
+    verifier, ErrorKind,
 };
 
+use async_global_executor::block_on;
+use indy_utils::Validatable;
 use serde_json::json;
 
-use self::utils::anoncreds::{IssuerWallet, ProverWallet};
+use self::utils::anoncreds::{IssuerWallet, ProverWallet, PROVER_DID};
 
 mod utils;
 
-pub static GVT_SCHEMA_NAME: &'static str = "gvt";
-pub static GVT_SCHEMA_ATTRIBUTES: &[&'static str; 4] = &["name", "age", "sex", "height"];
+pub static GVT_SCHEMA_NAME: &str = "gvt";
+pub static GVT_SCHEMA_ATTRIBUTES: &[&str; 4] = &["name", "age", "sex", "height"];
 
 #[test]
 fn anoncreds_works_for_single_issuer_single_prover() {
@@ -52,7 +62,7 @@ fn anoncreds_works_for_single_issuer_single_prover() {
     // Issuer creates a Credential Offer
     let cred_offer = issuer::create_credential_offer(
         gvt_schema.id(),
-        &gvt_cred_def,
+        gvt_cred_def,
         &issuer_wallet.cred_defs[0].key_proof,
     )
     .expect("Error creating credential offer");
@@ -60,13 +70,20 @@ fn anoncreds_works_for_single_issuer_single_prover() {
     // Prover creates a Credential Request
     let (cred_request, cred_request_metadata) = prover::create_credential_request(
         &prover_wallet.did,
-        &*gvt_cred_def,
+        gvt_cred_def,
         &prover_wallet.master_secret,
         "default",
         &cred_offer,
     )
     .expect("Error creating credential request");
 
+    // Prover renames its master secret
+    let renamed_metadata =
+        prover::rename_master_secret_in_metadata(&cred_request_metadata, "renamed")
+            .expect("Error renaming master secret");
+    assert_eq!(renamed_metadata.master_secret_name, "renamed");
+    assert!(renamed_metadata.has_master_secret_name("default"));
+
     // Issuer creates a credential
     let mut cred_values = MakeCredentialValues::default();
     cred_values
@@ -82,7 +99,7 @@ fn anoncreds_works_for_single_issuer_single_prover() {
         .add_raw("age", "28")
         .expect("Error encoding attribute");
     let (issue_cred, _, _) = issuer::create_credential(
-        &*gvt_cred_def,
+        gvt_cred_def,
         &issuer_wallet.cred_defs[0].private,
         &cred_offer,
         &cred_request,
@@ -92,15 +109,31 @@ fn anoncreds_works_for_single_issuer_single_prover() {
     .expect("Error creating credential");
 
     // Prover receives the credential and processes it
+    let (extracted_cred, attributes) = prover::process_and_extract_credential(
+        &issue_cred,
+        &cred_offer,
+        &renamed_metadata,
+        &prover_wallet.master_secret,
+        gvt_cred_def,
+        None,
+    )
+    .expect("Error processing credential");
+    assert_eq!(attributes.get("name").map(String::as_str), Some("Alex"));
+    assert_eq!(attributes.len(), 4);
+
     let mut recv_cred = issue_cred;
     prover::process_credential(
         &mut recv_cred,
         &cred_request_metadata,
         &prover_wallet.master_secret,
-        &*gvt_cred_def,
+        gvt_cred_def,
         None,
     )
     .expect("Error processing credential");
+    assert_eq!(
+        serde_json::to_value(&extracted_cred).unwrap(),
+        serde_json::to_value(&recv_cred).unwrap()
+    );
     prover_wallet.credentials.push(recv_cred);
 
     // Verifier creates a presentation request
@@ -150,7 +183,7 @@ fn anoncreds_works_for_single_issuer_single_prover() {
     schemas.insert(gvt_schema.id().clone(), &gvt_schema);
 
     let mut cred_defs = HashMap::new();
-    cred_defs.insert(gvt_cred_def.id().clone(), &*gvt_cred_def);
+    cred_defs.insert(gvt_cred_def.id().clone(), gvt_cred_def);
 
     let presentation = prover::create_presentation(
         &pres_request,
@@ -209,6 +242,1284 @@ fn anoncreds_works_for_single_issuer_single_prover() {
     )
     .expect("Error verifying presentation");
     assert!(valid);
+
+    // Verifier checks a batch of presentations concurrently
+    let batch = vec![(&presentation, &pres_request); 4];
+    let results = verifier::verify_presentations(
+        &batch,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+        &VerificationPolicy::default(),
+    );
+    assert_eq!(results.len(), 4);
+    assert!(results.into_iter().all(|res| res.unwrap()));
+
+    // Service objects may be shared between verifying threads
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..2)
+            .map(|_| {
+                scope.spawn(|| {
+                    verifier::verify_presentation(
+                        &presentation,
+                        &pres_request,
+                        &schemas,
+                        &cred_defs,
+                        None,
+                        None,
+                    )
+                })
+            })
+            .collect();
+        for worker in workers {
+            assert!(worker
+                .join()
+                .unwrap()
+                .expect("Error verifying presentation"));
+        }
+    });
+
+    // Prover creates an auditable presentation revealing the predicate value
+    let mut present = PresentCredentials::default();
+    {
+        let mut cred1 = present.add_credential(&prover_wallet.credentials[0], None, None);
+        cred1.add_requested_attribute("attr1_referent", true);
+        cred1.add_requested_attribute("attr2_referent", false);
+        cred1.add_requested_attribute("attr4_referent", true);
+        cred1.add_requested_predicate("predicate1_referent");
+    }
+    let mut self_attested = HashMap::new();
+    self_attested.insert(
+        "attr3_referent".to_string(),
+        self_attested_phone.to_string(),
+    );
+    let presentation = prover::create_auditable_presentation(
+        &pres_request,
+        present,
+        Some(self_attested),
+        &prover_wallet.master_secret,
+        &schemas,
+        &cred_defs,
+    )
+    .expect("Error creating presentation");
+    assert_eq!(
+        "28",
+        presentation
+            .requested_proof
+            .revealed_predicates
+            .get("predicate1_referent")
+            .unwrap()
+            .raw
+    );
+    let valid = verifier::verify_presentation(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+    )
+    .expect("Error verifying presentation");
+    assert!(valid);
+
+    // a modified predicate value is rejected
+    let mut tampered = serde_json::to_value(&presentation).unwrap();
+    tampered["requested_proof"]["revealed_predicates"]["predicate1_referent"]["raw"] = json!("30");
+    tampered["requested_proof"]["revealed_predicates"]["predicate1_referent"]["encoded"] =
+        json!("30");
+    let tampered: Presentation = serde_json::from_value(tampered).unwrap();
+    assert!(verifier::verify_presentation(
+        &tampered,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+    )
+    .is_err());
+}
+
+#[test]
+fn anoncreds_works_for_large_encoded_values() {
+    let mut issuer_wallet = IssuerWallet::default();
+    let prover_wallet = ProverWallet::default();
+
+    let gvt_schema = issuer::create_schema(
+        &issuer_wallet.did,
+        GVT_SCHEMA_NAME,
+        "1.0",
+        GVT_SCHEMA_ATTRIBUTES[..].into(),
+        None,
+    )
+    .expect("Error creating gvt schema for issuer");
+    let cred_def_parts = issuer::create_credential_definition(
+        &issuer_wallet.did,
+        &gvt_schema,
+        "tag",
+        SignatureType::CL,
+        CredentialDefinitionConfig::new(false),
+    )
+    .expect("Error creating gvt credential definition");
+    issuer_wallet.cred_defs.push(cred_def_parts.into());
+    let gvt_cred_def = &issuer_wallet.cred_defs[0].public;
+
+    let cred_offer = issuer::create_credential_offer(
+        gvt_schema.id(),
+        gvt_cred_def,
+        &issuer_wallet.cred_defs[0].key_proof,
+    )
+    .expect("Error creating credential offer");
+    let (cred_request, cred_request_metadata) = prover::create_credential_request(
+        &prover_wallet.did,
+        gvt_cred_def,
+        &prover_wallet.master_secret,
+        "default",
+        &cred_offer,
+    )
+    .expect("Error creating credential request");
+
+    // Issuer provides a full 256-bit encoding for one of the attributes
+    let name_encoded =
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+    let mut cred_values = MakeCredentialValues::default();
+    cred_values.add_encoded("name", "Alex", name_encoded.to_string());
+    cred_values
+        .add_raw("sex", "male")
+        .expect("Error encoding attribute");
+    cred_values
+        .add_raw("height", "175")
+        .expect("Error encoding attribute");
+    cred_values
+        .add_raw("age", "28")
+        .expect("Error encoding attribute");
+    let (mut credential, _, _) = issuer::create_credential(
+        gvt_cred_def,
+        &issuer_wallet.cred_defs[0].private,
+        &cred_offer,
+        &cred_request,
+        cred_values.into(),
+        None,
+    )
+    .expect("Error creating credential");
+    prover::process_credential(
+        &mut credential,
+        &cred_request_metadata,
+        &prover_wallet.master_secret,
+        gvt_cred_def,
+        None,
+    )
+    .expect("Error processing credential");
+
+    // The encoded value survives serialization without loss of precision
+    let credential: indy_credx::types::Credential =
+        serde_json::from_value(serde_json::to_value(&credential).unwrap()).unwrap();
+    assert_eq!(
+        credential.values.0["name"].encoded_value().unwrap(),
+        name_encoded
+    );
+
+    // The wallet detects that the name was not encoded in the standard way
+    assert_eq!(
+        prover::check_credential_encoding(&credential).unwrap(),
+        vec!["name".to_string()]
+    );
+
+    let nonce = verifier::generate_nonce().expect("Error generating presentation request nonce");
+    let pres_request = serde_json::from_value(json!({
+        "nonce": nonce,
+        "name":"pres_req_1",
+        "version":"0.1",
+        "requested_attributes":{
+            "attr1_referent":{"name":"name"}
+        },
+        "requested_predicates":{
+            "predicate1_referent":{"name":"age","p_type":">=","p_value":18}
+        }
+    }))
+    .expect("Error creating proof request");
+
+    let mut present = PresentCredentials::default();
+    {
+        let mut cred1 = present.add_credential(&credential, None, None);
+        cred1.add_requested_attribute("attr1_referent", true);
+        cred1.add_requested_predicate("predicate1_referent");
+    }
+    let mut schemas = HashMap::new();
+    schemas.insert(gvt_schema.id().clone(), &gvt_schema);
+    let mut cred_defs = HashMap::new();
+    cred_defs.insert(gvt_cred_def.id().clone(), gvt_cred_def);
+
+    let presentation = prover::create_presentation(
+        &pres_request,
+        present,
+        None,
+        &prover_wallet.master_secret,
+        &schemas,
+        &cred_defs,
+    )
+    .expect("Error creating presentation");
+    let presentation: Presentation =
+        serde_json::from_value(serde_json::to_value(&presentation).unwrap()).unwrap();
+    assert_eq!(
+        presentation.requested_proof.revealed_attrs["attr1_referent"].encoded,
+        name_encoded
+    );
+
+    let valid = verifier::verify_presentation(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+    )
+    .expect("Error verifying presentation");
+    assert!(valid);
+}
+
+#[test]
+fn anoncreds_works_for_batched_revocation_states() {
+    let mut issuer_wallet = IssuerWallet::default();
+
+    let gvt_schema = issuer::create_schema(
+        &issuer_wallet.did,
+        GVT_SCHEMA_NAME,
+        "1.0",
+        GVT_SCHEMA_ATTRIBUTES[..].into(),
+        None,
+    )
+    .expect("Error creating gvt schema for issuer");
+
+    let cred_def_parts = issuer::create_credential_definition(
+        &issuer_wallet.did,
+        &gvt_schema,
+        "tag",
+        SignatureType::CL,
+        CredentialDefinitionConfig {
+            support_revocation: true,
+        },
+    )
+    .expect("Error creating gvt credential definition");
+    issuer_wallet.cred_defs.push(cred_def_parts.into());
+    let gvt_cred_def = &issuer_wallet.cred_defs[0].public;
+
+    let mut tails_writer = TailsFileWriter::new(None);
+    let (rev_reg_def, _rev_reg_def_pvt, _rev_reg, rev_reg_delta) =
+        issuer::create_revocation_registry(
+            &issuer_wallet.did,
+            gvt_cred_def,
+            "tag",
+            RegistryType::CL_ACCUM,
+            IssuanceType::ISSUANCE_BY_DEFAULT,
+            5,
+            &mut tails_writer,
+        )
+        .expect("Error creating revocation registry");
+    let tails_location = match &rev_reg_def {
+        RevocationRegistryDefinition::RevocationRegistryDefinitionV1(def) => {
+            def.value.tails_location.clone()
+        }
+    };
+
+    let indices = [1u32, 3, 5];
+    let batched = prover::create_or_update_revocation_states(
+        TailsFileReader::new(&tails_location),
+        &rev_reg_def,
+        &rev_reg_delta,
+        &indices.iter().map(|idx| (*idx, None)).collect::<Vec<_>>(),
+        1000,
+    )
+    .expect("Error creating revocation states");
+    assert_eq!(batched.len(), indices.len());
+
+    for (idx, state) in indices.iter().zip(batched) {
+        let single = prover::create_or_update_revocation_state(
+            TailsFileReader::new(&tails_location),
+            &rev_reg_def,
+            &rev_reg_delta,
+            *idx,
+            1000,
+            None,
+        )
+        .expect("Error creating revocation state");
+        assert_eq!(
+            serde_json::to_value(&state).unwrap(),
+            serde_json::to_value(&single).unwrap()
+        );
+    }
+}
+
+#[test]
+fn anoncreds_works_for_chunked_tails() {
+    let mut issuer_wallet = IssuerWallet::default();
+
+    let gvt_schema = issuer::create_schema(
+        &issuer_wallet.did,
+        GVT_SCHEMA_NAME,
+        "1.0",
+        GVT_SCHEMA_ATTRIBUTES[..].into(),
+        None,
+    )
+    .expect("Error creating gvt schema for issuer");
+
+    let cred_def_parts = issuer::create_credential_definition(
+        &issuer_wallet.did,
+        &gvt_schema,
+        "tag",
+        SignatureType::CL,
+        CredentialDefinitionConfig {
+            support_revocation: true,
+        },
+    )
+    .expect("Error creating gvt credential definition");
+    issuer_wallet.cred_defs.push(cred_def_parts.into());
+    let gvt_cred_def = &issuer_wallet.cred_defs[0].public;
+
+    // 11 tails are written across four chunks
+    let mut tails_writer = TailsChunkedWriter::new(None, Some(3));
+    let (rev_reg_def, _rev_reg_def_pvt, _rev_reg, rev_reg_delta) =
+        issuer::create_revocation_registry(
+            &issuer_wallet.did,
+            gvt_cred_def,
+            "tag",
+            RegistryType::CL_ACCUM,
+            IssuanceType::ISSUANCE_BY_DEFAULT,
+            5,
+            &mut tails_writer,
+        )
+        .expect("Error creating revocation registry");
+    let tails_location = match &rev_reg_def {
+        RevocationRegistryDefinition::RevocationRegistryDefinitionV1(def) => {
+            def.value.tails_location.clone()
+        }
+    };
+    for chunk in 0..4 {
+        assert!(std::path::Path::new(&format!("{}.{}", tails_location, chunk)).exists());
+    }
+
+    let indices = [1u32, 2, 5];
+    let batched = prover::create_or_update_revocation_states(
+        TailsFileReader::new(&tails_location),
+        &rev_reg_def,
+        &rev_reg_delta,
+        &indices.iter().map(|idx| (*idx, None)).collect::<Vec<_>>(),
+        1000,
+    )
+    .expect("Error creating revocation states");
+
+    for (idx, state) in indices.iter().zip(batched) {
+        let single = prover::create_or_update_revocation_state(
+            TailsFileReader::new(&tails_location),
+            &rev_reg_def,
+            &rev_reg_delta,
+            *idx,
+            1000,
+            None,
+        )
+        .expect("Error creating revocation state");
+        assert_eq!(
+            serde_json::to_value(&state).unwrap(),
+            serde_json::to_value(&single).unwrap()
+        );
+    }
+
+    // a modified chunk is rejected
+    std::fs::write(format!("{}.{}", tails_location, 1), b"invalid").unwrap();
+    assert!(prover::create_or_update_revocation_state(
+        TailsFileReader::new(&tails_location),
+        &rev_reg_def,
+        &rev_reg_delta,
+        1,
+        1000,
+        None,
+    )
+    .is_err());
+}
+
+#[test]
+fn anoncreds_works_for_lazy_tails() {
+    let mut issuer_wallet = IssuerWallet::default();
+
+    let gvt_schema = issuer::create_schema(
+        &issuer_wallet.did,
+        GVT_SCHEMA_NAME,
+        "1.0",
+        GVT_SCHEMA_ATTRIBUTES[..].into(),
+        None,
+    )
+    .expect("Error creating gvt schema for issuer");
+
+    let cred_def_parts = issuer::create_credential_definition(
+        &issuer_wallet.did,
+        &gvt_schema,
+        "tag",
+        SignatureType::CL,
+        CredentialDefinitionConfig {
+            support_revocation: true,
+        },
+    )
+    .expect("Error creating gvt credential definition");
+    issuer_wallet.cred_defs.push(cred_def_parts.into());
+    let gvt_cred_def = &issuer_wallet.cred_defs[0].public;
+
+    let (rev_reg_def, rev_reg_def_pvt, _rev_reg, rev_reg_delta) =
+        issuer::create_revocation_registry_lazy(
+            &issuer_wallet.did,
+            gvt_cred_def,
+            "tag",
+            RegistryType::CL_ACCUM,
+            IssuanceType::ISSUANCE_BY_DEFAULT,
+            5,
+        )
+        .expect("Error creating revocation registry");
+
+    let lazy_state = prover::create_or_update_revocation_state(
+        TailsGeneratorReader::new(gvt_cred_def, &rev_reg_def, &rev_reg_def_pvt).unwrap(),
+        &rev_reg_def,
+        &rev_reg_delta,
+        2,
+        1000,
+        None,
+    )
+    .expect("Error creating revocation state");
+
+    // the tails written afterwards match those generated on demand
+    let mut tails_writer = TailsFileWriter::new(None);
+    let rev_reg_def = issuer::write_revocation_registry_tails(
+        gvt_cred_def,
+        &rev_reg_def,
+        &rev_reg_def_pvt,
+        &mut tails_writer,
+    )
+    .expect("Error writing tails");
+    let tails_location = match &rev_reg_def {
+        RevocationRegistryDefinition::RevocationRegistryDefinitionV1(def) => {
+            assert!(!def.value.tails_hash.is_empty());
+            def.value.tails_location.clone()
+        }
+    };
+    let file_state = prover::create_or_update_revocation_state(
+        TailsFileReader::new(&tails_location),
+        &rev_reg_def,
+        &rev_reg_delta,
+        2,
+        1000,
+        None,
+    )
+    .expect("Error creating revocation state");
+    assert_eq!(
+        serde_json::to_value(&lazy_state).unwrap(),
+        serde_json::to_value(&file_state).unwrap()
+    );
+}
+
+#[test]
+fn anoncreds_works_for_policy_requiring_non_revocation() {
+    let mut issuer_wallet = IssuerWallet::default();
+    let mut prover_wallet = ProverWallet::default();
+
+    let gvt_schema = issuer::create_schema(
+        &issuer_wallet.did,
+        GVT_SCHEMA_NAME,
+        "1.0",
+        GVT_SCHEMA_ATTRIBUTES[..].into(),
+        None,
+    )
+    .expect("Error creating gvt schema for issuer");
+
+    let cred_def_parts = issuer::create_credential_definition(
+        &issuer_wallet.did,
+        &gvt_schema,
+        "tag",
+        SignatureType::CL,
+        CredentialDefinitionConfig {
+            support_revocation: true,
+        },
+    )
+    .expect("Error creating gvt credential definition");
+    issuer_wallet.cred_defs.push(cred_def_parts.into());
+    let gvt_cred_def = &issuer_wallet.cred_defs[0].public;
+
+    let mut tails_writer = TailsFileWriter::new(None);
+    let (rev_reg_def, rev_reg_def_pvt, rev_reg, rev_reg_delta) =
+        issuer::create_revocation_registry(
+            &issuer_wallet.did,
+            gvt_cred_def,
+            "tag",
+            RegistryType::CL_ACCUM,
+            IssuanceType::ISSUANCE_BY_DEFAULT,
+            5,
+            &mut tails_writer,
+        )
+        .expect("Error creating revocation registry");
+    let tails_location = match &rev_reg_def {
+        RevocationRegistryDefinition::RevocationRegistryDefinitionV1(def) => {
+            def.value.tails_location.clone()
+        }
+    };
+
+    let mut cred_values = MakeCredentialValues::default();
+    for (name, value) in [
+        ("sex", "male"),
+        ("name", "Alex"),
+        ("height", "175"),
+        ("age", "28"),
+    ] {
+        cred_values
+            .add_raw(name, value)
+            .expect("Error encoding attribute");
+    }
+    let issued = flows::issue_credential(
+        IssuerObjects {
+            schema_id: gvt_schema.id(),
+            cred_def: gvt_cred_def,
+            cred_def_private: &issuer_wallet.cred_defs[0].private,
+            key_correctness_proof: &issuer_wallet.cred_defs[0].key_proof,
+            revocation: Some(CredentialRevocationConfig {
+                reg_def: &rev_reg_def,
+                reg_def_private: &rev_reg_def_pvt,
+                registry: &rev_reg,
+                registry_idx: 1,
+                registry_used: &HashSet::new(),
+                tails_reader: TailsFileReader::new(&tails_location),
+            }),
+        },
+        HolderObjects {
+            prover_did: &prover_wallet.did,
+            master_secret: &prover_wallet.master_secret,
+            master_secret_id: "default",
+        },
+        cred_values.into(),
+    )
+    .expect("Error issuing credential");
+    prover_wallet.credentials.push(issued.credential);
+
+    // The request omits `non_revoked`, so no non-revocation proof is produced
+    let nonce = verifier::generate_nonce().expect("Error generating presentation request nonce");
+    let pres_request = serde_json::from_value(json!({
+        "nonce": nonce,
+        "name":"pres_req_1",
+        "version":"0.1",
+        "requested_attributes":{
+            "attr1_referent":{"name":"name"}
+        },
+        "requested_predicates":{}
+    }))
+    .expect("Error creating proof request");
+
+    let mut present = PresentCredentials::default();
+    present
+        .add_credential(&prover_wallet.credentials[0], None, None)
+        .add_requested_attribute("attr1_referent", true);
+
+    let mut schemas = HashMap::new();
+    schemas.insert(gvt_schema.id().clone(), &gvt_schema);
+    let mut cred_defs = HashMap::new();
+    cred_defs.insert(gvt_cred_def.id().clone(), gvt_cred_def);
+
+    let presentation = prover::create_presentation(
+        &pres_request,
+        present,
+        None,
+        &prover_wallet.master_secret,
+        &schemas,
+        &cred_defs,
+    )
+    .expect("Error creating presentation");
+
+    let valid = verifier::verify_presentation(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+    )
+    .expect("Error verifying presentation");
+    assert!(valid);
+
+    let err = verifier::verify_presentation_with_policy(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+        &VerificationPolicy::new(true),
+    )
+    .expect_err("Expected presentation without non-revocation proof to be rejected");
+    assert_eq!(err.kind(), ErrorKind::ProofRejected);
+
+    // The verifier only trusts a different issuer for the referent
+    let mut policy = VerificationPolicy::default();
+    policy.add_accepted_issuers(
+        "attr1_referent",
+        AcceptedIssuers {
+            issuer_dids: vec![DidValue::from(PROVER_DID.to_string())],
+            ..Default::default()
+        },
+    );
+    let mismatches = verifier::check_accepted_issuers(&presentation, &policy)
+        .expect("Error checking accepted issuers");
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(mismatches[0].referent, "attr1_referent");
+    assert_eq!(mismatches[0].issuer_did, Some(issuer_wallet.did.clone()));
+    let err = verifier::verify_presentation_with_policy(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+        &policy,
+    )
+    .expect_err("Expected presentation from untrusted issuer to be rejected");
+    assert_eq!(err.kind(), ErrorKind::ProofRejected);
+
+    policy.add_accepted_issuers(
+        "attr1_referent",
+        AcceptedIssuers {
+            cred_def_ids: vec![gvt_cred_def.id().clone()],
+            ..Default::default()
+        },
+    );
+    let valid = verifier::verify_presentation_with_policy(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+        &policy,
+    )
+    .expect("Error verifying presentation");
+    assert!(valid);
+
+    // The prover presents the latest cached state within an open-ended interval
+    let rev_states = [1000, 2000, 3000]
+        .iter()
+        .map(|timestamp| {
+            prover::create_or_update_revocation_state(
+                TailsFileReader::new(&tails_location),
+                &rev_reg_def,
+                &rev_reg_delta,
+                1,
+                *timestamp,
+                None,
+            )
+            .expect("Error creating revocation state")
+        })
+        .collect::<Vec<_>>();
+    let pres_request = serde_json::from_value(json!({
+        "nonce": nonce,
+        "name":"pres_req_2",
+        "version":"0.1",
+        "requested_attributes":{
+            "attr1_referent":{"name":"name", "non_revoked": {"from": 1500, "to": 2500}}
+        },
+        "requested_predicates":{},
+        "non_revoked": {"from": 1500}
+    }))
+    .expect("Error creating proof request");
+
+    let mut present = PresentCredentials::default();
+    present
+        .add_credential_with_latest_state(&prover_wallet.credentials[0], &rev_states)
+        .add_requested_attribute("attr1_referent", true);
+    let presentation = prover::create_presentation(
+        &pres_request,
+        present,
+        None,
+        &prover_wallet.master_secret,
+        &schemas,
+        &cred_defs,
+    )
+    .expect("Error creating presentation");
+    assert_eq!(presentation.identifiers[0].timestamp, Some(2000));
+
+    let mut rev_reg_defs = HashMap::new();
+    rev_reg_defs.insert(rev_reg_def.id().clone(), &rev_reg_def);
+    let mut rev_regs = HashMap::new();
+    rev_regs.insert(
+        rev_reg_def.id().clone(),
+        [(2000, &rev_reg)]
+            .iter()
+            .cloned()
+            .collect::<HashMap<_, _>>(),
+    );
+    let valid = verifier::verify_presentation_with_policy(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        Some(&rev_reg_defs),
+        Some(&rev_regs),
+        &VerificationPolicy::new(true),
+    )
+    .expect("Error verifying presentation");
+    assert!(valid);
+
+    // The verifier resolves the referenced objects instead of collecting them
+    let mut objects = InMemoryResolver::new();
+    objects
+        .add_schema(gvt_schema.clone())
+        .add_cred_def(serde_json::from_value(serde_json::to_value(gvt_cred_def).unwrap()).unwrap())
+        .add_rev_reg_def(rev_reg_def.clone())
+        .add_rev_reg_delta(rev_reg_def.id(), 1000, rev_reg_delta.clone());
+    let resolver = CachingResolver::new(objects);
+    for _ in 0..2 {
+        let valid = block_on(verifier::verify_presentation_with_resolver(
+            &presentation,
+            &pres_request,
+            &resolver,
+            &VerificationPolicy::new(true),
+        ))
+        .expect("Error verifying presentation");
+        assert!(valid);
+    }
+}
+
+#[test]
+fn anoncreds_works_for_revocation_registry_delta_conversion() {
+    let mut issuer_wallet = IssuerWallet::default();
+
+    let gvt_schema = issuer::create_schema(
+        &issuer_wallet.did,
+        GVT_SCHEMA_NAME,
+        "1.0",
+        GVT_SCHEMA_ATTRIBUTES[..].into(),
+        None,
+    )
+    .expect("Error creating gvt schema for issuer");
+
+    let cred_def_parts = issuer::create_credential_definition(
+        &issuer_wallet.did,
+        &gvt_schema,
+        "tag",
+        SignatureType::CL,
+        CredentialDefinitionConfig {
+            support_revocation: true,
+        },
+    )
+    .expect("Error creating gvt credential definition");
+    issuer_wallet.cred_defs.push(cred_def_parts.into());
+    let gvt_cred_def = &issuer_wallet.cred_defs[0].public;
+
+    let mut tails_writer = TailsFileWriter::new(None);
+    let (rev_reg_def, _rev_reg_def_pvt, rev_reg, init_delta) = issuer::create_revocation_registry(
+        &issuer_wallet.did,
+        gvt_cred_def,
+        "tag",
+        RegistryType::CL_ACCUM,
+        IssuanceType::ISSUANCE_BY_DEFAULT,
+        5,
+        &mut tails_writer,
+    )
+    .expect("Error creating revocation registry");
+    let tails_reader = match &rev_reg_def {
+        RevocationRegistryDefinition::RevocationRegistryDefinitionV1(def) => {
+            TailsFileReader::new(&def.value.tails_location)
+        }
+    };
+
+    let (revoked_reg, revoke_delta) =
+        issuer::revoke_credential(&rev_reg_def, &rev_reg, 2, &tails_reader)
+            .expect("Error revoking credential");
+
+    let deltas = [(20, &revoke_delta), (10, &init_delta)];
+    let at_initial = verifier::revocation_registry_from_deltas(&deltas, 15)
+        .expect("Error converting deltas to registry");
+    assert_eq!(
+        serde_json::to_value(&at_initial).unwrap(),
+        serde_json::to_value(&rev_reg).unwrap()
+    );
+    let at_revoked = verifier::revocation_registry_from_deltas(&deltas, 25)
+        .expect("Error converting deltas to registry");
+    assert_eq!(
+        serde_json::to_value(&at_revoked).unwrap(),
+        serde_json::to_value(&revoked_reg).unwrap()
+    );
+    assert!(verifier::revocation_registry_from_deltas(&deltas, 5).is_err());
+
+    let delta = verifier::revocation_registry_to_delta(
+        &revoked_reg,
+        Some(&rev_reg),
+        &BTreeSet::new(),
+        &[2].iter().cloned().collect(),
+    );
+    assert_eq!(
+        serde_json::to_value(&delta).unwrap(),
+        serde_json::to_value(&revoke_delta).unwrap()
+    );
+}
+
+#[test]
+fn anoncreds_works_for_revocation_status_list_conversion() {
+    let mut issuer_wallet = IssuerWallet::default();
+
+    let gvt_schema = issuer::create_schema(
+        &issuer_wallet.did,
+        GVT_SCHEMA_NAME,
+        "1.0",
+        GVT_SCHEMA_ATTRIBUTES[..].into(),
+        None,
+    )
+    .expect("Error creating gvt schema for issuer");
+
+    let cred_def_parts = issuer::create_credential_definition(
+        &issuer_wallet.did,
+        &gvt_schema,
+        "tag",
+        SignatureType::CL,
+        CredentialDefinitionConfig {
+            support_revocation: true,
+        },
+    )
+    .expect("Error creating gvt credential definition");
+    issuer_wallet.cred_defs.push(cred_def_parts.into());
+    let gvt_cred_def = &issuer_wallet.cred_defs[0].public;
+
+    let mut tails_writer = TailsFileWriter::new(None);
+    let (rev_reg_def, _rev_reg_def_pvt, rev_reg, _init_delta) = issuer::create_revocation_registry(
+        &issuer_wallet.did,
+        gvt_cred_def,
+        "tag",
+        RegistryType::CL_ACCUM,
+        IssuanceType::ISSUANCE_BY_DEFAULT,
+        5,
+        &mut tails_writer,
+    )
+    .expect("Error creating revocation registry");
+    let tails_location = match &rev_reg_def {
+        RevocationRegistryDefinition::RevocationRegistryDefinitionV1(def) => {
+            def.value.tails_location.clone()
+        }
+    };
+
+    let (revoked_reg, revoke_delta) = issuer::revoke_credential(
+        &rev_reg_def,
+        &rev_reg,
+        2,
+        &TailsFileReader::new(&tails_location),
+    )
+    .expect("Error revoking credential");
+
+    // issuer side: publish status lists for both registry states
+    let initial_list =
+        RevocationStatusList::new(&rev_reg_def, Some(&rev_reg), &BTreeSet::new(), Some(10))
+            .expect("Error creating revocation status list");
+    let revoked_list = RevocationStatusList::new(
+        &rev_reg_def,
+        Some(&revoked_reg),
+        &[2].iter().cloned().collect(),
+        Some(20),
+    )
+    .expect("Error creating revocation status list");
+    assert_eq!(
+        serde_json::to_value(revoked_list.to_delta(Some(&initial_list)).unwrap()).unwrap(),
+        serde_json::to_value(&revoke_delta).unwrap()
+    );
+
+    // applying the ledger delta reproduces the published status list
+    let updated = initial_list
+        .update_from_delta(&revoke_delta, Some(20))
+        .expect("Error applying delta to status list");
+    assert_eq!(
+        serde_json::to_value(&updated).unwrap(),
+        serde_json::to_value(&revoked_list).unwrap()
+    );
+    assert!(revoked_list.update_from_delta(&revoke_delta, None).is_err());
+
+    // holder side: build a revocation state from the status list alone
+    let list_delta = revoked_list
+        .to_delta(None)
+        .expect("Error converting status list to delta");
+    let delta_json = serde_json::to_value(&list_delta).unwrap();
+    let indices = |field: &str| -> BTreeSet<u32> {
+        serde_json::from_value(delta_json["value"][field].clone()).unwrap()
+    };
+    assert_eq!(indices("issued"), [1, 3, 4, 5].iter().cloned().collect());
+    assert_eq!(indices("revoked"), [2].iter().cloned().collect());
+    let rev_state = prover::create_or_update_revocation_state(
+        TailsFileReader::new(&tails_location),
+        &rev_reg_def,
+        &list_delta,
+        1,
+        20,
+        None,
+    )
+    .expect("Error creating revocation state");
+    assert_eq!(
+        serde_json::to_value(&rev_state).unwrap()["rev_reg"],
+        serde_json::to_value(&revoked_reg).unwrap()["value"]
+    );
+}
+
+#[test]
+fn anoncreds_works_for_credentials_migrated_to_uri_identifiers() {
+    let mut issuer_wallet = IssuerWallet::default();
+    let mut prover_wallet = ProverWallet::default();
+
+    // Issuer publishes legacy objects, with the schema sequence number
+    // assigned by the ledger
+    let gvt_schema = issuer::create_schema(
+        &issuer_wallet.did,
+        GVT_SCHEMA_NAME,
+        "1.0",
+        GVT_SCHEMA_ATTRIBUTES[..].into(),
+        Some(15),
+    )
+    .expect("Error creating gvt schema for issuer");
+    let cred_def_parts = issuer::create_credential_definition(
+        &issuer_wallet.did,
+        &gvt_schema,
+        "tag",
+        SignatureType::CL,
+        CredentialDefinitionConfig {
+            support_revocation: false,
+        },
+    )
+    .expect("Error creating gvt credential definition");
+    issuer_wallet.cred_defs.push(cred_def_parts.into());
+    let gvt_cred_def = &issuer_wallet.cred_defs[0].public;
+
+    // Prover holds a credential issued with the legacy identifiers
+    let cred_offer = issuer::create_credential_offer(
+        gvt_schema.id(),
+        gvt_cred_def,
+        &issuer_wallet.cred_defs[0].key_proof,
+    )
+    .expect("Error creating credential offer");
+    let (cred_request, cred_request_metadata) = prover::create_credential_request(
+        &prover_wallet.did,
+        gvt_cred_def,
+        &prover_wallet.master_secret,
+        "default",
+        &cred_offer,
+    )
+    .expect("Error creating credential request");
+    let mut cred_values = MakeCredentialValues::default();
+    for (name, value) in [
+        ("sex", "male"),
+        ("name", "Alex"),
+        ("height", "175"),
+        ("age", "28"),
+    ] {
+        cred_values
+            .add_raw(name, value)
+            .expect("Error encoding attribute");
+    }
+    let (mut credential, _, _) = issuer::create_credential(
+        gvt_cred_def,
+        &issuer_wallet.cred_defs[0].private,
+        &cred_offer,
+        &cred_request,
+        cred_values.into(),
+        None,
+    )
+    .expect("Error creating credential");
+    prover::process_credential(
+        &mut credential,
+        &cred_request_metadata,
+        &prover_wallet.master_secret,
+        gvt_cred_def,
+        None,
+    )
+    .expect("Error processing credential");
+
+    // All parties migrate their objects to the same namespace
+    let schema =
+        migration::migrate_schema(gvt_schema.clone(), "sovrin").expect("Error migrating schema");
+    let cred_def = migration::migrate_credential_definition(
+        issuer_wallet.cred_defs.remove(0).public,
+        "sovrin",
+    )
+    .expect("Error migrating credential definition");
+    prover_wallet.credentials.push(
+        migration::migrate_credential(credential, "sovrin").expect("Error migrating credential"),
+    );
+    let cred_def_id = cred_def.id().clone();
+    assert_eq!(
+        cred_def_id.0.as_ref(),
+        format!(
+            "did:indy:sovrin:{}/anoncreds/v0/CLAIM_DEF/15/tag",
+            issuer_wallet.did
+        )
+    );
+    assert_eq!(&prover_wallet.credentials[0].schema_id, schema.id());
+    assert_eq!(prover_wallet.credentials[0].cred_def_id, cred_def_id);
+    // migration is idempotent
+    let remigrated =
+        migration::migrate_schema(schema.clone(), "other").expect("Error migrating schema");
+    assert_eq!(remigrated.id(), schema.id());
+
+    let nonce = verifier::generate_nonce().expect("Error generating presentation request nonce");
+    let pres_request = serde_json::from_value(json!({
+        "nonce": nonce,
+        "name":"pres_req_1",
+        "version":"0.1",
+        "requested_attributes":{
+            "attr1_referent":{
+                "name":"name",
+                "restrictions": {"cred_def_id": cred_def_id, "schema_name": GVT_SCHEMA_NAME}
+            }
+        },
+        "requested_predicates":{
+            "predicate1_referent":{"name":"age","p_type":">=","p_value":18}
+        }
+    }))
+    .expect("Error creating proof request");
+
+    let mut present = PresentCredentials::default();
+    {
+        let mut cred1 = present.add_credential(&prover_wallet.credentials[0], None, None);
+        cred1.add_requested_attribute("attr1_referent", true);
+        cred1.add_requested_predicate("predicate1_referent");
+    }
+
+    let mut schemas = HashMap::new();
+    schemas.insert(schema.id().clone(), &schema);
+    let mut cred_defs = HashMap::new();
+    cred_defs.insert(cred_def_id, &cred_def);
+
+    let presentation = prover::create_presentation(
+        &pres_request,
+        present,
+        None,
+        &prover_wallet.master_secret,
+        &schemas,
+        &cred_defs,
+    )
+    .expect("Error creating presentation");
+    let valid = verifier::verify_presentation(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+    )
+    .expect("Error verifying presentation");
+    assert!(valid);
+}
+
+#[test]
+fn anoncreds_works_for_credential_definition_v2() {
+    let issuer_wallet = IssuerWallet::default();
+
+    let gvt_schema = issuer::create_schema(
+        &issuer_wallet.did,
+        GVT_SCHEMA_NAME,
+        "1.0",
+        GVT_SCHEMA_ATTRIBUTES[..].into(),
+        None,
+    )
+    .expect("Error creating gvt schema for issuer");
+
+    let (cred_def, _cred_def_pvt, _key_proof) = issuer::create_credential_definition(
+        &issuer_wallet.did,
+        &gvt_schema,
+        "tag",
+        SignatureType::CL,
+        CredentialDefinitionConfig::default(),
+    )
+    .expect("Error creating gvt credential definition");
+    let cred_def_json = serde_json::to_value(&cred_def).unwrap();
+
+    // Issuer publishes a BBS+ key alongside the existing CL key
+    let mut cred_def_v2 = CredentialDefinitionV2::from_v1(cred_def);
+    cred_def_v2.keys.insert(
+        0,
+        CredentialDefinitionKey::BBS(json!({"publicKey": "bbs-public-key"})),
+    );
+    cred_def_v2
+        .validate()
+        .expect("Error validating credential definition");
+    let v2_json = serde_json::to_value(&cred_def_v2).unwrap();
+    assert_eq!(v2_json["ver"], "2.0");
+    assert_eq!(v2_json["keys"][0]["type"], "BBS+");
+    assert_eq!(v2_json["keys"][1]["type"], "CL");
+    let mut cred_def_v2: CredentialDefinitionV2 =
+        serde_json::from_value(v2_json).expect("Error parsing credential definition");
+    assert_eq!(
+        cred_def_v2.key_types(),
+        vec![
+            CredentialDefinitionKeyType::BBS,
+            CredentialDefinitionKeyType::CL
+        ]
+    );
+
+    // The issuer's preference is used when both types are supported
+    assert_eq!(
+        cred_def_v2.negotiate(&[
+            CredentialDefinitionKeyType::CL,
+            CredentialDefinitionKeyType::BBS
+        ]),
+        Some(CredentialDefinitionKeyType::BBS)
+    );
+    assert_eq!(
+        cred_def_v2.negotiate(&[SignatureType::CL.into()]),
+        Some(CredentialDefinitionKeyType::CL)
+    );
+    assert_eq!(cred_def_v2.negotiate(&[]), None);
+
+    // Holders supporting only CL continue to use the original definition
+    let cred_def_v1 = cred_def_v2.to_v1().expect("Error extracting CL definition");
+    assert_eq!(serde_json::to_value(&cred_def_v1).unwrap(), cred_def_json);
+
+    cred_def_v2
+        .keys
+        .push(CredentialDefinitionKey::BBS(json!({})));
+    assert!(cred_def_v2.validate().is_err());
+}
+
+#[test]
+fn anoncreds_works_for_committed_attributes() {
+    let mut issuer_wallet = IssuerWallet::default();
+    let prover_wallet = ProverWallet::default();
+
+    let gvt_schema = issuer::create_schema(
+        &issuer_wallet.did,
+        GVT_SCHEMA_NAME,
+        "1.0",
+        GVT_SCHEMA_ATTRIBUTES[..].into(),
+        None,
+    )
+    .expect("Error creating gvt schema for issuer");
+    let cred_def_parts = issuer::create_credential_definition(
+        &issuer_wallet.did,
+        &gvt_schema,
+        "tag",
+        SignatureType::CL,
+        CredentialDefinitionConfig::default(),
+    )
+    .expect("Error creating gvt credential definition");
+    issuer_wallet.cred_defs.push(cred_def_parts.into());
+    let gvt_cred_def = &issuer_wallet.cred_defs[0].public;
+    let cred_offer = issuer::create_credential_offer(
+        gvt_schema.id(),
+        gvt_cred_def,
+        &issuer_wallet.cred_defs[0].key_proof,
+    )
+    .expect("Error creating credential offer");
+
+    // Prover commits to its age, which the issuer signs without learning
+    let mut committed = MakeCredentialValues::default();
+    committed
+        .add_raw("age", "28")
+        .expect("Error encoding attribute");
+    let (cred_request, cred_request_metadata) = prover::create_committed_credential_request(
+        &prover_wallet.did,
+        gvt_cred_def,
+        &prover_wallet.master_secret,
+        "default",
+        &cred_offer,
+        committed.into(),
+    )
+    .expect("Error creating credential request");
+    assert_eq!(
+        cred_request.committed_attributes().unwrap(),
+        BTreeSet::from(["age".to_string()])
+    );
+    let request_json = serde_json::to_string(&cred_request).unwrap();
+    assert!(!request_json.contains("\"28\""));
+
+    let issuer_values = |age: Option<&str>| {
+        let mut cred_values = MakeCredentialValues::default();
+        let values = [
+            ("sex", Some("male")),
+            ("name", Some("Alex")),
+            ("height", Some("175")),
+            ("age", age),
+        ];
+        for (name, raw) in values.iter() {
+            if let Some(raw) = raw {
+                cred_values
+                    .add_raw(*name, *raw)
+                    .expect("Error encoding attribute");
+            }
+        }
+        cred_values
+    };
+
+    // The issuer cannot provide a value for a committed attribute
+    let err = issuer::create_credential(
+        gvt_cred_def,
+        &issuer_wallet.cred_defs[0].private,
+        &cred_offer,
+        &cred_request,
+        issuer_values(Some("99")).into(),
+        None,
+    )
+    .expect_err("Committed attribute values cannot be overridden");
+    assert_eq!(err.kind(), ErrorKind::Input);
+
+    let (issue_cred, _, _) = issuer::create_credential(
+        gvt_cred_def,
+        &issuer_wallet.cred_defs[0].private,
+        &cred_offer,
+        &cred_request,
+        issuer_values(None).into(),
+        None,
+    )
+    .expect("Error creating credential");
+    assert!(!issue_cred.values.0.contains_key("age"));
+
+    // Prover adds the committed value to the received credential
+    let (credential, attributes) = prover::process_and_extract_credential(
+        &issue_cred,
+        &cred_offer,
+        &cred_request_metadata,
+        &prover_wallet.master_secret,
+        gvt_cred_def,
+        None,
+    )
+    .expect("Error processing credential");
+    assert_eq!(attributes.get("age").map(String::as_str), Some("28"));
+    assert_eq!(attributes.len(), 4);
+
+    // The committed attribute can be used in predicates like any other
+    let nonce = verifier::generate_nonce().expect("Error generating presentation request nonce");
+    let pres_request = serde_json::from_value(json!({
+        "nonce": nonce,
+        "name":"pres_req_1",
+        "version":"0.1",
+        "requested_attributes":{
+            "attr1_referent":{"name":"name"}
+        },
+        "requested_predicates":{
+            "predicate1_referent":{"name":"age","p_type":">=","p_value":18}
+        }
+    }))
+    .expect("Error creating proof request");
+    let mut present = PresentCredentials::default();
+    {
+        let mut cred = present.add_credential(&credential, None, None);
+        cred.add_requested_attribute("attr1_referent", true);
+        cred.add_requested_predicate("predicate1_referent");
+    }
+    let schemas = HashMap::from([(gvt_schema.id().clone(), &gvt_schema)]);
+    let cred_defs = HashMap::from([(gvt_cred_def.id().clone(), gvt_cred_def)]);
+    let presentation = prover::create_presentation(
+        &pres_request,
+        present,
+        None,
+        &prover_wallet.master_secret,
+        &schemas,
+        &cred_defs,
+    )
+    .expect("Error creating presentation");
+    let valid = verifier::verify_presentation(
+        &presentation,
+        &pres_request,
+        &schemas,
+        &cred_defs,
+        None,
+        None,
+    )
+    .expect("Error verifying presentation");
+    assert!(valid);
 }
 
 /*
@@ -1,9 +1,10 @@
 use crate::identifiers::cred_def::CredentialDefinitionId;
 use crate::identifiers::schema::SchemaId;
 use crate::utils::Qualifiable;
-use crate::{ConversionError, Validatable, ValidationError};
+use crate::{invalid, ConversionError, Validatable, ValidationError};
 
 pub const CL_SIGNATURE_TYPE: &str = "CL";
+pub const BBS_SIGNATURE_TYPE: &str = "BBS+";
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -12,6 +13,7 @@ pub enum SignatureType {
 }
 
 impl SignatureType {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(value: &str) -> Result<Self, ConversionError> {
         match value {
             CL_SIGNATURE_TYPE => Ok(Self::CL),
@@ -26,6 +28,45 @@ impl SignatureType {
     }
 }
 
+/// The type of a key published in a V2 credential definition. Unlike
+/// `SignatureType`, this includes key types which cannot be used by the
+/// issuance, presentation and verification operations.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CredentialDefinitionKeyType {
+    CL,
+    /// BBS+ signatures, which may only be published in a V2 credential
+    /// definition alongside a CL key
+    #[cfg_attr(feature = "serde", serde(rename = "BBS+"))]
+    BBS,
+}
+
+impl CredentialDefinitionKeyType {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(value: &str) -> Result<Self, ConversionError> {
+        match value {
+            CL_SIGNATURE_TYPE => Ok(Self::CL),
+            BBS_SIGNATURE_TYPE => Ok(Self::BBS),
+            _ => Err(ConversionError::from_msg("Invalid key type")),
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match *self {
+            Self::CL => CL_SIGNATURE_TYPE,
+            Self::BBS => BBS_SIGNATURE_TYPE,
+        }
+    }
+}
+
+impl From<SignatureType> for CredentialDefinitionKeyType {
+    fn from(signature_type: SignatureType) -> Self {
+        match signature_type {
+            SignatureType::CL => Self::CL,
+        }
+    }
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CredentialDefinitionData {
@@ -34,6 +75,25 @@ pub struct CredentialDefinitionData {
     pub revocation: Option<ursa_cl!(CredentialRevocationPublicKey)>,
 }
 
+impl CredentialDefinitionData {
+    pub fn try_clone(&self) -> Result<Self, ConversionError> {
+        #[cfg(any(feature = "cl", feature = "cl_native"))]
+        {
+            Ok(Self {
+                primary: self.primary.try_clone().map_err(|e| e.to_string())?,
+                revocation: self.revocation.clone(),
+            })
+        }
+        #[cfg(not(any(feature = "cl", feature = "cl_native")))]
+        {
+            Ok(Self {
+                primary: self.primary.clone(),
+                revocation: self.revocation.clone(),
+            })
+        }
+    }
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(tag = "ver"))]
 pub enum CredentialDefinition {
@@ -79,6 +139,7 @@ impl Validatable for CredentialDefinition {
 )]
 pub struct CredentialDefinitionV1 {
     pub id: CredentialDefinitionId,
+    #[cfg_attr(feature = "serde", serde(alias = "schema_id"))]
     pub schema_id: SchemaId,
     #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub signature_type: SignatureType,
@@ -105,6 +166,137 @@ impl Validatable for CredentialDefinitionV1 {
     }
 }
 
+/// A public key published in a V2 credential definition
+#[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(tag = "type", content = "value")
+)]
+pub enum CredentialDefinitionKey {
+    #[cfg_attr(feature = "serde", serde(rename = "CL"))]
+    CL(Box<CredentialDefinitionData>),
+    /// A BBS+ public key, which is carried without interpretation
+    #[cfg_attr(feature = "serde", serde(rename = "BBS+"))]
+    BBS(serde_json::Value),
+}
+
+impl CredentialDefinitionKey {
+    pub fn key_type(&self) -> CredentialDefinitionKeyType {
+        match self {
+            Self::CL(_) => CredentialDefinitionKeyType::CL,
+            Self::BBS(_) => CredentialDefinitionKeyType::BBS,
+        }
+    }
+}
+
+/// A credential definition publishing public keys for one or more
+/// signature types under a single identifier, so that an issuer may adopt a
+/// new signature type for an existing schema and credential definition ID
+#[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(tag = "ver", rename = "2.0", rename_all = "camelCase")
+)]
+pub struct CredentialDefinitionV2 {
+    pub id: CredentialDefinitionId,
+    pub schema_id: SchemaId,
+    pub tag: String,
+    /// The published keys, in order of the issuer's preference
+    pub keys: Vec<CredentialDefinitionKey>,
+}
+
+impl CredentialDefinitionV2 {
+    /// Wrap a V1 credential definition, publishing its CL key
+    pub fn from_v1(cred_def: CredentialDefinition) -> Self {
+        let CredentialDefinition::CredentialDefinitionV1(cred_def) = cred_def;
+        Self {
+            id: cred_def.id,
+            schema_id: cred_def.schema_id,
+            tag: cred_def.tag,
+            keys: vec![CredentialDefinitionKey::CL(Box::new(cred_def.value))],
+        }
+    }
+
+    /// The published key types, in order of the issuer's preference
+    pub fn key_types(&self) -> Vec<CredentialDefinitionKeyType> {
+        self.keys.iter().map(|key| key.key_type()).collect()
+    }
+
+    pub fn get_key(
+        &self,
+        key_type: CredentialDefinitionKeyType,
+    ) -> Option<&CredentialDefinitionKey> {
+        self.keys.iter().find(|key| key.key_type() == key_type)
+    }
+
+    /// Select the key type to use with a party supporting the given key
+    /// types: the first published type which is supported
+    pub fn negotiate(
+        &self,
+        supported: &[CredentialDefinitionKeyType],
+    ) -> Option<CredentialDefinitionKeyType> {
+        self.keys
+            .iter()
+            .map(|key| key.key_type())
+            .find(|key_type| supported.contains(key_type))
+    }
+
+    /// Extract the V1 credential definition for the CL key, as accepted by
+    /// the issuance, presentation and verification operations
+    pub fn to_v1(&self) -> Result<CredentialDefinition, ConversionError> {
+        let value = match self.get_key(CredentialDefinitionKeyType::CL) {
+            Some(CredentialDefinitionKey::CL(value)) => value.as_ref().try_clone()?,
+            _ => {
+                return Err(ConversionError::from_msg(
+                    "Credential definition does not publish a CL key",
+                ))
+            }
+        };
+        Ok(CredentialDefinition::CredentialDefinitionV1(
+            CredentialDefinitionV1 {
+                id: self.id.clone(),
+                schema_id: self.schema_id.clone(),
+                signature_type: SignatureType::CL,
+                tag: self.tag.clone(),
+                value,
+            },
+        ))
+    }
+
+    pub fn to_unqualified(self) -> CredentialDefinitionV2 {
+        CredentialDefinitionV2 {
+            id: self.id.to_unqualified(),
+            schema_id: self.schema_id.to_unqualified(),
+            tag: self.tag,
+            keys: self.keys,
+        }
+    }
+}
+
+impl Validatable for CredentialDefinitionV2 {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.id.validate()?;
+        self.schema_id.validate()?;
+        if self.keys.is_empty() {
+            return Err(invalid!(
+                "Credential definition must publish at least one key"
+            ));
+        }
+        let key_types = self.key_types();
+        for (idx, key_type) in key_types.iter().enumerate() {
+            if key_types[..idx].contains(key_type) {
+                return Err(invalid!(
+                    "Credential definition publishes more than one {} key",
+                    key_type.to_str()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct CredentialDefinitionPrivate {
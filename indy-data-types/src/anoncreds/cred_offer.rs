@@ -7,11 +7,15 @@ use crate::{Validatable, ValidationError};
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct CredentialOffer {
+    #[cfg_attr(feature = "serde", serde(alias = "schemaId"))]
     pub schema_id: SchemaId,
+    #[cfg_attr(feature = "serde", serde(alias = "credDefId"))]
     pub cred_def_id: CredentialDefinitionId,
+    #[cfg_attr(feature = "serde", serde(alias = "keyCorrectnessProof"))]
     pub key_correctness_proof: ursa_cl!(CredentialKeyCorrectnessProof),
     pub nonce: Nonce,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(feature = "serde", serde(alias = "methodName"))]
     pub method_name: Option<String>,
 }
 
@@ -1,15 +1,29 @@
+#[cfg(any(feature = "cl", feature = "cl_native"))]
+use std::collections::BTreeSet;
+
+use super::credential::CredentialValues;
 use super::nonce::Nonce;
 use crate::identifiers::cred_def::CredentialDefinitionId;
 use crate::utils::Qualifiable;
+#[cfg(any(feature = "cl", feature = "cl_native"))]
+use crate::ConversionError;
 use crate::{Validatable, ValidationError};
 use indy_utils::did::DidValue;
 
+/// The hidden attribute holding the link secret of the holder
+#[cfg(any(feature = "cl", feature = "cl_native"))]
+const MASTER_SECRET_ATTRIBUTE: &str = "master_secret";
+
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct CredentialRequest {
+    #[cfg_attr(feature = "serde", serde(alias = "proverDid"))]
     pub prover_did: DidValue,
+    #[cfg_attr(feature = "serde", serde(alias = "credDefId"))]
     pub cred_def_id: CredentialDefinitionId,
+    #[cfg_attr(feature = "serde", serde(alias = "blindedMs"))]
     pub blinded_ms: ursa_cl!(BlindedCredentialSecrets),
+    #[cfg_attr(feature = "serde", serde(alias = "blindedMsCorrectnessProof"))]
     pub blinded_ms_correctness_proof: ursa_cl!(BlindedCredentialSecretsCorrectnessProof),
     pub nonce: Nonce,
 }
@@ -25,6 +39,25 @@ impl CredentialRequest {
             nonce: self.nonce,
         }
     }
+
+    /// The names of the attributes committed by the holder, which the issuer
+    /// signs blindly and must not provide values for. These are read from
+    /// the blinded secrets, as only they determine the signed values.
+    ///
+    /// The CL library has no accessor for the hidden attributes, so they are
+    /// read from the serialized blinded secrets. This layout is private to
+    /// the library and is pinned by the exact version dependency on `ursa`.
+    #[cfg(any(feature = "cl", feature = "cl_native"))]
+    pub fn committed_attributes(&self) -> Result<BTreeSet<String>, ConversionError> {
+        let value = serde_json::to_value(&self.blinded_ms).map_err(ConversionError::from_err)?;
+        let hidden = value
+            .get("hidden_attributes")
+            .ok_or_else(|| ConversionError::from_msg("Hidden attributes not found"))?;
+        let mut hidden: BTreeSet<String> =
+            serde_json::from_value(hidden.clone()).map_err(ConversionError::from_err)?;
+        hidden.remove(MASTER_SECRET_ATTRIBUTE);
+        Ok(hidden)
+    }
 }
 
 impl Validatable for CredentialRequest {
@@ -38,9 +71,45 @@ impl Validatable for CredentialRequest {
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct CredentialRequestMetadata {
+    #[cfg_attr(feature = "serde", serde(alias = "masterSecretBlindingData"))]
     pub master_secret_blinding_data: ursa_cl!(CredentialSecretsBlindingFactors),
     pub nonce: Nonce,
+    #[cfg_attr(feature = "serde", serde(alias = "masterSecretName"))]
     pub master_secret_name: String,
+    /// Previous names of the master secret, oldest first
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    #[cfg_attr(feature = "serde", serde(alias = "masterSecretAliases"))]
+    pub master_secret_aliases: Vec<String>,
+    /// The attribute values committed by the holder in the request, which
+    /// are added to the credential once it is received
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    #[cfg_attr(feature = "serde", serde(alias = "committedValues"))]
+    pub committed_values: Option<CredentialValues>,
+}
+
+impl CredentialRequestMetadata {
+    /// Check whether the master secret is known by the given name, either
+    /// currently or before being renamed
+    pub fn has_master_secret_name(&self, name: &str) -> bool {
+        self.master_secret_name == name || self.master_secret_aliases.iter().any(|a| a == name)
+    }
+
+    /// Rename the master secret, recording the previous name as an alias
+    pub fn rename_master_secret(&mut self, name: impl Into<String>) {
+        let name = name.into();
+        if name == self.master_secret_name {
+            return;
+        }
+        self.master_secret_aliases.retain(|alias| *alias != name);
+        let prev = std::mem::replace(&mut self.master_secret_name, name);
+        self.master_secret_aliases.push(prev);
+    }
 }
 
 impl Validatable for CredentialRequestMetadata {}
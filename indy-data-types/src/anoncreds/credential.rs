@@ -5,19 +5,31 @@ use zeroize::Zeroize;
 use crate::identifiers::cred_def::CredentialDefinitionId;
 use crate::identifiers::rev_reg::RevocationRegistryId;
 use crate::identifiers::schema::SchemaId;
-use crate::{Validatable, ValidationError};
+use crate::{ConversionError, Validatable, ValidationError};
 
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Credential {
+    #[cfg_attr(feature = "serde", serde(alias = "schemaId"))]
     pub schema_id: SchemaId,
+    #[cfg_attr(feature = "serde", serde(alias = "credDefId"))]
     pub cred_def_id: CredentialDefinitionId,
+    #[cfg_attr(feature = "serde", serde(alias = "revRegId"))]
     pub rev_reg_id: Option<RevocationRegistryId>,
     pub values: CredentialValues,
     pub signature: ursa_cl!(CredentialSignature),
+    #[cfg_attr(feature = "serde", serde(alias = "signatureCorrectnessProof"))]
     pub signature_correctness_proof: ursa_cl!(SignatureCorrectnessProof),
+    #[cfg_attr(feature = "serde", serde(alias = "revReg"))]
     pub rev_reg: Option<ursa_cl!(RevocationRegistry)>,
     pub witness: Option<ursa_cl!(Witness)>,
+    /// Display hints for the attribute values, which are not covered by the
+    /// signature and may be replaced by the holder
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub display: Option<CredentialDisplay>,
 }
 
 #[cfg(any(feature = "cl", feature = "cl_native"))]
@@ -35,6 +47,7 @@ impl Credential {
                 .map_err(|e| e.to_string())?,
             rev_reg: self.rev_reg.clone(),
             witness: self.witness.clone(),
+            display: self.display.clone(),
         })
     }
 }
@@ -47,6 +60,26 @@ impl Credential {
         "schema_issuer_did",
         "rev_reg_id",
     ];
+
+    /// The display hints for an attribute, if any were provided
+    pub fn attribute_display(&self, name: &str) -> Option<&AttributeDisplay> {
+        self.display
+            .as_ref()
+            .and_then(|display| display.0.get(name))
+    }
+
+    /// Replace the display hints of the credential. Hints may only be given
+    /// for attributes contained in the credential values.
+    pub fn set_display(
+        &mut self,
+        display: Option<CredentialDisplay>,
+    ) -> Result<(), ValidationError> {
+        if let Some(display) = display.as_ref() {
+            display.validate_for(&self.values)?;
+        }
+        self.display = display;
+        Ok(())
+    }
 }
 
 impl Validatable for Credential {
@@ -63,6 +96,10 @@ impl Validatable for Credential {
             return Err("Credential validation failed: `values` is empty".into());
         }
 
+        if let Some(display) = self.display.as_ref() {
+            display.validate_for(&self.values)?;
+        }
+
         Ok(())
     }
 }
@@ -72,9 +109,13 @@ impl Validatable for Credential {
 pub struct CredentialInfo {
     pub referent: String,
     pub attrs: ShortCredentialValues,
+    #[cfg_attr(feature = "serde", serde(alias = "schemaId"))]
     pub schema_id: SchemaId,
+    #[cfg_attr(feature = "serde", serde(alias = "credDefId"))]
     pub cred_def_id: CredentialDefinitionId,
+    #[cfg_attr(feature = "serde", serde(alias = "revRegId"))]
     pub rev_reg_id: Option<RevocationRegistryId>,
+    #[cfg_attr(feature = "serde", serde(alias = "credRevId"))]
     pub cred_rev_id: Option<String>,
 }
 
@@ -112,5 +153,246 @@ impl Zeroize for CredentialValues {
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct AttributeValues {
     pub raw: String,
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "deserialize_encoded_value")
+    )]
     pub encoded: String,
 }
+
+/// Display hints for the attributes of a credential, indexed by attribute name
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct CredentialDisplay(pub HashMap<String, AttributeDisplay>);
+
+impl CredentialDisplay {
+    /// Add a label for an attribute in the given language
+    pub fn label(
+        &mut self,
+        attr: impl Into<String>,
+        lang: impl Into<String>,
+        label: impl Into<String>,
+    ) -> &mut Self {
+        self.0
+            .entry(attr.into())
+            .or_default()
+            .labels
+            .insert(lang.into(), label.into());
+        self
+    }
+
+    /// Set the MIME type of an attribute value
+    pub fn mime_type(
+        &mut self,
+        attr: impl Into<String>,
+        mime_type: impl Into<String>,
+    ) -> &mut Self {
+        self.0.entry(attr.into()).or_default().mime_type = Some(mime_type.into());
+        self
+    }
+
+    /// Set the format of an attribute value
+    pub fn format(&mut self, attr: impl Into<String>, format: impl Into<String>) -> &mut Self {
+        self.0.entry(attr.into()).or_default().format = Some(format.into());
+        self
+    }
+
+    fn validate_for(&self, values: &CredentialValues) -> Result<(), ValidationError> {
+        for name in self.0.keys() {
+            if !values.0.contains_key(name) {
+                return Err(format!(
+                    "Credential validation failed: display hints given for unknown attribute: {}",
+                    name
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Display hints for a single attribute value
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct AttributeDisplay {
+    /// Labels for the attribute, indexed by language tag
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "HashMap::is_empty")
+    )]
+    pub labels: HashMap<String, String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, rename = "mime-type", skip_serializing_if = "Option::is_none")
+    )]
+    pub mime_type: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub format: Option<String>,
+}
+
+impl AttributeDisplay {
+    /// The label for a language, if one was provided
+    pub fn label(&self, lang: &str) -> Option<&str> {
+        self.labels.get(lang).map(String::as_str)
+    }
+}
+
+impl AttributeValues {
+    /// The encoded value in canonical decimal form, without loss of precision
+    pub fn encoded_value(&self) -> Result<String, ConversionError> {
+        normalize_encoded_value(&self.encoded)
+    }
+}
+
+/// Convert an encoded attribute value to its canonical decimal form by
+/// removing any leading zeros. Encoded values are integers of arbitrary size
+/// and are never converted to a fixed-width type.
+pub fn normalize_encoded_value(value: &str) -> Result<String, ConversionError> {
+    let (sign, digits) = match value.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", value),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid encoded attribute value: {:?}", value).into());
+    }
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        Ok("0".to_string())
+    } else {
+        Ok(format!("{}{}", sign, digits))
+    }
+}
+
+/// Deserialize an encoded attribute value given as a decimal string or as a
+/// JSON integer. Integers which may not be represented exactly are rejected,
+/// as these can only be transported losslessly as strings.
+#[cfg(feature = "serde")]
+pub(crate) fn deserialize_encoded_value<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::{Error, Visitor};
+    use std::fmt;
+
+    struct EncodedValueVisitor;
+
+    impl<'de> Visitor<'de> for EncodedValueVisitor {
+        type Value = String;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("integer or decimal string")
+        }
+
+        fn visit_i64<E: Error>(self, value: i64) -> Result<String, E> {
+            Ok(value.to_string())
+        }
+
+        fn visit_u64<E: Error>(self, value: u64) -> Result<String, E> {
+            Ok(value.to_string())
+        }
+
+        fn visit_i128<E: Error>(self, value: i128) -> Result<String, E> {
+            Ok(value.to_string())
+        }
+
+        fn visit_u128<E: Error>(self, value: u128) -> Result<String, E> {
+            Ok(value.to_string())
+        }
+
+        fn visit_f64<E: Error>(self, value: f64) -> Result<String, E> {
+            // integers beyond 2^53 may have been rounded by the parser
+            const MAX_EXACT: f64 = 9_007_199_254_740_992.0;
+            if value.fract() == 0.0 && value.abs() <= MAX_EXACT {
+                Ok((value as i64).to_string())
+            } else {
+                Err(E::custom(
+                    "Encoded attribute value exceeds the precision of a JSON number, \
+                    it must be provided as a string",
+                ))
+            }
+        }
+
+        fn visit_str<E: Error>(self, value: &str) -> Result<String, E> {
+            normalize_encoded_value(value).map_err(E::custom)?;
+            Ok(value.to_string())
+        }
+    }
+
+    deserializer.deserialize_any(EncodedValueVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_encoded_values() {
+        let big = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        assert_eq!(normalize_encoded_value(big).unwrap(), big);
+        assert_eq!(normalize_encoded_value("000123").unwrap(), "123");
+        assert_eq!(normalize_encoded_value("-0042").unwrap(), "-42");
+        assert_eq!(normalize_encoded_value("000").unwrap(), "0");
+        assert!(normalize_encoded_value("").is_err());
+        assert!(normalize_encoded_value("12a").is_err());
+        assert!(normalize_encoded_value("1.5").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_encoded_values() {
+        let big = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        let values: AttributeValues =
+            serde_json::from_value(json!({"raw": "x", "encoded": big})).unwrap();
+        assert_eq!(values.encoded, big);
+        let values: AttributeValues =
+            serde_json::from_value(json!({"raw": "28", "encoded": 28})).unwrap();
+        assert_eq!(values.encoded, "28");
+        assert!(serde_json::from_str::<AttributeValues>(&format!(
+            r#"{{"raw":"x","encoded":{}}}"#,
+            big
+        ))
+        .is_err());
+        assert!(
+            serde_json::from_value::<AttributeValues>(json!({"raw": "x", "encoded": "abc"}))
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn credential_display_hints() {
+        let mut display = CredentialDisplay::default();
+        display
+            .label("name", "en", "Name")
+            .label("name", "fr", "Nom")
+            .mime_type("photo", "image/png")
+            .format("photo", "base64");
+        let json = serde_json::to_value(&display).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "name": {"labels": {"en": "Name", "fr": "Nom"}},
+                "photo": {"mime-type": "image/png", "format": "base64"}
+            })
+        );
+        let parsed: CredentialDisplay = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, display);
+        assert_eq!(parsed.0["name"].label("fr"), Some("Nom"));
+
+        let mut values = HashMap::new();
+        values.insert(
+            "name".to_string(),
+            AttributeValues {
+                raw: "Alex".to_string(),
+                encoded: "1".to_string(),
+            },
+        );
+        let values = CredentialValues(values);
+        assert!(display.validate_for(&values).is_err());
+        display.0.remove("photo");
+        assert!(display.validate_for(&values).is_ok());
+    }
+}
@@ -0,0 +1,208 @@
+//! Conversions between the credential objects and the underlying ursa CL
+//! structures, for protocols which operate on the CL primitives directly.
+//!
+//! Most ursa structures cannot be cloned infallibly, so conversions from a
+//! borrowed object are provided through `TryFrom`. Objects which only wrap a
+//! single ursa structure may also be converted by value in either direction.
+
+use std::convert::TryFrom;
+
+use super::cred_def::{
+    CredentialDefinition, CredentialDefinitionData, CredentialDefinitionPrivate,
+    CredentialKeyCorrectnessProof,
+};
+use super::cred_request::CredentialRequest;
+use super::credential::Credential;
+use super::master_secret::MasterSecret;
+use super::nonce::Nonce;
+use super::presentation::Presentation;
+use super::rev_reg::RevocationRegistry;
+use super::rev_reg_def::RevocationRegistryDefinition;
+use crate::ursa::cl;
+use crate::ConversionError;
+
+impl TryFrom<&CredentialDefinition> for cl::CredentialPublicKey {
+    type Error = ConversionError;
+
+    fn try_from(cred_def: &CredentialDefinition) -> Result<Self, Self::Error> {
+        match cred_def {
+            CredentialDefinition::CredentialDefinitionV1(cred_def) => cred_def.get_public_key(),
+        }
+    }
+}
+
+impl TryFrom<&cl::CredentialPublicKey> for CredentialDefinitionData {
+    type Error = ConversionError;
+
+    fn try_from(key: &cl::CredentialPublicKey) -> Result<Self, Self::Error> {
+        Ok(Self {
+            primary: key.get_primary_key().map_err(|e| e.to_string())?,
+            revocation: key.get_revocation_key().map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+impl From<CredentialDefinitionPrivate> for cl::CredentialPrivateKey {
+    fn from(cred_def_private: CredentialDefinitionPrivate) -> Self {
+        cred_def_private.value
+    }
+}
+
+impl From<cl::CredentialPrivateKey> for CredentialDefinitionPrivate {
+    fn from(value: cl::CredentialPrivateKey) -> Self {
+        Self { value }
+    }
+}
+
+impl TryFrom<&CredentialKeyCorrectnessProof> for cl::CredentialKeyCorrectnessProof {
+    type Error = ConversionError;
+
+    fn try_from(proof: &CredentialKeyCorrectnessProof) -> Result<Self, Self::Error> {
+        proof.value.try_clone().map_err(|e| e.to_string().into())
+    }
+}
+
+impl From<cl::CredentialKeyCorrectnessProof> for CredentialKeyCorrectnessProof {
+    fn from(value: cl::CredentialKeyCorrectnessProof) -> Self {
+        Self { value }
+    }
+}
+
+impl TryFrom<&CredentialRequest>
+    for (
+        cl::BlindedCredentialSecrets,
+        cl::BlindedCredentialSecretsCorrectnessProof,
+    )
+{
+    type Error = ConversionError;
+
+    fn try_from(cred_request: &CredentialRequest) -> Result<Self, Self::Error> {
+        Ok((
+            cred_request
+                .blinded_ms
+                .try_clone()
+                .map_err(|e| e.to_string())?,
+            cred_request
+                .blinded_ms_correctness_proof
+                .try_clone()
+                .map_err(|e| e.to_string())?,
+        ))
+    }
+}
+
+impl TryFrom<&Credential> for (cl::CredentialSignature, cl::SignatureCorrectnessProof) {
+    type Error = ConversionError;
+
+    fn try_from(credential: &Credential) -> Result<Self, Self::Error> {
+        Ok((
+            credential
+                .signature
+                .try_clone()
+                .map_err(|e| e.to_string())?,
+            credential
+                .signature_correctness_proof
+                .try_clone()
+                .map_err(|e| e.to_string())?,
+        ))
+    }
+}
+
+impl TryFrom<&MasterSecret> for cl::MasterSecret {
+    type Error = ConversionError;
+
+    fn try_from(master_secret: &MasterSecret) -> Result<Self, Self::Error> {
+        master_secret
+            .value
+            .try_clone()
+            .map_err(|e| e.to_string().into())
+    }
+}
+
+impl From<cl::MasterSecret> for MasterSecret {
+    fn from(value: cl::MasterSecret) -> Self {
+        Self { value }
+    }
+}
+
+impl TryFrom<&Nonce> for cl::Nonce {
+    type Error = ConversionError;
+
+    fn try_from(nonce: &Nonce) -> Result<Self, Self::Error> {
+        nonce
+            .as_native()
+            .try_clone()
+            .map_err(|e| e.to_string().into())
+    }
+}
+
+impl TryFrom<cl::Nonce> for Nonce {
+    type Error = ConversionError;
+
+    fn try_from(native: cl::Nonce) -> Result<Self, Self::Error> {
+        Self::from_native(native)
+    }
+}
+
+impl From<Presentation> for cl::Proof {
+    fn from(presentation: Presentation) -> Self {
+        presentation.proof
+    }
+}
+
+impl From<&RevocationRegistry> for cl::RevocationRegistry {
+    fn from(rev_reg: &RevocationRegistry) -> Self {
+        match rev_reg {
+            RevocationRegistry::RevocationRegistryV1(rev_reg) => rev_reg.value.clone(),
+        }
+    }
+}
+
+impl From<&RevocationRegistryDefinition> for cl::RevocationKeyPublic {
+    fn from(rev_reg_def: &RevocationRegistryDefinition) -> Self {
+        match rev_reg_def {
+            RevocationRegistryDefinition::RevocationRegistryDefinitionV1(rev_reg_def) => {
+                rev_reg_def.value.public_keys.accum_key.clone()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ursa::cl::issuer::Issuer;
+
+    #[test]
+    fn public_key_round_trip() {
+        let mut schema = Issuer::new_credential_schema_builder().unwrap();
+        schema.add_attr("name").unwrap();
+        let mut non_schema = Issuer::new_non_credential_schema_builder().unwrap();
+        non_schema.add_attr("master_secret").unwrap();
+        let (public_key, _private_key, _proof) = Issuer::new_credential_def(
+            &schema.finalize().unwrap(),
+            &non_schema.finalize().unwrap(),
+            false,
+        )
+        .unwrap();
+
+        let data = CredentialDefinitionData::try_from(&public_key).unwrap();
+        assert!(data.revocation.is_none());
+        let cred_def = CredentialDefinition::CredentialDefinitionV1(
+            crate::anoncreds::cred_def::CredentialDefinitionV1 {
+                id: "NcYxiDXkpYi6ov5FcYDi1e:3:CL:1:tag".to_string().into(),
+                schema_id: "1".to_string().into(),
+                signature_type: crate::anoncreds::cred_def::SignatureType::CL,
+                tag: "tag".to_string(),
+                value: data,
+            },
+        );
+        assert_eq!(
+            cl::CredentialPublicKey::try_from(&cred_def).unwrap(),
+            public_key
+        );
+
+        let nonce = Nonce::new().unwrap();
+        let native = cl::Nonce::try_from(&nonce).unwrap();
+        assert_eq!(Nonce::try_from(native).unwrap(), nonce);
+    }
+}
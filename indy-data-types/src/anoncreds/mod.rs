@@ -13,6 +13,10 @@ pub mod cred_request;
 /// Credentials
 pub mod credential;
 
+/// Conversions to and from the ursa CL structures
+#[cfg(all(feature = "ursa-interop", any(feature = "cl", feature = "cl_native")))]
+mod interop;
+
 /// Identity master secret
 #[cfg(any(feature = "cl", feature = "cl_native"))]
 pub mod master_secret;
@@ -26,12 +30,22 @@ pub mod pres_request;
 /// Presentations
 pub mod presentation;
 
+/// Restrictions of presentation requests
+pub mod restrictions;
+
+/// Field naming conventions for serialized objects
+#[cfg(feature = "serde")]
+pub mod profile;
+
 /// Revocation registries
 pub mod rev_reg;
 
 /// Revocation registry definitions
 pub mod rev_reg_def;
 
+/// Revocation status lists
+pub mod rev_status_list;
+
 #[cfg(any(feature = "rich_schema", test))]
 /// Rich schemas
 pub mod rich_schema;
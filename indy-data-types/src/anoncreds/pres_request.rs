@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 use std::fmt;
+use std::str::FromStr;
 
 #[cfg(feature = "serde")]
-use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use serde::{de, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
 #[cfg(feature = "serde")]
 use serde_json::Value;
 
@@ -12,7 +13,7 @@ use crate::identifiers::cred_def::CredentialDefinitionId;
 use crate::identifiers::rev_reg::RevocationRegistryId;
 use crate::identifiers::schema::SchemaId;
 use crate::utils::{qualifiable, Qualifiable};
-use crate::{Validatable, ValidationError};
+use crate::{ConversionError, Validatable, ValidationError};
 use indy_utils::did::DidValue;
 use indy_utils::invalid;
 use indy_utils::wql::Query;
@@ -24,10 +25,54 @@ pub struct PresentationRequestPayload {
     pub name: String,
     pub version: String,
     #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(alias = "requestedAttributes"))]
     pub requested_attributes: HashMap<String, AttributeInfo>,
     #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(alias = "requestedPredicates"))]
     pub requested_predicates: HashMap<String, PredicateInfo>,
+    /// Attributes for which the range of a set of integers containing the
+    /// value is disclosed, instead of the value itself
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "HashMap::is_empty")
+    )]
+    #[cfg_attr(feature = "serde", serde(alias = "requestedRangeDisclosures"))]
+    pub requested_range_disclosures: HashMap<String, RangeDisclosureInfo>,
+    #[cfg_attr(feature = "serde", serde(alias = "nonRevoked"))]
     pub non_revoked: Option<NonRevocedInterval>,
+    /// Groups of attribute and predicate referents which must each be
+    /// satisfied by a single credential
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    #[cfg_attr(feature = "serde", serde(alias = "sameCredential"))]
+    pub same_credential: Vec<Vec<String>>,
+    /// Groups of attribute referents, presented from different credentials,
+    /// whose values must be proven equal without being revealed. The
+    /// referents of a group request the same attribute name.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    #[cfg_attr(feature = "serde", serde(alias = "equalAttributes"))]
+    pub equal_attributes: Vec<Vec<String>>,
+    /// Attribute referents, presented unrevealed, whose values must be
+    /// verifiably encrypted to the key of an auditor
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "HashMap::is_empty")
+    )]
+    #[cfg_attr(feature = "serde", serde(alias = "escrowedAttributes"))]
+    pub escrowed_attributes: HashMap<String, EscrowInfo>,
+    /// The domain of the pseudonym of the link secret to be presented, such
+    /// as an identifier of the verifier
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    #[cfg_attr(feature = "serde", serde(alias = "pseudonymDomain"))]
+    pub pseudonym_domain: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -42,8 +87,40 @@ pub enum PresentationRequestVersion {
     V2,
 }
 
+impl PresentationRequestVersion {
+    /// The value of the `ver` property for this version
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PresentationRequestVersion::V1 => "1.0",
+            PresentationRequestVersion::V2 => "2.0",
+        }
+    }
+}
+
+impl fmt::Display for PresentationRequestVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PresentationRequestVersion {
+    type Err = ConversionError;
+
+    fn from_str(ver: &str) -> Result<Self, Self::Err> {
+        match ver {
+            "1.0" => Ok(PresentationRequestVersion::V1),
+            "2.0" => Ok(PresentationRequestVersion::V2),
+            _ => Err(format!(
+                "Unsupported presentation request version: {:?}, expected \"1.0\" or \"2.0\"",
+                ver
+            )
+            .into()),
+        }
+    }
+}
+
 impl PresentationRequest {
-    pub fn value<'a>(&'a self) -> &'a PresentationRequestPayload {
+    pub fn value(&self) -> &PresentationRequestPayload {
         match self {
             PresentationRequest::PresentationRequestV1(req) => req,
             PresentationRequest::PresentationRequestV2(req) => req,
@@ -56,6 +133,28 @@ impl PresentationRequest {
             PresentationRequest::PresentationRequestV2(_) => PresentationRequestVersion::V2,
         }
     }
+
+    pub fn into_value(self) -> PresentationRequestPayload {
+        match self {
+            PresentationRequest::PresentationRequestV1(req) => req,
+            PresentationRequest::PresentationRequestV2(req) => req,
+        }
+    }
+
+    /// Convert the request to another version. Fully qualified identifiers in
+    /// the restrictions are replaced by their unqualified forms when
+    /// converting to version 1.0, which does not support them. Unqualified
+    /// identifiers remain valid in version 2.0 and are left unchanged.
+    pub fn into_version(self, version: PresentationRequestVersion) -> PresentationRequest {
+        match version {
+            PresentationRequestVersion::V1 => {
+                PresentationRequest::PresentationRequestV1(self.to_unqualified().into_value())
+            }
+            PresentationRequestVersion::V2 => {
+                PresentationRequest::PresentationRequestV2(self.into_value())
+            }
+        }
+    }
 }
 
 #[cfg(feature = "serde")]
@@ -73,27 +172,22 @@ impl<'de> Deserialize<'de> for PresentationRequest {
 
         let helper = Helper::deserialize(&v).map_err(de::Error::custom)?;
 
-        let req = match helper.ver {
-            Some(version) => match version.as_ref() {
-                "1.0" => {
-                    let request =
-                        PresentationRequestPayload::deserialize(v).map_err(de::Error::custom)?;
-                    PresentationRequest::PresentationRequestV1(request)
-                }
-                "2.0" => {
-                    let request =
-                        PresentationRequestPayload::deserialize(v).map_err(de::Error::custom)?;
-                    PresentationRequest::PresentationRequestV2(request)
-                }
-                _ => return Err(de::Error::unknown_variant(&version, &["2.0"])),
-            },
-            None => {
-                let request =
-                    PresentationRequestPayload::deserialize(v).map_err(de::Error::custom)?;
-                PresentationRequest::PresentationRequestV1(request)
-            }
+        let version = match helper.ver {
+            Some(ver) => ver
+                .parse::<PresentationRequestVersion>()
+                .map_err(de::Error::custom)?,
+            None => PresentationRequestVersion::V1,
         };
-        Ok(req)
+        let request = PresentationRequestPayload::deserialize(v).map_err(|err| {
+            de::Error::custom(format!(
+                "Invalid presentation request (version {}): {}",
+                version, err
+            ))
+        })?;
+        Ok(match version {
+            PresentationRequestVersion::V1 => PresentationRequest::PresentationRequestV1(request),
+            PresentationRequestVersion::V2 => PresentationRequest::PresentationRequestV2(request),
+        })
     }
 }
 
@@ -103,26 +197,61 @@ impl Serialize for PresentationRequest {
     where
         S: Serializer,
     {
-        let value = match self {
-            PresentationRequest::PresentationRequestV1(v1) => {
-                let mut value = ::serde_json::to_value(v1).map_err(ser::Error::custom)?;
-                value
-                    .as_object_mut()
-                    .unwrap()
-                    .insert("ver".into(), Value::from("1.0"));
-                value
-            }
-            PresentationRequest::PresentationRequestV2(v2) => {
-                let mut value = ::serde_json::to_value(v2).map_err(ser::Error::custom)?;
-                value
-                    .as_object_mut()
-                    .unwrap()
-                    .insert("ver".into(), Value::from("2.0"));
-                value
-            }
-        };
-
-        value.serialize(serializer)
+        // Fields are written in sorted order as a struct rather than a map,
+        // so that serialization profiles may rename them
+        let req = self.value();
+        let mut len = 7;
+        if !req.same_credential.is_empty() {
+            len += 1;
+        }
+        if !req.equal_attributes.is_empty() {
+            len += 1;
+        }
+        if !req.requested_range_disclosures.is_empty() {
+            len += 1;
+        }
+        if !req.escrowed_attributes.is_empty() {
+            len += 1;
+        }
+        if req.pseudonym_domain.is_some() {
+            len += 1;
+        }
+        let mut state = serializer.serialize_struct("PresentationRequestPayload", len)?;
+        if req.equal_attributes.is_empty() {
+            state.skip_field("equal_attributes")?;
+        } else {
+            state.serialize_field("equal_attributes", &req.equal_attributes)?;
+        }
+        if req.escrowed_attributes.is_empty() {
+            state.skip_field("escrowed_attributes")?;
+        } else {
+            state.serialize_field("escrowed_attributes", &req.escrowed_attributes)?;
+        }
+        state.serialize_field("name", &req.name)?;
+        state.serialize_field("non_revoked", &req.non_revoked)?;
+        state.serialize_field("nonce", &req.nonce)?;
+        match &req.pseudonym_domain {
+            Some(domain) => state.serialize_field("pseudonym_domain", domain)?,
+            None => state.skip_field("pseudonym_domain")?,
+        }
+        state.serialize_field("requested_attributes", &req.requested_attributes)?;
+        state.serialize_field("requested_predicates", &req.requested_predicates)?;
+        if req.requested_range_disclosures.is_empty() {
+            state.skip_field("requested_range_disclosures")?;
+        } else {
+            state.serialize_field(
+                "requested_range_disclosures",
+                &req.requested_range_disclosures,
+            )?;
+        }
+        if req.same_credential.is_empty() {
+            state.skip_field("same_credential")?;
+        } else {
+            state.serialize_field("same_credential", &req.same_credential)?;
+        }
+        state.serialize_field("ver", self.version().as_str())?;
+        state.serialize_field("version", &req.version)?;
+        state.end()
     }
 }
 
@@ -144,6 +273,7 @@ pub struct AttributeInfo {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub names: Option<Vec<String>>,
     pub restrictions: Option<Query>,
+    #[cfg_attr(feature = "serde", serde(alias = "nonRevoked"))]
     pub non_revoked: Option<NonRevocedInterval>,
 }
 
@@ -151,12 +281,81 @@ pub struct AttributeInfo {
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct PredicateInfo {
     pub name: String,
+    #[cfg_attr(feature = "serde", serde(alias = "pType"))]
     pub p_type: PredicateTypes,
+    #[cfg_attr(feature = "serde", serde(alias = "pValue"))]
     pub p_value: i32,
     pub restrictions: Option<Query>,
+    #[cfg_attr(feature = "serde", serde(alias = "nonRevoked"))]
     pub non_revoked: Option<NonRevocedInterval>,
 }
 
+/// A request for a verifiably encrypted copy of the encoded value of an
+/// attribute, which only the designated auditor may decrypt
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct EscrowInfo {
+    /// The Paillier public key of the auditor, as its decimal modulus
+    #[cfg_attr(feature = "serde", serde(alias = "auditorKey"))]
+    pub auditor_key: String,
+}
+
+/// A request to disclose the range of a set of integers which contains the
+/// encoded value of an attribute.
+///
+/// This is not a zero-knowledge proof of set membership, which CL proofs
+/// cannot express. The set is split into its runs of consecutive integers,
+/// and the prover discloses the run containing the value, proving with a
+/// pair of range predicates that the value lies within it. Values encoded as
+/// hashes are not ordered, and would be disclosed by such a proof.
+///
+/// # Privacy
+///
+/// The value is hidden only among the members of its run, and entirely
+/// hidden only when the set is a single run of consecutive integers. A
+/// verifier can therefore learn the value exactly by requesting a sparse
+/// set, in which each value is a run of its own. Provers refuse to disclose
+/// a run of fewer than [`MIN_RUN_WIDTH`](Self::MIN_RUN_WIDTH) values unless
+/// the holder explicitly allows it for the referent.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct RangeDisclosureInfo {
+    pub name: String,
+    pub values: Vec<i32>,
+    pub restrictions: Option<Query>,
+    #[cfg_attr(feature = "serde", serde(alias = "nonRevoked"))]
+    pub non_revoked: Option<NonRevocedInterval>,
+}
+
+impl RangeDisclosureInfo {
+    /// The fewest values in a run which is presented without the explicit
+    /// consent of the holder
+    pub const MIN_RUN_WIDTH: u32 = 10;
+
+    /// The maximal runs of consecutive integers in the set, as inclusive
+    /// bounds in ascending order
+    pub fn ranges(&self) -> Vec<(i32, i32)> {
+        let mut values = self.values.clone();
+        values.sort_unstable();
+        values.dedup();
+        let mut ranges: Vec<(i32, i32)> = Vec::new();
+        for value in values {
+            match ranges.last_mut() {
+                Some((_, upper)) if i64::from(*upper) + 1 == i64::from(value) => *upper = value,
+                _ => ranges.push((value, value)),
+            }
+        }
+        ranges
+    }
+
+    /// The run of the set containing a value
+    pub fn range_containing(&self, value: i64) -> Option<(i32, i32)> {
+        self.ranges()
+            .into_iter()
+            .find(|(lower, upper)| i64::from(*lower) <= value && value <= i64::from(*upper))
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum PredicateTypes {
@@ -201,8 +400,11 @@ impl Validatable for PresentationRequest {
         let value = self.value();
         let version = self.version();
 
-        if value.requested_attributes.is_empty() && value.requested_predicates.is_empty() {
-            return Err(invalid!("Presentation request validation failed: both `requested_attributes` and `requested_predicates` are empty"));
+        if value.requested_attributes.is_empty()
+            && value.requested_predicates.is_empty()
+            && value.requested_range_disclosures.is_empty()
+        {
+            return Err(invalid!("Presentation request validation failed: `requested_attributes`, `requested_predicates` and `requested_range_disclosures` are all empty"));
         }
 
         for (_, requested_attribute) in value.requested_attributes.iter() {
@@ -228,7 +430,7 @@ impl Validatable for PresentationRequest {
             }
 
             if let Some(ref restrictions) = requested_attribute.restrictions {
-                _process_operator(&restrictions, &version)?;
+                _process_operator(restrictions, &version)?;
             }
         }
 
@@ -240,7 +442,102 @@ impl Validatable for PresentationRequest {
                 ));
             }
             if let Some(ref restrictions) = requested_predicate.restrictions {
-                _process_operator(&restrictions, &version)?;
+                _process_operator(restrictions, &version)?;
+            }
+        }
+
+        for (referent, disclosure) in value.requested_range_disclosures.iter() {
+            if disclosure.name.is_empty() || disclosure.values.is_empty() {
+                return Err(invalid!(
+                    "Presentation request validation failed: there is an empty requested range disclosure: {:?}",
+                    disclosure
+                ));
+            }
+            if value.requested_attributes.contains_key(referent)
+                || value.requested_predicates.contains_key(referent)
+            {
+                return Err(invalid!(
+                    "Presentation request validation failed: duplicate referent for range disclosure: {}",
+                    referent
+                ));
+            }
+            if let Some(ref restrictions) = disclosure.restrictions {
+                _process_operator(restrictions, &version)?;
+            }
+        }
+
+        for group in value.same_credential.iter() {
+            if group.len() < 2 {
+                return Err(invalid!(
+                    "Presentation request validation failed: a same credential group must contain at least two referents: {:?}",
+                    group
+                ));
+            }
+            for referent in group {
+                if !value.requested_attributes.contains_key(referent)
+                    && !value.requested_predicates.contains_key(referent)
+                    && !value.requested_range_disclosures.contains_key(referent)
+                {
+                    return Err(invalid!(
+                        "Presentation request validation failed: unknown referent in same credential group: {}",
+                        referent
+                    ));
+                }
+            }
+        }
+
+        for group in value.equal_attributes.iter() {
+            if group.len() < 2 {
+                return Err(invalid!(
+                    "Presentation request validation failed: an equal attributes group must contain at least two referents: {:?}",
+                    group
+                ));
+            }
+            let mut group_name = None;
+            for referent in group {
+                let name = value
+                    .requested_attributes
+                    .get(referent)
+                    .and_then(|info| info.name.as_ref())
+                    .ok_or_else(|| invalid!(
+                        "Presentation request validation failed: an equal attributes group must contain attribute referents with a single name: {}",
+                        referent
+                    ))?;
+                let name = name.replace(' ', "").to_lowercase();
+                if *group_name.get_or_insert_with(|| name.clone()) != name {
+                    return Err(invalid!(
+                        "Presentation request validation failed: the referents of an equal attributes group must request the same attribute: {:?}",
+                        group
+                    ));
+                }
+            }
+        }
+
+        if value.pseudonym_domain.as_deref() == Some("") {
+            return Err(invalid!(
+                "Presentation request validation failed: empty pseudonym domain"
+            ));
+        }
+
+        for (referent, escrow) in value.escrowed_attributes.iter() {
+            if value
+                .requested_attributes
+                .get(referent)
+                .and_then(|info| info.name.as_ref())
+                .is_none()
+            {
+                return Err(invalid!(
+                    "Presentation request validation failed: an escrowed attribute must be an attribute referent with a single name: {}",
+                    referent
+                ));
+            }
+            if escrow.auditor_key.is_empty()
+                || !escrow.auditor_key.bytes().all(|c| c.is_ascii_digit())
+            {
+                return Err(invalid!(
+                    "Presentation request validation failed: invalid auditor key for escrowed attribute: {}",
+                    referent
+                ));
             }
         }
 
@@ -256,13 +553,19 @@ impl PresentationRequest {
                 requested_attribute.restrictions = requested_attribute
                     .restrictions
                     .as_mut()
-                    .map(|ref mut restrictions| _convert_query_to_unqualified(&restrictions));
+                    .map(|ref mut restrictions| _convert_query_to_unqualified(restrictions));
             }
             for (_, requested_predicate) in request.requested_predicates.iter_mut() {
                 requested_predicate.restrictions = requested_predicate
                     .restrictions
                     .as_mut()
-                    .map(|ref mut restrictions| _convert_query_to_unqualified(&restrictions));
+                    .map(|ref mut restrictions| _convert_query_to_unqualified(restrictions));
+            }
+            for (_, disclosure) in request.requested_range_disclosures.iter_mut() {
+                disclosure.restrictions = disclosure
+                    .restrictions
+                    .as_mut()
+                    .map(|ref mut restrictions| _convert_query_to_unqualified(restrictions));
             }
         };
 
@@ -299,16 +602,16 @@ fn _convert_query_to_unqualified(query: &Query) -> Query {
         Query::And(ref queries) => Query::And(
             queries
                 .iter()
-                .map(|query| _convert_query_to_unqualified(query))
+                .map(_convert_query_to_unqualified)
                 .collect::<Vec<Query>>(),
         ),
         Query::Or(ref queries) => Query::Or(
             queries
                 .iter()
-                .map(|query| _convert_query_to_unqualified(query))
+                .map(_convert_query_to_unqualified)
                 .collect::<Vec<Query>>(),
         ),
-        Query::Not(ref query) => _convert_query_to_unqualified(query),
+        Query::Not(ref query) => Query::Not(Box::new(_convert_query_to_unqualified(query))),
         query => query.clone(),
     }
 }
@@ -479,7 +782,12 @@ mod tests {
                 version: "1.0".to_string(),
                 requested_attributes,
                 requested_predicates,
+                requested_range_disclosures: HashMap::new(),
                 non_revoked: None,
+                same_credential: vec![],
+                equal_attributes: vec![],
+                escrowed_attributes: HashMap::new(),
+                pseudonym_domain: None,
             });
 
             let mut expected_requested_attributes: HashMap<String, AttributeInfo> = HashMap::new();
@@ -527,4 +835,184 @@ mod tests {
             assert_eq!(PresentationRequestVersion::V2, request.version());
         }
     }
+
+    #[cfg(feature = "serde")]
+    mod versions {
+        use super::*;
+
+        const CRED_DEF_ID_QUALIFIED: &str = "creddef:sov:did:sov:NcYxiDXkpYi6ov5FcYDi1e:3:CL:schema:sov:did:sov:NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0:tag";
+        const CRED_DEF_ID_UNQUALIFIED: &str =
+            "NcYxiDXkpYi6ov5FcYDi1e:3:CL:NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0:tag";
+
+        fn request(ver: &str) -> serde_json::Value {
+            json!({
+                "nonce": "123456",
+                "name": "name",
+                "version": "1.0",
+                "ver": ver,
+                "requested_attributes": {
+                    "attr1": {
+                        "name": "name",
+                        "restrictions": {"$not": {"cred_def_id": CRED_DEF_ID_QUALIFIED}}
+                    }
+                },
+            })
+        }
+
+        #[test]
+        fn parse_versions() {
+            assert_eq!(
+                "2.0".parse::<PresentationRequestVersion>().unwrap(),
+                PresentationRequestVersion::V2
+            );
+            let err = serde_json::from_value::<PresentationRequest>(request("3.0")).unwrap_err();
+            assert!(err
+                .to_string()
+                .contains("Unsupported presentation request version"));
+        }
+
+        #[test]
+        fn convert_versions() {
+            let req: PresentationRequest = serde_json::from_value(request("2.0")).unwrap();
+            assert_eq!(req.version(), PresentationRequestVersion::V2);
+            req.validate().unwrap();
+
+            let req = req.into_version(PresentationRequestVersion::V1);
+            assert_eq!(req.version(), PresentationRequestVersion::V1);
+            req.validate().unwrap();
+            assert_eq!(
+                req.value().requested_attributes["attr1"].restrictions,
+                Some(Query::Not(Box::new(Query::Eq(
+                    "cred_def_id".to_string(),
+                    CRED_DEF_ID_UNQUALIFIED.to_string()
+                ))))
+            );
+
+            let req = req.into_version(PresentationRequestVersion::V2);
+            assert_eq!(serde_json::to_value(&req).unwrap()["ver"], "2.0");
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn validate_same_credential_groups() {
+        let request = |groups: serde_json::Value| -> PresentationRequest {
+            serde_json::from_value(json!({
+                "nonce": "123456",
+                "name": "name",
+                "version": "1.0",
+                "requested_attributes": {"attr1": {"names": ["name", "photo"]}},
+                "requested_predicates": {
+                    "pred1": {"name": "age", "p_type": ">=", "p_value": 18}
+                },
+                "same_credential": groups,
+            }))
+            .unwrap()
+        };
+        request(json!([["attr1", "pred1"]])).validate().unwrap();
+        assert!(request(json!([["attr1"]])).validate().is_err());
+        assert!(request(json!([["attr1", "pred2"]])).validate().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn validate_equal_attributes_groups() {
+        let request = |groups: serde_json::Value| -> PresentationRequest {
+            serde_json::from_value(json!({
+                "nonce": "123456",
+                "name": "name",
+                "version": "1.0",
+                "requested_attributes": {
+                    "tax_ssn": {"name": "ssn"},
+                    "job_ssn": {"name": "SSN"},
+                    "job_name": {"name": "name"},
+                    "ident": {"names": ["ssn", "name"]}
+                },
+                "equal_attributes": groups,
+            }))
+            .unwrap()
+        };
+        let valid = request(json!([["tax_ssn", "job_ssn"]]));
+        valid.validate().unwrap();
+        assert_eq!(
+            serde_json::to_value(&valid).unwrap()["equal_attributes"],
+            json!([["tax_ssn", "job_ssn"]])
+        );
+        assert!(request(json!([["tax_ssn"]])).validate().is_err());
+        assert!(request(json!([["tax_ssn", "job_name"]]))
+            .validate()
+            .is_err());
+        assert!(request(json!([["tax_ssn", "ident"]])).validate().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn validate_range_disclosures() {
+        let request = |disclosures: serde_json::Value| -> PresentationRequest {
+            serde_json::from_value(json!({
+                "nonce": "123456",
+                "name": "name",
+                "version": "1.0",
+                "requested_attributes": {"name": {"name": "name"}},
+                "requested_range_disclosures": disclosures,
+            }))
+            .unwrap()
+        };
+        let valid = request(json!({"country": {"name": "country", "values": [40, 56, 55, 57]}}));
+        valid.validate().unwrap();
+        assert_eq!(
+            valid.value().requested_range_disclosures["country"].ranges(),
+            vec![(40, 40), (55, 57)]
+        );
+        assert_eq!(
+            valid.value().requested_range_disclosures["country"].range_containing(56),
+            Some((55, 57))
+        );
+        assert_eq!(
+            serde_json::to_value(&valid).unwrap()["requested_range_disclosures"]["country"]
+                ["values"],
+            json!([40, 56, 55, 57])
+        );
+        assert!(
+            request(json!({"country": {"name": "country", "values": []}}))
+                .validate()
+                .is_err()
+        );
+        assert!(request(json!({"name": {"name": "name", "values": [1]}}))
+            .validate()
+            .is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn validate_escrowed_attributes() {
+        let request = |escrowed: serde_json::Value| -> PresentationRequest {
+            serde_json::from_value(json!({
+                "nonce": "123456",
+                "name": "name",
+                "version": "1.0",
+                "requested_attributes": {
+                    "ssn": {"name": "ssn"},
+                    "ident": {"names": ["ssn", "name"]}
+                },
+                "escrowedAttributes": escrowed,
+            }))
+            .unwrap()
+        };
+        let valid = request(json!({"ssn": {"auditorKey": "3233"}}));
+        valid.validate().unwrap();
+        assert_eq!(
+            serde_json::to_value(&valid).unwrap()["escrowed_attributes"],
+            json!({"ssn": {"auditor_key": "3233"}})
+        );
+        assert!(request(json!({"ident": {"auditor_key": "3233"}}))
+            .validate()
+            .is_err());
+        assert!(request(json!({"other": {"auditor_key": "3233"}}))
+            .validate()
+            .is_err());
+        assert!(request(json!({"ssn": {"auditor_key": "0x0ca1"}}))
+            .validate()
+            .is_err());
+    }
 }
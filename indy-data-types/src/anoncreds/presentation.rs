@@ -9,54 +9,110 @@ use crate::Validatable;
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Presentation {
     pub proof: ursa_cl!(Proof),
+    #[cfg_attr(feature = "serde", serde(alias = "requestedProof"))]
     pub requested_proof: RequestedProof,
     pub identifiers: Vec<Identifier>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Default)]
 pub struct RequestedProof {
+    #[cfg_attr(feature = "serde", serde(alias = "revealedAttrs"))]
     pub revealed_attrs: HashMap<String, RevealedAttributeInfo>,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "HashMap::is_empty"))]
     #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(alias = "revealedAttrGroups"))]
     pub revealed_attr_groups: HashMap<String, RevealedAttributeGroupInfo>,
     #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(alias = "selfAttestedAttrs"))]
     pub self_attested_attrs: HashMap<String, String>,
     #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(alias = "unrevealedAttrs"))]
     pub unrevealed_attrs: HashMap<String, SubProofReferent>,
     #[cfg_attr(feature = "serde", serde(default))]
     pub predicates: HashMap<String, SubProofReferent>,
+    /// Values of the attributes behind satisfied predicates, only present
+    /// in auditable presentations
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "HashMap::is_empty"))]
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(alias = "revealedPredicates"))]
+    pub revealed_predicates: HashMap<String, RevealedAttributeInfo>,
+    /// The disclosed runs of the requested sets which contain the attribute
+    /// values
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "HashMap::is_empty"))]
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(alias = "rangeDisclosures"))]
+    pub range_disclosures: HashMap<String, RangeDisclosureReferent>,
+    /// The verifiably encrypted values of escrowed attributes
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "HashMap::is_empty"))]
+    #[cfg_attr(feature = "serde", serde(default))]
+    #[cfg_attr(feature = "serde", serde(alias = "escrowedAttrs"))]
+    pub escrowed_attrs: HashMap<String, EscrowedAttributeInfo>,
+    /// The pseudonym of the link secret in the requested domain
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pseudonym: Option<DomainPseudonym>,
 }
 
-impl Default for RequestedProof {
-    fn default() -> Self {
-        RequestedProof {
-            revealed_attrs: HashMap::new(),
-            revealed_attr_groups: HashMap::new(),
-            self_attested_attrs: HashMap::new(),
-            unrevealed_attrs: HashMap::new(),
-            predicates: HashMap::new(),
-        }
-    }
+/// The pseudonym of the link secret in a domain, with the commitment of the
+/// proof that it derives from the link secret of the sub-proofs
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct DomainPseudonym {
+    pub domain: String,
+    pub nym: String,
+    pub commitment: String,
+}
+
+/// The Paillier encryption of the encoded value of an attribute for an
+/// auditor, with the proof that it encrypts the value in the sub-proof
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct EscrowedAttributeInfo {
+    #[cfg_attr(feature = "serde", serde(alias = "subProofIndex"))]
+    pub sub_proof_index: u32,
+    pub ciphertext: String,
+    pub commitment: String,
+    pub response: String,
+}
+
+/// The run of a requested set disclosed to contain an attribute value
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct RangeDisclosureReferent {
+    #[cfg_attr(feature = "serde", serde(alias = "subProofIndex"))]
+    pub sub_proof_index: u32,
+    /// The inclusive lower bound of the run
+    pub lower: i32,
+    /// The inclusive upper bound of the run
+    pub upper: i32,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct SubProofReferent {
+    #[cfg_attr(feature = "serde", serde(alias = "subProofIndex"))]
     pub sub_proof_index: u32,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct RevealedAttributeInfo {
+    #[cfg_attr(feature = "serde", serde(alias = "subProofIndex"))]
     pub sub_proof_index: u32,
     pub raw: String,
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "super::credential::deserialize_encoded_value")
+    )]
     pub encoded: String,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct RevealedAttributeGroupInfo {
+    #[cfg_attr(feature = "serde", serde(alias = "subProofIndex"))]
     pub sub_proof_index: u32,
     pub values: HashMap<String /* attribute name */, AttributeValue>,
 }
@@ -65,14 +121,21 @@ pub struct RevealedAttributeGroupInfo {
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct AttributeValue {
     pub raw: String,
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "super::credential::deserialize_encoded_value")
+    )]
     pub encoded: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Identifier {
+    #[cfg_attr(feature = "serde", serde(alias = "schemaId"))]
     pub schema_id: SchemaId,
+    #[cfg_attr(feature = "serde", serde(alias = "credDefId"))]
     pub cred_def_id: CredentialDefinitionId,
+    #[cfg_attr(feature = "serde", serde(alias = "revRegId"))]
     pub rev_reg_id: Option<RevocationRegistryId>,
     pub timestamp: Option<u64>,
 }
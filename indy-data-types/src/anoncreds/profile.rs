@@ -0,0 +1,676 @@
+//! Ledger objects such as schemas and credential definitions use camelCase
+//! names for their fields, while the objects exchanged between issuers,
+//! holders and verifiers use snake_case. Some agents apply one convention to
+//! every object. Each object accepts either form of its field names when
+//! deserialized, and a [`SerializationProfile`] selects the form produced
+//! when it is serialized.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::ser::{self, Serialize, Serializer};
+
+use crate::ConversionError;
+
+/// The struct name, snake_case and camelCase forms of each field which
+/// differs between the two conventions
+const FIELD_NAMES: &[(&str, &str, &str)] = &[
+    ("AttributeInfo", "non_revoked", "nonRevoked"),
+    ("Credential", "schema_id", "schemaId"),
+    ("Credential", "cred_def_id", "credDefId"),
+    ("Credential", "rev_reg_id", "revRegId"),
+    (
+        "Credential",
+        "signature_correctness_proof",
+        "signatureCorrectnessProof",
+    ),
+    ("Credential", "rev_reg", "revReg"),
+    ("CredentialDefinitionV1", "schema_id", "schemaId"),
+    ("CredentialInfo", "schema_id", "schemaId"),
+    ("CredentialInfo", "cred_def_id", "credDefId"),
+    ("CredentialInfo", "rev_reg_id", "revRegId"),
+    ("CredentialInfo", "cred_rev_id", "credRevId"),
+    ("CredentialOffer", "schema_id", "schemaId"),
+    ("CredentialOffer", "cred_def_id", "credDefId"),
+    (
+        "CredentialOffer",
+        "key_correctness_proof",
+        "keyCorrectnessProof",
+    ),
+    ("CredentialOffer", "method_name", "methodName"),
+    ("CredentialRequest", "prover_did", "proverDid"),
+    ("CredentialRequest", "cred_def_id", "credDefId"),
+    ("CredentialRequest", "blinded_ms", "blindedMs"),
+    (
+        "CredentialRequest",
+        "blinded_ms_correctness_proof",
+        "blindedMsCorrectnessProof",
+    ),
+    (
+        "CredentialRequestMetadata",
+        "master_secret_blinding_data",
+        "masterSecretBlindingData",
+    ),
+    (
+        "CredentialRequestMetadata",
+        "master_secret_name",
+        "masterSecretName",
+    ),
+    (
+        "CredentialRequestMetadata",
+        "master_secret_aliases",
+        "masterSecretAliases",
+    ),
+    ("EscrowInfo", "auditor_key", "auditorKey"),
+    ("EscrowedAttributeInfo", "sub_proof_index", "subProofIndex"),
+    ("Identifier", "schema_id", "schemaId"),
+    ("Identifier", "cred_def_id", "credDefId"),
+    ("Identifier", "rev_reg_id", "revRegId"),
+    ("PredicateInfo", "p_type", "pType"),
+    ("PredicateInfo", "p_value", "pValue"),
+    ("PredicateInfo", "non_revoked", "nonRevoked"),
+    ("Presentation", "requested_proof", "requestedProof"),
+    (
+        "PresentationRequestPayload",
+        "requested_attributes",
+        "requestedAttributes",
+    ),
+    (
+        "PresentationRequestPayload",
+        "requested_predicates",
+        "requestedPredicates",
+    ),
+    (
+        "PresentationRequestPayload",
+        "requested_range_disclosures",
+        "requestedRangeDisclosures",
+    ),
+    ("PresentationRequestPayload", "non_revoked", "nonRevoked"),
+    (
+        "PresentationRequestPayload",
+        "same_credential",
+        "sameCredential",
+    ),
+    (
+        "PresentationRequestPayload",
+        "equal_attributes",
+        "equalAttributes",
+    ),
+    (
+        "PresentationRequestPayload",
+        "escrowed_attributes",
+        "escrowedAttributes",
+    ),
+    (
+        "PresentationRequestPayload",
+        "pseudonym_domain",
+        "pseudonymDomain",
+    ),
+    ("RequestedProof", "revealed_attrs", "revealedAttrs"),
+    (
+        "RequestedProof",
+        "revealed_attr_groups",
+        "revealedAttrGroups",
+    ),
+    ("RequestedProof", "self_attested_attrs", "selfAttestedAttrs"),
+    ("RequestedProof", "unrevealed_attrs", "unrevealedAttrs"),
+    ("RequestedProof", "range_disclosures", "rangeDisclosures"),
+    ("RequestedProof", "escrowed_attrs", "escrowedAttrs"),
+    (
+        "RequestedProof",
+        "revealed_predicates",
+        "revealedPredicates",
+    ),
+    (
+        "RevealedAttributeGroupInfo",
+        "sub_proof_index",
+        "subProofIndex",
+    ),
+    ("RevealedAttributeInfo", "sub_proof_index", "subProofIndex"),
+    (
+        "RevocationRegistryDefinitionV1",
+        "revoc_def_type",
+        "revocDefType",
+    ),
+    ("RevocationRegistryDefinitionV1", "cred_def_id", "credDefId"),
+    (
+        "RevocationRegistryDefinitionValue",
+        "issuance_type",
+        "issuanceType",
+    ),
+    (
+        "RevocationRegistryDefinitionValue",
+        "max_cred_num",
+        "maxCredNum",
+    ),
+    (
+        "RevocationRegistryDefinitionValue",
+        "public_keys",
+        "publicKeys",
+    ),
+    (
+        "RevocationRegistryDefinitionValue",
+        "tails_hash",
+        "tailsHash",
+    ),
+    (
+        "RevocationRegistryDefinitionValue",
+        "tails_location",
+        "tailsLocation",
+    ),
+    (
+        "RevocationRegistryDefinitionValuePublicKeys",
+        "accum_key",
+        "accumKey",
+    ),
+    ("SchemaV1", "attr_names", "attrNames"),
+    ("SchemaV1", "seq_no", "seqNo"),
+    ("RangeDisclosureInfo", "non_revoked", "nonRevoked"),
+    (
+        "RangeDisclosureReferent",
+        "sub_proof_index",
+        "subProofIndex",
+    ),
+    ("SubProofReferent", "sub_proof_index", "subProofIndex"),
+];
+
+/// The naming convention used for the fields of serialized objects
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SerializationProfile {
+    /// The standard field names of each object
+    #[default]
+    Native,
+    /// snake_case field names for every object
+    SnakeCase,
+    /// camelCase field names for every object
+    CamelCase,
+}
+
+impl SerializationProfile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Native => "native",
+            Self::SnakeCase => "snake_case",
+            Self::CamelCase => "camel_case",
+        }
+    }
+
+    /// The name of a field of the named struct under this profile
+    pub fn field_name(&self, struct_name: &str, field: &'static str) -> &'static str {
+        if *self == Self::Native {
+            return field;
+        }
+        FIELD_NAMES
+            .iter()
+            .find(|(name, snake, camel)| {
+                *name == struct_name && (*snake == field || *camel == field)
+            })
+            .map(|(_, snake, camel)| match self {
+                Self::CamelCase => *camel,
+                _ => *snake,
+            })
+            .unwrap_or(field)
+    }
+}
+
+impl fmt::Display for SerializationProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SerializationProfile {
+    type Err = ConversionError;
+
+    fn from_str(profile: &str) -> Result<Self, Self::Err> {
+        match profile {
+            "native" => Ok(Self::Native),
+            "snake_case" => Ok(Self::SnakeCase),
+            "camel_case" => Ok(Self::CamelCase),
+            _ => Err(format!("Unknown serialization profile: {:?}", profile).into()),
+        }
+    }
+}
+
+/// Serializes the wrapped value using the field names of a profile
+#[derive(Debug)]
+pub struct WithProfile<'a, T: ?Sized>(&'a T, SerializationProfile);
+
+impl<'a, T: ?Sized> WithProfile<'a, T> {
+    pub fn new(value: &'a T, profile: SerializationProfile) -> Self {
+        Self(value, profile)
+    }
+}
+
+impl<T: Serialize + ?Sized> Serialize for WithProfile<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.1 == SerializationProfile::Native {
+            self.0.serialize(serializer)
+        } else {
+            self.0.serialize(ProfileSerializer {
+                inner: serializer,
+                profile: self.1,
+            })
+        }
+    }
+}
+
+/// Renames the fields of known structs, passing all other values through
+struct ProfileSerializer<S> {
+    inner: S,
+    profile: SerializationProfile,
+}
+
+/// Wraps each nested value of a compound with the profile
+struct Compound<C> {
+    inner: C,
+    profile: SerializationProfile,
+    name: &'static str,
+}
+
+impl<C> Compound<C> {
+    fn new(inner: C, profile: SerializationProfile, name: &'static str) -> Self {
+        Self {
+            inner,
+            profile,
+            name,
+        }
+    }
+}
+
+macro_rules! forward_serialize {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method(self, v: $ty) -> Result<Self::Ok, Self::Error> {
+                self.inner.$method(v)
+            }
+        )*
+    };
+}
+
+impl<S: Serializer> Serializer for ProfileSerializer<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Compound<S::SerializeSeq>;
+    type SerializeTuple = Compound<S::SerializeTuple>;
+    type SerializeTupleStruct = Compound<S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Compound<S::SerializeTupleVariant>;
+    type SerializeMap = Compound<S::SerializeMap>;
+    type SerializeStruct = Compound<S::SerializeStruct>;
+    type SerializeStructVariant = Compound<S::SerializeStructVariant>;
+
+    forward_serialize!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_unit_struct(&'static str),
+    );
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_none()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_some(&WithProfile(value, self.profile))
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.inner
+            .serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.inner
+            .serialize_newtype_struct(name, &WithProfile(value, self.profile))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_newtype_variant(
+            name,
+            variant_index,
+            variant,
+            &WithProfile(value, self.profile),
+        )
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(Compound::new(
+            self.inner.serialize_seq(len)?,
+            self.profile,
+            "",
+        ))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Ok(Compound::new(
+            self.inner.serialize_tuple(len)?,
+            self.profile,
+            "",
+        ))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Ok(Compound::new(
+            self.inner.serialize_tuple_struct(name, len)?,
+            self.profile,
+            name,
+        ))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Ok(Compound::new(
+            self.inner
+                .serialize_tuple_variant(name, variant_index, variant, len)?,
+            self.profile,
+            name,
+        ))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(Compound::new(
+            self.inner.serialize_map(len)?,
+            self.profile,
+            "",
+        ))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(Compound::new(
+            self.inner.serialize_struct(name, len)?,
+            self.profile,
+            name,
+        ))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Ok(Compound::new(
+            self.inner
+                .serialize_struct_variant(name, variant_index, variant, len)?,
+            self.profile,
+            variant,
+        ))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+impl<C: ser::SerializeSeq> ser::SerializeSeq for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.inner
+            .serialize_element(&WithProfile(value, self.profile))
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeTuple> ser::SerializeTuple for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.inner
+            .serialize_element(&WithProfile(value, self.profile))
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeTupleStruct> ser::SerializeTupleStruct for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.inner
+            .serialize_field(&WithProfile(value, self.profile))
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeTupleVariant> ser::SerializeTupleVariant for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.inner
+            .serialize_field(&WithProfile(value, self.profile))
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeMap> ser::SerializeMap for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.inner.serialize_key(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.inner
+            .serialize_value(&WithProfile(value, self.profile))
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeStruct> ser::SerializeStruct for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.inner.serialize_field(
+            self.profile.field_name(self.name, key),
+            &WithProfile(value, self.profile),
+        )
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.inner
+            .skip_field(self.profile.field_name(self.name, key))
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeStructVariant> ser::SerializeStructVariant for Compound<C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.inner.serialize_field(
+            self.profile.field_name(self.name, key),
+            &WithProfile(value, self.profile),
+        )
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.inner
+            .skip_field(self.profile.field_name(self.name, key))
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.inner.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anoncreds::credential::CredentialInfo;
+    use crate::anoncreds::pres_request::PresentationRequest;
+    use crate::anoncreds::schema::Schema;
+
+    fn with_profile<T: Serialize>(value: &T, profile: SerializationProfile) -> serde_json::Value {
+        serde_json::to_value(WithProfile::new(value, profile)).unwrap()
+    }
+
+    #[test]
+    fn schema_profiles() {
+        let schema: Schema = serde_json::from_value(json!({
+            "ver": "1.0",
+            "id": "NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0",
+            "name": "gvt",
+            "version": "1.0",
+            "attrNames": ["name"],
+            "seqNo": 10
+        }))
+        .unwrap();
+        let snake = with_profile(&schema, SerializationProfile::SnakeCase);
+        assert_eq!(snake["attr_names"], json!(["name"]));
+        assert_eq!(snake["seq_no"], 10);
+        assert!(snake.get("attrNames").is_none());
+        let parsed: Schema = serde_json::from_value(snake).unwrap();
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(&schema).unwrap()
+        );
+        assert_eq!(
+            with_profile(&schema, SerializationProfile::CamelCase),
+            serde_json::to_value(&schema).unwrap()
+        );
+    }
+
+    #[test]
+    fn credential_info_profiles() {
+        let snake = json!({
+            "referent": "cred1",
+            "attrs": {"first_name": "Alex"},
+            "schema_id": "NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0",
+            "cred_def_id": "NcYxiDXkpYi6ov5FcYDi1e:3:CL:1:tag",
+            "rev_reg_id": null,
+            "cred_rev_id": "1"
+        });
+        let info: CredentialInfo = serde_json::from_value(snake.clone()).unwrap();
+        let camel = with_profile(&info, SerializationProfile::CamelCase);
+        assert_eq!(camel["credDefId"], snake["cred_def_id"]);
+        assert_eq!(camel["credRevId"], "1");
+        assert_eq!(camel["attrs"]["first_name"], "Alex");
+        let parsed: CredentialInfo = serde_json::from_value(camel).unwrap();
+        assert_eq!(parsed, info);
+        assert_eq!(with_profile(&info, SerializationProfile::SnakeCase), snake);
+    }
+
+    #[test]
+    fn pres_request_profiles() {
+        let req: PresentationRequest = serde_json::from_value(json!({
+            "nonce": "123432421212",
+            "name": "proof_req",
+            "version": "0.1",
+            "requested_attributes": {
+                "attr_referent": {
+                    "name": "name",
+                    "restrictions": {"schema_id": "NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0"},
+                    "non_revoked": {"from": 1}
+                }
+            },
+            "requested_predicates": {
+                "pred_referent": {"name": "age", "p_type": ">=", "p_value": 18}
+            }
+        }))
+        .unwrap();
+        let camel = with_profile(&req, SerializationProfile::CamelCase);
+        let attr = &camel["requestedAttributes"]["attr_referent"];
+        assert_eq!(attr["nonRevoked"]["from"], 1);
+        assert_eq!(
+            attr["restrictions"]["schema_id"],
+            "NcYxiDXkpYi6ov5FcYDi1e:2:gvt:1.0"
+        );
+        assert_eq!(camel["requestedPredicates"]["pred_referent"]["pValue"], 18);
+        let parsed: PresentationRequest = serde_json::from_value(camel).unwrap();
+        assert_eq!(parsed, req);
+    }
+
+    #[test]
+    fn parse_profile() {
+        for profile in [
+            SerializationProfile::Native,
+            SerializationProfile::SnakeCase,
+            SerializationProfile::CamelCase,
+        ] {
+            assert_eq!(
+                profile.as_str().parse::<SerializationProfile>().unwrap(),
+                profile
+            );
+        }
+        assert!("kebab-case".parse::<SerializationProfile>().is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use indy_utils::wql::{builder::check_query, Query, QueryBuilder};
+
+use crate::ValidationError;
+use indy_utils::invalid;
+
+/// The credential identifier tags which may be used in restrictions
+pub const RESTRICTION_TAGS: [&str; 6] = [
+    "schema_id",
+    "schema_issuer_did",
+    "schema_name",
+    "schema_version",
+    "issuer_did",
+    "cred_def_id",
+];
+
+/// Check that a restriction query only uses the operators and tag names
+/// supported in the verification of presentations. Besides the identifier
+/// tags, restrictions may use `attr::<name>::value` to require a revealed
+/// attribute value and `attr::<name>::marker` to require an attribute.
+///
+/// Identifier tags may also be matched against a pattern with `$like`, where
+/// `%` matches any sequence of characters and `_` matches any single
+/// character, such as `{"cred_def_id": {"$like": "did:sov:ABC%"}}`.
+pub fn validate_restrictions(query: &Query) -> Result<(), ValidationError> {
+    check_query(query).map_err(|err| invalid!("Invalid restriction: {}", err))?;
+    check_operator(query)
+}
+
+fn check_operator(query: &Query) -> Result<(), ValidationError> {
+    match query {
+        Query::And(queries) | Query::Or(queries) => queries.iter().try_for_each(check_operator),
+        Query::Not(query) => check_operator(query),
+        Query::Eq(name, value) | Query::Neq(name, value) => check_tag(name, value),
+        Query::In(name, values) => values.iter().try_for_each(|value| check_tag(name, value)),
+        Query::Gt(..) | Query::Gte(..) | Query::Lt(..) | Query::Lte(..) => Err(invalid!(
+            "Invalid restriction: comparison operators are not supported, use a predicate"
+        )),
+        Query::Like(name, _) if RESTRICTION_TAGS.contains(&name.as_str()) => Ok(()),
+        Query::Like(name, _) => Err(invalid!(
+            "Invalid restriction: the $like operator only applies to identifier tags: {}",
+            name
+        )),
+        Query::Exist(..) => Err(invalid!(
+            "Invalid restriction: the $exist operator is not supported, use an attribute marker"
+        )),
+    }
+}
+
+fn check_tag(name: &str, value: &str) -> Result<(), ValidationError> {
+    if RESTRICTION_TAGS.contains(&name) {
+        return Ok(());
+    }
+    if let Some(attr) = name
+        .strip_prefix("attr::")
+        .and_then(|rest| rest.strip_suffix("::marker"))
+    {
+        if attr.is_empty() || value != "1" {
+            return Err(invalid!(
+                "Invalid restriction: attribute markers must name an attribute and have the value \"1\": {}",
+                name
+            ));
+        }
+        return Ok(());
+    }
+    if let Some(attr) = name
+        .strip_prefix("attr::")
+        .and_then(|rest| rest.strip_suffix("::value"))
+    {
+        if attr.is_empty() {
+            return Err(invalid!(
+                "Invalid restriction: attribute values must name an attribute: {}",
+                name
+            ));
+        }
+        return Ok(());
+    }
+    Err(invalid!("Invalid restriction: unsupported tag: {}", name))
+}
+
+/// A builder for the restrictions of a requested attribute or predicate.
+/// Each method adds a condition which the credential must satisfy.
+#[derive(Clone, Debug, Default)]
+pub struct RestrictionBuilder {
+    query: QueryBuilder,
+}
+
+impl RestrictionBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn schema_id(self, schema_id: &str) -> Self {
+        self.tag("schema_id", schema_id)
+    }
+
+    pub fn schema_issuer_did(self, did: &str) -> Self {
+        self.tag("schema_issuer_did", did)
+    }
+
+    pub fn schema_name(self, name: &str) -> Self {
+        self.tag("schema_name", name)
+    }
+
+    pub fn schema_version(self, version: &str) -> Self {
+        self.tag("schema_version", version)
+    }
+
+    pub fn issuer_did(self, did: &str) -> Self {
+        self.tag("issuer_did", did)
+    }
+
+    pub fn cred_def_id(self, cred_def_id: &str) -> Self {
+        self.tag("cred_def_id", cred_def_id)
+    }
+
+    /// Require the credential to be issued under one of a set of credential
+    /// definitions
+    pub fn cred_def_id_in<I, S>(self, cred_def_ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            query: self.query.one_of("cred_def_id", cred_def_ids),
+        }
+    }
+
+    /// Require the credential to be issued by a DID matching a `$like`
+    /// pattern
+    pub fn issuer_did_like(self, pattern: &str) -> Self {
+        Self {
+            query: self.query.like("issuer_did", pattern),
+        }
+    }
+
+    /// Require the credential to be issued under a credential definition
+    /// with an identifier matching a `$like` pattern, such as any credential
+    /// definition of an issuer namespace
+    pub fn cred_def_id_like(self, pattern: &str) -> Self {
+        Self {
+            query: self.query.like("cred_def_id", pattern),
+        }
+    }
+
+    /// Require the revealed value of an attribute
+    pub fn attr_value(self, attr: &str, value: &str) -> Self {
+        self.tag(&format!("attr::{}::value", attr), value)
+    }
+
+    /// Require the credential to contain an attribute
+    pub fn attr_marker(self, attr: &str) -> Self {
+        self.tag(&format!("attr::{}::marker", attr), "1")
+    }
+
+    /// Require at least one of a set of restrictions to be satisfied
+    pub fn any(self, restrictions: impl IntoIterator<Item = Query>) -> Self {
+        Self {
+            query: self.query.any(restrictions),
+        }
+    }
+
+    /// Require a restriction not to be satisfied
+    pub fn not(self, restriction: Query) -> Self {
+        Self {
+            query: self.query.not(restriction),
+        }
+    }
+
+    /// Check and return the restriction query
+    pub fn build(self) -> Result<Query, ValidationError> {
+        let query = self
+            .query
+            .build()
+            .map_err(|err| invalid!("Invalid restriction: {}", err))?;
+        validate_restrictions(&query)?;
+        Ok(query)
+    }
+
+    fn tag(self, name: &str, value: &str) -> Self {
+        Self {
+            query: self.query.eq(name, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_restrictions() {
+        let restriction = RestrictionBuilder::new()
+            .schema_name("gvt")
+            .any(vec![
+                RestrictionBuilder::new().issuer_did("A").build().unwrap(),
+                RestrictionBuilder::new().issuer_did("B").build().unwrap(),
+            ])
+            .attr_marker("age")
+            .build()
+            .unwrap();
+        assert_eq!(
+            restriction,
+            Query::And(vec![
+                Query::Eq("schema_name".to_string(), "gvt".to_string()),
+                Query::Or(vec![
+                    Query::Eq("issuer_did".to_string(), "A".to_string()),
+                    Query::Eq("issuer_did".to_string(), "B".to_string()),
+                ]),
+                Query::Eq("attr::age::marker".to_string(), "1".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn reject_unsupported_restrictions() {
+        let query = |name: &str, value: &str| Query::Eq(name.to_string(), value.to_string());
+        assert!(validate_restrictions(&query("rev_reg_id", "x")).is_err());
+        assert!(validate_restrictions(&query("attr::age::marker", "0")).is_err());
+        assert!(validate_restrictions(&query("attr::::value", "x")).is_err());
+        assert!(
+            validate_restrictions(&Query::Gt("issuer_did".to_string(), "1".to_string())).is_err()
+        );
+        assert!(validate_restrictions(&Query::Exist(vec!["issuer_did".to_string()])).is_err());
+        assert!(validate_restrictions(&Query::Like(
+            "attr::name::value".to_string(),
+            "A%".to_string()
+        ))
+        .is_err());
+        validate_restrictions(&Query::Like(
+            "cred_def_id".to_string(),
+            "did:sov:ABC%".to_string(),
+        ))
+        .unwrap();
+        assert!(RestrictionBuilder::new().any(vec![]).build().is_err());
+        validate_restrictions(&query("attr::first name::value", "Alex")).unwrap();
+    }
+}
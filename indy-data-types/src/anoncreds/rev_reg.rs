@@ -14,15 +14,28 @@ pub enum RevocationRegistry {
 impl RevocationRegistry {
     #[cfg(any(feature = "cl", feature = "cl_native"))]
     pub fn initial_delta(&self) -> RevocationRegistryDelta {
+        let empty = HashSet::new();
+        self.delta_from(None, &empty, &empty)
+    }
+
+    /// Construct a delta describing the transition from a previous registry
+    /// state (or the initial state) to this one
+    #[cfg(any(feature = "cl", feature = "cl_native"))]
+    pub fn delta_from(
+        &self,
+        prev: Option<&RevocationRegistry>,
+        issued: &HashSet<u32>,
+        revoked: &HashSet<u32>,
+    ) -> RevocationRegistryDelta {
+        let prev = prev.map(|prev| match prev {
+            Self::RevocationRegistryV1(v1) => &v1.value,
+        });
         match self {
             Self::RevocationRegistryV1(v1) => {
                 RevocationRegistryDelta::RevocationRegistryDeltaV1(RevocationRegistryDeltaV1 {
-                    value: {
-                        let empty = HashSet::new();
-                        crate::ursa::cl::RevocationRegistryDelta::from_parts(
-                            None, &v1.value, &empty, &empty,
-                        )
-                    },
+                    value: crate::ursa::cl::RevocationRegistryDelta::from_parts(
+                        prev, &v1.value, issued, revoked,
+                    ),
                 })
             }
         }
@@ -43,6 +56,20 @@ pub enum RevocationRegistryDelta {
     RevocationRegistryDeltaV1(RevocationRegistryDeltaV1),
 }
 
+impl RevocationRegistryDelta {
+    /// The registry state resulting from the application of this delta
+    #[cfg(any(feature = "cl", feature = "cl_native"))]
+    pub fn to_registry(&self) -> RevocationRegistry {
+        match self {
+            Self::RevocationRegistryDeltaV1(v1) => {
+                RevocationRegistry::RevocationRegistryV1(RevocationRegistryV1 {
+                    value: v1.value.clone().into(),
+                })
+            }
+        }
+    }
+}
+
 impl Validatable for RevocationRegistryDelta {}
 
 #[derive(Clone, Debug)]
@@ -11,12 +11,15 @@ pub const ISSUANCE_ON_DEMAND: &str = "ISSUANCE_ON_DEMAND";
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Default)]
 pub enum IssuanceType {
+    #[default]
     ISSUANCE_BY_DEFAULT,
     ISSUANCE_ON_DEMAND,
 }
 
 impl IssuanceType {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(value: &str) -> Result<Self, ConversionError> {
         match value {
             ISSUANCE_BY_DEFAULT => Ok(Self::ISSUANCE_BY_DEFAULT),
@@ -26,7 +29,7 @@ impl IssuanceType {
     }
 
     pub fn to_bool(&self) -> bool {
-        self.clone() == IssuanceType::ISSUANCE_BY_DEFAULT
+        *self == IssuanceType::ISSUANCE_BY_DEFAULT
     }
 
     pub fn to_str(&self) -> &'static str {
@@ -37,12 +40,6 @@ impl IssuanceType {
     }
 }
 
-impl Default for IssuanceType {
-    fn default() -> Self {
-        Self::ISSUANCE_BY_DEFAULT
-    }
-}
-
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
@@ -51,6 +48,7 @@ pub enum RegistryType {
 }
 
 impl RegistryType {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(value: &str) -> Result<Self, ConversionError> {
         match value {
             CL_ACCUM => Ok(Self::CL_ACCUM),
@@ -69,10 +67,15 @@ impl RegistryType {
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct RevocationRegistryDefinitionValue {
+    #[cfg_attr(feature = "serde", serde(alias = "issuance_type"))]
     pub issuance_type: IssuanceType,
+    #[cfg_attr(feature = "serde", serde(alias = "max_cred_num"))]
     pub max_cred_num: u32,
+    #[cfg_attr(feature = "serde", serde(alias = "public_keys"))]
     pub public_keys: RevocationRegistryDefinitionValuePublicKeys,
+    #[cfg_attr(feature = "serde", serde(alias = "tails_hash"))]
     pub tails_hash: String,
+    #[cfg_attr(feature = "serde", serde(alias = "tails_location"))]
     pub tails_location: String,
 }
 
@@ -80,6 +83,7 @@ pub struct RevocationRegistryDefinitionValue {
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct RevocationRegistryDefinitionValuePublicKeys {
+    #[cfg_attr(feature = "serde", serde(alias = "accum_key"))]
     pub accum_key: ursa_cl!(RevocationKeyPublic),
 }
 
@@ -134,8 +138,10 @@ impl Validatable for RevocationRegistryDefinition {
 )]
 pub struct RevocationRegistryDefinitionV1 {
     pub id: RevocationRegistryId,
+    #[cfg_attr(feature = "serde", serde(alias = "revoc_def_type"))]
     pub revoc_def_type: RegistryType,
     pub tag: String,
+    #[cfg_attr(feature = "serde", serde(alias = "cred_def_id"))]
     pub cred_def_id: CredentialDefinitionId,
     pub value: RevocationRegistryDefinitionValue,
 }
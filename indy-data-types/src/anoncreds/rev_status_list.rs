@@ -0,0 +1,313 @@
+use std::collections::BTreeSet;
+#[cfg(any(feature = "cl", feature = "cl_native"))]
+use std::collections::HashSet;
+
+#[cfg(any(feature = "cl", feature = "cl_native"))]
+use super::rev_reg::RevocationRegistryDelta;
+use super::rev_reg::{RevocationRegistry, RevocationRegistryV1};
+use super::rev_reg_def::RevocationRegistryDefinition;
+use crate::identifiers::rev_reg::RevocationRegistryId;
+use crate::{invalid, ConversionError, Validatable, ValidationError};
+use indy_utils::did::DidValue;
+
+/// The AnonCreds revocation status list: the revocation state of every
+/// index of a registry together with the matching accumulator value
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct RevocationStatusList {
+    pub rev_reg_def_id: RevocationRegistryId,
+    pub issuer_id: DidValue,
+    /// One entry per credential index, starting with index 1
+    #[cfg_attr(feature = "serde", serde(with = "serde_revocation_list"))]
+    pub revocation_list: Vec<bool>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "currentAccumulator",
+            with = "serde_accumulator",
+            default,
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    pub registry: Option<ursa_cl!(RevocationRegistry)>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub timestamp: Option<u64>,
+}
+
+impl RevocationStatusList {
+    /// Build a status list for a registry definition from the set of
+    /// revoked credential indices
+    pub fn new(
+        rev_reg_def: &RevocationRegistryDefinition,
+        rev_reg: Option<&RevocationRegistry>,
+        revoked: &BTreeSet<u32>,
+        timestamp: Option<u64>,
+    ) -> Result<Self, ConversionError> {
+        let RevocationRegistryDefinition::RevocationRegistryDefinitionV1(def) = rev_reg_def;
+        let (issuer_id, _, _, _) = def
+            .id
+            .parts()
+            .ok_or_else(|| ConversionError::from_msg("Invalid revocation registry identifier"))?;
+        let max_cred_num = def.value.max_cred_num;
+        let mut revocation_list = vec![false; max_cred_num as usize];
+        for idx in revoked {
+            if *idx == 0 || *idx > max_cred_num {
+                return Err(ConversionError::from_msg(format!(
+                    "Revoked index {} is outside of the revocation registry",
+                    idx
+                )));
+            }
+            revocation_list[*idx as usize - 1] = true;
+        }
+        Ok(Self {
+            rev_reg_def_id: def.id.clone(),
+            issuer_id,
+            revocation_list,
+            registry: rev_reg.map(|rev_reg| match rev_reg {
+                RevocationRegistry::RevocationRegistryV1(v1) => v1.value.clone(),
+            }),
+            timestamp,
+        })
+    }
+
+    /// Whether the credential with the given index is marked as revoked
+    pub fn is_revoked(&self, idx: u32) -> bool {
+        idx > 0
+            && self
+                .revocation_list
+                .get(idx as usize - 1)
+                .copied()
+                .unwrap_or(false)
+    }
+
+    /// The set of revoked credential indices
+    pub fn revoked_indices(&self) -> BTreeSet<u32> {
+        self.revocation_list
+            .iter()
+            .enumerate()
+            .filter(|(_, revoked)| **revoked)
+            .map(|(pos, _)| pos as u32 + 1)
+            .collect()
+    }
+
+    /// The registry state matching the current accumulator, if any
+    pub fn to_registry(&self) -> Option<RevocationRegistry> {
+        self.registry
+            .clone()
+            .map(|value| RevocationRegistry::RevocationRegistryV1(RevocationRegistryV1 { value }))
+    }
+
+    /// Construct the registry delta describing the transition from a previous
+    /// status list to this one. Without a previous list, every index not
+    /// marked as revoked is reported as issued.
+    #[cfg(any(feature = "cl", feature = "cl_native"))]
+    pub fn to_delta(
+        &self,
+        prev: Option<&RevocationStatusList>,
+    ) -> Result<RevocationRegistryDelta, ConversionError> {
+        let registry = self
+            .to_registry()
+            .ok_or_else(|| ConversionError::from_msg("Missing current accumulator"))?;
+        let (issued, revoked): (HashSet<u32>, HashSet<u32>) = match prev {
+            Some(prev) => {
+                if prev.rev_reg_def_id != self.rev_reg_def_id {
+                    return Err(ConversionError::from_msg(
+                        "Revocation status lists are for different registries",
+                    ));
+                }
+                let prev_revoked = prev.revoked_indices();
+                let revoked = self.revoked_indices();
+                (
+                    prev_revoked.difference(&revoked).copied().collect(),
+                    revoked.difference(&prev_revoked).copied().collect(),
+                )
+            }
+            None => {
+                let revoked = self.revoked_indices();
+                (
+                    (1..=self.revocation_list.len() as u32)
+                        .filter(|idx| !revoked.contains(idx))
+                        .collect(),
+                    revoked.into_iter().collect(),
+                )
+            }
+        };
+        let prev_registry = match prev {
+            Some(prev) => Some(prev.to_registry().ok_or_else(|| {
+                ConversionError::from_msg("Missing accumulator of previous status list")
+            })?),
+            None => None,
+        };
+        Ok(registry.delta_from(prev_registry.as_ref(), &issued, &revoked))
+    }
+
+    /// Apply a registry delta to this status list, producing the status list
+    /// for the resulting registry state
+    #[cfg(any(feature = "cl", feature = "cl_native"))]
+    pub fn update_from_delta(
+        &self,
+        delta: &RevocationRegistryDelta,
+        timestamp: Option<u64>,
+    ) -> Result<Self, ConversionError> {
+        let RevocationRegistryDelta::RevocationRegistryDeltaV1(v1) = delta;
+        // the fields of the CL delta are only accessible in serialized form
+        let value = serde_json::to_value(&v1.value).map_err(ConversionError::from_err)?;
+        if let (Some(prev_accum), Some(registry)) = (value.get("prevAccum"), &self.registry) {
+            let accum = serde_json::to_value(registry).map_err(ConversionError::from_err)?;
+            if accum.get("accum") != Some(prev_accum) {
+                return Err(ConversionError::from_msg(
+                    "Revocation registry delta does not apply to the current accumulator",
+                ));
+            }
+        }
+        let indices = |field: &str| -> Result<BTreeSet<u32>, ConversionError> {
+            match value.get(field) {
+                Some(indices) => {
+                    serde_json::from_value(indices.clone()).map_err(ConversionError::from_err)
+                }
+                None => Ok(BTreeSet::new()),
+            }
+        };
+        let mut revocation_list = self.revocation_list.clone();
+        for (indices, revoked) in [(indices("issued")?, false), (indices("revoked")?, true)] {
+            for idx in indices {
+                let entry = idx
+                    .checked_sub(1)
+                    .and_then(|pos| revocation_list.get_mut(pos as usize))
+                    .ok_or_else(|| {
+                        ConversionError::from_msg(format!(
+                            "Delta index {} is outside of the revocation registry",
+                            idx
+                        ))
+                    })?;
+                *entry = revoked;
+            }
+        }
+        let RevocationRegistry::RevocationRegistryV1(registry) = delta.to_registry();
+        Ok(Self {
+            rev_reg_def_id: self.rev_reg_def_id.clone(),
+            issuer_id: self.issuer_id.clone(),
+            revocation_list,
+            registry: Some(registry.value),
+            timestamp,
+        })
+    }
+}
+
+impl Validatable for RevocationStatusList {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.rev_reg_def_id.validate()?;
+        self.issuer_id.validate()?;
+        if self.revocation_list.is_empty() {
+            return Err(invalid!(
+                "RevocationStatusList validation failed: `revocationList` must not be empty"
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_revocation_list {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(list: &[bool], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(list.iter().map(|revoked| *revoked as u8))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<bool>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<u8>::deserialize(deserializer)?
+            .into_iter()
+            .map(|entry| match entry {
+                0 => Ok(false),
+                1 => Ok(true),
+                _ => Err(D::Error::custom("Revocation list entries must be 0 or 1")),
+            })
+            .collect()
+    }
+}
+
+/// The accumulator is published as a bare string rather than the
+/// `{"accum": ...}` object used by the registry entry
+#[cfg(feature = "serde")]
+mod serde_accumulator {
+    use serde::{de::DeserializeOwned, Serialize};
+    use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T, S>(registry: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        match registry {
+            Some(registry) => {
+                let value = serde_json::to_value(registry).map_err(S::Error::custom)?;
+                match value.get("accum").and_then(serde_json::Value::as_str) {
+                    Some(accum) => serializer.serialize_some(accum),
+                    None => Err(S::Error::custom("Missing accumulator value")),
+                }
+            }
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        T: DeserializeOwned,
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(accum) => serde_json::from_value(serde_json::json!({ "accum": accum }))
+                .map(Some)
+                .map_err(D::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_list_json() -> serde_json::Value {
+        json!({
+            "revRegDefId": "NcYxiDXkpYi6ov5FcYDi1e:4:NcYxiDXkpYi6ov5FcYDi1e:3:CL:1:tag:CL_ACCUM:tag",
+            "issuerId": "NcYxiDXkpYi6ov5FcYDi1e",
+            "revocationList": [0, 1, 0, 0, 1],
+            "timestamp": 1669640864
+        })
+    }
+
+    #[test]
+    fn test_status_list_serde_round_trip() {
+        let list: RevocationStatusList = serde_json::from_value(status_list_json()).unwrap();
+        list.validate().unwrap();
+        assert_eq!(list.revoked_indices(), [2, 5].iter().copied().collect());
+        assert!(list.is_revoked(2));
+        assert!(!list.is_revoked(1));
+        assert!(!list.is_revoked(0));
+        assert!(!list.is_revoked(6));
+        assert!(list.to_registry().is_none());
+        assert_eq!(serde_json::to_value(&list).unwrap(), status_list_json());
+    }
+
+    #[test]
+    fn test_status_list_rejects_invalid_entries() {
+        let mut value = status_list_json();
+        value["revocationList"] = json!([0, 2]);
+        assert!(serde_json::from_value::<RevocationStatusList>(value).is_err());
+    }
+}
@@ -51,7 +51,9 @@ pub struct SchemaV1 {
     pub name: String,
     pub version: String,
     #[cfg_attr(feature = "serde", serde(rename = "attrNames"))]
+    #[cfg_attr(feature = "serde", serde(alias = "attr_names"))]
     pub attr_names: AttributeNames,
+    #[cfg_attr(feature = "serde", serde(alias = "seq_no"))]
     pub seq_no: Option<u32>,
 }
 
@@ -59,6 +61,12 @@ pub struct SchemaV1 {
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AttributeNames(pub HashSet<String>);
 
+impl Default for AttributeNames {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AttributeNames {
     pub fn new() -> Self {
         AttributeNames(HashSet::new())
@@ -87,9 +95,9 @@ impl From<HashSet<String>> for AttributeNames {
     }
 }
 
-impl Into<HashSet<String>> for AttributeNames {
-    fn into(self) -> HashSet<String> {
-        self.0
+impl From<AttributeNames> for HashSet<String> {
+    fn from(val: AttributeNames) -> Self {
+        val.0
     }
 }
 
@@ -1,4 +1,5 @@
 use super::schema::SchemaId;
+use super::uri;
 use crate::utils::{qualifiable, Qualifiable};
 use crate::{Validatable, ValidationError};
 use indy_utils::did::DidValue;
@@ -11,6 +12,7 @@ qualifiable_type!(CredentialDefinitionId, "A credential definition identifier");
 impl CredentialDefinitionId {
     pub const PREFIX: &'static str = "creddef";
     pub const MARKER: &'static str = "3";
+    const SIGNATURE_TYPE_CL: &'static str = "CL";
 
     pub fn new(
         did: &DidValue,
@@ -19,7 +21,7 @@ impl CredentialDefinitionId {
         tag: &str,
     ) -> CredentialDefinitionId {
         let tag = if tag.is_empty() {
-            format!("")
+            String::new()
         } else {
             format!("{}{}", DELIMITER, tag)
         };
@@ -42,6 +44,17 @@ impl CredentialDefinitionId {
     }
 
     pub fn parts(&self) -> Option<(Option<&str>, DidValue, String, SchemaId, String)> {
+        if let Some((did, segments)) = uri::parse(&self.0, uri::CLAIM_DEF, 2) {
+            // did:indy:sovrin:NcYxiDXkpYi6ov5FcYDi1e/anoncreds/v0/CLAIM_DEF/1/tag
+            return Some((
+                None,
+                did,
+                Self::SIGNATURE_TYPE_CL.to_string(),
+                SchemaId(segments[0].to_string()),
+                segments[1].to_string(),
+            ));
+        }
+
         let parts = self.0.split_terminator(DELIMITER).collect::<Vec<&str>>();
 
         if parts.len() == 4 {
@@ -142,6 +155,35 @@ impl CredentialDefinitionId {
     pub fn issuer_did(&self) -> Option<DidValue> {
         self.parts().map(|(_, did, _, _, _)| did)
     }
+
+    /// Whether this is an AnonCreds identifier URI rather than a legacy
+    /// identifier
+    pub fn is_uri(&self) -> bool {
+        uri::is_uri(&self.0)
+    }
+
+    /// Rewrite a legacy credential definition identifier as a `did:indy`
+    /// AnonCreds URI in the given ledger namespace. Only CL credential
+    /// definitions with a tag which reference their schema by sequence
+    /// number can be expressed in this form.
+    pub fn to_uri(&self, namespace: &str) -> Option<CredentialDefinitionId> {
+        if self.is_uri() {
+            return Some(self.clone());
+        }
+        let (_, did, signature_type, schema_id, tag) = self.parts()?;
+        if signature_type != Self::SIGNATURE_TYPE_CL
+            || tag.is_empty()
+            || schema_id.0.parse::<u32>().is_err()
+        {
+            return None;
+        }
+        let did = uri::indy_did(&did, namespace)?;
+        Some(CredentialDefinitionId(uri::build(
+            &did,
+            uri::CLAIM_DEF,
+            &[&schema_id.0, &tag],
+        )))
+    }
 }
 
 impl Qualifiable for CredentialDefinitionId {
@@ -151,6 +193,9 @@ impl Qualifiable for CredentialDefinitionId {
 
     fn combine(method: Option<&str>, entity: &str) -> Self {
         let cid = Self(entity.to_owned());
+        if cid.is_uri() {
+            return cid;
+        }
         match cid.parts() {
             Some((_, did, sigtype, schema_id, tag)) => Self::new(
                 &did.default_method(method),
@@ -163,6 +208,9 @@ impl Qualifiable for CredentialDefinitionId {
     }
 
     fn to_unqualified(&self) -> Self {
+        if self.is_uri() {
+            return self.clone();
+        }
         match self.parts() {
             Some((_, did, sig_type, schema_id, tag)) => Self::new(
                 &did.to_unqualified(),
@@ -419,4 +467,52 @@ mod tests {
             )
         }
     }
+
+    mod to_uri {
+        use super::*;
+
+        fn _cred_def_id_uri() -> CredentialDefinitionId {
+            CredentialDefinitionId(
+                "did:indy:sovrin:NcYxiDXkpYi6ov5FcYDi1e/anoncreds/v0/CLAIM_DEF/1/tag".into(),
+            )
+        }
+
+        #[test]
+        fn test_cred_def_id_to_uri() {
+            assert_eq!(
+                _cred_def_id_unqualified_with_schema_as_seq_no().to_uri("sovrin"),
+                Some(_cred_def_id_uri())
+            );
+            assert_eq!(
+                _cred_def_id_qualified_with_schema_as_seq_no().to_uri("sovrin"),
+                Some(_cred_def_id_uri())
+            );
+            assert_eq!(
+                _cred_def_id_uri().to_uri("sovrin"),
+                Some(_cred_def_id_uri())
+            );
+        }
+
+        #[test]
+        fn test_cred_def_id_to_uri_requires_schema_seq_no_and_tag() {
+            assert!(_cred_def_id_unqualified().to_uri("sovrin").is_none());
+            assert!(_cred_def_id_unqualified_with_schema_as_seq_no_without_tag()
+                .to_uri("sovrin")
+                .is_none());
+        }
+
+        #[test]
+        fn test_cred_def_id_parts_for_uri() {
+            let (_, did, signature_type, schema_id, tag) = _cred_def_id_uri().parts().unwrap();
+            assert_eq!(
+                did,
+                DidValue("did:indy:sovrin:NcYxiDXkpYi6ov5FcYDi1e".to_string())
+            );
+            assert_eq!(_signature_type(), signature_type);
+            assert_eq!(_schema_id_seq_no(), schema_id);
+            assert_eq!(_tag(), tag);
+            assert_eq!(_cred_def_id_uri().to_unqualified(), _cred_def_id_uri());
+            _cred_def_id_uri().validate().unwrap();
+        }
+    }
 }
@@ -4,10 +4,12 @@ pub mod cred_def;
 pub mod rev_reg;
 /// V1 schema identifiers
 pub mod schema;
+/// AnonCreds object identifier URIs
+pub(crate) mod uri;
 
 #[cfg(any(feature = "rich_schema", test))]
 /// Rich schema identifiers
 pub mod rich_schema;
 
 /// The standard delimiter used in identifier strings
-pub const DELIMITER: &'static str = ":";
+pub const DELIMITER: &str = ":";
@@ -3,6 +3,7 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 
 use super::cred_def::CredentialDefinitionId;
+use super::uri;
 use super::DELIMITER;
 use crate::utils::{qualifiable, Qualifiable};
 use crate::{Validatable, ValidationError};
@@ -18,6 +19,7 @@ qualifiable_type!(RevocationRegistryId, "A revocation registry identifier");
 impl RevocationRegistryId {
     pub const PREFIX: &'static str = "revreg";
     pub const MARKER: &'static str = "4";
+    const REGISTRY_TYPE_CL_ACCUM: &'static str = "CL_ACCUM";
 
     pub fn new(
         did: &DidValue,
@@ -45,15 +47,51 @@ impl RevocationRegistryId {
     }
 
     pub fn parts(&self) -> Option<(DidValue, CredentialDefinitionId, String, String)> {
-        match QUALIFIED_REV_REG_ID.captures(&self.0) {
-            Some(caps) => Some((
+        if let Some((did, segments)) = uri::parse(&self.0, uri::REV_REG_DEF, 3) {
+            // did:indy:sovrin:NcYxiDXkpYi6ov5FcYDi1e/anoncreds/v0/REV_REG_DEF/1/tag/TAG_1
+            let cred_def_id = uri::build(&did, uri::CLAIM_DEF, &segments[..2]);
+            return Some((
+                did,
+                CredentialDefinitionId(cred_def_id),
+                Self::REGISTRY_TYPE_CL_ACCUM.to_string(),
+                segments[2].to_string(),
+            ));
+        }
+
+        QUALIFIED_REV_REG_ID.captures(&self.0).map(|caps| {
+            (
                 DidValue(caps["did"].to_string()),
                 CredentialDefinitionId(caps["cred_def_id"].to_string()),
                 caps["rev_reg_type"].to_string(),
                 caps["tag"].to_string(),
-            )),
-            None => None,
+            )
+        })
+    }
+
+    /// Whether this is an AnonCreds identifier URI rather than a legacy
+    /// identifier
+    pub fn is_uri(&self) -> bool {
+        uri::is_uri(&self.0)
+    }
+
+    /// Rewrite a legacy revocation registry identifier as a `did:indy`
+    /// AnonCreds URI in the given ledger namespace, which is only possible
+    /// when the credential definition identifier can be rewritten as well
+    pub fn to_uri(&self, namespace: &str) -> Option<RevocationRegistryId> {
+        if self.is_uri() {
+            return Some(self.clone());
+        }
+        let (did, cred_def_id, rev_reg_type, tag) = self.parts()?;
+        if rev_reg_type != Self::REGISTRY_TYPE_CL_ACCUM {
+            return None;
         }
+        let (_, _, _, schema_id, cred_def_tag) = cred_def_id.to_uri(namespace)?.parts()?;
+        let did = uri::indy_did(&did, namespace)?;
+        Some(RevocationRegistryId(uri::build(
+            &did,
+            uri::REV_REG_DEF,
+            &[&schema_id.0, &cred_def_tag, &tag],
+        )))
     }
 }
 
@@ -64,6 +102,9 @@ impl Qualifiable for RevocationRegistryId {
 
     fn combine(method: Option<&str>, entity: &str) -> Self {
         let sid = Self(entity.to_owned());
+        if sid.is_uri() {
+            return sid;
+        }
         match sid.parts() {
             Some((did, cred_def_id, rev_reg_type, tag)) => Self::new(
                 &did.default_method(method),
@@ -76,6 +117,9 @@ impl Qualifiable for RevocationRegistryId {
     }
 
     fn to_unqualified(&self) -> Self {
+        if self.is_uri() {
+            return self.clone();
+        }
         match self.parts() {
             Some((did, cred_def_id, rev_reg_type, tag)) => Self::new(
                 &did.to_unqualified(),
@@ -203,4 +247,46 @@ mod tests {
             )
         }
     }
+
+    mod to_uri {
+        use super::*;
+
+        fn _rev_reg_id_uri() -> RevocationRegistryId {
+            RevocationRegistryId(
+                "did:indy:sovrin:NcYxiDXkpYi6ov5FcYDi1e/anoncreds/v0/REV_REG_DEF/1/tag/TAG_1"
+                    .into(),
+            )
+        }
+
+        fn _cred_def_id_uri() -> CredentialDefinitionId {
+            CredentialDefinitionId(
+                "did:indy:sovrin:NcYxiDXkpYi6ov5FcYDi1e/anoncreds/v0/CLAIM_DEF/1/tag".into(),
+            )
+        }
+
+        #[test]
+        fn test_rev_reg_id_to_uri() {
+            let id = RevocationRegistryId(
+                "NcYxiDXkpYi6ov5FcYDi1e:4:NcYxiDXkpYi6ov5FcYDi1e:3:CL:1:tag:CL_ACCUM:TAG_1".into(),
+            );
+            assert_eq!(id.to_uri("sovrin"), Some(_rev_reg_id_uri()));
+            assert_eq!(_rev_reg_id_uri().to_uri("sovrin"), Some(_rev_reg_id_uri()));
+            // the credential definition references its schema by identifier
+            assert!(_rev_reg_id_unqualified().to_uri("sovrin").is_none());
+        }
+
+        #[test]
+        fn test_rev_reg_id_parts_for_uri() {
+            let (did, cred_def_id, rev_reg_type, tag) = _rev_reg_id_uri().parts().unwrap();
+            assert_eq!(
+                did,
+                DidValue("did:indy:sovrin:NcYxiDXkpYi6ov5FcYDi1e".to_string())
+            );
+            assert_eq!(_cred_def_id_uri(), cred_def_id);
+            assert_eq!(_rev_reg_type(), rev_reg_type);
+            assert_eq!(_tag(), tag);
+            assert_eq!(_rev_reg_id_uri().to_unqualified(), _rev_reg_id_uri());
+            _rev_reg_id_uri().validate().unwrap();
+        }
+    }
 }
@@ -8,14 +8,14 @@ impl RichSchemaId {
     pub const PREFIX: &'static str = "rich_schema";
     pub fn new(did_string: String) -> RichSchemaId {
         // ToDo: add RichSchema specific id forming if needed
-        return RichSchemaId(did_string);
+        RichSchemaId(did_string)
     }
 }
 
 impl Validatable for RichSchemaId {
     fn validate(&self) -> Result<(), ValidationError> {
         // ToDO: add RichSchema ID specific validation
-        return Ok(());
+        Ok(())
     }
 }
 
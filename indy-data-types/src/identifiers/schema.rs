@@ -3,6 +3,7 @@ use crate::{Validatable, ValidationError};
 use indy_utils::did::DidValue;
 use indy_utils::qualifiable_type;
 
+use super::uri;
 use super::DELIMITER;
 
 qualifiable_type!(SchemaId, "A V1 schema identifier");
@@ -30,6 +31,11 @@ impl SchemaId {
     }
 
     pub fn parts(&self) -> Option<(Option<&str>, DidValue, String, String)> {
+        if let Some((did, segments)) = uri::parse(&self.0, uri::SCHEMA, 2) {
+            // did:indy:sovrin:NcYxiDXkpYi6ov5FcYDi1e/anoncreds/v0/SCHEMA/gvt/1.0
+            return Some((None, did, segments[0].to_string(), segments[1].to_string()));
+        }
+
         let parts = self.0.split_terminator(DELIMITER).collect::<Vec<&str>>();
 
         if parts.len() == 1 {
@@ -56,6 +62,23 @@ impl SchemaId {
 
         None
     }
+
+    /// Whether this is an AnonCreds identifier URI rather than a legacy
+    /// identifier
+    pub fn is_uri(&self) -> bool {
+        uri::is_uri(&self.0)
+    }
+
+    /// Rewrite a legacy schema identifier as a `did:indy` AnonCreds URI
+    /// in the given ledger namespace
+    pub fn to_uri(&self, namespace: &str) -> Option<SchemaId> {
+        if self.is_uri() {
+            return Some(self.clone());
+        }
+        let (_, did, name, version) = self.parts()?;
+        let did = uri::indy_did(&did, namespace)?;
+        Some(SchemaId(uri::build(&did, uri::SCHEMA, &[&name, &version])))
+    }
 }
 
 impl Qualifiable for SchemaId {
@@ -65,6 +88,9 @@ impl Qualifiable for SchemaId {
 
     fn combine(method: Option<&str>, entity: &str) -> Self {
         let sid = Self(entity.to_owned());
+        if sid.is_uri() {
+            return sid;
+        }
         match sid.parts() {
             Some((_, did, name, version)) => {
                 Self::new(&did.default_method(method), &name, &version)
@@ -74,6 +100,9 @@ impl Qualifiable for SchemaId {
     }
 
     fn to_unqualified(&self) -> Self {
+        if self.is_uri() {
+            return self.clone();
+        }
         match self.parts() {
             Some((method, did, name, version)) => {
                 let did = if let Some(method) = method {
@@ -228,4 +257,51 @@ mod tests {
             )
         }
     }
+
+    mod to_uri {
+        use super::*;
+
+        fn _schema_id_uri() -> SchemaId {
+            SchemaId(
+                "did:indy:sovrin:NcYxiDXkpYi6ov5FcYDi1e/anoncreds/v0/SCHEMA/gvt/1.0".to_string(),
+            )
+        }
+
+        #[test]
+        fn test_schema_id_to_uri() {
+            assert_eq!(
+                _schema_id_unqualified().to_uri("sovrin"),
+                Some(_schema_id_uri())
+            );
+            assert_eq!(
+                _schema_id_qualified().to_uri("sovrin"),
+                Some(_schema_id_uri())
+            );
+            assert_eq!(_schema_id_uri().to_uri("sovrin"), Some(_schema_id_uri()));
+            assert!(_schema_id_seq_no().to_uri("sovrin").is_none());
+        }
+
+        #[test]
+        fn test_schema_id_parts_for_uri() {
+            let id = _schema_id_uri();
+            let (method, did, name, version) = id.parts().unwrap();
+            assert_eq!(method, None);
+            assert_eq!(
+                did,
+                DidValue("did:indy:sovrin:NcYxiDXkpYi6ov5FcYDi1e".to_string())
+            );
+            assert_eq!(name, "gvt");
+            assert_eq!(version, "1.0");
+            _schema_id_uri().validate().unwrap();
+        }
+
+        #[test]
+        fn test_schema_id_uri_is_not_qualifiable() {
+            assert_eq!(_schema_id_uri().to_unqualified(), _schema_id_uri());
+            assert_eq!(
+                _schema_id_uri().to_qualified("sov").unwrap(),
+                _schema_id_uri()
+            );
+        }
+    }
 }
@@ -0,0 +1,65 @@
+use indy_utils::did::DidValue;
+use indy_utils::Qualifiable;
+
+/// The path separating the issuer DID from the object reference
+pub const ANONCREDS_PATH: &str = "/anoncreds/v0/";
+
+pub const SCHEMA: &str = "SCHEMA";
+pub const CLAIM_DEF: &str = "CLAIM_DEF";
+pub const REV_REG_DEF: &str = "REV_REG_DEF";
+
+/// The method of issuer DIDs in AnonCreds identifier URIs
+pub const INDY_METHOD: &str = "indy";
+
+/// Check whether an identifier is an AnonCreds object URI rather than a
+/// legacy colon-delimited identifier
+pub fn is_uri(id: &str) -> bool {
+    id.starts_with("did:") && id.contains(ANONCREDS_PATH)
+}
+
+/// Construct an AnonCreds object URI for an object published by `did`
+pub fn build(did: &DidValue, object_type: &str, segments: &[&str]) -> String {
+    format!(
+        "{}{}{}/{}",
+        did.0,
+        ANONCREDS_PATH,
+        object_type,
+        segments.join("/")
+    )
+}
+
+/// Split an AnonCreds object URI of the given type into the issuer DID and
+/// exactly `count` non-empty path segments
+pub fn parse<'a>(id: &'a str, object_type: &str, count: usize) -> Option<(DidValue, Vec<&'a str>)> {
+    if !is_uri(id) {
+        return None;
+    }
+    let (did, path) = id.split_once(ANONCREDS_PATH)?;
+    let (found_type, rest) = path.split_once('/')?;
+    if found_type != object_type {
+        return None;
+    }
+    let segments = rest.split('/').collect::<Vec<&str>>();
+    if segments.len() != count || segments.iter().any(|s| s.is_empty()) {
+        return None;
+    }
+    Some((DidValue(did.into()), segments))
+}
+
+/// Rewrite a legacy issuer DID as a `did:indy` DID in the given namespace
+pub fn indy_did(did: &DidValue, namespace: &str) -> Option<DidValue> {
+    if namespace.is_empty() {
+        return None;
+    }
+    if did.get_method() == Some(INDY_METHOD) {
+        return Some(did.clone());
+    }
+    let did = did.to_unqualified();
+    if did.contains(':') {
+        return None;
+    }
+    Some(DidValue::new(
+        &format!("{}:{}", namespace, did.0),
+        Some(INDY_METHOD),
+    ))
+}
@@ -10,6 +10,7 @@ extern crate serde_json;
 mod macros;
 
 mod utils {
+    #[cfg(test)]
     pub use indy_utils::base58;
     pub use indy_utils::{qualifiable, Qualifiable};
 }
@@ -1,3 +1,5 @@
+#![allow(clippy::assertions_on_constants, clippy::ptr_arg)]
+
 use indy_utils::hash::{TreeHash, SHA256::DigestType as Hash};
 
 use self::tree::{Tree, TreeLeafData};
@@ -81,7 +83,7 @@ impl MerkleTree {
         let mut old_node = self.count - 1;
         let mut new_node = new_size - 1;
 
-        while old_node % 2 != 0 {
+        while !old_node.is_multiple_of(2) {
             old_node /= 2;
             new_node /= 2;
         }
@@ -99,7 +101,7 @@ impl MerkleTree {
         }
 
         while old_node != 0 {
-            if old_node % 2 != 0 {
+            if !old_node.is_multiple_of(2) {
                 let next_proof = unwrap_opt_or_return!(proofs.next(), Ok(false));
                 old_hash = Hash::hash_nodes(next_proof, &old_hash)?.to_vec();
                 new_hash = Hash::hash_nodes(next_proof, &new_hash)?.to_vec();
@@ -292,7 +294,7 @@ mod tests {
         assert!(mt
             .consistency_proof(
                 &vec![
-                    0x77 as u8, 0xf1, 0x5a, 0x58, 0x07, 0xfd, 0xaa, 0x56, 0x51, 0x28, 0xc5, 0x8f,
+                    0x77_u8, 0xf1, 0x5a, 0x58, 0x07, 0xfd, 0xaa, 0x56, 0x51, 0x28, 0xc5, 0x8f,
                     0x59, 0x1f, 0x4f, 0x03, 0x25, 0x81, 0xfe, 0xe7, 0xd8, 0x61, 0x99, 0xae, 0xf8,
                     0xae, 0xac, 0x7b, 0x05, 0x80, 0xbe, 0x0a
                 ],
@@ -315,11 +317,9 @@ mod tests {
 
         let proofs: Vec<Vec<u8>> = vec![];
 
-        assert_eq!(
-            false,
-            mt.consistency_proof(&vec![0x77 as u8, 0xf1, 0x5a], 4, &proofs)
-                .unwrap()
-        );
+        assert!(!mt
+            .consistency_proof(&vec![0x77_u8, 0xf1, 0x5a], 4, &proofs)
+            .unwrap());
     }
 
     #[test]
@@ -335,7 +335,7 @@ mod tests {
         for value in values {
             let proof = tree.gen_proof(value).unwrap();
             let is_valid = proof
-                .map(|p| p.validate(&root_hash).unwrap())
+                .map(|p| p.validate(root_hash).unwrap())
                 .unwrap_or(false);
 
             assert!(is_valid);
@@ -366,7 +366,7 @@ mod tests {
 
     #[test]
     fn consistency_proof_works_for_old4_new8() {
-        let all_str_values = vec![
+        let all_str_values = [
             r#"{"data":{"alias":"Node1","client_ip":"10.0.0.2","client_port":9702,"node_ip":"10.0.0.2","node_port":9701,"services":["VALIDATOR"]},"dest":"Gw6pDLhcBcoQesN72qfotTgFa7cbuqZpkX3Xo6pLhPhv","identifier":"FYmoFw55GeQH7SRFa37dkx1d2dZ3zUF8ckg7wmL7ofN4","txnId":"fea82e10e894419fe2bea7d96296a6d46f50f93f9eeda954ec461b2ed2950b62","type":"0"}"#,
             r#"{"data":{"alias":"Node2","client_ip":"10.0.0.2","client_port":9704,"node_ip":"10.0.0.2","node_port":9703,"services":["VALIDATOR"]},"dest":"8ECVSk179mjsjKRLWiQtssMLgp6EPhWXtaYyStWPSGAb","identifier":"8QhFxKxyaFsJy4CyxeYX34dFH8oWqyBv1P4HLQCsoeLy","txnId":"1ac8aece2a18ced660fef8694b61aac3af08ba875ce3026a160acbc3a3af35fc","type":"0"}"#,
             r#"{"data":{"alias":"Node3","client_ip":"10.0.0.2","client_port":9706,"node_ip":"10.0.0.2","node_port":9705,"services":["VALIDATOR"]},"dest":"DKVxG2fXXTU8yT5N7hGEbXB3dfdAnYv1JczDUHpmDxya","identifier":"2yAeV5ftuasWNgQwVYzeHeTuM7LwwNtPR3Zg9N4JiDgF","txnId":"7e9f355dffa78ed24668f0e0e369fd8c224076571c51e2ea8be5f26479edebe4","type":"0"}"#,
@@ -399,7 +399,7 @@ mod tests {
         //add 5th node
         mt.append(all_values[5 - 1].clone()).unwrap();
         assert!(mt
-            .consistency_proof(&full_root_hash, 8, &proofs_for_5)
+            .consistency_proof(full_root_hash, 8, &proofs_for_5)
             .unwrap());
 
         //try to add 6th node
@@ -415,7 +415,7 @@ mod tests {
         //add 6th node
         mt.append(all_values[6 - 1].clone()).unwrap();
         assert!(mt
-            .consistency_proof(&full_root_hash, 8, &proofs_for_6)
+            .consistency_proof(full_root_hash, 8, &proofs_for_6)
             .unwrap());
 
         //try to add 7th node
@@ -432,7 +432,7 @@ mod tests {
         //add 7th node
         mt.append(all_values[7 - 1].clone()).unwrap();
         assert!(mt
-            .consistency_proof(&full_root_hash, 8, &proofs_for_7)
+            .consistency_proof(full_root_hash, 8, &proofs_for_7)
             .unwrap());
 
         //try to add 8th node, empty proof
@@ -440,7 +440,7 @@ mod tests {
         //add 7th node
         mt.append(all_values[8 - 1].clone()).unwrap();
         assert!(mt
-            .consistency_proof(&full_root_hash, 8, &proofs_for_8)
+            .consistency_proof(full_root_hash, 8, &proofs_for_8)
             .unwrap());
     }
 }
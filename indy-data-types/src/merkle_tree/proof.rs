@@ -34,7 +34,7 @@ impl Proof {
             return Ok(false);
         }
 
-        Ok(self.validate_lemma(&self.lemma)?)
+        self.validate_lemma(&self.lemma)
     }
 
     fn validate_lemma(&self, lemma: &Lemma) -> Result<bool, ValidationError> {
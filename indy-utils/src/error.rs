@@ -76,9 +76,9 @@ macro_rules! define_error {
             }
         }
 
-        impl Into<String> for $name {
-            fn into(self) -> String {
-                self.to_string()
+        impl From<$name> for String {
+            fn from(err: $name) -> String {
+                err.to_string()
             }
         }
 
@@ -1,7 +1,7 @@
 #[cfg(feature = "ed25519")]
 use curve25519_dalek::edwards::CompressedEdwardsY;
 #[cfg(feature = "ed25519")]
-use ed25519_dalek::{ExpandedSecretKey, PublicKey, SecretKey, Signature};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
 #[cfg(feature = "ed25519")]
 use rand::{thread_rng, RngCore};
 #[cfg(feature = "ed25519")]
@@ -54,12 +54,12 @@ impl PrivateKey {
 
     #[cfg(feature = "ed25519")]
     pub fn from_seed(seed: &[u8]) -> Result<Self, ConversionError> {
-        let sk = SecretKey::from_bytes(seed)
-            .map_err(|err| format!("Error creating signing key: {}", err))?;
-        let mut esk = [0u8; 64];
-        esk[..32].copy_from_slice(sk.as_bytes());
-        esk[32..].copy_from_slice(PublicKey::from(&sk).as_bytes());
-        Ok(Self::new(esk, Some(KeyType::ED25519)))
+        let seed = <[u8; 32]>::try_from(seed)
+            .map_err(|_| "Error creating signing key: invalid seed length")?;
+        let mut esk = SigningKey::from_bytes(&seed).to_keypair_bytes();
+        let key = Self::new(esk, Some(KeyType::ED25519));
+        esk.zeroize();
+        Ok(key)
     }
 
     pub fn public_key(&self) -> Result<VerKey, ConversionError> {
@@ -81,7 +81,7 @@ impl PrivateKey {
                 let x_sk =
                     x25519_dalek::StaticSecret::from(<[u8; 32]>::try_from(&hash[..32]).unwrap());
                 hash.zeroize();
-                Ok(Self::new(&x_sk.to_bytes(), Some(KeyType::X25519)))
+                Ok(Self::new(x_sk.to_bytes(), Some(KeyType::X25519)))
             }
             _ => Err("Unsupported key format for key exchange".into()),
         }
@@ -91,9 +91,11 @@ impl PrivateKey {
     pub fn sign<M: AsRef<[u8]>>(&self, message: M) -> Result<Vec<u8>, ConversionError> {
         match self.alg {
             KeyType::ED25519 => {
-                let esk = ExpandedSecretKey::from(&SecretKey::from_bytes(&self.key[..32]).unwrap());
-                let pk = PublicKey::from_bytes(&self.key[32..]).unwrap();
-                let sig = esk.sign(message.as_ref(), &pk);
+                let sk = <&[u8; 64]>::try_from(&self.key[..])
+                    .ok()
+                    .and_then(|keypair| SigningKey::from_keypair_bytes(keypair).ok())
+                    .ok_or("Invalid signing key")?;
+                let sig = sk.sign(message.as_ref());
                 Ok(sig.to_bytes().into())
             }
             _ => Err("Unsupported key format for signing".into()),
@@ -176,10 +178,11 @@ impl VerKey {
     pub fn key_exchange(&self) -> Result<Self, ConversionError> {
         match self.alg {
             KeyType::ED25519 => {
-                let vky = CompressedEdwardsY::from_slice(&self.key[..]);
+                let vky = CompressedEdwardsY::from_slice(&self.key[..])
+                    .map_err(|_| "Invalid verkey length")?;
                 if let Some(x_vk) = vky.decompress() {
                     Ok(Self::new(
-                        x_vk.to_montgomery().as_bytes().to_vec(),
+                        x_vk.to_montgomery().as_bytes(),
                         Some(KeyType::X25519),
                     ))
                 } else {
@@ -198,7 +201,10 @@ impl VerKey {
     ) -> Result<bool, ConversionError> {
         match self.alg {
             KeyType::ED25519 => {
-                let vk = PublicKey::from_bytes(&self.key[..]).unwrap();
+                let vk = <&[u8; 32]>::try_from(&self.key[..])
+                    .ok()
+                    .and_then(|key| VerifyingKey::from_bytes(key).ok())
+                    .ok_or("Invalid verkey")?;
                 if let Ok(sig) = Signature::try_from(signature.as_ref()) {
                     Ok(vk.verify_strict(message.as_ref(), &sig).is_ok())
                 } else {
@@ -273,8 +279,8 @@ impl EncodedVerKey {
     }
 
     pub fn from_did_and_verkey(did: &str, key: &str) -> Result<Self, ConversionError> {
-        if key.chars().next() == Some('~') {
-            let mut vk_bytes = base58::decode(&key[1..])?;
+        if let Some(key) = key.strip_prefix('~') {
+            let mut vk_bytes = base58::decode(key)?;
             if vk_bytes.len() != 16 {
                 return Err("Expected 16-byte abbreviated verkey".into());
             }
@@ -284,7 +290,7 @@ impl EncodedVerKey {
             }
             did_bytes.append(&mut vk_bytes);
             Ok(Self::new(
-                &base58::encode(did_bytes),
+                base58::encode(did_bytes),
                 Some(KeyType::ED25519),
                 Some(KeyEncoding::BASE58),
             ))
@@ -327,6 +333,7 @@ impl EncodedVerKey {
         Self::from_str_qualified(key, None, None, None)
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(key: &str) -> Result<Self, ConversionError> {
         Self::from_str_qualified(key, None, None, None)
     }
@@ -348,13 +355,13 @@ impl EncodedVerKey {
             (key, alg)
         };
 
-        if key.starts_with('~') {
+        if let Some(key) = key.strip_prefix('~') {
             let dest =
                 unwrap_opt_or_return!(dest, Err("Destination required for short verkey".into()));
             let mut result = base58::decode(dest)?;
-            let mut end = base58::decode(&key[1..])?;
+            let mut end = base58::decode(key)?;
             result.append(&mut end);
-            Ok(Self::new(&base58::encode(result), alg, enc))
+            Ok(Self::new(base58::encode(result), alg, enc))
         } else {
             Ok(Self::new(key, alg, enc))
         }
@@ -509,7 +516,7 @@ mod tests {
         const SEED: &[u8; 32] = b"aaaabbbbccccddddeeeeffffgggghhhh";
         let sk = PrivateKey::from_seed(&SEED[..]).unwrap();
         assert_eq!(
-            &sk.as_ref()[..],
+            sk.as_ref(),
             &[
                 97, 97, 97, 97, 98, 98, 98, 98, 99, 99, 99, 99, 100, 100, 100, 100, 101, 101, 101,
                 101, 102, 102, 102, 102, 103, 103, 103, 103, 104, 104, 104, 104, 113, 22, 13, 44,
@@ -532,11 +539,11 @@ mod tests {
     fn sign_and_verify() {
         let message = b"hello there";
         let sk = PrivateKey::generate(None).unwrap();
-        let sig = sk.sign(&message).unwrap();
+        let sig = sk.sign(message).unwrap();
         let vk = sk.public_key().unwrap();
-        assert!(vk.verify_signature(&message, &sig).unwrap());
-        assert!(vk.verify_signature(&message, &[]).is_err());
-        assert!(!vk.verify_signature(&"goodbye", &sig).unwrap());
+        assert!(vk.verify_signature(message, &sig).unwrap());
+        assert!(vk.verify_signature(message, []).is_err());
+        assert!(!vk.verify_signature("goodbye", &sig).unwrap());
     }
 
     #[cfg(feature = "ed25519")]
@@ -548,8 +555,8 @@ mod tests {
         vk.validate().unwrap();
 
         let sk = PrivateKey::new(b"bad key", Some(KeyType::ED25519));
-        assert_eq!(sk.validate().is_ok(), false);
+        assert!(sk.validate().is_err());
         let vk = VerKey::new(b"bad key", Some(KeyType::ED25519));
-        assert_eq!(vk.validate().is_ok(), false);
+        assert!(vk.validate().is_err());
     }
 }
@@ -1,17 +1,19 @@
-pub const KEY_ENC_BASE58: &'static str = "base58";
+pub const KEY_ENC_BASE58: &str = "base58";
 
-pub const KEY_TYPE_ED25519: &'static str = "ed25519";
-pub const KEY_TYPE_X25519: &'static str = "x25519";
+pub const KEY_TYPE_ED25519: &str = "ed25519";
+pub const KEY_TYPE_X25519: &str = "x25519";
 
 /// Enum of known and unknown key types
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub enum KeyType {
+    #[default]
     ED25519,
     X25519,
     Other(String),
 }
 
 impl KeyType {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(keytype: &str) -> KeyType {
         match keytype.to_ascii_lowercase().as_str() {
             KEY_TYPE_ED25519 => KeyType::ED25519,
@@ -21,10 +23,7 @@ impl KeyType {
     }
 
     pub fn is_known(&self) -> bool {
-        match self {
-            Self::Other(_) => false,
-            _ => true,
-        }
+        !matches!(self, Self::Other(_))
     }
 
     pub fn as_str(&self) -> &str {
@@ -36,15 +35,9 @@ impl KeyType {
     }
 }
 
-impl std::string::ToString for KeyType {
-    fn to_string(&self) -> String {
-        self.as_str().to_owned()
-    }
-}
-
-impl Default for KeyType {
-    fn default() -> Self {
-        KeyType::ED25519
+impl std::fmt::Display for KeyType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
@@ -68,13 +61,15 @@ impl From<String> for KeyType {
 }
 
 /// Enum of known and unknown key encodings
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub enum KeyEncoding {
+    #[default]
     BASE58,
     Other(String),
 }
 
 impl KeyEncoding {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(keyenc: &str) -> KeyEncoding {
         match keyenc.to_ascii_lowercase().as_str() {
             KEY_ENC_BASE58 => KeyEncoding::BASE58,
@@ -90,15 +85,9 @@ impl KeyEncoding {
     }
 }
 
-impl std::string::ToString for KeyEncoding {
-    fn to_string(&self) -> String {
-        self.as_str().to_owned()
-    }
-}
-
-impl Default for KeyEncoding {
-    fn default() -> Self {
-        KeyEncoding::BASE58
+impl std::fmt::Display for KeyEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
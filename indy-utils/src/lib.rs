@@ -32,6 +32,10 @@ pub mod keys;
 #[cfg(feature = "base64")]
 pub mod base64;
 
+/// Multibase encoding and decoding
+#[cfg(feature = "base64")]
+pub mod multibase;
+
 /// Hash algorithms
 #[cfg(feature = "hash")]
 pub mod hash;
@@ -0,0 +1,106 @@
+use base64_rs as base64;
+
+use super::base58;
+use crate::error::ConversionError;
+
+/// The multibase encodings supported for encoding and decoding
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Base {
+    /// Base58 using the Bitcoin alphabet, prefix `z`
+    Base58Btc,
+    /// Unpadded base64, prefix `m`
+    Base64,
+    /// Padded base64, prefix `M`
+    Base64Pad,
+    /// Unpadded URL-safe base64, prefix `u`
+    Base64Url,
+    /// Padded URL-safe base64, prefix `U`
+    Base64UrlPad,
+}
+
+impl Base {
+    /// The multibase prefix character of the encoding
+    pub fn code(&self) -> char {
+        match self {
+            Self::Base58Btc => 'z',
+            Self::Base64 => 'm',
+            Self::Base64Pad => 'M',
+            Self::Base64Url => 'u',
+            Self::Base64UrlPad => 'U',
+        }
+    }
+
+    pub fn from_code(code: char) -> Option<Self> {
+        match code {
+            'z' => Some(Self::Base58Btc),
+            'm' => Some(Self::Base64),
+            'M' => Some(Self::Base64Pad),
+            'u' => Some(Self::Base64Url),
+            'U' => Some(Self::Base64UrlPad),
+            _ => None,
+        }
+    }
+
+    fn base64_config(&self) -> Option<base64::Config> {
+        match self {
+            Self::Base58Btc => None,
+            Self::Base64 => Some(base64::STANDARD_NO_PAD),
+            Self::Base64Pad => Some(base64::STANDARD),
+            Self::Base64Url => Some(base64::URL_SAFE_NO_PAD),
+            Self::Base64UrlPad => Some(base64::URL_SAFE),
+        }
+    }
+}
+
+/// Encode data with a multibase prefix
+pub fn encode<T: AsRef<[u8]>>(base: Base, val: T) -> String {
+    let encoded = match base.base64_config() {
+        Some(config) => base64::encode_config(val, config),
+        None => base58::encode(val),
+    };
+    format!("{}{}", base.code(), encoded)
+}
+
+/// Decode multibase data, returning the encoding indicated by its prefix
+pub fn decode<T: AsRef<str>>(val: T) -> Result<(Base, Vec<u8>), ConversionError> {
+    let val = val.as_ref();
+    let code = val
+        .chars()
+        .next()
+        .ok_or_else(|| ConversionError::from_msg("Empty multibase data"))?;
+    let base = Base::from_code(code).ok_or_else(|| {
+        ConversionError::from_msg(format!("Unsupported multibase prefix: {:?}", code))
+    })?;
+    let data = &val[code.len_utf8()..];
+    let decoded = match base.base64_config() {
+        Some(config) => base64::decode_config(data, config)
+            .map_err(|err| ("Error decoding multibase data", err))?,
+        None => base58::decode(data)?,
+    };
+    Ok((base, decoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multibase_round_trip() {
+        let data = b"\xfb\xff multibase";
+        for base in [
+            Base::Base58Btc,
+            Base::Base64,
+            Base::Base64Pad,
+            Base::Base64Url,
+            Base::Base64UrlPad,
+        ] {
+            let encoded = encode(base, data);
+            assert!(encoded.starts_with(base.code()));
+            assert_eq!(decode(&encoded).unwrap(), (base, data.to_vec()));
+        }
+        assert_eq!(encode(Base::Base64Url, data), "u-_8gbXVsdGliYXNl");
+        assert!(decode("").is_err());
+        assert!(decode("x1234").is_err());
+        assert!(decode("m-_8").is_err());
+    }
+}
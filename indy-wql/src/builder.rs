@@ -0,0 +1,263 @@
+//! Programmatic construction of WQL queries
+
+use std::error::Error;
+use std::fmt;
+
+use super::Query;
+
+/// An error in the construction of a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError(String);
+
+impl QueryError {
+    /// Create a new error with a message
+    pub fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+
+    /// The error message
+    pub fn message(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for QueryError {}
+
+/// A builder for a conjunction of query clauses.
+///
+/// Each method adds a clause which must be satisfied by matching records.
+/// The query is checked for empty or reserved tag names and empty value
+/// lists when built. The JSON form of the resulting query is produced by its
+/// `Display` and `Serialize` implementations.
+#[derive(Clone, Debug, Default)]
+pub struct QueryBuilder {
+    clauses: Vec<Query>,
+}
+
+impl QueryBuilder {
+    /// Create a new, empty query builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require a tag to equal a value
+    pub fn eq(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.with(Query::Eq(name.into(), value.into()))
+    }
+
+    /// Require a tag to differ from a value
+    pub fn neq(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.with(Query::Neq(name.into(), value.into()))
+    }
+
+    /// Require a tag to be greater than a value
+    pub fn gt(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.with(Query::Gt(name.into(), value.into()))
+    }
+
+    /// Require a tag to be greater than or equal to a value
+    pub fn gte(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.with(Query::Gte(name.into(), value.into()))
+    }
+
+    /// Require a tag to be less than a value
+    pub fn lt(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.with(Query::Lt(name.into(), value.into()))
+    }
+
+    /// Require a tag to be less than or equal to a value
+    pub fn lte(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.with(Query::Lte(name.into(), value.into()))
+    }
+
+    /// Require a tag to match an SQL `LIKE` pattern
+    pub fn like(self, name: impl Into<String>, pattern: impl Into<String>) -> Self {
+        self.with(Query::Like(name.into(), pattern.into()))
+    }
+
+    /// Require a tag to equal one of a set of values
+    pub fn one_of<I, S>(self, name: impl Into<String>, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.with(Query::In(
+            name.into(),
+            values.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    /// Require each of a set of tags to be present
+    pub fn exist<I, S>(self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.with(Query::Exist(names.into_iter().map(Into::into).collect()))
+    }
+
+    /// Require at least one of a set of queries to be satisfied
+    pub fn any<I>(self, queries: I) -> Self
+    where
+        I: IntoIterator<Item = Query>,
+    {
+        self.with(Query::Or(queries.into_iter().collect()))
+    }
+
+    /// Require a query not to be satisfied
+    pub fn not(self, query: Query) -> Self {
+        self.with(Query::Not(Box::new(query)))
+    }
+
+    /// Require an existing query to be satisfied
+    pub fn with(mut self, query: Query) -> Self {
+        self.clauses.push(query);
+        self
+    }
+
+    /// Check and return the query. A builder without clauses produces the
+    /// empty query, which matches every record.
+    pub fn build(mut self) -> Result<Query, QueryError> {
+        let query = if self.clauses.len() == 1 {
+            self.clauses.remove(0)
+        } else {
+            Query::And(self.clauses)
+        };
+        check_query(&query)?;
+        Ok(query)
+    }
+}
+
+/// Check a query for empty or reserved tag names, empty value lists, and
+/// empty combinations of clauses other than the top-level empty query
+pub fn check_query(query: &Query) -> Result<(), QueryError> {
+    fn check_name(name: &str) -> Result<(), QueryError> {
+        if name.is_empty() {
+            Err(QueryError::new("Tag names must not be empty"))
+        } else if name.starts_with('$') {
+            Err(QueryError::new(format!(
+                "Tag names must not begin with '$': {}",
+                name
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_nested(query: &Query) -> Result<(), QueryError> {
+        match query {
+            Query::And(queries) if queries.is_empty() => Err(QueryError::new(
+                "Nested combinations of clauses must not be empty",
+            )),
+            query => check_query(query),
+        }
+    }
+
+    match query {
+        // an empty $or would be emitted as the empty query, matching anything
+        Query::Or(queries) if queries.is_empty() => {
+            Err(QueryError::new("$or must be used with at least one clause"))
+        }
+        Query::And(queries) | Query::Or(queries) => queries.iter().try_for_each(check_nested),
+        Query::Not(query) => check_nested(query),
+        Query::Eq(name, _)
+        | Query::Neq(name, _)
+        | Query::Gt(name, _)
+        | Query::Gte(name, _)
+        | Query::Lt(name, _)
+        | Query::Lte(name, _)
+        | Query::Like(name, _) => check_name(name),
+        Query::In(name, values) => {
+            check_name(name)?;
+            if values.is_empty() {
+                return Err(QueryError::new(format!(
+                    "$in must be used with at least one value for tag: {}",
+                    name
+                )));
+            }
+            Ok(())
+        }
+        Query::Exist(names) => {
+            if names.is_empty() {
+                return Err(QueryError::new("$exist must be used with at least one tag"));
+            }
+            names.iter().try_for_each(|name| check_name(name))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_query() {
+        let query = QueryBuilder::new()
+            .eq("schema_name", "gvt")
+            .one_of("issuer_did", vec!["A", "B"])
+            .not(
+                QueryBuilder::new()
+                    .eq("attr::age::value", "18")
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(
+            query,
+            Query::And(vec![
+                Query::Eq("schema_name".to_string(), "gvt".to_string()),
+                Query::In(
+                    "issuer_did".to_string(),
+                    vec!["A".to_string(), "B".to_string()]
+                ),
+                Query::Not(Box::new(Query::Eq(
+                    "attr::age::value".to_string(),
+                    "18".to_string()
+                ))),
+            ])
+        );
+        assert_eq!(
+            QueryBuilder::new().eq("a", "b").build().unwrap(),
+            Query::Eq("a".to_string(), "b".to_string())
+        );
+        assert_eq!(QueryBuilder::new().build().unwrap(), Query::And(vec![]));
+    }
+
+    #[cfg(feature = "serde_support")]
+    #[test]
+    fn emit_query_json() {
+        let query = QueryBuilder::new()
+            .eq("schema_name", "gvt")
+            .any(vec![
+                Query::Eq("issuer_did".to_string(), "A".to_string()),
+                Query::Eq("issuer_did".to_string(), "B".to_string()),
+            ])
+            .build()
+            .unwrap();
+        let json = query.to_string();
+        assert_eq!(serde_json::from_str::<Query>(&json).unwrap(), query);
+    }
+
+    #[test]
+    fn reject_invalid_queries() {
+        assert!(QueryBuilder::new().eq("", "value").build().is_err());
+        assert!(QueryBuilder::new().eq("$or", "value").build().is_err());
+        assert!(QueryBuilder::new()
+            .one_of("name", Vec::<String>::new())
+            .build()
+            .is_err());
+        assert!(QueryBuilder::new()
+            .exist(Vec::<String>::new())
+            .build()
+            .is_err());
+        assert!(QueryBuilder::new().any(vec![]).build().is_err());
+        assert!(QueryBuilder::new().not(Query::And(vec![])).build().is_err());
+    }
+}
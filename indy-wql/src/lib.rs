@@ -2,6 +2,9 @@
 
 #![deny(missing_debug_implementations, missing_docs, rust_2018_idioms)]
 
+pub mod builder;
+pub use builder::{QueryBuilder, QueryError};
+
 /// An abstract query representation over a key and value type
 #[derive(Debug, Hash, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum AbstractQuery<K, V> {
@@ -171,7 +174,7 @@ impl<K, V> Default for AbstractQuery<K, V> {
 
 #[cfg(feature = "serde_support")]
 mod serde_support {
-    use std::string;
+    use std::fmt;
 
     use serde::ser::{Serialize, Serializer};
     use serde::{de, Deserialize, Deserializer};
@@ -200,9 +203,7 @@ mod serde_support {
             let v = JsonValue::deserialize(deserializer)?;
 
             match v {
-                JsonValue::Object(map) => {
-                    parse_query(map).map_err(|err| de::Error::missing_field(err))
-                }
+                JsonValue::Object(map) => parse_query(map).map_err(de::Error::missing_field),
                 JsonValue::Array(array) => {
                     // cast old restrictions format to wql
                     let mut res: Vec<JsonValue> = Vec::new();
@@ -212,7 +213,7 @@ mod serde_support {
                             .ok_or_else(|| de::Error::custom("Restriction is invalid"))?
                             .clone()
                             .into_iter()
-                            .filter(|&(_, ref v)| !v.is_null())
+                            .filter(|(_, v)| !v.is_null())
                             .collect();
 
                         if !sub_query.is_empty() {
@@ -223,7 +224,7 @@ mod serde_support {
                     let mut map = serde_json::Map::new();
                     map.insert("$or".to_string(), JsonValue::Array(res));
 
-                    parse_query(map).map_err(|err| de::Error::custom(err))
+                    parse_query(map).map_err(de::Error::custom)
                 }
                 _ => Err(de::Error::missing_field(
                     "Restriction must be either object or array",
@@ -273,9 +274,9 @@ mod serde_support {
         }
     }
 
-    impl string::ToString for Query {
-        fn to_string(&self) -> String {
-            self.to_value().to_string()
+    impl fmt::Display for Query {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.to_value())
         }
     }
 
@@ -343,7 +344,7 @@ mod serde_support {
             (_, JsonValue::Object(map)) => {
                 if map.len() == 1 {
                     let (operator_name, value) = map.into_iter().next().unwrap();
-                    parse_single_operator(operator_name, key, value).map(|operator| Some(operator))
+                    parse_single_operator(operator_name, key, value).map(Some)
                 } else {
                     Err("value must be JSON object of length 1")
                 }
@@ -2854,7 +2855,7 @@ mod tests {
 
     #[test]
     fn test_old_format_empty() {
-        let json = format!(r#"[]"#);
+        let json = r#"[]"#.to_string();
 
         let query: Query = ::serde_json::from_str(&json).unwrap();
 
@@ -2895,7 +2896,7 @@ mod tests {
     fn test_optimise_or() {
         let json = r#"[]"#;
 
-        let query: Query = ::serde_json::from_str(&json).unwrap();
+        let query: Query = ::serde_json::from_str(json).unwrap();
 
         assert_eq!(query.optimise(), None);
     }
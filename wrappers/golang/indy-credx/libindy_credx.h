@@ -13,9 +13,12 @@ enum ErrorCode {
   InvalidUserRevocId = 6,
   ProofRejected = 7,
   RevocationRegistryFull = 8,
+  Cancelled = 9,
+  HandleEvicted = 10,
+  LimitExceeded = 11,
 };
 typedef uintptr_t ErrorCode;
-typedef uintptr_t ObjectHandle;
+typedef int64_t ObjectHandle;
 
 typedef const char *FfiStr;
 
@@ -79,27 +82,136 @@ typedef struct FfiRevocationEntry {
   int64_t timestamp;
 } FfiRevocationEntry;
 
+typedef struct FfiRevocationDeltaEntry {
+  ObjectHandle delta;
+  int64_t timestamp;
+} FfiRevocationDeltaEntry;
+
+typedef struct FfiList_FfiRevocationDeltaEntry {
+  uintptr_t count;
+  const struct FfiRevocationDeltaEntry *data;
+} FfiList_FfiRevocationDeltaEntry;
+
 typedef struct FfiList_FfiRevocationEntry {
   uintptr_t count;
   const struct FfiRevocationEntry *data;
 } FfiList_FfiRevocationEntry;
 
+typedef void (*FatalHandler)(const char *report_json);
+
+ErrorCode credx_aries_issue_credential(ObjectHandle cred,
+                                       FfiStr thread_id,
+                                       FfiStr comment,
+                                       const char **message_p);
+
+ErrorCode credx_aries_message_get_attachment(FfiStr message,
+                                             FfiStr format,
+                                             ObjectHandle *handle_p);
+
+ErrorCode credx_aries_offer_credential(ObjectHandle cred_offer,
+                                       FfiStrList preview_names,
+                                       FfiStrList preview_values,
+                                       FfiStr comment,
+                                       const char **message_p);
+
+ErrorCode credx_aries_presentation(ObjectHandle presentation,
+                                   FfiStr thread_id,
+                                   FfiStr comment,
+                                   const char **message_p);
+
+ErrorCode credx_aries_request_credential(ObjectHandle cred_request,
+                                         FfiStr thread_id,
+                                         FfiStr comment,
+                                         const char **message_p);
+
+ErrorCode credx_aries_request_presentation(ObjectHandle pres_req,
+                                           FfiStr comment,
+                                           const char **message_p);
+
+ErrorCode credx_aries_revocation_notification(FfiStr rev_reg_id,
+                                              int64_t cred_rev_idx,
+                                              FfiStr comment,
+                                              const char **message_p);
+
+ErrorCode credx_attachment_decode(FfiStr attachment, FfiStr format, ObjectHandle *handle_p);
+
+ErrorCode credx_attachment_encode(ObjectHandle handle,
+                                  FfiStr attach_id,
+                                  const char **attachment_p,
+                                  const char **format_p);
+
 void credx_buffer_free(ByteBuffer buffer);
 
 void credx_string_free(const char *error_json_p);
 
+ErrorCode credx_buffer_free_checked(ByteBuffer buffer);
+
+ErrorCode credx_string_free_checked(const char *value);
+
+ErrorCode credx_set_allocation_debug(int8_t enabled);
+
+ErrorCode credx_get_allocation_report(const char **report_p);
+
 ErrorCode credx_set_default_logger(void);
 
+ErrorCode credx_set_tails_encryption_key(ByteBuffer key);
+
+ErrorCode credx_set_thread_pool_size(int64_t size);
+
+ErrorCode credx_set_clock(int64_t fixed_time, int64_t offset_secs);
+
+ErrorCode credx_get_current_time(int64_t *time_p);
+
+ErrorCode credx_set_serialization_profile(FfiStr profile);
+
+ErrorCode credx_set_verification_limits(int64_t max_referents,
+                                        int64_t max_credentials,
+                                        int64_t max_json_size);
+
+ErrorCode credx_register_attribute_encoder(FfiStr schema_id, FfiStr pattern, FfiStr encoder);
+
+ErrorCode credx_clear_attribute_encoders(void);
+
 char *credx_version(void);
 
 ErrorCode credx_get_current_error(const char **error_json_p);
 
+ErrorCode credx_object_from_entry(FfiStr category, ByteBuffer value, ObjectHandle *result_p);
+
+ErrorCode credx_object_get_entry(ObjectHandle handle,
+                                 const char **category_p,
+                                 const char **name_p,
+                                 const char **tags_p);
+
 ErrorCode credx_object_get_json(ObjectHandle handle, ByteBuffer *result_p);
 
+ErrorCode credx_object_get_json_into(ObjectHandle handle,
+                                     uint8_t *buf,
+                                     uintptr_t buf_len,
+                                     uintptr_t *required_len);
+
 ErrorCode credx_object_get_type_name(ObjectHandle handle, const char **result_p);
 
+ErrorCode credx_object_copy(ObjectHandle handle, ObjectHandle *new_handle_p);
+
+ErrorCode credx_object_set_tag(ObjectHandle handle, ByteBuffer tag);
+
+ErrorCode credx_object_get_tag(ObjectHandle handle, ByteBuffer *tag_p);
+
 void credx_object_free(ObjectHandle handle);
 
+ErrorCode credx_object_free_checked(ObjectHandle handle);
+
+ErrorCode credx_object_list_free(FfiList_ObjectHandle handles);
+
+ErrorCode credx_set_handle_lifetime(FfiStr type_name, int64_t ttl_secs, int64_t idle_secs);
+
+ErrorCode credx_evict_expired_handles(int64_t *count_p);
+
+ErrorCode credx_create_cancellation_token(ObjectHandle *token_p);
+
+ErrorCode credx_cancellation_token_cancel(ObjectHandle token);
+
 ErrorCode credx_create_credential_definition(FfiStr origin_did,
                                              ObjectHandle schema,
                                              FfiStr tag,
@@ -109,6 +221,25 @@ ErrorCode credx_create_credential_definition(FfiStr origin_did,
                                              ObjectHandle *cred_def_pvt_p,
                                              ObjectHandle *key_proof_p);
 
+ErrorCode credx_create_credential_definition_cancellable(FfiStr origin_did,
+                                                         ObjectHandle schema,
+                                                         FfiStr tag,
+                                                         FfiStr signature_type,
+                                                         int8_t support_revocation,
+                                                         ObjectHandle cancel_token,
+                                                         ObjectHandle *cred_def_p,
+                                                         ObjectHandle *cred_def_pvt_p,
+                                                         ObjectHandle *key_proof_p);
+
+ErrorCode credx_create_credential_definition_from_seed(FfiStr origin_did,
+                                                       ObjectHandle schema,
+                                                       FfiStr tag,
+                                                       FfiStr signature_type,
+                                                       ByteBuffer seed,
+                                                       ObjectHandle *cred_def_p,
+                                                       ObjectHandle *cred_def_pvt_p,
+                                                       ObjectHandle *key_proof_p);
+
 ErrorCode credx_credential_definition_get_attribute(ObjectHandle handle,
                                                     FfiStr name,
                                                     const char **result_p);
@@ -118,6 +249,16 @@ ErrorCode credx_create_credential_offer(FfiStr schema_id,
                                         ObjectHandle key_proof,
                                         ObjectHandle *cred_offer_p);
 
+ErrorCode credx_create_credential_offers(FfiStr schema_id,
+                                         ObjectHandle cred_def,
+                                         ObjectHandle key_proof,
+                                         int64_t count,
+                                         ObjectHandle *cred_offers_p);
+
+ErrorCode credx_credential_offer_get_attribute(ObjectHandle handle,
+                                               FfiStr name,
+                                               const char **result_p);
+
 ErrorCode credx_create_credential_request(FfiStr prover_did,
                                           ObjectHandle cred_def,
                                           ObjectHandle master_secret,
@@ -126,6 +267,36 @@ ErrorCode credx_create_credential_request(FfiStr prover_did,
                                           ObjectHandle *cred_req_p,
                                           ObjectHandle *cred_req_meta_p);
 
+ErrorCode credx_create_committed_credential_request(FfiStr prover_did,
+                                                    ObjectHandle cred_def,
+                                                    ObjectHandle master_secret,
+                                                    FfiStr master_secret_id,
+                                                    ObjectHandle cred_offer,
+                                                    FfiStrList attr_names,
+                                                    FfiStrList attr_raw_values,
+                                                    ObjectHandle *cred_req_p,
+                                                    ObjectHandle *cred_req_meta_p);
+
+ErrorCode credx_credential_request_get_attribute(ObjectHandle handle,
+                                                 FfiStr name,
+                                                 const char **result_p);
+
+ErrorCode credx_credential_request_metadata_rename_master_secret(ObjectHandle cred_req_metadata,
+                                                                 FfiStr master_secret_id,
+                                                                 ObjectHandle *cred_req_meta_p);
+
+ErrorCode credx_credential_request_metadata_has_master_secret(ObjectHandle cred_req_metadata,
+                                                              FfiStr master_secret_id,
+                                                              int8_t *result_p);
+
+ErrorCode credx_credential_request_metadata_wrap(ObjectHandle cred_req_metadata,
+                                                 ByteBuffer key,
+                                                 const char **wrapped_p);
+
+ErrorCode credx_credential_request_metadata_unwrap(FfiStr wrapped,
+                                                   ByteBuffer key,
+                                                   ObjectHandle *cred_req_meta_p);
+
 ErrorCode credx_create_credential(ObjectHandle cred_def,
                                   ObjectHandle cred_def_private,
                                   ObjectHandle cred_offer,
@@ -147,8 +318,48 @@ ErrorCode credx_process_credential(ObjectHandle cred,
                                    ObjectHandle rev_reg_def,
                                    ObjectHandle *cred_p);
 
+ErrorCode credx_process_and_extract_credential(ObjectHandle cred,
+                                               ObjectHandle cred_offer,
+                                               ObjectHandle cred_req_metadata,
+                                               ObjectHandle master_secret,
+                                               ObjectHandle cred_def,
+                                               ObjectHandle rev_reg_def,
+                                               ObjectHandle *cred_p,
+                                               const char **attributes_p);
+
 ErrorCode credx_credential_get_attribute(ObjectHandle handle, FfiStr name, const char **result_p);
 
+ErrorCode credx_credential_get_attribute_names(ObjectHandle handle, const char **names_p);
+
+ErrorCode credx_credential_get_attribute_value(ObjectHandle handle,
+                                               FfiStr name,
+                                               const char **raw_p,
+                                               const char **encoded_p);
+
+ErrorCode credx_credential_derive_values(ObjectHandle cred,
+                                         FfiStrList attr_names,
+                                         const char **result_p);
+
+ErrorCode credx_credential_search_fetch(ObjectHandle search,
+                                       int64_t limit,
+                                       ObjectHandle *handles_p,
+                                       int64_t *count_p);
+
+ErrorCode credx_credential_search_free(ObjectHandle search);
+
+ErrorCode credx_credential_search_open(ObjectHandle pres_req,
+                                      FfiStr referent,
+                                      FfiList_ObjectHandle credentials,
+                                      ObjectHandle *search_p);
+
+ErrorCode credx_credential_set_display(ObjectHandle cred,
+                                      FfiStr display_json,
+                                      ObjectHandle *cred_p);
+
+ErrorCode credx_credential_tags(ObjectHandle cred, const char **tags_p);
+
+ErrorCode credx_check_credential_encoding(ObjectHandle cred, const char **mismatched_p);
+
 ErrorCode credx_create_master_secret(ObjectHandle *master_secret_p);
 
 ErrorCode credx_master_secret_from_json(ByteBuffer json, ObjectHandle *pointer);
@@ -157,14 +368,80 @@ ErrorCode credx_credential_request_from_json(ByteBuffer json, ObjectHandle *poin
 
 ErrorCode credx_credential_request_metadata_from_json(ByteBuffer json, ObjectHandle *pointer);
 
+ErrorCode credx_credential_definition_validate(ObjectHandle cred_def,
+                                               ObjectHandle schema,
+                                               const char **report_p);
+
+ErrorCode credx_object_from_msgpack(FfiStr type_name, ByteBuffer data, ObjectHandle *result_p);
+
+ErrorCode credx_object_get_msgpack(ObjectHandle handle, ByteBuffer *result_p);
+
+ErrorCode credx_object_from_proto(FfiStr type_name, ByteBuffer data, ObjectHandle *result_p);
+
+ErrorCode credx_object_get_proto(ObjectHandle handle, ByteBuffer *result_p);
+
+ErrorCode credx_proto_definitions(const char **result_p);
+
+ErrorCode credx_object_validate(ObjectHandle handle, const char **report_p);
+
+ErrorCode credx_decode_data(FfiStr value, FfiStr encoding, ByteBuffer *result_p);
+
+ErrorCode credx_encode_data(ByteBuffer data, FfiStr encoding, const char **result_p);
+
+ErrorCode credx_multibase_decode(FfiStr value, const char **base_p, ByteBuffer *result_p);
+
+ErrorCode credx_multibase_encode(ByteBuffer data, FfiStr base, const char **result_p);
+
+ErrorCode credx_encrypt_tails_file(FfiStr source_path,
+                                   FfiStr dest_path,
+                                   ByteBuffer key,
+                                   const char **hash_p);
+
+ErrorCode credx_envelope_open(FfiStr envelope, ByteBuffer recipient_secret, ByteBuffer *result_p);
+
+ErrorCode credx_envelope_seal(ObjectHandle handle,
+                              ByteBuffer recipient_key,
+                              FfiStr kid,
+                              const char **envelope_p);
+
+ErrorCode credx_set_fatal_handler(FatalHandler callback);
+
 ErrorCode credx_generate_nonce(const char **nonce_p);
 
 ErrorCode credx_presentation_request_from_json(ByteBuffer json, ObjectHandle *pointer);
 
+ErrorCode credx_presentation_request_get_attribute(ObjectHandle handle,
+                                                   FfiStr name,
+                                                   const char **result_p);
+
+ErrorCode credx_presentation_request_to_version(ObjectHandle handle,
+                                               FfiStr ver,
+                                               ObjectHandle *pres_req_p);
+
+ErrorCode credx_register_proof_request_template(FfiStr name, FfiStr template_json);
+
+ErrorCode credx_remove_proof_request_template(FfiStr name);
+
+ErrorCode credx_create_presentation_request_from_template(FfiStr name,
+                                                          FfiStr values_json,
+                                                          ObjectHandle *pres_req_p);
+
+ErrorCode credx_merge_presentation_requests(FfiStr name,
+                                            FfiStr version,
+                                            struct FfiList_ObjectHandle pres_reqs,
+                                            ObjectHandle *pres_req_p);
+
 ErrorCode credx_presentation_from_json(ByteBuffer json, ObjectHandle *pointer);
 
 ErrorCode credx_credential_offer_from_json(ByteBuffer json, ObjectHandle *pointer);
 
+ErrorCode credx_select_revocation_state(ObjectHandle pres_req,
+                                        FfiStrList attr_referents,
+                                        FfiStrList predicate_referents,
+                                        struct FfiList_ObjectHandle rev_states,
+                                        int64_t *index_p,
+                                        int64_t *timestamp_p);
+
 ErrorCode credx_create_presentation(ObjectHandle pres_req,
                                     struct FfiList_FfiCredentialEntry credentials,
                                     struct FfiList_FfiCredentialProve credentials_prove,
@@ -175,6 +452,27 @@ ErrorCode credx_create_presentation(ObjectHandle pres_req,
                                     struct FfiList_ObjectHandle cred_defs,
                                     ObjectHandle *presentation_p);
 
+ErrorCode credx_create_auditable_presentation(ObjectHandle pres_req,
+                                              struct FfiList_FfiCredentialEntry credentials,
+                                              struct FfiList_FfiCredentialProve credentials_prove,
+                                              FfiStrList self_attest_names,
+                                              FfiStrList self_attest_values,
+                                              ObjectHandle master_secret,
+                                              struct FfiList_ObjectHandle schemas,
+                                              struct FfiList_ObjectHandle cred_defs,
+                                              ObjectHandle *presentation_p);
+
+ErrorCode credx_create_presentation_from_seed(ObjectHandle pres_req,
+                                              struct FfiList_FfiCredentialEntry credentials,
+                                              struct FfiList_FfiCredentialProve credentials_prove,
+                                              FfiStrList self_attest_names,
+                                              FfiStrList self_attest_values,
+                                              ObjectHandle master_secret,
+                                              struct FfiList_ObjectHandle schemas,
+                                              struct FfiList_ObjectHandle cred_defs,
+                                              ByteBuffer seed,
+                                              ObjectHandle *presentation_p);
+
 ErrorCode credx_verify_presentation(ObjectHandle presentation,
                                     ObjectHandle pres_req,
                                     struct FfiList_ObjectHandle schemas,
@@ -183,6 +481,32 @@ ErrorCode credx_verify_presentation(ObjectHandle presentation,
                                     struct FfiList_FfiRevocationEntry rev_reg_entries,
                                     int8_t *result_p);
 
+ErrorCode credx_verify_presentation_with_policy(ObjectHandle presentation,
+                                                ObjectHandle pres_req,
+                                                struct FfiList_ObjectHandle schemas,
+                                                struct FfiList_ObjectHandle cred_defs,
+                                                struct FfiList_ObjectHandle rev_reg_defs,
+                                                struct FfiList_FfiRevocationEntry rev_reg_entries,
+                                                FfiStr policy_json,
+                                                int8_t *result_p);
+
+ErrorCode credx_verify_presentations_batch(struct FfiList_ObjectHandle presentations,
+                                           struct FfiList_ObjectHandle pres_reqs,
+                                           struct FfiList_ObjectHandle schemas,
+                                           struct FfiList_ObjectHandle cred_defs,
+                                           struct FfiList_ObjectHandle rev_reg_defs,
+                                           struct FfiList_FfiRevocationEntry rev_reg_entries,
+                                           FfiStr policy_json,
+                                           const char **results_json_p);
+
+ErrorCode credx_presentation_get_attribute(ObjectHandle handle,
+                                           FfiStr name,
+                                           const char **result_p);
+
+ErrorCode credx_presentation_check_accepted_issuers(ObjectHandle presentation,
+                                                    FfiStr policy_json,
+                                                    const char **mismatches_p);
+
 ErrorCode credx_create_revocation_registry(FfiStr origin_did,
                                            ObjectHandle cred_def,
                                            FfiStr tag,
@@ -195,6 +519,44 @@ ErrorCode credx_create_revocation_registry(FfiStr origin_did,
                                            ObjectHandle *reg_entry_p,
                                            ObjectHandle *reg_init_delta_p);
 
+ErrorCode credx_create_revocation_registry_cancellable(FfiStr origin_did,
+                                                       ObjectHandle cred_def,
+                                                       FfiStr tag,
+                                                       FfiStr rev_reg_type,
+                                                       FfiStr issuance_type,
+                                                       int64_t max_cred_num,
+                                                       FfiStr tails_dir_path,
+                                                       ObjectHandle cancel_token,
+                                                       ObjectHandle *reg_def_p,
+                                                       ObjectHandle *reg_def_private_p,
+                                                       ObjectHandle *reg_entry_p,
+                                                       ObjectHandle *reg_init_delta_p);
+
+ErrorCode credx_create_revocation_registry_lazy(FfiStr origin_did,
+                                                ObjectHandle cred_def,
+                                                FfiStr tag,
+                                                FfiStr rev_reg_type,
+                                                FfiStr issuance_type,
+                                                int64_t max_cred_num,
+                                                ObjectHandle *reg_def_p,
+                                                ObjectHandle *reg_def_private_p,
+                                                ObjectHandle *reg_entry_p,
+                                                ObjectHandle *reg_init_delta_p);
+
+ErrorCode credx_create_revocation_registry_chunked(FfiStr origin_did,
+                                                   ObjectHandle cred_def,
+                                                   FfiStr tag,
+                                                   FfiStr rev_reg_type,
+                                                   FfiStr issuance_type,
+                                                   int64_t max_cred_num,
+                                                   FfiStr tails_dir_path,
+                                                   int64_t tails_chunk_size,
+                                                   ObjectHandle cancel_token,
+                                                   ObjectHandle *reg_def_p,
+                                                   ObjectHandle *reg_def_private_p,
+                                                   ObjectHandle *reg_entry_p,
+                                                   ObjectHandle *reg_init_delta_p);
+
 ErrorCode credx_revocation_registry_definition_from_json(ByteBuffer json, ObjectHandle *pointer);
 
 ErrorCode credx_revocation_registry_from_json(ByteBuffer json, ObjectHandle *pointer);
@@ -230,10 +592,66 @@ ErrorCode credx_credential_definition_private_from_json(ByteBuffer json, ObjectH
 
 ErrorCode credx_key_correctness_proof_from_json(ByteBuffer json, ObjectHandle *pointer);
 
+ErrorCode credx_tails_verify(FfiStr tails_path,
+                             FfiStr expected_hash,
+                             int8_t *valid_p,
+                             const char **hash_p);
+
+ErrorCode credx_tails_verify_buffer(ByteBuffer tails,
+                                    FfiStr expected_hash,
+                                    int8_t *valid_p,
+                                    const char **hash_p);
+
+ErrorCode credx_write_revocation_registry_tails(ObjectHandle cred_def,
+                                                ObjectHandle rev_reg_def,
+                                                ObjectHandle rev_reg_def_private,
+                                                FfiStr tails_dir_path,
+                                                int64_t tails_chunk_size,
+                                                ObjectHandle cancel_token,
+                                                ObjectHandle *rev_reg_def_p);
+
+ErrorCode credx_write_revocation_registry_tails_to_buffer(ObjectHandle cred_def,
+                                                          ObjectHandle rev_reg_def,
+                                                          ObjectHandle rev_reg_def_private,
+                                                          FfiStr tails_location,
+                                                          ObjectHandle cancel_token,
+                                                          ObjectHandle *rev_reg_def_p,
+                                                          ByteBuffer *tails_p);
+
+ErrorCode credx_extend_revocation_registry_tails(ObjectHandle cred_def,
+                                                 ObjectHandle rev_reg_def,
+                                                 ObjectHandle rev_reg_def_private,
+                                                 FfiStr partial_path,
+                                                 int64_t count,
+                                                 int64_t *remaining_p);
+
+ErrorCode credx_complete_revocation_registry_tails(ObjectHandle cred_def,
+                                                   ObjectHandle rev_reg_def,
+                                                   ObjectHandle rev_reg_def_private,
+                                                   FfiStr partial_path,
+                                                   FfiStr tails_dir_path,
+                                                   int64_t tails_chunk_size,
+                                                   ObjectHandle cancel_token,
+                                                   ObjectHandle *rev_reg_def_p);
+
 ErrorCode credx_merge_revocation_registry_deltas(ObjectHandle rev_reg_delta_1,
                                                  ObjectHandle rev_reg_delta_2,
                                                  ObjectHandle *rev_reg_delta_p);
 
+ErrorCode credx_revocation_registry_from_deltas(struct FfiList_FfiRevocationDeltaEntry deltas,
+                                                int64_t timestamp,
+                                                ObjectHandle *rev_reg_p);
+
+ErrorCode credx_revocation_registry_to_delta(ObjectHandle rev_reg,
+                                             ObjectHandle prev_rev_reg,
+                                             struct FfiList_i64 issued,
+                                             struct FfiList_i64 revoked,
+                                             ObjectHandle *rev_reg_delta_p);
+
+ErrorCode credx_revocation_registry_delta_get_attribute(ObjectHandle handle,
+                                                        FfiStr name,
+                                                        const char **result_p);
+
 ErrorCode credx_create_or_update_revocation_state(ObjectHandle rev_reg_def,
                                                   ObjectHandle rev_reg_delta,
                                                   int64_t rev_reg_index,
@@ -242,6 +660,14 @@ ErrorCode credx_create_or_update_revocation_state(ObjectHandle rev_reg_def,
                                                   ObjectHandle rev_state,
                                                   ObjectHandle *rev_state_p);
 
+ErrorCode credx_create_or_update_revocation_states(ObjectHandle rev_reg_def,
+                                                   ObjectHandle rev_reg_delta,
+                                                   struct FfiList_i64 rev_reg_indices,
+                                                   struct FfiList_ObjectHandle rev_states,
+                                                   int64_t timestamp,
+                                                   FfiStr tails_path,
+                                                   ObjectHandle *rev_states_p);
+
 ErrorCode credx_create_schema(FfiStr origin_did,
                               FfiStr schema_name,
                               FfiStr schema_version,
@@ -252,3 +678,7 @@ ErrorCode credx_create_schema(FfiStr origin_did,
 ErrorCode credx_schema_from_json(ByteBuffer json, ObjectHandle *pointer);
 
 ErrorCode credx_schema_get_attribute(ObjectHandle handle, FfiStr name, const char **result_p);
+
+ErrorCode credx_get_operation_stats(const char **stats_p);
+
+ErrorCode credx_reset_operation_stats(void);
@@ -32,7 +32,7 @@ func handleLibError(context string) error {
 
 // Generics are part of go 1.8, it might make sense to refactor some this code using generics
 
-type ObjectHandle C.ulong
+type ObjectHandle C.ObjectHandle
 
 type StrBuffer *C.char
 
@@ -127,7 +127,7 @@ func genericToJSON(indyObject IndyObject) (json.RawMessage, error) {
 	buf := NewByteBuffer(4096)
 	defer buf.Close()
 
-	if err := C.credx_object_get_json((C.ulong)(indyObject.getHandle()), (*C.ByteBuffer)(&buf)); err != 0 {
+	if err := C.credx_object_get_json((C.ObjectHandle)(indyObject.getHandle()), (*C.ByteBuffer)(&buf)); err != 0 {
 		objectType := reflect.TypeOf(indyObject).String()
 		context := fmt.Sprintf("Couldn't get %s as json, received code %d", objectType, int(err))
 
@@ -143,17 +143,17 @@ type FfiListObjectHandle C.FfiList_ObjectHandle
 
 func NewFfiListObjectHandle(list []ObjectHandle) FfiListObjectHandle {
 	cCount := C.uintptr_t(len(list))
-	cArray := C.malloc(C.size_t(len(list)))
+	cArray := C.malloc(C.size_t(len(list)) * C.size_t(unsafe.Sizeof(C.ObjectHandle(0))))
 
-	a := (*[1 << 30]C.ulong)(cArray)
+	a := (*[1 << 30]C.ObjectHandle)(cArray)
 
 	for index, item := range list {
-		a[index] = (C.ulong)(item)
+		a[index] = (C.ObjectHandle)(item)
 	}
 
 	return FfiListObjectHandle{
 		count: cCount,
-		data:  (*C.ulong)(cArray),
+		data:  (*C.ObjectHandle)(cArray),
 	}
 }
 
@@ -36,17 +36,17 @@ func NewCredential(
 	cCredRevInfo := credentialRevocationInfo.ToC()
 
 	err := C.credx_create_credential(
-		(C.ulong)(credDef.handle),
-		(C.ulong)(credDefPvt.handle),
-		(C.ulong)(credOffer.handle),
-		(C.ulong)(credRequest.handle),
+		(C.ObjectHandle)(credDef.handle),
+		(C.ObjectHandle)(credDefPvt.handle),
+		(C.ObjectHandle)(credOffer.handle),
+		(C.ObjectHandle)(credRequest.handle),
 		(C.FfiStrList)(cAttrNames),
 		(C.FfiStrList)(cAttrRawValues),
 		(C.FfiStrList)(cAttrEnc),
 		(*C.FfiCredRevInfo)(cCredRevInfo),
-		(*C.ulong)(&credHandle),
-		(*C.ulong)(&revRegHandle),
-		(*C.ulong)(&revDeltaHandle),
+		(*C.ObjectHandle)(&credHandle),
+		(*C.ObjectHandle)(&revRegHandle),
+		(*C.ObjectHandle)(&revDeltaHandle),
 	)
 
 	if err != 0 {
@@ -70,7 +70,7 @@ func LoadCredentialFromJSON(json json.RawMessage) (*Credential, error) {
 	var handle ObjectHandle
 	buf := ByteBufferFromRawMessage(json)
 
-	err := C.credx_credential_from_json((C.ByteBuffer)(buf), (*C.ulong)(&handle))
+	err := C.credx_credential_from_json((C.ByteBuffer)(buf), (*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create Credential from json, recevied code %d.", int(err))
@@ -95,12 +95,12 @@ func (c *Credential) Process(
 	var credHandle ObjectHandle
 
 	err := C.credx_process_credential(
-		(C.ulong)(c.handle),
-		(C.ulong)(metadata.handle),
-		(C.ulong)(secret.handle),
-		(C.ulong)(definition.handle),
-		(C.ulong)(registryDefinition.handle),
-		(*C.ulong)(&credHandle),
+		(C.ObjectHandle)(c.handle),
+		(C.ObjectHandle)(metadata.handle),
+		(C.ObjectHandle)(secret.handle),
+		(C.ObjectHandle)(definition.handle),
+		(C.ObjectHandle)(registryDefinition.handle),
+		(*C.ObjectHandle)(&credHandle),
 	)
 
 	if err != 0 {
@@ -146,7 +146,7 @@ func (c *Credential) getCredentialAttr(attr string) (string, error) {
 	defer CloseStrBuffer(cArray)
 
 	err := C.credx_credential_get_attribute(
-		(C.ulong)(c.handle),
+		(C.ObjectHandle)(c.handle),
 		(C.FfiStr)(C.CString(attr)),
 		(**C.char)(&cArray),
 	)
@@ -164,7 +164,7 @@ func (c *Credential) getCredentialAttr(attr string) (string, error) {
 
 func (c *Credential) close() {
 	if !c.isClosed {
-		C.credx_object_free((C.ulong)(c.handle))
+		C.credx_object_free((C.ObjectHandle)(c.handle))
 	}
 
 	c.isClosed = true
@@ -189,13 +189,13 @@ func NewCredentialRevocationState(
 	}
 
 	err := C.credx_create_or_update_revocation_state(
-		(C.ulong)(registryDef.handle),
-		(C.ulong)(registryDelta.handle),
+		(C.ObjectHandle)(registryDef.handle),
+		(C.ObjectHandle)(registryDelta.handle),
 		C.int64_t(credRevInfo.regIdx),
 		C.int64_t(timestamp),
 		(*C.char)(C.CString(tails)),
-		(C.ulong)(0),
-		(*C.ulong)(&handle),
+		(C.ObjectHandle)(0),
+		(*C.ObjectHandle)(&handle),
 	)
 
 	if err != 0 {
@@ -223,13 +223,13 @@ func (p *CredentialRevocationState) Update(
 	var handle ObjectHandle
 
 	err := C.credx_create_or_update_revocation_state(
-		(C.ulong)(definition.handle),
-		(C.ulong)(delta.handle),
+		(C.ObjectHandle)(definition.handle),
+		(C.ObjectHandle)(delta.handle),
 		C.int64_t(revRegIndex),
 		C.int64_t(timestamp),
 		(*C.char)(C.CString(tailsPath)),
-		(C.ulong)(p.handle),
-		(*C.ulong)(&handle),
+		(C.ObjectHandle)(p.handle),
+		(*C.ObjectHandle)(&handle),
 	)
 
 	if err != 0 {
@@ -249,7 +249,7 @@ func (p *CredentialRevocationState) Update(
 
 func (p *CredentialRevocationState) close() {
 	if !p.isClosed {
-		C.credx_object_free((C.ulong)(p.handle))
+		C.credx_object_free((C.ObjectHandle)(p.handle))
 	}
 	p.isClosed = true
 }
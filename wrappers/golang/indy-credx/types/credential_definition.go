@@ -24,7 +24,7 @@ func LoadCredentialDefinitionPrivateFromJSON(json json.RawMessage) (*CredentialD
 	var handle ObjectHandle
 	buf := ByteBufferFromRawMessage(json)
 
-	err := C.credx_credential_definition_private_from_json((C.ByteBuffer)(buf), (*C.ulong)(&handle))
+	err := C.credx_credential_definition_private_from_json((C.ByteBuffer)(buf), (*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create cred def private from json, recevied code %d.", int(err))
@@ -46,7 +46,7 @@ func (c *CredentialDefinitionPrivate) ToJSON() (json.RawMessage, error) {
 
 func (c *CredentialDefinitionPrivate) close() {
 	if !c.isClosed {
-		C.credx_object_free((C.ulong)(c.handle))
+		C.credx_object_free((C.ObjectHandle)(c.handle))
 	}
 
 	c.isClosed = true
@@ -65,7 +65,7 @@ func LoadKeyCorrectnessProofFromJSON(json json.RawMessage) (*KeyCorrectnessProof
 	var handle ObjectHandle
 	buf := ByteBufferFromRawMessage(json)
 
-	err := C.credx_key_correctness_proof_from_json((C.ByteBuffer)(buf), (*C.ulong)(&handle))
+	err := C.credx_key_correctness_proof_from_json((C.ByteBuffer)(buf), (*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create cred def private from json, recevied code %d.", int(err))
@@ -87,7 +87,7 @@ func (c *KeyCorrectnessProof) ToJSON() (json.RawMessage, error) {
 
 func (c *KeyCorrectnessProof) close() {
 	if !c.isClosed {
-		C.credx_object_free((C.ulong)(c.handle))
+		C.credx_object_free((C.ObjectHandle)(c.handle))
 	}
 
 	c.isClosed = true
@@ -108,7 +108,7 @@ func (c *CredentialDefinition) ToJSON() (json.RawMessage, error) {
 
 func (c *CredentialDefinition) close() {
 	if !c.isClosed {
-		C.credx_object_free((C.ulong)(c.handle))
+		C.credx_object_free((C.ObjectHandle)(c.handle))
 	}
 
 	c.isClosed = true
@@ -137,13 +137,13 @@ func NewCredentialDefinition(
 
 	err := C.credx_create_credential_definition(
 		cDid,
-		(C.ulong)(schema.handle),
+		(C.ObjectHandle)(schema.handle),
 		cTag,
 		cSignatureType,
 		cSupportRevocation,
-		(*C.ulong)(&credDefHandle),
-		(*C.ulong)(&credDefPvtHandle),
-		(*C.ulong)(&keyProofHandle),
+		(*C.ObjectHandle)(&credDefHandle),
+		(*C.ObjectHandle)(&credDefPvtHandle),
+		(*C.ObjectHandle)(&keyProofHandle),
 	)
 
 	if err != 0 {
@@ -173,7 +173,7 @@ func LoadCredentialDefinitionFromJSON(json json.RawMessage) (*CredentialDefiniti
 	var handle ObjectHandle
 	buf := ByteBufferFromRawMessage(json)
 
-	err := C.credx_credential_definition_from_json((C.ByteBuffer)(buf), (*C.ulong)(&handle))
+	err := C.credx_credential_definition_from_json((C.ByteBuffer)(buf), (*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create cred def from json, recevied code %d.", int(err))
@@ -194,7 +194,7 @@ func (c *CredentialDefinition) getCredDefAttribute(attr string) (string, error)
 	defer CloseStrBuffer(cArray)
 
 	err := C.credx_credential_definition_get_attribute(
-		(C.ulong)(c.handle),
+		(C.ObjectHandle)(c.handle),
 		(C.FfiStr)(C.CString(attr)),
 		(**C.char)(&cArray),
 	)
@@ -26,9 +26,9 @@ func NewCredentialOffer(schemaId string, credDef *CredentialDefinition, proof *K
 
 	err := C.credx_create_credential_offer(
 		cSchemaId,
-		(C.ulong)(credDef.handle),
-		(C.ulong)(proof.handle),
-		(*C.ulong)(&handle),
+		(C.ObjectHandle)(credDef.handle),
+		(C.ObjectHandle)(proof.handle),
+		(*C.ObjectHandle)(&handle),
 	)
 
 	if err != 0 {
@@ -53,7 +53,7 @@ func LoadCredentialOfferFromJSON(json json.RawMessage) (*CredentialOffer, error)
 	var handle ObjectHandle
 	buf := ByteBufferFromRawMessage(json)
 
-	err := C.credx_credential_offer_from_json((C.ByteBuffer)(buf), (*C.ulong)(&handle))
+	err := C.credx_credential_offer_from_json((C.ByteBuffer)(buf), (*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create CredentialOffer from json, recevied code %d.", int(err))
@@ -71,7 +71,7 @@ func LoadCredentialOfferFromJSON(json json.RawMessage) (*CredentialOffer, error)
 
 func (c *CredentialOffer) close() {
 	if !c.isClosed {
-		C.credx_object_free((C.ulong)(c.handle))
+		C.credx_object_free((C.ObjectHandle)(c.handle))
 	}
 
 	c.isClosed = true
@@ -100,12 +100,12 @@ func NewCredentialRequest(
 
 	err := C.credx_create_credential_request(
 		cDid,
-		(C.ulong)(credDef.handle),
-		(C.ulong)(masterSecret.handle),
+		(C.ObjectHandle)(credDef.handle),
+		(C.ObjectHandle)(masterSecret.handle),
 		cMasterId,
-		(C.ulong)(credOffer.handle),
-		(*C.ulong)(&credReqHandle),
-		(*C.ulong)(&credReqMetaHandle),
+		(C.ObjectHandle)(credOffer.handle),
+		(*C.ObjectHandle)(&credReqHandle),
+		(*C.ObjectHandle)(&credReqMetaHandle),
 	)
 
 	if err != 0 {
@@ -134,7 +134,7 @@ func LoadCredentialRequestFromJSON(json json.RawMessage) (*CredentialRequest, er
 	var handle ObjectHandle
 	buf := ByteBufferFromRawMessage(json)
 
-	err := C.credx_credential_request_from_json((C.ByteBuffer)(buf), (*C.ulong)(&handle))
+	err := C.credx_credential_request_from_json((C.ByteBuffer)(buf), (*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create CredentialRequest from json, recevied code %d.", int(err))
@@ -152,7 +152,7 @@ func LoadCredentialRequestFromJSON(json json.RawMessage) (*CredentialRequest, er
 
 func (c *CredentialRequest) close() {
 	if !c.isClosed {
-		C.credx_object_free((C.ulong)(c.handle))
+		C.credx_object_free((C.ObjectHandle)(c.handle))
 	}
 
 	c.isClosed = true
@@ -175,7 +175,7 @@ func LoadCredentialRequestMetadataFromJSON(json json.RawMessage) (*CredentialReq
 	var handle ObjectHandle
 	buf := ByteBufferFromRawMessage(json)
 
-	err := C.credx_credential_request_metadata_from_json((C.ByteBuffer)(buf), (*C.ulong)(&handle))
+	err := C.credx_credential_request_metadata_from_json((C.ByteBuffer)(buf), (*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create CredentialRequestMetadata from json, recevied code %d.", int(err))
@@ -193,7 +193,7 @@ func LoadCredentialRequestMetadataFromJSON(json json.RawMessage) (*CredentialReq
 
 func (c *CredentialRequestMetadata) close() {
 	if !c.isClosed {
-		C.credx_object_free((C.ulong)(c.handle))
+		C.credx_object_free((C.ObjectHandle)(c.handle))
 	}
 
 	c.isClosed = true
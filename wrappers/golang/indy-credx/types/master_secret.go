@@ -22,7 +22,7 @@ func (c *MasterSecret) getHandle() ObjectHandle {
 
 func NewMasterSecret() (*MasterSecret, error) {
 	var handle ObjectHandle
-	err := C.credx_create_master_secret((*C.ulong)(&handle))
+	err := C.credx_create_master_secret((*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create MasterSecret, received code %d", int(err))
@@ -42,7 +42,7 @@ func LoadMasterSecretFromJSON(json json.RawMessage) (*MasterSecret, error) {
 	var handle ObjectHandle
 	buf := ByteBufferFromRawMessage(json)
 
-	err := C.credx_master_secret_from_json((C.ByteBuffer)(buf), (*C.ulong)(&handle))
+	err := C.credx_master_secret_from_json((C.ByteBuffer)(buf), (*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create master secret from json, recevied code %d.", int(err))
@@ -64,7 +64,7 @@ func (c *MasterSecret) ToJSON() (json.RawMessage, error) {
 
 func (c *MasterSecret) close() {
 	if !c.isClosed {
-		C.credx_object_free((C.ulong)(c.handle))
+		C.credx_object_free((C.ObjectHandle)(c.handle))
 	}
 	c.isClosed = true
 }
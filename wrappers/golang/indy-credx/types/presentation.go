@@ -23,9 +23,9 @@ type CredentialEntry struct {
 
 func (c *CredentialEntry) ToC() FfiCredentialEntry {
 	return FfiCredentialEntry{
-		credential: (C.ulong)(c.credential.handle),
+		credential: (C.ObjectHandle)(c.credential.handle),
 		timestamp:  C.int64_t(c.timestamp),
-		rev_state:  (C.ulong)(c.revState.handle),
+		rev_state:  (C.ObjectHandle)(c.revState.handle),
 	}
 }
 
@@ -66,7 +66,7 @@ type RevocationEntry struct {
 func (r RevocationEntry) ToC() FfiRevocationEntry {
 	return FfiRevocationEntry{
 		def_entry_idx: C.int64_t(r.defEntryIndex),
-		entry:         (C.ulong)(r.revReg.handle),
+		entry:         (C.ObjectHandle)(r.revReg.handle),
 		timestamp:     C.int64_t(r.timestamp),
 	}
 }
@@ -111,15 +111,15 @@ func NewPresentation(
 	cCredDefHandles := NewFfiListObjectHandle(credDefHandles)
 
 	err := C.credx_create_presentation(
-		(C.ulong)(presentationRequest.handle),
+		(C.ObjectHandle)(presentationRequest.handle),
 		(C.FfiList_FfiCredentialEntry)(cCredEntries),
 		(C.FfiList_FfiCredentialProve)(cCredProves),
 		(C.FfiStrList)(cSelfAttestedAttrNames),
 		(C.FfiStrList)(cSelfAttestedAttrValues),
-		(C.ulong)(masterSecret.handle),
+		(C.ObjectHandle)(masterSecret.handle),
 		(C.FfiList_ObjectHandle)(cSchemaHandles),
 		(C.FfiList_ObjectHandle)(cCredDefHandles),
-		(*C.ulong)(&handle),
+		(*C.ObjectHandle)(&handle),
 	)
 
 	if err != 0 {
@@ -141,7 +141,7 @@ func LoadPresentationFromJSON(json json.RawMessage) (*Presentation, error) {
 	var handle ObjectHandle
 	buf := ByteBufferFromRawMessage(json)
 
-	err := C.credx_presentation_from_json((C.ByteBuffer)(buf), (*C.ulong)(&handle))
+	err := C.credx_presentation_from_json((C.ByteBuffer)(buf), (*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create Presentation from json, recevied code %d.", int(err))
@@ -195,8 +195,8 @@ func (c *Presentation) Verify(
 	cRevEntries := NewFfiListFfiRevocationEntry(revEntries)
 
 	err := C.credx_verify_presentation(
-		(C.ulong)(c.handle),
-		(C.ulong)(presReq.handle),
+		(C.ObjectHandle)(c.handle),
+		(C.ObjectHandle)(presReq.handle),
 		(C.FfiList_ObjectHandle)(cSchemaHandles),
 		(C.FfiList_ObjectHandle)(cCredDefHandles),
 		(C.FfiList_ObjectHandle)(cRegDefHandles),
@@ -215,7 +215,7 @@ func (c *Presentation) Verify(
 
 func (c *Presentation) close() {
 	if !c.isClosed {
-		C.credx_object_free((C.ulong)(c.handle))
+		C.credx_object_free((C.ObjectHandle)(c.handle))
 	}
 	c.isClosed = true
 }
@@ -226,7 +226,7 @@ func PresentationRequestFromJSON(json json.RawMessage) (*PresentationRequest, er
 
 	err := C.credx_presentation_request_from_json(
 		(C.ByteBuffer)(cJson),
-		(*C.ulong)(&handle),
+		(*C.ObjectHandle)(&handle),
 	)
 
 	if err != 0 {
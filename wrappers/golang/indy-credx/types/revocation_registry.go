@@ -27,7 +27,7 @@ func (s *RevocationRegistryDefinition) ToJSON() (json.RawMessage, error) {
 
 func (s *RevocationRegistryDefinition) close() {
 	if !s.isClosed {
-		C.credx_object_free((C.ulong)(s.handle))
+		C.credx_object_free((C.ObjectHandle)(s.handle))
 	}
 
 	s.isClosed = true
@@ -48,7 +48,7 @@ func (s *RevocationRegistryDefinitionPrivate) ToJSON() (json.RawMessage, error)
 
 func (s *RevocationRegistryDefinitionPrivate) close() {
 	if !s.isClosed {
-		C.credx_object_free((C.ulong)(s.handle))
+		C.credx_object_free((C.ObjectHandle)(s.handle))
 	}
 
 	s.isClosed = true
@@ -69,7 +69,7 @@ func (s *RevocationRegistryDelta) ToJSON() (json.RawMessage, error) {
 
 func (s *RevocationRegistryDelta) close() {
 	if !s.isClosed {
-		C.credx_object_free((C.ulong)(s.handle))
+		C.credx_object_free((C.ObjectHandle)(s.handle))
 	}
 
 	s.isClosed = true
@@ -90,7 +90,7 @@ func (s *RevocationRegistry) ToJSON() (json.RawMessage, error) {
 
 func (s *RevocationRegistry) close() {
 	if !s.isClosed {
-		C.credx_object_free((C.ulong)(s.handle))
+		C.credx_object_free((C.ObjectHandle)(s.handle))
 	}
 
 	s.isClosed = true
@@ -112,9 +112,9 @@ func (c *CredentialRevocationInfo) ToC() *FfiCredentialRevocationInfo {
 	cTailsPath := C.CString(c.tailsPath)
 
 	return &FfiCredentialRevocationInfo{
-		reg_def:         (C.ulong)(c.registryDefinition.handle),
-		reg_def_private: (C.ulong)(c.regDefinitionPrivate.handle),
-		registry:        (C.ulong)(c.regEntry.handle),
+		reg_def:         (C.ObjectHandle)(c.registryDefinition.handle),
+		reg_def_private: (C.ObjectHandle)(c.regDefinitionPrivate.handle),
+		registry:        (C.ObjectHandle)(c.regEntry.handle),
 		reg_idx:         C.int64_t(c.regIdx),
 		reg_used:        (C.FfiList_i64)(cRegUsed),
 		tails_path:      (C.FfiStr)(cTailsPath),
@@ -156,16 +156,16 @@ func NewRevocationRegistryDefinition(
 
 	err := C.credx_create_revocation_registry(
 		cDid,
-		(C.ulong)(credDef.handle),
+		(C.ObjectHandle)(credDef.handle),
 		cTag,
 		cRevRegType,
 		cIssuanceType,
 		(C.int64_t)(maxCredNum),
 		cTailsDirPath,
-		(*C.ulong)(&regDefHandle),
-		(*C.ulong)(&regDefPrivateHandle),
-		(*C.ulong)(&regEntryHandle),
-		(*C.ulong)(&regInitDeltaHandle),
+		(*C.ObjectHandle)(&regDefHandle),
+		(*C.ObjectHandle)(&regDefPrivateHandle),
+		(*C.ObjectHandle)(&regEntryHandle),
+		(*C.ObjectHandle)(&regInitDeltaHandle),
 	)
 
 	if err != 0 {
@@ -199,7 +199,7 @@ func LoadRevocationRegistryDefinitionFromJSON(json json.RawMessage) (*Revocation
 	var handle ObjectHandle
 	buf := ByteBufferFromRawMessage(json)
 
-	err := C.credx_revocation_registry_definition_from_json((C.ByteBuffer)(buf), (*C.ulong)(&handle))
+	err := C.credx_revocation_registry_definition_from_json((C.ByteBuffer)(buf), (*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create RevocationRegistryDefinition from json, recevied code %d.", int(err))
@@ -219,7 +219,7 @@ func LoadRevocationRegistryDefinitionPrivateFromJSON(json json.RawMessage) (*Rev
 	var handle ObjectHandle
 	buf := ByteBufferFromRawMessage(json)
 
-	err := C.credx_revocation_registry_definition_private_from_json((C.ByteBuffer)(buf), (*C.ulong)(&handle))
+	err := C.credx_revocation_registry_definition_private_from_json((C.ByteBuffer)(buf), (*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create RevocationRegistryDefinitionPrivate from json, recevied code %d.", int(err))
@@ -241,7 +241,7 @@ func LoadRevocationRegistryFromJSON(json json.RawMessage) (*RevocationRegistry,
 	var handle ObjectHandle
 	buf := ByteBufferFromRawMessage(json)
 
-	err := C.credx_revocation_registry_from_json((C.ByteBuffer)(buf), (*C.ulong)(&handle))
+	err := C.credx_revocation_registry_from_json((C.ByteBuffer)(buf), (*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create RevocationRegistry from json, recevied code %d.", int(err))
@@ -261,7 +261,7 @@ func LoadRevocationRegistryDeltaFromJSON(json json.RawMessage) (*RevocationRegis
 	var handle ObjectHandle
 	buf := ByteBufferFromRawMessage(json)
 
-	err := C.credx_revocation_registry_delta_from_json((C.ByteBuffer)(buf), (*C.ulong)(&handle))
+	err := C.credx_revocation_registry_delta_from_json((C.ByteBuffer)(buf), (*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create RevocationRegistryDelta from json, recevied code %d.", int(err))
@@ -288,12 +288,12 @@ func (s *RevocationRegistry) RevokeCredential(
 	cTailsPath := C.CString(tailsPath)
 
 	err := C.credx_revoke_credential(
-		(C.ulong)(revRegDefinition.handle),
-		(C.ulong)(s.handle),
+		(C.ObjectHandle)(revRegDefinition.handle),
+		(C.ObjectHandle)(s.handle),
 		C.int64_t(credRevIndex),
 		(C.FfiStr)(cTailsPath),
-		(*C.ulong)(&regEntryHandle),
-		(*C.ulong)(&regDeltaHandle),
+		(*C.ObjectHandle)(&regEntryHandle),
+		(*C.ObjectHandle)(&regDeltaHandle),
 	)
 
 	if err != 0 {
@@ -323,13 +323,13 @@ func (s *RevocationRegistry) Update(
 	cTailsPath := C.CString(tailsPath)
 
 	err := C.credx_update_revocation_registry(
-		(C.ulong)(revRegDefinition.handle),
-		(C.ulong)(s.handle),
+		(C.ObjectHandle)(revRegDefinition.handle),
+		(C.ObjectHandle)(s.handle),
 		(C.FfiList_i64)(cIssued),
 		(C.FfiList_i64)(cRevoked),
 		(C.FfiStr)(cTailsPath),
-		(*C.ulong)(&regEntryHandle),
-		(*C.ulong)(&regDeltaHandle),
+		(*C.ObjectHandle)(&regEntryHandle),
+		(*C.ObjectHandle)(&regDeltaHandle),
 	)
 
 	if err != 0 {
@@ -349,9 +349,9 @@ func (s *RevocationRegistryDelta) UpdateWith(delta RevocationRegistryDelta) (*Re
 	var updatedDelta ObjectHandle
 
 	err := C.credx_merge_revocation_registry_deltas(
-		(C.ulong)(s.handle),
-		(C.ulong)(delta.handle),
-		(*C.ulong)(&updatedDelta),
+		(C.ObjectHandle)(s.handle),
+		(C.ObjectHandle)(delta.handle),
+		(*C.ObjectHandle)(&updatedDelta),
 	)
 
 	if err != 0 {
@@ -372,7 +372,7 @@ func (s *RevocationRegistryDefinition) getAttr(attrName string) (string, error)
 	cAttrName := C.CString(attrName)
 
 	err := C.credx_revocation_registry_definition_get_attribute(
-		(C.ulong)(s.handle),
+		(C.ObjectHandle)(s.handle),
 		(C.FfiStr)(cAttrName),
 		(**C.char)(&cArray),
 	)
@@ -9,6 +9,7 @@ import (
 	"encoding/json"
 	"fmt"
 	"runtime"
+	"strconv"
 )
 
 type Schema struct {
@@ -41,7 +42,7 @@ func NewSchema(
 		cSchemaVersion,
 		(C.FfiStrList)(cAttrs),
 		cSeqNo,
-		(*C.ulong)(&handle),
+		(*C.ObjectHandle)(&handle),
 	)
 
 	if err != 0 {
@@ -68,7 +69,7 @@ func LoadSchemaFromJSON(json json.RawMessage) (*Schema, error) {
 	var handle ObjectHandle
 	buf := ByteBufferFromRawMessage(json)
 
-	err := C.credx_schema_from_json((C.ByteBuffer)(buf), (*C.ulong)(&handle))
+	err := C.credx_schema_from_json((C.ByteBuffer)(buf), (*C.ObjectHandle)(&handle))
 
 	if err != 0 {
 		context := fmt.Sprintf("Couldn't create schema from json, recevied code %d.", int(err))
@@ -90,12 +91,45 @@ func (s *Schema) GetID() (string, error) {
 	return s.getSchemaAttribute("id")
 }
 
+func (s *Schema) GetName() (string, error) {
+	return s.getSchemaAttribute("name")
+}
+
+func (s *Schema) GetVersion() (string, error) {
+	return s.getSchemaAttribute("version")
+}
+
+func (s *Schema) GetAttrNames() ([]string, error) {
+	attrNames, err := s.getSchemaAttribute("attr_names")
+	if err != nil {
+		return nil, err
+	}
+
+	var names []string
+	if err := json.Unmarshal([]byte(attrNames), &names); err != nil {
+		return nil, err
+	}
+
+	return names, nil
+}
+
+// GetSeqNo returns the ledger sequence number of the schema, or zero when
+// it has not been set.
+func (s *Schema) GetSeqNo() (int64, error) {
+	seqNo, err := s.getSchemaAttribute("seq_no")
+	if err != nil || seqNo == "" {
+		return 0, err
+	}
+
+	return strconv.ParseInt(seqNo, 10, 64)
+}
+
 func (s *Schema) getSchemaAttribute(attr string) (string, error) {
 	var cArray StrBuffer
 	defer CloseStrBuffer(cArray)
 
 	err := C.credx_schema_get_attribute(
-		(C.ulong)(s.handle),
+		(C.ObjectHandle)(s.handle),
 		(C.FfiStr)(C.CString(attr)),
 		(**C.char)(&cArray),
 	)
@@ -113,7 +147,7 @@ func (s *Schema) getSchemaAttribute(attr string) (string, error) {
 
 func (s *Schema) close() {
 	if !s.isClosed {
-		C.credx_object_free((C.ulong)(s.handle))
+		C.credx_object_free((C.ObjectHandle)(s.handle))
 	}
 
 	s.isClosed = true
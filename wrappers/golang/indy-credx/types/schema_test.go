@@ -72,6 +72,46 @@ func Test_GetSchemaID(t *testing.T) {
 	require.Equal(t, expectedResponse, id)
 }
 
+func Test_GetSchemaAttributes(t *testing.T) {
+	schema, _ := NewSchema(
+		testDid,
+		testSchemaName,
+		testSchemaVersion,
+		testAttrsNames,
+		testSeqNo,
+	)
+
+	name, err := schema.GetName()
+	require.Nil(t, err)
+	require.Equal(t, testSchemaName, name)
+
+	version, err := schema.GetVersion()
+	require.Nil(t, err)
+	require.Equal(t, testSchemaVersion, version)
+
+	attrNames, err := schema.GetAttrNames()
+	require.Nil(t, err)
+	require.Equal(t, testAttrsNames, attrNames)
+
+	seqNo, err := schema.GetSeqNo()
+	require.Nil(t, err)
+	require.Equal(t, testSeqNo, seqNo)
+}
+
+func Test_GetSchemaSeqNoUnset(t *testing.T) {
+	schema, _ := NewSchema(
+		testDid,
+		testSchemaName,
+		testSchemaVersion,
+		testAttrsNames,
+		0,
+	)
+
+	seqNo, err := schema.GetSeqNo()
+	require.Nil(t, err)
+	require.Equal(t, int64(0), seqNo)
+}
+
 func Test_closeSchema(t *testing.T) {
 	schema, _ := NewSchema(
 		testDid,
@@ -1,13 +1,45 @@
 """Indy-Credx Python wrapper library"""
 
-from .bindings import encode_credential_attributes, generate_nonce, library_version
+from .bindings import (
+    allocation_report,
+    clear_attribute_encoders,
+    decode_data,
+    encode_credential_attributes,
+    encode_data,
+    encrypt_tails_file,
+    evict_expired_handles,
+    generate_nonce,
+    get_current_time,
+    library_version,
+    multibase_decode,
+    multibase_encode,
+    operation_stats,
+    proto_definitions,
+    register_attribute_encoder,
+    register_proof_request_template,
+    remove_proof_request_template,
+    reset_operation_stats,
+    set_allocation_debug,
+    set_clock,
+    set_fatal_handler,
+    set_handle_lifetime,
+    set_serialization_profile,
+    set_tails_encryption_key,
+    set_thread_pool_size,
+    set_verification_limits,
+    verify_tails,
+)
 from .error import CredxError, CredxErrorCode
+from .predicates import SignedRange
+from .tails import TailsSource
 from .types import (
+    CancellationToken,
     Credential,
     CredentialDefinition,
     CredentialDefinitionPrivate,
     CredentialRevocationConfig,
     CredentialRevocationState,
+    CredentialSearch,
     KeyCorrectnessProof,
     CredentialOffer,
     CredentialRequest,
@@ -24,16 +56,42 @@ from .types import (
 )
 
 __all__ = (
+    "allocation_report",
+    "clear_attribute_encoders",
+    "decode_data",
     "encode_credential_attributes",
+    "encode_data",
+    "encrypt_tails_file",
+    "evict_expired_handles",
     "generate_nonce",
+    "get_current_time",
     "library_version",
+    "multibase_decode",
+    "multibase_encode",
+    "operation_stats",
+    "proto_definitions",
+    "register_attribute_encoder",
+    "register_proof_request_template",
+    "remove_proof_request_template",
+    "reset_operation_stats",
+    "set_allocation_debug",
+    "set_clock",
+    "set_fatal_handler",
+    "set_handle_lifetime",
+    "set_serialization_profile",
+    "set_tails_encryption_key",
+    "set_thread_pool_size",
+    "set_verification_limits",
+    "verify_tails",
     "CredxError",
     "CredxErrorCode",
+    "CancellationToken",
     "Credential",
     "CredentialDefinition",
     "CredentialDefinitionPrivate",
     "CredentialRevocationConfig",
     "CredentialRevocationState",
+    "CredentialSearch",
     "KeyCorrectnessProof",
     "CredentialOffer",
     "CredentialRequest",
@@ -47,4 +105,6 @@ __all__ = (
     "RevocationRegistryDefinitionPrivate",
     "RevocationRegistryDelta",
     "Schema",
+    "SignedRange",
+    "TailsSource",
 )
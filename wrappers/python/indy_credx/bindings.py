@@ -4,9 +4,11 @@ import json
 import logging
 import os
 import sys
+import weakref
 from ctypes import (
     Array,
     CDLL,
+    CFUNCTYPE,
     POINTER,
     Structure,
     byref,
@@ -20,7 +22,8 @@ from ctypes import (
 )
 from ctypes.util import find_library
 from io import BytesIO
-from typing import Optional, Mapping, Sequence, Union
+from itertools import count
+from typing import Callable, Optional, Mapping, Sequence, Tuple, Union
 
 from .error import CredxError, CredxErrorCode
 
@@ -29,14 +32,31 @@ CALLBACKS = {}
 LIB: CDLL = None
 LOGGER = logging.getLogger(__name__)
 
+# Handles which may still reference a library object, by order of creation
+LIVE_HANDLES = weakref.WeakValueDictionary()
+HANDLE_SEQ = count()
+
 
 class ObjectHandle(c_int64):
     """Index of an active IndyObject instance."""
 
+    def __init__(self, value: int = 0):
+        c_int64.__init__(self, value)
+        LIVE_HANDLES[next(HANDLE_SEQ)] = self
+
     @property
     def type_name(self) -> str:
         return object_get_type_name(self)
 
+    @property
+    def tag(self) -> Optional[bytes]:
+        """Opaque data attached to the handle, released along with it."""
+        return object_get_tag(self)
+
+    @tag.setter
+    def tag(self, value: Optional[Union[str, bytes]]):
+        object_set_tag(self, value)
+
     def __repr__(self) -> str:
         """Format object handle as a string."""
         if self.value:
@@ -48,12 +68,26 @@ class ObjectHandle(c_int64):
             type_name = "<none>"
         return f"{self.__class__.__name__}({type_name}, {self.value})"
 
+    def free(self):
+        """Release the library object, invalidating the handle."""
+        if self.value:
+            object_free(self)
+            self.value = 0
+
     def __del__(self):
-        object_free(self)
+        # handles released at shutdown are reset, so no module state is needed
+        if self.value:
+            self.free()
 
 
 class IndyObject:
-    """A generic Indy object allocated by the library."""
+    """A generic Indy object allocated by the library.
+
+    Objects may be used as context managers to release the underlying handle
+    when the block exits. Copies share the handle of the original object.
+    """
+
+    FIELDS: Tuple[str, ...] = ()
 
     def __init__(self, handle: ObjectHandle) -> "IndyObject":
         self.handle = handle
@@ -61,13 +95,104 @@ class IndyObject:
     def __bytes__(self) -> bytes:
         return bytes(self.to_json_buffer())
 
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *exc):
+        self.free()
+
     def __repr__(self) -> str:
         """Format object as a string."""
         return f"{self.__class__.__name__}({self.handle.value})"
 
+    @property
+    def is_freed(self) -> bool:
+        return not self.handle.value
+
     def copy(self):
         return self.__class__(self.handle)
 
+    def deep_copy(self):
+        """Copy the library object, returning an instance with its own handle."""
+        return self.__class__(object_copy(self.handle))
+
+    def __deepcopy__(self, memo):
+        return self.deep_copy()
+
+    def fields(self) -> dict:
+        """Read the attributes of the object which are available without parsing."""
+        return {name: getattr(self, name) for name in self.FIELDS}
+
+    def free(self):
+        """Release the library object. Further use of this instance will fail."""
+        self.handle.free()
+
+    @classmethod
+    def open_envelope(cls, envelope: str, recipient_secret: bytes):
+        return cls.load(memoryview(envelope_open(envelope, recipient_secret).raw))
+
+    def seal(self, recipient_key: bytes, kid: str = None) -> str:
+        return envelope_seal(self.handle, recipient_key, kid)
+
+    @classmethod
+    def from_attachment(cls, attachment: Union[dict, str], format: str):
+        return cls(attachment_decode(attachment, format))
+
+    def to_attachment(self, attach_id: str) -> Tuple[dict, str]:
+        return attachment_encode(self.handle, attach_id)
+
+    @classmethod
+    def from_askar_entry(cls, entry):
+        """Load an object from an Askar entry, or any object with `category`
+        and `value` attributes."""
+        return cls(object_from_entry(entry.category, bytes(entry.value)))
+
+    def to_askar_entry(self, name: Optional[str] = None) -> dict:
+        """The category, name, value and tags with which to store the object
+        in Askar. The name defaults to the ledger identifier of the object."""
+        category, default_name, tags = object_get_entry(self.handle)
+        name = name or default_name
+        if not name:
+            raise CredxError(
+                CredxErrorCode.INPUT, f"An entry name is required for {category}"
+            )
+        return {
+            "category": category,
+            "name": name,
+            "value": bytes(self.to_json_buffer()),
+            "tags": tags,
+        }
+
+    @classmethod
+    def from_msgpack(cls, data: bytes):
+        """Load an object from MessagePack. Requires the `msgpack` feature."""
+        return cls(object_from_msgpack(cls.__name__, data))
+
+    def to_msgpack(self) -> bytes:
+        """Encode the object as MessagePack. Requires the `msgpack` feature."""
+        return bytes(object_get_msgpack(self.handle))
+
+    @classmethod
+    def from_proto(cls, data: bytes):
+        """Load an object from a protocol buffer message. Supported for
+        schemas, credential definitions, credentials and presentations, and
+        requires the `proto` feature."""
+        return cls(object_from_proto(cls.__name__, data))
+
+    def to_proto(self) -> bytes:
+        """Encode the object as a protocol buffer message. Requires the
+        `proto` feature."""
+        return bytes(object_get_proto(self.handle))
+
+    def validate(self) -> list:
+        """Check the object for problems, returning a list of dicts with a
+        `message` and an optional `field`."""
+        return object_validate(self.handle)
+
+    @classmethod
+    def from_aries_message(cls, message: Union[dict, str], format: str):
+        return cls(aries_message_get_attachment(message, format))
+
     def to_dict(self) -> dict:
         return json.load(BytesIO(self.to_json_buffer()))
 
@@ -77,6 +202,11 @@ class IndyObject:
     def to_json_buffer(self) -> memoryview:
         return memoryview(object_get_json(self.handle).raw)
 
+    def to_json_into(self, buffer) -> int:
+        """Serialize the object into a writable buffer such as a `bytearray`,
+        returning the length of the JSON."""
+        return object_get_json_into(self.handle, buffer)
+
 
 class ByteBuffer(Structure):
     """A byte buffer allocated by the library."""
@@ -313,15 +443,40 @@ class RevocationEntryList(Structure):
     ]
 
 
+class RevocationDeltaEntry(Structure):
+    _fields_ = [
+        ("delta", ObjectHandle),
+        ("timestamp", c_int64),
+    ]
+
+
+class RevocationDeltaEntryList(Structure):
+    _fields_ = [
+        ("count", c_int64),
+        ("data", POINTER(RevocationDeltaEntry)),
+    ]
+
+
 def get_library() -> CDLL:
     """Return the CDLL instance, loading it if necessary."""
     global LIB
     if LIB is None:
         LIB = _load_library("indy_credx")
         do_call("credx_set_default_logger")
+        # runs at interpreter exit, before module globals are torn down
+        weakref.finalize(LIB, _release_handles, LIB)
     return LIB
 
 
+def _release_handles(lib: CDLL):
+    """Free all remaining handles, most recently created first."""
+    for key in sorted(LIVE_HANDLES.keys(), reverse=True):
+        handle = LIVE_HANDLES.get(key)
+        if handle is not None and handle.value:
+            lib.credx_object_free(handle)
+            handle.value = 0
+
+
 def library_version() -> str:
     """Get the version of the installed aries-askar library."""
     lib = get_library()
@@ -329,6 +484,187 @@ def library_version() -> str:
     return str(StrBuffer(lib.credx_version()))
 
 
+def set_thread_pool_size(size: Optional[int]):
+    """Cap the number of worker threads used by batch operations.
+
+    Batch verification always uses worker threads, while tails generation and
+    batch issuance only do when the library is built with the `parallel`
+    feature.
+
+    Passing `None` restores the default, which is the number of available CPUs.
+    """
+    do_call("credx_set_thread_pool_size", c_int64(size or 0))
+
+
+def set_clock(fixed_time: Optional[int] = None, offset: int = 0):
+    """Control the time used by the library, for instance in freshness checks.
+
+    A `fixed_time` freezes the clock at a Unix timestamp, and `offset` adjusts
+    the clock by a number of seconds. Calling with no arguments restores the
+    system clock.
+    """
+    do_call("credx_set_clock", c_int64(fixed_time or 0), c_int64(offset))
+
+
+def set_serialization_profile(profile: Optional[str] = None):
+    """Select the field names of serialized objects: `native`, `snake_case` or
+    `camel_case`. Objects accept either convention when loaded."""
+    do_call("credx_set_serialization_profile", encode_str(profile))
+
+
+def set_verification_limits(
+    max_referents: Optional[int] = None,
+    max_credentials: Optional[int] = None,
+    max_json_size: Optional[int] = None,
+):
+    """Limit the size of presentations accepted for verification.
+
+    Presentations exceeding a limit are rejected when they are loaded or
+    verified. Calling with no arguments removes the limits.
+    """
+    do_call(
+        "credx_set_verification_limits",
+        c_int64(max_referents or 0),
+        c_int64(max_credentials or 0),
+        c_int64(max_json_size or 0),
+    )
+
+
+def register_attribute_encoder(
+    pattern: str, encoder: str, schema_id: Optional[str] = None
+):
+    """Encode the raw values of the attributes matching a `LIKE` pattern
+    with the `standard` or `dateint` encoder, under a single schema or under
+    any schema when no schema is given."""
+    do_call(
+        "credx_register_attribute_encoder",
+        encode_str(schema_id),
+        encode_str(pattern),
+        encode_str(encoder),
+    )
+
+
+def clear_attribute_encoders():
+    """Remove the registered attribute encoders."""
+    do_call("credx_clear_attribute_encoders")
+
+
+def verify_tails(tails: Union[str, bytes], expected_hash: str) -> Tuple[bool, str]:
+    """Check a tails file path or downloaded tails against the tails hash of a
+    registry definition, returning the result and the recomputed hash."""
+    valid = c_int8()
+    result = StrBuffer()
+    if isinstance(tails, str):
+        do_call(
+            "credx_tails_verify",
+            encode_str(tails),
+            encode_str(expected_hash),
+            byref(valid),
+            byref(result),
+        )
+    else:
+        do_call(
+            "credx_tails_verify_buffer",
+            encode_bytes(tails),
+            encode_str(expected_hash),
+            byref(valid),
+            byref(result),
+        )
+    return bool(valid.value), str(result)
+
+
+def set_tails_encryption_key(key: Optional[bytes]):
+    """Set the 32-byte key used to decrypt encrypted tails files, or clear it
+    when no key is given. Requires a library built with tails encryption."""
+    do_call("credx_set_tails_encryption_key", encode_bytes(key or b""))
+
+
+def encrypt_tails_file(source_path: str, dest_path: str, key: bytes) -> str:
+    """Encrypt a downloaded tails file for storage, returning its tails hash."""
+    result = StrBuffer()
+    do_call(
+        "credx_encrypt_tails_file",
+        encode_str(source_path),
+        encode_str(dest_path),
+        encode_bytes(key),
+        byref(result),
+    )
+    return str(result)
+
+
+def set_fatal_handler(handler: Optional[Callable[[dict], None]]):
+    """Register a handler receiving a crash report for each panic caught in the
+    library, or remove the handler when none is given. The report includes
+    the operation, message, location and backtrace of the panic."""
+    callback = None
+    if handler:
+
+        def _on_fatal(report: bytes):
+            try:
+                handler(json.loads(report.decode()))
+            except Exception:
+                LOGGER.exception("Error in fatal handler")
+
+        callback = CFUNCTYPE(None, c_char_p)(_on_fatal)
+    do_call("credx_set_fatal_handler", callback)
+    # keep a reference to the callback while it is registered
+    CALLBACKS["fatal"] = callback
+
+
+def get_current_time() -> int:
+    """Fetch the current Unix timestamp according to the library clock."""
+    result = c_int64()
+    do_call("credx_get_current_time", byref(result))
+    return result.value
+
+
+def set_handle_lifetime(
+    type_name: Optional[str], ttl: Optional[int] = None, idle: Optional[int] = None
+):
+    """Evict objects of a type (or of any type, when `None`) from the library
+    after `ttl` seconds, or after `idle` seconds without being accessed.
+
+    Access to one of the last 65536 evicted handles raises a `HANDLE_EVICTED`
+    error, while older evicted handles are reported as invalid."""
+    do_call(
+        "credx_set_handle_lifetime",
+        encode_str(type_name),
+        c_int64(ttl or 0),
+        c_int64(idle or 0),
+    )
+
+
+def evict_expired_handles() -> int:
+    count = c_int64()
+    do_call("credx_evict_expired_handles", byref(count))
+    return count.value
+
+
+def set_allocation_debug(enabled: bool):
+    """Log library allocations and rejected frees, for diagnosing leaks."""
+    do_call("credx_set_allocation_debug", c_int8(enabled))
+
+
+def allocation_report() -> dict:
+    """Count the strings, buffers and objects not yet released by the wrapper."""
+    report = StrBuffer()
+    do_call("credx_get_allocation_report", byref(report))
+    return json.loads(report.value)
+
+
+def operation_stats() -> dict:
+    """Fetch the call count and the total, average and maximum durations in
+    milliseconds of each library operation. Requires the `stats` feature."""
+    stats = StrBuffer()
+    do_call("credx_get_operation_stats", byref(stats))
+    return json.loads(stats.value)
+
+
+def reset_operation_stats():
+    """Discard the operation statistics collected so far."""
+    do_call("credx_reset_operation_stats")
+
+
 def _load_library(lib_name: str) -> CDLL:
     """Load the CDLL library.
     The python module directory is searched first, followed by the usual
@@ -405,6 +741,8 @@ def encode_str(arg: Optional[Union[str, bytes]]) -> c_char_p:
     """
     if arg is None:
         return c_char_p()
+    if isinstance(arg, os.PathLike):
+        arg = os.fspath(arg)
     if isinstance(arg, str):
         return c_char_p(arg.encode("utf-8"))
     return c_char_p(arg)
@@ -448,6 +786,276 @@ def object_get_json(handle: ObjectHandle) -> ByteBuffer:
     return result
 
 
+def object_set_tag(handle: ObjectHandle, tag: Optional[Union[str, bytes]]):
+    do_call("credx_object_set_tag", handle, encode_bytes(tag))
+
+
+def object_get_tag(handle: ObjectHandle) -> Optional[bytes]:
+    result = ByteBuffer()
+    do_call("credx_object_get_tag", handle, byref(result))
+    return bytes(result) or None
+
+
+def object_get_json_into(handle: ObjectHandle, buffer) -> int:
+    """Serialize an object into a writable buffer, returning the JSON length.
+
+    Passing `None` only computes the length.
+    """
+    required = c_size_t()
+    if buffer is None:
+        do_call("credx_object_get_json_into", handle, None, c_size_t(0), byref(required))
+    else:
+        view = memoryview(buffer)
+        buf = (c_ubyte * view.nbytes).from_buffer(view.cast("B"))
+        do_call(
+            "credx_object_get_json_into",
+            handle,
+            buf,
+            c_size_t(view.nbytes),
+            byref(required),
+        )
+    return required.value
+
+
+def aries_offer_credential(
+    cred_offer: ObjectHandle,
+    preview: Mapping[str, str],
+    comment: Optional[str] = None,
+) -> dict:
+    message = StrBuffer()
+    do_call(
+        "credx_aries_offer_credential",
+        cred_offer,
+        FfiStrList.create(preview.keys()),
+        FfiStrList.create(preview.values()),
+        encode_str(comment),
+        byref(message),
+    )
+    return json.loads(str(message))
+
+
+def aries_request_credential(
+    cred_request: ObjectHandle, thread_id: str, comment: Optional[str] = None
+) -> dict:
+    message = StrBuffer()
+    do_call(
+        "credx_aries_request_credential",
+        cred_request,
+        encode_str(thread_id),
+        encode_str(comment),
+        byref(message),
+    )
+    return json.loads(str(message))
+
+
+def aries_issue_credential(
+    cred: ObjectHandle, thread_id: str, comment: Optional[str] = None
+) -> dict:
+    message = StrBuffer()
+    do_call(
+        "credx_aries_issue_credential",
+        cred,
+        encode_str(thread_id),
+        encode_str(comment),
+        byref(message),
+    )
+    return json.loads(str(message))
+
+
+def aries_request_presentation(
+    pres_req: ObjectHandle, comment: Optional[str] = None
+) -> dict:
+    message = StrBuffer()
+    do_call(
+        "credx_aries_request_presentation",
+        pres_req,
+        encode_str(comment),
+        byref(message),
+    )
+    return json.loads(str(message))
+
+
+def aries_presentation(
+    presentation: ObjectHandle, thread_id: str, comment: Optional[str] = None
+) -> dict:
+    message = StrBuffer()
+    do_call(
+        "credx_aries_presentation",
+        presentation,
+        encode_str(thread_id),
+        encode_str(comment),
+        byref(message),
+    )
+    return json.loads(str(message))
+
+
+def aries_revocation_notification(
+    rev_reg_id: str, cred_rev_idx: int, comment: Optional[str] = None
+) -> dict:
+    message = StrBuffer()
+    do_call(
+        "credx_aries_revocation_notification",
+        encode_str(rev_reg_id),
+        c_int64(cred_rev_idx),
+        encode_str(comment),
+        byref(message),
+    )
+    return json.loads(str(message))
+
+
+def aries_message_get_attachment(
+    message: Union[dict, str], format: str
+) -> ObjectHandle:
+    if isinstance(message, dict):
+        message = json.dumps(message)
+    handle = ObjectHandle()
+    do_call(
+        "credx_aries_message_get_attachment",
+        encode_str(message),
+        encode_str(format),
+        byref(handle),
+    )
+    return handle
+
+
+def attachment_encode(handle: ObjectHandle, attach_id: str) -> Tuple[dict, str]:
+    attachment = StrBuffer()
+    format = StrBuffer()
+    do_call(
+        "credx_attachment_encode",
+        handle,
+        encode_str(attach_id),
+        byref(attachment),
+        byref(format),
+    )
+    return json.loads(str(attachment)), str(format)
+
+
+def attachment_decode(attachment: Union[dict, str], format: str) -> ObjectHandle:
+    if isinstance(attachment, dict):
+        attachment = json.dumps(attachment)
+    handle = ObjectHandle()
+    do_call(
+        "credx_attachment_decode",
+        encode_str(attachment),
+        encode_str(format),
+        byref(handle),
+    )
+    return handle
+
+
+def envelope_seal(
+    handle: ObjectHandle, recipient_key: bytes, kid: Optional[str] = None
+) -> str:
+    envelope = StrBuffer()
+    do_call(
+        "credx_envelope_seal",
+        handle,
+        encode_bytes(recipient_key),
+        encode_str(kid),
+        byref(envelope),
+    )
+    return str(envelope)
+
+
+def envelope_open(envelope: str, recipient_secret: bytes) -> ByteBuffer:
+    result = ByteBuffer()
+    do_call(
+        "credx_envelope_open",
+        encode_str(envelope),
+        encode_bytes(recipient_secret),
+        byref(result),
+    )
+    return result
+
+
+def object_get_entry(handle: ObjectHandle) -> Tuple[str, Optional[str], dict]:
+    category = StrBuffer()
+    name = StrBuffer()
+    tags = StrBuffer()
+    do_call(
+        "credx_object_get_entry",
+        handle,
+        byref(category),
+        byref(name),
+        byref(tags),
+    )
+    return str(category), name.opt_str(), json.loads(str(tags))
+
+
+def object_get_msgpack(handle: ObjectHandle) -> ByteBuffer:
+    result = ByteBuffer()
+    do_call("credx_object_get_msgpack", handle, byref(result))
+    return result
+
+
+def object_from_msgpack(type_name: str, data: bytes) -> ObjectHandle:
+    result = ObjectHandle()
+    do_call(
+        "credx_object_from_msgpack",
+        encode_str(type_name),
+        encode_bytes(data),
+        byref(result),
+    )
+    return result
+
+
+def object_get_proto(handle: ObjectHandle) -> ByteBuffer:
+    result = ByteBuffer()
+    do_call("credx_object_get_proto", handle, byref(result))
+    return result
+
+
+def object_from_proto(type_name: str, data: bytes) -> ObjectHandle:
+    result = ObjectHandle()
+    do_call(
+        "credx_object_from_proto",
+        encode_str(type_name),
+        encode_bytes(data),
+        byref(result),
+    )
+    return result
+
+
+def proto_definitions() -> str:
+    """Get the protocol buffer definitions of the objects supporting
+    `to_proto`. Requires the `proto` feature."""
+    result = StrBuffer()
+    do_call("credx_proto_definitions", byref(result))
+    return str(result)
+
+
+def object_validate(handle: ObjectHandle) -> list:
+    report = StrBuffer()
+    do_call("credx_object_validate", handle, byref(report))
+    return json.loads(str(report))
+
+
+def credential_definition_validate(
+    cred_def: ObjectHandle, schema: ObjectHandle
+) -> list:
+    report = StrBuffer()
+    do_call("credx_credential_definition_validate", cred_def, schema, byref(report))
+    return json.loads(str(report))
+
+
+def object_from_entry(category: str, value: bytes) -> ObjectHandle:
+    result = ObjectHandle()
+    do_call(
+        "credx_object_from_entry",
+        encode_str(category),
+        encode_bytes(value),
+        byref(result),
+    )
+    return result
+
+
+def object_copy(handle: ObjectHandle) -> ObjectHandle:
+    result = ObjectHandle()
+    do_call("credx_object_copy", handle, byref(result))
+    return result
+
+
 def object_get_type_name(handle: ObjectHandle) -> StrBuffer:
     result = StrBuffer()
     do_call("credx_object_get_type_name", handle, byref(result))
@@ -472,62 +1080,246 @@ def _object_get_attribute(
     return result
 
 
+def _object_get_json_attribute(method: str, handle: ObjectHandle, name: str):
+    result = _object_get_attribute(method, handle, name)
+    return json.loads(result.value) if result is not None else None
+
+
 def generate_nonce() -> str:
     result = StrBuffer()
     do_call("credx_generate_nonce", byref(result))
     return str(result)
 
 
-def create_schema(
-    origin_did: str,
-    name: str,
-    version: str,
-    attr_names: Sequence[str],
-    seq_no: int = None,
-) -> ObjectHandle:
-    result = ObjectHandle()
-    attrs = FfiStrList.create(attr_names)
+def encode_data(data: bytes, encoding: str) -> str:
+    """Encode data as `base58`, `base64` or `base64url`."""
+    result = StrBuffer()
     do_call(
-        "credx_create_schema",
-        encode_str(origin_did),
-        encode_str(name),
-        encode_str(version),
-        attrs,
-        c_int64(seq_no or -1),
-        byref(result),
+        "credx_encode_data", encode_bytes(data), encode_str(encoding), byref(result)
     )
-    return result
+    return str(result)
 
 
-def create_credential_definition(
-    origin_did: str,
-    schema: ObjectHandle,
-    tag: str,
-    signature_type: str,
-    support_revocation: bool,
-) -> (ObjectHandle, ObjectHandle, ObjectHandle):
-    cred_def, cred_def_pvt, key_proof = ObjectHandle(), ObjectHandle(), ObjectHandle()
+def decode_data(value: str, encoding: str) -> bytes:
+    """Decode `base58`, `base64` or `base64url` data."""
+    result = ByteBuffer()
     do_call(
-        "credx_create_credential_definition",
-        encode_str(origin_did),
-        schema,
-        encode_str(tag),
-        encode_str(signature_type),
-        c_int8(support_revocation),
-        byref(cred_def),
+        "credx_decode_data", encode_str(value), encode_str(encoding), byref(result)
+    )
+    return bytes(result)
+
+
+def multibase_encode(data: bytes, base: str = "z") -> str:
+    """Encode data with a multibase prefix, `z` (base58btc) by default."""
+    result = StrBuffer()
+    do_call(
+        "credx_multibase_encode", encode_bytes(data), encode_str(base), byref(result)
+    )
+    return str(result)
+
+
+def multibase_decode(value: str) -> Tuple[str, bytes]:
+    """Decode multibase data, returning its base prefix and the data."""
+    base = StrBuffer()
+    result = ByteBuffer()
+    do_call("credx_multibase_decode", encode_str(value), byref(base), byref(result))
+    return str(base), bytes(result)
+
+
+def register_proof_request_template(name: str, template: Union[dict, str]):
+    if isinstance(template, dict):
+        template = json.dumps(template)
+    do_call(
+        "credx_register_proof_request_template",
+        encode_str(name),
+        encode_str(template),
+    )
+
+
+def remove_proof_request_template(name: str):
+    do_call("credx_remove_proof_request_template", encode_str(name))
+
+
+def create_presentation_request_from_template(
+    name: str, values: Optional[Mapping] = None
+) -> ObjectHandle:
+    pres_req = ObjectHandle()
+    do_call(
+        "credx_create_presentation_request_from_template",
+        encode_str(name),
+        encode_str(json.dumps(values) if values is not None else None),
+        byref(pres_req),
+    )
+    return pres_req
+
+
+def presentation_request_to_version(pres_req: ObjectHandle, ver: str) -> ObjectHandle:
+    result = ObjectHandle()
+    do_call(
+        "credx_presentation_request_to_version",
+        pres_req,
+        encode_str(ver),
+        byref(result),
+    )
+    return result
+
+
+def merge_presentation_requests(
+    name: str, version: str, pres_reqs: Sequence[ObjectHandle]
+) -> ObjectHandle:
+    pres_req = ObjectHandle()
+    do_call(
+        "credx_merge_presentation_requests",
+        encode_str(name),
+        encode_str(version),
+        FfiObjectHandleList.create(pres_reqs),
+        byref(pres_req),
+    )
+    return pres_req
+
+
+def create_schema(
+    origin_did: str,
+    name: str,
+    version: str,
+    attr_names: Sequence[str],
+    seq_no: int = None,
+) -> ObjectHandle:
+    result = ObjectHandle()
+    attrs = FfiStrList.create(attr_names)
+    do_call(
+        "credx_create_schema",
+        encode_str(origin_did),
+        encode_str(name),
+        encode_str(version),
+        attrs,
+        c_int64(seq_no or -1),
+        byref(result),
+    )
+    return result
+
+
+def create_cancellation_token() -> ObjectHandle:
+    token = ObjectHandle()
+    do_call("credx_create_cancellation_token", byref(token))
+    return token
+
+
+def cancellation_token_cancel(token: ObjectHandle):
+    do_call("credx_cancellation_token_cancel", token)
+
+
+def create_credential_definition(
+    origin_did: str,
+    schema: ObjectHandle,
+    tag: str,
+    signature_type: str,
+    support_revocation: bool,
+    cancel_token: Optional[ObjectHandle] = None,
+) -> (ObjectHandle, ObjectHandle, ObjectHandle):
+    cred_def, cred_def_pvt, key_proof = ObjectHandle(), ObjectHandle(), ObjectHandle()
+    do_call(
+        "credx_create_credential_definition_cancellable",
+        encode_str(origin_did),
+        schema,
+        encode_str(tag),
+        encode_str(signature_type),
+        c_int8(support_revocation),
+        cancel_token or ObjectHandle(),
+        byref(cred_def),
         byref(cred_def_pvt),
         byref(key_proof),
     )
     return (cred_def, cred_def_pvt, key_proof)
 
 
+def create_credential_definition_from_seed(
+    origin_did: str,
+    schema: ObjectHandle,
+    tag: str,
+    signature_type: str,
+    seed: bytes,
+) -> (ObjectHandle, ObjectHandle, ObjectHandle):
+    """Create a credential definition with keys derived from a seed.
+
+    The keys are insecure and must only be used in tests. The library must be
+    built with the `test_seeded_keys` feature.
+    """
+    cred_def, cred_def_pvt, key_proof = ObjectHandle(), ObjectHandle(), ObjectHandle()
+    do_call(
+        "credx_create_credential_definition_from_seed",
+        encode_str(origin_did),
+        schema,
+        encode_str(tag),
+        encode_str(signature_type),
+        encode_bytes(seed),
+        byref(cred_def),
+        byref(cred_def_pvt),
+        byref(key_proof),
+    )
+    return (cred_def, cred_def_pvt, key_proof)
+
+
+def credential_request_metadata_rename_master_secret(
+    cred_req_metadata: ObjectHandle, master_secret_id: str
+) -> ObjectHandle:
+    result = ObjectHandle()
+    do_call(
+        "credx_credential_request_metadata_rename_master_secret",
+        cred_req_metadata,
+        encode_str(master_secret_id),
+        byref(result),
+    )
+    return result
+
+
+def credential_request_metadata_has_master_secret(
+    cred_req_metadata: ObjectHandle, master_secret_id: str
+) -> bool:
+    result = c_int8()
+    do_call(
+        "credx_credential_request_metadata_has_master_secret",
+        cred_req_metadata,
+        encode_str(master_secret_id),
+        byref(result),
+    )
+    return bool(result)
+
+
+def credential_request_metadata_wrap(
+    cred_req_metadata: ObjectHandle, key: bytes
+) -> str:
+    """Encrypt credential request metadata under a 32-byte wallet key.
+    Requires a library built with metadata encryption."""
+    wrapped = StrBuffer()
+    do_call(
+        "credx_credential_request_metadata_wrap",
+        cred_req_metadata,
+        encode_bytes(key),
+        byref(wrapped),
+    )
+    return str(wrapped)
+
+
+def credential_request_metadata_unwrap(wrapped: str, key: bytes) -> ObjectHandle:
+    """Decrypt credential request metadata wrapped under a 32-byte wallet key."""
+    cred_req_metadata = ObjectHandle()
+    do_call(
+        "credx_credential_request_metadata_unwrap",
+        encode_str(wrapped),
+        encode_bytes(key),
+        byref(cred_req_metadata),
+    )
+    return cred_req_metadata
+
+
 def create_credential(
     cred_def: ObjectHandle,
     cred_def_private: ObjectHandle,
     cred_offer: ObjectHandle,
     cred_request: ObjectHandle,
     attr_raw_values: Mapping[str, str],
-    attr_enc_values: Optional[Mapping[str, str]],
+    attr_enc_values: Optional[Mapping[str, Union[str, int]]],
     revocation_config: Optional[RevocationConfig],
 ) -> (ObjectHandle, ObjectHandle, ObjectHandle):
     cred = ObjectHandle()
@@ -539,7 +1331,9 @@ def create_credential(
     if attr_enc_values:
         enc_values_list = []
         for name in attr_raw_values:
-            enc_values_list.append(attr_enc_values.get(name))
+            value = attr_enc_values.get(name)
+            # encoded values may exceed 64 bits and are passed as strings
+            enc_values_list.append(None if value is None else str(value))
     else:
         enc_values_list = None
     enc_values_list = FfiStrList().create(enc_values_list)
@@ -592,6 +1386,125 @@ def process_credential(
     return result
 
 
+def process_and_extract_credential(
+    cred: ObjectHandle,
+    cred_offer: ObjectHandle,
+    cred_req_metadata: ObjectHandle,
+    master_secret: ObjectHandle,
+    cred_def: ObjectHandle,
+    rev_reg_def: Optional[ObjectHandle],
+) -> Tuple[ObjectHandle, Mapping[str, str]]:
+    result = ObjectHandle()
+    attributes = StrBuffer()
+    do_call(
+        "credx_process_and_extract_credential",
+        cred,
+        cred_offer,
+        cred_req_metadata,
+        master_secret,
+        cred_def,
+        rev_reg_def or ObjectHandle(),
+        byref(result),
+        byref(attributes),
+    )
+    return result, json.loads(str(attributes))
+
+
+def credential_derive_values(
+    cred: ObjectHandle, attr_names: Sequence[str]
+) -> Tuple[Mapping[str, str], Mapping[str, str]]:
+    result = StrBuffer()
+    do_call(
+        "credx_credential_derive_values",
+        cred,
+        FfiStrList.create(attr_names),
+        byref(result),
+    )
+    values = json.loads(str(result))
+    return (
+        {name: val["raw"] for name, val in values.items()},
+        {name: val["encoded"] for name, val in values.items()},
+    )
+
+
+def credential_set_display(
+    cred: ObjectHandle, display: Optional[Mapping[str, dict]]
+) -> ObjectHandle:
+    result = ObjectHandle()
+    do_call(
+        "credx_credential_set_display",
+        cred,
+        encode_str(json.dumps(display) if display else None),
+        byref(result),
+    )
+    return result
+
+
+def credential_tags(cred: ObjectHandle) -> Mapping[str, str]:
+    result = StrBuffer()
+    do_call("credx_credential_tags", cred, byref(result))
+    return json.loads(str(result))
+
+
+def credential_get_attribute_names(cred: ObjectHandle) -> Sequence[str]:
+    result = StrBuffer()
+    do_call("credx_credential_get_attribute_names", cred, byref(result))
+    return json.loads(str(result))
+
+
+def credential_get_attribute_value(
+    cred: ObjectHandle, name: str, encoded: bool = False
+) -> Union[str, Tuple[str, str]]:
+    raw = StrBuffer()
+    enc = StrBuffer()
+    do_call(
+        "credx_credential_get_attribute_value",
+        cred,
+        encode_str(name),
+        byref(raw),
+        byref(enc) if encoded else None,
+    )
+    return (str(raw), str(enc)) if encoded else str(raw)
+
+
+def credential_search_open(
+    pres_req: ObjectHandle, referent: str, credentials: Sequence[ObjectHandle]
+) -> ObjectHandle:
+    search = ObjectHandle()
+    do_call(
+        "credx_credential_search_open",
+        pres_req,
+        encode_str(referent),
+        FfiObjectHandleList.create(credentials),
+        byref(search),
+    )
+    return search
+
+
+def credential_search_fetch(search: ObjectHandle, limit: int) -> Sequence[int]:
+    handles = (c_int64 * max(limit, 1))()
+    count = c_int64()
+    do_call(
+        "credx_credential_search_fetch",
+        search,
+        c_int64(limit),
+        handles,
+        byref(count),
+    )
+    return list(handles[: count.value])
+
+
+def credential_search_free(search: ObjectHandle):
+    do_call("credx_credential_search_free", search)
+    search.value = 0
+
+
+def check_credential_encoding(cred: ObjectHandle) -> Sequence[str]:
+    result = StrBuffer()
+    do_call("credx_check_credential_encoding", cred, byref(result))
+    return json.loads(str(result))
+
+
 def revoke_credential(
     rev_reg_def: ObjectHandle,
     rev_reg: ObjectHandle,
@@ -626,6 +1539,21 @@ def create_credential_offer(
     return cred_offer
 
 
+def create_credential_offers(
+    schema_id: str, cred_def: ObjectHandle, key_proof: ObjectHandle, count: int
+) -> Sequence[ObjectHandle]:
+    result = (c_int64 * count)()
+    do_call(
+        "credx_create_credential_offers",
+        encode_str(schema_id),
+        cred_def,
+        key_proof,
+        c_int64(count),
+        result,
+    )
+    return [ObjectHandle(handle) for handle in result]
+
+
 def create_credential_request(
     prover_did: str,
     cred_def: ObjectHandle,
@@ -647,6 +1575,31 @@ def create_credential_request(
     return (cred_req, cred_req_metadata)
 
 
+def create_committed_credential_request(
+    prover_did: str,
+    cred_def: ObjectHandle,
+    master_secret: ObjectHandle,
+    master_secret_id: str,
+    cred_offer: ObjectHandle,
+    committed_values: Mapping[str, str],
+) -> (ObjectHandle, ObjectHandle):
+    attr_keys = list(committed_values.keys())
+    cred_req, cred_req_metadata = ObjectHandle(), ObjectHandle()
+    do_call(
+        "credx_create_committed_credential_request",
+        encode_str(prover_did),
+        cred_def,
+        master_secret,
+        encode_str(master_secret_id),
+        cred_offer,
+        FfiStrList.create(attr_keys),
+        FfiStrList.create(str(committed_values[k]) for k in attr_keys),
+        byref(cred_req),
+        byref(cred_req_metadata),
+    )
+    return (cred_req, cred_req_metadata)
+
+
 def create_master_secret() -> ObjectHandle:
     secret = ObjectHandle()
     do_call(
@@ -656,6 +1609,26 @@ def create_master_secret() -> ObjectHandle:
     return secret
 
 
+def select_revocation_state(
+    pres_req: ObjectHandle,
+    attr_referents: Sequence[str],
+    predicate_referents: Sequence[str],
+    rev_states: Sequence[ObjectHandle],
+) -> Tuple[int, int]:
+    index = c_int64()
+    timestamp = c_int64()
+    do_call(
+        "credx_select_revocation_state",
+        pres_req,
+        FfiStrList.create(attr_referents),
+        FfiStrList.create(predicate_referents),
+        FfiObjectHandleList.create(rev_states),
+        byref(index),
+        byref(timestamp),
+    )
+    return index.value, timestamp.value
+
+
 def create_presentation(
     pres_req: ObjectHandle,
     credentials: Sequence[CredentialEntry],
@@ -664,16 +1637,24 @@ def create_presentation(
     master_secret: ObjectHandle,
     schemas: Sequence[ObjectHandle],
     cred_defs: Sequence[ObjectHandle],
+    auditable: bool = False,
+    seed: Optional[bytes] = None,
 ) -> ObjectHandle:
+    """Create a presentation.
+
+    A `seed` derives the random values of the proof for golden-file tests. Seeded
+    presentations must only be used in tests, and require a library built with
+    the `test_seeded_keys` feature.
+    """
+    if seed is not None and auditable:
+        raise ValueError("Seeded presentations cannot be auditable")
     entry_list = CredentialEntryList()
     entry_list.count = len(credentials)
     entry_list.data = (CredentialEntry * entry_list.count)(*credentials)
     prove_list = CredentialProveList()
     prove_list.count = len(credentials_prove)
     prove_list.data = (CredentialProve * prove_list.count)(*credentials_prove)
-    present = ObjectHandle()
-    do_call(
-        "credx_create_presentation",
+    args = [
         pres_req,
         entry_list,
         prove_list,
@@ -682,8 +1663,16 @@ def create_presentation(
         master_secret,
         FfiObjectHandleList.create(schemas),
         FfiObjectHandleList.create(cred_defs),
-        byref(present),
-    )
+    ]
+    if seed is not None:
+        method = "credx_create_presentation_from_seed"
+        args.append(encode_bytes(seed))
+    elif auditable:
+        method = "credx_create_auditable_presentation"
+    else:
+        method = "credx_create_presentation"
+    present = ObjectHandle()
+    do_call(method, *args, byref(present))
     return present
 
 
@@ -694,6 +1683,7 @@ def verify_presentation(
     cred_defs: Sequence[ObjectHandle],
     rev_reg_defs: Sequence[ObjectHandle],
     rev_regs: Sequence[RevocationEntry],
+    policy: Optional[Mapping] = None,
 ) -> bool:
     verify = c_int8()
     entry_list = RevocationEntryList()
@@ -701,18 +1691,60 @@ def verify_presentation(
         entry_list.count = len(rev_regs)
         entry_list.data = (RevocationEntry * entry_list.count)(*rev_regs)
     do_call(
-        "credx_verify_presentation",
+        "credx_verify_presentation_with_policy",
         presentation,
         pres_req,
         FfiObjectHandleList.create(schemas),
         FfiObjectHandleList.create(cred_defs),
         FfiObjectHandleList.create(rev_reg_defs),
         entry_list,
+        encode_str(json.dumps(policy) if policy is not None else None),
         byref(verify),
     )
     return bool(verify)
 
 
+def verify_presentations_batch(
+    presentations: Sequence[ObjectHandle],
+    pres_reqs: Sequence[ObjectHandle],
+    schemas: Sequence[ObjectHandle],
+    cred_defs: Sequence[ObjectHandle],
+    rev_reg_defs: Sequence[ObjectHandle],
+    rev_regs: Sequence[RevocationEntry],
+    policy: Optional[Mapping] = None,
+) -> Sequence[dict]:
+    results = StrBuffer()
+    entry_list = RevocationEntryList()
+    if rev_regs:
+        entry_list.count = len(rev_regs)
+        entry_list.data = (RevocationEntry * entry_list.count)(*rev_regs)
+    do_call(
+        "credx_verify_presentations_batch",
+        FfiObjectHandleList.create(presentations),
+        FfiObjectHandleList.create(pres_reqs),
+        FfiObjectHandleList.create(schemas),
+        FfiObjectHandleList.create(cred_defs),
+        FfiObjectHandleList.create(rev_reg_defs),
+        entry_list,
+        encode_str(json.dumps(policy) if policy is not None else None),
+        byref(results),
+    )
+    return json.loads(str(results))
+
+
+def presentation_check_accepted_issuers(
+    presentation: ObjectHandle, policy: Mapping
+) -> Sequence[dict]:
+    mismatches = StrBuffer()
+    do_call(
+        "credx_presentation_check_accepted_issuers",
+        presentation,
+        encode_str(json.dumps(policy)),
+        byref(mismatches),
+    )
+    return json.loads(str(mismatches))
+
+
 def create_revocation_registry(
     origin_did: str,
     cred_def: ObjectHandle,
@@ -721,13 +1753,15 @@ def create_revocation_registry(
     issuance_type: Optional[str],
     max_cred_num: int,
     tails_dir_path: Optional[str],
+    cancel_token: Optional[ObjectHandle] = None,
+    tails_chunk_size: Optional[int] = None,
 ) -> (ObjectHandle, ObjectHandle, ObjectHandle, ObjectHandle):
     reg_def = ObjectHandle()
     reg_def_private = ObjectHandle()
     reg_entry = ObjectHandle()
     reg_init_delta = ObjectHandle()
     do_call(
-        "credx_create_revocation_registry",
+        "credx_create_revocation_registry_chunked",
         encode_str(origin_did),
         cred_def,
         encode_str(tag),
@@ -735,6 +1769,36 @@ def create_revocation_registry(
         encode_str(issuance_type),
         c_int64(max_cred_num),
         encode_str(tails_dir_path),
+        c_int64(tails_chunk_size or 0),
+        cancel_token or ObjectHandle(),
+        byref(reg_def),
+        byref(reg_def_private),
+        byref(reg_entry),
+        byref(reg_init_delta),
+    )
+    return reg_def, reg_def_private, reg_entry, reg_init_delta
+
+
+def create_revocation_registry_lazy(
+    origin_did: str,
+    cred_def: ObjectHandle,
+    tag: str,
+    rev_reg_type: str,
+    issuance_type: Optional[str],
+    max_cred_num: int,
+) -> (ObjectHandle, ObjectHandle, ObjectHandle, ObjectHandle):
+    reg_def = ObjectHandle()
+    reg_def_private = ObjectHandle()
+    reg_entry = ObjectHandle()
+    reg_init_delta = ObjectHandle()
+    do_call(
+        "credx_create_revocation_registry_lazy",
+        encode_str(origin_did),
+        cred_def,
+        encode_str(tag),
+        encode_str(rev_reg_type),
+        encode_str(issuance_type),
+        c_int64(max_cred_num),
         byref(reg_def),
         byref(reg_def_private),
         byref(reg_entry),
@@ -743,6 +1807,94 @@ def create_revocation_registry(
     return reg_def, reg_def_private, reg_entry, reg_init_delta
 
 
+def write_revocation_registry_tails(
+    cred_def: ObjectHandle,
+    rev_reg_def: ObjectHandle,
+    rev_reg_def_private: ObjectHandle,
+    tails_dir_path: Optional[str],
+    tails_chunk_size: Optional[int] = None,
+    cancel_token: Optional[ObjectHandle] = None,
+) -> ObjectHandle:
+    upd_rev_reg_def = ObjectHandle()
+    do_call(
+        "credx_write_revocation_registry_tails",
+        cred_def,
+        rev_reg_def,
+        rev_reg_def_private,
+        encode_str(tails_dir_path),
+        c_int64(tails_chunk_size or 0),
+        cancel_token or ObjectHandle(),
+        byref(upd_rev_reg_def),
+    )
+    return upd_rev_reg_def
+
+
+def write_revocation_registry_tails_to_buffer(
+    cred_def: ObjectHandle,
+    rev_reg_def: ObjectHandle,
+    rev_reg_def_private: ObjectHandle,
+    tails_location: Optional[str],
+    cancel_token: Optional[ObjectHandle] = None,
+) -> Tuple[ObjectHandle, ByteBuffer]:
+    upd_rev_reg_def = ObjectHandle()
+    tails = ByteBuffer()
+    do_call(
+        "credx_write_revocation_registry_tails_to_buffer",
+        cred_def,
+        rev_reg_def,
+        rev_reg_def_private,
+        encode_str(tails_location),
+        cancel_token or ObjectHandle(),
+        byref(upd_rev_reg_def),
+        byref(tails),
+    )
+    return upd_rev_reg_def, tails
+
+
+def extend_revocation_registry_tails(
+    cred_def: ObjectHandle,
+    rev_reg_def: ObjectHandle,
+    rev_reg_def_private: ObjectHandle,
+    partial_path: str,
+    count: int,
+) -> int:
+    remaining = c_int64()
+    do_call(
+        "credx_extend_revocation_registry_tails",
+        cred_def,
+        rev_reg_def,
+        rev_reg_def_private,
+        encode_str(partial_path),
+        c_int64(count),
+        byref(remaining),
+    )
+    return remaining.value
+
+
+def complete_revocation_registry_tails(
+    cred_def: ObjectHandle,
+    rev_reg_def: ObjectHandle,
+    rev_reg_def_private: ObjectHandle,
+    partial_path: str,
+    tails_dir_path: Optional[str],
+    tails_chunk_size: Optional[int] = None,
+    cancel_token: Optional[ObjectHandle] = None,
+) -> ObjectHandle:
+    upd_rev_reg_def = ObjectHandle()
+    do_call(
+        "credx_complete_revocation_registry_tails",
+        cred_def,
+        rev_reg_def,
+        rev_reg_def_private,
+        encode_str(partial_path),
+        encode_str(tails_dir_path),
+        c_int64(tails_chunk_size or 0),
+        cancel_token or ObjectHandle(),
+        byref(upd_rev_reg_def),
+    )
+    return upd_rev_reg_def
+
+
 def update_revocation_registry(
     rev_reg_def: ObjectHandle,
     rev_reg: ObjectHandle,
@@ -779,6 +1931,47 @@ def merge_revocation_registry_deltas(
     return rev_delta
 
 
+def revocation_registry_from_deltas(
+    deltas: Sequence[Tuple[int, ObjectHandle]],
+    timestamp: int,
+) -> ObjectHandle:
+    rev_reg = ObjectHandle()
+    entry_list = RevocationDeltaEntryList()
+    if deltas:
+        entry_list.count = len(deltas)
+        entry_list.data = (RevocationDeltaEntry * entry_list.count)(
+            *(
+                RevocationDeltaEntry(delta=delta, timestamp=delta_ts)
+                for (delta_ts, delta) in deltas
+            )
+        )
+    do_call(
+        "credx_revocation_registry_from_deltas",
+        entry_list,
+        c_int64(timestamp),
+        byref(rev_reg),
+    )
+    return rev_reg
+
+
+def revocation_registry_to_delta(
+    rev_reg: ObjectHandle,
+    prev_rev_reg: Optional[ObjectHandle],
+    issued: Sequence[int],
+    revoked: Sequence[int],
+) -> ObjectHandle:
+    rev_delta = ObjectHandle()
+    do_call(
+        "credx_revocation_registry_to_delta",
+        rev_reg,
+        prev_rev_reg or ObjectHandle(),
+        FfiIntList.create(issued),
+        FfiIntList.create(revoked),
+        byref(rev_delta),
+    )
+    return rev_delta
+
+
 def create_or_update_revocation_state(
     rev_reg_def: ObjectHandle,
     rev_reg_delta: ObjectHandle,
@@ -799,3 +1992,29 @@ def create_or_update_revocation_state(
         byref(rev_state),
     )
     return rev_state
+
+
+def create_or_update_revocation_states(
+    rev_reg_def: ObjectHandle,
+    rev_reg_delta: ObjectHandle,
+    rev_reg_indices: Sequence[int],
+    timestamp: int,
+    tails_path: str,
+    prev_rev_states: Optional[Sequence[Optional[ObjectHandle]]] = None,
+) -> Sequence[ObjectHandle]:
+    rev_reg_indices = list(rev_reg_indices)
+    result = (c_int64 * len(rev_reg_indices))()
+    do_call(
+        "credx_create_or_update_revocation_states",
+        rev_reg_def,
+        rev_reg_delta,
+        FfiIntList.create(rev_reg_indices),
+        FfiObjectHandleList.create(
+            prev_rev_states
+            and [state or ObjectHandle() for state in prev_rev_states]
+        ),
+        c_int64(timestamp),
+        encode_str(tails_path),
+        result,
+    )
+    return [ObjectHandle(handle) for handle in result]
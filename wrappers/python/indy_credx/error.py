@@ -13,6 +13,9 @@ class CredxErrorCode(IntEnum):
     INVALID_USER_REVOC_ID = 6
     PROOF_REJECTED = 7
     REVOCATION_REGISTRY_FULL = 8
+    CANCELLED = 9
+    HANDLE_EVICTED = 10
+    LIMIT_EXCEEDED = 11
     WRAPPER = 99
 
 
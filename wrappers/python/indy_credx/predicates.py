@@ -0,0 +1,54 @@
+"""Encoding of signed attribute values for use in predicates."""
+
+INT32_MAX = 2**31 - 1
+
+PREDICATE_TYPES = (">=", ">", "<=", "<")
+
+
+class SignedRange:
+    """An offset encoding for signed attribute values.
+
+    Predicate proofs operate on 32-bit integers, and the difference between
+    an attribute value and a predicate bound must also fit in that range.
+    Encoding values relative to the minimum of a range maps `min..=max` onto
+    `0..=max-min`, so that any value may be compared with any bound in the
+    range.
+    """
+
+    def __init__(self, min: int, max: int):
+        if min > max:
+            raise ValueError(f"Invalid range: {min} > {max}")
+        if max - min > INT32_MAX:
+            raise ValueError(
+                f"Range {min}..={max} is too wide to be compared by predicates"
+            )
+        self.min = min
+        self.max = max
+
+    def __repr__(self) -> str:
+        return f"{self.__class__.__name__}({self.min}, {self.max})"
+
+    def _offset(self, value: int) -> int:
+        if not self.min <= value <= self.max:
+            raise ValueError(
+                f"Value {value} is outside of the range {self.min}..={self.max}"
+            )
+        return value - self.min
+
+    def encode(self, value: int) -> str:
+        """The raw attribute value to issue for a value in the range."""
+        return str(self._offset(value))
+
+    def decode(self, raw: str) -> int:
+        """Recover a value from a raw attribute value issued with `encode`."""
+        offset = int(raw)
+        if not 0 <= offset <= self.max - self.min:
+            raise ValueError(f"Invalid offset-encoded value: {raw}")
+        return self.min + offset
+
+    def predicate(self, name: str, p_type: str, bound: int) -> dict:
+        """Build a requested predicate comparing an offset-encoded attribute
+        to a bound within the range."""
+        if p_type not in PREDICATE_TYPES:
+            raise ValueError(f"Invalid predicate type: {p_type}")
+        return {"name": name, "p_type": p_type, "p_value": self._offset(bound)}
@@ -0,0 +1,117 @@
+"""Sources of tails data for revocation registries."""
+
+import hashlib
+import os
+import tempfile
+from typing import AsyncIterator, Iterator, Optional, Union
+
+CHUNK_SIZE = 65536
+B58_ALPHABET = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz"
+
+TAILS_DIR: Optional[tempfile.TemporaryDirectory] = None
+
+
+class TailsSource(os.PathLike):
+    """The contents of a tails file, stored on disk or held in memory.
+
+    A source may be passed wherever a tails path is accepted. Contents held in
+    memory are written to a temporary directory, named by the tails hash, the
+    first time a path is required. Chunked tails must be read from a path.
+    """
+
+    def __init__(self, *, path: str = None, data: bytes = None):
+        if (path is None) == (data is None):
+            raise ValueError("Exactly one of path or data must be provided")
+        self._path = path
+        self._data = data
+
+    @classmethod
+    def from_path(cls, path: Union[str, os.PathLike]) -> "TailsSource":
+        return cls(path=os.fspath(path))
+
+    @classmethod
+    def from_bytes(cls, data: Union[bytes, bytearray, memoryview]) -> "TailsSource":
+        return cls(data=bytes(data))
+
+    @classmethod
+    async def from_stream(cls, stream) -> "TailsSource":
+        """Read the tails from an async iterable of byte strings, or from an
+        object with an async `read` method such as an HTTP response body."""
+        data = bytearray()
+        if hasattr(stream, "read"):
+            while True:
+                chunk = await stream.read(CHUNK_SIZE)
+                if not chunk:
+                    break
+                data.extend(chunk)
+        else:
+            async for chunk in stream:
+                data.extend(chunk)
+        return cls(data=bytes(data))
+
+    @property
+    def hash(self) -> str:
+        """The base58-encoded SHA-256 digest, as found in the registry definition."""
+        digest = hashlib.sha256()
+        for chunk in self.chunks():
+            digest.update(chunk)
+        return _b58_encode(digest.digest())
+
+    @property
+    def path(self) -> str:
+        if self._path is None:
+            self._path = _write_tails(self.hash, self._data)
+        return self._path
+
+    def __fspath__(self) -> str:
+        return self.path
+
+    def __repr__(self) -> str:
+        if self._data is not None:
+            return f"{self.__class__.__name__}(<{len(self._data)} bytes>)"
+        return f"{self.__class__.__name__}({self._path!r})"
+
+    def chunks(self, size: int = CHUNK_SIZE) -> Iterator[bytes]:
+        """Iterate over the tails contents, for instance to upload them."""
+        if self._data is not None:
+            for offset in range(0, len(self._data), size):
+                yield self._data[offset : offset + size]
+        else:
+            with open(self._path, "rb") as tails:
+                while True:
+                    chunk = tails.read(size)
+                    if not chunk:
+                        break
+                    yield chunk
+
+    async def stream(self, size: int = CHUNK_SIZE) -> AsyncIterator[bytes]:
+        for chunk in self.chunks(size):
+            yield chunk
+
+    def to_bytes(self) -> bytes:
+        if self._data is not None:
+            return self._data
+        return b"".join(self.chunks())
+
+
+def _write_tails(tails_hash: str, data: bytes) -> str:
+    global TAILS_DIR
+    if TAILS_DIR is None:
+        TAILS_DIR = tempfile.TemporaryDirectory(prefix="indy_credx_tails_")
+    path = os.path.join(TAILS_DIR.name, tails_hash)
+    if not os.path.exists(path):
+        fd, temp_path = tempfile.mkstemp(dir=TAILS_DIR.name)
+        with os.fdopen(fd, "wb") as tails:
+            tails.write(data)
+        os.replace(temp_path, path)
+    return path
+
+
+def _b58_encode(data: bytes) -> str:
+    value = int.from_bytes(data, "big")
+    result = ""
+    while value:
+        value, rem = divmod(value, 58)
+        result = B58_ALPHABET[rem] + result
+    pad = len(data) - len(data.lstrip(b"\0"))
+    return B58_ALPHABET[0] * pad + result
@@ -1,10 +1,21 @@
-from typing import Mapping, Optional, Sequence, Union
+from typing import Mapping, Optional, Sequence, Tuple, Union
 
 from . import bindings
+from .tails import TailsSource
+
+
+class CancellationToken(bindings.IndyObject):
+    @classmethod
+    def create(cls) -> "CancellationToken":
+        return CancellationToken(bindings.create_cancellation_token())
+
+    def cancel(self):
+        bindings.cancellation_token_cancel(self.handle)
 
 
 class CredentialDefinition(bindings.IndyObject):
     GET_ATTR = "credx_credential_definition_get_attribute"
+    FIELDS = ("id", "schema_id", "signature_type", "tag", "supports_revocation")
 
     @classmethod
     def create(
@@ -15,12 +26,47 @@ class CredentialDefinition(bindings.IndyObject):
         tag: str,
         *,
         support_revocation: bool = False,
+        cancel_token: CancellationToken = None,
     ) -> ("CredentialDefinition", "CredentialDefinitionPrivate", "KeyCorrectnessProof"):
+        """Create a credential definition and its keys.
+
+        The key generation stops shortly after `cancel_token` is cancelled."""
         if not isinstance(schema, bindings.IndyObject):
             schema = Schema.load(schema)
         cred_def, cred_def_pvt, key_proof = bindings.create_credential_definition(
-            origin_did, schema.handle, tag, signature_type, support_revocation
+            origin_did,
+            schema.handle,
+            tag,
+            signature_type,
+            support_revocation,
+            cancel_token and cancel_token.handle,
+        )
+        return (
+            CredentialDefinition(cred_def),
+            CredentialDefinitionPrivate(cred_def_pvt),
+            KeyCorrectnessProof(key_proof),
+        )
+
+    @classmethod
+    def create_from_seed(
+        cls,
+        origin_did: str,
+        schema: [str, "Schema"],
+        signature_type: str,
+        tag: str,
+        seed: bytes,
+    ) -> ("CredentialDefinition", "CredentialDefinitionPrivate", "KeyCorrectnessProof"):
+        """Create a credential definition with keys derived from `seed`, for
+        reproducible test fixtures.
+
+        The keys must never be used outside of tests.
+        """
+        if not isinstance(schema, bindings.IndyObject):
+            schema = Schema.load(schema)
+        handles = bindings.create_credential_definition_from_seed(
+            origin_did, schema.handle, tag, signature_type, seed
         )
+        cred_def, cred_def_pvt, key_proof = handles
         return (
             CredentialDefinition(cred_def),
             CredentialDefinitionPrivate(cred_def_pvt),
@@ -33,6 +79,15 @@ class CredentialDefinition(bindings.IndyObject):
             bindings._object_from_json("credx_credential_definition_from_json", value)
         )
 
+    def validate(self, schema: Union[str, "Schema"] = None) -> list:
+        """Check the credential definition for problems, and when a schema is
+        given, that the credential definition matches it."""
+        if schema is None:
+            return super().validate()
+        if not isinstance(schema, bindings.IndyObject):
+            schema = Schema.load(schema)
+        return bindings.credential_definition_validate(self.handle, schema.handle)
+
     @property
     def id(self) -> str:
         return str(
@@ -53,6 +108,34 @@ class CredentialDefinition(bindings.IndyObject):
             )
         )
 
+    @property
+    def signature_type(self) -> str:
+        return str(
+            bindings._object_get_attribute(
+                self.GET_ATTR,
+                self.handle,
+                "signature_type",
+            )
+        )
+
+    @property
+    def tag(self) -> str:
+        return str(
+            bindings._object_get_attribute(
+                self.GET_ATTR,
+                self.handle,
+                "tag",
+            )
+        )
+
+    @property
+    def supports_revocation(self) -> bool:
+        return bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "supports_revocation",
+        )
+
 
 class CredentialDefinitionPrivate(bindings.IndyObject):
     @classmethod
@@ -75,6 +158,9 @@ class KeyCorrectnessProof(bindings.IndyObject):
 
 
 class CredentialOffer(bindings.IndyObject):
+    GET_ATTR = "credx_credential_offer_get_attribute"
+    FIELDS = ("schema_id", "cred_def_id", "nonce", "method_name")
+
     @classmethod
     def create(
         cls,
@@ -92,14 +178,78 @@ class CredentialOffer(bindings.IndyObject):
             )
         )
 
+    @classmethod
+    def create_many(
+        cls,
+        schema_id: str,
+        cred_def: [str, CredentialDefinition],
+        key_proof: [str, KeyCorrectnessProof],
+        count: int,
+    ) -> Sequence["CredentialOffer"]:
+        if not isinstance(cred_def, bindings.IndyObject):
+            cred_def = CredentialDefinition.load(cred_def)
+        if not isinstance(key_proof, bindings.IndyObject):
+            key_proof = KeyCorrectnessProof.load(key_proof)
+        return [
+            CredentialOffer(handle)
+            for handle in bindings.create_credential_offers(
+                schema_id, cred_def.handle, key_proof.handle, count
+            )
+        ]
+
     @classmethod
     def load(cls, value: Union[dict, str, bytes, memoryview]) -> "CredentialOffer":
         return CredentialOffer(
             bindings._object_from_json("credx_credential_offer_from_json", value)
         )
 
+    def to_aries_message(self, preview: Mapping[str, str], comment: str = None) -> dict:
+        return bindings.aries_offer_credential(self.handle, preview, comment)
+
+    @property
+    def schema_id(self) -> str:
+        return str(
+            bindings._object_get_attribute(
+                self.GET_ATTR,
+                self.handle,
+                "schema_id",
+            )
+        )
+
+    @property
+    def cred_def_id(self) -> str:
+        return str(
+            bindings._object_get_attribute(
+                self.GET_ATTR,
+                self.handle,
+                "cred_def_id",
+            )
+        )
+
+    @property
+    def nonce(self) -> str:
+        return str(
+            bindings._object_get_attribute(
+                self.GET_ATTR,
+                self.handle,
+                "nonce",
+            )
+        )
+
+    @property
+    def method_name(self) -> Optional[str]:
+        sval = bindings._object_get_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "method_name",
+        )
+        return str(sval) if sval is not None else None
+
 
 class CredentialRequest(bindings.IndyObject):
+    GET_ATTR = "credx_credential_request_get_attribute"
+    FIELDS = ("prover_did", "cred_def_id", "nonce")
+
     @classmethod
     def create(
         cls,
@@ -124,14 +274,94 @@ class CredentialRequest(bindings.IndyObject):
         )
         return CredentialRequest(cred_def), CredentialRequestMetadata(cred_def_metadata)
 
+    @classmethod
+    def create_committed(
+        cls,
+        prover_did: str,
+        cred_def: [str, CredentialDefinition],
+        master_secret: [str, "MasterSecret"],
+        master_secret_id: str,
+        cred_offer: [str, CredentialOffer],
+        committed_values: Mapping[str, str],
+    ) -> ("CredentialRequest", "CredentialRequestMetadata"):
+        if not isinstance(cred_def, bindings.IndyObject):
+            cred_def = CredentialDefinition.load(cred_def)
+        if not isinstance(master_secret, bindings.IndyObject):
+            master_secret = MasterSecret.load(master_secret)
+        if not isinstance(cred_offer, bindings.IndyObject):
+            cred_offer = CredentialOffer.load(cred_offer)
+        cred_req, cred_req_metadata = bindings.create_committed_credential_request(
+            prover_did,
+            cred_def.handle,
+            master_secret.handle,
+            master_secret_id,
+            cred_offer.handle,
+            committed_values,
+        )
+        return CredentialRequest(cred_req), CredentialRequestMetadata(cred_req_metadata)
+
     @classmethod
     def load(cls, value: Union[dict, str, bytes, memoryview]) -> "CredentialRequest":
         return CredentialRequest(
             bindings._object_from_json("credx_credential_request_from_json", value)
         )
 
+    def to_aries_message(self, thread_id: str, comment: str = None) -> dict:
+        return bindings.aries_request_credential(self.handle, thread_id, comment)
+
+    @property
+    def prover_did(self) -> str:
+        return str(
+            bindings._object_get_attribute(
+                self.GET_ATTR,
+                self.handle,
+                "prover_did",
+            )
+        )
+
+    @property
+    def cred_def_id(self) -> str:
+        return str(
+            bindings._object_get_attribute(
+                self.GET_ATTR,
+                self.handle,
+                "cred_def_id",
+            )
+        )
+
+    @property
+    def nonce(self) -> str:
+        return str(
+            bindings._object_get_attribute(
+                self.GET_ATTR,
+                self.handle,
+                "nonce",
+            )
+        )
+
 
 class CredentialRequestMetadata(bindings.IndyObject):
+    def rename_master_secret(self, master_secret_id: str) -> "CredentialRequestMetadata":
+        return CredentialRequestMetadata(
+            bindings.credential_request_metadata_rename_master_secret(
+                self.handle, master_secret_id
+            )
+        )
+
+    def has_master_secret(self, master_secret_id: str) -> bool:
+        return bindings.credential_request_metadata_has_master_secret(
+            self.handle, master_secret_id
+        )
+
+    def wrap(self, key: bytes) -> str:
+        return bindings.credential_request_metadata_wrap(self.handle, key)
+
+    @classmethod
+    def unwrap(cls, wrapped: str, key: bytes) -> "CredentialRequestMetadata":
+        return CredentialRequestMetadata(
+            bindings.credential_request_metadata_unwrap(wrapped, key)
+        )
+
     @classmethod
     def load(
         cls, value: Union[dict, str, bytes, memoryview]
@@ -157,6 +387,7 @@ class MasterSecret(bindings.IndyObject):
 
 class Schema(bindings.IndyObject):
     GET_ATTR = "credx_schema_get_attribute"
+    FIELDS = ("id", "name", "version", "attr_names", "seq_no")
 
     @classmethod
     def create(
@@ -186,9 +417,55 @@ class Schema(bindings.IndyObject):
             )
         )
 
+    @property
+    def name(self) -> str:
+        return str(
+            bindings._object_get_attribute(
+                self.GET_ATTR,
+                self.handle,
+                "name",
+            )
+        )
+
+    @property
+    def version(self) -> str:
+        return str(
+            bindings._object_get_attribute(
+                self.GET_ATTR,
+                self.handle,
+                "version",
+            )
+        )
+
+    @property
+    def attr_names(self) -> Sequence[str]:
+        return bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "attr_names",
+        )
+
+    @property
+    def seq_no(self) -> Optional[int]:
+        return bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "seq_no",
+        )
+
 
 class Credential(bindings.IndyObject):
     GET_ATTR = "credx_credential_get_attribute"
+    FIELDS = (
+        "schema_id",
+        "cred_def_id",
+        "rev_reg_id",
+        "rev_reg_index",
+        "cred_rev_id",
+        "values",
+        "encoded_values",
+        "display",
+    )
 
     @classmethod
     def create(
@@ -198,7 +475,7 @@ class Credential(bindings.IndyObject):
         cred_offer: [str, CredentialOffer],
         cred_request: [str, CredentialRequest],
         attr_raw_values: Mapping[str, str],
-        attr_enc_values: Mapping[str, str] = None,
+        attr_enc_values: Mapping[str, Union[str, int]] = None,
         revocation_config: "CredentialRevocationConfig" = None,
     ) -> (
         "Credential",
@@ -253,64 +530,314 @@ class Credential(bindings.IndyObject):
             )
         )
 
-    @classmethod
-    def load(cls, value: Union[dict, str, bytes, memoryview]) -> "Credential":
-        return Credential(
-            bindings._object_from_json("credx_credential_from_json", value)
+    def derive_values(
+        self, attr_names: Sequence[str]
+    ) -> Tuple[Mapping[str, str], Mapping[str, str]]:
+        return bindings.credential_derive_values(self.handle, attr_names)
+
+    def process_and_extract(
+        self,
+        cred_offer: [str, CredentialOffer],
+        cred_req_metadata: [str, CredentialRequestMetadata],
+        master_secret: [str, MasterSecret],
+        cred_def: [str, CredentialDefinition],
+        rev_reg_def: Optional[Union[str, "RevocationRegistryDefinition"]] = None,
+    ) -> Tuple["Credential", Mapping[str, str]]:
+        if not isinstance(cred_offer, bindings.IndyObject):
+            cred_offer = CredentialOffer.load(cred_offer)
+        if not isinstance(cred_req_metadata, bindings.IndyObject):
+            cred_req_metadata = CredentialRequestMetadata.load(cred_req_metadata)
+        if not isinstance(master_secret, bindings.IndyObject):
+            master_secret = MasterSecret.load(master_secret)
+        if not isinstance(cred_def, bindings.IndyObject):
+            cred_def = CredentialDefinition.load(cred_def)
+        if rev_reg_def and not isinstance(rev_reg_def, bindings.IndyObject):
+            rev_reg_def = RevocationRegistryDefinition.load(rev_reg_def)
+        cred, attributes = bindings.process_and_extract_credential(
+            self.handle,
+            cred_offer.handle,
+            cred_req_metadata.handle,
+            master_secret.handle,
+            cred_def.handle,
+            rev_reg_def.handle if rev_reg_def else None,
+        )
+        return Credential(cred), attributes
+
+    @classmethod
+    def load(cls, value: Union[dict, str, bytes, memoryview]) -> "Credential":
+        return Credential(
+            bindings._object_from_json("credx_credential_from_json", value)
+        )
+
+    def to_aries_message(self, thread_id: str, comment: str = None) -> dict:
+        return bindings.aries_issue_credential(self.handle, thread_id, comment)
+
+    @property
+    def schema_id(self) -> str:
+        return str(
+            bindings._object_get_attribute(
+                self.GET_ATTR,
+                self.handle,
+                "schema_id",
+            )
+        )
+
+    @property
+    def cred_def_id(self) -> str:
+        return str(
+            bindings._object_get_attribute(
+                self.GET_ATTR,
+                self.handle,
+                "cred_def_id",
+            )
+        )
+
+    @property
+    def rev_reg_id(self) -> Optional[str]:
+        sval = bindings._object_get_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "rev_reg_id",
+        )
+        return str(sval) if sval is not None else None
+
+    @property
+    def rev_reg_index(self) -> Optional[int]:
+        sval = bindings._object_get_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "rev_reg_index",
+        )
+        return int(str(sval)) if sval is not None else None
+
+    @property
+    def cred_rev_id(self) -> Optional[str]:
+        """The index of the credential in its revocation registry, in the
+        string form used by ledger and agent tooling."""
+        sval = bindings._object_get_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "cred_rev_id",
+        )
+        return str(sval) if sval is not None else None
+
+    @property
+    def supports_revocation(self) -> bool:
+        return self.rev_reg_id is not None
+
+    @property
+    def values(self) -> Mapping[str, str]:
+        return bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "values",
+        )
+
+    @property
+    def attribute_names(self) -> Sequence[str]:
+        return bindings.credential_get_attribute_names(self.handle)
+
+    def attribute_value(self, name: str, *, encoded: bool = False):
+        """Read the raw value of an attribute, or the raw and encoded values
+        when `encoded` is set, without serializing the credential."""
+        return bindings.credential_get_attribute_value(self.handle, name, encoded)
+
+    def tags(self) -> Mapping[str, str]:
+        """The wallet search tags of the credential, as stored by libindy."""
+        return bindings.credential_tags(self.handle)
+
+    def check_encoding(self) -> Sequence[str]:
+        """List the attributes whose encoded values do not match the standard
+        encoding of their raw values."""
+        return bindings.check_credential_encoding(self.handle)
+
+    @property
+    def encoded_values(self) -> Mapping[str, int]:
+        """The encoded attribute values, as integers of arbitrary size."""
+        values = bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "encoded_values",
+        )
+        return {name: int(value) for name, value in values.items()}
+
+    @property
+    def display(self) -> Optional[Mapping[str, dict]]:
+        """The display hints for the attribute values, which are not signed."""
+        return bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "display",
+        )
+
+    def set_display(self, display: Optional[Mapping[str, dict]]) -> "Credential":
+        """Create a copy of the credential with the given display hints."""
+        return Credential(bindings.credential_set_display(self.handle, display))
+
+
+class PresentationRequest(bindings.IndyObject):
+    GET_ATTR = "credx_presentation_request_get_attribute"
+    FIELDS = (
+        "name",
+        "version",
+        "nonce",
+        "ver",
+        "requested_attributes",
+        "requested_predicates",
+        "non_revoked",
+        "referents",
+    )
+
+    @classmethod
+    def load(cls, value: Union[dict, str, bytes, memoryview]) -> "PresentationRequest":
+        return PresentationRequest(
+            bindings._object_from_json("credx_presentation_request_from_json", value)
+        )
+
+    @classmethod
+    def from_template(
+        cls, name: str, values: Mapping = None
+    ) -> "PresentationRequest":
+        return PresentationRequest(
+            bindings.create_presentation_request_from_template(name, values)
+        )
+
+    @classmethod
+    def merge(
+        cls,
+        name: str,
+        version: str,
+        pres_reqs: Sequence[Union[str, "PresentationRequest"]],
+    ) -> "PresentationRequest":
+        pres_reqs = [
+            (
+                PresentationRequest.load(r)
+                if not isinstance(r, bindings.IndyObject)
+                else r
+            ).handle
+            for r in pres_reqs
+        ]
+        return PresentationRequest(
+            bindings.merge_presentation_requests(name, version, pres_reqs)
+        )
+
+    def to_aries_message(self, comment: str = None) -> dict:
+        return bindings.aries_request_presentation(self.handle, comment)
+
+    def to_version(self, ver: str) -> "PresentationRequest":
+        """Convert the request to version "1.0" or "2.0". Fully qualified
+        identifiers in restrictions are unqualified for version "1.0"."""
+        return PresentationRequest(
+            bindings.presentation_request_to_version(self.handle, ver)
+        )
+
+    @property
+    def name(self) -> str:
+        return str(
+            bindings._object_get_attribute(
+                self.GET_ATTR,
+                self.handle,
+                "name",
+            )
         )
 
     @property
-    def schema_id(self) -> str:
+    def version(self) -> str:
         return str(
             bindings._object_get_attribute(
                 self.GET_ATTR,
                 self.handle,
-                "schema_id",
+                "version",
             )
         )
 
     @property
-    def cred_def_id(self) -> str:
+    def nonce(self) -> str:
         return str(
             bindings._object_get_attribute(
                 self.GET_ATTR,
                 self.handle,
-                "cred_def_id",
+                "nonce",
             )
         )
 
     @property
-    def rev_reg_id(self) -> str:
+    def ver(self) -> str:
         return str(
             bindings._object_get_attribute(
                 self.GET_ATTR,
                 self.handle,
-                "rev_reg_id",
+                "ver",
             )
         )
 
     @property
-    def rev_reg_index(self) -> Optional[int]:
-        sval = bindings._object_get_attribute(
+    def requested_attributes(self) -> Mapping[str, dict]:
+        return bindings._object_get_json_attribute(
             self.GET_ATTR,
             self.handle,
-            "rev_reg_index",
+            "requested_attributes",
         )
-        return int(str(sval)) if sval is not None else None
 
+    @property
+    def requested_predicates(self) -> Mapping[str, dict]:
+        return bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "requested_predicates",
+        )
 
-class PresentationRequest(bindings.IndyObject):
-    @classmethod
-    def load(cls, value: Union[dict, str, bytes, memoryview]) -> "PresentationRequest":
-        return PresentationRequest(
-            bindings._object_from_json("credx_presentation_request_from_json", value)
+    @property
+    def non_revoked(self) -> Optional[dict]:
+        return bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "non_revoked",
+        )
+
+    @property
+    def referents(self) -> Sequence[dict]:
+        """The requested attribute and predicate referents, with their
+        names, restrictions and effective non-revocation intervals."""
+        return bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "referents",
+        )
+
+
+class CredentialSearch:
+    """An incremental search for the credentials which may be used for a
+    referent of a presentation request."""
+
+    def __init__(
+        self,
+        pres_req: PresentationRequest,
+        referent: str,
+        credentials: Sequence[Credential],
+    ):
+        self._credentials = {cred.handle.value: cred for cred in credentials}
+        self.handle = bindings.credential_search_open(
+            pres_req.handle, referent, [cred.handle for cred in credentials]
         )
 
+    def fetch(self, limit: int) -> Sequence[Credential]:
+        """Fetch up to `limit` further matching credentials. An empty result
+        indicates that the search is complete."""
+        found = bindings.credential_search_fetch(self.handle, limit)
+        return [self._credentials[handle] for handle in found]
+
+    def close(self):
+        """Release the search and its references to the credentials."""
+        if self.handle.value:
+            bindings.credential_search_free(self.handle)
+
 
 class PresentCredentials:
     def __init__(self):
         self.entries = {}
         self.self_attest = {}
+        self.rev_states = {}
 
     def add_self_attested(self, attest: Mapping[str, str]):
         if attest:
@@ -359,8 +886,72 @@ class PresentCredentials:
         for reft in referents:
             entry[1].add(reft)
 
+    def use_latest_revocation_state(
+        self,
+        cred: Credential,
+        rev_states: Sequence[Union[str, "CredentialRevocationState"]],
+    ):
+        """Present the referents added for a credential without a timestamp
+        using the latest of the given cached revocation states which satisfies
+        the `non_revoked` intervals of the request."""
+        self.rev_states[cred] = [
+            (
+                CredentialRevocationState.load(state)
+                if not isinstance(state, bindings.IndyObject)
+                else state
+            )
+            for state in rev_states
+        ]
+
+
+def _load_ledger_objects(
+    schemas: Sequence[Union[str, Schema]],
+    cred_defs: Sequence[Union[str, CredentialDefinition]],
+    rev_reg_defs: Optional[Sequence[Union[str, "RevocationRegistryDefinition"]]],
+    rev_reg_entries: Optional[
+        Mapping[str, Mapping[int, Union[str, "RevocationRegistry"]]]
+    ],
+):
+    schemas = [
+        (Schema.load(s) if not isinstance(s, bindings.IndyObject) else s).handle
+        for s in schemas
+    ]
+    cred_defs = [
+        (
+            CredentialDefinition.load(c)
+            if not isinstance(c, bindings.IndyObject)
+            else c
+        ).handle
+        for c in cred_defs
+    ]
+    reg_defs = []
+    reg_entries = []
+    for reg_def in rev_reg_defs or ():
+        if not isinstance(reg_def, bindings.IndyObject):
+            reg_def = RevocationRegistryDefinition.load(reg_def)
+        reg_def_id = reg_def.id
+        if rev_reg_entries and reg_def_id in rev_reg_entries:
+            for timestamp, entry in rev_reg_entries[reg_def_id].items():
+                if not isinstance(entry, bindings.IndyObject):
+                    entry = RevocationRegistry.load(entry)
+                reg_entries.append(
+                    bindings.RevocationEntry.create(
+                        len(reg_defs), entry.handle, timestamp
+                    )
+                )
+        reg_defs.append(reg_def.handle)
+    return schemas, cred_defs, reg_defs, reg_entries or None
+
 
 class Presentation(bindings.IndyObject):
+    GET_ATTR = "credx_presentation_get_attribute"
+    FIELDS = (
+        "identifiers",
+        "revealed_attrs",
+        "revealed_attr_groups",
+        "self_attested_attrs",
+    )
+
     @classmethod
     def create(
         cls,
@@ -370,6 +961,9 @@ class Presentation(bindings.IndyObject):
         master_secret: [str, MasterSecret],
         schemas: Sequence[Union[str, Schema]],
         cred_defs: Sequence[Union[str, CredentialDefinition]],
+        *,
+        auditable: bool = False,
+        seed: Optional[bytes] = None,
     ) -> "Presentation":
         if not isinstance(pres_req, bindings.IndyObject):
             pres_req = PresentationRequest.load(pres_req)
@@ -391,6 +985,16 @@ class Presentation(bindings.IndyObject):
         creds_prove = []
         for (cred, cred_ts) in present_creds.entries.items():
             for (timestamp, (attrs, preds, rev_state)) in cred_ts.items():
+                states = present_creds.rev_states.get(cred)
+                if timestamp is None and rev_state is None and states:
+                    idx, selected_ts = bindings.select_revocation_state(
+                        pres_req.handle,
+                        [reft for (reft, _reveal) in attrs],
+                        list(preds),
+                        [state.handle for state in states],
+                    )
+                    if idx >= 0:
+                        timestamp, rev_state = selected_ts, states[idx]
                 entry_idx = len(creds)
                 creds.append(
                     bindings.CredentialEntry.create(
@@ -414,6 +1018,8 @@ class Presentation(bindings.IndyObject):
                 master_secret.handle,
                 schemas,
                 cred_defs,
+                auditable,
+                seed,
             )
         )
 
@@ -423,6 +1029,9 @@ class Presentation(bindings.IndyObject):
             bindings._object_from_json("credx_presentation_from_json", value)
         )
 
+    def to_aries_message(self, thread_id: str, comment: str = None) -> dict:
+        return bindings.aries_presentation(self.handle, thread_id, comment)
+
     def verify(
         self,
         pres_req: [str, PresentationRequest],
@@ -432,45 +1041,97 @@ class Presentation(bindings.IndyObject):
         rev_reg_entries: Mapping[
             str, Mapping[int, Union[str, "RevocationRegistry"]]
         ] = None,
+        *,
+        policy: Mapping = None,
     ) -> bool:
         if not isinstance(pres_req, bindings.IndyObject):
             pres_req = PresentationRequest.load(pres_req)
-        schemas = [
-            (Schema.load(s) if not isinstance(s, bindings.IndyObject) else s).handle
-            for s in schemas
+        schemas, cred_defs, reg_defs, reg_entries = _load_ledger_objects(
+            schemas, cred_defs, rev_reg_defs, rev_reg_entries
+        )
+        return bindings.verify_presentation(
+            self.handle,
+            pres_req.handle,
+            schemas,
+            cred_defs,
+            reg_defs,
+            reg_entries,
+            policy,
+        )
+
+    @classmethod
+    def verify_batch(
+        cls,
+        presentations: Sequence[Union[str, "Presentation"]],
+        pres_reqs: Sequence[Union[str, PresentationRequest]],
+        schemas: Sequence[Union[str, Schema]],
+        cred_defs: Sequence[Union[str, CredentialDefinition]],
+        rev_reg_defs: Sequence[Union[str, "RevocationRegistryDefinition"]] = None,
+        rev_reg_entries: Mapping[
+            str, Mapping[int, Union[str, "RevocationRegistry"]]
+        ] = None,
+        *,
+        policy: Mapping = None,
+    ) -> Sequence[dict]:
+        presentations = [
+            (cls.load(p) if not isinstance(p, bindings.IndyObject) else p).handle
+            for p in presentations
         ]
-        cred_defs = [
+        pres_reqs = [
             (
-                CredentialDefinition.load(c)
-                if not isinstance(c, bindings.IndyObject)
-                else c
+                PresentationRequest.load(r)
+                if not isinstance(r, bindings.IndyObject)
+                else r
             ).handle
-            for c in cred_defs
+            for r in pres_reqs
         ]
-        reg_defs = []
-        reg_entries = []
-        for reg_def in rev_reg_defs:
-            if not isinstance(reg_def, bindings.IndyObject):
-                reg_def = RevocationRegistryDefinition.load(reg_def)
-            reg_def_id = reg_def.id
-            if rev_reg_entries and reg_def_id in rev_reg_entries:
-                for timestamp, entry in rev_reg_entries[reg_def_id].items():
-                    if not isinstance(entry, bindings.IndyObject):
-                        entry = RevocationRegistry.load(entry)
-                    reg_entries.append(
-                        bindings.RevocationEntry.create(
-                            len(reg_defs), entry.handle, timestamp
-                        )
-                    )
-            reg_defs.append(reg_def.handle)
-
-        return bindings.verify_presentation(
-            self.handle,
-            pres_req.handle,
+        schemas, cred_defs, reg_defs, reg_entries = _load_ledger_objects(
+            schemas, cred_defs, rev_reg_defs, rev_reg_entries
+        )
+        return bindings.verify_presentations_batch(
+            presentations,
+            pres_reqs,
             schemas,
             cred_defs,
             reg_defs,
-            reg_entries or None,
+            reg_entries,
+            policy,
+        )
+
+    def check_accepted_issuers(self, policy: Mapping) -> Sequence[dict]:
+        return bindings.presentation_check_accepted_issuers(self.handle, policy)
+
+    @property
+    def identifiers(self) -> Sequence[dict]:
+        """The identifiers of the credential behind each sub-proof."""
+        return bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "identifiers",
+        )
+
+    @property
+    def revealed_attrs(self) -> Mapping[str, dict]:
+        return bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "revealed_attrs",
+        )
+
+    @property
+    def revealed_attr_groups(self) -> Mapping[str, dict]:
+        return bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "revealed_attr_groups",
+        )
+
+    @property
+    def self_attested_attrs(self) -> Mapping[str, str]:
+        return bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "self_attested_attrs",
         )
 
 
@@ -488,6 +1149,8 @@ class RevocationRegistryDefinition(bindings.IndyObject):
         *,
         issuance_type: str = None,
         tails_dir_path: str = None,
+        tails_chunk_size: int = None,
+        cancel_token: CancellationToken = None,
     ) -> (
         "RevocationRegistryDefinition",
         "RevocationRegistryDefinitionPrivate",
@@ -509,6 +1172,46 @@ class RevocationRegistryDefinition(bindings.IndyObject):
             issuance_type,
             max_cred_num,
             tails_dir_path,
+            cancel_token and cancel_token.handle,
+            tails_chunk_size,
+        )
+        return (
+            RevocationRegistryDefinition(reg_def),
+            RevocationRegistryDefinitionPrivate(reg_def_private),
+            RevocationRegistry(reg_entry),
+            RevocationRegistryDelta(reg_init_delta),
+        )
+
+    @classmethod
+    def create_lazy(
+        cls,
+        origin_did: str,
+        cred_def: [str, CredentialDefinition],
+        tag: str,
+        registry_type: str,
+        max_cred_num: int,
+        *,
+        issuance_type: str = None,
+    ) -> (
+        "RevocationRegistryDefinition",
+        "RevocationRegistryDefinitionPrivate",
+        "RevocationRegistry",
+        "RevocationRegistryDelta",
+    ):
+        if not isinstance(cred_def, bindings.IndyObject):
+            cred_def = CredentialDefinition.load(cred_def)
+        (
+            reg_def,
+            reg_def_private,
+            reg_entry,
+            reg_init_delta,
+        ) = bindings.create_revocation_registry_lazy(
+            origin_did,
+            cred_def.handle,
+            tag,
+            registry_type,
+            issuance_type,
+            max_cred_num,
         )
         return (
             RevocationRegistryDefinition(reg_def),
@@ -517,6 +1220,103 @@ class RevocationRegistryDefinition(bindings.IndyObject):
             RevocationRegistryDelta(reg_init_delta),
         )
 
+    def write_tails(
+        self,
+        cred_def: [str, CredentialDefinition],
+        reg_def_private: [str, "RevocationRegistryDefinitionPrivate"],
+        *,
+        tails_dir_path: str = None,
+        tails_chunk_size: int = None,
+        cancel_token: CancellationToken = None,
+    ) -> "RevocationRegistryDefinition":
+        if not isinstance(cred_def, bindings.IndyObject):
+            cred_def = CredentialDefinition.load(cred_def)
+        if not isinstance(reg_def_private, bindings.IndyObject):
+            reg_def_private = RevocationRegistryDefinitionPrivate.load(reg_def_private)
+        return RevocationRegistryDefinition(
+            bindings.write_revocation_registry_tails(
+                cred_def.handle,
+                self.handle,
+                reg_def_private.handle,
+                tails_dir_path,
+                tails_chunk_size,
+                cancel_token and cancel_token.handle,
+            )
+        )
+
+    def extend_tails(
+        self,
+        cred_def: [str, CredentialDefinition],
+        reg_def_private: [str, "RevocationRegistryDefinitionPrivate"],
+        partial_path: str,
+        count: int,
+    ) -> int:
+        """Generate more tails into a partial tails file, returning the number
+        of tails still to be generated."""
+        if not isinstance(cred_def, bindings.IndyObject):
+            cred_def = CredentialDefinition.load(cred_def)
+        if not isinstance(reg_def_private, bindings.IndyObject):
+            reg_def_private = RevocationRegistryDefinitionPrivate.load(reg_def_private)
+        return bindings.extend_revocation_registry_tails(
+            cred_def.handle,
+            self.handle,
+            reg_def_private.handle,
+            partial_path,
+            count,
+        )
+
+    def complete_tails(
+        self,
+        cred_def: [str, CredentialDefinition],
+        reg_def_private: [str, "RevocationRegistryDefinitionPrivate"],
+        partial_path: str,
+        *,
+        tails_dir_path: str = None,
+        tails_chunk_size: int = None,
+        cancel_token: CancellationToken = None,
+    ) -> "RevocationRegistryDefinition":
+        """Write the tails, reusing those generated into a partial tails file."""
+        if not isinstance(cred_def, bindings.IndyObject):
+            cred_def = CredentialDefinition.load(cred_def)
+        if not isinstance(reg_def_private, bindings.IndyObject):
+            reg_def_private = RevocationRegistryDefinitionPrivate.load(reg_def_private)
+        return RevocationRegistryDefinition(
+            bindings.complete_revocation_registry_tails(
+                cred_def.handle,
+                self.handle,
+                reg_def_private.handle,
+                partial_path,
+                tails_dir_path,
+                tails_chunk_size,
+                cancel_token and cancel_token.handle,
+            )
+        )
+
+    def write_tails_to_buffer(
+        self,
+        cred_def: [str, CredentialDefinition],
+        reg_def_private: [str, "RevocationRegistryDefinitionPrivate"],
+        *,
+        tails_location: str = None,
+        cancel_token: CancellationToken = None,
+    ) -> Tuple["RevocationRegistryDefinition", TailsSource]:
+        if not isinstance(cred_def, bindings.IndyObject):
+            cred_def = CredentialDefinition.load(cred_def)
+        if not isinstance(reg_def_private, bindings.IndyObject):
+            reg_def_private = RevocationRegistryDefinitionPrivate.load(reg_def_private)
+        reg_def, tails = bindings.write_revocation_registry_tails_to_buffer(
+            cred_def.handle,
+            self.handle,
+            reg_def_private.handle,
+            tails_location,
+            cancel_token and cancel_token.handle,
+        )
+        return RevocationRegistryDefinition(reg_def), TailsSource.from_bytes(bytes(tails))
+
+    def revocation_notification(self, cred_rev_idx: int, comment: str = None) -> dict:
+        """Build the Aries revocation notification of a revoked credential."""
+        return bindings.aries_revocation_notification(self.id, cred_rev_idx, comment)
+
     @classmethod
     def load(
         cls, value: Union[dict, str, bytes, memoryview]
@@ -616,8 +1416,51 @@ class RevocationRegistry(bindings.IndyObject):
         )
         return RevocationRegistryDelta(rev_delta)
 
+    @classmethod
+    def from_deltas(
+        cls,
+        deltas: Sequence[Tuple[int, Union[str, "RevocationRegistryDelta"]]],
+        timestamp: int,
+    ) -> "RevocationRegistry":
+        deltas = [
+            (
+                delta_ts,
+                delta
+                if isinstance(delta, bindings.IndyObject)
+                else RevocationRegistryDelta.load(delta),
+            )
+            for (delta_ts, delta) in deltas
+        ]
+        return RevocationRegistry(
+            bindings.revocation_registry_from_deltas(
+                [(delta_ts, delta.handle) for (delta_ts, delta) in deltas], timestamp
+            )
+        )
+
+    def to_delta(
+        self,
+        prev_rev_reg: Optional[Union[str, "RevocationRegistry"]] = None,
+        issued: Sequence[int] = None,
+        revoked: Sequence[int] = None,
+    ) -> "RevocationRegistryDelta":
+        if prev_rev_reg is not None and not isinstance(
+            prev_rev_reg, bindings.IndyObject
+        ):
+            prev_rev_reg = RevocationRegistry.load(prev_rev_reg)
+        return RevocationRegistryDelta(
+            bindings.revocation_registry_to_delta(
+                self.handle,
+                prev_rev_reg and prev_rev_reg.handle,
+                issued or [],
+                revoked or [],
+            )
+        )
+
 
 class RevocationRegistryDelta(bindings.IndyObject):
+    GET_ATTR = "credx_revocation_registry_delta_get_attribute"
+    FIELDS = ("issued", "revoked", "accum", "prev_accum")
+
     @classmethod
     def load(
         cls, value: Union[dict, str, bytes, memoryview]
@@ -637,6 +1480,41 @@ class RevocationRegistryDelta(bindings.IndyObject):
             self.handle, next_delta.handle
         )
 
+    @property
+    def issued(self) -> Sequence[int]:
+        return bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "issued",
+        )
+
+    @property
+    def revoked(self) -> Sequence[int]:
+        return bindings._object_get_json_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "revoked",
+        )
+
+    @property
+    def accum(self) -> str:
+        return str(
+            bindings._object_get_attribute(
+                self.GET_ATTR,
+                self.handle,
+                "accum",
+            )
+        )
+
+    @property
+    def prev_accum(self) -> Optional[str]:
+        sval = bindings._object_get_attribute(
+            self.GET_ATTR,
+            self.handle,
+            "prev_accum",
+        )
+        return str(sval) if sval is not None else None
+
 
 class CredentialRevocationConfig:
     def __init__(
@@ -700,6 +1578,30 @@ class CredentialRevocationState(bindings.IndyObject):
             )
         )
 
+    @classmethod
+    def create_many(
+        cls,
+        rev_reg_def: [str, RevocationRegistryDefinition],
+        rev_reg_delta: [str, RevocationRegistryDelta],
+        cred_rev_ids: Sequence[int],
+        timestamp: int,
+        tails_path: str,
+    ) -> Sequence["CredentialRevocationState"]:
+        if not isinstance(rev_reg_def, bindings.IndyObject):
+            rev_reg_def = RevocationRegistryDefinition.load(rev_reg_def)
+        if not isinstance(rev_reg_delta, bindings.IndyObject):
+            rev_reg_delta = RevocationRegistryDelta.load(rev_reg_delta)
+        return [
+            CredentialRevocationState(handle)
+            for handle in bindings.create_or_update_revocation_states(
+                rev_reg_def.handle,
+                rev_reg_delta.handle,
+                cred_rev_ids,
+                timestamp,
+                tails_path,
+            )
+        ]
+
     @classmethod
     def load(
         cls, value: Union[dict, str, bytes, memoryview]